@@ -0,0 +1,21 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate tantivy;
+
+use tantivy::directory::ReadOnlySource;
+use tantivy::fastfield::DeleteBitSet;
+use tantivy::store::{Compressor, StoreReader};
+
+fuzz_target!(|data: &[u8]| {
+    let source = ReadOnlySource::from(data.to_vec());
+    if let Ok(store_reader) = StoreReader::from_source(source, Compressor::None) {
+        for doc in 0..16 {
+            let _ = store_reader.get(doc);
+        }
+        let delete_bitset = DeleteBitSet::empty();
+        for doc_res in store_reader.iter_documents(&delete_bitset).take(1_000) {
+            let _ = doc_res;
+        }
+    }
+});
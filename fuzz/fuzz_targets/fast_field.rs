@@ -0,0 +1,19 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate tantivy;
+
+use tantivy::directory::ReadOnlySource;
+use tantivy::fastfield::FastFieldReader;
+
+fuzz_target!(|data: &[u8]| {
+    let source = ReadOnlySource::from(data.to_vec());
+    if let Ok(fast_field_reader) = FastFieldReader::<u64>::open(source) {
+        let num_vals = fast_field_reader.capacity().min(1_000);
+        for doc in 0..num_vals {
+            fast_field_reader.get(doc as u32);
+        }
+        let mut output = vec![0u64; num_vals];
+        fast_field_reader.get_range(0, &mut output);
+    }
+});
@@ -0,0 +1,45 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate tantivy;
+
+use tantivy::{DocSet, SkipResult};
+use tantivy::directory::ReadOnlySource;
+use tantivy::fastfield::DeleteBitSet;
+use tantivy::postings::{BlockSegmentPostings, Postings, SegmentPostings};
+use tantivy::schema::IndexRecordOption;
+
+fuzz_target!(|data: &[u8]| {
+    if data.len() < 3 {
+        return;
+    }
+    // the first bytes give the number of documents of the posting list,
+    // and whether its term frequencies are recorded.
+    let doc_freq = (data[0] as usize) << 8 | data[1] as usize;
+    let record_option = if data[2] % 2 == 0 {
+        IndexRecordOption::Basic
+    } else {
+        IndexRecordOption::WithFreqs
+    };
+    let source = ReadOnlySource::from(data[3..].to_vec());
+    if let Ok(mut block_postings) =
+        BlockSegmentPostings::open(doc_freq, source.clone(), record_option)
+    {
+        while block_postings.advance() {
+            let _ = block_postings.docs();
+            let _ = block_postings.freqs();
+        }
+    }
+    if let Ok(block_postings) = BlockSegmentPostings::open(doc_freq, source, record_option) {
+        let mut postings =
+            SegmentPostings::from_block_postings(block_postings, DeleteBitSet::empty(), None, false);
+        let mut target = 0u32;
+        while postings.skip_next(target) != SkipResult::End {
+            let _ = postings.term_freq();
+            target = match postings.doc().checked_add(100) {
+                Some(target) => target,
+                None => break,
+            };
+        }
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+extern crate tantivy;
+
+use tantivy::directory::ReadOnlySource;
+use tantivy::termdict::{TermDictionary, TermDictionaryImpl, TermStreamer};
+
+fuzz_target!(|data: &[u8]| {
+    let source = ReadOnlySource::from(data.to_vec());
+    if let Ok(term_dictionary) = TermDictionaryImpl::from_source(source) {
+        let _ = term_dictionary.get(b"tantivy");
+        let mut stream = term_dictionary.stream();
+        let mut num_terms = 0;
+        while num_terms < 1_000 && stream.advance() {
+            let _ = stream.value();
+            num_terms += 1;
+        }
+    }
+});
@@ -1,4 +1,4 @@
-use super::Collector;
+use super::{Collector, MergeableCollector};
 use DocId;
 use Score;
 use Result;
@@ -34,6 +34,16 @@ impl Collector for CountCollector {
     }
 }
 
+impl MergeableCollector for CountCollector {
+    fn for_segment(&self) -> CountCollector {
+        CountCollector::default()
+    }
+
+    fn merge(&mut self, other: CountCollector) {
+        self.count += other.count;
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -65,6 +65,38 @@ pub trait Collector {
 
     /// Returns true iff the collector requires to compute scores for documents.
     fn requires_scoring(&self) -> bool;
+
+    /// Returns a score below which documents are not collected anymore, if any.
+    ///
+    /// It is pushed down to the scorer, so that the documents
+    /// that cannot reach it can be skipped.
+    /// See `Scorer::set_min_competitive_score`.
+    fn min_competitive_score(&self) -> Option<Score> {
+        None
+    }
+
+    /// Returns true iff the collector may report a minimum competitive score.
+    ///
+    /// Only then are the documents pushed to the collector one at a time,
+    /// so that its minimum competitive score can be pushed down to the
+    /// scorer as it rises. Otherwise, they go through `Weight::for_each`.
+    fn has_min_competitive_score(&self) -> bool {
+        false
+    }
+}
+
+/// Collectors whose segments can be collected in parallel.
+///
+/// Each segment is collected by its own collector, obtained from `.for_segment()`,
+/// and the resulting collectors are then merged, in the order of the segments.
+///
+/// See `Searcher::search_in_parallel`.
+pub trait MergeableCollector: Collector + Send + Sized + 'static {
+    /// Returns an empty collector, with the same configuration as `self`.
+    fn for_segment(&self) -> Self;
+
+    /// Merges the documents collected by `other` into `self`.
+    fn merge(&mut self, other: Self);
 }
 
 impl<'a, C: Collector> Collector for &'a mut C {
@@ -83,6 +115,14 @@ impl<'a, C: Collector> Collector for &'a mut C {
     fn requires_scoring(&self) -> bool {
         C::requires_scoring(self)
     }
+
+    fn min_competitive_score(&self) -> Option<Score> {
+        C::min_competitive_score(self)
+    }
+
+    fn has_min_competitive_score(&self) -> bool {
+        C::has_min_competitive_score(self)
+    }
 }
 
 #[cfg(test)]
@@ -1,4 +1,4 @@
-use super::Collector;
+use super::{Collector, MergeableCollector};
 use SegmentReader;
 use SegmentLocalId;
 use DocAddress;
@@ -96,48 +96,76 @@ impl TopCollector {
     pub fn at_capacity(&self) -> bool {
         self.heap.len() >= self.limit
     }
-}
-
-impl Collector for TopCollector {
-    fn set_segment(&mut self, segment_id: SegmentLocalId, _: &SegmentReader) -> Result<()> {
-        self.segment_id = segment_id;
-        Ok(())
-    }
 
-    fn collect(&mut self, doc: DocId, score: Score) {
+    fn push(&mut self, scored_doc: GlobalScoredDoc) {
         if self.at_capacity() {
             // It's ok to unwrap as long as a limit of 0 is forbidden.
             let limit_doc: GlobalScoredDoc = *self.heap
                 .peek()
                 .expect("Top collector with size 0 is forbidden");
-            if limit_doc.score < score {
+            if limit_doc.score < scored_doc.score {
                 let mut mut_head = self.heap
                     .peek_mut()
                     .expect("Top collector with size 0 is forbidden");
-                mut_head.score = score;
-                mut_head.doc_address = DocAddress(self.segment_id, doc);
+                *mut_head = scored_doc;
             }
         } else {
-            let wrapped_doc = GlobalScoredDoc {
-                score: score,
-                doc_address: DocAddress(self.segment_id, doc),
-            };
-            self.heap.push(wrapped_doc);
+            self.heap.push(scored_doc);
         }
     }
+}
+
+impl Collector for TopCollector {
+    fn set_segment(&mut self, segment_id: SegmentLocalId, _: &SegmentReader) -> Result<()> {
+        self.segment_id = segment_id;
+        Ok(())
+    }
+
+    fn collect(&mut self, doc: DocId, score: Score) {
+        let wrapped_doc = GlobalScoredDoc {
+            score: score,
+            doc_address: DocAddress(self.segment_id, doc),
+        };
+        self.push(wrapped_doc);
+    }
 
     fn requires_scoring(&self) -> bool {
         true
     }
+
+    fn min_competitive_score(&self) -> Option<Score> {
+        if self.at_capacity() {
+            self.heap.peek().map(|limit_doc| limit_doc.score)
+        } else {
+            None
+        }
+    }
+
+    fn has_min_competitive_score(&self) -> bool {
+        true
+    }
+}
+
+impl MergeableCollector for TopCollector {
+    fn for_segment(&self) -> TopCollector {
+        TopCollector::with_limit(self.limit)
+    }
+
+    fn merge(&mut self, other: TopCollector) {
+        for scored_doc in other.heap {
+            self.push(scored_doc);
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use DocAddress;
     use DocId;
     use Score;
-    use collector::Collector;
+    use collector::{Collector, MergeableCollector};
 
     #[test]
     fn test_top_collector_not_at_capacity() {
@@ -181,6 +209,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_top_collector_merge() {
+        let mut top_collector = TopCollector::with_limit(3);
+        top_collector.collect(1, 0.8);
+        top_collector.collect(3, 0.2);
+        let mut segment_collector = top_collector.for_segment();
+        segment_collector.segment_id = 1;
+        segment_collector.collect(2, 0.5);
+        segment_collector.collect(4, 0.9);
+        top_collector.merge(segment_collector);
+        let score_docs: Vec<(Score, DocAddress)> = top_collector.score_docs();
+        assert_eq!(
+            score_docs,
+            vec![
+                (0.9, DocAddress(1, 4)),
+                (0.8, DocAddress(0, 1)),
+                (0.5, DocAddress(1, 2)),
+            ]
+        );
+    }
+
     #[test]
     #[should_panic]
     fn test_top_0() {
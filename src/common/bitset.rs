@@ -1,4 +1,5 @@
 use std::fmt;
+use std::mem;
 
 #[derive(Clone, Copy, Eq, PartialEq)]
 pub(crate) struct TinySet(u64);
@@ -26,6 +27,18 @@ impl IntoIterator for TinySet {
     }
 }
 
+impl From<u64> for TinySet {
+    fn from(mask: u64) -> TinySet {
+        TinySet(mask)
+    }
+}
+
+impl From<TinySet> for u64 {
+    fn from(tinyset: TinySet) -> u64 {
+        tinyset.0
+    }
+}
+
 impl TinySet {
     /// Returns an empty `TinySet`.
     pub fn empty() -> TinySet {
@@ -129,6 +142,10 @@ impl TinySet {
     }
 }
 
+/// Set of `u32` elements within `[0, max_value[`,
+/// typically used to hold a set of `DocId`.
+///
+/// It can be iterated through with a `BitSetDocSet`.
 #[derive(Clone)]
 pub struct BitSet {
     tinysets: Box<[TinySet]>,
@@ -167,6 +184,11 @@ impl BitSet {
         self.len
     }
 
+    /// Returns the number of bytes used by the buckets of the `BitSet`.
+    pub(crate) fn num_bytes(&self) -> usize {
+        self.tinysets.len() * mem::size_of::<TinySet>()
+    }
+
     /// Inserts an element in the `BitSet`
     pub fn insert(&mut self, el: u32) {
         // we do not check saturated els.
@@ -179,6 +201,18 @@ impl BitSet {
         };
     }
 
+    /// Inserts the elements of the bucket `bucket`
+    /// whose bit is set in `mask`.
+    ///
+    /// Reminder: the bucket `bucket` represents the
+    /// elements from `bucket * 64` to `(bucket+1) * 64`.
+    pub(crate) fn insert_mask(&mut self, bucket: u32, mask: u64) {
+        let old_tinyset = self.tinysets[bucket as usize];
+        let new_tinyset = old_tinyset.union(TinySet(mask));
+        self.len += (new_tinyset.len() - old_tinyset.len()) as usize;
+        self.tinysets[bucket as usize] = new_tinyset;
+    }
+
     /// Returns true iff the elements is in the `BitSet`.
     pub fn contains(&self, el: u32) -> bool {
         self.tinyset(el / 64u32).contains(el % 64)
@@ -197,6 +231,7 @@ impl BitSet {
             .map(|delta_bucket| bucket + delta_bucket as u32)
     }
 
+    /// Returns the upper bound, excluded, of the elements of the `BitSet`.
     pub fn max_value(&self) -> u32 {
         self.max_value
     }
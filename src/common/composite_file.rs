@@ -7,6 +7,7 @@ use directory::WritePtr;
 use std::io::{self, Read};
 use directory::ReadOnlySource;
 use common::BinarySerializable;
+use error::DataCorruption;
 
 #[derive(Eq, PartialEq, Hash, Copy, Ord, PartialOrd, Clone, Debug)]
 pub struct FileAddr {
@@ -114,10 +115,21 @@ pub struct CompositeFile {
 impl CompositeFile {
     /// Opens a composite file stored in a given
     /// `ReadOnlySource`.
+    ///
+    /// A footer that is out of bounds, or that describes
+    /// overlapping fields, returns a `DataCorruption` error.
     pub fn open(data: &ReadOnlySource) -> io::Result<CompositeFile> {
         let end = data.len();
+        if end < 4 {
+            return Err(DataCorruption::comment_only("Composite file is too short.").into());
+        }
         let footer_len_data = data.slice_from(end - 4);
         let footer_len = u32::deserialize(&mut footer_len_data.as_slice())? as usize;
+        if footer_len > end - 4 {
+            return Err(
+                DataCorruption::comment_only("Composite file footer is out of bounds.").into(),
+            );
+        }
 
         let footer_start = end - 4 - footer_len;
         let footer_data = data.slice(footer_start, footer_start + footer_len);
@@ -131,12 +143,17 @@ impl CompositeFile {
 
         let mut offset = 0;
         for _ in 0..num_fields {
-            offset += VInt::deserialize(&mut footer_buffer)?.0 as usize;
+            offset = offset.saturating_add(VInt::deserialize(&mut footer_buffer)?.0 as usize);
             let file_addr = FileAddr::deserialize(&mut footer_buffer)?;
             offsets.push(offset);
             file_addrs.push(file_addr);
         }
         offsets.push(footer_start);
+        if offsets.windows(2).any(|window| window[0] > window[1]) {
+            return Err(
+                DataCorruption::comment_only("Composite file field offsets are invalid.").into(),
+            );
+        }
         for i in 0..num_fields {
             let file_addr = file_addrs[i];
             let start_offset = offsets[i];
@@ -175,6 +192,16 @@ impl CompositeFile {
             })
             .map(|&(from, to)| self.data.slice(from, to))
     }
+
+    /// Returns the number of bytes used by each field,
+    /// summed over its different indexes.
+    pub fn num_bytes_per_field(&self) -> HashMap<Field, usize> {
+        let mut num_bytes_per_field = HashMap::new();
+        for (file_addr, &(from, to)) in &self.offsets_index {
+            *num_bytes_per_field.entry(file_addr.field).or_insert(0) += to - from;
+        }
+        num_bytes_per_field
+    }
 }
 
 #[cfg(test)]
@@ -186,6 +213,7 @@ mod test {
     use schema::Field;
     use common::VInt;
     use common::BinarySerializable;
+    use directory::ReadOnlySource;
     use std::path::Path;
 
     #[test]
@@ -225,7 +253,33 @@ mod test {
                 assert_eq!(file4_buf.len(), 0);
                 assert_eq!(payload_4, 2u64);
             }
+            let num_bytes_per_field = composite_file.num_bytes_per_field();
+            assert_eq!(num_bytes_per_field.len(), 2);
+            assert_eq!(num_bytes_per_field[&Field(0u32)], 4);
+            assert_eq!(num_bytes_per_field[&Field(4u32)], 1);
+        }
+    }
+
+    #[test]
+    fn test_composite_file_corrupted() {
+        let path = Path::new("test_path");
+        let mut directory = RAMDirectory::create();
+        {
+            let w = directory.open_write(path).unwrap();
+            let mut composite_write = CompositeWrite::wrap(w);
+            VInt(2).serialize(composite_write.for_field(Field(0u32))).unwrap();
+            VInt(3).serialize(composite_write.for_field(Field(1u32))).unwrap();
+            composite_write.close().unwrap();
+        }
+        let data = directory.open_read(path).unwrap();
+        // truncated files must not cause a panic.
+        for len in 0..data.len() {
+            let _ = CompositeFile::open(&data.slice_to(len));
         }
+        let mut corrupted = data.as_slice().to_vec();
+        let footer_len_offset = corrupted.len() - 1;
+        corrupted[footer_len_offset] = 255;
+        assert!(CompositeFile::open(&ReadOnlySource::from(corrupted)).is_err());
     }
 
 }
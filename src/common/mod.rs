@@ -5,6 +5,7 @@ mod counting_writer;
 mod composite_file;
 pub mod bitpacker;
 mod bitset;
+mod span;
 
 pub(crate) use self::composite_file::{CompositeFile, CompositeWrite};
 pub use self::serialize::{BinarySerializable, FixedSize};
@@ -15,8 +16,10 @@ pub use self::vint::VInt;
 pub use self::counting_writer::CountingWriter;
 pub use self::bitset::BitSet;
 pub(crate) use self::bitset::TinySet;
+pub(crate) use self::span::Span;
 pub use byteorder::LittleEndian as Endianness;
 
+use std::cmp;
 use std::io;
 
 /// Computes the number of bits that will be used for bitpacking.
@@ -56,6 +59,42 @@ pub(crate) fn is_power_of_2(n: usize) -> bool {
     (n > 0) && (n & (n - 1) == 0)
 }
 
+/// Returns the index of the first element of `sorted` that is
+/// greater or equal to `target`, or `sorted.len()` if there is none.
+///
+/// The search starts with an exponential search from the beginning
+/// of the slice, followed by a binary search. Its cost is therefore
+/// logarithmic in the returned index rather than in the length of the slice,
+/// which matters when skipping through a docset by short jumps.
+pub(crate) fn galloping_search(sorted: &[u32], target: u32) -> usize {
+    let mut start = 0;
+    let mut count = 1;
+    loop {
+        let new = start + count;
+        if new < sorted.len() && sorted[new] < target {
+            start = new;
+            count *= 2;
+        } else {
+            break;
+        }
+    }
+    let end = cmp::min(start + count, sorted.len());
+
+    // now do a binary search
+    let mut count = end - start;
+    while count > 0 {
+        let step = count / 2;
+        let mid = start + step;
+        if sorted[mid] < target {
+            start = mid + 1;
+            count -= step + 1;
+        } else {
+            count = step;
+        }
+    }
+    start
+}
+
 /// Create a default io error given a string.
 pub(crate) fn make_io_err(msg: String) -> io::Error {
     io::Error::new(io::ErrorKind::Other, msg)
@@ -108,7 +147,7 @@ pub fn u64_to_i64(val: u64) -> i64 {
 #[cfg(test)]
 pub(crate) mod test {
 
-    use super::{compute_num_bits, i64_to_u64, u64_to_i64};
+    use super::{compute_num_bits, galloping_search, i64_to_u64, u64_to_i64};
     pub use super::serialize::test::fixed_size_test;
 
     fn test_i64_converter_helper(val: i64) {
@@ -138,4 +177,14 @@ pub(crate) mod test {
         assert_eq!(compute_num_bits(256), 9u8);
         assert_eq!(compute_num_bits(5_000_000_000), 33u8);
     }
+
+    #[test]
+    fn test_galloping_search() {
+        assert_eq!(galloping_search(&[], 3), 0);
+        let sorted: Vec<u32> = (0u32..100u32).map(|i| i * 3).collect();
+        for target in 0u32..310u32 {
+            let expected = sorted.iter().take_while(|&&val| val < target).count();
+            assert_eq!(galloping_search(&sorted, target), expected);
+        }
+    }
 }
@@ -4,6 +4,7 @@ use std::fmt;
 use std::io::Write;
 use std::io::Read;
 use std::io;
+use std::cmp::min;
 use common::VInt;
 
 /// Trait for a simple binary serialization.
@@ -43,7 +44,9 @@ impl<T: BinarySerializable> BinarySerializable for Vec<T> {
     }
     fn deserialize<R: Read>(reader: &mut R) -> io::Result<Vec<T>> {
         let num_items = VInt::deserialize(reader)?.val();
-        let mut items: Vec<T> = Vec::with_capacity(num_items as usize);
+        // the number of items is not trusted to preallocate
+        // the vector, as the data may be corrupted.
+        let mut items: Vec<T> = Vec::with_capacity(min(num_items, 1_024) as usize);
         for _ in 0..num_items {
             let item = T::deserialize(reader)?;
             items.push(item);
@@ -1,4 +1,5 @@
 use super::BinarySerializable;
+use error::DataCorruption;
 use std::io;
 use std::io::Write;
 use std::io::Read;
@@ -42,6 +43,9 @@ impl BinarySerializable for VInt {
         loop {
             match bytes.next() {
                 Some(Ok(b)) => {
+                    if shift > 63 {
+                        return Err(DataCorruption::comment_only("VInt is too long.").into());
+                    }
                     result += u64::from(b % 128u8) << shift;
                     if b & 128u8 != 0u8 {
                         break;
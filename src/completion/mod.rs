@@ -0,0 +1,293 @@
+//! Completion of prefixes into the values of the completion fields.
+//!
+//! For each completion field (See `TextOptions::set_completion`),
+//! a segment stores a weighted FST associating the raw values of the field,
+//! which are not tokenized, to the number of documents containing them.
+//! It is serialized in the `COMPLETIONS` component of the segment,
+//! and merged by summing the weights of the values shared by several segments.
+//!
+//! `Searcher::complete` merges the values starting with a given prefix
+//! over all of the segments, and returns the ones with the highest weights.
+
+use common::CompositeWrite;
+use directory::{ReadOnlySource, WritePtr};
+use fst;
+use fst::Streamer;
+use fst::map::{OpBuilder, StreamBuilder};
+use fst::raw::Fst;
+use schema::{Document, Field, FieldType, Schema, Value};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::io;
+use termdict::next_prefix;
+
+/// A completion of a prefix, returned by `Searcher::complete(...)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Completion {
+    /// Value of the completion field starting with the prefix.
+    pub text: String,
+    /// Number of documents containing the value,
+    /// deleted documents included.
+    pub weight: u64,
+}
+
+fn convert_fst_error(e: fst::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, e)
+}
+
+/// Returns the completion fields of the schema.
+pub(crate) fn completion_fields(schema: &Schema) -> Vec<Field> {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|&(_, field_entry)| match *field_entry.field_type() {
+            FieldType::Str(ref text_options) => text_options.is_completion(),
+            _ => false,
+        })
+        .map(|(field_id, _)| Field(field_id as u32))
+        .collect()
+}
+
+/// Writes the weighted FST of a completion field in the composite file.
+///
+/// `values` must be sorted and without duplicates.
+pub(crate) fn write_completion_index<'a, I>(
+    field: Field,
+    values: I,
+    serializer: &mut CompositeWrite<WritePtr>,
+) -> io::Result<()>
+where
+    I: Iterator<Item = (&'a [u8], u64)>,
+{
+    let mut map_builder =
+        fst::MapBuilder::new(serializer.for_field(field)).map_err(convert_fst_error)?;
+    for (value, weight) in values {
+        map_builder
+            .insert(value, weight)
+            .map_err(convert_fst_error)?;
+    }
+    map_builder.finish().map_err(convert_fst_error)
+}
+
+/// Writes the union of the completion indexes of a field in the composite file,
+/// summing the weights of the values shared by several indexes.
+pub(crate) fn merge_completion_indexes(
+    field: Field,
+    completion_indexes: &[CompletionIndex],
+    serializer: &mut CompositeWrite<WritePtr>,
+) -> io::Result<()> {
+    let mut op_builder = OpBuilder::new();
+    for completion_index in completion_indexes {
+        op_builder.push(completion_index.stream());
+    }
+    let mut map_builder =
+        fst::MapBuilder::new(serializer.for_field(field)).map_err(convert_fst_error)?;
+    let mut union = op_builder.union();
+    while let Some((value, indexed_weights)) = union.next() {
+        let weight: u64 = indexed_weights
+            .iter()
+            .map(|indexed_weight| indexed_weight.value)
+            .sum();
+        map_builder
+            .insert(value, weight)
+            .map_err(convert_fst_error)?;
+    }
+    map_builder.finish().map_err(convert_fst_error)
+}
+
+/// Accumulates the values of the completion fields of the documents of a segment.
+pub(crate) struct CompletionsWriter {
+    field_writers: Vec<(Field, HashMap<String, u64>)>,
+}
+
+impl CompletionsWriter {
+    pub fn from_schema(schema: &Schema) -> CompletionsWriter {
+        CompletionsWriter {
+            field_writers: completion_fields(schema)
+                .into_iter()
+                .map(|field| (field, HashMap::new()))
+                .collect(),
+        }
+    }
+
+    /// Records the values of the completion fields of a document.
+    ///
+    /// A value appearing several times in the document is only counted once.
+    pub fn add_document(&mut self, doc: &Document) {
+        for &mut (field, ref mut weights) in &mut self.field_writers {
+            let mut texts: Vec<&str> = doc.get_all(field)
+                .into_iter()
+                .filter_map(|value| match *value {
+                    Value::Str(ref text) => Some(&text[..]),
+                    _ => None,
+                })
+                .collect();
+            texts.sort();
+            texts.dedup();
+            for text in texts {
+                *weights.entry(text.to_string()).or_insert(0u64) += 1;
+            }
+        }
+    }
+
+    /// Serializes the completion index of each of the completion fields.
+    pub fn serialize(&self, serializer: &mut CompositeWrite<WritePtr>) -> io::Result<()> {
+        for &(field, ref weights) in &self.field_writers {
+            let mut values: Vec<(&[u8], u64)> = weights
+                .iter()
+                .map(|(text, &weight)| (text.as_bytes(), weight))
+                .collect();
+            values.sort();
+            write_completion_index(field, values.into_iter(), serializer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Weighted FST of the values of a completion field, in a segment.
+pub(crate) struct CompletionIndex {
+    fst_index: fst::Map,
+}
+
+impl CompletionIndex {
+    pub fn open(source: ReadOnlySource) -> io::Result<CompletionIndex> {
+        let fst_result = match source {
+            ReadOnlySource::Anonymous(data) => {
+                Fst::from_shared_bytes(data.data, data.start, data.len)
+            }
+            #[cfg(feature = "mmap")]
+            ReadOnlySource::Mmap(mmap_readonly) => Fst::from_mmap(mmap_readonly),
+            ReadOnlySource::Static(data) => Fst::from_static_slice(data),
+            #[cfg(feature = "object_store")]
+            lazy @ ReadOnlySource::Lazy(_) => Fst::from_bytes(lazy.as_slice().to_vec()),
+        };
+        let fst = fst_result.map_err(convert_fst_error)?;
+        Ok(CompletionIndex {
+            fst_index: fst::Map::from(fst),
+        })
+    }
+
+    /// Returns a stream over all of the values and their weights.
+    pub fn stream(&self) -> StreamBuilder {
+        self.fst_index.range()
+    }
+
+    /// Returns a stream over the values starting with `prefix`, and their weights.
+    pub fn prefix_stream(&self, prefix: &[u8]) -> StreamBuilder {
+        let stream_builder = self.fst_index.range().ge(prefix);
+        match next_prefix(prefix) {
+            Some(upper_bound) => stream_builder.lt(upper_bound),
+            None => stream_builder,
+        }
+    }
+}
+
+/// Returns the `k` values of the completion indexes starting with `prefix`
+/// having the highest weights, summed over the indexes.
+///
+/// Completions are sorted by decreasing weight, then by value.
+pub(crate) fn complete(
+    completion_indexes: &[CompletionIndex],
+    prefix: &str,
+    k: usize,
+) -> Vec<Completion> {
+    if k == 0 {
+        return Vec::new();
+    }
+    let mut op_builder = OpBuilder::new();
+    for completion_index in completion_indexes {
+        op_builder.push(completion_index.prefix_stream(prefix.as_bytes()));
+    }
+    // min-heap over the `k` best completions, with the worst one on top.
+    let mut heap: BinaryHeap<Reverse<(u64, Reverse<Vec<u8>>)>> = BinaryHeap::with_capacity(k + 1);
+    let mut union = op_builder.union();
+    while let Some((value, indexed_weights)) = union.next() {
+        let weight: u64 = indexed_weights
+            .iter()
+            .map(|indexed_weight| indexed_weight.value)
+            .sum();
+        if heap.len() == k {
+            let is_better = {
+                let &Reverse((worst_weight, Reverse(ref worst_value))) =
+                    heap.peek().expect("The heap cannot be empty");
+                (weight, Reverse(value)) > (worst_weight, Reverse(&worst_value[..]))
+            };
+            if !is_better {
+                continue;
+            }
+            heap.pop();
+        }
+        heap.push(Reverse((weight, Reverse(value.to_vec()))));
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((weight, Reverse(value)))| Completion {
+            text: String::from_utf8_lossy(&value).into_owned(),
+            weight,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{complete, Completion, CompletionIndex, CompletionsWriter};
+    use common::{CompositeFile, CompositeWrite};
+    use directory::{Directory, RAMDirectory};
+    use schema::{SchemaBuilder, TextOptions, STORED};
+    use std::path::Path;
+
+    fn completion(text: &str, weight: u64) -> Completion {
+        Completion {
+            text: text.to_string(),
+            weight,
+        }
+    }
+
+    #[test]
+    fn test_completions_writer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TextOptions::default().set_completion());
+        let other = schema_builder.add_text_field("other", STORED);
+        let schema = schema_builder.build();
+        let mut completions_writer = CompletionsWriter::from_schema(&schema);
+        completions_writer.add_document(&doc!(title=>"the old man", title=>"the old man"));
+        completions_writer.add_document(&doc!(title=>"the old man", title=>"the sea"));
+        completions_writer.add_document(&doc!(title=>"theater", other=>"the end"));
+        completions_writer.add_document(&doc!(other=>"the end"));
+
+        let mut directory = RAMDirectory::create();
+        let path = Path::new("completions");
+        {
+            let write = directory.open_write(path).unwrap();
+            let mut composite_write = CompositeWrite::wrap(write);
+            completions_writer.serialize(&mut composite_write).unwrap();
+            composite_write.close().unwrap();
+        }
+        let source = directory.open_read(path).unwrap();
+        let composite_file = CompositeFile::open(&source).unwrap();
+        assert!(composite_file.open_read(other).is_none());
+        let completion_index = CompletionIndex::open(composite_file.open_read(title).unwrap())
+            .unwrap();
+        let completion_indexes = vec![completion_index];
+        assert_eq!(
+            complete(&completion_indexes, "the", 10),
+            vec![
+                completion("the old man", 2),
+                completion("the sea", 1),
+                completion("theater", 1),
+            ]
+        );
+        assert_eq!(
+            complete(&completion_indexes, "the ", 1),
+            vec![completion("the old man", 2)]
+        );
+        assert_eq!(
+            complete(&completion_indexes, "", 10),
+            complete(&completion_indexes, "the", 10)
+        );
+        assert!(complete(&completion_indexes, "x", 10).is_empty());
+        assert!(complete(&completion_indexes, "the", 0).is_empty());
+    }
+}
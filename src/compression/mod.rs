@@ -1,3 +1,17 @@
+//! Compression of the blocks of integers of the postings.
+//!
+//! Postings are encoded in blocks of `COMPRESSION_BLOCK_SIZE` integers,
+//! that are bitpacked, and an incomplete block of variable byte encoded integers.
+//!
+//! With the `simdcompression` feature, bitpacking and delta-decoding
+//! rely on the SSE implementation of the `simdcomp` and `streamvbyte` C libraries.
+//! Without it, blocks are decoded by the Rust kernels of `pack::kernels`,
+//! that detect the SIMD instructions available at runtime
+//! with the `runtime-simd` feature, and fall back to a scalar implementation.
+//!
+//! The C and Rust implementations do not lay out the bits of a block in the
+//! same order, so an index must be read with the same `simdcompression`
+//! feature as the one it was written with.
 #![allow(dead_code)]
 
 
@@ -14,6 +28,7 @@ pub fn compressed_block_size(num_bits: u8) -> usize {
 
 #[cfg(not(feature = "simdcompression"))]
 mod pack {
+    mod kernels;
     mod compression_pack_nosimd;
     pub use self::compression_pack_nosimd::{BlockDecoder, BlockEncoder};
 }
@@ -38,6 +53,13 @@ mod vint {
     pub(crate) use self::compression_vint_simd::*;
 }
 
+/// Returns the number of bytes of the `num_els` variable byte encoded
+/// integers at the beginning of `compressed_data`, or `None` if they
+/// do not fit in `compressed_data`.
+pub(crate) fn compressed_vint_num_bytes(compressed_data: &[u8], num_els: usize) -> Option<usize> {
+    vint::compressed_num_bytes(compressed_data, num_els)
+}
+
 pub trait VIntEncoder {
     /// Compresses an array of `u32` integers,
     /// using [delta-encoding](https://en.wikipedia.org/wiki/Delta_encoding)
@@ -209,6 +231,22 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_compressed_vint_num_bytes() {
+        let mut encoder = BlockEncoder::new();
+        let input: Vec<u32> = (0u32..123u32).map(|i| i * i * i).collect();
+        let encoded_data = encoder.compress_vint_unsorted(&input).to_owned();
+        assert_eq!(
+            compressed_vint_num_bytes(&encoded_data, input.len()),
+            Some(encoded_data.len())
+        );
+        assert_eq!(compressed_vint_num_bytes(&encoded_data, 0), Some(0));
+        assert_eq!(
+            compressed_vint_num_bytes(&encoded_data[..encoded_data.len() - 1], input.len()),
+            None
+        );
+    }
+
     #[bench]
     fn bench_compress(b: &mut Bencher) {
         let mut encoder = BlockEncoder::new();
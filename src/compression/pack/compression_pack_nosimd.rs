@@ -1,9 +1,10 @@
 use common::bitpacker::compute_num_bits;
-use common::bitpacker::{BitPacker, BitUnpacker};
+use common::bitpacker::BitPacker;
 use common::CountingWriter;
 use std::cmp;
 use std::io::Write;
 use super::super::{compressed_block_size, COMPRESSION_BLOCK_SIZE};
+use super::kernels;
 
 const COMPRESSED_BLOCK_MAX_SIZE: usize = COMPRESSION_BLOCK_SIZE * 4 + 1;
 
@@ -30,7 +31,7 @@ pub fn compress_sorted(vals: &mut [u32], output: &mut [u8], offset: u32) -> usiz
             .unwrap();
     }
     let compressed_size = counting_writer.written_bytes();
-    assert_eq!(compressed_size, compute_block_size(num_bits));
+    assert_eq!(compressed_size, compressed_block_size(num_bits));
     compressed_size
 }
 
@@ -105,32 +106,22 @@ impl BlockDecoder {
         }
     }
 
-    pub fn uncompress_block_sorted<'a>(
-        &mut self,
-        compressed_data: &'a [u8],
-        mut offset: u32,
-    ) -> usize {
-        let consumed_size = {
-            let num_bits = compressed_data[0];
-            let bit_unpacker = BitUnpacker::new(&compressed_data[1..], num_bits as usize);
-            for i in 0..COMPRESSION_BLOCK_SIZE {
-                let delta = bit_unpacker.get(i);
-                let val = offset + delta as u32;
-                self.output[i] = val;
-                offset = val;
-            }
-            compute_block_size(num_bits)
-        };
+    pub fn uncompress_block_sorted<'a>(&mut self, compressed_data: &'a [u8], offset: u32) -> usize {
+        let num_bits = compressed_data[0];
+        let output = &mut self.output[..COMPRESSION_BLOCK_SIZE];
+        kernels::unpack(num_bits, &compressed_data[1..], output);
+        kernels::integrate_delta(offset, output);
         self.output_len = COMPRESSION_BLOCK_SIZE;
-        consumed_size
+        compressed_block_size(num_bits)
     }
 
     pub fn uncompress_block_unsorted<'a>(&mut self, compressed_data: &'a [u8]) -> usize {
         let num_bits = compressed_data[0];
-        let bit_unpacker = BitUnpacker::new(&compressed_data[1..], num_bits as usize);
-        for i in 0..COMPRESSION_BLOCK_SIZE {
-            self.output[i] = bit_unpacker.get(i) as u32;
-        }
+        kernels::unpack(
+            num_bits,
+            &compressed_data[1..],
+            &mut self.output[..COMPRESSION_BLOCK_SIZE],
+        );
         let consumed_size = 1 + (num_bits as usize * COMPRESSION_BLOCK_SIZE + 7) / 8;
         self.output_len = COMPRESSION_BLOCK_SIZE;
         consumed_size
@@ -0,0 +1,235 @@
+//! Kernels decoding the blocks of the scalar bitpacking layout.
+//!
+//! With the `runtime-simd` feature, the kernels pick the SIMD instructions
+//! of the CPU they run on:
+//! - on `x86_64`, bits are unpacked with AVX2 when it is detected at runtime,
+//! and prefix sums are computed with SSE2.
+//! - on `aarch64`, prefix sums are computed with NEON.
+//!
+//! Otherwise, or on other targets, the scalar implementation is used.
+//! All the kernels decode the same layout, so the choice of
+//! the kernel does not change the format of the index.
+
+use common::bitpacker::BitUnpacker;
+
+mod scalar {
+    use super::BitUnpacker;
+
+    /// Unpacks the values `start..output.len()` of the block.
+    pub fn unpack_from(num_bits: u8, data: &[u8], output: &mut [u32], start: usize) {
+        let bit_unpacker = BitUnpacker::new(data, num_bits);
+        for i in start..output.len() {
+            output[i] = bit_unpacker.get(i) as u32;
+        }
+    }
+
+    pub fn unpack(num_bits: u8, data: &[u8], output: &mut [u32]) {
+        unpack_from(num_bits, data, output, 0);
+    }
+
+    pub fn integrate_delta(mut offset: u32, output: &mut [u32]) {
+        for val in output.iter_mut() {
+            offset = offset.wrapping_add(*val);
+            *val = offset;
+        }
+    }
+}
+
+#[cfg(all(feature = "runtime-simd", target_arch = "x86_64"))]
+mod x86_64 {
+    use std::arch::x86_64::*;
+    use super::scalar;
+
+    // Number of values whose 8 bytes can be loaded without
+    // reading past the end of `data`.
+    fn num_loadable_vals(num_bits: usize, data_len: usize) -> usize {
+        if data_len < 8 {
+            0
+        } else {
+            ((data_len - 8) * 8 + 7) / num_bits + 1
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn unpack_avx2(num_bits: u8, data: &[u8], output: &mut [u32]) {
+        if num_bits == 0 {
+            for val in output.iter_mut() {
+                *val = 0u32;
+            }
+            return;
+        }
+        let num_bits = num_bits as usize;
+        let mask = _mm256_set1_epi64x(((1u64 << num_bits) - 1) as i64);
+        // gathers the low 32 bits of the four 64 bits lanes.
+        let low_halves = _mm256_setr_epi32(0, 2, 4, 6, 0, 2, 4, 6);
+        let num_vals = num_loadable_vals(num_bits, data.len()).min(output.len()) / 4 * 4;
+        let mut i = 0;
+        while i < num_vals {
+            let addr_in_bits = (i * num_bits) as i64;
+            let num_bits = num_bits as i64;
+            let addrs_in_bits = _mm256_setr_epi64x(
+                addr_in_bits,
+                addr_in_bits + num_bits,
+                addr_in_bits + 2 * num_bits,
+                addr_in_bits + 3 * num_bits,
+            );
+            let addrs = _mm256_srli_epi64(addrs_in_bits, 3);
+            let bit_shifts = _mm256_and_si256(addrs_in_bits, _mm256_set1_epi64x(7));
+            let words = _mm256_i64gather_epi64(data.as_ptr() as *const i64, addrs, 1);
+            let vals = _mm256_and_si256(_mm256_srlv_epi64(words, bit_shifts), mask);
+            let vals = _mm256_permutevar8x32_epi32(vals, low_halves);
+            _mm_storeu_si128(
+                output[i..].as_mut_ptr() as *mut __m128i,
+                _mm256_castsi256_si128(vals),
+            );
+            i += 4;
+        }
+        scalar::unpack_from(num_bits as u8, data, output, num_vals);
+    }
+
+    pub fn integrate_delta_sse2(offset: u32, output: &mut [u32]) {
+        let num_vals = output.len() / 4 * 4;
+        unsafe {
+            let mut carry = _mm_set1_epi32(offset as i32);
+            let mut i = 0;
+            while i < num_vals {
+                let ptr = output[i..].as_mut_ptr() as *mut __m128i;
+                let mut vals = _mm_loadu_si128(ptr);
+                vals = _mm_add_epi32(vals, _mm_slli_si128(vals, 4));
+                vals = _mm_add_epi32(vals, _mm_slli_si128(vals, 8));
+                vals = _mm_add_epi32(vals, carry);
+                _mm_storeu_si128(ptr, vals);
+                carry = _mm_shuffle_epi32(vals, 0xFF);
+                i += 4;
+            }
+        }
+        let offset = if num_vals > 0 {
+            output[num_vals - 1]
+        } else {
+            offset
+        };
+        scalar::integrate_delta(offset, &mut output[num_vals..]);
+    }
+}
+
+#[cfg(all(feature = "runtime-simd", target_arch = "aarch64"))]
+mod aarch64 {
+    use std::arch::aarch64::*;
+    use super::scalar;
+
+    pub fn integrate_delta_neon(offset: u32, output: &mut [u32]) {
+        let num_vals = output.len() / 4 * 4;
+        unsafe {
+            let zeros = vdupq_n_u32(0);
+            let mut carry = vdupq_n_u32(offset);
+            let mut i = 0;
+            while i < num_vals {
+                let ptr = output[i..].as_mut_ptr();
+                let mut vals = vld1q_u32(ptr);
+                vals = vaddq_u32(vals, vextq_u32(zeros, vals, 3));
+                vals = vaddq_u32(vals, vextq_u32(zeros, vals, 2));
+                vals = vaddq_u32(vals, carry);
+                vst1q_u32(ptr, vals);
+                carry = vdupq_n_u32(vgetq_lane_u32(vals, 3));
+                i += 4;
+            }
+        }
+        let offset = if num_vals > 0 {
+            output[num_vals - 1]
+        } else {
+            offset
+        };
+        scalar::integrate_delta(offset, &mut output[num_vals..]);
+    }
+}
+
+/// Unpacks the `output.len()` first values bitpacked over `num_bits`
+/// at the beginning of `data`.
+#[cfg(all(feature = "runtime-simd", target_arch = "x86_64"))]
+pub fn unpack(num_bits: u8, data: &[u8], output: &mut [u32]) {
+    if is_x86_feature_detected!("avx2") {
+        unsafe { x86_64::unpack_avx2(num_bits, data, output) }
+    } else {
+        scalar::unpack(num_bits, data, output)
+    }
+}
+
+/// Unpacks the `output.len()` first values bitpacked over `num_bits`
+/// at the beginning of `data`.
+#[cfg(not(all(feature = "runtime-simd", target_arch = "x86_64")))]
+pub fn unpack(num_bits: u8, data: &[u8], output: &mut [u32]) {
+    scalar::unpack(num_bits, data, output)
+}
+
+/// Replaces the deltas of `output` by their prefix sums,
+/// starting from `offset`.
+#[cfg(all(feature = "runtime-simd", target_arch = "x86_64"))]
+pub fn integrate_delta(offset: u32, output: &mut [u32]) {
+    x86_64::integrate_delta_sse2(offset, output)
+}
+
+/// Replaces the deltas of `output` by their prefix sums,
+/// starting from `offset`.
+#[cfg(all(feature = "runtime-simd", target_arch = "aarch64"))]
+pub fn integrate_delta(offset: u32, output: &mut [u32]) {
+    aarch64::integrate_delta_neon(offset, output)
+}
+
+/// Replaces the deltas of `output` by their prefix sums,
+/// starting from `offset`.
+#[cfg(not(all(feature = "runtime-simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+pub fn integrate_delta(offset: u32, output: &mut [u32]) {
+    scalar::integrate_delta(offset, output)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use common::bitpacker::BitPacker;
+    use compression::COMPRESSION_BLOCK_SIZE;
+    use super::{integrate_delta, scalar, unpack};
+
+    fn pack(vals: &[u32], num_bits: u8) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut bit_packer = BitPacker::new();
+        for &val in vals {
+            bit_packer.write(val as u64, num_bits, &mut data).unwrap();
+        }
+        bit_packer.flush(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_unpack_against_scalar() {
+        for num_bits in 0u8..33u8 {
+            let max_val = ((1u64 << num_bits) - 1) as u32;
+            let vals: Vec<u32> = (0u32..COMPRESSION_BLOCK_SIZE as u32)
+                .map(|i| i.wrapping_mul(2_654_435_761u32) & max_val)
+                .collect();
+            // without any padding, the last values cannot be loaded
+            // as 8 bytes words.
+            let data = pack(&vals, num_bits);
+            let mut output = [0u32; COMPRESSION_BLOCK_SIZE];
+            unpack(num_bits, &data, &mut output);
+            assert_eq!(&output[..], &vals[..]);
+            let mut scalar_output = [0u32; COMPRESSION_BLOCK_SIZE];
+            scalar::unpack(num_bits, &data, &mut scalar_output);
+            assert_eq!(&scalar_output[..], &vals[..]);
+        }
+    }
+
+    #[test]
+    fn test_integrate_delta_against_scalar() {
+        for &len in &[0, 1, 3, 4, 5, 127, 128] {
+            let deltas: Vec<u32> = (0u32..len).map(|i| i * 7 + 1).collect();
+            let mut output = deltas.clone();
+            integrate_delta(11, &mut output);
+            let mut scalar_output = deltas.clone();
+            scalar::integrate_delta(11, &mut scalar_output);
+            assert_eq!(output, scalar_output);
+            if len > 0 {
+                assert_eq!(output[len as usize - 1], 11 + deltas.iter().sum::<u32>());
+            }
+        }
+    }
+}
@@ -59,7 +59,7 @@ pub(crate) fn uncompress_sorted<'a>(
         loop {
             let cur_byte = compressed_data[read_byte];
             read_byte += 1;
-            result += ((cur_byte % 128u8) as u32) << shift;
+            result = result.wrapping_add(((cur_byte % 128u8) as u32) << shift);
             if cur_byte & 128u8 != 0u8 {
                 break;
             }
@@ -80,7 +80,7 @@ pub(crate) fn uncompress_unsorted<'a>(compressed_data: &'a [u8], output: &mut [u
         loop {
             let cur_byte = compressed_data[read_byte];
             read_byte += 1;
-            result += ((cur_byte % 128u8) as u32) << shift;
+            result = result.wrapping_add(((cur_byte % 128u8) as u32) << shift);
             if cur_byte & 128u8 != 0u8 {
                 break;
             }
@@ -90,3 +90,26 @@ pub(crate) fn uncompress_unsorted<'a>(compressed_data: &'a [u8], output: &mut [u
     }
     read_byte
 }
+
+/// Returns the number of bytes of the `num_els` integers encoded
+/// at the beginning of `compressed_data`, or `None` if they
+/// do not fit in `compressed_data`.
+///
+/// An integer takes at most 5 bytes.
+pub(crate) fn compressed_num_bytes(compressed_data: &[u8], num_els: usize) -> Option<usize> {
+    let mut read_byte = 0;
+    for _ in 0..num_els {
+        let start = read_byte;
+        loop {
+            let cur_byte = *compressed_data.get(read_byte)?;
+            read_byte += 1;
+            if cur_byte & 128u8 != 0u8 {
+                break;
+            }
+            if read_byte - start == 5 {
+                return None;
+            }
+        }
+    }
+    Some(read_byte)
+}
@@ -48,25 +48,66 @@ pub(crate) fn compress_unsorted<'a>(input: &[u32], output: &'a mut [u8]) -> &'a
     &output[..compress_length]
 }
 
+// The SSE decoder of streamvbyte loads 16 bytes at a time,
+// and may read past the last encoded integer.
+const DECODER_PADDING: usize = 16;
+
+// Calls `decode` on `compressed_data`, copied into a zero-padded buffer
+// if it is too short for `num_els` integers to be decoded
+// without reading out of bounds.
+#[inline(always)]
+fn with_padding<F: FnOnce(&[u8]) -> usize>(
+    compressed_data: &[u8],
+    num_els: usize,
+    decode: F,
+) -> usize {
+    let max_num_bytes = (num_els + 3) / 4 + num_els * 4 + DECODER_PADDING;
+    if compressed_data.len() >= max_num_bytes {
+        decode(compressed_data)
+    } else {
+        let mut buffer = vec![0u8; max_num_bytes];
+        buffer[..compressed_data.len()].copy_from_slice(compressed_data);
+        decode(&buffer)
+    }
+}
+
+/// Returns the number of bytes of the `num_els` integers encoded
+/// at the beginning of `compressed_data`, or `None` if they
+/// do not fit in `compressed_data`.
+pub(crate) fn compressed_num_bytes(compressed_data: &[u8], num_els: usize) -> Option<usize> {
+    let num_key_bytes = (num_els + 3) / 4;
+    let keys = compressed_data.get(..num_key_bytes)?;
+    // every integer has a 2 bits key, giving its number of bytes minus one.
+    let num_data_bytes: usize = (0..num_els)
+        .map(|i| ((keys[i / 4] >> (2 * (i % 4))) & 3u8) as usize + 1)
+        .sum();
+    let num_bytes = num_key_bytes + num_data_bytes;
+    if num_bytes <= compressed_data.len() {
+        Some(num_bytes)
+    } else {
+        None
+    }
+}
+
 #[inline(always)]
 pub(crate) fn uncompress_sorted<'a>(
     compressed_data: &'a [u8],
     output: &mut [u32],
     offset: u32,
 ) -> usize {
-    unsafe {
+    with_padding(compressed_data, output.len(), |compressed_data| unsafe {
         streamvbyte::streamvbyte_delta_decode(
             compressed_data.as_ptr(),
             output.as_mut_ptr(),
             output.len() as u32,
             offset,
         )
-    }
+    })
 }
 
 #[inline(always)]
 pub(crate) fn uncompress_unsorted<'a>(compressed_data: &'a [u8], output: &mut [u32]) -> usize {
-    unsafe {
+    with_padding(compressed_data, output.len(), |compressed_data| unsafe {
         streamvbyte::streamvbyte_decode(compressed_data.as_ptr(), output.as_mut_ptr(), output.len())
-    }
+    })
 }
@@ -0,0 +1,109 @@
+use Result;
+use DocAddress;
+use core::Index;
+use collector::Collector;
+use futures_cpupool::{CpuFuture, CpuPool};
+use query::Query;
+use schema::Document;
+use error::Error;
+
+/// Runs the read path of an `Index` on a dedicated thread pool,
+/// and exposes it as futures.
+///
+/// Searching may block on IO: page faults on mmapped files,
+/// or network reads for a remote `Directory`. The `AsyncSearcher`
+/// makes it possible to use tantivy from an event loop (tokio for instance)
+/// without blocking its threads.
+///
+/// Each call acquires its own `Searcher`, so that
+/// a query is always run on a consistent set of segments.
+#[derive(Clone)]
+pub struct AsyncSearcher {
+    index: Index,
+    pool: CpuPool,
+}
+
+impl AsyncSearcher {
+    /// Creates an `AsyncSearcher` running searches on
+    /// a new pool of `num_threads` threads.
+    pub fn new(index: Index, num_threads: usize) -> AsyncSearcher {
+        let pool = CpuPool::new(num_threads);
+        AsyncSearcher::with_pool(index, pool)
+    }
+
+    /// Creates an `AsyncSearcher` running searches on
+    /// an existing thread pool.
+    pub fn with_pool(index: Index, pool: CpuPool) -> AsyncSearcher {
+        AsyncSearcher { index, pool }
+    }
+
+    /// Runs a query, and resolves to the collector
+    /// once all of the matching documents have been collected.
+    pub fn search<Q, C>(&self, query: Q, collector: C) -> CpuFuture<C, Error>
+    where
+        Q: Query + Send + 'static,
+        C: Collector + Send + 'static,
+    {
+        let index = self.index.clone();
+        self.pool.spawn_fn(move || -> Result<C> {
+            let mut collector = collector;
+            let searcher = index.searcher();
+            searcher.search(&query, &mut collector)?;
+            Ok(collector)
+        })
+    }
+
+    /// Fetches a document from the store given its `DocAddress`.
+    ///
+    /// The `DocAddress` must have been obtained from a search
+    /// on the same generation of searchers, that is without calling
+    /// `.load_searchers()` in between.
+    pub fn doc(&self, doc_address: DocAddress) -> CpuFuture<Document, Error> {
+        let index = self.index.clone();
+        self.pool.spawn_fn(move || -> Result<Document> { index.searcher().doc(&doc_address) })
+    }
+
+    /// Reloads the searchers of the index. See `Index::load_searchers`.
+    pub fn load_searchers(&self) -> CpuFuture<(), Error> {
+        let index = self.index.clone();
+        self.pool.spawn_fn(move || index.load_searchers())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::AsyncSearcher;
+    use Index;
+    use Term;
+    use collector::CountCollector;
+    use futures::Future;
+    use query::TermQuery;
+    use schema::{IndexRecordOption, SchemaBuilder, STORED, TEXT};
+
+    #[test]
+    fn test_async_searcher() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let async_searcher = AsyncSearcher::new(index.clone(), 2);
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a b"));
+            index_writer.add_document(doc!(text_field=>"a"));
+            index_writer.commit().unwrap();
+        }
+        async_searcher.load_searchers().wait().unwrap();
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::Basic,
+        );
+        let collector = async_searcher
+            .search(query, CountCollector::default())
+            .wait()
+            .unwrap();
+        assert_eq!(collector.count(), 2);
+        let doc = async_searcher.doc(::DocAddress(0, 1)).wait().unwrap();
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "a");
+    }
+}
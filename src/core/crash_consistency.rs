@@ -0,0 +1,112 @@
+use Result;
+use error::{ErrorKind, ResultExt};
+use core::{Index, LOCKFILE_FILEPATH};
+use directory::{Directory, FaultyDirectory, ManagedDirectory};
+use directory::error::DeleteError;
+use indexer::index_writer::HEAP_SIZE_LIMIT;
+use schema::Schema;
+use std::panic::{self, AssertUnwindSafe};
+
+/// Checks that an index recovered after a crash can be opened,
+/// searched, and written to.
+fn check_recovered_index(directory: &FaultyDirectory) -> Result<()> {
+    let index = Index::open_directory(directory.clone())?;
+    if let Some(path) = index.validate_checksums()?.into_iter().next() {
+        bail!(ErrorKind::CorruptedFile(path));
+    }
+    index.validate_doc_counts()?;
+    index.load_searchers()?;
+    // the lockfile of the crashed writer, if it was persisted, is stale.
+    if let Err(DeleteError::IOError(io_error)) = directory.delete(&LOCKFILE_FILEPATH) {
+        bail!(ErrorKind::IOError(io_error));
+    }
+    let mut index_writer = index.writer_with_num_threads(1, HEAP_SIZE_LIMIT as usize)?;
+    index_writer.commit()?;
+    index.load_searchers()
+}
+
+/// Crash-consistency test harness.
+///
+/// Runs a `workload` against an index created with `schema` in a
+/// `FaultyDirectory`, crashing the directory at each of the write operations
+/// of the workload in turn. After each crash, the directory is restarted,
+/// and the recovered index is checked:
+/// - it opens, and its searchers load,
+/// - the checksums of its segment files are valid,
+/// - the components of its segments agree on their number of documents,
+/// - once the stale lockfile is removed, a writer can be opened, and can commit.
+///
+/// If `torn_writes` is true, the files that were not flushed
+/// at the time of the crash keep a random prefix of their content.
+///
+/// The workload is expected to fail, or to panic, once the directory has
+/// crashed. It should also be deterministic, so that the crash points
+/// are the same from one run to the other: opening the writer with
+/// `Index::deterministic_writer` is recommended.
+///
+/// Returns the number of crash points that were exercised,
+/// or the first inconsistency found.
+pub fn check_crash_consistency<F>(schema: Schema, torn_writes: bool, workload: F) -> Result<usize>
+where
+    F: Fn(&Index) -> Result<()>,
+{
+    let mut crash_point = 0;
+    loop {
+        let directory = FaultyDirectory::create(crash_point as u64);
+        directory.set_torn_writes(torn_writes);
+        let managed_directory = ManagedDirectory::new(directory.clone())?;
+        let index = Index::from_directory(managed_directory, schema.clone())?;
+        directory.crash_after(crash_point);
+        let workload_result = panic::catch_unwind(AssertUnwindSafe(|| workload(&index)));
+        drop(index);
+        if !directory.has_crashed() {
+            // the workload completed before the crash point:
+            // every crash point has been exercised.
+            match workload_result {
+                Ok(result) => result?,
+                Err(panic_payload) => panic::resume_unwind(panic_payload),
+            }
+            return Ok(crash_point);
+        }
+        check_recovered_index(&directory.restart()?).chain_err(|| {
+            format!(
+                "The index is inconsistent after a crash at write operation {}.",
+                crash_point
+            )
+        })?;
+        crash_point += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Result;
+    use Term;
+    use indexer::merge_policy::tests::MergeWheneverPossible;
+    use schema::{SchemaBuilder, STORED, TEXT};
+    use super::check_crash_consistency;
+
+    #[test]
+    fn test_crash_consistency() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let workload = |index: &Index| -> Result<()> {
+            let mut index_writer = index.deterministic_writer(40_000_000, 2)?;
+            index_writer.set_merge_policy(box MergeWheneverPossible);
+            for i in 0..5 {
+                index_writer.add_document(doc!(text_field => format!("doc {}", i)));
+            }
+            index_writer.commit()?;
+            index_writer.delete_term(Term::from_field_text(text_field, "3"));
+            index_writer.add_document(doc!(text_field => "doc 5"));
+            index_writer.commit()?;
+            Ok(())
+        };
+        let schema = schema_builder.build();
+        let num_crash_points = check_crash_consistency(schema.clone(), false, &workload).unwrap();
+        assert!(num_crash_points > 10);
+        check_crash_consistency(schema, true, &workload).unwrap();
+    }
+}
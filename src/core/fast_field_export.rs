@@ -0,0 +1,269 @@
+use Result;
+use DocAddress;
+use DocId;
+use SegmentLocalId;
+use core::{Searcher, SegmentReader};
+use docset::DocSet;
+use fastfield::{BytesFastFieldReader, FacetReader, FastFieldNotAvailableError, FastFieldReader,
+                MultiValueIntFastFieldReader, StrFastFieldReader, VectorFastFieldReader};
+use query::{Query, Scorer};
+use schema::{Cardinality, Facet, Field, FieldType, Value, Vector};
+
+/// Fast field values of a document, yielded by a `FastFieldExport`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExportedDoc {
+    /// Address of the document.
+    pub doc_address: DocAddress,
+    /// `values[i]` are the values of the `i`-th exported field.
+    ///
+    /// It is empty if the document does not have any value for the field.
+    pub values: Vec<Vec<Value>>,
+}
+
+enum FieldValuesReader {
+    U64(FastFieldReader<u64>),
+    I64(FastFieldReader<i64>),
+    MultiU64(MultiValueIntFastFieldReader<u64>),
+    MultiI64(MultiValueIntFastFieldReader<i64>),
+    Str(StrFastFieldReader),
+    Facet(FacetReader),
+    Bytes(BytesFastFieldReader),
+    Vector(VectorFastFieldReader),
+}
+
+impl FieldValuesReader {
+    fn open(segment_reader: &SegmentReader, field: Field) -> Result<FieldValuesReader> {
+        let field_entry = segment_reader.schema().get_field_entry(field);
+        let field_values_reader = match *field_entry.field_type() {
+            FieldType::U64(ref options) => match options.get_fastfield_cardinality() {
+                Some(Cardinality::SingleValue) => {
+                    FieldValuesReader::U64(segment_reader.fast_field_reader(field)?)
+                }
+                Some(Cardinality::MultiValues) => {
+                    FieldValuesReader::MultiU64(segment_reader.multi_fast_field_reader(field)?)
+                }
+                None => return Err(FastFieldNotAvailableError::new(field_entry).into()),
+            },
+            FieldType::I64(ref options) => match options.get_fastfield_cardinality() {
+                Some(Cardinality::SingleValue) => {
+                    FieldValuesReader::I64(segment_reader.fast_field_reader(field)?)
+                }
+                Some(Cardinality::MultiValues) => {
+                    FieldValuesReader::MultiI64(segment_reader.multi_fast_field_reader(field)?)
+                }
+                None => return Err(FastFieldNotAvailableError::new(field_entry).into()),
+            },
+            FieldType::Str(_) => {
+                FieldValuesReader::Str(segment_reader.str_fast_field_reader(field)?)
+            }
+            FieldType::HierarchicalFacet => {
+                FieldValuesReader::Facet(segment_reader.facet_reader(field)?)
+            }
+            FieldType::Bytes => {
+                FieldValuesReader::Bytes(segment_reader.bytes_fast_field_reader(field)?)
+            }
+            FieldType::Vector(_) => {
+                FieldValuesReader::Vector(segment_reader.vector_fast_field_reader(field)?)
+            }
+        };
+        Ok(field_values_reader)
+    }
+
+    fn values(&mut self, doc: DocId, ords: &mut Vec<u64>) -> Vec<Value> {
+        match *self {
+            FieldValuesReader::U64(ref reader) => {
+                reader.get_opt(doc).map(Value::U64).into_iter().collect()
+            }
+            FieldValuesReader::I64(ref reader) => {
+                reader.get_opt(doc).map(Value::I64).into_iter().collect()
+            }
+            FieldValuesReader::MultiU64(ref reader) => {
+                reader.get_vals(doc, ords);
+                ords.iter().cloned().map(Value::U64).collect()
+            }
+            FieldValuesReader::MultiI64(ref reader) => {
+                let mut vals = vec![];
+                reader.get_vals(doc, &mut vals);
+                vals.into_iter().map(Value::I64).collect()
+            }
+            FieldValuesReader::Str(ref reader) => {
+                reader.term_ords(doc, ords);
+                ords.iter()
+                    .filter_map(|&term_ord| {
+                        let mut text = String::new();
+                        if reader.ord_to_str(term_ord, &mut text) {
+                            Some(Value::Str(text))
+                        } else {
+                            None
+                        }
+                    })
+                    .collect()
+            }
+            FieldValuesReader::Facet(ref mut reader) => {
+                reader.facet_ords(doc, ords);
+                ords.iter()
+                    .map(|&facet_ord| {
+                        let mut facet = Facet::root();
+                        reader.facet_from_ord(facet_ord, &mut facet);
+                        Value::Facet(facet)
+                    })
+                    .collect()
+            }
+            FieldValuesReader::Bytes(ref reader) => {
+                let val = reader.get_val(doc);
+                if val.is_empty() {
+                    vec![]
+                } else {
+                    vec![Value::Bytes(val.to_vec())]
+                }
+            }
+            FieldValuesReader::Vector(ref reader) => {
+                let mut components = vec![];
+                if reader.get_val(doc, &mut components) {
+                    vec![Value::Vector(Vector::from(components))]
+                } else {
+                    vec![]
+                }
+            }
+        }
+    }
+}
+
+struct SegmentExport {
+    segment_ord: SegmentLocalId,
+    segment_reader: SegmentReader,
+    scorer: Box<Scorer>,
+    field_values_readers: Vec<FieldValuesReader>,
+}
+
+/// Iterator over the fast field values of the documents matching a query,
+/// returned by `Searcher::export_fast_fields(...)`.
+///
+/// Documents are yielded segment by segment, by increasing `DocId`.
+/// Deleted documents are skipped.
+pub struct FastFieldExport {
+    segment_exports: Vec<SegmentExport>,
+    // buffer for the ordinals of multivalued fields.
+    ords: Vec<u64>,
+}
+
+impl FastFieldExport {
+    pub(crate) fn new(
+        searcher: &Searcher,
+        query: &Query,
+        fields: &[Field],
+    ) -> Result<FastFieldExport> {
+        let weight = searcher.restrict_weight(query.weight(searcher, false)?);
+        let mut segment_exports = vec![];
+        for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
+            let field_values_readers = fields
+                .iter()
+                .map(|&field| FieldValuesReader::open(segment_reader, field))
+                .collect::<Result<Vec<_>>>()?;
+            segment_exports.push(SegmentExport {
+                segment_ord: segment_ord as SegmentLocalId,
+                segment_reader: segment_reader.clone(),
+                scorer: weight.scorer(segment_reader)?,
+                field_values_readers,
+            });
+        }
+        // segments are popped from the end of the vec.
+        segment_exports.reverse();
+        Ok(FastFieldExport {
+            segment_exports,
+            ords: vec![],
+        })
+    }
+}
+
+impl Iterator for FastFieldExport {
+    type Item = ExportedDoc;
+
+    fn next(&mut self) -> Option<ExportedDoc> {
+        loop {
+            {
+                let segment_export = self.segment_exports.last_mut()?;
+                while segment_export.scorer.advance() {
+                    let doc = segment_export.scorer.doc();
+                    if segment_export.segment_reader.is_deleted(doc) {
+                        continue;
+                    }
+                    let ords = &mut self.ords;
+                    let values = segment_export
+                        .field_values_readers
+                        .iter_mut()
+                        .map(|field_values_reader| field_values_reader.values(doc, ords))
+                        .collect();
+                    return Some(ExportedDoc {
+                        doc_address: DocAddress(segment_export.segment_ord, doc),
+                        values,
+                    });
+                }
+            }
+            self.segment_exports.pop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use DocAddress;
+    use Index;
+    use Term;
+    use query::TermQuery;
+    use schema::{Cardinality, IndexRecordOption, IntOptions, SchemaBuilder, Value, FAST,
+                 INT_INDEXED, STRING, TEXT};
+
+    #[test]
+    fn test_export_fast_fields() {
+        let mut schema_builder = SchemaBuilder::default();
+        let body = schema_builder.add_text_field("body", TEXT);
+        let id = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let scores_options = IntOptions::default().set_fast(Cardinality::MultiValues);
+        let scores = schema_builder.add_i64_field("scores", scores_options);
+        let tag = schema_builder.add_text_field("tag", STRING.set_fast());
+        let payload = schema_builder.add_bytes_field("payload");
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(
+            body=>"a b",
+            id=>1u64,
+            scores=>-1i64,
+            scores=>2i64,
+            tag=>"x",
+            payload=>vec![1u8, 2u8]
+        ));
+        index_writer.add_document(doc!(body=>"b", id=>2u64, tag=>"y"));
+        index_writer.add_document(doc!(body=>"a", id=>3u64, scores=>4i64));
+        index_writer.add_document(doc!(body=>"a c", id=>4u64));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id, 4u64));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        let query = TermQuery::new(Term::from_field_text(body, "a"), IndexRecordOption::Basic);
+        let exported_docs: Vec<_> = searcher
+            .export_fast_fields(&query, &[id, scores, tag, payload])
+            .unwrap()
+            .collect();
+        assert_eq!(exported_docs.len(), 2);
+        assert_eq!(exported_docs[0].doc_address, DocAddress(0, 0));
+        assert_eq!(
+            exported_docs[0].values,
+            vec![
+                vec![Value::U64(1)],
+                vec![Value::I64(-1), Value::I64(2)],
+                vec![Value::Str("x".to_string())],
+                vec![Value::Bytes(vec![1u8, 2u8])],
+            ]
+        );
+        assert_eq!(exported_docs[1].doc_address, DocAddress(0, 2));
+        assert_eq!(
+            exported_docs[1].values,
+            vec![vec![Value::U64(3)], vec![Value::I64(4)], vec![], vec![]]
+        );
+        assert!(searcher.export_fast_fields(&query, &[body]).is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use common::BitSet;
+use core::SegmentReader;
+use docset::DocSet;
+use schema::{Field, FieldType, IndexRecordOption};
+use std::io;
+use termdict::TermDictionary;
+
+/// Statistics of an indexed field within a segment.
+///
+/// They are returned by `SegmentReader::field_statistics(...)`,
+/// and are the building blocks of scoring functions such as BM25F,
+/// which need the average length of a field.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FieldStatistics {
+    /// Number of documents with at least one token in the field.
+    /// Deleted documents are not counted.
+    pub doc_count: u32,
+    /// Number of tokens in the field, over all of the documents
+    /// that are not deleted.
+    ///
+    /// Numeric and facet fields count one token per value.
+    pub total_num_tokens: u64,
+    /// Number of distinct terms in the term dictionary of the field.
+    ///
+    /// Terms only found in deleted documents are counted until
+    /// their segment is merged.
+    pub num_terms: u64,
+}
+
+impl FieldStatistics {
+    /// Returns the average number of tokens of the field,
+    /// over the documents containing the field.
+    pub fn average_num_tokens(&self) -> f32 {
+        if self.doc_count == 0 {
+            0f32
+        } else {
+            self.total_num_tokens as f32 / self.doc_count as f32
+        }
+    }
+}
+
+/// Computes the statistics of an indexed field.
+///
+/// For text fields, the fieldnorms hold the number of tokens of each
+/// document, so that a single pass over them is needed. Other fields
+/// do not record fieldnorms, and their postings are scanned instead.
+pub(crate) fn compute_field_statistics(
+    segment_reader: &SegmentReader,
+    field: Field,
+) -> io::Result<Option<FieldStatistics>> {
+    let field_entry = segment_reader.schema().get_field_entry(field);
+    if !field_entry.is_indexed() {
+        return Ok(None);
+    }
+    let inverted_index = segment_reader.inverted_index(field);
+    let num_terms = inverted_index.terms().num_terms() as u64;
+    let mut doc_count = 0u32;
+    let mut total_num_tokens = 0u64;
+    match *field_entry.field_type() {
+        FieldType::Str(_) => {
+            let fieldnorm_reader = match segment_reader.fieldnorm_reader(field) {
+                Some(fieldnorm_reader) => fieldnorm_reader,
+                None => return Ok(None),
+            };
+            for doc in 0..segment_reader.max_doc() {
+                if segment_reader.is_deleted(doc) {
+                    continue;
+                }
+                let num_tokens = fieldnorm_reader.fieldnorm(doc);
+                if num_tokens > 0 {
+                    doc_count += 1;
+                    total_num_tokens += u64::from(num_tokens);
+                }
+            }
+        }
+        _ => {
+            let mut docs = BitSet::with_max_value(segment_reader.max_doc());
+            let mut postings_stream = inverted_index.postings_stream(IndexRecordOption::Basic);
+            while postings_stream.advance()? {
+                let postings = postings_stream.postings();
+                while postings.advance() {
+                    docs.insert(postings.doc());
+                    total_num_tokens += 1;
+                }
+            }
+            doc_count = docs.len() as u32;
+        }
+    }
+    Ok(Some(FieldStatistics {
+        doc_count,
+        total_num_tokens,
+        num_terms,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use schema::{SchemaBuilder, INT_INDEXED, STORED, TEXT};
+    use super::FieldStatistics;
+
+    #[test]
+    fn test_field_statistics() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED);
+        let stored_field = schema_builder.add_text_field("stored", STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a b c", id_field=>1u64, id_field=>2u64));
+        index_writer.add_document(doc!(text_field=>"a b", text_field=>"d", id_field=>1u64));
+        index_writer.add_document(doc!(stored_field=>"no indexed field"));
+        index_writer.add_document(doc!(text_field=>"e f g h", id_field=>3u64));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id_field, 3u64));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let text_statistics = segment_reader.field_statistics(text_field).unwrap().unwrap();
+        assert_eq!(
+            text_statistics,
+            FieldStatistics {
+                doc_count: 2,
+                total_num_tokens: 6,
+                num_terms: 8,
+            }
+        );
+        assert_eq!(text_statistics.average_num_tokens(), 3f32);
+        let id_statistics = segment_reader.field_statistics(id_field).unwrap().unwrap();
+        assert_eq!(
+            id_statistics,
+            FieldStatistics {
+                doc_count: 2,
+                total_num_tokens: 3,
+                num_terms: 3,
+            }
+        );
+        assert!(segment_reader.field_statistics(stored_field).unwrap().is_none());
+    }
+}
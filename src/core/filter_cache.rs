@@ -0,0 +1,241 @@
+use Result;
+use common::BitSet;
+use core::{MemoryBudget, MemoryReservation, SegmentId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+struct CacheEntry {
+    bitset: Arc<BitSet>,
+    last_access: u64,
+    reservation: Option<MemoryReservation>,
+}
+
+struct CacheEntries {
+    entries: HashMap<(u64, SegmentId), CacheEntry>,
+    clock: u64,
+}
+
+/// LRU cache of the documents matching filter queries,
+/// for each segment.
+///
+/// The entries are keyed by a hash of the query, and the `SegmentId`.
+/// A `FilterCache` is shared by the searchers of a same generation:
+/// reloading the searchers starts with an empty cache.
+///
+/// If the `Index` has a `MemoryBudget`, the bitsets are also registered
+/// against it, and may be evicted to make room for other entries.
+///
+/// See `CachedFilterQuery`, and `IndexReaderBuilder::filter_cache_capacity`.
+pub struct FilterCache {
+    capacity: usize,
+    entries: Arc<Mutex<CacheEntries>>,
+    memory_budget: Option<MemoryBudget>,
+}
+
+impl FilterCache {
+    /// Creates a cache holding at most `capacity` bitsets.
+    pub fn with_capacity(capacity: usize) -> FilterCache {
+        FilterCache {
+            capacity,
+            entries: Arc::new(Mutex::new(CacheEntries {
+                entries: HashMap::new(),
+                clock: 0u64,
+            })),
+            memory_budget: None,
+        }
+    }
+
+    /// Creates a cache holding at most `capacity` bitsets,
+    /// whose bitsets are registered against `memory_budget`.
+    pub fn with_memory_budget(capacity: usize, memory_budget: MemoryBudget) -> FilterCache {
+        FilterCache {
+            memory_budget: Some(memory_budget),
+            ..FilterCache::with_capacity(capacity)
+        }
+    }
+
+    /// Returns the maximum number of bitsets in the cache.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of bitsets in the cache.
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .expect("Filter cache lock poisoned")
+            .entries
+            .len()
+    }
+
+    /// Returns true iff the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the bitset cached for the given query hash and segment,
+    /// or computes it with `compute` and caches it.
+    ///
+    /// The lock of the cache is not held while computing the bitset,
+    /// so that concurrent queries on other filters are not blocked.
+    pub(crate) fn get_or_compute<F>(
+        &self,
+        query_hash: u64,
+        segment_id: SegmentId,
+        compute: F,
+    ) -> Result<Arc<BitSet>>
+    where
+        F: FnOnce() -> Result<BitSet>,
+    {
+        let key = (query_hash, segment_id);
+        {
+            let mut entries_lock = self.entries.lock().expect("Filter cache lock poisoned");
+            entries_lock.clock += 1;
+            let clock = entries_lock.clock;
+            if let Some(entry) = entries_lock.entries.get_mut(&key) {
+                entry.last_access = clock;
+                if let Some(ref reservation) = entry.reservation {
+                    reservation.touch();
+                }
+                return Ok(Arc::clone(&entry.bitset));
+            }
+        }
+        let bitset = Arc::new(compute()?);
+        if self.capacity == 0 {
+            return Ok(bitset);
+        }
+        let mut evictions = None;
+        {
+            let mut entries_lock = self.entries.lock().expect("Filter cache lock poisoned");
+            let reservation = match self.memory_budget {
+                Some(ref memory_budget) => {
+                    let entries = Arc::downgrade(&self.entries);
+                    let evict = move |entry_id: u64| {
+                        if let Some(entries) = entries.upgrade() {
+                            let mut entries_lock =
+                                entries.lock().expect("Filter cache lock poisoned");
+                            let is_evicted_entry = entries_lock
+                                .entries
+                                .get(&key)
+                                .and_then(|entry| entry.reservation.as_ref())
+                                .map(|reservation| reservation.entry_id() == entry_id)
+                                .unwrap_or(false);
+                            if is_evicted_entry {
+                                entries_lock.entries.remove(&key);
+                            }
+                        }
+                    };
+                    match memory_budget.reserve(bitset.num_bytes(), evict) {
+                        Some((reservation, entry_evictions)) => {
+                            evictions = Some(entry_evictions);
+                            Some(reservation)
+                        }
+                        // larger than the whole budget.
+                        None => return Ok(bitset),
+                    }
+                }
+                None => None,
+            };
+            entries_lock.clock += 1;
+            let clock = entries_lock.clock;
+            if !entries_lock.entries.contains_key(&key)
+                && entries_lock.entries.len() >= self.capacity
+            {
+                let least_recently_used = entries_lock
+                    .entries
+                    .iter()
+                    .min_by_key(|&(_, entry)| entry.last_access)
+                    .map(|(key, _)| *key);
+                if let Some(evicted_key) = least_recently_used {
+                    entries_lock.entries.remove(&evicted_key);
+                }
+            }
+            entries_lock.entries.insert(
+                key,
+                CacheEntry {
+                    bitset: Arc::clone(&bitset),
+                    last_access: clock,
+                    reservation,
+                },
+            );
+        }
+        if let Some(evictions) = evictions {
+            evictions.run();
+        }
+        Ok(bitset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::FilterCache;
+    use common::BitSet;
+    use core::{MemoryBudget, SegmentId};
+    use Result;
+
+    fn bitset_with(doc: u32) -> Result<BitSet> {
+        let mut bitset = BitSet::with_max_value(100);
+        bitset.insert(doc);
+        Ok(bitset)
+    }
+
+    #[test]
+    fn test_filter_cache_lru() {
+        let cache = FilterCache::with_capacity(2);
+        let segment_id = SegmentId::generate_random();
+        assert!(cache.get_or_compute(1, segment_id, || bitset_with(1)).unwrap().contains(1));
+        assert!(cache.get_or_compute(2, segment_id, || bitset_with(2)).unwrap().contains(2));
+        // cached, and now the most recently used.
+        assert!(cache.get_or_compute(1, segment_id, || bitset_with(3)).unwrap().contains(1));
+        assert_eq!(cache.len(), 2);
+        // evicts the entry of the query 2.
+        assert!(cache.get_or_compute(3, segment_id, || bitset_with(3)).unwrap().contains(3));
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get_or_compute(1, segment_id, || bitset_with(4)).unwrap().contains(1));
+        assert!(cache.get_or_compute(2, segment_id, || bitset_with(4)).unwrap().contains(4));
+        // the key includes the segment.
+        let other_segment_id = SegmentId::generate_random();
+        assert!(cache
+            .get_or_compute(2, other_segment_id, || bitset_with(5))
+            .unwrap()
+            .contains(5));
+    }
+
+    #[test]
+    fn test_filter_cache_errors_are_not_cached() {
+        let cache = FilterCache::with_capacity(2);
+        let segment_id = SegmentId::generate_random();
+        assert!(cache
+            .get_or_compute(1, segment_id, || bail!("failed"))
+            .is_err());
+        assert!(cache.is_empty());
+        assert!(cache.get_or_compute(1, segment_id, || bitset_with(1)).is_ok());
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_filter_cache_memory_budget() {
+        // each bitset takes 16 bytes.
+        let memory_budget = MemoryBudget::with_capacity(40);
+        let cache = FilterCache::with_memory_budget(10, memory_budget.clone());
+        let segment_id = SegmentId::generate_random();
+        for query_hash in 0..3 {
+            cache.get_or_compute(query_hash, segment_id, || bitset_with(1)).unwrap();
+        }
+        assert_eq!(cache.len(), 2);
+        assert_eq!(memory_budget.num_bytes_used(), 32);
+        // the entry of the query 0 was evicted.
+        assert!(cache.get_or_compute(0, segment_id, || bitset_with(2)).unwrap().contains(2));
+        // larger than the whole budget.
+        let large_bitset = cache
+            .get_or_compute(3, segment_id, || Ok(BitSet::with_max_value(1_000)))
+            .unwrap();
+        assert_eq!(large_bitset.len(), 0);
+        assert_eq!(cache.len(), 2);
+        memory_budget.evict_all();
+        assert!(cache.is_empty());
+        drop(cache);
+        assert_eq!(memory_budget.num_bytes_used(), 0);
+    }
+}
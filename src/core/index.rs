@@ -1,13 +1,17 @@
 use Result;
-use error::{ErrorKind, ResultExt};
-use serde_json;
+use error::{Error, ErrorKind};
 use schema::Schema;
 use std::sync::Arc;
 use std::borrow::BorrowMut;
 use std::fmt;
-use core::SegmentId;
-use directory::{Directory, MmapDirectory, RAMDirectory};
-use indexer::index_writer::open_index_writer;
+use core::{SegmentId, SegmentIdSequence};
+use core::SegmentComponent;
+use directory::{Directory, RAMDirectory, ReadOnlyDirectory};
+#[cfg(feature = "mmap")]
+use directory::{MmapDirectory, PackedDirectory};
+use directory::error::OpenReadError;
+use directory::{IoStats, WatchCallback, WatchHandle};
+use indexer::index_writer::{open_index_writer, DeterministicSettings};
 use core::searcher::Searcher;
 use std::convert::From;
 use num_cpus;
@@ -16,30 +20,91 @@ use core::SegmentReader;
 use super::pool::Pool;
 use core::SegmentMeta;
 use super::pool::LeasedItem;
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 use core::IndexMeta;
-use indexer::DirectoryLock;
+use core::IndexSettings;
+use core::MemoryBudget;
+use core::IndexSnapshot;
+use core::{IndexReader, IndexReaderBuilder};
+use core::IndexInfo;
+use core::index_info::inspect as inspect_index;
+use core::replication::install_replica;
+use core::SpaceUsage;
+use core::space_usage::space_usage as compute_space_usage;
+use directory::DirectoryLock;
 use IndexWriter;
+use IndexWriterHandle;
 use directory::ManagedDirectory;
-use core::META_FILEPATH;
+use core::{META_FILEPATH, PREVIOUS_META_FILEPATH};
 use super::segment::create_segment;
 use indexer::segment_updater::save_new_metas;
 use tokenizer::TokenizerManager;
 
 const NUM_SEARCHERS: usize = 12;
 
+/// Defines when the searchers of an `Index` or of an `IndexReader` are reloaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReloadPolicy {
+    /// Searchers are only reloaded upon calls to `.load_searchers()`,
+    /// or `.reload()` for an `IndexReader`.
+    Manual,
+    /// Searchers are reloaded automatically whenever a commit
+    /// changes the meta file, including commits from other processes.
+    OnCommit,
+}
+
+fn read_metas(directory: &Directory, path: &Path) -> Result<IndexMeta> {
+    let meta_data = directory.atomic_read(path)?;
+    IndexMeta::deserialize(&meta_data, path)
+}
+
+/// Returns the previous metas, if they are valid and all
+/// of their files are still present in the directory.
+fn load_previous_metas(directory: &Directory) -> Option<IndexMeta> {
+    let previous_metas = read_metas(directory, &PREVIOUS_META_FILEPATH).ok()?;
+    let all_files_exist = previous_metas.segments.iter().all(|segment_meta| {
+        SegmentComponent::iterator()
+            .filter(|component| match **component {
+                SegmentComponent::DELETE => segment_meta.has_deletes(),
+                SegmentComponent::FASTFIELDUPDATES => segment_meta.has_fast_field_updates(),
+                _ => true,
+            })
+            .all(|component| directory.exists(&segment_meta.relative_path(*component)))
+    });
+    if all_files_exist {
+        Some(previous_metas)
+    } else {
+        None
+    }
+}
+
 fn load_metas(directory: &Directory) -> Result<IndexMeta> {
-    let meta_data = directory.atomic_read(&META_FILEPATH)?;
-    let meta_string = String::from_utf8_lossy(&meta_data);
-    serde_json::from_str(&meta_string).chain_err(|| ErrorKind::CorruptedFile(META_FILEPATH.clone()))
+    match read_metas(directory, &META_FILEPATH) {
+        Err(Error(ErrorKind::CorruptedFile(path), state)) => {
+            if let Some(previous_metas) = load_previous_metas(directory) {
+                warn!(
+                    "{:?} is corrupted. Falling back to the previous commit (opstamp={}).",
+                    path, previous_metas.opstamp
+                );
+                return Ok(previous_metas);
+            }
+            Err(Error(ErrorKind::CorruptedFile(path), state))
+        }
+        metas_res => metas_res,
+    }
 }
 
 /// Search Index
 pub struct Index {
     directory: ManagedDirectory,
     schema: Schema,
+    settings: IndexSettings,
     searcher_pool: Arc<Pool<Searcher>>,
     tokenizers: TokenizerManager,
+    pinned_metas: Option<Arc<IndexMeta>>,
+    reload_watch: Option<Arc<WatchHandle>>,
+    memory_budget: Option<MemoryBudget>,
 }
 
 impl Index {
@@ -61,10 +126,24 @@ impl Index {
     /// The index will use the `MMapDirectory`.
     ///
     /// If a previous index was in this directory, then its meta file will be destroyed.
+    #[cfg(feature = "mmap")]
     pub fn create<P: AsRef<Path>>(directory_path: P, schema: Schema) -> Result<Index> {
+        Index::create_with_settings(directory_path, schema, IndexSettings::default())
+    }
+
+    /// Creates a new index in a given filepath, with the given `IndexSettings`.
+    /// The index will use the `MMapDirectory`.
+    ///
+    /// If a previous index was in this directory, then its meta file will be destroyed.
+    #[cfg(feature = "mmap")]
+    pub fn create_with_settings<P: AsRef<Path>>(
+        directory_path: P,
+        schema: Schema,
+        settings: IndexSettings,
+    ) -> Result<Index> {
         let mmap_directory = MmapDirectory::open(directory_path)?;
         let directory = ManagedDirectory::new(mmap_directory)?;
-        Index::from_directory(directory, schema)
+        Index::from_directory_with_settings(directory, schema, settings)
     }
 
     /// Accessor for the tokenizer manager.
@@ -80,6 +159,7 @@ impl Index {
     ///
     /// The temp directory is only used for testing the `MmapDirectory`.
     /// For other unit tests, prefer the `RAMDirectory`, see: `create_in_ram`.
+    #[cfg(feature = "mmap")]
     pub fn create_from_tempdir(schema: Schema) -> Result<Index> {
         let mmap_directory = MmapDirectory::create_from_tempdir()?;
         let directory = ManagedDirectory::new(mmap_directory)?;
@@ -87,35 +167,111 @@ impl Index {
     }
 
     /// Creates a new index given a directory and an `IndexMeta`.
-    fn create_from_metas(directory: ManagedDirectory, metas: &IndexMeta) -> Result<Index> {
+    ///
+    /// If `pinned_metas` is set, the index is read-only and
+    /// never reads the meta file of the directory again.
+    fn create_from_metas(
+        directory: ManagedDirectory,
+        metas: &IndexMeta,
+        pinned_metas: Option<Arc<IndexMeta>>,
+    ) -> Result<Index> {
         let schema = metas.schema.clone();
+        let settings = metas.settings.clone();
         let index = Index {
             directory,
             schema,
+            settings,
             searcher_pool: Arc::new(Pool::new()),
             tokenizers: TokenizerManager::default(),
+            pinned_metas,
+            reload_watch: None,
+            memory_budget: None,
         };
         index.load_searchers()?;
         Ok(index)
     }
 
     /// Create a new index from a directory.
-    pub fn from_directory(mut directory: ManagedDirectory, schema: Schema) -> Result<Index> {
-        save_new_metas(schema.clone(), 0, directory.borrow_mut())?;
-        let metas = IndexMeta::with_schema(schema);
-        Index::create_from_metas(directory, &metas)
+    pub fn from_directory(directory: ManagedDirectory, schema: Schema) -> Result<Index> {
+        Index::from_directory_with_settings(directory, schema, IndexSettings::default())
+    }
+
+    /// Create a new index from a directory, with the given `IndexSettings`.
+    pub fn from_directory_with_settings(
+        mut directory: ManagedDirectory,
+        schema: Schema,
+        settings: IndexSettings,
+    ) -> Result<Index> {
+        save_new_metas(schema.clone(), settings.clone(), 0, directory.borrow_mut())?;
+        let metas = IndexMeta::with_schema_and_settings(schema, settings);
+        Index::create_from_metas(directory, &metas, None)
     }
 
     /// Opens a new directory from an index path.
+    #[cfg(feature = "mmap")]
     pub fn open<P: AsRef<Path>>(directory_path: P) -> Result<Index> {
         let mmap_directory = MmapDirectory::open(directory_path)?;
-        let directory = ManagedDirectory::new(mmap_directory)?;
+        Index::open_directory(mmap_directory)
+    }
+
+    /// Opens an index stored in the given directory.
+    ///
+    /// This makes it possible to open an index with a custom
+    /// `Directory`, or with a configured `MmapDirectory`.
+    pub fn open_directory<Dir: Directory>(directory: Dir) -> Result<Index> {
+        let directory = ManagedDirectory::new(directory)?;
+        let metas = load_metas(&directory)?;
+        Index::create_from_metas(directory, &metas, None)
+    }
+
+    /// Opens an index in read-only mode from an index path.
+    ///
+    /// See `Index::open_directory_read_only`.
+    #[cfg(feature = "mmap")]
+    pub fn open_read_only<P: AsRef<Path>>(directory_path: P) -> Result<Index> {
+        let mmap_directory = MmapDirectory::open(directory_path)?;
+        Index::open_directory_read_only(mmap_directory)
+    }
+
+    /// Opens an index stored in the given directory in read-only mode.
+    ///
+    /// Tantivy will never write, lock or delete any file of the directory,
+    /// so that the index can be opened without any write permission,
+    /// for instance from a read-only container layer.
+    ///
+    /// The meta file is read once upon opening: the index
+    /// sticks to this generation of segments for its entire lifetime,
+    /// even if another process commits to the directory.
+    ///
+    /// Opening an `IndexWriter` on a read-only index fails.
+    pub fn open_directory_read_only<Dir: Directory>(directory: Dir) -> Result<Index> {
+        let directory = ManagedDirectory::new(ReadOnlyDirectory::wrap(directory))?;
         let metas = load_metas(&directory)?;
-        Index::create_from_metas(directory, &metas)
+        let pinned_metas = Arc::new(metas.clone());
+        Index::create_from_metas(directory, &metas, Some(pinned_metas))
+    }
+
+    /// Opens an index packed into a single file by `IndexSnapshot::pack_to(...)`.
+    ///
+    /// The packed file is mmapped, and the index is opened in read-only mode.
+    #[cfg(feature = "mmap")]
+    pub fn open_packed<P: AsRef<Path>>(packed_path: P) -> Result<Index> {
+        let packed_directory = PackedDirectory::open(packed_path)?;
+        Index::open_directory_read_only(packed_directory)
+    }
+
+    /// Returns true iff the index was opened in read-only mode.
+    pub fn is_read_only(&self) -> bool {
+        self.pinned_metas.is_some()
     }
 
     /// Reads the index meta file from the directory.
+    ///
+    /// A read-only index returns the metas it was opened with.
     pub fn load_metas(&self) -> Result<IndexMeta> {
+        if let Some(ref pinned_metas) = self.pinned_metas {
+            return Ok(IndexMeta::clone(pinned_metas));
+        }
         load_metas(self.directory())
     }
 
@@ -131,7 +287,8 @@ impl Index {
     /// should work at the same time.
     ///
     /// # Errors
-    /// If the lockfile already exists, returns `Error::FileAlreadyExists`.
+    /// If the lockfile is held by a living process, returns `ErrorKind::IndexLocked`.
+    /// If the index was opened in read-only mode, returns `ErrorKind::InvalidArgument`.
     /// # Panics
     /// If the heap size per thread is too small, panics.
     pub fn writer_with_num_threads(
@@ -139,21 +296,121 @@ impl Index {
         num_threads: usize,
         heap_size_in_bytes: usize,
     ) -> Result<IndexWriter> {
+        if self.is_read_only() {
+            bail!(ErrorKind::InvalidArgument(
+                "Cannot open a writer on an index opened in read-only mode.".to_string()
+            ));
+        }
         let directory_lock = DirectoryLock::lock(self.directory().box_clone())?;
-        open_index_writer(self, num_threads, heap_size_in_bytes, directory_lock)
+        open_index_writer(self, num_threads, heap_size_in_bytes, directory_lock, None)
+    }
+
+    /// Creates a deterministic writer, for reproducible tests.
+    ///
+    /// Two indexes fed with the same sequence of operations by deterministic
+    /// writers end up with the same segments, byte for byte:
+    /// - documents are indexed by a single thread,
+    /// - a segment is flushed as soon as it reaches `max_docs_per_segment`
+    ///   documents, or when its heap is full,
+    /// - segment ids are taken from an increasing sequence
+    ///   instead of being random,
+    /// - merges are not run in the background: the merges suggested
+    ///   by the merge policy are run one after the other upon commit,
+    ///   before `.commit()` returns.
+    ///
+    /// Indexing is slower than with a regular writer, so that
+    /// this mode is only meant for tests and debugging.
+    ///
+    /// # Errors
+    /// If the lockfile is held by a living process, returns `ErrorKind::IndexLocked`.
+    /// If the index was opened in read-only mode, returns `ErrorKind::InvalidArgument`.
+    /// # Panics
+    /// If the heap size is too small, or if `max_docs_per_segment` is 0, panics.
+    pub fn deterministic_writer(
+        &self,
+        heap_size_in_bytes: usize,
+        max_docs_per_segment: usize,
+    ) -> Result<IndexWriter> {
+        assert!(
+            max_docs_per_segment > 0,
+            "max_docs_per_segment must be strictly positive."
+        );
+        if self.is_read_only() {
+            bail!(ErrorKind::InvalidArgument(
+                "Cannot open a writer on an index opened in read-only mode.".to_string()
+            ));
+        }
+        let directory_lock = DirectoryLock::lock(self.directory().box_clone())?;
+        let segment_ids = self.searchable_segment_ids()?;
+        let deterministic_settings = DeterministicSettings {
+            max_docs_per_segment,
+            segment_id_sequence: Arc::new(SegmentIdSequence::starting_after(&segment_ids)),
+        };
+        open_index_writer(
+            self,
+            1,
+            heap_size_in_bytes,
+            directory_lock,
+            Some(deterministic_settings),
+        )
     }
 
     /// Creates a multithreaded writer
     /// It just calls `writer_with_num_threads` with the number of cores as `num_threads`
     ///
     /// # Errors
-    /// If the lockfile already exists, returns `Error::FileAlreadyExists`.
+    /// If the lockfile is held by a living process, returns `ErrorKind::IndexLocked`.
     /// # Panics
     /// If the heap size per thread is too small, panics.
     pub fn writer(&self, heap_size_in_bytes: usize) -> Result<IndexWriter> {
         self.writer_with_num_threads(num_cpus::get(), heap_size_in_bytes)
     }
 
+    /// Creates an asynchronous front-end to a writer.
+    ///
+    /// The writer is opened with `writer_with_num_threads` in a dedicated
+    /// thread, which consumes the operations sent through the returned handle.
+    /// At most `queue_capacity` operations, plus one per clone of
+    /// the handle, wait in the queue of the writer before the futures
+    /// returned by the handle stop resolving.
+    ///
+    /// See [`IndexWriterHandle`](struct.IndexWriterHandle.html).
+    ///
+    /// # Errors
+    /// If the lockfile is held by a living process, returns `ErrorKind::IndexLocked`.
+    /// If the index was opened in read-only mode, returns `ErrorKind::InvalidArgument`.
+    pub fn writer_handle(
+        &self,
+        num_threads: usize,
+        heap_size_in_bytes: usize,
+        queue_capacity: usize,
+    ) -> Result<IndexWriterHandle> {
+        IndexWriterHandle::spawn(self, num_threads, heap_size_in_bytes, queue_capacity)
+    }
+
+    /// Accessor to the index settings.
+    pub fn settings(&self) -> &IndexSettings {
+        &self.settings
+    }
+
+    /// Sets the `MemoryBudget` shared by the caches of the readers of the index:
+    /// the fast fields, the doc store blocks and the `FilterCache`s.
+    ///
+    /// The budget applies to the segment readers and the `IndexReader`s
+    /// opened afterwards. The searchers of the index are reloaded right away.
+    /// A budget can be shared by several indexes, to cap their footprint as a whole.
+    ///
+    /// By default, the caches are not bounded by any budget.
+    pub fn set_memory_budget(&mut self, memory_budget: MemoryBudget) -> Result<()> {
+        self.memory_budget = Some(memory_budget);
+        self.load_searchers()
+    }
+
+    /// Returns the `MemoryBudget` of the index, if any.
+    pub fn memory_budget(&self) -> Option<&MemoryBudget> {
+        self.memory_budget.as_ref()
+    }
+
     /// Accessor to the index schema
     ///
     /// The schema is actually cloned.
@@ -190,12 +447,119 @@ impl Index {
         &mut self.directory
     }
 
+    /// Returns the IO statistics of the index, broken down by file type.
+    ///
+    /// The counters are shared by all of the clones of the index,
+    /// and accumulate until `.reset_io_stats()` is called.
+    pub fn io_stats(&self) -> IoStats {
+        self.directory.io_stats()
+    }
+
+    /// Resets the IO statistics of the index.
+    pub fn reset_io_stats(&self) {
+        self.directory.reset_io_stats()
+    }
+
+    /// Takes a snapshot of the last commit of the index.
+    ///
+    /// The files of the snapshot are protected from garbage collection
+    /// until the snapshot is dropped, which makes it possible
+    /// to backup the index while it is being written to.
+    pub fn snapshot(&self) -> Result<IndexSnapshot> {
+        IndexSnapshot::create(self.directory())
+    }
+
+    /// Installs a commit received from a primary index on this replica.
+    ///
+    /// `meta_data` is the content of the `meta.json` file of the commit
+    /// (See `IndexSnapshot::meta_data()`), and `files` are the segment files
+    /// of the commit, checksum footer included, that the replica does not have yet
+    /// (See `ReplicationManifest::delta_since(...)`).
+    ///
+    /// The checksums of the received files are verified before anything is written.
+    /// The commit is published by atomically replacing the `meta.json` file,
+    /// after which the files that are not used by the commit anymore
+    /// are garbage collected and the searchers are reloaded.
+    ///
+    /// # Errors
+    /// If an `IndexWriter` is opened on the replica, returns `ErrorKind::IndexLocked`.
+    /// If a received file is corrupted, returns `ErrorKind::CorruptedFile`.
+    /// If the index was opened in read-only mode, if the schema of the commit
+    /// differs from the schema of the index, or if a file of the commit is
+    /// missing, returns `ErrorKind::InvalidArgument`.
+    pub fn install_replica(&self, meta_data: &[u8], files: &[(PathBuf, Vec<u8>)]) -> Result<()> {
+        if self.is_read_only() {
+            bail!(ErrorKind::InvalidArgument(
+                "Cannot install a replica on an index opened in read-only mode.".to_string()
+            ));
+        }
+        install_replica(self, meta_data, files)
+    }
+
+    /// Verifies the checksums of the files of the searchable segments.
+    ///
+    /// Returns the list of corrupted files.
+    /// Files written by a version of tantivy that did not record checksums
+    /// are not checked.
+    pub fn validate_checksums(&self) -> Result<HashSet<PathBuf>> {
+        let directory = self.directory();
+        let mut corrupted_files = HashSet::new();
+        for segment_meta in self.searchable_segment_metas()? {
+            for path in segment_meta.list_files() {
+                match directory.validate_checksum(&path) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        corrupted_files.insert(path);
+                    }
+                    // optional components, such as the delete bitset,
+                    // may not exist.
+                    Err(OpenReadError::FileDoesNotExist(_)) => {}
+                    Err(OpenReadError::IOError(io_error)) => {
+                        return Err(ErrorKind::IOError(io_error).into());
+                    }
+                }
+            }
+        }
+        Ok(corrupted_files)
+    }
+
+    /// Checks that the components of every searchable segment
+    /// agree on their number of documents.
+    ///
+    /// See `SegmentReader::validate_doc_counts`.
+    pub fn validate_doc_counts(&self) -> Result<()> {
+        for segment in self.searchable_segments()? {
+            SegmentReader::open(&segment)?.validate_doc_counts()?;
+        }
+        Ok(())
+    }
+
     /// Reads the meta.json and returns the list of
     /// `SegmentMeta` from the last commit.
     pub fn searchable_segment_metas(&self) -> Result<Vec<SegmentMeta>> {
         Ok(self.load_metas()?.segments)
     }
 
+    /// Returns structured information about the last commit:
+    /// its segments, their number of documents, and the size of their files.
+    ///
+    /// It is meant for admin UIs and debugging, and saves
+    /// parsing the `meta.json` file by hand.
+    pub fn inspect(&self) -> Result<IndexInfo> {
+        let metas = self.load_metas()?;
+        inspect_index(self.directory(), metas)
+    }
+
+    /// Returns the breakdown of the disk space used by the searchable segments,
+    /// per segment, per component, and per field for the components
+    /// that are stored per field.
+    ///
+    /// See `SpaceUsage`.
+    pub fn space_usage(&self) -> Result<SpaceUsage> {
+        let metas = self.load_metas()?;
+        compute_space_usage(self.directory(), &metas)
+    }
+
     /// Returns the list of segment ids that are searchable.
     pub fn searchable_segment_ids(&self) -> Result<Vec<SegmentId>> {
         Ok(self.searchable_segment_metas()?
@@ -204,6 +568,30 @@ impl Index {
             .collect())
     }
 
+    /// Opens a `SegmentReader` for each of the searchable segments.
+    pub(crate) fn open_searchable_segment_readers(&self) -> Result<Vec<SegmentReader>> {
+        self.searchable_segments()?
+            .iter()
+            .map(SegmentReader::open)
+            .collect()
+    }
+
+    /// Creates an `IndexReader` with the default settings.
+    ///
+    /// See `.reader_builder()`.
+    pub fn reader(&self) -> Result<IndexReader> {
+        self.reader_builder().try_into()
+    }
+
+    /// Returns a builder to configure and create an `IndexReader`.
+    ///
+    /// Servers should prefer an `IndexReader` over the searchers
+    /// of the `Index` itself: each reader owns its own pool of searchers,
+    /// and its own `ReloadPolicy`.
+    pub fn reader_builder(&self) -> IndexReaderBuilder {
+        IndexReaderBuilder::new(self.clone())
+    }
+
     /// Creates a new generation of searchers after
 
     /// a change of the set of searchable indexes.
@@ -211,16 +599,60 @@ impl Index {
     /// This needs to be called when a new segment has been
     /// published or after a merge.
     pub fn load_searchers(&self) -> Result<()> {
-        let searchable_segments = self.searchable_segments()?;
-        let segment_readers: Vec<SegmentReader> = searchable_segments
-            .iter()
-            .map(SegmentReader::open)
-            .collect::<Result<_>>()?;
+        let segment_readers = self.open_searchable_segment_readers()?;
+        self.publish_segment_readers(segment_readers);
+        Ok(())
+    }
+
+    /// Sets the `ReloadPolicy` of the index.
+    ///
+    /// By default, searchers are reloaded manually.
+    /// With `ReloadPolicy::OnCommit`, the meta file is watched,
+    /// and searchers are reloaded in the background after every commit.
+    /// The watch stops when the index and all of its clones are dropped.
+    ///
+    /// # Errors
+    /// If the index was opened in read-only mode, `OnCommit` returns
+    /// `ErrorKind::InvalidArgument`, as its meta file is never read again.
+    pub fn set_reload_policy(&mut self, reload_policy: ReloadPolicy) -> Result<()> {
+        match reload_policy {
+            ReloadPolicy::Manual => {
+                self.reload_watch = None;
+                Ok(())
+            }
+            ReloadPolicy::OnCommit => self.reload_on_commit(Box::new(|| {})),
+        }
+    }
+
+    /// Reloads the searchers after every commit, and calls
+    /// `on_reload` once they are reloaded.
+    pub(crate) fn reload_on_commit(&mut self, on_reload: WatchCallback) -> Result<()> {
+        // The callback holds a clone of the index. Dropping the current watch first
+        // ensures that this clone does not keep its own watch alive.
+        self.reload_watch = None;
+        if self.is_read_only() {
+            bail!(ErrorKind::InvalidArgument(
+                "A read-only index cannot be reloaded on commit.".to_string()
+            ));
+        }
+        let index = self.clone();
+        let watch_handle = self.directory.watch(Box::new(move || {
+            if let Err(e) = index.load_searchers() {
+                error!("Failed to reload searchers: {:?}", e);
+            }
+            on_reload();
+        }))?;
+        self.reload_watch = Some(Arc::new(watch_handle));
+        Ok(())
+    }
+
+    /// Publishes a new generation of searchers over
+    /// the given segment readers.
+    pub(crate) fn publish_segment_readers(&self, segment_readers: Vec<SegmentReader>) {
         let searchers = (0..NUM_SEARCHERS)
             .map(|_| Searcher::from(segment_readers.clone()))
             .collect();
         self.searcher_pool.publish_new_generation(searchers);
-        Ok(())
     }
 
     /// Returns a searcher
@@ -249,8 +681,135 @@ impl Clone for Index {
         Index {
             directory: self.directory.clone(),
             schema: self.schema.clone(),
+            settings: self.settings.clone(),
             searcher_pool: Arc::clone(&self.searcher_pool),
             tokenizers: self.tokenizers.clone(),
+            pinned_metas: self.pinned_metas.clone(),
+            reload_watch: self.reload_watch.clone(),
+            memory_budget: self.memory_budget.clone(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "mmap"))]
+mod tests {
+
+    use DocAddress;
+    use core::{IndexSettings, ReloadPolicy, SegmentComponent};
+    use schema::{SchemaBuilder, STORED, TEXT};
+    use store::Compressor;
+    use super::Index;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_index_settings_docstore_compression() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let schema = schema_builder.build();
+        let tempdir = TempDir::new("index_settings").unwrap();
+        let settings = IndexSettings {
+            docstore_compression: Compressor::None,
+            ..IndexSettings::default()
+        };
+        {
+            let index =
+                Index::create_with_settings(tempdir.path(), schema, settings.clone()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a b c"));
+            index_writer.commit().unwrap();
+        }
+        let index = Index::open(tempdir.path()).unwrap();
+        assert_eq!(index.settings(), &settings);
+        let searcher = index.searcher();
+        let doc = searcher.doc(&DocAddress(0, 0)).unwrap();
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "a b c");
+    }
+
+    #[test]
+    fn test_validate_checksums() {
+        use std::fs::OpenOptions;
+        use std::io::{Read, Seek, SeekFrom, Write};
+
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let tempdir = TempDir::new("checksums").unwrap();
+        let store_path = {
+            let index = Index::create(tempdir.path(), schema_builder.build()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a b c"));
+            index_writer.add_document(doc!(text_field=>"a d"));
+            index_writer.commit().unwrap();
+            assert!(index.validate_checksums().unwrap().is_empty());
+            index.validate_doc_counts().unwrap();
+            let segment_metas = index.searchable_segment_metas().unwrap();
+            segment_metas[0].relative_path(SegmentComponent::STORE)
+        };
+        {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(tempdir.path().join(&store_path))
+                .unwrap();
+            let mut first_byte = [0u8; 1];
+            file.read_exact(&mut first_byte).unwrap();
+            file.seek(SeekFrom::Start(0)).unwrap();
+            file.write_all(&[first_byte[0] ^ 1u8]).unwrap();
+            file.flush().unwrap();
         }
+        let index = Index::open(tempdir.path()).unwrap();
+        let corrupted_files = index.validate_checksums().unwrap();
+        assert_eq!(corrupted_files.len(), 1);
+        assert!(corrupted_files.contains(&store_path));
+    }
+
+    #[test]
+    fn test_open_read_only() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let tempdir = TempDir::new("read_only").unwrap();
+        let index = Index::create(tempdir.path(), schema_builder.build()).unwrap();
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a"));
+        index_writer.commit().unwrap();
+
+        let read_only_index = Index::open_read_only(tempdir.path()).unwrap();
+        assert!(read_only_index.is_read_only());
+        assert!(read_only_index.writer_with_num_threads(1, 40_000_000).is_err());
+        assert_eq!(read_only_index.searcher().num_docs(), 1);
+
+        index_writer.add_document(doc!(text_field=>"b"));
+        index_writer.commit().unwrap();
+        read_only_index.load_searchers().unwrap();
+        assert_eq!(read_only_index.searcher().num_docs(), 1);
+        assert_eq!(read_only_index.load_metas().unwrap().opstamp, 1);
+    }
+
+    #[test]
+    fn test_reload_policy_on_commit() {
+        use std::sync::Mutex;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let tempdir = TempDir::new("reload").unwrap();
+        let index = Index::create(tempdir.path(), schema_builder.build()).unwrap();
+        let mut reader_index = Index::open(tempdir.path()).unwrap();
+        reader_index.set_reload_policy(ReloadPolicy::OnCommit).unwrap();
+        assert_eq!(reader_index.searcher().num_docs(), 0);
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        reader_index
+            .reload_on_commit(Box::new(move || {
+                sender.lock().unwrap().send(()).unwrap();
+            }))
+            .unwrap();
+
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a"));
+        index_writer.commit().unwrap();
+        // the timeout only prevents the test from hanging forever.
+        assert!(receiver.recv_timeout(Duration::from_secs(30)).is_ok());
+        assert_eq!(reader_index.searcher().num_docs(), 1);
     }
 }
@@ -0,0 +1,162 @@
+use Result;
+use common::HasLen;
+use core::{IndexMeta, IndexSettings, SegmentComponent, SegmentId, SegmentMeta};
+use directory::Directory;
+use directory::error::OpenReadError;
+use std::path::PathBuf;
+
+/// Structured information about an `Index`, and its searchable segments.
+///
+/// It is returned by `Index::inspect()`, and can be serialized,
+/// for instance to be displayed by an admin UI.
+#[derive(Clone, Debug, Serialize)]
+pub struct IndexInfo {
+    /// Opstamp of the last commit.
+    pub opstamp: u64,
+    /// Settings of the index.
+    pub settings: IndexSettings,
+    /// Payload of the last commit, if any.
+    pub payload: Option<String>,
+    /// Searchable segments of the last commit.
+    pub segments: Vec<SegmentInfo>,
+}
+
+impl IndexInfo {
+    /// Returns the number of documents of the index,
+    /// deleted documents excluded.
+    pub fn num_docs(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|segment| u64::from(segment.num_docs))
+            .sum()
+    }
+
+    /// Returns the overall size of the files of the segments.
+    pub fn num_bytes(&self) -> usize {
+        self.segments.iter().map(SegmentInfo::num_bytes).sum()
+    }
+}
+
+/// Information about a segment. See `IndexInfo`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentInfo {
+    /// Id of the segment.
+    pub segment_id: SegmentId,
+    /// Number of documents, deleted documents included.
+    pub max_doc: u32,
+    /// Number of documents, deleted documents excluded.
+    pub num_docs: u32,
+    /// Number of deleted documents.
+    pub num_deleted_docs: u32,
+    /// Opstamp of the last deletes applied to the segment, if any.
+    ///
+    /// Segments do not record the opstamp at which they were created.
+    pub delete_opstamp: Option<u64>,
+    /// Opstamp of the last fast field updates applied to the segment, if any.
+    pub fast_field_updates_opstamp: Option<u64>,
+    /// Files of the segment.
+    pub components: Vec<ComponentInfo>,
+}
+
+impl SegmentInfo {
+    /// Returns the overall size of the files of the segment.
+    pub fn num_bytes(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component| component.num_bytes)
+            .sum()
+    }
+}
+
+/// Information about a file of a segment. See `IndexInfo`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ComponentInfo {
+    /// Component stored in the file.
+    pub component: SegmentComponent,
+    /// Path of the file, relative to the directory of the index.
+    pub path: PathBuf,
+    /// Size of the file, in bytes.
+    pub num_bytes: usize,
+}
+
+fn inspect_segment(directory: &Directory, segment_meta: &SegmentMeta) -> Result<SegmentInfo> {
+    let mut components = vec![];
+    for &component in SegmentComponent::iterator() {
+        let path = segment_meta.relative_path(component);
+        match directory.open_read(&path) {
+            Ok(source) => components.push(ComponentInfo {
+                component,
+                path,
+                num_bytes: source.len(),
+            }),
+            // optional components, such as the delete bitset,
+            // may not exist.
+            Err(OpenReadError::FileDoesNotExist(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(SegmentInfo {
+        segment_id: segment_meta.id(),
+        max_doc: segment_meta.max_doc(),
+        num_docs: segment_meta.num_docs(),
+        num_deleted_docs: segment_meta.num_deleted_docs(),
+        delete_opstamp: segment_meta.delete_opstamp(),
+        fast_field_updates_opstamp: segment_meta.fast_field_updates_opstamp(),
+        components,
+    })
+}
+
+pub(crate) fn inspect(directory: &Directory, metas: IndexMeta) -> Result<IndexInfo> {
+    let segments = metas
+        .segments
+        .iter()
+        .map(|segment_meta| inspect_segment(directory, segment_meta))
+        .collect::<Result<_>>()?;
+    Ok(IndexInfo {
+        opstamp: metas.opstamp,
+        settings: metas.settings,
+        payload: metas.payload,
+        segments,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use core::SegmentComponent;
+    use schema::{SchemaBuilder, INT_INDEXED};
+    use serde_json;
+
+    #[test]
+    fn test_inspect() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        assert!(index.inspect().unwrap().segments.is_empty());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for id in 0u64..10u64 {
+            index_writer.add_document(doc!(id_field=>id));
+        }
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id_field, 3u64));
+        let opstamp = index_writer.commit().unwrap();
+
+        let index_info = index.inspect().unwrap();
+        assert_eq!(index_info.opstamp, opstamp);
+        assert_eq!(index_info.num_docs(), 9);
+        assert_eq!(index_info.segments.len(), 1);
+        let segment_info = &index_info.segments[0];
+        assert_eq!(segment_info.max_doc, 10);
+        assert_eq!(segment_info.num_deleted_docs, 1);
+        assert!(segment_info.delete_opstamp.is_some());
+        assert!(segment_info
+            .components
+            .iter()
+            .any(|component_info| component_info.component == SegmentComponent::DELETE));
+        assert!(segment_info.num_bytes() > 0);
+        assert_eq!(index_info.num_bytes(), segment_info.num_bytes());
+        assert!(serde_json::to_string(&index_info).is_ok());
+    }
+}
@@ -1,8 +1,37 @@
+use Result;
+use error::{ErrorKind, ResultExt};
 use schema::Schema;
 use core::SegmentMeta;
+use core::IndexSettings;
 use std::fmt;
+use std::path::Path;
 use serde_json;
 
+/// Version of the format of the index written by this version of tantivy.
+///
+/// It is recorded in `meta.json`, and in the meta of each segment.
+/// Indexes and segments written before versions were recorded
+/// are considered to be at version `0`.
+///
+/// An index, or a segment, with a more recent version cannot be opened.
+/// Segments with an older version can be rewritten in the
+/// current format with `upgrade_index`, as long as they are not
+/// older than `MIN_READABLE_FORMAT_VERSION`.
+pub const INDEX_FORMAT_VERSION: u32 = 1;
+
+/// Oldest version of the format that this version of tantivy can read.
+///
+/// All of the versions are currently readable, including the segments
+/// written before versions were recorded, at version `0`: their files do
+/// not end with a checksum footer, their posting lists do not have any
+/// skip data, and their fast fields do not record their codec.
+///
+/// Raising it drops the decoders of the older formats. The segments written
+/// in these formats must then be rejected with an `IncompatibleIndexVersion`
+/// error when they are opened, and their documents reindexed,
+/// unless they were rewritten with `upgrade_index` beforehand.
+pub const MIN_READABLE_FORMAT_VERSION: u32 = 0;
+
 /// Meta information about the `Index`.
 ///
 /// This object is serialized on disk in the `meta.json` file.
@@ -10,25 +39,71 @@ use serde_json;
 /// * the searchable segments,
 /// * the index `docstamp`
 /// * the schema
+/// * the index settings
+/// * the version of the format of the index
 ///
 #[derive(Clone, Serialize, Deserialize)]
 pub struct IndexMeta {
     pub segments: Vec<SegmentMeta>,
     pub schema: Schema,
+    #[serde(default)]
+    pub settings: IndexSettings,
     pub opstamp: u64,
+    #[serde(default)]
+    pub index_format_version: u32,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub payload: Option<String>,
 }
 
 impl IndexMeta {
     pub fn with_schema(schema: Schema) -> IndexMeta {
+        IndexMeta::with_schema_and_settings(schema, IndexSettings::default())
+    }
+
+    pub fn with_schema_and_settings(schema: Schema, settings: IndexSettings) -> IndexMeta {
         IndexMeta {
             segments: vec![],
             schema,
+            settings,
             opstamp: 0u64,
+            index_format_version: INDEX_FORMAT_VERSION,
             payload: None,
         }
     }
+
+    /// Deserializes the content of a meta file.
+    ///
+    /// The format version of the index is checked before the rest of
+    /// the file is deserialized, so that a meta file written in a more
+    /// recent format returns an `IncompatibleIndexVersion` error rather
+    /// than a `CorruptedFile` one. The versions of the segments are checked
+    /// as well. Invalid JSON returns a `CorruptedFile` error pointing to `path`.
+    pub(crate) fn deserialize(meta_data: &[u8], path: &Path) -> Result<IndexMeta> {
+        #[derive(Deserialize)]
+        struct VersionProbe {
+            #[serde(default)]
+            index_format_version: u32,
+        }
+        if let Ok(probe) = serde_json::from_slice::<VersionProbe>(meta_data) {
+            check_format_version(probe.index_format_version)?;
+        }
+        let metas: IndexMeta = serde_json::from_slice(meta_data)
+            .chain_err(|| ErrorKind::CorruptedFile(path.to_owned()))?;
+        for segment_meta in &metas.segments {
+            check_format_version(segment_meta.format_version())?;
+        }
+        Ok(metas)
+    }
+}
+
+fn check_format_version(format_version: u32) -> Result<()> {
+    if format_version > INDEX_FORMAT_VERSION {
+        bail!(ErrorKind::IncompatibleIndexVersion(
+            format_version,
+            INDEX_FORMAT_VERSION
+        ));
+    }
+    Ok(())
 }
 
 impl fmt::Debug for IndexMeta {
@@ -41,8 +116,11 @@ impl fmt::Debug for IndexMeta {
 mod tests {
 
     use serde_json;
-    use super::IndexMeta;
+    use super::{IndexMeta, INDEX_FORMAT_VERSION};
+    use core::{IndexSettings, SegmentId, SegmentMeta};
+    use error::{Error, ErrorKind};
     use schema::{SchemaBuilder, TEXT};
+    use std::path::Path;
 
     #[test]
     fn test_serialize_metas() {
@@ -54,10 +132,54 @@ mod tests {
         let index_metas = IndexMeta {
             segments: Vec::new(),
             schema: schema,
+            settings: IndexSettings::default(),
             opstamp: 0u64,
+            index_format_version: INDEX_FORMAT_VERSION,
             payload: None,
         };
         let json = serde_json::ser::to_string(&index_metas).expect("serialization failed");
-        assert_eq!(json, r#"{"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","tokenizer":"default"},"stored":false}}],"opstamp":0}"#);
+        assert_eq!(json, r#"{"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","tokenizer":"default","position_gap":1,"term_vectors":false},"stored":false,"fast":false,"completion":false}}],"settings":{"docstore_compression":"Lz4","docstore_blocksize":16384,"docstore_layout":"LargeBlock"},"opstamp":0,"index_format_version":1}"#);
+    }
+
+    #[test]
+    fn test_deserialize_metas_without_settings() {
+        let json = r#"{"segments":[],"schema":[{"name":"text","type":"text","options":{"indexing":{"record":"position","tokenizer":"default"},"stored":false}}],"opstamp":0}"#;
+        let index_metas: IndexMeta = serde_json::from_str(json).expect("deserialization failed");
+        assert_eq!(index_metas.settings, IndexSettings::default());
+        assert_eq!(index_metas.index_format_version, 0);
+    }
+
+    #[test]
+    fn test_deserialize_metas_incompatible_version() {
+        let path = Path::new("meta.json");
+        let mut index_metas = IndexMeta::with_schema(SchemaBuilder::new().build());
+        index_metas
+            .segments
+            .push(SegmentMeta::new(SegmentId::generate_random()));
+        let mut json = serde_json::to_value(&index_metas).unwrap();
+        json["segments"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("format_version");
+        let deserialized = IndexMeta::deserialize(json.to_string().as_bytes(), path).unwrap();
+        assert_eq!(deserialized.segments[0].format_version(), 0);
+
+        json["segments"][0]["format_version"] = serde_json::Value::from(INDEX_FORMAT_VERSION + 1);
+        match IndexMeta::deserialize(json.to_string().as_bytes(), path) {
+            Err(Error(ErrorKind::IncompatibleIndexVersion(found, INDEX_FORMAT_VERSION), _)) => {
+                assert_eq!(found, INDEX_FORMAT_VERSION + 1);
+            }
+            _ => panic!("Expected an IncompatibleIndexVersion error"),
+        }
+        // the layout of the meta files of future versions is unknown.
+        let json = r#"{"index_format_version":1000,"segments":{}}"#;
+        match IndexMeta::deserialize(json.as_bytes(), path) {
+            Err(Error(ErrorKind::IncompatibleIndexVersion(1000, INDEX_FORMAT_VERSION), _)) => {}
+            _ => panic!("Expected an IncompatibleIndexVersion error"),
+        }
+        match IndexMeta::deserialize(b"{", path) {
+            Err(Error(ErrorKind::CorruptedFile(_), _)) => {}
+            _ => panic!("Expected a CorruptedFile error"),
+        }
     }
 }
@@ -0,0 +1,414 @@
+use Result;
+use error::ErrorKind;
+use core::Index;
+use core::ReloadPolicy;
+use core::{FilterCache, Searcher, Warmer};
+use core::point_in_time::PointInTimeRegistry;
+use directory::{Directory, WatchHandle};
+use std::sync::Arc;
+use std::time::Duration;
+use super::pool::{LeasedItem, Pool};
+
+const DEFAULT_NUM_SEARCHERS: usize = 12;
+
+/// Builder for an `IndexReader`. See `Index::reader_builder()`.
+#[derive(Clone)]
+pub struct IndexReaderBuilder {
+    index: Index,
+    reload_policy: ReloadPolicy,
+    num_searchers: usize,
+    filter_cache_capacity: Option<usize>,
+    warmers: Vec<Arc<Warmer>>,
+}
+
+impl IndexReaderBuilder {
+    pub(crate) fn new(index: Index) -> IndexReaderBuilder {
+        IndexReaderBuilder {
+            index,
+            reload_policy: ReloadPolicy::Manual,
+            num_searchers: DEFAULT_NUM_SEARCHERS,
+            filter_cache_capacity: None,
+            warmers: Vec::new(),
+        }
+    }
+
+    /// Sets the `ReloadPolicy` of the reader.
+    ///
+    /// Defaults to `ReloadPolicy::Manual`.
+    pub fn reload_policy(mut self, reload_policy: ReloadPolicy) -> IndexReaderBuilder {
+        self.reload_policy = reload_policy;
+        self
+    }
+
+    /// Sets the number of `Searcher`s in the pool of the reader.
+    ///
+    /// It bounds the number of queries that can run concurrently:
+    /// `.searcher()` blocks when all of the searchers are in use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_searchers` is 0.
+    pub fn num_searchers(mut self, num_searchers: usize) -> IndexReaderBuilder {
+        assert!(num_searchers > 0, "An IndexReader requires at least one searcher.");
+        self.num_searchers = num_searchers;
+        self
+    }
+
+    /// Gives the searchers of the reader a `FilterCache` holding
+    /// at most `capacity` bitsets, used by `CachedFilterQuery`.
+    ///
+    /// The cache is shared by the searchers of a same generation,
+    /// and starts empty after every reload.
+    ///
+    /// By default, searchers do not have any filter cache.
+    pub fn filter_cache_capacity(mut self, capacity: usize) -> IndexReaderBuilder {
+        self.filter_cache_capacity = Some(capacity);
+        self
+    }
+
+    /// Adds a `Warmer`, called on each segment of every new
+    /// generation of searchers, before it is published.
+    ///
+    /// Warmers are called in the order they were added.
+    pub fn warmer(mut self, warmer: Arc<Warmer>) -> IndexReaderBuilder {
+        self.warmers.push(warmer);
+        self
+    }
+
+    /// Builds the `IndexReader`, and loads its first generation of searchers.
+    ///
+    /// # Errors
+    /// If the index was opened in read-only mode, `ReloadPolicy::OnCommit`
+    /// returns `ErrorKind::InvalidArgument`, as its meta file is never read again.
+    pub fn try_into(self) -> Result<IndexReader> {
+        let inner = Arc::new(InnerIndexReader {
+            index: self.index,
+            num_searchers: self.num_searchers,
+            filter_cache_capacity: self.filter_cache_capacity,
+            warmers: self.warmers,
+            searcher_pool: Pool::new(),
+            point_in_times: PointInTimeRegistry::default(),
+        });
+        inner.reload()?;
+        let watch_handle = match self.reload_policy {
+            ReloadPolicy::Manual => None,
+            ReloadPolicy::OnCommit => {
+                if inner.index.is_read_only() {
+                    bail!(ErrorKind::InvalidArgument(
+                        "A read-only index cannot be reloaded on commit.".to_string()
+                    ));
+                }
+                let callback_inner = Arc::clone(&inner);
+                let watch_handle = inner.index.directory().watch(Box::new(move || {
+                    if let Err(e) = callback_inner.reload() {
+                        error!("Failed to reload searchers: {:?}", e);
+                    }
+                }))?;
+                Some(Arc::new(watch_handle))
+            }
+        };
+        Ok(IndexReader {
+            inner,
+            reload_policy: self.reload_policy,
+            _watch_handle: watch_handle,
+        })
+    }
+}
+
+struct InnerIndexReader {
+    index: Index,
+    num_searchers: usize,
+    filter_cache_capacity: Option<usize>,
+    warmers: Vec<Arc<Warmer>>,
+    searcher_pool: Pool<Searcher>,
+    point_in_times: PointInTimeRegistry,
+}
+
+impl InnerIndexReader {
+    /// Opens `num_searchers` searchers over the last commit,
+    /// and warms their segments.
+    fn open_searchers(&self, num_searchers: usize) -> Result<Vec<Searcher>> {
+        let segment_readers = self.index.open_searchable_segment_readers()?;
+        let filter_cache = self.filter_cache_capacity.map(|capacity| {
+            let filter_cache = match self.index.memory_budget() {
+                Some(memory_budget) => {
+                    FilterCache::with_memory_budget(capacity, memory_budget.clone())
+                }
+                None => FilterCache::with_capacity(capacity),
+            };
+            Arc::new(filter_cache)
+        });
+        let searchers: Vec<Searcher> = (0..num_searchers)
+            .map(|_| match filter_cache {
+                Some(ref filter_cache) => {
+                    Searcher::with_filter_cache(segment_readers.clone(), Arc::clone(filter_cache))
+                }
+                None => Searcher::from(segment_readers.clone()),
+            })
+            .collect();
+        for warmer in &self.warmers {
+            for segment_reader in &segment_readers {
+                warmer.warm(&searchers[0], segment_reader)?;
+            }
+        }
+        Ok(searchers)
+    }
+
+    fn reload(&self) -> Result<()> {
+        let _span = span!("reload");
+        let searchers = self.open_searchers(self.num_searchers)?;
+        self.searcher_pool.publish_new_generation(searchers);
+        Ok(())
+    }
+}
+
+/// `IndexReader` owns a pool of `Searcher`s over an `Index`.
+///
+/// It is cheap to clone, and meant to be shared by the threads
+/// of a server: each query acquires its own `Searcher` through
+/// `.searcher()`, and releases it when the `Searcher` is dropped.
+///
+/// Searchers are reloaded following the `ReloadPolicy` of the reader,
+/// and their segments are warmed by the `Warmer`s of the reader
+/// before they are published.
+/// Reloading publishes a new generation of searchers: the searchers of
+/// the previous generation that are in use keep working on their segments,
+/// and the files of these segments are not deleted
+/// until the last of these searchers is dropped.
+///
+/// Changes made visible by `IndexWriter::soft_commit()` are
+/// only published to the searchers of the `Index` itself.
+#[derive(Clone)]
+pub struct IndexReader {
+    inner: Arc<InnerIndexReader>,
+    reload_policy: ReloadPolicy,
+    // the directory watch stops when the last clone of the reader is dropped.
+    _watch_handle: Option<Arc<WatchHandle>>,
+}
+
+impl IndexReader {
+    /// Returns the `Index` read by the reader.
+    pub fn index(&self) -> &Index {
+        &self.inner.index
+    }
+
+    /// Returns the `ReloadPolicy` of the reader.
+    pub fn reload_policy(&self) -> ReloadPolicy {
+        self.reload_policy
+    }
+
+    /// Loads a new generation of searchers over the last commit.
+    ///
+    /// With `ReloadPolicy::OnCommit`, this is done automatically
+    /// after each commit.
+    pub fn reload(&self) -> Result<()> {
+        self.inner.reload()
+    }
+
+    /// Returns a `Searcher` over the last generation of segments.
+    ///
+    /// This method should be called every single time a search
+    /// query is performed, and the same searcher must be used for a given
+    /// query, as it ensures the use of a consistent segment set.
+    ///
+    /// If all of the searchers are in use, this blocks until
+    /// one of them is released.
+    pub fn searcher(&self) -> LeasedItem<Searcher> {
+        self.inner.searcher_pool.acquire()
+    }
+
+    /// Opens a named point-in-time reader over the last commit.
+    ///
+    /// The returned `Searcher` keeps seeing the same documents,
+    /// whatever commits and merges happen afterwards, which makes it possible
+    /// to run long exports or to paginate consistently while indexing continues.
+    /// The files of its segments are protected from garbage collection
+    /// for as long as the point-in-time reader is retained.
+    ///
+    /// The point-in-time reader is retained by the `IndexReader` and
+    /// all of its clones, and can be retrieved with `.point_in_time(name)`.
+    /// It expires after `keep_alive`, unless it is retrieved again in the meantime,
+    /// or when it is closed with `.close_point_in_time(name)`.
+    ///
+    /// # Errors
+    /// If a point-in-time reader with the same name is already open,
+    /// returns `ErrorKind::InvalidArgument`.
+    pub fn open_point_in_time(&self, name: &str, keep_alive: Duration) -> Result<Arc<Searcher>> {
+        let searcher = self.inner
+            .open_searchers(1)?
+            .pop()
+            .expect("One searcher was requested");
+        self.inner.point_in_times.register(name, searcher, keep_alive)
+    }
+
+    /// Returns the `Searcher` of the point-in-time reader `name`,
+    /// or `None` if it was closed or has expired.
+    ///
+    /// Retrieving a point-in-time reader extends its retention
+    /// by its `keep_alive` duration.
+    pub fn point_in_time(&self, name: &str) -> Option<Arc<Searcher>> {
+        self.inner.point_in_times.get(name)
+    }
+
+    /// Closes the point-in-time reader `name`.
+    ///
+    /// Its segment files may be garbage collected as soon as the
+    /// searchers previously returned for it are dropped.
+    ///
+    /// Returns false if there was no such point-in-time reader,
+    /// or if it had already expired.
+    pub fn close_point_in_time(&self, name: &str) -> bool {
+        self.inner.point_in_times.remove(name)
+    }
+
+    /// Returns the names of the point-in-time readers that are open, sorted.
+    pub fn point_in_time_names(&self) -> Vec<String> {
+        self.inner.point_in_times.names()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use ReloadPolicy;
+    use Result;
+    use core::{Searcher, SegmentReader, Warmer};
+    use directory::Directory;
+    use futures::Future;
+    use schema::{Field, SchemaBuilder, FAST, INT_INDEXED, TEXT};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[test]
+    fn test_index_reader_manual_reload() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index.reader_builder().num_searchers(2).try_into().unwrap();
+        assert_eq!(reader.reload_policy(), ReloadPolicy::Manual);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a"));
+        index_writer.commit().unwrap();
+
+        let old_searcher = reader.searcher();
+        assert_eq!(old_searcher.num_docs(), 0);
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 1);
+        assert_eq!(reader.clone().searcher().num_docs(), 1);
+        // searchers of the previous generation keep working.
+        assert_eq!(old_searcher.num_docs(), 0);
+    }
+
+    #[test]
+    fn test_index_reader_pins_segment_files() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let reader = index.reader().unwrap();
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a"));
+        index_writer.commit().unwrap();
+        let directory = index.directory();
+        let segment_files: Vec<_> = index.searchable_segment_metas().unwrap()[0]
+            .list_files()
+            .into_iter()
+            .filter(|path| directory.exists(path))
+            .collect();
+        assert!(!segment_files.is_empty());
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+
+        index_writer.add_document(doc!(text_field=>"b"));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        reader.reload().unwrap();
+        index_writer.garbage_collect_files().unwrap();
+        assert!(segment_files.iter().all(|path| directory.exists(path)));
+        assert_eq!(searcher.num_docs(), 1);
+        assert_eq!(reader.searcher().num_docs(), 2);
+
+        drop(searcher);
+        index_writer.garbage_collect_files().unwrap();
+        assert!(segment_files.iter().all(|path| !directory.exists(path)));
+    }
+
+    #[test]
+    fn test_index_reader_point_in_time() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a"));
+        index_writer.commit().unwrap();
+        let reader = index.reader().unwrap();
+        let keep_alive = Duration::from_secs(3600);
+        {
+            let searcher = reader.open_point_in_time("export", keep_alive).unwrap();
+            assert_eq!(searcher.num_docs(), 1);
+        }
+        assert!(reader.open_point_in_time("export", keep_alive).is_err());
+        assert_eq!(reader.point_in_time_names(), vec!["export".to_string()]);
+
+        index_writer.add_document(doc!(text_field=>"b"));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.garbage_collect_files().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(reader.searcher().num_docs(), 2);
+        assert_eq!(reader.clone().point_in_time("export").unwrap().num_docs(), 1);
+
+        assert!(reader.close_point_in_time("export"));
+        assert!(!reader.close_point_in_time("export"));
+        assert!(reader.point_in_time("export").is_none());
+
+        reader
+            .open_point_in_time("expired", Duration::from_secs(0))
+            .unwrap();
+        assert!(reader.point_in_time("expired").is_none());
+        assert!(reader.point_in_time_names().is_empty());
+    }
+
+    struct FastFieldWarmer {
+        field: Field,
+        num_warmed_segments: AtomicUsize,
+    }
+
+    impl Warmer for FastFieldWarmer {
+        fn warm(&self, _searcher: &Searcher, segment_reader: &SegmentReader) -> Result<()> {
+            segment_reader.fast_field_reader::<u64>(self.field)?;
+            self.num_warmed_segments.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_index_reader_warmer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let warmer = Arc::new(FastFieldWarmer {
+            field: id_field,
+            num_warmed_segments: AtomicUsize::new(0),
+        });
+        let reader = index
+            .reader_builder()
+            .warmer(warmer.clone())
+            .try_into()
+            .unwrap();
+        assert_eq!(warmer.num_warmed_segments.load(Ordering::SeqCst), 0);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>1u64));
+        index_writer.commit().unwrap();
+        reader.reload().unwrap();
+        assert_eq!(warmer.num_warmed_segments.load(Ordering::SeqCst), 1);
+        let searcher = reader.searcher();
+        assert_eq!(
+            searcher.segment_reader(0).loaded_fast_fields(),
+            vec![id_field]
+        );
+    }
+}
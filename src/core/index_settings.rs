@@ -0,0 +1,36 @@
+use store::{Compressor, DocStoreLayout, DEFAULT_BLOCK_SIZE};
+
+/// Settings of an `Index`.
+///
+/// The settings are defined upon the creation of the index,
+/// and are serialized in the `meta.json` file alongside the schema.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexSettings {
+    /// Compressor used for the doc store.
+    #[serde(default)]
+    pub docstore_compression: Compressor,
+    /// Size of the uncompressed blocks of the doc store, in bytes.
+    ///
+    /// Smaller blocks make retrieving a single document faster,
+    /// while larger blocks compress better and make scanning many
+    /// documents faster. Defaults to 16KB.
+    #[serde(default = "default_docstore_blocksize")]
+    pub docstore_blocksize: usize,
+    /// Defines how documents are grouped into the blocks of the doc store.
+    #[serde(default)]
+    pub docstore_layout: DocStoreLayout,
+}
+
+fn default_docstore_blocksize() -> usize {
+    DEFAULT_BLOCK_SIZE
+}
+
+impl Default for IndexSettings {
+    fn default() -> IndexSettings {
+        IndexSettings {
+            docstore_compression: Compressor::default(),
+            docstore_blocksize: default_docstore_blocksize(),
+            docstore_layout: DocStoreLayout::default(),
+        }
+    }
+}
@@ -1,12 +1,14 @@
 use directory::{ReadOnlySource, SourceRead};
-use termdict::{TermDictionary, TermDictionaryImpl};
+use termdict::{TermDictionary, TermDictionaryImpl, TermStreamer, TermStreamerImpl};
 use postings::{BlockSegmentPostings, SegmentPostings};
 use postings::TermInfo;
 use schema::IndexRecordOption;
 use schema::Term;
 use fastfield::DeleteBitSet;
 use compression::CompressedIntStream;
+use error::DataCorruption;
 use postings::FreqReadingOption;
+use std::io;
 
 /// The inverted index reader is in charge of accessing
 /// the inverted index associated to a specific field.
@@ -27,6 +29,7 @@ pub struct InvertedIndexReader {
     positions_source: ReadOnlySource,
     delete_bitset: DeleteBitSet,
     record_option: IndexRecordOption,
+    format_version: u32,
 }
 
 impl InvertedIndexReader {
@@ -36,13 +39,16 @@ impl InvertedIndexReader {
         positions_source: ReadOnlySource,
         delete_bitset: DeleteBitSet,
         record_option: IndexRecordOption,
+        format_version: u32,
     ) -> InvertedIndexReader {
         InvertedIndexReader {
-            termdict: TermDictionaryImpl::from_source(termdict_source),
+            termdict: TermDictionaryImpl::from_source(termdict_source)
+                .expect("Term dictionaries are checked when the segment is opened"),
             postings_source,
             positions_source,
             delete_bitset,
             record_option,
+            format_version,
         }
     }
 
@@ -66,29 +72,42 @@ impl InvertedIndexReader {
     /// # Warning
     ///
     /// This does not reset the positions list.
+    ///
+    /// Returns a `DataCorruption` error if the posting list
+    /// is truncated or malformed.
     pub fn reset_block_postings_from_terminfo(
         &self,
         term_info: &TermInfo,
         block_postings: &mut BlockSegmentPostings,
-    ) {
-        let offset = term_info.postings_offset as usize;
-        let end_source = self.postings_source.len();
-        let postings_slice = self.postings_source.slice(offset, end_source);
+    ) -> io::Result<()> {
+        let postings_slice = self.postings_slice(term_info)?;
         let postings_reader = SourceRead::from(postings_slice);
-        block_postings.reset(term_info.doc_freq as usize, postings_reader);
+        block_postings.reset(term_info.doc_freq as usize, postings_reader)
+    }
+
+    fn postings_slice(&self, term_info: &TermInfo) -> io::Result<ReadOnlySource> {
+        let offset = term_info.postings_offset as usize;
+        if offset > self.postings_source.len() {
+            return Err(DataCorruption::comment_only(
+                "Postings offset is out of the postings file.",
+            ).into());
+        }
+        Ok(self.postings_source.slice_from(offset))
     }
 
     /// Returns a block postings given a `term_info`.
     /// This method is for an advanced usage only.
     ///
     /// Most user should prefer using `read_postings` instead.
+    ///
+    /// Returns a `DataCorruption` error if the posting list
+    /// is truncated or malformed.
     pub fn read_block_postings_from_terminfo(
         &self,
         term_info: &TermInfo,
         requested_option: IndexRecordOption,
-    ) -> BlockSegmentPostings {
-        let offset = term_info.postings_offset as usize;
-        let postings_data = self.postings_source.slice_from(offset);
+    ) -> io::Result<BlockSegmentPostings> {
+        let postings_data = self.postings_slice(term_info)?;
         let freq_reading_option = match (self.record_option, requested_option) {
             (IndexRecordOption::Basic, _) => FreqReadingOption::NoFreq,
             (_, IndexRecordOption::Basic) => FreqReadingOption::SkipFreq,
@@ -98,6 +117,7 @@ impl InvertedIndexReader {
             term_info.doc_freq as usize,
             SourceRead::from(postings_data),
             freq_reading_option,
+            self.format_version,
         )
     }
 
@@ -105,17 +125,25 @@ impl InvertedIndexReader {
     /// This method is for an advanced usage only.
     ///
     /// Most user should prefer using `read_postings` instead.
+    ///
+    /// Returns a `DataCorruption` error if the posting list
+    /// is truncated or malformed.
     pub fn read_postings_from_terminfo(
         &self,
         term_info: &TermInfo,
         option: IndexRecordOption,
-    ) -> SegmentPostings {
-        let block_postings = self.read_block_postings_from_terminfo(term_info, option);
+    ) -> io::Result<SegmentPostings> {
+        let block_postings = self.read_block_postings_from_terminfo(term_info, option)?;
         let delete_bitset = self.delete_bitset.clone();
         let position_stream = {
             if option.has_positions() {
-                let position_offset = term_info.positions_offset;
-                let positions_source = self.positions_source.slice_from(position_offset as usize);
+                let position_offset = term_info.positions_offset as usize;
+                if position_offset > self.positions_source.len() {
+                    return Err(DataCorruption::comment_only(
+                        "Positions offset is out of the positions file.",
+                    ).into());
+                }
+                let positions_source = self.positions_source.slice_from(position_offset);
                 let mut stream = CompressedIntStream::wrap(positions_source);
                 stream.skip(term_info.positions_inner_offset as usize);
                 Some(stream)
@@ -123,7 +151,12 @@ impl InvertedIndexReader {
                 None
             }
         };
-        SegmentPostings::from_block_postings(block_postings, delete_bitset, position_stream)
+        Ok(SegmentPostings::from_block_postings(
+            block_postings,
+            delete_bitset,
+            position_stream,
+            self.record_option.has_offsets(),
+        ))
     }
 
     /// Returns the segment postings associated with the term, and with the given option,
@@ -136,9 +169,18 @@ impl InvertedIndexReader {
     /// For instance, requesting `IndexRecordOption::Freq` for a
     /// `TextIndexingOptions` that does not index position will return a `SegmentPostings`
     /// with `DocId`s and frequencies.
-    pub fn read_postings(&self, term: &Term, option: IndexRecordOption) -> Option<SegmentPostings> {
-        let term_info = get!(self.get_term_info(term));
-        Some(self.read_postings_from_terminfo(&term_info, option))
+    ///
+    /// Returns a `DataCorruption` error if the posting list
+    /// is truncated or malformed.
+    pub fn read_postings(
+        &self,
+        term: &Term,
+        option: IndexRecordOption,
+    ) -> io::Result<Option<SegmentPostings>> {
+        match self.get_term_info(term) {
+            Some(term_info) => self.read_postings_from_terminfo(&term_info, option).map(Some),
+            None => Ok(None),
+        }
     }
 
     /// Returns the number of documents containing the term.
@@ -147,4 +189,154 @@ impl InvertedIndexReader {
             .map(|term_info| term_info.doc_freq)
             .unwrap_or(0u32)
     }
+
+    /// Returns a stream over all of the terms of the field,
+    /// in lexicographical order, together with their postings.
+    ///
+    /// This is the entry point for tools that need to read the
+    /// raw content of an index (export, analysis, transcoding...),
+    /// without going through a `Query`.
+    ///
+    /// The postings are read with the given `option`, with the same
+    /// fallback as `.read_postings(...)`.
+    pub fn postings_stream(&self, option: IndexRecordOption) -> TermPostingsStream {
+        TermPostingsStream {
+            inverted_index: self,
+            term_stream: self.termdict.stream(),
+            option,
+            postings: SegmentPostings::empty(),
+        }
+    }
+}
+
+/// Stream over the terms of a field and their postings.
+///
+/// It is created by calling `InvertedIndexReader::postings_stream(...)`.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate tantivy;
+/// # use tantivy::{DocSet, Index, Postings};
+/// # use tantivy::schema::{IndexRecordOption, SchemaBuilder, TEXT};
+/// # fn main() {
+/// # let mut schema_builder = SchemaBuilder::default();
+/// # let text = schema_builder.add_text_field("text", TEXT);
+/// # let index = Index::create_in_ram(schema_builder.build());
+/// # let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+/// # index_writer.add_document(doc!(text=>"hello happy tax payer"));
+/// # index_writer.commit().unwrap();
+/// # index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+/// for segment_reader in searcher.segment_readers() {
+///     let inverted_index = segment_reader.inverted_index(text);
+///     let mut postings_stream =
+///         inverted_index.postings_stream(IndexRecordOption::WithFreqsAndPositions);
+///     while postings_stream.advance().unwrap() {
+///         let term = String::from_utf8_lossy(postings_stream.term_bytes()).into_owned();
+///         let postings = postings_stream.postings();
+///         while postings.advance() {
+///             println!(
+///                 "{} doc={} tf={} positions={:?}",
+///                 term,
+///                 postings.doc(),
+///                 postings.term_freq(),
+///                 postings.positions()
+///             );
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct TermPostingsStream<'a> {
+    inverted_index: &'a InvertedIndexReader,
+    term_stream: TermStreamerImpl<'a>,
+    option: IndexRecordOption,
+    postings: SegmentPostings,
+}
+
+impl<'a> TermPostingsStream<'a> {
+    /// Advances to the next term.
+    ///
+    /// Returns false iff there are no remaining terms, or a `DataCorruption`
+    /// error if the postings of the next term are truncated or malformed.
+    pub fn advance(&mut self) -> io::Result<bool> {
+        if !self.term_stream.advance() {
+            return Ok(false);
+        }
+        self.postings = self.inverted_index
+            .read_postings_from_terminfo(self.term_stream.value(), self.option)?;
+        Ok(true)
+    }
+
+    /// Returns the bytes of the current term,
+    /// without the field.
+    pub fn term_bytes(&self) -> &[u8] {
+        self.term_stream.key()
+    }
+
+    /// Returns the `TermInfo` of the current term.
+    pub fn term_info(&self) -> &TermInfo {
+        self.term_stream.value()
+    }
+
+    /// Returns the postings of the current term.
+    ///
+    /// They give access to the `DocId`s, term frequencies
+    /// and positions of the term. Deleted documents are skipped.
+    pub fn postings(&mut self) -> &mut SegmentPostings {
+        &mut self.postings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use docset::DocSet;
+    use postings::Postings;
+    use schema::{IndexRecordOption, SchemaBuilder, Term, TEXT};
+
+    #[test]
+    fn test_postings_stream() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"b a b"));
+        index_writer.add_document(doc!(text_field=>"c"));
+        index_writer.add_document(doc!(text_field=>"a c"));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_text(text_field, "c"));
+        index_writer.add_document(doc!(text_field=>"b"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut tuples = Vec::new();
+        for segment_reader in searcher.segment_readers() {
+            let inverted_index = segment_reader.inverted_index(text_field);
+            let mut postings_stream =
+                inverted_index.postings_stream(IndexRecordOption::WithFreqsAndPositions);
+            while postings_stream.advance().unwrap() {
+                let term = String::from_utf8(postings_stream.term_bytes().to_vec()).unwrap();
+                let postings = postings_stream.postings();
+                while postings.advance() {
+                    tuples.push((
+                        term.clone(),
+                        postings.doc(),
+                        postings.term_freq(),
+                        postings.positions().to_vec(),
+                    ));
+                }
+            }
+        }
+        tuples.sort();
+        assert_eq!(
+            tuples,
+            vec![
+                ("a".to_string(), 0, 1, vec![1]),
+                ("b".to_string(), 0, 1, vec![0]),
+                ("b".to_string(), 0, 2, vec![0, 2]),
+            ]
+        );
+    }
 }
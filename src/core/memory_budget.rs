@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Callback removing an entry from the cache holding it.
+///
+/// The callback is given the id of the evicted entry, so that the cache
+/// does not remove an entry that has been replaced in the meantime.
+type EvictCallback = Box<Fn(u64) + Send + Sync>;
+
+struct BudgetEntry {
+    num_bytes: usize,
+    last_access: u64,
+    evict: EvictCallback,
+}
+
+struct InnerMemoryBudget {
+    capacity: usize,
+    num_bytes_used: usize,
+    clock: u64,
+    next_entry_id: u64,
+    entries: HashMap<u64, BudgetEntry>,
+}
+
+impl InnerMemoryBudget {
+    fn touch(&mut self, entry_id: u64) {
+        self.clock += 1;
+        let clock = self.clock;
+        if let Some(entry) = self.entries.get_mut(&entry_id) {
+            entry.last_access = clock;
+        }
+    }
+
+    fn remove(&mut self, entry_id: u64) -> Option<(u64, BudgetEntry)> {
+        let entry = self.entries.remove(&entry_id)?;
+        self.num_bytes_used -= entry.num_bytes;
+        Some((entry_id, entry))
+    }
+
+    fn remove_least_recently_used(&mut self) -> Option<(u64, BudgetEntry)> {
+        let least_recently_used = self.entries
+            .iter()
+            .min_by_key(|&(_, entry)| entry.last_access)
+            .map(|(&entry_id, _)| entry_id)?;
+        self.remove(least_recently_used)
+    }
+}
+
+/// Memory budget shared by the caches of the readers of an `Index`.
+///
+/// The fast field columns loaded by the `SegmentReader`s, the bitsets
+/// of the `FilterCache` and the decompressed blocks of the doc store
+/// register their size against the budget. When an entry does not fit,
+/// the least recently used entries are evicted from their cache,
+/// whichever cache they belong to, until it does. An entry larger than
+/// the whole budget is not cached at all.
+///
+/// Readers already handed out by a cache remain valid after their entry
+/// has been evicted, but their memory is not accounted for anymore.
+/// The footprint of the caches is therefore capped deterministically,
+/// while the footprint of the queries running concurrently is not.
+///
+/// The sizes are those of the data read or decoded by the caches.
+/// Fast fields are accounted for the size of their column, even though
+/// their data is usually mmapped rather than copied in anonymous memory.
+///
+/// See `Index::set_memory_budget`.
+#[derive(Clone)]
+pub struct MemoryBudget {
+    inner: Arc<Mutex<InnerMemoryBudget>>,
+}
+
+impl MemoryBudget {
+    /// Creates a budget of `capacity_in_bytes` bytes.
+    pub fn with_capacity(capacity_in_bytes: usize) -> MemoryBudget {
+        MemoryBudget {
+            inner: Arc::new(Mutex::new(InnerMemoryBudget {
+                capacity: capacity_in_bytes,
+                num_bytes_used: 0,
+                clock: 0u64,
+                next_entry_id: 0u64,
+                entries: HashMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<InnerMemoryBudget> {
+        self.inner.lock().expect("Memory budget lock poisoned")
+    }
+
+    /// Returns the capacity of the budget, in bytes.
+    pub fn capacity(&self) -> usize {
+        self.lock().capacity
+    }
+
+    /// Returns the number of bytes currently registered by the caches.
+    pub fn num_bytes_used(&self) -> usize {
+        self.lock().num_bytes_used
+    }
+
+    /// Returns the number of entries currently registered by the caches.
+    pub fn num_entries(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Evicts all of the entries registered against the budget.
+    pub fn evict_all(&self) {
+        let evicted: Vec<(u64, BudgetEntry)> = {
+            let mut inner = self.lock();
+            inner.num_bytes_used = 0;
+            inner.entries.drain().collect()
+        };
+        Evictions(evicted).run();
+    }
+
+    /// Registers an entry of `num_bytes` bytes, that is removed
+    /// from its cache by calling `evict`.
+    ///
+    /// Returns `None` if the entry is larger than the whole budget,
+    /// in which case it should not be cached.
+    ///
+    /// The entries evicted to make room for the new entry are returned
+    /// rather than evicted right away, as the caller usually holds the
+    /// lock of its cache. The caller must `.run()` them after
+    /// releasing its lock.
+    pub(crate) fn reserve<F>(
+        &self,
+        num_bytes: usize,
+        evict: F,
+    ) -> Option<(MemoryReservation, Evictions)>
+    where
+        F: Fn(u64) + Send + Sync + 'static,
+    {
+        let mut inner = self.lock();
+        if num_bytes > inner.capacity {
+            return None;
+        }
+        let mut evicted = Vec::new();
+        while inner.num_bytes_used + num_bytes > inner.capacity {
+            match inner.remove_least_recently_used() {
+                Some(entry) => evicted.push(entry),
+                None => break,
+            }
+        }
+        inner.clock += 1;
+        let entry_id = inner.next_entry_id;
+        inner.next_entry_id += 1;
+        let last_access = inner.clock;
+        inner.num_bytes_used += num_bytes;
+        inner.entries.insert(
+            entry_id,
+            BudgetEntry {
+                num_bytes,
+                last_access,
+                evict: Box::new(evict),
+            },
+        );
+        let reservation = MemoryReservation {
+            budget: self.clone(),
+            entry_id,
+        };
+        Some((reservation, Evictions(evicted)))
+    }
+}
+
+/// Entry registered against a `MemoryBudget`.
+///
+/// The entry is unregistered when the reservation is dropped,
+/// which happens when the cache entry holding it is removed,
+/// whether it was evicted or not.
+pub(crate) struct MemoryReservation {
+    budget: MemoryBudget,
+    entry_id: u64,
+}
+
+impl MemoryReservation {
+    /// Returns the id of the entry, which identifies it
+    /// in the eviction callback.
+    pub fn entry_id(&self) -> u64 {
+        self.entry_id
+    }
+
+    /// Marks the entry as the most recently used one.
+    pub fn touch(&self) {
+        self.budget.lock().touch(self.entry_id);
+    }
+}
+
+impl Drop for MemoryReservation {
+    fn drop(&mut self) {
+        self.budget.lock().remove(self.entry_id);
+    }
+}
+
+/// Entries removed from a `MemoryBudget`, that remain
+/// to be removed from their cache.
+#[must_use]
+pub(crate) struct Evictions(Vec<(u64, BudgetEntry)>);
+
+impl Evictions {
+    /// Removes the entries from their cache.
+    pub fn run(self) {
+        for (entry_id, entry) in self.0 {
+            (entry.evict)(entry_id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::MemoryBudget;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_memory_budget_lru() {
+        let budget = MemoryBudget::with_capacity(100);
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let reserve = |name: &'static str, num_bytes: usize| {
+            let evicted = Arc::clone(&evicted);
+            let (reservation, evictions) = budget
+                .reserve(num_bytes, move |_| evicted.lock().unwrap().push(name))
+                .unwrap();
+            evictions.run();
+            reservation
+        };
+        let a = reserve("a", 40);
+        let b = reserve("b", 40);
+        assert_eq!(budget.num_bytes_used(), 80);
+        // `a` is now the most recently used.
+        a.touch();
+        let c = reserve("c", 30);
+        assert_eq!(*evicted.lock().unwrap(), vec!["b"]);
+        assert_eq!(budget.num_bytes_used(), 70);
+        // dropping an evicted reservation is a no-op.
+        drop(b);
+        assert_eq!(budget.num_bytes_used(), 70);
+        drop(c);
+        assert_eq!(budget.num_bytes_used(), 40);
+        assert_eq!(budget.num_entries(), 1);
+        // larger than the whole budget.
+        assert!(budget.reserve(101, |_| {}).is_none());
+        budget.evict_all();
+        assert_eq!(*evicted.lock().unwrap(), vec!["b", "a"]);
+        assert_eq!(budget.num_bytes_used(), 0);
+        drop(a);
+        assert_eq!(budget.num_bytes_used(), 0);
+    }
+}
@@ -5,20 +5,53 @@ mod segment_id;
 mod segment_component;
 mod segment;
 mod index_meta;
+mod index_settings;
 mod pool;
 mod segment_meta;
 mod inverted_index_reader;
+mod snapshot;
+mod index_reader;
+mod filter_cache;
+mod memory_budget;
+mod warmer;
+mod index_info;
+mod async_searcher;
+mod fast_field_export;
+mod statistics_provider;
+mod replication;
+mod point_in_time;
+mod space_usage;
+mod crash_consistency;
+mod field_statistics;
+mod search_permissions;
 
-pub use self::inverted_index_reader::InvertedIndexReader;
-pub use self::searcher::Searcher;
+pub use self::inverted_index_reader::{InvertedIndexReader, TermPostingsStream};
+pub use self::searcher::{FieldTermStream, FieldTerms, Searcher, TermSuggestion};
 pub use self::segment_component::SegmentComponent;
 pub use self::segment_id::SegmentId;
+pub(crate) use self::segment_id::SegmentIdSequence;
 pub use self::segment_reader::SegmentReader;
 pub use self::segment::Segment;
 pub use self::segment::SerializableSegment;
-pub use self::index::Index;
+pub use self::index::{Index, ReloadPolicy};
 pub use self::segment_meta::SegmentMeta;
-pub use self::index_meta::IndexMeta;
+pub use self::index_meta::{IndexMeta, INDEX_FORMAT_VERSION, MIN_READABLE_FORMAT_VERSION};
+pub use self::index_settings::IndexSettings;
+pub use self::snapshot::IndexSnapshot;
+pub use self::index_reader::{IndexReader, IndexReaderBuilder};
+pub use self::filter_cache::FilterCache;
+pub use self::memory_budget::MemoryBudget;
+pub(crate) use self::memory_budget::{Evictions, MemoryReservation};
+pub use self::warmer::Warmer;
+pub use self::index_info::{ComponentInfo, IndexInfo, SegmentInfo};
+pub use self::async_searcher::AsyncSearcher;
+pub use self::fast_field_export::{ExportedDoc, FastFieldExport};
+pub use self::statistics_provider::{StatisticsProvider, TermStatistics};
+pub use self::replication::{ReplicatedFile, ReplicationManifest};
+pub use self::space_usage::{ComponentSpaceUsage, FieldSpaceUsage, SegmentSpaceUsage, SpaceUsage};
+pub use self::crash_consistency::check_crash_consistency;
+pub use self::field_statistics::FieldStatistics;
+pub use self::search_permissions::SearchPermissions;
 
 use std::path::PathBuf;
 
@@ -27,6 +60,10 @@ lazy_static! {
     /// of the index.
     pub static ref META_FILEPATH: PathBuf = PathBuf::from("meta.json");
 
+    /// Copy of the last valid meta file, kept as a fallback in case
+    /// `meta.json` is found corrupted when opening the index.
+    pub static ref PREVIOUS_META_FILEPATH: PathBuf = PathBuf::from("meta.prev.json");
+
     /// The managed file contains a list of files that were created by the tantivy
     /// and will therefore be garbage collected when they are deemed useless by tantivy.
     ///
@@ -0,0 +1,82 @@
+use Result;
+use error::ErrorKind;
+use core::Searcher;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+struct PointInTime {
+    searcher: Arc<Searcher>,
+    keep_alive: Duration,
+    expires_at: Instant,
+}
+
+impl PointInTime {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Named searchers pinned by an `IndexReader` until they expire.
+///
+/// Expired searchers are dropped lazily, the next time
+/// the registry is accessed.
+#[derive(Default)]
+pub(crate) struct PointInTimeRegistry {
+    point_in_times: Mutex<HashMap<String, PointInTime>>,
+}
+
+impl PointInTimeRegistry {
+    fn lock(&self) -> MutexGuard<HashMap<String, PointInTime>> {
+        let mut point_in_times = self.point_in_times
+            .lock()
+            .expect("Point-in-time registry lock poisoned");
+        let now = Instant::now();
+        point_in_times.retain(|_, point_in_time| !point_in_time.is_expired(now));
+        point_in_times
+    }
+
+    pub fn register(
+        &self,
+        name: &str,
+        searcher: Searcher,
+        keep_alive: Duration,
+    ) -> Result<Arc<Searcher>> {
+        let mut point_in_times = self.lock();
+        if point_in_times.contains_key(name) {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "A point-in-time reader named {:?} is already open.",
+                name
+            )));
+        }
+        let searcher = Arc::new(searcher);
+        point_in_times.insert(
+            name.to_string(),
+            PointInTime {
+                searcher: Arc::clone(&searcher),
+                keep_alive,
+                expires_at: Instant::now() + keep_alive,
+            },
+        );
+        Ok(searcher)
+    }
+
+    /// Returns the searcher of a point-in-time reader,
+    /// and extends its retention by its keep alive duration.
+    pub fn get(&self, name: &str) -> Option<Arc<Searcher>> {
+        let mut point_in_times = self.lock();
+        let point_in_time = point_in_times.get_mut(name)?;
+        point_in_time.expires_at = Instant::now() + point_in_time.keep_alive;
+        Some(Arc::clone(&point_in_time.searcher))
+    }
+
+    pub fn remove(&self, name: &str) -> bool {
+        self.lock().remove(name).is_some()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.lock().keys().cloned().collect();
+        names.sort();
+        names
+    }
+}
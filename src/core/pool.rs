@@ -12,7 +12,7 @@ pub struct GenerationItem<T> {
 
 pub struct Pool<T> {
     queue: Arc<MsQueue<GenerationItem<T>>>,
-    freshest_generation: AtomicUsize,
+    freshest_generation: Arc<AtomicUsize>,
     next_generation: AtomicUsize,
 }
 
@@ -21,7 +21,7 @@ impl<T> Pool<T> {
         let queue = Arc::new(MsQueue::new());
         Pool {
             queue,
-            freshest_generation: AtomicUsize::default(),
+            freshest_generation: Arc::new(AtomicUsize::default()),
             next_generation: AtomicUsize::default(),
         }
     }
@@ -36,6 +36,22 @@ impl<T> Pool<T> {
             self.queue.push(gen_item);
         }
         self.advertise_generation(next_generation);
+        self.drop_obsolete_items();
+    }
+
+    /// Removes the items of the previous generations
+    /// that are waiting in the queue.
+    ///
+    /// The items that are currently leased are dropped
+    /// when they are released.
+    fn drop_obsolete_items(&self) {
+        let generation = self.generation();
+        while let Some(gen_item) = self.queue.try_pop() {
+            if gen_item.generation >= generation {
+                self.queue.push(gen_item);
+                break;
+            }
+        }
     }
 
     /// At the exit of this method,
@@ -69,6 +85,7 @@ impl<T> Pool<T> {
                 return LeasedItem {
                     gen_item: Some(gen_item),
                     recycle_queue: Arc::clone(&self.queue),
+                    freshest_generation: Arc::clone(&self.freshest_generation),
                 };
             } else {
                 // this searcher is obsolete,
@@ -81,6 +98,7 @@ impl<T> Pool<T> {
 pub struct LeasedItem<T> {
     gen_item: Option<GenerationItem<T>>,
     recycle_queue: Arc<MsQueue<GenerationItem<T>>>,
+    freshest_generation: Arc<AtomicUsize>,
 }
 
 impl<T> Deref for LeasedItem<T> {
@@ -107,7 +125,11 @@ impl<T> Drop for LeasedItem<T> {
     fn drop(&mut self) {
         let gen_item: GenerationItem<T> = mem::replace(&mut self.gen_item, None)
             .expect("Unwrapping a leased item should never fail");
-        self.recycle_queue.push(gen_item);
+        // obsolete items are not recycled, so that the resources
+        // they hold are released as soon as possible.
+        if gen_item.generation >= self.freshest_generation.load(Ordering::Acquire) {
+            self.recycle_queue.push(gen_item);
+        }
     }
 }
 
@@ -115,6 +137,7 @@ impl<T> Drop for LeasedItem<T> {
 mod tests {
 
     use std::iter;
+    use std::sync::Arc;
     use super::Pool;
 
     #[test]
@@ -131,4 +154,17 @@ mod tests {
             assert_eq!(*pool.acquire(), 11);
         }
     }
+
+    #[test]
+    fn test_pool_drops_obsolete_items() {
+        let counter = Arc::new(());
+        let pool = Pool::new();
+        pool.publish_new_generation(vec![Arc::clone(&counter), Arc::clone(&counter)]);
+        let leased = pool.acquire();
+        assert_eq!(Arc::strong_count(&counter), 3);
+        pool.publish_new_generation(vec![Arc::new(())]);
+        assert_eq!(Arc::strong_count(&counter), 2);
+        drop(leased);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
 }
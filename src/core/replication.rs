@@ -0,0 +1,252 @@
+use Result;
+use error::ErrorKind;
+use core::{Index, IndexMeta, SegmentComponent};
+use core::{LOCKFILE_FILEPATH, META_FILEPATH, PREVIOUS_META_FILEPATH};
+use directory::{compute_checksum, split_footer, Directory, DirectoryLock, ReadOnlySource};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+/// A file of a replicated commit. See `ReplicationManifest`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ReplicatedFile {
+    /// Path of the file, relative to the directory of the index.
+    pub path: PathBuf,
+    /// Size of the file, in bytes, checksum footer included.
+    pub num_bytes: usize,
+    /// Checksum stored in the footer of the file.
+    ///
+    /// Files written by a version of tantivy that did not record
+    /// checksums do not have any.
+    pub checksum: Option<u32>,
+}
+
+/// List of the segment files of a commit, used to replicate an index.
+///
+/// The manifest of a commit is obtained on the primary index with
+/// `IndexSnapshot::manifest()`. A replica fetches the files of the manifest
+/// it does not have yet (see `.delta_since(...)`), through
+/// `IndexSnapshot::read_file(...)`, and installs them along with the
+/// meta file of the commit with `Index::install_replica(...)`.
+///
+/// Segment files are never modified once written, so that two generations
+/// of an index typically share most of their files.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicationManifest {
+    /// Opstamp of the commit.
+    pub opstamp: u64,
+    /// Segment files of the commit, sorted by path.
+    pub files: Vec<ReplicatedFile>,
+}
+
+impl ReplicationManifest {
+    pub(crate) fn create(metas: &IndexMeta, files: Vec<ReplicatedFile>) -> ReplicationManifest {
+        ReplicationManifest {
+            opstamp: metas.opstamp,
+            files,
+        }
+    }
+
+    /// Returns the files of the manifest that are not part of the `previous`
+    /// manifest, or whose checksum differs.
+    ///
+    /// These are the files a replica holding the `previous` generation
+    /// needs to fetch to install this one.
+    pub fn delta_since(&self, previous: &ReplicationManifest) -> Vec<ReplicatedFile> {
+        let previous_files: HashSet<&ReplicatedFile> = previous.files.iter().collect();
+        self.files
+            .iter()
+            .filter(|file| !previous_files.contains(file))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the overall size of the files of the manifest.
+    pub fn num_bytes(&self) -> usize {
+        self.files.iter().map(|file| file.num_bytes).sum()
+    }
+}
+
+impl ReplicatedFile {
+    pub(crate) fn create(path: PathBuf, source: ReadOnlySource) -> ReplicatedFile {
+        let num_bytes = source.len();
+        let (_, checksum) = split_footer(source);
+        ReplicatedFile {
+            path,
+            num_bytes,
+            checksum,
+        }
+    }
+}
+
+fn validate_file(path: &PathBuf, data: &[u8]) -> Result<()> {
+    let (content, checksum_opt) = split_footer(ReadOnlySource::from(data.to_vec()));
+    if let Some(checksum) = checksum_opt {
+        if compute_checksum(content.as_slice()) != checksum {
+            bail!(ErrorKind::CorruptedFile(path.clone()));
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn install_replica(
+    index: &Index,
+    meta_data: &[u8],
+    files: &[(PathBuf, Vec<u8>)],
+) -> Result<()> {
+    let metas = IndexMeta::deserialize(meta_data, &META_FILEPATH)?;
+    if metas.schema != index.schema() {
+        bail!(ErrorKind::InvalidArgument(
+            "The schema of the replicated commit differs from the schema of the index."
+                .to_string()
+        ));
+    }
+    for &(ref path, ref data) in files {
+        validate_file(path, data)?;
+    }
+    // prevents an `IndexWriter` from being opened while the commit is installed.
+    let _directory_lock = DirectoryLock::lock(index.directory().box_clone())?;
+    let mut directory = index.directory().clone();
+
+    let received_files: HashMap<&PathBuf, &[u8]> = files
+        .iter()
+        .map(|&(ref path, ref data)| (path, &data[..]))
+        .collect();
+    let mut living_files: HashSet<PathBuf> = HashSet::new();
+    for segment_meta in &metas.segments {
+        for &component in SegmentComponent::iterator() {
+            let path = segment_meta.relative_path(component);
+            if directory.exists(&path) {
+                living_files.insert(path);
+                continue;
+            }
+            match received_files.get(&path) {
+                Some(data) => {
+                    directory.write_with_footer(&path, data)?;
+                    living_files.insert(path);
+                }
+                None => {
+                    let is_optional = match component {
+                        SegmentComponent::DELETE => !segment_meta.has_deletes(),
+                        SegmentComponent::FASTFIELDUPDATES => {
+                            !segment_meta.has_fast_field_updates()
+                        }
+                        // segments written before the introduction of
+                        // completion fields do not have this component.
+                        SegmentComponent::COMPLETIONS => true,
+                        // nor do the segments written before term vectors.
+                        SegmentComponent::TERMVECTORS => true,
+                        _ => false,
+                    };
+                    if !is_optional {
+                        bail!(ErrorKind::InvalidArgument(format!(
+                            "The file {:?} of the replicated commit was not received.",
+                            path
+                        )));
+                    }
+                }
+            }
+        }
+    }
+
+    // installing the meta file publishes the commit.
+    directory.atomic_write(&META_FILEPATH, meta_data)?;
+
+    living_files.insert(META_FILEPATH.clone());
+    living_files.insert(PREVIOUS_META_FILEPATH.clone());
+    living_files.insert(LOCKFILE_FILEPATH.clone());
+    directory.garbage_collect(|| living_files);
+    index.load_searchers()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use core::IndexSnapshot;
+    use directory::Directory;
+    use error::{Error, ErrorKind};
+    use indexer::merge_policy::tests::MergeWheneverPossible;
+    use schema::{SchemaBuilder, TEXT};
+    use std::path::PathBuf;
+    use super::ReplicatedFile;
+
+    fn fetch(snapshot: &IndexSnapshot, files: &[ReplicatedFile]) -> Vec<(PathBuf, Vec<u8>)> {
+        files
+            .iter()
+            .map(|file| {
+                let data = snapshot.read_file(&file.path).unwrap();
+                (file.path.clone(), data.as_slice().to_vec())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replication() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let primary = Index::create_in_ram(schema.clone());
+        let replica = Index::create_in_ram(schema.clone());
+        let mut index_writer = primary.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+
+        let first_snapshot = primary.snapshot().unwrap();
+        let first_manifest = first_snapshot.manifest().unwrap();
+        assert!(first_manifest.files.iter().all(|file| file.checksum.is_some()));
+        assert_eq!(first_manifest.delta_since(&first_manifest), vec![]);
+        {
+            let meta_data = first_snapshot.meta_data();
+            // the segment files are required.
+            match replica.install_replica(meta_data, &[]) {
+                Err(Error(ErrorKind::InvalidArgument(_), _)) => {}
+                _ => panic!("Expected an InvalidArgument error."),
+            }
+            let mut files = fetch(&first_snapshot, &first_manifest.files);
+            for &mut (_, ref mut data) in &mut files {
+                data[0] ^= 1u8;
+            }
+            match replica.install_replica(meta_data, &files) {
+                Err(Error(ErrorKind::CorruptedFile(_), _)) => {}
+                _ => panic!("Expected a CorruptedFile error."),
+            }
+        }
+        let files = fetch(&first_snapshot, &first_manifest.files);
+        replica
+            .install_replica(first_snapshot.meta_data(), &files)
+            .unwrap();
+        assert_eq!(replica.searcher().num_docs(), 2);
+        assert!(replica.validate_checksums().unwrap().is_empty());
+
+        index_writer.set_merge_policy(box MergeWheneverPossible);
+        index_writer.add_document(doc!(text_field => "c"));
+        index_writer.commit().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        let second_snapshot = primary.snapshot().unwrap();
+        let second_manifest = second_snapshot.manifest().unwrap();
+        assert_eq!(second_snapshot.metas().segments.len(), 1);
+        let delta = second_manifest.delta_since(&first_manifest);
+        assert!(!delta.is_empty());
+        assert!(delta.iter().all(|file| !first_manifest.files.contains(file)));
+        replica
+            .install_replica(second_snapshot.meta_data(), &fetch(&second_snapshot, &delta))
+            .unwrap();
+        let searcher = replica.searcher();
+        assert_eq!(searcher.num_docs(), 3);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "c")), 1);
+        // the files of the merged segments are garbage collected.
+        for file in &first_manifest.files {
+            assert!(!replica.directory().exists(&file.path));
+        }
+
+        // a replica cannot be written to while it is being replicated.
+        let _replica_writer = replica.writer_with_num_threads(1, 40_000_000).unwrap();
+        match replica.install_replica(second_snapshot.meta_data(), &[]) {
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected an IndexLocked error."),
+        }
+    }
+}
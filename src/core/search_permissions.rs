@@ -0,0 +1,134 @@
+use Result;
+use common::BitSet;
+use core::SegmentReader;
+use query::{BitSetDocSet, ConstScorer, Intersection, Scorer, Weight};
+use std::sync::Arc;
+
+/// Restricts the documents that searches may return.
+///
+/// Multi-tenant applications typically need every search to be limited to
+/// the documents the current user is allowed to see. Rather than adding a
+/// filter clause to each of the queries, the permissions are attached once
+/// to a `Searcher`, with `Searcher::with_permissions(...)`, and are
+/// intersected with every query it runs: `Searcher::search`,
+/// `search_in_parallel`, `search_many`, `search_with_profile`,
+/// `export_fast_fields`, as well as `Query::search` and `Query::count`.
+///
+/// The permissions do not change the score of the documents.
+/// Fetching a document by its `DocAddress` is not restricted.
+pub trait SearchPermissions: Send + Sync {
+    /// Returns the documents of the segment that searches may return,
+    /// or `None` if all of them are allowed.
+    ///
+    /// It is called once per segment, for every query.
+    /// Implementations are expected to cache the bitsets of
+    /// the segments if they are expensive to compute.
+    fn allowed_docs(&self, segment_reader: &SegmentReader) -> Result<Option<BitSet>>;
+}
+
+/// Weight intersecting the documents of a weight with
+/// the documents allowed by the `SearchPermissions`.
+pub(crate) struct PermissionsWeight {
+    weight: Box<Weight>,
+    permissions: Arc<SearchPermissions>,
+}
+
+impl PermissionsWeight {
+    pub fn new(weight: Box<Weight>, permissions: Arc<SearchPermissions>) -> PermissionsWeight {
+        PermissionsWeight {
+            weight,
+            permissions,
+        }
+    }
+}
+
+impl Weight for PermissionsWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let scorer = self.weight.scorer(reader)?;
+        match self.permissions.allowed_docs(reader)? {
+            Some(allowed_docs) => {
+                // scores of the intersection are summed: the
+                // allowed documents must not add anything.
+                let mut allowed_scorer = ConstScorer::new(BitSetDocSet::from(allowed_docs));
+                allowed_scorer.set_score(0f32);
+                let allowed_scorer: Box<Scorer> = box allowed_scorer;
+                Ok(box Intersection::from(vec![scorer, allowed_scorer]))
+            }
+            None => Ok(scorer),
+        }
+    }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        self.weight.cost(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Result;
+    use Term;
+    use collector::{CountCollector, TopCollector};
+    use common::BitSet;
+    use core::SegmentReader;
+    use query::{Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, TEXT};
+    use std::sync::Arc;
+    use super::SearchPermissions;
+
+    // only documents with an even doc id are allowed.
+    struct EvenDocs;
+
+    impl SearchPermissions for EvenDocs {
+        fn allowed_docs(&self, segment_reader: &SegmentReader) -> Result<Option<BitSet>> {
+            let max_doc = segment_reader.max_doc();
+            let mut allowed_docs = BitSet::with_max_value(max_doc);
+            for doc in (0..max_doc).filter(|doc| doc % 2 == 0) {
+                allowed_docs.insert(doc);
+            }
+            Ok(Some(allowed_docs))
+        }
+    }
+
+    #[test]
+    fn test_search_permissions() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for text in &["a", "a b", "b", "a", "a a c"] {
+                index_writer.add_document(doc!(text_field => *text));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher.search(&query, &mut top_collector).unwrap();
+        let all_score_docs = top_collector.score_docs();
+        assert_eq!(all_score_docs.len(), 4);
+
+        let restricted_searcher = searcher.with_permissions(Arc::new(EvenDocs));
+        assert_eq!(query.count(&restricted_searcher).unwrap(), 2);
+        let mut count_collector = CountCollector::default();
+        restricted_searcher
+            .search(&query, &mut count_collector)
+            .unwrap();
+        assert_eq!(count_collector.count(), 2);
+        let mut top_collector = TopCollector::with_limit(10);
+        restricted_searcher
+            .search(&query, &mut top_collector)
+            .unwrap();
+        let expected_score_docs: Vec<_> = all_score_docs
+            .into_iter()
+            .filter(|&(_, doc_address)| doc_address.doc() % 2 == 0)
+            .collect();
+        assert_eq!(top_collector.score_docs(), expected_score_docs);
+    }
+}
@@ -1,16 +1,33 @@
 use Result;
+use core::Index;
 use core::SegmentReader;
+use error::ErrorKind;
 use schema::Document;
-use collector::Collector;
+use store::LazyDocument;
+use collector::{Collector, MergeableCollector};
+use error::Error;
+use futures::Future;
+use futures_cpupool::{CpuFuture, CpuPool};
+use SegmentLocalId;
 use common::TimerTree;
-use query::Query;
+use query::{Query, QueryProfile, QueryProfiler, Weight};
 use DocId;
 use DocAddress;
 use schema::{Field, Term};
-use termdict::{TermDictionary, TermMerger};
+use termdict::{next_prefix, search_levenshtein, LevenshteinAutomaton, TermDictionary, TermMerger,
+               TermStreamer, TermStreamerBuilder};
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::fmt;
 use core::InvertedIndexReader;
+use core::FilterCache;
+use core::FastFieldExport;
+use core::{StatisticsProvider, TermStatistics};
+use core::search_permissions::{PermissionsWeight, SearchPermissions};
+use std::collections::BTreeSet;
+use completion::{self, Completion, CompletionIndex};
+use termvector::TermVector;
 
 /// Holds a list of `SegmentReader`s ready for search.
 ///
@@ -19,9 +36,56 @@ use core::InvertedIndexReader;
 ///
 pub struct Searcher {
     segment_readers: Vec<SegmentReader>,
+    filter_cache: Option<Arc<FilterCache>>,
+    statistics_provider: Option<Arc<StatisticsProvider>>,
+    profiler: Option<Arc<QueryProfiler>>,
+    permissions: Option<Arc<SearchPermissions>>,
 }
 
 impl Searcher {
+    pub(crate) fn with_filter_cache(
+        segment_readers: Vec<SegmentReader>,
+        filter_cache: Arc<FilterCache>,
+    ) -> Searcher {
+        Searcher {
+            segment_readers,
+            filter_cache: Some(filter_cache),
+            statistics_provider: None,
+            profiler: None,
+            permissions: None,
+        }
+    }
+
+    /// Creates a searcher over several indexes sharing the same schema.
+    ///
+    /// The segments of all of the indexes are searched as if they belonged
+    /// to a single index: scoring statistics are computed over all of them,
+    /// and segment ordinals in `DocAddress`es are attributed following
+    /// the order of `indexes`.
+    ///
+    /// Each index contributes the segments of its current searcher, so
+    /// `.load_searchers()` should be called on the indexes beforehand
+    /// to take in account their latest commit.
+    ///
+    /// # Errors
+    /// If the schemas of the indexes differ, returns `ErrorKind::InvalidArgument`.
+    pub fn for_indexes(indexes: &[&Index]) -> Result<Searcher> {
+        let mut segment_readers = vec![];
+        if let Some(first_index) = indexes.first() {
+            let schema = first_index.schema();
+            for index in indexes {
+                if index.schema() != schema {
+                    bail!(ErrorKind::InvalidArgument(format!(
+                        "Cannot search {:?} along with {:?}: their schemas differ.",
+                        index, first_index
+                    )));
+                }
+                segment_readers.extend_from_slice(index.searcher().segment_readers());
+            }
+        }
+        Ok(Searcher::from(segment_readers))
+    }
+
     /// Fetches a document from tantivy's store given a `DocAddress`.
     ///
     /// The searcher uses the segment ordinal to route the
@@ -32,6 +96,32 @@ impl Searcher {
         segment_reader.doc(doc_id)
     }
 
+    /// Fetches a document from tantivy's store given a `DocAddress`,
+    /// without deserializing its fields.
+    ///
+    /// The values of the returned `LazyDocument` are deserialized when they
+    /// are requested, which is faster than `.doc(...)` when
+    /// only a few fields of large documents are needed.
+    pub fn doc_lazy(&self, doc_address: &DocAddress) -> Result<LazyDocument> {
+        let DocAddress(segment_local_id, doc_id) = *doc_address;
+        let segment_reader = &self.segment_readers[segment_local_id as usize];
+        segment_reader.doc_lazy(doc_id)
+    }
+
+    /// Returns the term vector of a field of a document, given its `DocAddress`.
+    ///
+    /// Returns `None` if the term vectors of the field are not stored.
+    /// (See `TextFieldIndexing::set_term_vectors`.)
+    pub fn term_vector(
+        &self,
+        doc_address: &DocAddress,
+        field: Field,
+    ) -> Result<Option<TermVector>> {
+        let DocAddress(segment_local_id, doc_id) = *doc_address;
+        let segment_reader = &self.segment_readers[segment_local_id as usize];
+        segment_reader.term_vector(doc_id, field)
+    }
+
     /// Returns the overall number of documents in the index.
     pub fn num_docs(&self) -> DocId {
         self.segment_readers
@@ -59,11 +149,292 @@ impl Searcher {
         &self.segment_readers[segment_ord as usize]
     }
 
+    /// Returns a searcher over the same segments, scoring documents
+    /// with the statistics of `statistics_provider` instead of the
+    /// statistics of its own segments.
+    ///
+    /// The returned searcher shares the `FilterCache` of this searcher, if any.
+    pub fn with_statistics_provider(
+        &self,
+        statistics_provider: Arc<StatisticsProvider>,
+    ) -> Searcher {
+        Searcher {
+            segment_readers: self.segment_readers.clone(),
+            filter_cache: self.filter_cache.clone(),
+            statistics_provider: Some(statistics_provider),
+            profiler: self.profiler.clone(),
+            permissions: self.permissions.clone(),
+        }
+    }
+
+    /// Returns a searcher over the same segments, restricting
+    /// all of its searches to the documents allowed by `permissions`.
+    ///
+    /// The returned searcher shares the `FilterCache` and the
+    /// statistics provider of this searcher, if any.
+    /// See `SearchPermissions`.
+    pub fn with_permissions(&self, permissions: Arc<SearchPermissions>) -> Searcher {
+        Searcher {
+            segment_readers: self.segment_readers.clone(),
+            filter_cache: self.filter_cache.clone(),
+            statistics_provider: self.statistics_provider.clone(),
+            profiler: self.profiler.clone(),
+            permissions: Some(permissions),
+        }
+    }
+
+    /// Returns the statistics used to score documents.
+    ///
+    /// Unless the searcher was created with `.with_statistics_provider(...)`,
+    /// these are the statistics of the segments of the searcher.
+    pub fn statistics_provider(&self) -> &StatisticsProvider {
+        match self.statistics_provider {
+            Some(ref statistics_provider) => &**statistics_provider,
+            None => self,
+        }
+    }
+
+    /// Returns the statistics of `terms` in the segments of the searcher,
+    /// typically the terms of a query given by `Query::extract_terms()`.
+    ///
+    /// See `TermStatistics`.
+    pub fn term_statistics(&self, terms: &BTreeSet<Term>) -> TermStatistics {
+        TermStatistics::compute(self, terms)
+    }
+
+    /// Returns the `FilterCache` of the searcher, if any.
+    ///
+    /// Only the searchers of an `IndexReader` configured with
+    /// `IndexReaderBuilder::filter_cache_capacity` have a filter cache.
+    pub fn filter_cache(&self) -> Option<Arc<FilterCache>> {
+        self.filter_cache.clone()
+    }
+
     /// Runs a query on the segment readers wrapped by the searcher
     pub fn search<C: Collector>(&self, query: &Query, collector: &mut C) -> Result<TimerTree> {
         query.search(self, collector)
     }
 
+    /// Runs a query like `.search(...)`, and returns the time spent
+    /// in each node of the query.
+    ///
+    /// The weight and the scorers of each node are wrapped to time
+    /// the creation of the scorers and the calls to `advance`, `skip_next`
+    /// and `score`. This comes with an overhead, and disables some
+    /// of the optimizations of the boolean queries: timings are meant
+    /// to compare the nodes of a query, rather than to measure its
+    /// actual execution time.
+    ///
+    /// Composite queries only report their sub-queries if they create
+    /// their weights with `Searcher::create_weight(...)`.
+    pub fn search_with_profile<C: Collector>(
+        &self,
+        query: &Query,
+        collector: &mut C,
+    ) -> Result<QueryProfile> {
+        let profiler = Arc::new(QueryProfiler::default());
+        let searcher = Searcher {
+            segment_readers: self.segment_readers.clone(),
+            filter_cache: self.filter_cache.clone(),
+            statistics_provider: self.statistics_provider.clone(),
+            profiler: Some(Arc::clone(&profiler)),
+            permissions: self.permissions.clone(),
+        };
+        {
+            let weight = searcher.create_weight(query, collector.requires_scoring())?;
+            let weight = searcher.restrict_weight(weight);
+            for (segment_ord, segment_reader) in self.segment_readers.iter().enumerate() {
+                collector.set_segment(segment_ord as SegmentLocalId, segment_reader)?;
+                let mut scorer = weight.scorer(segment_reader)?;
+                scorer.collect(collector);
+            }
+        }
+        Ok(profiler
+            .profile()
+            .expect("The weight of the query was created by the profiler"))
+    }
+
+    /// Creates the `Weight` of a query.
+    ///
+    /// This is equivalent to `query.weight(searcher, scoring_enabled)`, except
+    /// that the query is timed when profiling. Composite queries should create
+    /// the weights of their sub-queries with this method.
+    pub fn create_weight(&self, query: &Query, scoring_enabled: bool) -> Result<Box<Weight>> {
+        match self.profiler {
+            Some(ref profiler) => profiler.weight(self, query, scoring_enabled),
+            None => query.weight(self, scoring_enabled),
+        }
+    }
+
+    /// Restricts the weight of a query run by the searcher
+    /// to the documents allowed by its permissions, if any.
+    pub(crate) fn restrict_weight(&self, weight: Box<Weight>) -> Box<Weight> {
+        match self.permissions {
+            Some(ref permissions) => box PermissionsWeight::new(weight, Arc::clone(permissions)),
+            None => weight,
+        }
+    }
+
+    /// Runs several queries on the same set of segments,
+    /// in a single pass over the segments.
+    ///
+    /// The documents matching `queries[i]` are pushed to `collectors[i]`.
+    /// As all of the queries are run by the same searcher, their results
+    /// are consistent with each other.
+    ///
+    /// # Errors
+    /// If the number of queries and collectors differ,
+    /// returns `ErrorKind::InvalidArgument`.
+    pub fn search_many(&self, queries: &[&Query], collectors: &mut [&mut Collector]) -> Result<()> {
+        if queries.len() != collectors.len() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "search_many got {} queries, but {} collectors.",
+                queries.len(),
+                collectors.len()
+            )));
+        }
+        let weights = queries
+            .iter()
+            .zip(collectors.iter())
+            .map(|(query, collector)| {
+                let weight = query.weight(self, collector.requires_scoring())?;
+                Ok(self.restrict_weight(weight))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (segment_ord, segment_reader) in self.segment_readers.iter().enumerate() {
+            for (weight, collector) in weights.iter().zip(collectors.iter_mut()) {
+                collector.set_segment(segment_ord as SegmentLocalId, segment_reader)?;
+                let mut scorer = weight.scorer(segment_reader)?;
+                scorer.collect(&mut **collector);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an iterator over the fast field values of `fields`
+    /// for each of the documents matching `query`.
+    ///
+    /// Values are read from the fast fields only, without decompressing
+    /// the stored documents, which makes it suited to export large result sets,
+    /// for instance to feed a training pipeline.
+    /// The values of str fast fields and facets are returned as
+    /// `Value::Str` and `Value::Facet`.
+    ///
+    /// # Errors
+    /// If one of `fields` is not a fast field, returns
+    /// `ErrorKind::FastFieldError`.
+    pub fn export_fast_fields(&self, query: &Query, fields: &[Field]) -> Result<FastFieldExport> {
+        FastFieldExport::new(self, query, fields)
+    }
+
+    /// Runs a query, searching the segments in parallel on the given thread pool.
+    ///
+    /// Each segment is collected by its own collector, obtained from
+    /// `collector.for_segment()`. Once all of the segments have been searched,
+    /// their collectors are merged into `collector`, in the order of the segments.
+    pub fn search_in_parallel<C: MergeableCollector>(
+        &self,
+        query: &Query,
+        collector: &mut C,
+        pool: &CpuPool,
+    ) -> Result<()> {
+        let weight = query.weight(self, collector.requires_scoring())?;
+        let weight = Arc::new(self.restrict_weight(weight));
+        let segment_futures: Vec<CpuFuture<C, Error>> = self.segment_readers
+            .iter()
+            .enumerate()
+            .map(|(segment_ord, segment_reader)| {
+                let weight = Arc::clone(&weight);
+                let segment_reader = segment_reader.clone();
+                let mut segment_collector = collector.for_segment();
+                pool.spawn_fn(move || -> Result<C> {
+                    segment_collector.set_segment(segment_ord as SegmentLocalId, &segment_reader)?;
+                    let mut scorer = weight.scorer(&segment_reader)?;
+                    scorer.collect(&mut segment_collector);
+                    Ok(segment_collector)
+                })
+            })
+            .collect();
+        for segment_future in segment_futures {
+            collector.merge(segment_future.wait()?);
+        }
+        Ok(())
+    }
+
+    /// Returns the terms of `field` within a Levenshtein distance of
+    /// `max_distance` of `text`, to propose corrections of a possibly
+    /// misspelled query term.
+    ///
+    /// `text` is compared to the indexed terms as is: it should
+    /// be normalized like the terms of the field, for instance lowercased.
+    ///
+    /// Suggestions are sorted by increasing distance, then by decreasing
+    /// document frequency. If `text` itself is a term of the field,
+    /// it is suggested with a distance of 0.
+    pub fn suggest_term(&self, field: Field, text: &str, max_distance: u8) -> Vec<TermSuggestion> {
+        let automaton = LevenshteinAutomaton::new(text, max_distance);
+        let mut suggestions: HashMap<Vec<u8>, TermSuggestion> = HashMap::new();
+        for segment_reader in &self.segment_readers {
+            let inverted_index = segment_reader.inverted_index(field);
+            search_levenshtein(
+                inverted_index.terms(),
+                &automaton,
+                |term_bytes, distance, term_info| {
+                    suggestions
+                        .entry(term_bytes.to_vec())
+                        .or_insert_with(|| TermSuggestion {
+                            text: String::from_utf8_lossy(term_bytes).into_owned(),
+                            distance,
+                            doc_freq: 0u32,
+                        })
+                        .doc_freq += term_info.doc_freq;
+                },
+            );
+        }
+        let mut suggestions: Vec<TermSuggestion> = suggestions
+            .into_iter()
+            .map(|(_, suggestion)| suggestion)
+            .collect();
+        suggestions.sort_by(|left, right| {
+            (left.distance, Reverse(left.doc_freq), &left.text).cmp(&(
+                right.distance,
+                Reverse(right.doc_freq),
+                &right.text,
+            ))
+        });
+        suggestions
+    }
+
+    /// Returns the `k` values of the completion field `field` starting
+    /// with `prefix` that are contained in the most documents.
+    ///
+    /// The values of completion fields are not tokenized: `prefix` is
+    /// matched as is against the beginning of the raw values.
+    /// The weights of the values are summed over all of the segments.
+    ///
+    /// Completions are sorted by decreasing weight, then by text.
+    /// If `field` is not a completion field, no completion is returned.
+    /// (See `TextOptions::set_completion`.)
+    pub fn complete(&self, field: Field, prefix: &str, k: usize) -> Vec<Completion> {
+        let completion_indexes: Vec<CompletionIndex> = self.segment_readers
+            .iter()
+            .filter_map(|segment_reader| segment_reader.completion_index(field))
+            .collect();
+        completion::complete(&completion_indexes, prefix, k)
+    }
+
+    /// Returns the terms of `field` over all of the segments, for instance
+    /// to export the vocabulary of the field.
+    ///
+    /// See `FieldTerms::stream()`.
+    pub fn field_terms(&self, field: Field) -> FieldTerms {
+        let inv_index_readers = self.segment_readers
+            .iter()
+            .map(|segment_reader| segment_reader.inverted_index(field))
+            .collect::<Vec<_>>();
+        FieldTerms { inv_index_readers }
+    }
+
     /// Return the field searcher associated to a `Field`.
     pub fn field(&self, field: Field) -> FieldSearcher {
         let inv_index_readers = self.segment_readers
@@ -74,6 +445,18 @@ impl Searcher {
     }
 }
 
+/// A correction of a query term, returned by `Searcher::suggest_term(...)`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermSuggestion {
+    /// Text of the suggested term.
+    pub text: String,
+    /// Levenshtein distance between the suggested term and the query term.
+    pub distance: u8,
+    /// Number of documents containing the suggested term,
+    /// deleted documents included.
+    pub doc_freq: u32,
+}
+
 pub struct FieldSearcher {
     inv_index_readers: Vec<Arc<InvertedIndexReader>>,
 }
@@ -94,9 +477,113 @@ impl FieldSearcher {
     }
 }
 
+/// Term dictionaries of a field in all of the segments
+/// of a `Searcher`, returned by `Searcher::field_terms(...)`.
+pub struct FieldTerms {
+    inv_index_readers: Vec<Arc<InvertedIndexReader>>,
+}
+
+impl FieldTerms {
+    /// Returns a stream over the sorted unique terms of the field,
+    /// with their document frequency summed over the segments.
+    pub fn stream(&self) -> FieldTermStream {
+        let term_streamers: Vec<_> = self.inv_index_readers
+            .iter()
+            .map(|inverted_index| inverted_index.terms().stream())
+            .collect();
+        FieldTermStream::new(TermMerger::new(term_streamers))
+    }
+
+    /// Returns a stream over the unique terms of the field in
+    /// decreasing order, for instance to get the last terms
+    /// of a field.
+    pub fn backward_stream(&self) -> FieldTermStream {
+        let term_streamers: Vec<_> = self.inv_index_readers
+            .iter()
+            .map(|inverted_index| inverted_index.terms().range().backward().into_stream())
+            .collect();
+        FieldTermStream::new(TermMerger::new(term_streamers))
+    }
+
+    /// Returns a stream over the sorted unique terms of the field
+    /// starting with `prefix`, for instance to propose the terms
+    /// matching the beginning of a user input.
+    pub fn prefix_stream(&self, prefix: &[u8]) -> FieldTermStream {
+        let upper_bound = next_prefix(prefix);
+        let term_streamers: Vec<_> = self.inv_index_readers
+            .iter()
+            .map(|inverted_index| {
+                let stream_builder = inverted_index.terms().range().ge(prefix);
+                let stream_builder = match upper_bound {
+                    Some(ref upper_bound) => stream_builder.lt(upper_bound),
+                    None => stream_builder,
+                };
+                stream_builder.into_stream()
+            })
+            .collect();
+        FieldTermStream::new(TermMerger::new(term_streamers))
+    }
+}
+
+/// Stream over the terms of a field, deduplicated across segments.
+///
+/// See `FieldTerms`.
+pub struct FieldTermStream<'a> {
+    term_merger: TermMerger<'a>,
+    doc_freq: u32,
+}
+
+impl<'a> FieldTermStream<'a> {
+    fn new(term_merger: TermMerger<'a>) -> FieldTermStream<'a> {
+        FieldTermStream {
+            term_merger,
+            doc_freq: 0u32,
+        }
+    }
+
+    /// Advances the stream to the next term.
+    /// Returns false if there is no more term.
+    pub fn advance(&mut self) -> bool {
+        if !self.term_merger.advance() {
+            return false;
+        }
+        self.doc_freq = self.term_merger
+            .current_kvs()
+            .iter()
+            .map(|heap_item| heap_item.streamer.value().doc_freq)
+            .sum();
+        true
+    }
+
+    /// Returns the bytes of the current term, without its field.
+    ///
+    /// This method may be called
+    /// iff advance() has been called before
+    /// and "true" was returned.
+    pub fn key(&self) -> &[u8] {
+        self.term_merger.key()
+    }
+
+    /// Returns the number of documents containing the current term
+    /// in all of the segments, deleted documents included.
+    ///
+    /// This method may be called
+    /// iff advance() has been called before
+    /// and "true" was returned.
+    pub fn doc_freq(&self) -> u32 {
+        self.doc_freq
+    }
+}
+
 impl From<Vec<SegmentReader>> for Searcher {
     fn from(segment_readers: Vec<SegmentReader>) -> Searcher {
-        Searcher { segment_readers }
+        Searcher {
+            segment_readers,
+            filter_cache: None,
+            statistics_provider: None,
+            profiler: None,
+            permissions: None,
+        }
     }
 }
 
@@ -109,3 +596,260 @@ impl fmt::Debug for Searcher {
         write!(f, "Searcher({:?})", segment_ids)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::{FieldTermStream, Searcher};
+    use DocAddress;
+    use Index;
+    use Term;
+    use collector::{CountCollector, TopCollector};
+    use futures_cpupool::CpuPool;
+    use indexer::NoMergePolicy;
+    use query::TermQuery;
+    use schema::{IndexRecordOption, SchemaBuilder, TextOptions, STORED, TEXT};
+    use futures::Future;
+
+    #[test]
+    fn test_searcher_for_indexes() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let schema = schema_builder.build();
+        let index_2017 = Index::create_in_ram(schema.clone());
+        let index_2018 = Index::create_in_ram(schema);
+        for &(index, texts) in &[(&index_2017, &["a", "a b"][..]), (&index_2018, &["b"][..])] {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for text in texts {
+                index_writer.add_document(doc!(text_field=>*text));
+            }
+            index_writer.commit().unwrap();
+            index.load_searchers().unwrap();
+        }
+        let searcher = Searcher::for_indexes(&[&index_2017, &index_2018]).unwrap();
+        assert_eq!(searcher.num_docs(), 3);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "a")), 2);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "b")), 2);
+        let doc = searcher.doc(&DocAddress(1, 0)).unwrap();
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "b");
+
+        let other_index = Index::create_in_ram(SchemaBuilder::default().build());
+        assert!(Searcher::for_indexes(&[&index_2017, &other_index]).is_err());
+    }
+
+    #[test]
+    fn test_search_in_parallel() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        for i in 0..20 {
+            let text = if i % 2 == 0 { "a" } else { "a a b" };
+            index_writer.add_document(doc!(text_field=>text));
+            if i % 5 == 4 {
+                index_writer.commit().unwrap();
+            }
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 4);
+        let query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let pool = CpuPool::new(3);
+
+        let mut count_collector = CountCollector::default();
+        searcher
+            .search_in_parallel(&query, &mut count_collector, &pool)
+            .unwrap();
+        assert_eq!(count_collector.count(), 20);
+
+        let mut top_collector = TopCollector::with_limit(7);
+        searcher.search(&query, &mut top_collector).unwrap();
+        let mut parallel_top_collector = TopCollector::with_limit(7);
+        searcher
+            .search_in_parallel(&query, &mut parallel_top_collector, &pool)
+            .unwrap();
+        let scores = |top_collector: &TopCollector| {
+            top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, _)| score)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scores(&parallel_top_collector), scores(&top_collector));
+    }
+
+    #[test]
+    fn test_search_many() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        for &text in &["a", "a b", "b c"] {
+            index_writer.add_document(doc!(text_field=>text));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query_a = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::Basic,
+        );
+        let query_b = TermQuery::new(
+            Term::from_field_text(text_field, "b"),
+            IndexRecordOption::Basic,
+        );
+        let mut count_a = CountCollector::default();
+        let mut count_b = CountCollector::default();
+        let mut top_b = TopCollector::with_limit(1);
+        searcher
+            .search_many(
+                &[&query_a, &query_b, &query_b],
+                &mut [&mut count_a, &mut count_b, &mut top_b],
+            )
+            .unwrap();
+        assert_eq!(count_a.count(), 2);
+        assert_eq!(count_b.count(), 2);
+        assert_eq!(top_b.docs().len(), 1);
+
+        let mut count = CountCollector::default();
+        assert!(searcher
+            .search_many(&[&query_a, &query_b], &mut [&mut count])
+            .is_err());
+    }
+
+    #[test]
+    fn test_suggest_term() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        index_writer.add_document(doc!(text_field=>"hello world"));
+        index_writer.add_document(doc!(text_field=>"help"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(text_field=>"hello hallo"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let suggestions = |text, max_distance| -> Vec<(String, u8, u32)> {
+            searcher
+                .suggest_term(text_field, text, max_distance)
+                .into_iter()
+                .map(|suggestion| (suggestion.text, suggestion.distance, suggestion.doc_freq))
+                .collect()
+        };
+        assert_eq!(
+            suggestions("helo", 1),
+            vec![("hello".to_string(), 1, 2), ("help".to_string(), 1, 1)]
+        );
+        assert_eq!(
+            suggestions("helo", 2),
+            vec![
+                ("hello".to_string(), 1, 2),
+                ("help".to_string(), 1, 1),
+                ("hallo".to_string(), 2, 1),
+            ]
+        );
+        assert_eq!(suggestions("hello", 0), vec![("hello".to_string(), 0, 2)]);
+        assert!(suggestions("xyz", 1).is_empty());
+    }
+
+    #[test]
+    fn test_complete() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title_options = TEXT | TextOptions::default().set_completion();
+        let title = schema_builder.add_text_field("title", title_options);
+        let body = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        index_writer.add_document(doc!(title=>"the old man and the sea"));
+        index_writer.add_document(doc!(title=>"the sun also rises"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(title=>"the old man and the sea"));
+        index_writer.add_document(doc!(title=>"the great gatsby", body=>"the"));
+        index_writer.commit().unwrap();
+        let completions = |prefix, k| -> Vec<(String, u64)> {
+            index.load_searchers().unwrap();
+            index
+                .searcher()
+                .complete(title, prefix, k)
+                .into_iter()
+                .map(|completion| (completion.text, completion.weight))
+                .collect()
+        };
+        let expected = vec![
+            ("the old man and the sea".to_string(), 2),
+            ("the great gatsby".to_string(), 1),
+            ("the sun also rises".to_string(), 1),
+        ];
+        assert_eq!(completions("the ", 3), expected);
+        assert_eq!(completions("the ", 1), expected[..1].to_vec());
+        assert!(completions("sea", 3).is_empty());
+        assert!(index.searcher().complete(body, "the", 3).is_empty());
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().segment_readers().len(), 1);
+        assert_eq!(completions("the ", 3), expected);
+    }
+
+    #[test]
+    fn test_field_terms() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        index_writer.add_document(doc!(text_field=>"hello world"));
+        index_writer.add_document(doc!(text_field=>"help"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(text_field=>"hello happy world"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 2);
+        let field_terms = searcher.field_terms(text_field);
+        let collect_terms = |mut stream: FieldTermStream| -> Vec<(String, u32)> {
+            let mut terms = vec![];
+            while stream.advance() {
+                let term = String::from_utf8(stream.key().to_vec()).unwrap();
+                terms.push((term, stream.doc_freq()));
+            }
+            terms
+        };
+        assert_eq!(
+            collect_terms(field_terms.stream()),
+            vec![
+                ("happy".to_string(), 1),
+                ("hello".to_string(), 2),
+                ("help".to_string(), 1),
+                ("world".to_string(), 2),
+            ]
+        );
+        assert_eq!(
+            collect_terms(field_terms.prefix_stream(b"hel")),
+            vec![("hello".to_string(), 2), ("help".to_string(), 1)]
+        );
+        assert!(collect_terms(field_terms.prefix_stream(b"x")).is_empty());
+        let last_terms: Vec<_> = collect_terms(field_terms.backward_stream())
+            .into_iter()
+            .take(3)
+            .collect();
+        assert_eq!(
+            last_terms,
+            vec![
+                ("world".to_string(), 2),
+                ("help".to_string(), 1),
+                ("hello".to_string(), 2),
+            ]
+        );
+    }
+}
@@ -53,6 +53,11 @@ impl Segment {
         self.meta.set_delete_meta(num_deleted_docs, opstamp);
     }
 
+    #[doc(hidden)]
+    pub fn set_fast_field_updates_meta(&mut self, num_updated_values: u32, opstamp: u64) {
+        self.meta.set_fast_field_updates_meta(num_updated_values, opstamp);
+    }
+
     /// Returns the segment's id.
     pub fn id(&self) -> SegmentId {
         self.meta.id()
@@ -1,8 +1,9 @@
 /// Enum describing each component of a tantivy segment.
 /// Each component is stored in its own file,
 /// using the pattern `segment_uuid`.`component_extension`,
-/// except the delete component that takes an `segment_uuid`.`delete_opstamp`.`component_extension`
-#[derive(Copy, Clone)]
+/// except the delete and fast field updates components that take a
+/// `segment_uuid`.`opstamp`.`component_extension`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
 pub enum SegmentComponent {
     /// Postings (or inverted list). Sorted lists of document ids, associated to terms
     POSTINGS,
@@ -22,12 +23,21 @@ pub enum SegmentComponent {
     STORE,
     /// Bitset describing which document of the segment is deleted.
     DELETE,
+    /// Fast field values updated after the segment was written.
+    /// They are stacked over the values of the `FASTFIELDS` component.
+    FASTFIELDUPDATES,
+    /// Values of the completion fields, associated to the number
+    /// of documents containing them. See `Searcher::complete`.
+    COMPLETIONS,
+    /// Terms of each document, with their positions and offsets,
+    /// for the fields with term vectors. See `Searcher::term_vector`.
+    TERMVECTORS,
 }
 
 impl SegmentComponent {
     /// Iterates through the components.
     pub fn iterator() -> impl Iterator<Item = &'static SegmentComponent> {
-        static SEGMENT_COMPONENTS: [SegmentComponent; 7] = [
+        static SEGMENT_COMPONENTS: [SegmentComponent; 10] = [
             SegmentComponent::POSTINGS,
             SegmentComponent::POSITIONS,
             SegmentComponent::FASTFIELDS,
@@ -35,6 +45,9 @@ impl SegmentComponent {
             SegmentComponent::TERMS,
             SegmentComponent::STORE,
             SegmentComponent::DELETE,
+            SegmentComponent::FASTFIELDUPDATES,
+            SegmentComponent::COMPLETIONS,
+            SegmentComponent::TERMVECTORS,
         ];
         SEGMENT_COMPONENTS.into_iter()
     }
@@ -1,9 +1,8 @@
 use uuid::Uuid;
 use std::fmt;
 use std::cmp::{Ord, Ordering};
-
-#[cfg(test)]
-use std::sync::atomic;
+use std::sync::atomic::{self, AtomicUsize};
+use byteorder::{BigEndian, ByteOrder};
 
 /// Uuid identifying a segment.
 ///
@@ -59,6 +58,57 @@ impl SegmentId {
     pub fn uuid_string(&self) -> String {
         self.0.simple().to_string()
     }
+
+    /// Returns the `sequence_number`-th segment id of a `SegmentIdSequence`.
+    ///
+    /// Its first 8 bytes are 0, which is never the case of a random UUID4.
+    fn from_sequence_number(sequence_number: u64) -> SegmentId {
+        let mut bytes = [0u8; 16];
+        BigEndian::write_u64(&mut bytes[8..], sequence_number);
+        SegmentId(Uuid::from_bytes(&bytes).expect("A uuid is made of 16 bytes"))
+    }
+
+    /// Returns the sequence number of the segment id, if it
+    /// was generated by a `SegmentIdSequence`.
+    fn sequence_number(&self) -> Option<u64> {
+        let bytes = self.0.as_bytes();
+        if bytes[..8].iter().all(|&b| b == 0u8) {
+            Some(BigEndian::read_u64(&bytes[8..]))
+        } else {
+            None
+        }
+    }
+}
+
+/// Generates increasing segment ids, for the segments of
+/// a deterministic `IndexWriter`. See `Index::deterministic_writer`.
+pub(crate) struct SegmentIdSequence {
+    next_sequence_number: AtomicUsize,
+}
+
+impl SegmentIdSequence {
+    /// Creates a sequence starting after the given segment ids.
+    pub fn starting_after<'a, I>(segment_ids: I) -> SegmentIdSequence
+    where
+        I: IntoIterator<Item = &'a SegmentId>,
+    {
+        let next_sequence_number = segment_ids
+            .into_iter()
+            .filter_map(SegmentId::sequence_number)
+            .max()
+            .map(|sequence_number| sequence_number + 1)
+            .unwrap_or(0u64);
+        SegmentIdSequence {
+            next_sequence_number: AtomicUsize::new(next_sequence_number as usize),
+        }
+    }
+
+    /// Returns the next segment id of the sequence.
+    pub fn next(&self) -> SegmentId {
+        let sequence_number = self.next_sequence_number
+            .fetch_add(1, atomic::Ordering::SeqCst);
+        SegmentId::from_sequence_number(sequence_number as u64)
+    }
 }
 
 impl fmt::Debug for SegmentId {
@@ -1,5 +1,6 @@
 use core::SegmentId;
 use super::SegmentComponent;
+use super::INDEX_FORMAT_VERSION;
 use std::path::PathBuf;
 use std::collections::HashSet;
 
@@ -9,6 +10,12 @@ struct DeleteMeta {
     opstamp: u64,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct FastFieldUpdatesMeta {
+    num_updated_values: u32,
+    opstamp: u64,
+}
+
 /// `SegmentMeta` contains simple meta information about a segment.
 ///
 /// For instance the number of docs it contains,
@@ -18,6 +25,9 @@ pub struct SegmentMeta {
     segment_id: SegmentId,
     max_doc: u32,
     deletes: Option<DeleteMeta>,
+    fast_field_updates: Option<FastFieldUpdatesMeta>,
+    #[serde(default)]
+    format_version: u32,
 }
 
 impl SegmentMeta {
@@ -28,9 +38,18 @@ impl SegmentMeta {
             segment_id,
             max_doc: 0,
             deletes: None,
+            fast_field_updates: None,
+            format_version: INDEX_FORMAT_VERSION,
         }
     }
 
+    /// Returns the version of the format the segment was written in.
+    ///
+    /// See `INDEX_FORMAT_VERSION`.
+    pub fn format_version(&self) -> u32 {
+        self.format_version
+    }
+
     /// Returns the segment id.
     pub fn id(&self) -> SegmentId {
         self.segment_id
@@ -69,7 +88,13 @@ impl SegmentMeta {
             SegmentComponent::STORE => ".store".to_string(),
             SegmentComponent::FASTFIELDS => ".fast".to_string(),
             SegmentComponent::FIELDNORMS => ".fieldnorm".to_string(),
+            SegmentComponent::COMPLETIONS => ".completion".to_string(),
+            SegmentComponent::TERMVECTORS => ".termvec".to_string(),
             SegmentComponent::DELETE => format!(".{}.del", self.delete_opstamp().unwrap_or(0)),
+            SegmentComponent::FASTFIELDUPDATES => format!(
+                ".{}.upd",
+                self.fast_field_updates_opstamp().unwrap_or(0)
+            ),
         });
         PathBuf::from(path)
     }
@@ -100,6 +125,29 @@ impl SegmentMeta {
         self.deletes.is_some()
     }
 
+    /// Returns the opstamp of the last fast field update
+    /// taken in account in this segment.
+    pub fn fast_field_updates_opstamp(&self) -> Option<u64> {
+        self.fast_field_updates
+            .as_ref()
+            .map(|fast_field_updates_meta| fast_field_updates_meta.opstamp)
+    }
+
+    /// Returns the number of fast field values updated
+    /// since the segment was written.
+    pub fn num_updated_fast_field_values(&self) -> u32 {
+        self.fast_field_updates
+            .as_ref()
+            .map(|fast_field_updates_meta| fast_field_updates_meta.num_updated_values)
+            .unwrap_or(0u32)
+    }
+
+    /// Returns true iff the segment meta contains
+    /// fast field updates.
+    pub fn has_fast_field_updates(&self) -> bool {
+        self.fast_field_updates.is_some()
+    }
+
     #[doc(hidden)]
     pub fn set_max_doc(&mut self, max_doc: u32) {
         self.max_doc = max_doc;
@@ -112,4 +160,12 @@ impl SegmentMeta {
             opstamp,
         });
     }
+
+    #[doc(hidden)]
+    pub fn set_fast_field_updates_meta(&mut self, num_updated_values: u32, opstamp: u64) {
+        self.fast_field_updates = Some(FastFieldUpdatesMeta {
+            num_updated_values,
+            opstamp,
+        });
+    }
 }
@@ -1,14 +1,17 @@
 use Result;
 use core::Segment;
 use core::SegmentId;
+use core::{MemoryBudget, MemoryReservation};
 use core::SegmentComponent;
-use std::sync::RwLock;
+use std::any::{Any, TypeId};
+use std::sync::{Mutex, RwLock};
 use common::HasLen;
 use core::SegmentMeta;
 use fastfield::{self, FastFieldNotAvailableError};
 use fastfield::DeleteBitSet;
-use store::StoreReader;
-use directory::ReadOnlySource;
+use fastfield::FastFieldUpdates;
+use store::{LazyDocument, StoreDocumentIter, StoreReader};
+use directory::{FileProtection, ReadOnlySource};
 use schema::Document;
 use DocId;
 use std::sync::Arc;
@@ -16,16 +19,50 @@ use std::collections::HashMap;
 use common::CompositeFile;
 use std::fmt;
 use core::InvertedIndexReader;
+use core::FieldStatistics;
+use core::field_statistics::compute_field_statistics;
 use schema::Field;
 use schema::FieldType;
+use schema::IndexRecordOption;
 use error::ErrorKind;
 use termdict::TermDictionaryImpl;
-use fastfield::FacetReader;
-use fastfield::FastFieldReader;
+use fastfield::{FacetReader, FieldNormReader, StrFastFieldReader};
+use fastfield::{open_fast_field_column, FastFieldReader};
 use schema::Schema;
-use termdict::TermDictionary;
-use fastfield::{FastValue, MultiValueIntFastFieldReader};
+use termdict::{TermDictionary, TermStreamer};
+use fastfield::{BytesFastFieldReader, FastValue, MultiValueIntFastFieldReader};
+use fastfield::VectorFastFieldReader;
 use schema::Cardinality;
+use completion::CompletionIndex;
+use termvector::{TermVector, TermVectorReader};
+
+type FastFieldCache = HashMap<(Field, usize), CachedFastFieldColumn>;
+
+struct CachedFastFieldColumn {
+    reader: FastFieldReader<u64>,
+    // registered against the memory budget of the index, if any.
+    reservation: Option<MemoryReservation>,
+}
+
+/// Removes a column evicted by the memory budget,
+/// unless it has been replaced in the meantime.
+fn evict_fast_field_column(
+    fast_field_cache: &RwLock<FastFieldCache>,
+    key: (Field, usize),
+    entry_id: u64,
+) {
+    let mut fast_field_cache = fast_field_cache
+        .write()
+        .expect("Fast field cache lock poisoned. This should never happen.");
+    let is_evicted_column = fast_field_cache
+        .get(&key)
+        .and_then(|cached_column| cached_column.reservation.as_ref())
+        .map(|reservation| reservation.entry_id() == entry_id)
+        .unwrap_or(false);
+    if is_evicted_column {
+        fast_field_cache.remove(&key);
+    }
+}
 
 /// Entry point to access all of the datastructures of the `Segment`
 ///
@@ -43,6 +80,14 @@ use schema::Cardinality;
 #[derive(Clone)]
 pub struct SegmentReader {
     inv_idx_reader_cache: Arc<RwLock<HashMap<Field, Arc<InvertedIndexReader>>>>,
+    // fast field columns, keyed by field and index in the composite file,
+    // opened on first access.
+    fast_field_cache: Arc<RwLock<FastFieldCache>>,
+    // user data attached to the segment, keyed by its type.
+    // Each value is an `Arc<T>`, where `T` is the type of its key.
+    extensions: Arc<Mutex<HashMap<TypeId, Box<Any + Send>>>>,
+    // statistics of the indexed fields, computed on first access.
+    field_statistics_cache: Arc<RwLock<HashMap<Field, FieldStatistics>>>,
 
     segment_id: SegmentId,
     segment_meta: SegmentMeta,
@@ -52,9 +97,15 @@ pub struct SegmentReader {
     positions_composite: CompositeFile,
     fast_fields_composite: CompositeFile,
     fieldnorms_composite: CompositeFile,
+    completions_composite: CompositeFile,
+    term_vectors_composite: CompositeFile,
 
     store_reader: StoreReader,
     delete_bitset: DeleteBitSet,
+    memory_budget: Option<MemoryBudget>,
+    // Files are protected from deletion as long as a reader is using them.
+    _file_protections: Arc<Vec<FileProtection>>,
+    fast_field_updates: FastFieldUpdates,
     schema: Schema,
 }
 
@@ -98,10 +149,11 @@ impl SegmentReader {
     ) -> fastfield::Result<FastFieldReader<Item>> {
         let field_entry = self.schema.get_field_entry(field);
         if Item::fast_field_cardinality(field_entry.field_type()) == Some(Cardinality::SingleValue) {
-            self.fast_fields_composite
-                .open_read(field)
-                .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
-                .map(FastFieldReader::open)
+            let fast_field_reader = self.fast_field_column(field, 0)?;
+            match self.fast_field_updates.field_updates(field) {
+                Some(field_updates) => Ok(fast_field_reader.with_updates(field_updates)),
+                None => Ok(fast_field_reader),
+            }
         } else {
             Err(FastFieldNotAvailableError::new(field_entry))
         }
@@ -112,20 +164,194 @@ impl SegmentReader {
     pub fn multi_fast_field_reader<Item: FastValue>(&self, field: Field) -> fastfield::Result<MultiValueIntFastFieldReader<Item>> {
         let field_entry = self.schema.get_field_entry(field);
         if Item::fast_field_cardinality(field_entry.field_type()) == Some(Cardinality::MultiValues) {
-            let idx_reader = self.fast_fields_composite
-                .open_read_with_idx(field, 0)
-                .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
-                .map(FastFieldReader::open)?;
-            let vals_reader = self.fast_fields_composite
-                .open_read_with_idx(field, 1)
-                .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))
-                .map(FastFieldReader::open)?;
-            Ok(MultiValueIntFastFieldReader::open(idx_reader, vals_reader))
+            self.open_multi_fast_field_reader(field)
         } else {
             Err(FastFieldNotAvailableError::new(field_entry))
         }
     }
 
+    /// Accessor to the `MultiValueIntFastFieldReader` of any multivalued fast field,
+    /// returning the values as they are encoded, that is as `u64`.
+    ///
+    /// For facets and str fast fields, the values are term ordinals.
+    pub(crate) fn multi_fast_field_reader_u64(
+        &self,
+        field: Field,
+    ) -> fastfield::Result<MultiValueIntFastFieldReader<u64>> {
+        let field_entry = self.schema.get_field_entry(field);
+        match *field_entry.field_type() {
+            FieldType::U64(ref options) | FieldType::I64(ref options)
+                if options.get_fastfield_cardinality() == Some(Cardinality::MultiValues) =>
+            {
+                self.open_multi_fast_field_reader(field)
+            }
+            FieldType::Str(ref options) if options.is_fast() => {
+                self.open_multi_fast_field_reader(field)
+            }
+            FieldType::HierarchicalFacet => self.open_multi_fast_field_reader(field),
+            _ => Err(FastFieldNotAvailableError::new(field_entry)),
+        }
+    }
+
+    fn open_multi_fast_field_reader<Item: FastValue>(
+        &self,
+        field: Field,
+    ) -> fastfield::Result<MultiValueIntFastFieldReader<Item>> {
+        let idx_reader = self.fast_field_column(field, 0)?;
+        let vals_reader = self.fast_field_column(field, 1)?;
+        Ok(MultiValueIntFastFieldReader::open(idx_reader, vals_reader))
+    }
+
+    /// Returns the fast field column stored at the index `idx` of the
+    /// composite file for the given field.
+    ///
+    /// Columns are opened on first access, and kept until they are evicted,
+    /// either explicitly or by the `MemoryBudget` of the index.
+    fn fast_field_column<Item: FastValue>(
+        &self,
+        field: Field,
+        idx: usize,
+    ) -> fastfield::Result<FastFieldReader<Item>> {
+        let key = (field, idx);
+        if let Some(cached_column) = self.fast_field_cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .get(&key)
+        {
+            if let Some(ref reservation) = cached_column.reservation {
+                reservation.touch();
+            }
+            return Ok(cached_column.reader.clone().cast());
+        }
+        let field_entry = self.schema.get_field_entry(field);
+        let fast_field_reader_res = open_fast_field_column(
+            &self.fast_fields_composite,
+            field,
+            idx,
+            self.segment_meta.format_version(),
+            self.max_doc(),
+        ).ok_or_else(|| FastFieldNotAvailableError::new(field_entry))?;
+        let fast_field_reader: FastFieldReader<u64> = fast_field_reader_res
+            .expect("Fast field columns are checked when the segment is opened");
+        let num_bytes = self.fast_fields_composite
+            .open_read_with_idx(field, idx)
+            .map(|source| source.len())
+            .unwrap_or(0);
+        let mut evictions = None;
+        {
+            // by releasing the lock in between, we may end up opening the column
+            // twice, but this is fine.
+            let mut fast_field_cache = self.fast_field_cache
+                .write()
+                .expect("Fast field cache lock poisoned. This should never happen.");
+            let reservation = match self.memory_budget {
+                Some(ref memory_budget) => {
+                    let fast_field_cache_ref = Arc::downgrade(&self.fast_field_cache);
+                    let evict = move |entry_id: u64| {
+                        if let Some(fast_field_cache) = fast_field_cache_ref.upgrade() {
+                            evict_fast_field_column(&fast_field_cache, key, entry_id);
+                        }
+                    };
+                    match memory_budget.reserve(num_bytes, evict) {
+                        Some((reservation, column_evictions)) => {
+                            evictions = Some(column_evictions);
+                            Some(reservation)
+                        }
+                        // larger than the whole budget.
+                        None => return Ok(fast_field_reader.cast()),
+                    }
+                }
+                None => None,
+            };
+            fast_field_cache.insert(
+                key,
+                CachedFastFieldColumn {
+                    reader: fast_field_reader.clone(),
+                    reservation,
+                },
+            );
+        }
+        if let Some(evictions) = evictions {
+            evictions.run();
+        }
+        Ok(fast_field_reader.cast())
+    }
+
+    /// Returns the fields whose fast field columns are currently loaded.
+    pub fn loaded_fast_fields(&self) -> Vec<Field> {
+        let mut fields: Vec<Field> = self.fast_field_cache
+            .read()
+            .expect("Lock poisoned. This should never happen")
+            .keys()
+            .map(|&(field, _)| field)
+            .collect();
+        fields.sort();
+        fields.dedup();
+        fields
+    }
+
+    /// Evicts the fast field columns of a field.
+    ///
+    /// They will be opened again upon the next access.
+    /// Readers that were already handed out remain valid.
+    pub fn evict_fast_field(&self, field: Field) {
+        self.fast_field_cache
+            .write()
+            .expect("Fast field cache lock poisoned. This should never happen.")
+            .retain(|&(cached_field, _), _| cached_field != field);
+    }
+
+    /// Evicts all of the fast field columns loaded so far.
+    pub fn evict_fast_fields(&self) {
+        self.fast_field_cache
+            .write()
+            .expect("Fast field cache lock poisoned. This should never happen.")
+            .clear();
+    }
+
+    /// Accessor to the `BytesFastFieldReader` associated to a given bytes `Field`.
+    pub fn bytes_fast_field_reader(
+        &self,
+        field: Field,
+    ) -> fastfield::Result<BytesFastFieldReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        if field_entry.field_type() != &FieldType::Bytes {
+            return Err(FastFieldNotAvailableError::new(field_entry));
+        }
+        let idx_reader = self.fast_field_column(field, 0)?;
+        let values = self.fast_fields_composite
+            .open_read_with_idx(field, 1)
+            .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))?;
+        Ok(BytesFastFieldReader::open(idx_reader, values))
+    }
+
+    /// Accessor to the `VectorFastFieldReader` associated to a given vector `Field`.
+    pub fn vector_fast_field_reader(
+        &self,
+        field: Field,
+    ) -> fastfield::Result<VectorFastFieldReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        let vector_options = match *field_entry.field_type() {
+            FieldType::Vector(ref vector_options) => vector_options,
+            _ => return Err(FastFieldNotAvailableError::new(field_entry)),
+        };
+        let ords_reader = self.fast_field_column(field, 0)?;
+        let values = self.fast_fields_composite
+            .open_read_with_idx(field, 1)
+            .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))?;
+        let vector_reader =
+            VectorFastFieldReader::open(vector_options.dimension(), ords_reader, values);
+        match vector_options.ann_index() {
+            Some(similarity) => {
+                let hnsw_source = self.fast_fields_composite
+                    .open_read_with_idx(field, 2)
+                    .ok_or_else(|| FastFieldNotAvailableError::new(field_entry))?;
+                Ok(vector_reader.with_ann_index(similarity, hnsw_source))
+            }
+            None => Ok(vector_reader),
+        }
+    }
+
     /// Accessor to the `FacetReader` associated to a given `Field`.
     pub fn facet_reader(&self, field: Field) -> Result<FacetReader> {
         let field_entry = self.schema.get_field_entry(field);
@@ -145,11 +371,44 @@ impl SegmentReader {
                 field_entry.name()
             ))
         })?;
-        let termdict = TermDictionaryImpl::from_source(termdict_source);
+        let termdict = TermDictionaryImpl::from_source(termdict_source)
+            .expect("Term dictionaries are checked when the segment is opened");
         let facet_reader = FacetReader::new(term_ords_reader, termdict);
         Ok(facet_reader)
     }
 
+    /// Accessor to the `StrFastFieldReader` associated to a given text `Field`.
+    ///
+    /// The field must have been declared as fast in the schema.
+    /// See `TextOptions::set_fast`.
+    pub fn str_fast_field_reader(&self, field: Field) -> Result<StrFastFieldReader> {
+        let field_entry = self.schema.get_field_entry(field);
+        match *field_entry.field_type() {
+            FieldType::Str(ref options) if options.is_fast() => {}
+            _ => {
+                return Err(FastFieldNotAvailableError::new(field_entry).into());
+            }
+        }
+        let term_ords_reader = self.multi_fast_field_reader_u64(field)?;
+        let termdict = self.term_dict(field);
+        Ok(StrFastFieldReader::new(term_ords_reader, termdict))
+    }
+
+    /// Returns the term dictionary of an indexed field.
+    ///
+    /// If the segment does not contain any term for this field,
+    /// an empty term dictionary is returned.
+    pub(crate) fn term_dict(&self, field: Field) -> TermDictionaryImpl {
+        match self.termdict_composite.open_read(field) {
+            Some(termdict_source) => TermDictionaryImpl::from_source(termdict_source)
+                .expect("Term dictionaries are checked when the segment is opened"),
+            None => {
+                let field_type = self.schema.get_field_entry(field).field_type().clone();
+                TermDictionaryImpl::empty(field_type)
+            }
+        }
+    }
+
     /// Accessor to the segment's `Field norms`'s reader.
     ///
     /// Field norms are the length (in tokens) of the fields.
@@ -159,9 +418,61 @@ impl SegmentReader {
     /// They are simply stored as a fast field, serialized in
     /// the `.fieldnorm` file of the segment.
     pub fn get_fieldnorms_reader(&self, field: Field) -> Option<FastFieldReader<u64>> {
-        self.fieldnorms_composite
-            .open_read(field)
-            .map(FastFieldReader::open)
+        open_fast_field_column(
+            &self.fieldnorms_composite,
+            field,
+            0,
+            self.segment_meta.format_version(),
+            self.max_doc(),
+        ).map(|fieldnorms_reader_res| {
+            fieldnorms_reader_res.expect("Field norms are checked when the segment is opened")
+        })
+    }
+
+    /// Accessor to the `FieldNormReader` of a given field.
+    ///
+    /// It gives access to the length (in tokens) of the field for each document,
+    /// exactly or encoded in a single byte.
+    ///
+    /// Returns `None` if the field is not indexed.
+    pub fn fieldnorm_reader(&self, field: Field) -> Option<FieldNormReader> {
+        self.get_fieldnorms_reader(field).map(FieldNormReader::new)
+    }
+
+    /// Returns the completion index of a field.
+    ///
+    /// Returns `None` if the field is not a completion field.
+    pub(crate) fn completion_index(&self, field: Field) -> Option<CompletionIndex> {
+        self.completions_composite.open_read(field).map(|source| {
+            CompletionIndex::open(source)
+                .expect("Index corrupted. Failed to open completion index.")
+        })
+    }
+
+    /// Returns the term vectors of a field.
+    ///
+    /// Returns `None` if the term vectors of the field are not stored.
+    pub(crate) fn term_vector_reader(&self, field: Field) -> Option<TermVectorReader> {
+        self.term_vectors_composite.open_read(field).map(|source| {
+            TermVectorReader::open(source).expect("Index corrupted. Failed to open term vectors.")
+        })
+    }
+
+    /// Returns the term vector of a field of a document, listing
+    /// its terms with their positions and offsets.
+    ///
+    /// Returns `None` if the term vectors of the field are not stored.
+    /// (See `TextFieldIndexing::set_term_vectors`.)
+    pub fn term_vector(&self, doc_id: DocId, field: Field) -> Result<Option<TermVector>> {
+        match self.term_vector_reader(field) {
+            Some(term_vector_reader) => Ok(Some(term_vector_reader.term_vector(doc_id)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the schema of the segment.
+    pub fn schema(&self) -> &Schema {
+        &self.schema
     }
 
     /// Accessor to the segment's `StoreReader`.
@@ -171,11 +482,30 @@ impl SegmentReader {
 
     /// Open a new segment for reading.
     pub fn open(segment: &Segment) -> Result<SegmentReader> {
+        SegmentReader::open_with_file_protections(segment, Vec::new())
+    }
+
+    /// Opens a new segment for reading, keeping `file_protections` alive
+    /// as long as the reader, or any of its clones, is alive.
+    pub(crate) fn open_with_file_protections(
+        segment: &Segment,
+        extra_file_protections: Vec<FileProtection>,
+    ) -> Result<SegmentReader> {
+        let mut file_protections: Vec<FileProtection> = SegmentComponent::iterator()
+            .map(|component| segment.protect_from_delete(*component))
+            .collect();
+        file_protections.extend(extra_file_protections);
+
         let termdict_source = segment.open_read(SegmentComponent::TERMS)?;
         let termdict_composite = CompositeFile::open(&termdict_source)?;
 
         let store_source = segment.open_read(SegmentComponent::STORE)?;
-        let store_reader = StoreReader::from_source(store_source);
+        let store_compressor = segment.index().settings().docstore_compression.clone();
+        let mut store_reader = StoreReader::from_source(store_source, store_compressor)?;
+        let memory_budget = segment.index().memory_budget().cloned();
+        if let Some(ref memory_budget) = memory_budget {
+            store_reader.set_memory_budget(memory_budget.clone());
+        }
 
         let postings_source = segment.open_read(SegmentComponent::POSTINGS)?;
         let postings_composite = CompositeFile::open(&postings_source)?;
@@ -194,6 +524,25 @@ impl SegmentReader {
         let fieldnorms_data = segment.open_read(SegmentComponent::FIELDNORMS)?;
         let fieldnorms_composite = CompositeFile::open(&fieldnorms_data)?;
 
+        // segments written before completion indexes existed
+        // do not have this component.
+        let completions_composite = {
+            if let Ok(source) = segment.open_read(SegmentComponent::COMPLETIONS) {
+                CompositeFile::open(&source)?
+            } else {
+                CompositeFile::empty()
+            }
+        };
+
+        // nor do the segments written before term vectors existed.
+        let term_vectors_composite = {
+            if let Ok(source) = segment.open_read(SegmentComponent::TERMVECTORS) {
+                CompositeFile::open(&source)?
+            } else {
+                CompositeFile::empty()
+            }
+        };
+
         let delete_bitset = if segment.meta().has_deletes() {
             let delete_data = segment.open_read(SegmentComponent::DELETE)?;
             DeleteBitSet::open(delete_data)
@@ -201,19 +550,41 @@ impl SegmentReader {
             DeleteBitSet::empty()
         };
 
+        let fast_field_updates = if segment.meta().has_fast_field_updates() {
+            let fast_field_updates_data = segment.open_read(SegmentComponent::FASTFIELDUPDATES)?;
+            FastFieldUpdates::open(fast_field_updates_data)?
+        } else {
+            FastFieldUpdates::empty()
+        };
+
         let schema = segment.schema();
+        check_segment_data(
+            &schema,
+            segment.meta(),
+            &termdict_composite,
+            &fast_fields_composite,
+            &fieldnorms_composite,
+        )?;
         Ok(SegmentReader {
             inv_idx_reader_cache: Arc::new(RwLock::new(HashMap::new())),
+            fast_field_cache: Arc::new(RwLock::new(HashMap::new())),
+            extensions: Arc::new(Mutex::new(HashMap::new())),
+            field_statistics_cache: Arc::new(RwLock::new(HashMap::new())),
             segment_meta: segment.meta().clone(),
             termdict_composite,
             postings_composite,
             fast_fields_composite,
             fieldnorms_composite,
+            completions_composite,
+            term_vectors_composite,
             segment_id: segment.id(),
             store_reader,
             delete_bitset,
+            memory_budget,
+            fast_field_updates,
             positions_composite,
             schema,
+            _file_protections: Arc::new(file_protections),
         })
     }
 
@@ -255,6 +626,7 @@ impl SegmentReader {
             positions_source,
             self.delete_bitset.clone(),
             record_option,
+            self.segment_meta.format_version(),
         ));
 
         // by releasing the lock in between, we may end up opening the inverting index
@@ -267,6 +639,33 @@ impl SegmentReader {
         inv_idx_reader
     }
 
+    /// Returns the statistics of an indexed field: number of documents
+    /// containing the field, number of tokens, and number of distinct terms.
+    ///
+    /// They are computed on first access, and cached for the lifetime
+    /// of the reader.
+    ///
+    /// Returns `None` if the field is not indexed, or a `DataCorruption`
+    /// error if its postings had to be scanned and are malformed.
+    pub fn field_statistics(&self, field: Field) -> Result<Option<FieldStatistics>> {
+        if let Some(field_statistics) = self.field_statistics_cache
+            .read()
+            .expect("Field statistics cache lock poisoned. This should never happen.")
+            .get(&field)
+        {
+            return Ok(Some(*field_statistics));
+        }
+        let field_statistics = match compute_field_statistics(self, field)? {
+            Some(field_statistics) => field_statistics,
+            None => return Ok(None),
+        };
+        self.field_statistics_cache
+            .write()
+            .expect("Field statistics cache lock poisoned. This should never happen.")
+            .insert(field, field_statistics);
+        Ok(Some(field_statistics))
+    }
+
     /// Returns the document (or to be accurate, its stored field)
     /// bearing the given doc id.
     /// This method is slow and should seldom be called from
@@ -275,22 +674,228 @@ impl SegmentReader {
         self.store_reader.get(doc_id)
     }
 
+    /// Returns the stored fields of a given document, deserializing
+    /// them on demand.
+    ///
+    /// See `LazyDocument`.
+    pub fn doc_lazy(&self, doc_id: DocId) -> Result<LazyDocument> {
+        self.store_reader.get_lazy(doc_id)
+    }
+
+    /// Iterates over the stored fields of all of the documents
+    /// that are not deleted, in the order of their doc id.
+    ///
+    /// See `StoreReader::iter_documents`.
+    pub fn iter_documents(&self) -> StoreDocumentIter {
+        self.store_reader.iter_documents(&self.delete_bitset)
+    }
+
     /// Returns the segment id
     pub fn segment_id(&self) -> SegmentId {
         self.segment_id
     }
 
+    /// Returns the meta of the segment, as of the opening of the reader.
+    pub(crate) fn segment_meta(&self) -> &SegmentMeta {
+        &self.segment_meta
+    }
+
     /// Returns the bitset representing
     /// the documents that have been deleted.
     pub fn delete_bitset(&self) -> &DeleteBitSet {
         &self.delete_bitset
     }
 
+    /// Returns the fast field values updated since
+    /// the segment was written.
+    pub fn fast_field_updates(&self) -> &FastFieldUpdates {
+        &self.fast_field_updates
+    }
+
     /// Returns true iff the `doc` is marked
     /// as deleted.
     pub fn is_deleted(&self, doc: DocId) -> bool {
         self.delete_bitset.is_deleted(doc)
     }
+
+    /// Returns the extension of type `T` attached to the segment,
+    /// or `None` if it was never computed.
+    ///
+    /// See `.extension(...)`.
+    pub fn get_extension<T: Any + Send + Sync>(&self) -> Option<Arc<T>> {
+        self.extensions
+            .lock()
+            .expect("Extensions lock poisoned. This should never happen.")
+            .get(&TypeId::of::<T>())
+            .and_then(|extension| extension.downcast_ref::<Arc<T>>())
+            .cloned()
+    }
+
+    /// Returns the extension of type `T` attached to the segment.
+    ///
+    /// Extensions make it possible to attach arbitrary data derived from
+    /// a segment, for instance custom norms or a mapping to external ids.
+    /// There is at most one extension per type: it is computed by
+    /// `compute` upon the first call, and then shared by the reader
+    /// and all of its clones, for as long as they live.
+    ///
+    /// `compute` is called without holding any lock, so that it may
+    /// itself access other extensions. If several threads compute
+    /// the same extension concurrently, the first one to finish wins.
+    pub fn extension<T, F>(&self, compute: F) -> Result<Arc<T>>
+    where
+        T: Any + Send + Sync,
+        F: FnOnce(&SegmentReader) -> Result<T>,
+    {
+        if let Some(extension) = self.get_extension::<T>() {
+            return Ok(extension);
+        }
+        let extension: Box<Any + Send> = box Arc::new(compute(self)?);
+        let mut extensions = self.extensions
+            .lock()
+            .expect("Extensions lock poisoned. This should never happen.");
+        let attached = extensions.entry(TypeId::of::<T>()).or_insert(extension);
+        Ok(Arc::clone(attached
+            .downcast_ref::<Arc<T>>()
+            .expect("Extensions are keyed by their type")))
+    }
+
+    /// Checks that the different components of the segment
+    /// agree on its number of documents.
+    ///
+    /// - the store must contain exactly `max_doc` documents,
+    /// - the fast fields and the field norms must hold a value for every document,
+    /// - the doc ids of the posting lists must be sorted and lower than `max_doc`,
+    /// and their length must match the doc freq of the term dictionary.
+    ///
+    /// Returns a `CorruptedFile` error pointing to the first
+    /// inconsistent component.
+    ///
+    /// This method reads the entire segment, and is therefore slow.
+    /// It does not verify checksums: a file corrupted in a way that does
+    /// not affect document counts may go undetected, and a corrupted
+    /// posting list may cause a panic. See `Index::validate_checksums`.
+    pub fn validate_doc_counts(&self) -> Result<()> {
+        let max_doc = self.max_doc();
+        let corrupted = |component: SegmentComponent| {
+            ErrorKind::CorruptedFile(self.segment_meta.relative_path(component))
+        };
+        if self.store_reader.max_doc() != max_doc {
+            bail!(corrupted(SegmentComponent::STORE));
+        }
+        for (field_id, field_entry) in self.schema.fields().iter().enumerate() {
+            let field = Field(field_id as u32);
+            let is_single_value_fast_field = match *field_entry.field_type() {
+                FieldType::U64(ref options) | FieldType::I64(ref options) => {
+                    options.get_fastfield_cardinality() == Some(Cardinality::SingleValue)
+                }
+                _ => false,
+            };
+            if is_single_value_fast_field {
+                let fast_field_reader = open_fast_field_column(
+                    &self.fast_fields_composite,
+                    field,
+                    0,
+                    self.segment_meta.format_version(),
+                    max_doc,
+                ).ok_or_else(|| corrupted(SegmentComponent::FASTFIELDS))??;
+                if fast_field_reader.capacity() < max_doc as usize {
+                    bail!(corrupted(SegmentComponent::FASTFIELDS));
+                }
+            }
+            if !field_entry.is_indexed() {
+                continue;
+            }
+            let fieldnorms_reader = self.get_fieldnorms_reader(field)
+                .ok_or_else(|| corrupted(SegmentComponent::FIELDNORMS))?;
+            if fieldnorms_reader.capacity() < max_doc as usize {
+                bail!(corrupted(SegmentComponent::FIELDNORMS));
+            }
+            if self.termdict_composite.open_read(field).is_none() {
+                bail!(corrupted(SegmentComponent::TERMS));
+            }
+            if self.postings_composite.open_read(field).is_none() {
+                bail!(corrupted(SegmentComponent::POSTINGS));
+            }
+            let inverted_index = self.inverted_index(field);
+            let mut term_stream = inverted_index.terms().stream();
+            while term_stream.advance() {
+                let term_info = term_stream.value();
+                let mut block_postings = inverted_index
+                    .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic)
+                    .map_err(|_| corrupted(SegmentComponent::POSTINGS))?;
+                let mut num_docs = 0u32;
+                let mut previous_doc: Option<DocId> = None;
+                while block_postings.advance() {
+                    for &doc in block_postings.docs() {
+                        if doc >= max_doc || previous_doc.map(|prev| doc <= prev).unwrap_or(false) {
+                            bail!(corrupted(SegmentComponent::POSTINGS));
+                        }
+                        previous_doc = Some(doc);
+                        num_docs += 1;
+                    }
+                }
+                if num_docs != term_info.doc_freq {
+                    bail!(corrupted(SegmentComponent::POSTINGS));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the indexes, in the fast field composite file, of the columns
+/// encoded as fast fields for a field of the given type.
+fn fast_field_column_idxs(field_type: &FieldType) -> &'static [usize] {
+    match *field_type {
+        FieldType::U64(ref options) | FieldType::I64(ref options) => {
+            match options.get_fastfield_cardinality() {
+                Some(Cardinality::SingleValue) => &[0],
+                Some(Cardinality::MultiValues) => &[0, 1],
+                None => &[],
+            }
+        }
+        FieldType::Str(ref options) if options.is_fast() => &[0, 1],
+        FieldType::Str(_) => &[],
+        FieldType::HierarchicalFacet => &[0, 1],
+        // the values of bytes and vector fields are not encoded as fast fields.
+        FieldType::Bytes | FieldType::Vector(_) => &[0],
+    }
+}
+
+/// Checks the headers of the term dictionaries, the fast fields
+/// and the field norms of a segment.
+///
+/// They are then opened on demand by infallible accessors,
+/// which would otherwise panic on a malformed header.
+fn check_segment_data(
+    schema: &Schema,
+    segment_meta: &SegmentMeta,
+    termdict_composite: &CompositeFile,
+    fast_fields_composite: &CompositeFile,
+    fieldnorms_composite: &CompositeFile,
+) -> Result<()> {
+    let format_version = segment_meta.format_version();
+    let max_doc = segment_meta.max_doc();
+    for (field_id, field_entry) in schema.fields().iter().enumerate() {
+        let field = Field(field_id as u32);
+        if let Some(termdict_source) = termdict_composite.open_read(field) {
+            TermDictionaryImpl::from_source(termdict_source)?;
+        }
+        for &idx in fast_field_column_idxs(field_entry.field_type()) {
+            if let Some(fast_field_reader_res) =
+                open_fast_field_column(fast_fields_composite, field, idx, format_version, max_doc)
+            {
+                fast_field_reader_res?;
+            }
+        }
+        if let Some(fieldnorms_reader_res) =
+            open_fast_field_column(fieldnorms_composite, field, 0, format_version, max_doc)
+        {
+            fieldnorms_reader_res?;
+        }
+    }
+    Ok(())
 }
 
 impl fmt::Debug for SegmentReader {
@@ -298,3 +903,286 @@ impl fmt::Debug for SegmentReader {
         write!(f, "SegmentReader({:?})", self.segment_id)
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Result;
+    use core::{MemoryBudget, SegmentComponent, SegmentReader};
+    use directory::Directory;
+    use docset::DocSet;
+    use error::{Error, ErrorKind};
+    use schema::{Facet, Field, IndexRecordOption, SchemaBuilder, Term, FAST, INT_INDEXED, STORED,
+                 TEXT};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_fast_fields_loaded_on_demand() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let score_field = schema_builder.add_i64_field("score", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>1u64, score_field=>-3i64));
+        index_writer.add_document(doc!(id_field=>2u64, score_field=>4i64));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        assert!(segment_reader.loaded_fast_fields().is_empty());
+
+        let score_reader = segment_reader.fast_field_reader::<i64>(score_field).unwrap();
+        assert_eq!(segment_reader.loaded_fast_fields(), vec![score_field]);
+        assert_eq!(score_reader.get(0), -3i64);
+
+        let id_reader = segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+        assert_eq!(segment_reader.loaded_fast_fields(), vec![id_field, score_field]);
+        assert_eq!(id_reader.get(1), 2u64);
+
+        segment_reader.evict_fast_field(score_field);
+        assert_eq!(segment_reader.loaded_fast_fields(), vec![id_field]);
+        // readers handed out before the eviction are still valid.
+        assert_eq!(score_reader.get(1), 4i64);
+        assert_eq!(
+            segment_reader.fast_field_reader::<i64>(score_field).unwrap().get(1),
+            4i64
+        );
+
+        segment_reader.evict_fast_fields();
+        assert!(segment_reader.loaded_fast_fields().is_empty());
+    }
+
+    #[test]
+    fn test_fast_fields_memory_budget() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let score_field = schema_builder.add_i64_field("score", FAST);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>1u64, score_field=>-3i64));
+        index_writer.add_document(doc!(id_field=>2u64, score_field=>4i64));
+        index_writer.commit().unwrap();
+
+        // measures the size of the columns.
+        let memory_budget = MemoryBudget::with_capacity(1 << 20);
+        index.set_memory_budget(memory_budget.clone()).unwrap();
+        let (score_num_bytes, id_num_bytes) = {
+            let searcher = index.searcher();
+            let segment_reader = searcher.segment_reader(0);
+            segment_reader.fast_field_reader::<i64>(score_field).unwrap();
+            let score_num_bytes = memory_budget.num_bytes_used();
+            segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+            (score_num_bytes, memory_budget.num_bytes_used() - score_num_bytes)
+        };
+        assert!(score_num_bytes > 0 && id_num_bytes > 0);
+
+        // room for one column, but not both.
+        let capacity = score_num_bytes + id_num_bytes - 1;
+        let memory_budget = MemoryBudget::with_capacity(capacity);
+        index.set_memory_budget(memory_budget.clone()).unwrap();
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let score_reader = segment_reader.fast_field_reader::<i64>(score_field).unwrap();
+        let id_reader = segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+        // the score column was evicted to make room for the id column.
+        assert_eq!(segment_reader.loaded_fast_fields(), vec![id_field]);
+        assert_eq!(memory_budget.num_bytes_used(), id_num_bytes);
+        assert_eq!(score_reader.get(0), -3i64);
+        assert_eq!(id_reader.get(1), 2u64);
+        memory_budget.evict_all();
+        assert!(segment_reader.loaded_fast_fields().is_empty());
+    }
+
+    struct DocIdMapping(Vec<u64>);
+
+    #[test]
+    fn test_segment_reader_extension() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>10u64));
+        index_writer.add_document(doc!(id_field=>20u64));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        assert!(segment_reader.get_extension::<DocIdMapping>().is_none());
+        let compute_mapping = |segment_reader: &SegmentReader| -> Result<DocIdMapping> {
+            let id_reader = segment_reader.fast_field_reader::<u64>(id_field)?;
+            let ids = (0..segment_reader.max_doc())
+                .map(|doc| id_reader.get(doc))
+                .collect();
+            Ok(DocIdMapping(ids))
+        };
+        let mapping = segment_reader.extension(&compute_mapping).unwrap();
+        assert_eq!(mapping.0, vec![10u64, 20u64]);
+        // the extension is computed once, and shared by the clones of the reader.
+        let cloned_reader = segment_reader.clone();
+        let cached_mapping = cloned_reader
+            .extension::<DocIdMapping, _>(|_| panic!("Should not be recomputed"))
+            .unwrap();
+        assert!(Arc::ptr_eq(&mapping, &cached_mapping));
+        assert!(Arc::ptr_eq(
+            &mapping,
+            &cloned_reader.get_extension::<DocIdMapping>().unwrap()
+        ));
+        // extensions of other types are independent.
+        assert!(segment_reader.get_extension::<String>().is_none());
+    }
+
+    // Goes through the readers of the segment that are only checked
+    // when the segment is opened.
+    fn read_segment(
+        segment_reader: &SegmentReader,
+        text_field: Field,
+        num_field: Field,
+        facet_field: Field,
+    ) -> Result<()> {
+        for &field in &[text_field, num_field, facet_field] {
+            segment_reader.term_dict(field);
+            segment_reader.get_fieldnorms_reader(field);
+            let inverted_index = segment_reader.inverted_index(field);
+            let mut postings_stream = inverted_index.postings_stream(IndexRecordOption::WithFreqs);
+            while postings_stream.advance()? {
+                let postings = postings_stream.postings();
+                while postings.advance() {}
+            }
+        }
+        segment_reader.fast_field_reader::<u64>(num_field)?;
+        segment_reader.facet_reader(facet_field)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_truncated_segment() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let num_field = schema_builder.add_u64_field("num", INT_INDEXED | FAST);
+        let facet_field = schema_builder.add_facet_field("facet");
+        let mut index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for i in 0..300u64 {
+                index_writer.add_document(doc!(
+                    text_field=>"a b",
+                    num_field=>i % 7,
+                    facet_field=>Facet::from("/a/b")
+                ));
+            }
+            index_writer.commit().unwrap();
+        }
+        let segment = index.searchable_segments().unwrap().remove(0);
+        let components = [
+            SegmentComponent::TERMS,
+            SegmentComponent::FASTFIELDS,
+            SegmentComponent::FIELDNORMS,
+        ];
+        for &component in &components {
+            let path = segment.relative_path(component);
+            let data = index.directory().atomic_read(&path).unwrap();
+            let mut lens: Vec<usize> = (0..16).map(|i| i * data.len() / 16).collect();
+            lens.extend_from_slice(&[1, data.len() - 4, data.len() - 1]);
+            for &len in &lens {
+                index
+                    .directory_mut()
+                    .atomic_write(&path, &data[..len])
+                    .unwrap();
+                // a truncated file may still happen to be well-formed,
+                // but reading the segment must never panic then.
+                if let Ok(segment_reader) = SegmentReader::open(&segment) {
+                    assert!(len > 0);
+                    let _ = read_segment(&segment_reader, text_field, num_field, facet_field);
+                }
+            }
+            index.directory_mut().atomic_write(&path, &data).unwrap();
+        }
+        let segment_reader = SegmentReader::open(&segment).unwrap();
+        read_segment(&segment_reader, text_field, num_field, facet_field).unwrap();
+    }
+
+    #[test]
+    fn test_corrupted_postings() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let mut index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for _ in 0..300 {
+                index_writer.add_document(doc!(text_field=>"a b"));
+            }
+            index_writer.commit().unwrap();
+        }
+        let segment = index.searchable_segments().unwrap().remove(0);
+        let path = segment.relative_path(SegmentComponent::POSTINGS);
+        let mut data = index.directory().atomic_read(&path).unwrap();
+        // the postings of "a" start the file, with a header
+        // as they have full blocks.
+        data[0] = 255u8;
+        index.directory_mut().atomic_write(&path, &data).unwrap();
+        let segment_reader = SegmentReader::open(&segment).unwrap();
+        let inverted_index = segment_reader.inverted_index(text_field);
+        let term_a = Term::from_field_text(text_field, "a");
+        match inverted_index.read_postings(&term_a, IndexRecordOption::Basic) {
+            Err(io_error) => match Error::from(io_error) {
+                Error(ErrorKind::DataCorruption(_), _) => {}
+                _ => panic!("Expected a DataCorruption error"),
+            },
+            Ok(_) => panic!("Expected a DataCorruption error"),
+        }
+        let term_b = Term::from_field_text(text_field, "b");
+        assert!(
+            inverted_index
+                .read_postings(&term_b, IndexRecordOption::Basic)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_truncated_segment_files() {
+        use std::fs::File;
+        use std::io::{Read, Write};
+        use tempdir::TempDir;
+
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let tempdir = TempDir::new("truncated").unwrap();
+        let segment_meta = {
+            let index = Index::create(tempdir.path(), schema_builder.build()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a b c", num_field=>3u64));
+            index_writer.add_document(doc!(text_field=>"a d", num_field=>7u64));
+            index_writer.commit().unwrap();
+            index.searchable_segment_metas().unwrap()[0].clone()
+        };
+        let components = [
+            SegmentComponent::TERMS,
+            SegmentComponent::STORE,
+            SegmentComponent::FASTFIELDS,
+            SegmentComponent::FIELDNORMS,
+        ];
+        for &component in &components {
+            let path = tempdir.path().join(segment_meta.relative_path(component));
+            let mut data = vec![];
+            File::open(&path).unwrap().read_to_end(&mut data).unwrap();
+            for &len in &[0, 1, data.len() / 2, data.len() - 1] {
+                File::create(&path).unwrap().write_all(&data[..len]).unwrap();
+                let index = Index::open(tempdir.path()).unwrap();
+                let segment = index.searchable_segments().unwrap().remove(0);
+                // a truncated file may still happen to be well-formed,
+                // but opening the segment must never panic.
+                let segment_reader_res = SegmentReader::open(&segment);
+                if len == 0 {
+                    assert!(segment_reader_res.is_err());
+                }
+            }
+            File::create(&path).unwrap().write_all(&data).unwrap();
+        }
+    }
+}
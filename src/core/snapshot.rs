@@ -0,0 +1,243 @@
+use Result;
+use error::ErrorKind;
+use core::IndexMeta;
+use core::META_FILEPATH;
+use core::{ReplicatedFile, ReplicationManifest};
+use directory::{Directory, FileProtection, ManagedDirectory, PackWriter, ReadOnlySource};
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+fn segment_files(metas: &IndexMeta) -> HashSet<PathBuf> {
+    metas
+        .segments
+        .iter()
+        .flat_map(|segment_meta| segment_meta.list_files())
+        .collect()
+}
+
+fn read_metas(directory: &ManagedDirectory) -> Result<(Vec<u8>, IndexMeta)> {
+    let meta_data = directory.atomic_read(&META_FILEPATH)?;
+    let metas = IndexMeta::deserialize(&meta_data, &META_FILEPATH)?;
+    Ok((meta_data, metas))
+}
+
+/// A consistent view of the files of an index at a given commit.
+///
+/// As long as the snapshot is alive, none of its files
+/// can be garbage collected, even if the segments they belong to
+/// are merged or the commit is superseded by a new one.
+///
+/// This makes it possible to backup an index while it is
+/// being written to. See `.copy_to(...)`.
+pub struct IndexSnapshot {
+    directory: ManagedDirectory,
+    meta_data: Vec<u8>,
+    metas: IndexMeta,
+    files: Vec<PathBuf>,
+    _file_protections: Vec<FileProtection>,
+}
+
+impl IndexSnapshot {
+    pub(crate) fn create(directory: &ManagedDirectory) -> Result<IndexSnapshot> {
+        loop {
+            let (meta_data, metas) = read_metas(directory)?;
+            let files = segment_files(&metas);
+            let file_protections: Vec<FileProtection> = files
+                .iter()
+                .map(|path| directory.protect_file_from_delete(path))
+                .collect();
+
+            // The files are protected from now on, but they may have been
+            // garbage collected between our read of the meta file and
+            // their protection.
+            //
+            // If they are all still listed in the meta file, it means
+            // that they have never stopped being living files.
+            let (_, current_metas) = read_metas(directory)?;
+            if !files.is_subset(&segment_files(&current_metas)) {
+                info!("The index changed while taking a snapshot. Retrying.");
+                continue;
+            }
+
+            let mut files: Vec<PathBuf> = files
+                .into_iter()
+                .filter(|path| directory.exists(path))
+                .collect();
+            files.sort();
+            return Ok(IndexSnapshot {
+                directory: directory.clone(),
+                meta_data,
+                metas,
+                files,
+                _file_protections: file_protections,
+            });
+        }
+    }
+
+    /// Returns the metas of the commit captured by the snapshot.
+    pub fn metas(&self) -> &IndexMeta {
+        &self.metas
+    }
+
+    /// Returns the list of segment files of the snapshot.
+    ///
+    /// The `meta.json` file is not part of this list, as
+    /// its content is held in memory by the snapshot.
+    pub fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Returns the content of the `meta.json` file of the snapshot.
+    pub fn meta_data(&self) -> &[u8] {
+        &self.meta_data[..]
+    }
+
+    /// Returns the replication manifest of the snapshot,
+    /// listing its segment files with their size and checksum.
+    pub fn manifest(&self) -> Result<ReplicationManifest> {
+        let mut files = Vec::with_capacity(self.files.len());
+        for path in &self.files {
+            let source = self.directory.open_read_with_footer(path)?;
+            files.push(ReplicatedFile::create(path.clone(), source));
+        }
+        Ok(ReplicationManifest::create(&self.metas, files))
+    }
+
+    /// Reads a segment file of the snapshot, checksum footer included,
+    /// to ship it to a replica.
+    ///
+    /// # Errors
+    /// If the file is not part of the snapshot, returns `ErrorKind::InvalidArgument`.
+    pub fn read_file(&self, path: &Path) -> Result<ReadOnlySource> {
+        if self.files.binary_search_by(|file| file.as_path().cmp(path)).is_err() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The file {:?} is not part of the snapshot.",
+                path
+            )));
+        }
+        Ok(self.directory.open_read_with_footer(path)?)
+    }
+
+    /// Copies the snapshot into another directory.
+    ///
+    /// The `meta.json` file is written last, so that
+    /// the target directory only contains a valid index
+    /// once the copy is complete.
+    ///
+    /// Files are copied as is, checksum footer included.
+    /// The target should therefore not be a managed directory.
+    pub fn copy_to(&self, target: &mut Directory) -> Result<()> {
+        for path in &self.files {
+            // Files are copied with their checksum footer.
+            let source_data = self.directory.open_read_with_footer(path)?;
+            let mut target_write = target.open_write(path)?;
+            target_write.write_all(&*source_data)?;
+            target_write.flush()?;
+        }
+        target.atomic_write(&META_FILEPATH, &self.meta_data[..])?;
+        Ok(())
+    }
+
+    /// Packs the snapshot into a single file.
+    ///
+    /// The resulting file contains the segment files and
+    /// the `meta.json` file, and can be opened with `Index::open_packed(...)`,
+    /// or extracted with `PackedDirectory::unpack_to(...)`.
+    pub fn pack_to<W: Write>(&self, output: W) -> Result<W> {
+        let mut pack_writer = PackWriter::new(output)?;
+        for path in &self.files {
+            // Files are packed with their checksum footer.
+            let source_data = self.directory.open_read_with_footer(path)?;
+            pack_writer.add_file(path, &*source_data)?;
+        }
+        pack_writer.add_file(&META_FILEPATH, &self.meta_data[..])?;
+        Ok(pack_writer.finish()?)
+    }
+}
+
+impl fmt::Debug for IndexSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "IndexSnapshot(opstamp={}, {:?})", self.metas.opstamp, self.files)
+    }
+}
+
+#[cfg(all(test, feature = "mmap"))]
+mod tests {
+
+    use Index;
+    use Term;
+    use directory::{MmapDirectory, PackedDirectory};
+    use indexer::merge_policy::tests::MergeWheneverPossible;
+    use schema::{SchemaBuilder, TEXT};
+    use std::fs::File;
+    use tempdir::TempDir;
+
+    #[test]
+    fn test_snapshot_survives_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+
+        let snapshot = index.snapshot().unwrap();
+        assert_eq!(snapshot.metas().segments.len(), 2);
+        assert_eq!(snapshot.metas().opstamp, 3);
+
+        index_writer.set_merge_policy(box MergeWheneverPossible);
+        index_writer.add_document(doc!(text_field => "c"));
+        index_writer.commit().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().segment_readers().len(), 1);
+
+        let tempdir = TempDir::new("snapshot").unwrap();
+        {
+            let mut target_directory = MmapDirectory::open(tempdir.path()).unwrap();
+            snapshot.copy_to(&mut target_directory).unwrap();
+        }
+        let backup = Index::open(tempdir.path()).unwrap();
+        let searcher = backup.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "a")), 1);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "c")), 0);
+    }
+
+    #[test]
+    fn test_snapshot_pack() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+
+        let tempdir = TempDir::new("pack").unwrap();
+        let pack_path = tempdir.path().join("index.pack");
+        {
+            let pack_file = File::create(&pack_path).unwrap();
+            index.snapshot().unwrap().pack_to(pack_file).unwrap();
+        }
+        let packed_index = Index::open_packed(&pack_path).unwrap();
+        assert!(packed_index.is_read_only());
+        let searcher = packed_index.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "a")), 1);
+        assert!(packed_index.validate_checksums().unwrap().is_empty());
+
+        let unpack_dir = TempDir::new("unpack").unwrap();
+        {
+            let packed_directory = PackedDirectory::open(&pack_path).unwrap();
+            let mut target_directory = MmapDirectory::open(unpack_dir.path()).unwrap();
+            packed_directory.unpack_to(&mut target_directory).unwrap();
+        }
+        let unpacked_index = Index::open(unpack_dir.path()).unwrap();
+        assert_eq!(unpacked_index.searcher().num_docs(), 2);
+    }
+}
@@ -0,0 +1,207 @@
+use Result;
+use common::{CompositeFile, HasLen};
+use core::{IndexMeta, SegmentComponent, SegmentId, SegmentMeta};
+use directory::Directory;
+use directory::error::OpenReadError;
+use schema::{Field, Schema};
+
+/// Breakdown of the disk space used by the searchable segments of an `Index`.
+///
+/// It is returned by `Index::space_usage()`, and can be serialized.
+#[derive(Clone, Debug, Serialize)]
+pub struct SpaceUsage {
+    /// Space used by each of the searchable segments.
+    pub segments: Vec<SegmentSpaceUsage>,
+}
+
+impl SpaceUsage {
+    /// Returns the overall number of bytes used by the segments.
+    pub fn num_bytes(&self) -> usize {
+        self.segments.iter().map(SegmentSpaceUsage::num_bytes).sum()
+    }
+
+    /// Returns the number of bytes used by a component, over all of the segments.
+    pub fn component_num_bytes(&self, component: SegmentComponent) -> usize {
+        self.segments
+            .iter()
+            .flat_map(|segment| segment.components.iter())
+            .filter(|component_usage| component_usage.component == component)
+            .map(|component_usage| component_usage.num_bytes)
+            .sum()
+    }
+
+    /// Returns the number of bytes used by a field, over all of the segments
+    /// and all of the components stored per field.
+    pub fn field_num_bytes(&self, field: Field) -> usize {
+        self.segments
+            .iter()
+            .flat_map(|segment| segment.components.iter())
+            .flat_map(|component_usage| component_usage.fields.iter())
+            .filter(|field_usage| field_usage.field == field)
+            .map(|field_usage| field_usage.num_bytes)
+            .sum()
+    }
+}
+
+/// Space used by a segment. See `SpaceUsage`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentSpaceUsage {
+    /// Id of the segment.
+    pub segment_id: SegmentId,
+    /// Number of documents, deleted documents included.
+    pub max_doc: u32,
+    /// Space used by each of the files of the segment.
+    pub components: Vec<ComponentSpaceUsage>,
+}
+
+impl SegmentSpaceUsage {
+    /// Returns the overall number of bytes used by the segment.
+    pub fn num_bytes(&self) -> usize {
+        self.components
+            .iter()
+            .map(|component_usage| component_usage.num_bytes)
+            .sum()
+    }
+}
+
+/// Space used by a file of a segment. See `SpaceUsage`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ComponentSpaceUsage {
+    /// Component stored in the file.
+    pub component: SegmentComponent,
+    /// Size of the file, in bytes.
+    pub num_bytes: usize,
+    /// Space used by each field, sorted by field.
+    ///
+    /// It is empty for the components that are not stored per field,
+    /// namely the doc store, the delete bitset and the fast field updates.
+    /// The sum over the fields is slightly lower than the size of the file,
+    /// which also contains the offsets of the fields.
+    pub fields: Vec<FieldSpaceUsage>,
+}
+
+/// Space used by a field in a component of a segment. See `SpaceUsage`.
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldSpaceUsage {
+    /// Field.
+    pub field: Field,
+    /// Name of the field.
+    pub field_name: String,
+    /// Number of bytes used by the field.
+    pub num_bytes: usize,
+}
+
+fn is_stored_per_field(component: SegmentComponent) -> bool {
+    match component {
+        SegmentComponent::POSTINGS
+        | SegmentComponent::POSITIONS
+        | SegmentComponent::TERMS
+        | SegmentComponent::FASTFIELDS
+        | SegmentComponent::FIELDNORMS
+        | SegmentComponent::COMPLETIONS
+        | SegmentComponent::TERMVECTORS => true,
+        SegmentComponent::STORE
+        | SegmentComponent::DELETE
+        | SegmentComponent::FASTFIELDUPDATES => false,
+    }
+}
+
+fn segment_space_usage(
+    directory: &Directory,
+    schema: &Schema,
+    segment_meta: &SegmentMeta,
+) -> Result<SegmentSpaceUsage> {
+    let mut components = vec![];
+    for &component in SegmentComponent::iterator() {
+        let path = segment_meta.relative_path(component);
+        let source = match directory.open_read(&path) {
+            Ok(source) => source,
+            // optional components, such as the delete bitset,
+            // may not exist.
+            Err(OpenReadError::FileDoesNotExist(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+        let mut fields = vec![];
+        if is_stored_per_field(component) {
+            let composite_file = CompositeFile::open(&source)?;
+            for (field, num_bytes) in composite_file.num_bytes_per_field() {
+                fields.push(FieldSpaceUsage {
+                    field,
+                    field_name: schema.get_field_name(field).to_string(),
+                    num_bytes,
+                });
+            }
+            fields.sort_by_key(|field_usage| field_usage.field);
+        }
+        components.push(ComponentSpaceUsage {
+            component,
+            num_bytes: source.len(),
+            fields,
+        });
+    }
+    Ok(SegmentSpaceUsage {
+        segment_id: segment_meta.id(),
+        max_doc: segment_meta.max_doc(),
+        components,
+    })
+}
+
+pub(crate) fn space_usage(directory: &Directory, metas: &IndexMeta) -> Result<SpaceUsage> {
+    let segments = metas
+        .segments
+        .iter()
+        .map(|segment_meta| segment_space_usage(directory, &metas.schema, segment_meta))
+        .collect::<Result<_>>()?;
+    Ok(SpaceUsage { segments })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use core::SegmentComponent;
+    use schema::{SchemaBuilder, FAST, INT_INDEXED, STORED, TEXT};
+    use serde_json;
+
+    #[test]
+    fn test_space_usage() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        assert_eq!(index.space_usage().unwrap().num_bytes(), 0);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for id in 0u64..100u64 {
+            index_writer.add_document(doc!(id_field=>id, text_field=>"hello happy tax payer"));
+        }
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id_field, 3u64));
+        index_writer.commit().unwrap();
+
+        let space_usage = index.space_usage().unwrap();
+        assert_eq!(space_usage.segments.len(), 1);
+        let segment_usage = &space_usage.segments[0];
+        assert_eq!(segment_usage.max_doc, 100);
+        assert_eq!(space_usage.num_bytes(), segment_usage.num_bytes());
+        assert!(space_usage.component_num_bytes(SegmentComponent::STORE) > 0);
+        assert!(space_usage.component_num_bytes(SegmentComponent::DELETE) > 0);
+        for component_usage in &segment_usage.components {
+            let fields_num_bytes: usize = component_usage
+                .fields
+                .iter()
+                .map(|field_usage| field_usage.num_bytes)
+                .sum();
+            assert!(fields_num_bytes <= component_usage.num_bytes);
+        }
+        let fast_fields_usage = segment_usage
+            .components
+            .iter()
+            .find(|component_usage| component_usage.component == SegmentComponent::FASTFIELDS)
+            .unwrap();
+        assert_eq!(fast_fields_usage.fields.len(), 1);
+        assert_eq!(fast_fields_usage.fields[0].field_name, "id");
+        assert!(space_usage.field_num_bytes(text_field) > 0);
+        assert!(serde_json::to_string(&space_usage).is_ok());
+    }
+}
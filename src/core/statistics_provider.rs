@@ -0,0 +1,149 @@
+use Term;
+use core::Searcher;
+use std::collections::{BTreeSet, HashMap};
+
+/// Provides the collection statistics used to score documents.
+///
+/// By default, a `Searcher` scores documents with the statistics
+/// of its own segments. In a sharded deployment, each shard only sees
+/// a part of the collection: scores are only consistent across shards if
+/// they are computed with global statistics, gathered from all of the shards
+/// before running the query. See `Searcher::with_statistics_provider`.
+pub trait StatisticsProvider: Send + Sync {
+    /// Returns the overall number of documents, deleted documents excluded.
+    fn total_num_docs(&self) -> u64;
+
+    /// Returns the number of documents containing `term`.
+    fn doc_freq(&self, term: &Term) -> u64;
+}
+
+impl StatisticsProvider for Searcher {
+    fn total_num_docs(&self) -> u64 {
+        u64::from(self.num_docs())
+    }
+
+    fn doc_freq(&self, term: &Term) -> u64 {
+        u64::from(Searcher::doc_freq(self, term))
+    }
+}
+
+/// Statistics of a set of terms, to be exchanged between shards.
+///
+/// Each shard computes the statistics of the terms of a query
+/// with `Searcher::term_statistics(&query.extract_terms())`.
+/// Once merged, the statistics of all of the shards can be used
+/// to score the query on each shard, through `Searcher::with_statistics_provider`.
+///
+/// The document frequency of a term that is not part of the statistics is 0.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermStatistics {
+    total_num_docs: u64,
+    doc_freqs: HashMap<Term, u64>,
+}
+
+impl TermStatistics {
+    /// Creates statistics, from the overall number of documents
+    /// and the document frequencies of the terms.
+    pub fn new(total_num_docs: u64, doc_freqs: HashMap<Term, u64>) -> TermStatistics {
+        TermStatistics {
+            total_num_docs,
+            doc_freqs,
+        }
+    }
+
+    pub(crate) fn compute(
+        provider: &StatisticsProvider,
+        terms: &BTreeSet<Term>,
+    ) -> TermStatistics {
+        TermStatistics {
+            total_num_docs: provider.total_num_docs(),
+            doc_freqs: terms
+                .iter()
+                .map(|term| (term.clone(), provider.doc_freq(term)))
+                .collect(),
+        }
+    }
+
+    /// Adds the statistics of another part of the collection.
+    pub fn merge(&mut self, other: &TermStatistics) {
+        self.total_num_docs += other.total_num_docs;
+        for (term, &doc_freq) in &other.doc_freqs {
+            *self.doc_freqs.entry(term.clone()).or_insert(0u64) += doc_freq;
+        }
+    }
+
+    /// Returns the document frequencies of the terms.
+    pub fn doc_freqs(&self) -> &HashMap<Term, u64> {
+        &self.doc_freqs
+    }
+}
+
+impl StatisticsProvider for TermStatistics {
+    fn total_num_docs(&self) -> u64 {
+        self.total_num_docs
+    }
+
+    fn doc_freq(&self, term: &Term) -> u64 {
+        self.doc_freqs.get(term).cloned().unwrap_or(0u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use core::Searcher;
+    use collector::TopCollector;
+    use query::{Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, TEXT};
+    use std::sync::Arc;
+    use super::TermStatistics;
+
+    fn top_score(searcher: &Searcher, query: &Query) -> f32 {
+        let mut top_collector = TopCollector::with_limit(1);
+        searcher.search(query, &mut top_collector).unwrap();
+        top_collector.score_docs()[0].0
+    }
+
+    #[test]
+    fn test_statistics_provider() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let shards: Vec<Index> = vec![vec!["b", "a", "c"], vec!["b", "a b"]]
+            .into_iter()
+            .map(|texts| {
+                let index = Index::create_in_ram(schema.clone());
+                let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+                for text in texts {
+                    index_writer.add_document(doc!(text_field=>text));
+                }
+                index_writer.commit().unwrap();
+                index.load_searchers().unwrap();
+                index
+            })
+            .collect();
+        let term = Term::from_field_text(text_field, "b");
+        let query = TermQuery::new(term.clone(), IndexRecordOption::WithFreqs);
+        let terms = query.extract_terms();
+        assert_eq!(terms.len(), 1);
+
+        let mut statistics = TermStatistics::default();
+        for shard in &shards {
+            statistics.merge(&shard.searcher().term_statistics(&terms));
+        }
+        assert_eq!(statistics.doc_freqs().get(&term), Some(&3u64));
+        let statistics = Arc::new(statistics);
+
+        let all_shards = Searcher::for_indexes(&[&shards[0], &shards[1]]).unwrap();
+        let expected_score = top_score(&all_shards, &query);
+        for shard in &shards {
+            let searcher = shard.searcher();
+            assert!(top_score(&searcher, &query) != expected_score);
+            let global_searcher = searcher.with_statistics_provider(statistics.clone());
+            assert_eq!(global_searcher.statistics_provider().total_num_docs(), 5);
+            assert_eq!(top_score(&global_searcher, &query), expected_score);
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use Result;
+use core::{Searcher, SegmentReader};
+
+/// A `Warmer` prepares the segments of a new generation
+/// of searchers, before they are published by an `IndexReader`.
+///
+/// Reloading opens new `SegmentReader`s, whose fast fields,
+/// caches, and approximate nearest neighbor graphs are loaded lazily.
+/// Warming them, for instance by loading the fast fields used for sorting,
+/// or running the filters of a `CachedFilterQuery`, spares
+/// this latency to the first queries after a reload.
+///
+/// See `IndexReaderBuilder::warmer`.
+pub trait Warmer: Send + Sync {
+    /// Warms one of the segments of `searcher`.
+    ///
+    /// `searcher` is a searcher of the new generation, sharing
+    /// its `FilterCache`, if any, with the searchers that will be published.
+    ///
+    /// If warming fails, the reload fails, and the
+    /// previous generation of searchers remains in use.
+    fn warm(&self, searcher: &Searcher, segment_reader: &SegmentReader) -> Result<()>;
+}
@@ -16,7 +16,14 @@ impl<'a, T: BinarySerializable> Iterator for Layer<'a, T> {
 
     fn next(&mut self) -> Option<(u64, T)> {
         if let Some(cur_id) = self.next_id {
-            let cur_val = T::deserialize(&mut self.cursor).unwrap();
+            // a value that cannot be deserialized ends the layer.
+            let cur_val = match T::deserialize(&mut self.cursor) {
+                Ok(cur_val) => cur_val,
+                Err(_) => {
+                    self.next_id = None;
+                    return None;
+                }
+            };
             self.next_id = VInt::deserialize_u64(&mut self.cursor).ok();
             Some((cur_id, cur_val))
         } else {
@@ -49,7 +56,7 @@ impl<'a, T: BinarySerializable> Layer<'a, T> {
     }
 
     fn seek_offset(&mut self, offset: usize) {
-        self.cursor = &self.data[offset..];
+        self.cursor = self.data.get(offset..).unwrap_or(&EMPTY);
         self.next_id = VInt::deserialize_u64(&mut self.cursor).ok();
     }
 
@@ -107,12 +114,24 @@ impl<'a, T: BinarySerializable> SkipList<'a, T> {
 }
 
 impl<'a, T: BinarySerializable> From<&'a [u8]> for SkipList<'a, T> {
+    /// Opens a skip list.
+    ///
+    /// Malformed data does not cause a panic: the layers
+    /// that cannot be read are considered empty.
     fn from(mut data: &'a [u8]) -> SkipList<'a, T> {
-        let offsets: Vec<u64> = Vec::<VInt>::deserialize(&mut data)
-            .unwrap()
+        let mut offsets: Vec<u64> = Vec::<VInt>::deserialize(&mut data)
+            .unwrap_or_default()
             .into_iter()
             .map(|el| el.0)
             .collect();
+        let is_valid = offsets
+            .iter()
+            .zip(offsets.iter().skip(1))
+            .all(|(start, stop)| start <= stop)
+            && offsets.last().map(|&end| end as usize <= data.len()).unwrap_or(true);
+        if !is_valid {
+            offsets.clear();
+        }
         let num_layers = offsets.len();
         let layers_data: &[u8] = data;
         let data_layer: Layer<'a, T> = if num_layers == 0 {
@@ -0,0 +1,89 @@
+use directory::{Directory, ReadOnlySource};
+use directory::error::OpenReadError;
+use futures_cpupool::{CpuFuture, CpuPool};
+use std::fmt;
+use std::path::Path;
+
+/// Wraps a `Directory` to expose its read operations as futures.
+///
+/// `Directory` implementations are blocking. The reads are
+/// run on a dedicated thread pool, so that waiting for a remote
+/// directory or a page fault does not block an event loop.
+pub struct AsyncDirectory {
+    directory: Box<Directory>,
+    pool: CpuPool,
+}
+
+impl AsyncDirectory {
+    /// Wraps a directory, running its reads on the given thread pool.
+    pub fn new<Dir: Directory>(directory: Dir, pool: CpuPool) -> AsyncDirectory {
+        AsyncDirectory {
+            directory: box directory,
+            pool,
+        }
+    }
+
+    /// Opens a virtual file for read. See `Directory::open_read`.
+    pub fn open_read(&self, path: &Path) -> CpuFuture<ReadOnlySource, OpenReadError> {
+        let directory = self.directory.box_clone();
+        let path = path.to_owned();
+        self.pool.spawn_fn(move || directory.open_read(&path))
+    }
+
+    /// Reads the full content of a file. See `Directory::atomic_read`.
+    pub fn atomic_read(&self, path: &Path) -> CpuFuture<Vec<u8>, OpenReadError> {
+        let directory = self.directory.box_clone();
+        let path = path.to_owned();
+        self.pool.spawn_fn(move || directory.atomic_read(&path))
+    }
+
+    /// Returns true iff the file exists. See `Directory::exists`.
+    pub fn exists(&self, path: &Path) -> CpuFuture<bool, ()> {
+        let directory = self.directory.box_clone();
+        let path = path.to_owned();
+        self.pool.spawn_fn(move || Ok(directory.exists(&path)))
+    }
+}
+
+impl Clone for AsyncDirectory {
+    fn clone(&self) -> AsyncDirectory {
+        AsyncDirectory {
+            directory: self.directory.box_clone(),
+            pool: self.pool.clone(),
+        }
+    }
+}
+
+impl fmt::Debug for AsyncDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "AsyncDirectory({:?})", self.directory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::AsyncDirectory;
+    use directory::{Directory, RAMDirectory};
+    use futures::Future;
+    use futures_cpupool::CpuPool;
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_async_directory() {
+        let mut directory = RAMDirectory::create();
+        let path = PathBuf::from("test");
+        {
+            let mut w = directory.open_write(&path).unwrap();
+            w.write_all(&[1, 2]).unwrap();
+            w.flush().unwrap();
+        }
+        let async_directory = AsyncDirectory::new(directory, CpuPool::new(1));
+        let source = async_directory.open_read(&path).wait().unwrap();
+        assert_eq!(source.as_slice(), &[1, 2]);
+        assert_eq!(async_directory.atomic_read(&path).wait().unwrap(), vec![1, 2]);
+        assert!(async_directory.exists(&path).wait().unwrap());
+        assert!(async_directory.open_read(&PathBuf::from("missing")).wait().is_err());
+    }
+}
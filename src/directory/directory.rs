@@ -3,6 +3,7 @@ use std::fmt;
 use std::path::Path;
 use directory::error::{DeleteError, OpenReadError, OpenWriteError};
 use directory::{ReadOnlySource, WritePtr};
+use directory::watch::{watch_meta_by_polling, WatchCallback, WatchHandle};
 use std::result;
 use std::io;
 use std::marker::Sync;
@@ -76,4 +77,16 @@ pub trait Directory: fmt::Debug + Send + Sync + 'static {
 
     /// Clones the directory and boxes the clone
     fn box_clone(&self) -> Box<Directory>;
+
+    /// Registers a callback, called whenever the content
+    /// of the `meta.json` file changes, for instance
+    /// after a commit from another process.
+    ///
+    /// The callback is called from a different thread, until
+    /// the returned `WatchHandle` is dropped.
+    ///
+    /// The default implementation periodically polls the meta file.
+    fn watch(&self, callback: WatchCallback) -> io::Result<WatchHandle> {
+        watch_meta_by_polling(self.box_clone(), callback)
+    }
 }
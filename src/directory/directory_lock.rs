@@ -0,0 +1,351 @@
+use Result;
+use Directory;
+use error::{Error, ErrorKind};
+use directory::error::{DeleteError, OpenReadError, OpenWriteError};
+use core::LOCKFILE_FILEPATH;
+use serde_json;
+#[cfg(target_os = "linux")]
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::io::Read;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process;
+use uuid::Uuid;
+
+lazy_static! {
+    /// File created exclusively by the process taking over a stale lock,
+    /// so that two processes cannot both take it over.
+    static ref TAKEOVER_FILEPATH: PathBuf = PathBuf::from(".tantivy-indexer.lock.takeover");
+}
+
+/// Information about the owner of the lock.
+///
+/// It is serialized in the lockfile, and makes it possible
+/// to detect stale lockfiles.
+#[derive(Debug, Serialize, Deserialize)]
+struct LockOwner {
+    pid: u32,
+    hostname: Option<String>,
+    // identifies the lock itself, as two locks may share a same pid.
+    #[serde(default)]
+    lock_id: Option<String>,
+}
+
+/// Whether the owner of a lock is still alive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Liveness {
+    Alive,
+    Dead,
+    Unknown,
+}
+
+impl LockOwner {
+    fn current() -> LockOwner {
+        LockOwner {
+            pid: process::id(),
+            hostname: local_hostname(),
+            lock_id: Some(Uuid::new_v4().simple().to_string()),
+        }
+    }
+
+    /// Liveness can only be checked if the lock was taken on the same host,
+    /// and on targets where the processes can be listed.
+    fn liveness(&self) -> Liveness {
+        if self.hostname.is_none() || self.hostname != local_hostname() {
+            return Liveness::Unknown;
+        }
+        if self.pid == process::id() {
+            return Liveness::Alive;
+        }
+        process_liveness(self.pid)
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn local_hostname() -> Option<String> {
+    let mut hostname = String::new();
+    fs::File::open("/proc/sys/kernel/hostname")
+        .and_then(|mut file| file.read_to_string(&mut hostname))
+        .ok()
+        .map(|_| hostname.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn local_hostname() -> Option<String> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn process_liveness(pid: u32) -> Liveness {
+    if fs::metadata(format!("/proc/{}", pid)).is_ok() {
+        Liveness::Alive
+    } else {
+        Liveness::Dead
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_liveness(_pid: u32) -> Liveness {
+    Liveness::Unknown
+}
+
+/// Converts the error of an exclusive file creation into an `IndexLocked` error
+/// if the file already exists.
+fn lock_error(open_write_error: OpenWriteError, reason: &str) -> Error {
+    match open_write_error {
+        OpenWriteError::FileAlreadyExists(_) => ErrorKind::IndexLocked(reason.to_string()).into(),
+        e => e.into(),
+    }
+}
+
+/// The directory lock is a mechanism used to
+/// prevent the creation of two [`IndexWriter`](struct.IndexWriter.html)
+///
+/// Only one lock can exist at a time for a given directory.
+/// The lock is release automatically on `Drop`.
+///
+/// The lockfile contains the pid and the hostname of the process
+/// owning the lock. If the lockfile was left behind by a process
+/// that is known to have died on the same host (for instance after
+/// a crash), it is detected as stale and the lock is taken over.
+/// Liveness is only known on Linux: on other targets, a lockfile
+/// is always considered held.
+///
+/// Taking over a stale lock first requires creating a takeover file
+/// exclusively, so that two processes detecting the same stale lock
+/// cannot both acquire it.
+pub struct DirectoryLock {
+    directory: Box<Directory>,
+}
+
+impl DirectoryLock {
+    /// Acquires the lock on the directory.
+    ///
+    /// # Errors
+    /// If the lock is held by a living process, returns `ErrorKind::IndexLocked`.
+    pub fn lock(mut directory: Box<Directory>) -> Result<DirectoryLock> {
+        // The owner of an existing lockfile is read before any attempt
+        // to create it, as some directories truncate the file when
+        // its exclusive creation fails.
+        if !directory.exists(&LOCKFILE_FILEPATH) {
+            DirectoryLock::create_lockfile(directory.as_mut())
+                .map_err(|e| lock_error(e, "the lock was taken concurrently"))?;
+            return Ok(DirectoryLock { directory });
+        }
+        let lock_data_opt = match directory.atomic_read(&LOCKFILE_FILEPATH) {
+            Ok(data) => Some(data),
+            Err(OpenReadError::FileDoesNotExist(_)) => None,
+            Err(open_read_error) => {
+                return Err(open_read_error.into());
+            }
+        };
+        // the lockfile may have been created, but its content
+        // not been written yet, or it may have been removed since.
+        let lock_owner_opt: Option<LockOwner> = lock_data_opt
+            .as_ref()
+            .and_then(|data| serde_json::from_slice(data).ok());
+        let is_stale = lock_owner_opt
+            .as_ref()
+            .map(|lock_owner| lock_owner.liveness() == Liveness::Dead)
+            .unwrap_or(false);
+        match lock_data_opt {
+            Some(ref stale_lock_data) if is_stale => {
+                warn!("Taking over stale lockfile owned by {:?}", lock_owner_opt);
+                DirectoryLock::take_over(directory.as_mut(), stale_lock_data)?;
+                Ok(DirectoryLock { directory })
+            }
+            _ => bail!(ErrorKind::IndexLocked(format!(
+                "lockfile {:?} held by {:?}",
+                *LOCKFILE_FILEPATH, lock_owner_opt
+            ))),
+        }
+    }
+
+    /// Replaces the stale lockfile, whose content is `stale_lock_data`,
+    /// by a lockfile owned by the current process.
+    ///
+    /// Only the process that creates the takeover file may replace the
+    /// lockfile. It checks that the lockfile was not replaced in the meantime
+    /// by another process, which would have completed its own takeover.
+    fn take_over(directory: &mut Directory, stale_lock_data: &[u8]) -> Result<()> {
+        directory
+            .open_write(&TAKEOVER_FILEPATH)
+            .map_err(|e| {
+                let reason = format!(
+                    "the lock is being taken over concurrently, \
+                     or {:?} was left behind by a crashed process",
+                    *TAKEOVER_FILEPATH
+                );
+                lock_error(e, &reason)
+            })?;
+        let result = DirectoryLock::replace_stale_lockfile(directory, stale_lock_data);
+        if let Err(e) = directory.delete(&TAKEOVER_FILEPATH) {
+            error!("Failed to remove the lock takeover file. {:?}", e);
+        }
+        result
+    }
+
+    fn replace_stale_lockfile(directory: &mut Directory, stale_lock_data: &[u8]) -> Result<()> {
+        let is_still_stale = match directory.atomic_read(&LOCKFILE_FILEPATH) {
+            Ok(data) => data == stale_lock_data,
+            Err(OpenReadError::FileDoesNotExist(_)) => false,
+            Err(open_read_error) => {
+                return Err(open_read_error.into());
+            }
+        };
+        if !is_still_stale {
+            bail!(ErrorKind::IndexLocked(
+                "the lock was taken concurrently".to_string()
+            ));
+        }
+        match directory.delete(&LOCKFILE_FILEPATH) {
+            Ok(()) | Err(DeleteError::FileDoesNotExist(_)) => {}
+            Err(delete_error) => {
+                bail!(ErrorKind::IndexLocked(format!(
+                    "failed to remove stale lockfile: {}",
+                    delete_error
+                )));
+            }
+        }
+        DirectoryLock::create_lockfile(directory)
+            .map_err(|e| lock_error(e, "the lock was taken concurrently"))
+    }
+
+    /// Creates the lockfile exclusively, and then writes its owner.
+    ///
+    /// The owner is written with `atomic_write` rather than through the writer
+    /// returned by `open_write`, so that it is read back as is by `atomic_read`:
+    /// the `ManagedDirectory` appends a checksum footer to the files
+    /// written via `open_write`.
+    fn create_lockfile(directory: &mut Directory) -> ::std::result::Result<(), OpenWriteError> {
+        directory
+            .open_write(&*LOCKFILE_FILEPATH)?
+            .flush()
+            .map_err(|e| OpenWriteError::IOError(e.into()))?;
+        let lock_owner_json = serde_json::to_vec(&LockOwner::current())
+            .expect("Serializing the lock owner should never fail");
+        directory
+            .atomic_write(&*LOCKFILE_FILEPATH, &lock_owner_json)
+            .map_err(|e| OpenWriteError::IOError(e.into()))
+    }
+}
+
+impl Drop for DirectoryLock {
+    fn drop(&mut self) {
+        if let Err(e) = self.directory.delete(&*LOCKFILE_FILEPATH) {
+            error!("Failed to remove the lock file. {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use core::LOCKFILE_FILEPATH;
+    use directory::{Directory, RAMDirectory};
+    use error::*;
+    use schema::SchemaBuilder;
+    use serde_json;
+    use std::process;
+    use super::{DirectoryLock, LockOwner, TAKEOVER_FILEPATH};
+
+    fn lock_owner(directory: &Directory) -> LockOwner {
+        let lock_data = directory.atomic_read(&*LOCKFILE_FILEPATH).unwrap();
+        serde_json::from_slice(&lock_data).unwrap()
+    }
+
+    #[test]
+    fn test_lock_held_by_living_process() {
+        let index = Index::create_in_ram(SchemaBuilder::default().build());
+        let _index_writer = index.writer(40_000_000).unwrap();
+        let lock_data = index.directory().atomic_read(&*LOCKFILE_FILEPATH).unwrap();
+        let owner = lock_owner(index.directory());
+        assert_eq!(owner.pid, process::id());
+        assert_eq!(owner.hostname, super::local_hostname());
+        match index.writer(40_000_000) {
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected IndexLocked error"),
+        }
+        // the failed attempt left the lockfile untouched.
+        assert_eq!(
+            index.directory().atomic_read(&*LOCKFILE_FILEPATH).unwrap(),
+            lock_data
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let mut index = Index::create_in_ram(SchemaBuilder::default().build());
+        let hostname = super::local_hostname().unwrap();
+        let stale_lock = format!(
+            r#"{{"pid":{},"hostname":"{}"}}"#,
+            u32::max_value(),
+            hostname
+        );
+        index
+            .directory_mut()
+            .atomic_write(&*LOCKFILE_FILEPATH, stale_lock.as_bytes())
+            .unwrap();
+        {
+            let _index_writer = index.writer(40_000_000).unwrap();
+            let owner = lock_owner(index.directory());
+            assert_eq!(owner.pid, process::id());
+            assert!(owner.lock_id.is_some());
+            assert!(!index.directory().exists(&*TAKEOVER_FILEPATH));
+        }
+        assert!(!index.directory().exists(&*LOCKFILE_FILEPATH));
+    }
+
+    #[test]
+    fn test_concurrent_takeovers() {
+        let mut directory = RAMDirectory::create();
+        let stale_lock = br#"{"pid":4294967295,"hostname":"localhost"}"#;
+        directory.atomic_write(&*LOCKFILE_FILEPATH, stale_lock).unwrap();
+
+        // a takeover in progress.
+        directory.open_write(&*TAKEOVER_FILEPATH).unwrap();
+        match DirectoryLock::take_over(&mut directory, stale_lock) {
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected IndexLocked error"),
+        }
+        directory.delete(&*TAKEOVER_FILEPATH).unwrap();
+
+        // both processes read the same stale lock,
+        // but only the first one takes it over.
+        DirectoryLock::take_over(&mut directory, stale_lock).unwrap();
+        match DirectoryLock::take_over(&mut directory, stale_lock) {
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected IndexLocked error"),
+        }
+        assert!(directory.atomic_read(&*LOCKFILE_FILEPATH).unwrap() != &stale_lock[..]);
+        assert!(!directory.exists(&*TAKEOVER_FILEPATH));
+    }
+
+    #[test]
+    fn test_lock_from_other_host_is_kept() {
+        let mut index = Index::create_in_ram(SchemaBuilder::default().build());
+        let foreign_lock = format!(
+            r#"{{"pid":{},"hostname":"some-other-host.invalid"}}"#,
+            u32::max_value()
+        );
+        index
+            .directory_mut()
+            .atomic_write(&*LOCKFILE_FILEPATH, foreign_lock.as_bytes())
+            .unwrap();
+        match index.writer(40_000_000) {
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected IndexLocked error"),
+        }
+        assert_eq!(
+            index.directory().atomic_read(&*LOCKFILE_FILEPATH).unwrap(),
+            foreign_lock.as_bytes()
+        );
+        assert_eq!(
+            lock_owner(index.directory()).hostname,
+            Some("some-other-host.invalid".to_string())
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use core::{SegmentComponent, LOCKFILE_FILEPATH, MANAGED_FILEPATH, META_FILEPATH,
+           PREVIOUS_META_FILEPATH};
 use std::error::Error as StdError;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::io;
 use std::fmt;
 
@@ -36,6 +38,11 @@ impl IOError {
             err,
         }
     }
+
+    /// Returns the path of the offending file, if it is known.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_ref().map(|path| path.as_path())
+    }
 }
 
 impl From<io::Error> for IOError {
@@ -44,6 +51,12 @@ impl From<io::Error> for IOError {
     }
 }
 
+/// Error returned by the read-only directories upon
+/// any attempt to modify them.
+pub(crate) fn read_only_error(reason: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, reason.to_string())
+}
+
 /// Error that may occur when opening a directory
 #[derive(Debug)]
 pub enum OpenDirectoryError {
@@ -212,3 +225,173 @@ impl StdError for DeleteError {
         }
     }
 }
+
+/// Kind of the file a `DirectoryError` occurred on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    /// The `meta.json` file, or the copy of its previous version.
+    Meta,
+    /// The lockfile of the `IndexWriter`.
+    Lock,
+    /// The list of the files managed by tantivy.
+    Managed,
+    /// One of the components of a segment.
+    Segment(SegmentComponent),
+    /// A file unknown to tantivy.
+    Other,
+}
+
+impl FileKind {
+    /// Returns the kind of a file, given its path relative to the directory.
+    pub fn of_path(path: &Path) -> FileKind {
+        if path == &*META_FILEPATH || path == &*PREVIOUS_META_FILEPATH {
+            return FileKind::Meta;
+        }
+        if path == &*LOCKFILE_FILEPATH {
+            return FileKind::Lock;
+        }
+        if path == &*MANAGED_FILEPATH {
+            return FileKind::Managed;
+        }
+        let component = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("idx") => SegmentComponent::POSTINGS,
+            Some("pos") => SegmentComponent::POSITIONS,
+            Some("fast") => SegmentComponent::FASTFIELDS,
+            Some("fieldnorm") => SegmentComponent::FIELDNORMS,
+            Some("term") => SegmentComponent::TERMS,
+            Some("store") => SegmentComponent::STORE,
+            Some("del") => SegmentComponent::DELETE,
+            Some("upd") => SegmentComponent::FASTFIELDUPDATES,
+            Some("completion") => SegmentComponent::COMPLETIONS,
+            Some("termvec") => SegmentComponent::TERMVECTORS,
+            _ => {
+                return FileKind::Other;
+            }
+        };
+        FileKind::Segment(component)
+    }
+}
+
+/// Error that may occur when operating on a `Directory`.
+///
+/// The error of the operation is kept as the cause of the `DirectoryError`.
+#[derive(Debug)]
+pub enum DirectoryError {
+    /// The directory could not be opened.
+    OpenDirectory(OpenDirectoryError),
+    /// A file could not be opened for reading.
+    OpenRead(OpenReadError),
+    /// A file could not be opened for writing.
+    OpenWrite(OpenWriteError),
+    /// A file could not be deleted.
+    Delete(DeleteError),
+}
+
+impl DirectoryError {
+    /// Returns the path of the offending file, or directory, if it is known.
+    pub fn path(&self) -> Option<&Path> {
+        match *self {
+            DirectoryError::OpenDirectory(OpenDirectoryError::DoesNotExist(ref path))
+            | DirectoryError::OpenDirectory(OpenDirectoryError::NotADirectory(ref path))
+            | DirectoryError::OpenRead(OpenReadError::FileDoesNotExist(ref path))
+            | DirectoryError::OpenWrite(OpenWriteError::FileAlreadyExists(ref path))
+            | DirectoryError::Delete(DeleteError::FileDoesNotExist(ref path))
+            | DirectoryError::Delete(DeleteError::FileProtected(ref path)) => Some(path.as_path()),
+            DirectoryError::OpenRead(OpenReadError::IOError(ref err))
+            | DirectoryError::OpenWrite(OpenWriteError::IOError(ref err))
+            | DirectoryError::Delete(DeleteError::IOError(ref err)) => err.path(),
+        }
+    }
+
+    /// Returns the kind of the offending file, if it is known.
+    ///
+    /// Errors on the directory itself do not have a file kind.
+    pub fn file_kind(&self) -> Option<FileKind> {
+        match *self {
+            DirectoryError::OpenDirectory(_) => None,
+            _ => self.path().map(FileKind::of_path),
+        }
+    }
+
+    /// Returns true if the error is due to a missing file, or directory.
+    pub fn is_not_found(&self) -> bool {
+        match *self {
+            DirectoryError::OpenDirectory(OpenDirectoryError::DoesNotExist(_))
+            | DirectoryError::OpenRead(OpenReadError::FileDoesNotExist(_))
+            | DirectoryError::Delete(DeleteError::FileDoesNotExist(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+impl From<OpenDirectoryError> for DirectoryError {
+    fn from(err: OpenDirectoryError) -> DirectoryError {
+        DirectoryError::OpenDirectory(err)
+    }
+}
+
+impl From<OpenReadError> for DirectoryError {
+    fn from(err: OpenReadError) -> DirectoryError {
+        DirectoryError::OpenRead(err)
+    }
+}
+
+impl From<OpenWriteError> for DirectoryError {
+    fn from(err: OpenWriteError) -> DirectoryError {
+        DirectoryError::OpenWrite(err)
+    }
+}
+
+impl From<DeleteError> for DirectoryError {
+    fn from(err: DeleteError) -> DirectoryError {
+        DirectoryError::Delete(err)
+    }
+}
+
+impl fmt::Display for DirectoryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DirectoryError::OpenDirectory(ref err) => write!(f, "{}", err),
+            DirectoryError::OpenRead(ref err) => write!(f, "{}", err),
+            DirectoryError::OpenWrite(ref err) => write!(f, "{}", err),
+            DirectoryError::Delete(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl StdError for DirectoryError {
+    fn description(&self) -> &str {
+        "error occurred while operating on a directory"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            DirectoryError::OpenDirectory(ref err) => Some(err),
+            DirectoryError::OpenRead(ref err) => Some(err),
+            DirectoryError::OpenWrite(ref err) => Some(err),
+            DirectoryError::Delete(ref err) => Some(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::{SegmentComponent, META_FILEPATH};
+    use std::path::{Path, PathBuf};
+    use super::{DirectoryError, FileKind, OpenReadError};
+
+    #[test]
+    fn test_directory_error_file_kind() {
+        let path = PathBuf::from("00000000000000000000000000000000.7.del");
+        let error = DirectoryError::from(OpenReadError::FileDoesNotExist(path.clone()));
+        assert_eq!(error.path(), Some(path.as_path()));
+        assert_eq!(
+            error.file_kind(),
+            Some(FileKind::Segment(SegmentComponent::DELETE))
+        );
+        assert!(error.is_not_found());
+        assert_eq!(FileKind::of_path(&META_FILEPATH), FileKind::Meta);
+        assert_eq!(FileKind::of_path(Path::new("notes.txt")), FileKind::Other);
+    }
+}
@@ -0,0 +1,463 @@
+use Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, BufWriter, Cursor, Seek, SeekFrom, Write};
+use std::mem::ManuallyDrop;
+use std::path::{Path, PathBuf};
+use std::result;
+use std::sync::{Arc, RwLock, RwLockWriteGuard};
+use directory::{Directory, RAMDirectory, ReadOnlySource, WritePtr};
+use directory::error::{DeleteError, IOError, OpenReadError, OpenWriteError};
+
+fn crash_err() -> io::Error {
+    io::Error::new(io::ErrorKind::Other, "Simulated crash")
+}
+
+/// Small xorshift generator, so that the torn writes
+/// of a given seed are reproducible.
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> XorShift {
+        // the state of a xorshift generator must not be 0.
+        XorShift(seed ^ 0x9E37_79B9_7F4A_7C15)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Returns a number in `[low, high]`.
+    fn between(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next() % (high - low + 1) as u64) as usize
+    }
+}
+
+struct FaultyFile {
+    // content flushed to the inner directory.
+    content: Arc<Vec<u8>>,
+    // content surviving a crash, if any.
+    durable_content: Option<Arc<Vec<u8>>>,
+    // files written with `atomic_write` are never torn.
+    is_atomic: bool,
+}
+
+/// Failures to simulate, and files written through the `FaultyDirectory`.
+///
+/// The files of the inner directory that were not written through
+/// the `FaultyDirectory` are durable, and are not tracked.
+struct FaultyState {
+    files: HashMap<PathBuf, FaultyFile>,
+    torn_writes: bool,
+    ignore_fsyncs: bool,
+    remaining_operations: Option<usize>,
+    num_operations: usize,
+    has_crashed: bool,
+    rng: XorShift,
+}
+
+impl FaultyState {
+    fn new(seed: u64) -> FaultyState {
+        FaultyState {
+            files: HashMap::new(),
+            torn_writes: false,
+            ignore_fsyncs: false,
+            remaining_operations: None,
+            num_operations: 0,
+            has_crashed: false,
+            rng: XorShift::new(seed),
+        }
+    }
+
+    /// Accounts for a write operation, and returns an error
+    /// if the directory has crashed, or crashes on this operation.
+    fn start_operation(&mut self) -> io::Result<()> {
+        if self.has_crashed {
+            return Err(crash_err());
+        }
+        if let Some(remaining_operations) = self.remaining_operations {
+            if remaining_operations == 0 {
+                self.has_crashed = true;
+                return Err(crash_err());
+            }
+            self.remaining_operations = Some(remaining_operations - 1);
+        }
+        self.num_operations += 1;
+        Ok(())
+    }
+
+    /// Records that `data` was written to `path` in the inner directory.
+    ///
+    /// `untracked_content` is the content of the file in the inner directory
+    /// before it was written, if the file was not tracked yet.
+    fn record_write(
+        &mut self,
+        path: &Path,
+        data: &[u8],
+        is_atomic: bool,
+        untracked_content: Option<Arc<Vec<u8>>>,
+    ) {
+        let content = Arc::new(data.to_vec());
+        let durable_content = if self.ignore_fsyncs {
+            match self.files.get(path) {
+                Some(file) => file.durable_content.clone(),
+                None => untracked_content,
+            }
+        } else {
+            Some(Arc::clone(&content))
+        };
+        self.files.insert(
+            path.to_owned(),
+            FaultyFile {
+                content,
+                durable_content,
+                is_atomic,
+            },
+        );
+    }
+
+    /// Returns the tracked files as they would be found after a crash,
+    /// `None` standing for a lost file.
+    fn recovered_files(&mut self) -> Vec<(PathBuf, Option<Arc<Vec<u8>>>, bool)> {
+        let mut recovered_files = Vec::new();
+        for (path, file) in &self.files {
+            let is_durable = file.durable_content.as_ref() == Some(&file.content);
+            let recovered_content = if self.torn_writes && !file.is_atomic && !is_durable {
+                // only a prefix of the data written since the last fsync made it to disk.
+                let durable_len = file.durable_content
+                    .as_ref()
+                    .map(|durable_content| durable_content.len())
+                    .unwrap_or(0)
+                    .min(file.content.len());
+                let len = self.rng.between(durable_len, file.content.len());
+                Some(Arc::new(file.content[..len].to_vec()))
+            } else {
+                file.durable_content.clone()
+            };
+            recovered_files.push((path.clone(), recovered_content, file.is_atomic));
+        }
+        recovered_files
+    }
+}
+
+fn lock_state(state: &RwLock<FaultyState>) -> RwLockWriteGuard<FaultyState> {
+    state.write().expect("Faulty directory lock poisoned")
+}
+
+/// Writer associated with the `FaultyDirectory`.
+///
+/// Data is written to the writer of the inner directory.
+/// Flushing stands for an fsync.
+struct FaultyWriter {
+    path: PathBuf,
+    state: Arc<RwLock<FaultyState>>,
+    inner: ManuallyDrop<WritePtr>,
+    // copy of the data written so far.
+    data: Cursor<Vec<u8>>,
+}
+
+impl Drop for FaultyWriter {
+    fn drop(&mut self) {
+        let has_crashed = self.state
+            .read()
+            .map(|state| state.has_crashed)
+            .unwrap_or(true);
+        // after a crash, the writer of the inner directory is leaked
+        // rather than dropped, as the process writing to it would be dead.
+        if !has_crashed {
+            unsafe { ManuallyDrop::drop(&mut self.inner) }
+        }
+    }
+}
+
+impl Seek for FaultyWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)?;
+        self.data.seek(pos)
+    }
+}
+
+impl Write for FaultyWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write_all(buf)?;
+        self.data.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut state = lock_state(&self.state);
+        state.start_operation()?;
+        self.inner.flush()?;
+        state.record_write(&self.path, self.data.get_ref(), false, None);
+        Ok(())
+    }
+}
+
+/// `Directory` wrapper simulating storage failures of its inner
+/// directory, to test the crash consistency of an index.
+///
+/// It delegates to the inner directory until it crashes. A crash is either
+/// triggered explicitly with `.crash()`, or scheduled with `.crash_after(n)`,
+/// in which case the `n+1`-th write operation (opening a file for writing,
+/// flushing a file, writing a file atomically, or deleting a file) fails.
+/// After the crash, every write operation fails, as the process
+/// writing to the directory would be dead.
+///
+/// `.restart()` rewrites the inner directory as it would be found after the crash:
+/// - files flushed, or written atomically, are kept,
+/// - files that were not flushed are lost,
+/// - deletes are kept.
+///
+/// Two additional failures can be simulated:
+/// - missing fsyncs: flushing a file no longer makes it durable,
+/// as on a disk lying about its write cache. See `.set_ignore_fsyncs(...)`.
+/// - torn writes: upon restart, files that were not durable keep a random
+/// prefix of their content instead of being lost. Atomic writes are never
+/// torn. See `.set_torn_writes(...)`.
+///
+/// The faulty directory is cheap to clone, and all of its clones
+/// share the same failures.
+#[derive(Clone)]
+pub struct FaultyDirectory<D: Directory + Clone = RAMDirectory> {
+    inner: D,
+    state: Arc<RwLock<FaultyState>>,
+}
+
+impl FaultyDirectory<RAMDirectory> {
+    /// Creates a faulty directory over an empty `RAMDirectory`.
+    ///
+    /// The `seed` drives the length of the torn writes.
+    pub fn create(seed: u64) -> FaultyDirectory<RAMDirectory> {
+        FaultyDirectory::wrap(RAMDirectory::create(), seed)
+    }
+}
+
+impl<D: Directory + Clone> FaultyDirectory<D> {
+    /// Wraps `inner`, so that failures can be simulated.
+    ///
+    /// The `seed` drives the length of the torn writes.
+    pub fn wrap(inner: D, seed: u64) -> FaultyDirectory<D> {
+        FaultyDirectory {
+            inner,
+            state: Arc::new(RwLock::new(FaultyState::new(seed))),
+        }
+    }
+
+    fn state(&self) -> RwLockWriteGuard<FaultyState> {
+        lock_state(&self.state)
+    }
+
+    /// Enables or disables torn writes.
+    pub fn set_torn_writes(&self, torn_writes: bool) {
+        self.state().torn_writes = torn_writes;
+    }
+
+    /// Enables or disables missing fsyncs.
+    pub fn set_ignore_fsyncs(&self, ignore_fsyncs: bool) {
+        self.state().ignore_fsyncs = ignore_fsyncs;
+    }
+
+    /// Schedules a crash after `num_operations` more write operations.
+    pub fn crash_after(&self, num_operations: usize) {
+        self.state().remaining_operations = Some(num_operations);
+    }
+
+    /// Crashes the directory right away.
+    pub fn crash(&self) {
+        self.state().has_crashed = true;
+    }
+
+    /// Returns true iff the directory has crashed.
+    pub fn has_crashed(&self) -> bool {
+        self.state().has_crashed
+    }
+
+    /// Returns the number of successful write operations.
+    pub fn num_operations(&self) -> usize {
+        self.state().num_operations
+    }
+
+    /// Rewrites the files of the inner directory as they would be
+    /// found after a crash of this directory, and returns a new faulty
+    /// directory over it.
+    ///
+    /// The new directory does not share its failures with this one.
+    /// It does not simulate any failure until configured to do so.
+    pub fn restart(&self) -> Result<FaultyDirectory<D>> {
+        let mut state = self.state();
+        let mut inner = self.inner.clone();
+        for (path, recovered_content, is_atomic) in state.recovered_files() {
+            match inner.delete(&path) {
+                Ok(()) | Err(DeleteError::FileDoesNotExist(_)) => {}
+                Err(err) => return Err(err.into()),
+            }
+            let content = match recovered_content {
+                Some(content) => content,
+                None => continue,
+            };
+            if is_atomic {
+                inner.atomic_write(&path, &content)?;
+            } else {
+                let mut wrt = inner.open_write(&path)?;
+                wrt.write_all(&content)?;
+                wrt.flush()?;
+            }
+        }
+        let seed = state.rng.next();
+        Ok(FaultyDirectory::wrap(inner, seed))
+    }
+}
+
+impl<D: Directory + Clone> fmt::Debug for FaultyDirectory<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "FaultyDirectory({:?})", self.inner)
+    }
+}
+
+impl<D: Directory + Clone> Directory for FaultyDirectory<D> {
+    fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
+        self.inner.open_read(path)
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        let mut state = self.state();
+        state
+            .start_operation()
+            .map_err(|err| IOError::with_path(path.to_owned(), err))?;
+        self.inner.delete(path)?;
+        state.files.remove(path);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        let mut state = lock_state(&self.state);
+        if self.inner.exists(path) {
+            return Err(OpenWriteError::FileAlreadyExists(path.to_owned()));
+        }
+        state
+            .start_operation()
+            .map_err(|err| IOError::with_path(path.to_owned(), err))?;
+        let inner = self.inner.open_write(path)?;
+        // the file is created right away, but it is not durable.
+        state.files.insert(
+            path.to_owned(),
+            FaultyFile {
+                content: Arc::new(Vec::new()),
+                durable_content: None,
+                is_atomic: false,
+            },
+        );
+        let writer = FaultyWriter {
+            path: path.to_owned(),
+            state: Arc::clone(&self.state),
+            inner: ManuallyDrop::new(inner),
+            data: Cursor::new(Vec::new()),
+        };
+        Ok(BufWriter::new(Box::new(writer)))
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        self.inner.atomic_read(path)
+    }
+
+    fn atomic_write(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut state = lock_state(&self.state);
+        state.start_operation()?;
+        let untracked_content = if state.files.contains_key(path) {
+            None
+        } else {
+            self.inner.atomic_read(path).ok().map(Arc::new)
+        };
+        self.inner.atomic_write(path, data)?;
+        state.record_write(path, data, true, untracked_content);
+        Ok(())
+    }
+
+    fn box_clone(&self) -> Box<Directory> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::FaultyDirectory;
+    use directory::{Directory, RAMDirectory};
+    use std::io::Write;
+    use std::path::Path;
+
+    fn write_file(directory: &mut FaultyDirectory, path: &Path, data: &[u8], flush: bool) {
+        let mut wrt = directory.open_write(path).unwrap();
+        wrt.write_all(data).unwrap();
+        if flush {
+            wrt.flush().unwrap();
+        } else {
+            // leaks the writer, as a dead process would.
+            ::std::mem::forget(wrt);
+        }
+    }
+
+    #[test]
+    fn test_faulty_directory_crash() {
+        let mut directory = FaultyDirectory::create(1);
+        write_file(&mut directory, Path::new("flushed"), b"abc", true);
+        write_file(&mut directory, Path::new("not_flushed"), b"def", false);
+        directory.atomic_write(Path::new("meta"), b"1").unwrap();
+        directory.crash_after(1);
+        directory.atomic_write(Path::new("meta"), b"2").unwrap();
+        assert!(directory.atomic_write(Path::new("meta"), b"3").is_err());
+        assert!(directory.has_crashed());
+        assert!(directory.delete(Path::new("flushed")).is_err());
+        assert_eq!(directory.num_operations(), 5);
+
+        let restarted = directory.restart().unwrap();
+        assert!(!restarted.has_crashed());
+        assert_eq!(restarted.atomic_read(Path::new("flushed")).unwrap(), b"abc");
+        assert_eq!(restarted.atomic_read(Path::new("meta")).unwrap(), b"2");
+        assert!(!restarted.exists(Path::new("not_flushed")));
+    }
+
+    #[test]
+    fn test_faulty_directory_torn_writes_and_missing_fsyncs() {
+        let mut directory = FaultyDirectory::create(2);
+        directory.set_torn_writes(true);
+        directory.set_ignore_fsyncs(true);
+        directory.atomic_write(Path::new("meta"), b"1").unwrap();
+        write_file(&mut directory, Path::new("flushed"), b"abcdef", true);
+        directory.crash();
+
+        let restarted = directory.restart().unwrap();
+        // the atomic write was not fsynced, but it is never torn.
+        assert!(!restarted.exists(Path::new("meta")));
+        let flushed = restarted.atomic_read(Path::new("flushed")).unwrap();
+        assert!(b"abcdef".starts_with(&flushed));
+    }
+
+    #[test]
+    fn test_faulty_directory_wraps_inner_directory() {
+        let mut inner = RAMDirectory::create();
+        inner.atomic_write(Path::new("meta"), b"0").unwrap();
+        let mut directory = FaultyDirectory::wrap(inner.clone(), 3);
+        directory.set_ignore_fsyncs(true);
+        assert_eq!(directory.atomic_read(Path::new("meta")).unwrap(), b"0");
+        directory.atomic_write(Path::new("meta"), b"1").unwrap();
+        write_file(&mut directory, Path::new("flushed"), b"abc", true);
+        // writes go through to the inner directory.
+        assert_eq!(inner.atomic_read(Path::new("meta")).unwrap(), b"1");
+        assert_eq!(inner.atomic_read(Path::new("flushed")).unwrap(), b"abc");
+        directory.crash();
+
+        let restarted = directory.restart().unwrap();
+        // the content of the inner directory before it was wrapped is durable.
+        assert_eq!(restarted.atomic_read(Path::new("meta")).unwrap(), b"0");
+        assert_eq!(inner.atomic_read(Path::new("meta")).unwrap(), b"0");
+        assert!(!inner.exists(Path::new("flushed")));
+    }
+}
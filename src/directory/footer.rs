@@ -0,0 +1,186 @@
+use common::BinarySerializable;
+use common::HasLen;
+use crc::crc32::{self, Hasher32};
+use directory::ReadOnlySource;
+use std::io::{self, Seek, SeekFrom, Write};
+use std::mem;
+use std::thread;
+
+/// Magic number identifying the files ending with a checksum footer.
+///
+/// Files written before checksums were introduced do not have a footer.
+/// They are detected by the absence of the magic number.
+const FOOTER_MAGIC_NUMBER: u32 = 0x5446_4F54;
+const FOOTER_VERSION: u32 = 1;
+
+/// Length of the footer, in bytes.
+///
+/// The footer is made of
+/// - the crc32 checksum of the content of the file,
+/// - the version of the footer,
+/// - the magic number.
+pub const FOOTER_LEN: usize = 3 * mem::size_of::<u32>();
+
+/// Computes the crc32 checksum of some data.
+pub fn compute_checksum(data: &[u8]) -> u32 {
+    crc32::checksum_ieee(data)
+}
+
+/// Splits a source into its content and the checksum stored in its footer.
+///
+/// If the source does not end with a footer, the entire source
+/// is returned, without any checksum.
+pub fn split_footer(source: ReadOnlySource) -> (ReadOnlySource, Option<u32>) {
+    let len = source.len();
+    if len < FOOTER_LEN {
+        return (source, None);
+    }
+    let (checksum, version, magic_number) = {
+        let mut cursor = &source.as_slice()[len - FOOTER_LEN..];
+        let checksum = u32::deserialize(&mut cursor).expect("Reading from a slice cannot fail");
+        let version = u32::deserialize(&mut cursor).expect("Reading from a slice cannot fail");
+        let magic_number = u32::deserialize(&mut cursor).expect("Reading from a slice cannot fail");
+        (checksum, version, magic_number)
+    };
+    if magic_number != FOOTER_MAGIC_NUMBER || version != FOOTER_VERSION {
+        return (source, None);
+    }
+    (source.slice(0, len - FOOTER_LEN), Some(checksum))
+}
+
+/// Writer computing the checksum of the data written,
+/// and appending the footer to the file.
+///
+/// The footer is written upon each flush, so that a failure
+/// to write it is returned to the serializer closing the file.
+/// The writer then seeks back before the footer: if more data
+/// is written, it overwrites the footer, which is written again
+/// upon the next flush.
+///
+/// Seeking is not supported, as it would invalidate the checksum.
+pub struct FooterProxy<W: Write + Seek> {
+    digest: crc32::Digest,
+    writer: W,
+    is_sealed: bool,
+}
+
+impl<W: Write + Seek> FooterProxy<W> {
+    pub fn new(writer: W) -> FooterProxy<W> {
+        FooterProxy {
+            digest: crc32::Digest::new(crc32::IEEE),
+            writer,
+            is_sealed: false,
+        }
+    }
+
+    fn write_footer(&mut self) -> io::Result<()> {
+        self.digest.sum32().serialize(&mut self.writer)?;
+        FOOTER_VERSION.serialize(&mut self.writer)?;
+        FOOTER_MAGIC_NUMBER.serialize(&mut self.writer)?;
+        self.writer.flush()?;
+        self.writer.seek(SeekFrom::Current(-(FOOTER_LEN as i64)))?;
+        self.is_sealed = true;
+        Ok(())
+    }
+}
+
+impl<W: Write + Seek> Write for FooterProxy<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.is_sealed = false;
+        let written_len = self.writer.write(buf)?;
+        self.digest.write(&buf[..written_len]);
+        Ok(written_len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_footer()
+    }
+}
+
+impl<W: Write + Seek> Seek for FooterProxy<W> {
+    fn seek(&mut self, _: SeekFrom) -> io::Result<u64> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "Seeking is not supported on checksummed files.",
+        ))
+    }
+}
+
+impl<W: Write + Seek> Drop for FooterProxy<W> {
+    fn drop(&mut self) {
+        // A file written by a panicking thread is incomplete,
+        // and should not be sealed with a valid checksum.
+        if self.is_sealed || thread::panicking() {
+            return;
+        }
+        // the file was not flushed after its last write.
+        if let Err(e) = self.write_footer() {
+            error!("Failed to write the checksum footer: {:?}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use directory::{Directory, RAMDirectory};
+    use std::io::{BufWriter, Cursor, Write};
+    use std::path::Path;
+
+    #[test]
+    fn test_footer() {
+        let path = Path::new("test");
+        let mut directory = RAMDirectory::create();
+        {
+            let write_ptr = directory.open_write(path).unwrap();
+            let mut writer = BufWriter::new(FooterProxy::new(write_ptr));
+            writer.write_all(b"hello").unwrap();
+            writer.flush().unwrap();
+        }
+        let source = directory.open_read(path).unwrap();
+        assert_eq!(source.len(), 5 + FOOTER_LEN);
+        let (content, checksum) = split_footer(source);
+        assert_eq!(content.as_slice(), b"hello");
+        assert_eq!(checksum, Some(compute_checksum(b"hello")));
+    }
+
+    #[test]
+    fn test_footer_multiple_flushes() {
+        let path = Path::new("test");
+        let mut directory = RAMDirectory::create();
+        {
+            let write_ptr = directory.open_write(path).unwrap();
+            let mut writer = BufWriter::new(FooterProxy::new(write_ptr));
+            writer.write_all(b"hel").unwrap();
+            writer.flush().unwrap();
+            writer.write_all(b"lo").unwrap();
+            writer.flush().unwrap();
+        }
+        let source = directory.open_read(path).unwrap();
+        assert_eq!(source.len(), 5 + FOOTER_LEN);
+        let (content, checksum) = split_footer(source);
+        assert_eq!(content.as_slice(), b"hello");
+        assert_eq!(checksum, Some(compute_checksum(b"hello")));
+    }
+
+    #[test]
+    fn test_footer_write_error() {
+        // there is no room left for the footer.
+        let mut buffer = [0u8; 5];
+        let mut writer = FooterProxy::new(Cursor::new(&mut buffer[..]));
+        writer.write_all(b"hello").unwrap();
+        assert!(writer.flush().is_err());
+    }
+
+    #[test]
+    fn test_no_footer() {
+        let path = Path::new("test");
+        let mut directory = RAMDirectory::create();
+        directory.atomic_write(path, b"no footer here").unwrap();
+        let source = directory.open_read(path).unwrap();
+        let (content, checksum) = split_footer(source);
+        assert_eq!(content.as_slice(), b"no footer here");
+        assert!(checksum.is_none());
+    }
+}
@@ -0,0 +1,278 @@
+use directory::{Directory, ReadOnlySource, WritePtr};
+use directory::error::{DeleteError, OpenReadError, OpenWriteError};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::result;
+use std::sync::{Arc, RwLock};
+
+const PAGE_SIZE: usize = 4_096;
+
+/// Returns the ranges of `data` that are currently loaded in memory.
+#[cfg(unix)]
+fn resident_ranges(data: &[u8]) -> io::Result<Vec<(usize, usize)>> {
+    use libc;
+    if data.is_empty() {
+        return Ok(Vec::new());
+    }
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let addr = data.as_ptr() as usize;
+    let shift = addr % page_size;
+    let len = data.len() + shift;
+    let num_pages = (len + page_size - 1) / page_size;
+    let mut residency = vec![0u8; num_pages];
+    let ret = unsafe {
+        libc::mincore(
+            (addr - shift) as *mut libc::c_void,
+            len,
+            residency.as_mut_ptr() as *mut _,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (page_id, &page_residency) in residency.iter().enumerate() {
+        if page_residency & 1 == 0 {
+            continue;
+        }
+        let start = (page_id * page_size).saturating_sub(shift);
+        let end = ((page_id + 1) * page_size - shift).min(data.len());
+        push_range(&mut ranges, (start, end));
+    }
+    Ok(ranges)
+}
+
+#[cfg(not(unix))]
+fn resident_ranges(data: &[u8]) -> io::Result<Vec<(usize, usize)>> {
+    Ok(vec![(0, data.len())])
+}
+
+/// Appends a range, merging it with the last one if they are contiguous.
+fn push_range(ranges: &mut Vec<(usize, usize)>, range: (usize, usize)) {
+    if let Some(last_range) = ranges.last_mut() {
+        if last_range.1 == range.0 {
+            last_range.1 = range.1;
+            return;
+        }
+    }
+    ranges.push(range);
+}
+
+/// Byte ranges of the files of a directory that were accessed
+/// during a workload, as recorded by a `HotDirectory`.
+///
+/// The access log can be serialized, and used to warm
+/// the same files after the index is reopened.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessLog {
+    files: BTreeMap<PathBuf, Vec<(usize, usize)>>,
+}
+
+impl AccessLog {
+    /// Returns the recorded files, with their accessed byte ranges.
+    pub fn files(&self) -> &BTreeMap<PathBuf, Vec<(usize, usize)>> {
+        &self.files
+    }
+
+    /// Loads the recorded byte ranges in memory, by touching
+    /// every page they span.
+    ///
+    /// Files that do not exist anymore are ignored.
+    pub fn warm(&self, directory: &Directory) -> result::Result<(), OpenReadError> {
+        for (path, ranges) in &self.files {
+            let source = match directory.open_read(path) {
+                Ok(source) => source,
+                Err(OpenReadError::FileDoesNotExist(_)) => {
+                    continue;
+                }
+                Err(e) => {
+                    return Err(e);
+                }
+            };
+            let data = source.as_slice();
+            for &(start, end) in ranges {
+                let end = end.min(data.len());
+                if start >= end {
+                    continue;
+                }
+                for page in data[start..end].chunks(PAGE_SIZE) {
+                    // volatile to make sure the read is not optimized away.
+                    unsafe {
+                        ptr::read_volatile(&page[0]);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Wraps a `Directory` to record which parts of its files
+/// are accessed.
+///
+/// The typical usage is to open an index over a `HotDirectory`,
+/// run a sample query workload, and save the resulting `AccessLog`.
+/// After a restart, warming the directory with the `AccessLog`
+/// loads the useful parts of the index in memory before serving queries,
+/// which makes cold-cache latency predictable.
+///
+/// For mmapped files, accesses are detected by checking which
+/// pages are resident in memory. Pages that were already in the page cache
+/// before the workload are therefore recorded as well. For other
+/// sources, whole files are recorded.
+pub struct HotDirectory {
+    directory: Box<Directory>,
+    opened_files: Arc<RwLock<HashMap<PathBuf, ReadOnlySource>>>,
+}
+
+impl HotDirectory {
+    /// Wraps a directory.
+    pub fn wrap<Dir: Directory>(directory: Dir) -> HotDirectory {
+        HotDirectory {
+            directory: box directory,
+            opened_files: Arc::default(),
+        }
+    }
+
+    /// Returns the byte ranges accessed so far in the files
+    /// opened through this directory.
+    pub fn access_log(&self) -> io::Result<AccessLog> {
+        let opened_files = self.opened_files
+            .read()
+            .expect("Hot directory lock poisoned");
+        let mut files = BTreeMap::new();
+        for (path, source) in opened_files.iter() {
+            let ranges = match *source {
+                #[cfg(feature = "mmap")]
+                ReadOnlySource::Mmap(_) => resident_ranges(source.as_slice())?,
+                ReadOnlySource::Anonymous(_) | ReadOnlySource::Static(_) => {
+                    vec![(0, source.as_slice().len())]
+                }
+                #[cfg(feature = "object_store")]
+                ReadOnlySource::Lazy(ref lazy_slice) => vec![(0, lazy_slice.len())],
+            };
+            if !ranges.is_empty() {
+                files.insert(path.clone(), ranges);
+            }
+        }
+        Ok(AccessLog { files })
+    }
+}
+
+impl fmt::Debug for HotDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HotDirectory({:?})", self.directory)
+    }
+}
+
+impl Clone for HotDirectory {
+    fn clone(&self) -> HotDirectory {
+        HotDirectory {
+            directory: self.directory.box_clone(),
+            opened_files: Arc::clone(&self.opened_files),
+        }
+    }
+}
+
+impl Directory for HotDirectory {
+    fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
+        let source = self.directory.open_read(path)?;
+        self.opened_files
+            .write()
+            .expect("Hot directory lock poisoned")
+            .entry(path.to_owned())
+            .or_insert_with(|| source.clone());
+        Ok(source)
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        self.opened_files
+            .write()
+            .expect("Hot directory lock poisoned")
+            .remove(path);
+        self.directory.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.directory.exists(path)
+    }
+
+    fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        self.directory.open_write(path)
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        self.directory.atomic_read(path)
+    }
+
+    fn atomic_write(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
+        self.directory.atomic_write(path, data)
+    }
+
+    fn box_clone(&self) -> Box<Directory> {
+        box self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    #[cfg(feature = "mmap")]
+    use directory::MmapDirectory;
+    use directory::RAMDirectory;
+    use serde_json;
+    use std::io::Write;
+    use std::path::Path;
+
+    fn write_file(directory: &mut Directory, path: &Path, len: usize) {
+        let mut writer = directory.open_write(path).unwrap();
+        writer.write_all(&vec![1u8; len]).unwrap();
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn test_hot_directory_ram() {
+        let path = Path::new("test");
+        let mut hot_directory = HotDirectory::wrap(RAMDirectory::create());
+        write_file(&mut hot_directory, path, 10);
+        assert!(hot_directory.access_log().unwrap().files().is_empty());
+        hot_directory.open_read(path).unwrap();
+        let access_log = hot_directory.access_log().unwrap();
+        assert_eq!(access_log.files()[path], vec![(0, 10)]);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_hot_directory_mmap() {
+        let path = Path::new("test");
+        let mut hot_directory = HotDirectory::wrap(MmapDirectory::create_from_tempdir().unwrap());
+        write_file(&mut hot_directory, path, 5 * PAGE_SIZE);
+        {
+            let source = hot_directory.open_read(path).unwrap();
+            assert_eq!(source.as_slice()[2 * PAGE_SIZE + 10], 1u8);
+        }
+        let access_log = hot_directory.access_log().unwrap();
+        let ranges = &access_log.files()[path];
+        assert!(ranges
+            .iter()
+            .any(|&(start, end)| start <= 2 * PAGE_SIZE + 10 && 2 * PAGE_SIZE + 10 < end));
+
+        let json = serde_json::to_string(&access_log).unwrap();
+        let reloaded_access_log: AccessLog = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded_access_log, access_log);
+        reloaded_access_log.warm(&hot_directory).unwrap();
+    }
+
+    #[test]
+    fn test_push_range() {
+        let mut ranges = vec![];
+        push_range(&mut ranges, (0, 4));
+        push_range(&mut ranges, (4, 8));
+        push_range(&mut ranges, (12, 16));
+        assert_eq!(ranges, vec![(0, 8), (12, 16)]);
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// IO counters of a given type of file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTypeIoStats {
+    /// Number of read operations, that is calls
+    /// to `open_read` or `atomic_read`.
+    pub read_ops: u64,
+    /// Number of bytes served by the read operations.
+    pub bytes_read: u64,
+    /// Number of read operations on a file that was already
+    /// read before, and is therefore served from the cache
+    /// of the directory (mmap cache, page cache or RAM).
+    pub cache_hits: u64,
+}
+
+/// IO statistics of a directory, broken down by file extension
+/// (`idx`, `term`, `fast`, `store`, ...).
+///
+/// Files without any extension are recorded under their file name.
+///
+/// Read operations are counted when a file is opened.
+/// Segment files are opened once per segment reader, and the accesses to their
+/// content that follow are plain memory reads that are not recorded.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IoStats {
+    file_types: BTreeMap<String, FileTypeIoStats>,
+}
+
+impl IoStats {
+    /// Returns the counters of each file type.
+    pub fn file_types(&self) -> &BTreeMap<String, FileTypeIoStats> {
+        &self.file_types
+    }
+
+    /// Returns the counters of a given file type,
+    /// identified by its extension.
+    pub fn file_type(&self, extension: &str) -> FileTypeIoStats {
+        self.file_types
+            .get(extension)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns the sum of the counters of all of the file types.
+    pub fn total(&self) -> FileTypeIoStats {
+        let mut total = FileTypeIoStats::default();
+        for stats in self.file_types.values() {
+            total.read_ops += stats.read_ops;
+            total.bytes_read += stats.bytes_read;
+            total.cache_hits += stats.cache_hits;
+        }
+        total
+    }
+}
+
+fn file_type_of(path: &Path) -> String {
+    path.extension()
+        .or_else(|| path.file_name())
+        .map(|file_type| file_type.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Default)]
+struct InnerIoStats {
+    stats: IoStats,
+    read_files: HashSet<PathBuf>,
+}
+
+/// Records the read operations of a directory.
+///
+/// Clones share the same counters.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct IoStatsRecorder(Arc<Mutex<InnerIoStats>>);
+
+impl IoStatsRecorder {
+    pub fn record_read(&self, path: &Path, num_bytes: usize) {
+        let mut inner = self.0.lock().expect("IO stats lock poisoned");
+        let cache_hit = !inner.read_files.insert(path.to_owned());
+        let stats = inner
+            .stats
+            .file_types
+            .entry(file_type_of(path))
+            .or_insert_with(FileTypeIoStats::default);
+        stats.read_ops += 1;
+        stats.bytes_read += num_bytes as u64;
+        if cache_hit {
+            stats.cache_hits += 1;
+        }
+    }
+
+    /// Called when a file is deleted, so that a new file
+    /// with the same path does not count as a cache hit.
+    pub fn forget(&self, path: &Path) {
+        self.0
+            .lock()
+            .expect("IO stats lock poisoned")
+            .read_files
+            .remove(path);
+    }
+
+    pub fn stats(&self) -> IoStats {
+        self.0.lock().expect("IO stats lock poisoned").stats.clone()
+    }
+
+    pub fn reset(&self) {
+        self.0.lock().expect("IO stats lock poisoned").stats = IoStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use schema::{SchemaBuilder, STORED, TEXT};
+    use std::path::Path;
+    use Index;
+
+    #[test]
+    fn test_io_stats() {
+        let recorder = IoStatsRecorder::default();
+        recorder.record_read(Path::new("a.idx"), 10);
+        recorder.record_read(Path::new("a.idx"), 10);
+        recorder.record_read(Path::new("b.store"), 3);
+        recorder.record_read(Path::new("meta.json"), 5);
+        let io_stats = recorder.stats();
+        assert_eq!(
+            io_stats.file_type("idx"),
+            FileTypeIoStats {
+                read_ops: 2,
+                bytes_read: 20,
+                cache_hits: 1,
+            }
+        );
+        assert_eq!(io_stats.file_type("store").read_ops, 1);
+        assert_eq!(io_stats.file_type("json").bytes_read, 5);
+        assert_eq!(io_stats.file_type("fast"), FileTypeIoStats::default());
+        assert_eq!(io_stats.total().read_ops, 4);
+        recorder.reset();
+        assert_eq!(recorder.stats(), IoStats::default());
+    }
+
+    #[test]
+    fn test_index_io_stats() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a b"));
+        index_writer.commit().unwrap();
+
+        index.reset_io_stats();
+        index.load_searchers().unwrap();
+        let io_stats = index.io_stats();
+        for extension in &["idx", "term", "store"] {
+            let file_type_stats = io_stats.file_type(extension);
+            assert!(file_type_stats.read_ops >= 1);
+            assert!(file_type_stats.bytes_read > 0);
+        }
+
+        index.load_searchers().unwrap();
+        let reloaded_io_stats = index.io_stats();
+        for extension in &["idx", "term", "store"] {
+            let file_type_stats = io_stats.file_type(extension);
+            let reloaded_file_type_stats = reloaded_io_stats.file_type(extension);
+            assert!(reloaded_file_type_stats.read_ops > file_type_stats.read_ops);
+            assert!(reloaded_file_type_stats.cache_hits > file_type_stats.cache_hits);
+        }
+    }
+}
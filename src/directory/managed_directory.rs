@@ -2,13 +2,15 @@ use std::path::{Path, PathBuf};
 use serde_json;
 use directory::error::{DeleteError, IOError, OpenReadError, OpenWriteError};
 use directory::{ReadOnlySource, WritePtr};
+use directory::footer::{compute_checksum, split_footer, FooterProxy};
+use directory::io_stats::{IoStats, IoStatsRecorder};
 use std::result;
 use std::io;
 use Directory;
 use std::sync::{Arc, RwLock};
 use std::collections::HashSet;
 use std::sync::RwLockWriteGuard;
-use std::io::Write;
+use std::io::{BufWriter, Write};
 use core::MANAGED_FILEPATH;
 use std::collections::HashMap;
 use std::fmt;
@@ -23,16 +25,27 @@ use error::{ErrorKind, Result, ResultExt};
 /// Thanks to this list, it implements a `garbage_collect` method
 /// that removes the files that were created by tantivy and are not
 /// useful anymore.
+///
+/// Files that cannot be deleted because they are still protected
+/// or still open (on Windows, a mmapped file cannot be deleted)
+/// are put in a deletion queue. Their deletion is retried upon
+/// every garbage collection.
+///
+/// The files opened for write through a managed directory
+/// are appended a footer holding the checksum of their content.
+/// The footer is transparently removed upon `open_read`.
 #[derive(Debug)]
 pub struct ManagedDirectory {
     directory: Box<Directory>,
     meta_informations: Arc<RwLock<MetaInformation>>,
+    io_stats: IoStatsRecorder,
 }
 
 #[derive(Debug, Default)]
 struct MetaInformation {
     managed_paths: HashSet<PathBuf>,
     protected_files: HashMap<PathBuf, usize>,
+    pending_deletes: HashSet<PathBuf>,
 }
 
 /// A `FileProtection` prevents the garbage collection of a file.
@@ -43,13 +56,24 @@ pub struct FileProtection {
     path: PathBuf,
 }
 
+/// Releases a protection of a file.
+///
+/// If the file was waiting in the deletion queue, it is
+/// deleted by the next garbage collection.
 fn unprotect_file_from_delete(directory: &ManagedDirectory, path: &Path) {
     let mut meta_informations_wlock = directory
         .meta_informations
         .write()
         .expect("Managed file lock poisoned");
-    if let Some(counter_ref_mut) = meta_informations_wlock.protected_files.get_mut(path) {
-        (*counter_ref_mut) -= 1;
+    let is_unprotected =
+        if let Some(counter_ref_mut) = meta_informations_wlock.protected_files.get_mut(path) {
+            (*counter_ref_mut) -= 1;
+            *counter_ref_mut == 0
+        } else {
+            true
+        };
+    if is_unprotected {
+        meta_informations_wlock.protected_files.remove(path);
     }
 }
 
@@ -90,12 +114,15 @@ impl ManagedDirectory {
                     meta_informations: Arc::new(RwLock::new(MetaInformation {
                         managed_paths: managed_files,
                         protected_files: HashMap::default(),
+                        pending_deletes: HashSet::default(),
                     })),
+                    io_stats: IoStatsRecorder::default(),
                 })
             }
             Err(OpenReadError::FileDoesNotExist(_)) => Ok(ManagedDirectory {
                 directory: box directory,
                 meta_informations: Arc::default(),
+                io_stats: IoStatsRecorder::default(),
             }),
             Err(OpenReadError::IOError(e)) => Err(From::from(e)),
         }
@@ -109,9 +136,11 @@ impl ManagedDirectory {
     /// * `living_files` - List of files that are still used by the index.
     ///
     /// This method does not panick nor returns errors.
-    /// If a file cannot be deleted (for permission reasons for instance)
-    /// an error is simply logged, and the file remains in the list of managed
-    /// files.
+    /// If a file cannot be deleted (because it is protected, still mmapped on
+    /// Windows, or for permission reasons for instance), it is put in the
+    /// deletion queue and remains in the list of managed files.
+    /// The deletion of the files of the queue is retried upon each
+    /// garbage collection.
     pub fn garbage_collect<L: FnOnce() -> HashSet<PathBuf>>(&mut self, get_living_files: L) {
         info!("Garbage collect");
         let mut files_to_delete = vec![];
@@ -131,14 +160,23 @@ impl ManagedDirectory {
             // even though it is a living file.
             let living_files = get_living_files();
 
+            // the files of the deletion queue are retried first.
+            for pending_delete in &meta_informations_rlock.pending_deletes {
+                if !living_files.contains(pending_delete) {
+                    files_to_delete.push(pending_delete.clone());
+                }
+            }
             for managed_path in &meta_informations_rlock.managed_paths {
-                if !living_files.contains(managed_path) {
+                if !living_files.contains(managed_path)
+                    && !meta_informations_rlock.pending_deletes.contains(managed_path)
+                {
                     files_to_delete.push(managed_path.clone());
                 }
             }
         }
 
         let mut deleted_files = vec![];
+        let mut undeletable_files = vec![];
         {
             for file_to_delete in files_to_delete {
                 match self.delete(&file_to_delete) {
@@ -157,9 +195,11 @@ impl ManagedDirectory {
                                     // is mmapped.
                                     error!("Failed to delete {:?}", file_to_delete);
                                 }
+                                undeletable_files.push(file_to_delete);
                             }
                             DeleteError::FileProtected(_) => {
                                 // this is expected.
+                                undeletable_files.push(file_to_delete);
                             }
                         }
                     }
@@ -167,21 +207,41 @@ impl ManagedDirectory {
             }
         }
 
-        if !deleted_files.is_empty() {
-            // update the list of managed files by removing
-            // the file that were removed.
+        if !undeletable_files.is_empty() {
             let mut meta_informations_wlock = self.meta_informations
                 .write()
                 .expect("Managed directory wlock poisoned (2).");
-            {
-                let managed_paths_write = &mut meta_informations_wlock.managed_paths;
-                for delete_file in &deleted_files {
-                    managed_paths_write.remove(delete_file);
-                }
-            }
-            if save_managed_paths(self.directory.as_mut(), &meta_informations_wlock).is_err() {
-                error!("Failed to save the list of managed files.");
-            }
+            meta_informations_wlock
+                .pending_deletes
+                .extend(undeletable_files);
+        }
+        self.forget_deleted_files(&deleted_files);
+    }
+
+    /// Returns the files waiting to be deleted.
+    pub fn pending_deletes(&self) -> HashSet<PathBuf> {
+        self.meta_informations
+            .read()
+            .expect("Managed directory rlock poisoned.")
+            .pending_deletes
+            .clone()
+    }
+
+    /// Removes deleted files from the list of managed
+    /// files and from the deletion queue.
+    fn forget_deleted_files(&mut self, deleted_files: &[PathBuf]) {
+        if deleted_files.is_empty() {
+            return;
+        }
+        let mut meta_informations_wlock = self.meta_informations
+            .write()
+            .expect("Managed directory wlock poisoned (2).");
+        for deleted_file in deleted_files {
+            meta_informations_wlock.managed_paths.remove(deleted_file);
+            meta_informations_wlock.pending_deletes.remove(deleted_file);
+        }
+        if save_managed_paths(self.directory.as_mut(), &meta_informations_wlock).is_err() {
+            error!("Failed to save the list of managed files.");
         }
     }
 
@@ -207,6 +267,51 @@ impl ManagedDirectory {
         }
     }
 
+    /// Checks the integrity of a file, by comparing its content
+    /// with the checksum stored in its footer.
+    ///
+    /// Returns `false` if the file is corrupted.
+    /// Files without a footer, such as files written with a previous
+    /// version of tantivy, are considered valid.
+    pub fn validate_checksum(&self, path: &Path) -> result::Result<bool, OpenReadError> {
+        let (content, checksum_opt) = split_footer(self.directory.open_read(path)?);
+        Ok(checksum_opt
+            .map(|checksum| compute_checksum(content.as_slice()) == checksum)
+            .unwrap_or(true))
+    }
+
+    /// Returns the IO statistics of the reads made through
+    /// this directory and its clones.
+    pub fn io_stats(&self) -> IoStats {
+        self.io_stats.stats()
+    }
+
+    /// Resets the IO statistics.
+    pub fn reset_io_stats(&self) {
+        self.io_stats.reset()
+    }
+
+    /// Opens a file for read, without removing its footer.
+    pub(crate) fn open_read_with_footer(
+        &self,
+        path: &Path,
+    ) -> result::Result<ReadOnlySource, OpenReadError> {
+        self.directory.open_read(path)
+    }
+
+    /// Writes a file whose content already ends with its checksum footer,
+    /// such as a file copied from another managed directory.
+    ///
+    /// The file is registered as managed, but no footer is appended.
+    pub(crate) fn write_with_footer(&mut self, path: &Path, data: &[u8]) -> Result<()> {
+        self.register_file_as_managed(path)
+            .map_err(|e| IOError::with_path(path.to_owned(), e))?;
+        let mut write = self.directory.open_write(path)?;
+        write.write_all(data)?;
+        write.flush()?;
+        Ok(())
+    }
+
     /// Registers a file as managed
     ///
     /// This method must be called before the file is
@@ -228,13 +333,16 @@ impl ManagedDirectory {
 
 impl Directory for ManagedDirectory {
     fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
-        self.directory.open_read(path)
+        let (content, _) = split_footer(self.directory.open_read(path)?);
+        self.io_stats.record_read(path, content.len());
+        Ok(content)
     }
 
     fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
         self.register_file_as_managed(path)
             .map_err(|e| IOError::with_path(path.to_owned(), e))?;
-        self.directory.open_write(path)
+        let write_ptr = self.directory.open_write(path)?;
+        Ok(BufWriter::new(box FooterProxy::new(write_ptr)))
     }
 
     fn atomic_write(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
@@ -243,7 +351,9 @@ impl Directory for ManagedDirectory {
     }
 
     fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
-        self.directory.atomic_read(path)
+        let data = self.directory.atomic_read(path)?;
+        self.io_stats.record_read(path, data.len());
+        Ok(data)
     }
 
     fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
@@ -257,7 +367,9 @@ impl Directory for ManagedDirectory {
                 }
             }
         }
-        self.directory.delete(path)
+        self.directory.delete(path)?;
+        self.io_stats.forget(path);
+        Ok(())
     }
 
     fn exists(&self, path: &Path) -> bool {
@@ -274,11 +386,12 @@ impl Clone for ManagedDirectory {
         ManagedDirectory {
             directory: self.directory.box_clone(),
             meta_informations: Arc::clone(&self.meta_informations),
+            io_stats: self.io_stats.clone(),
         }
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "mmap"))]
 mod tests {
 
     use super::*;
@@ -392,4 +505,32 @@ mod tests {
         assert!(!managed_directory.exists(*TEST_PATH1));
     }
 
+    #[test]
+    fn test_managed_directory_deletion_queue() {
+        let tempdir = TempDir::new("index").unwrap();
+        let tempdir_path = PathBuf::from(tempdir.path());
+        let living_files = HashSet::new();
+
+        let mmap_directory = MmapDirectory::open(&tempdir_path).unwrap();
+        let mut managed_directory = ManagedDirectory::new(mmap_directory).unwrap();
+        managed_directory
+            .atomic_write(*TEST_PATH1, &vec![0u8, 1u8])
+            .unwrap();
+
+        let file_protection = managed_directory.protect_file_from_delete(*TEST_PATH1);
+        managed_directory.garbage_collect(|| living_files.clone());
+        assert!(managed_directory.exists(*TEST_PATH1));
+        assert!(managed_directory.pending_deletes().contains(*TEST_PATH1));
+
+        // releasing the protection does not delete the file...
+        drop(file_protection);
+        assert!(managed_directory.exists(*TEST_PATH1));
+        assert!(managed_directory.pending_deletes().contains(*TEST_PATH1));
+
+        // ... the deletion is retried by the next garbage collection.
+        managed_directory.garbage_collect(|| living_files.clone());
+        assert!(!managed_directory.exists(*TEST_PATH1));
+        assert!(managed_directory.pending_deletes().is_empty());
+    }
+
 }
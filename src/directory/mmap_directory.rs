@@ -1,4 +1,3 @@
-use atomicwrites;
 use common::make_io_err;
 use directory::Directory;
 use directory::error::{DeleteError, IOError, OpenDirectoryError, OpenReadError, OpenWriteError};
@@ -7,12 +6,13 @@ use directory::shared_vec_slice::SharedVecSlice;
 use directory::WritePtr;
 use fst::raw::MmapReadOnly;
 use std::collections::hash_map::Entry as HashMapEntry;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::From;
 use std::fmt;
 use std::fs::{self, File};
 use std::fs::OpenOptions;
 use std::io::{self, Seek, SeekFrom};
+use std::ptr;
 use std::io::{BufWriter, Read, Write};
 use std::path::{Path, PathBuf};
 use std::result;
@@ -45,6 +45,116 @@ fn open_mmap(full_path: &Path) -> result::Result<Option<MmapReadOnly>, OpenReadE
         .map_err(|e| From::from(IOError::with_path(full_path.to_owned(), e)))
 }
 
+/// Returns the path of the temporary file used to
+/// atomically write `full_path`.
+fn temp_path(full_path: &Path) -> PathBuf {
+    let mut temp_filename = full_path
+        .file_name()
+        .map(|filename| filename.to_os_string())
+        .unwrap_or_default();
+    temp_filename.push(".tmp");
+    full_path.with_file_name(temp_filename)
+}
+
+/// Access pattern of a file, given as a hint to the OS.
+///
+/// On unix, the advice is passed to `madvise`.
+/// It is ignored on other platforms.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Advice {
+    /// No specific access pattern.
+    Normal,
+    /// Pages will be accessed in random order.
+    /// Read-ahead is pointless.
+    Random,
+    /// Pages will be accessed sequentially.
+    /// Aggressive read-ahead is worth it.
+    Sequential,
+    /// Pages will be accessed soon.
+    /// The OS may start reading them in the background.
+    WillNeed,
+}
+
+#[cfg(unix)]
+fn advise(mmap: &MmapReadOnly, advice: Advice) -> io::Result<()> {
+    use libc;
+    let advice_flag = match advice {
+        Advice::Normal => libc::MADV_NORMAL,
+        Advice::Random => libc::MADV_RANDOM,
+        Advice::Sequential => libc::MADV_SEQUENTIAL,
+        Advice::WillNeed => libc::MADV_WILLNEED,
+    };
+    // The whole file is mmapped, so that the address is page-aligned.
+    let ret = unsafe {
+        let data = mmap.as_slice();
+        libc::madvise(
+            data.as_ptr() as *mut libc::c_void,
+            data.len(),
+            advice_flag,
+        )
+    };
+    if ret == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn advise(_mmap: &MmapReadOnly, _advice: Advice) -> io::Result<()> {
+    Ok(())
+}
+
+/// Touches all of the pages of the mmap, so that they
+/// are loaded in memory.
+fn preload(mmap: &MmapReadOnly) {
+    const PAGE_SIZE: usize = 4_096;
+    let data = unsafe { mmap.as_slice() };
+    for page in data.chunks(PAGE_SIZE) {
+        // volatile to make sure the read is not optimized away.
+        unsafe {
+            ptr::read_volatile(&page[0]);
+        }
+    }
+}
+
+/// Per file type settings of the `MmapDirectory`.
+///
+/// Files are identified by their extension.
+#[derive(Clone, Default)]
+struct MmapSettings {
+    advices: HashMap<String, Advice>,
+    preloaded_extensions: HashSet<String>,
+}
+
+fn extension(full_path: &Path) -> Option<&str> {
+    full_path.extension().and_then(|ext| ext.to_str())
+}
+
+impl MmapSettings {
+    /// Applies the advice of the file to a freshly mmapped file.
+    fn advise(&self, full_path: &Path, mmap: &MmapReadOnly) {
+        if let Some(&advice) = extension(full_path).and_then(|ext| self.advices.get(ext)) {
+            if let Err(e) = advise(mmap, advice) {
+                warn!("Failed to advise {:?} for {:?}: {:?}", advice, full_path, e);
+            }
+        }
+    }
+
+    /// Preloads a freshly mmapped file, if its extension requires it.
+    ///
+    /// Preloading reads the whole file, and should not be done
+    /// while holding the lock of the mmap cache.
+    fn preload(&self, full_path: &Path, mmap: &MmapReadOnly) {
+        if let Some(extension) = extension(full_path) {
+            if self.preloaded_extensions.contains(extension) {
+                debug!("Preloading {:?}", full_path);
+                preload(mmap);
+            }
+        }
+    }
+}
+
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct CacheCounters {
     // Number of time the cache prevents to call `mmap`
@@ -88,18 +198,25 @@ impl MmapCache {
         }
     }
 
-    fn get_mmap(&mut self, full_path: &Path) -> Result<Option<MmapReadOnly>, OpenReadError> {
+    /// Returns the mmap of the file, and whether it was
+    /// freshly mmapped.
+    fn get_mmap(
+        &mut self,
+        full_path: &Path,
+        settings: &MmapSettings,
+    ) -> Result<Option<(MmapReadOnly, bool)>, OpenReadError> {
         Ok(match self.cache.entry(full_path.to_owned()) {
             HashMapEntry::Occupied(occupied_entry) => {
                 let mmap = occupied_entry.get();
                 self.counters.hit += 1;
-                Some(mmap.clone())
+                Some((mmap.clone(), false))
             }
             HashMapEntry::Vacant(vacant_entry) => {
                 self.counters.miss += 1;
                 if let Some(mmap) = open_mmap(full_path)? {
+                    settings.advise(full_path, &mmap);
                     vacant_entry.insert(mmap.clone());
-                    Some(mmap)
+                    Some((mmap, true))
                 } else {
                     None
                 }
@@ -116,6 +233,7 @@ impl MmapCache {
 pub struct MmapDirectory {
     root_path: PathBuf,
     mmap_cache: Arc<RwLock<MmapCache>>,
+    settings: MmapSettings,
     _temp_directory: Arc<Option<TempDir>>,
 }
 
@@ -136,6 +254,7 @@ impl MmapDirectory {
         let directory = MmapDirectory {
             root_path: tempdir_path,
             mmap_cache: Arc::new(RwLock::new(MmapCache::default())),
+            settings: MmapSettings::default(),
             _temp_directory: Arc::new(Some(tempdir)),
         };
         Ok(directory)
@@ -159,11 +278,31 @@ impl MmapDirectory {
             Ok(MmapDirectory {
                 root_path: PathBuf::from(directory_path),
                 mmap_cache: Arc::new(RwLock::new(MmapCache::default())),
+                settings: MmapSettings::default(),
                 _temp_directory: Arc::new(None),
             })
         }
     }
 
+    /// Sets the access pattern advice given to the OS for the files
+    /// with the given extension (e.g. `"term"`, `"fast"`, `"store"`).
+    ///
+    /// The advice is applied when a file is mmapped. The directory
+    /// should therefore be configured before opening the index.
+    pub fn set_advice(&mut self, extension: &str, advice: Advice) {
+        self.settings.advices.insert(extension.to_string(), advice);
+    }
+
+    /// Makes the files with the given extension preloaded when they
+    /// are mmapped, by touching all of their pages.
+    ///
+    /// Preloading the term dictionary (`"term"`) and fast fields (`"fast"`)
+    /// moves the cost of the first queries after a cold start to the
+    /// opening of the index.
+    pub fn set_preload(&mut self, extension: &str) {
+        self.settings.preloaded_extensions.insert(extension.to_string());
+    }
+
     /// Joins a relative_path to the directory `root_path`
     /// to create a proper complete `filepath`.
     fn resolve_path(&self, relative_path: &Path) -> PathBuf {
@@ -242,19 +381,27 @@ impl Directory for MmapDirectory {
         debug!("Open Read {:?}", path);
         let full_path = self.resolve_path(path);
 
-        let mut mmap_cache = self.mmap_cache.write().map_err(|_| {
-            let msg = format!(
-                "Failed to acquired write lock \
-                 on mmap cache while reading {:?}",
-                path
-            );
-            IOError::with_path(path.to_owned(), make_io_err(msg))
-        })?;
+        let mmap_opt = {
+            let mut mmap_cache = self.mmap_cache.write().map_err(|_| {
+                let msg = format!(
+                    "Failed to acquired write lock \
+                     on mmap cache while reading {:?}",
+                    path
+                );
+                IOError::with_path(path.to_owned(), make_io_err(msg))
+            })?;
+            mmap_cache.get_mmap(&full_path, &self.settings)?
+        };
 
-        Ok(mmap_cache
-            .get_mmap(&full_path)?
-            .map(ReadOnlySource::Mmap)
-            .unwrap_or_else(|| ReadOnlySource::Anonymous(SharedVecSlice::empty())))
+        Ok(match mmap_opt {
+            Some((mmap, is_fresh)) => {
+                if is_fresh {
+                    self.settings.preload(&full_path, &mmap);
+                }
+                ReadOnlySource::Mmap(mmap)
+            }
+            None => ReadOnlySource::Anonymous(SharedVecSlice::empty()),
+        })
     }
 
     fn open_write(&mut self, path: &Path) -> Result<WritePtr, OpenWriteError> {
@@ -346,9 +493,24 @@ impl Directory for MmapDirectory {
     fn atomic_write(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
         debug!("Atomic Write {:?}", path);
         let full_path = self.resolve_path(path);
-        let meta_file = atomicwrites::AtomicFile::new(full_path, atomicwrites::AllowOverwrite);
-        meta_file.write(|f| f.write_all(data))?;
-        Ok(())
+        let temp_path = temp_path(&full_path);
+        {
+            let mut temp_file = OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(&temp_path)?;
+            temp_file.write_all(data)?;
+            temp_file.sync_all()?;
+        }
+        // `rename` atomically replaces the destination file, both
+        // on unix and on windows.
+        if let Err(e) = fs::rename(&temp_path, &full_path) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e);
+        }
+        // The rename is only persisted once the directory is synced.
+        self.sync_directory()
     }
 
     fn box_clone(&self) -> Box<Directory> {
@@ -420,4 +582,47 @@ mod tests {
         assert_eq!(mmap_directory.get_cache_info().mmapped.len(), 0);
     }
 
+    #[test]
+    fn test_advice_and_preload() {
+        let mut mmap_directory = MmapDirectory::create_from_tempdir().unwrap();
+        mmap_directory.set_advice("term", Advice::Random);
+        mmap_directory.set_advice("store", Advice::Sequential);
+        mmap_directory.set_preload("term");
+        mmap_directory.set_preload("fast");
+        let content: Vec<u8> = (0..10_000u32).map(|i| (i % 256) as u8).collect();
+        for filename in &["seg.term", "seg.fast", "seg.store", "seg.idx"] {
+            let path = PathBuf::from(filename);
+            {
+                let mut w = mmap_directory.open_write(&path).unwrap();
+                w.write_all(&content).unwrap();
+                w.flush().unwrap();
+            }
+            let source = mmap_directory.open_read(&path).unwrap();
+            assert_eq!(source.as_slice(), &content[..]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_advise() {
+        let mut mmap_directory = MmapDirectory::create_from_tempdir().unwrap();
+        let path = PathBuf::from("test");
+        {
+            let mut w = mmap_directory.open_write(&path).unwrap();
+            w.write_all(b"abc").unwrap();
+            w.flush().unwrap();
+        }
+        let mmap = open_mmap(&mmap_directory.resolve_path(&path))
+            .unwrap()
+            .unwrap();
+        for &advice in &[
+            Advice::Normal,
+            Advice::Random,
+            Advice::Sequential,
+            Advice::WillNeed,
+        ] {
+            advise(&mmap, advice).unwrap();
+        }
+    }
+
 }
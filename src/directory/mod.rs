@@ -3,12 +3,25 @@
 WORM directory abstraction.
 
 */
+#[cfg(feature = "mmap")]
 mod mmap_directory;
 mod ram_directory;
 mod directory;
 mod read_only_source;
 mod shared_vec_slice;
 mod managed_directory;
+mod directory_lock;
+mod footer;
+mod io_stats;
+mod hot_directory;
+mod async_directory;
+mod read_only_directory;
+mod packed_directory;
+mod static_directory;
+mod watch;
+mod faulty_directory;
+#[cfg(feature = "object_store")]
+mod object_store_directory;
 
 /// Errors specific to the directory module.
 pub mod error;
@@ -18,10 +31,25 @@ use std::io::{BufWriter, Seek, Write};
 pub use self::read_only_source::ReadOnlySource;
 pub use self::directory::Directory;
 pub use self::ram_directory::RAMDirectory;
-pub use self::mmap_directory::MmapDirectory;
+#[cfg(feature = "mmap")]
+pub use self::mmap_directory::{Advice, MmapDirectory};
+pub use self::async_directory::AsyncDirectory;
+pub use self::hot_directory::{AccessLog, HotDirectory};
+pub use self::io_stats::{FileTypeIoStats, IoStats};
+pub use self::read_only_directory::ReadOnlyDirectory;
+pub use self::packed_directory::PackedDirectory;
+pub use self::static_directory::StaticDirectory;
+pub use self::watch::{WatchCallback, WatchHandle};
+pub use self::faulty_directory::FaultyDirectory;
+#[cfg(feature = "object_store")]
+pub use self::object_store_directory::{BlockCache, MemoryBlockCache, ObjectStore,
+                                       ObjectStoreDirectory};
 
 pub(crate) use self::read_only_source::SourceRead;
+pub(crate) use self::packed_directory::PackWriter;
 pub(crate) use self::managed_directory::{FileProtection, ManagedDirectory};
+pub(crate) use self::directory_lock::DirectoryLock;
+pub(crate) use self::footer::{compute_checksum, split_footer};
 
 /// Synonym of Seek + Write
 pub trait SeekableWrite: Seek + Write {}
@@ -50,6 +78,7 @@ mod tests {
         test_directory(&mut ram_directory);
     }
 
+    #[cfg(feature = "mmap")]
     #[test]
     fn test_mmap_directory() {
         let mut mmap_directory = MmapDirectory::create_from_tempdir().unwrap();
@@ -0,0 +1,550 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufWriter, Cursor, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::result;
+use std::slice;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use directory::{Directory, ReadOnlySource};
+use directory::error::{DeleteError, IOError, OpenReadError, OpenWriteError};
+use directory::WritePtr;
+
+/// Default size of the blocks fetched from the object store.
+const DEFAULT_BLOCK_SIZE: u64 = 1 << 20;
+
+/// Minimal interface of an object store (S3, GCS, ...)
+/// required by the `ObjectStoreDirectory`.
+///
+/// Implementations are in charge of the network protocol,
+/// authentication, retries, etc.
+pub trait ObjectStore: Send + Sync + 'static {
+    /// Returns the length of an object, or `None`
+    /// if the object does not exist.
+    fn object_len(&self, key: &str) -> io::Result<Option<u64>>;
+
+    /// Reads the given byte range of an object.
+    ///
+    /// This is typically implemented as an HTTP range request.
+    fn get_range(&self, key: &str, range: Range<u64>) -> io::Result<Vec<u8>>;
+
+    /// Creates or replaces an object.
+    fn put(&self, key: &str, data: &[u8]) -> io::Result<()>;
+
+    /// Deletes an object.
+    ///
+    /// Returns false if the object did not exist.
+    fn delete(&self, key: &str) -> io::Result<bool>;
+}
+
+/// Local cache for the blocks read from the object store.
+pub trait BlockCache: Send + Sync + 'static {
+    /// Returns the block `block_id` of the object `key`, if it is cached.
+    fn get(&self, key: &str, block_id: u64) -> Option<Arc<Vec<u8>>>;
+
+    /// Adds a block to the cache.
+    fn put(&self, key: &str, block_id: u64, block: Arc<Vec<u8>>);
+
+    /// Removes all of the blocks of the object `key` from the cache.
+    ///
+    /// This is called whenever the object is written or deleted.
+    fn invalidate(&self, key: &str);
+}
+
+struct InnerMemoryBlockCache {
+    blocks: HashMap<(String, u64), Arc<Vec<u8>>>,
+    insertion_order: VecDeque<(String, u64)>,
+    num_bytes: usize,
+}
+
+/// `BlockCache` keeping blocks in anonymous memory.
+///
+/// Once the cache reaches its capacity, the
+/// oldest blocks are evicted first.
+pub struct MemoryBlockCache {
+    capacity_in_bytes: usize,
+    inner: Mutex<InnerMemoryBlockCache>,
+}
+
+impl MemoryBlockCache {
+    /// Creates a cache holding at most `capacity_in_bytes` bytes.
+    pub fn with_capacity(capacity_in_bytes: usize) -> MemoryBlockCache {
+        MemoryBlockCache {
+            capacity_in_bytes,
+            inner: Mutex::new(InnerMemoryBlockCache {
+                blocks: HashMap::new(),
+                insertion_order: VecDeque::new(),
+                num_bytes: 0,
+            }),
+        }
+    }
+}
+
+impl BlockCache for MemoryBlockCache {
+    fn get(&self, key: &str, block_id: u64) -> Option<Arc<Vec<u8>>> {
+        let inner = self.inner.lock().expect("Block cache lock poisoned.");
+        inner.blocks.get(&(key.to_string(), block_id)).cloned()
+    }
+
+    fn put(&self, key: &str, block_id: u64, block: Arc<Vec<u8>>) {
+        if block.len() > self.capacity_in_bytes {
+            return;
+        }
+        let mut inner = self.inner.lock().expect("Block cache lock poisoned.");
+        let block_key = (key.to_string(), block_id);
+        inner.num_bytes += block.len();
+        if let Some(previous_block) = inner.blocks.insert(block_key.clone(), block) {
+            inner.num_bytes -= previous_block.len();
+        } else {
+            inner.insertion_order.push_back(block_key);
+        }
+        while inner.num_bytes > self.capacity_in_bytes {
+            let evicted_key = match inner.insertion_order.pop_front() {
+                Some(evicted_key) => evicted_key,
+                None => break,
+            };
+            if let Some(evicted_block) = inner.blocks.remove(&evicted_key) {
+                inner.num_bytes -= evicted_block.len();
+            }
+        }
+    }
+
+    fn invalidate(&self, key: &str) {
+        let mut inner = self.inner.lock().expect("Block cache lock poisoned.");
+        let invalidated_keys: Vec<(String, u64)> = inner
+            .blocks
+            .keys()
+            .filter(|&&(ref block_key, _)| block_key == key)
+            .cloned()
+            .collect();
+        for invalidated_key in invalidated_keys {
+            if let Some(block) = inner.blocks.remove(&invalidated_key) {
+                inner.num_bytes -= block.len();
+            }
+        }
+        inner.insertion_order.retain(|&(ref block_key, _)| block_key != key);
+    }
+}
+
+/// Buffer holding an object, whose blocks are fetched
+/// the first time a slice covering them is read.
+///
+/// The buffer is allocated for the whole object upfront,
+/// but its pages are only committed as blocks are fetched.
+struct LazyObject {
+    directory: ObjectStoreDirectory,
+    key: String,
+    // only accessed through `data_ptr` once the object is created.
+    _data: Vec<u8>,
+    data_ptr: *mut u8,
+    len: usize,
+    fetched_blocks: Vec<AtomicBool>,
+    fetch_lock: Mutex<()>,
+}
+
+// The bytes of a block are only written before the block is
+// flagged as fetched, and only read after.
+unsafe impl Send for LazyObject {}
+unsafe impl Sync for LazyObject {}
+
+impl LazyObject {
+    fn new(directory: ObjectStoreDirectory, key: String, len: usize) -> LazyObject {
+        let block_size = directory.block_size as usize;
+        let num_blocks = (len + block_size - 1) / block_size;
+        let mut data = vec![0u8; len];
+        let data_ptr = data.as_mut_ptr();
+        LazyObject {
+            directory,
+            key,
+            _data: data,
+            data_ptr,
+            len,
+            fetched_blocks: (0..num_blocks).map(|_| AtomicBool::new(false)).collect(),
+            fetch_lock: Mutex::new(()),
+        }
+    }
+
+    /// Fetches the blocks covering the byte range `[from, to)`
+    /// that are not fetched yet.
+    fn fetch(&self, from: usize, to: usize) -> io::Result<()> {
+        if from >= to {
+            return Ok(());
+        }
+        let block_size = self.directory.block_size as usize;
+        let block_ids = from / block_size..(to + block_size - 1) / block_size;
+        if block_ids
+            .clone()
+            .all(|block_id| self.fetched_blocks[block_id].load(Ordering::Acquire))
+        {
+            return Ok(());
+        }
+        let _fetch_lock = self.fetch_lock.lock().expect("Fetch lock poisoned.");
+        for block_id in block_ids {
+            if self.fetched_blocks[block_id].load(Ordering::Acquire) {
+                continue;
+            }
+            let block = self.directory
+                .read_block(&self.key, block_id as u64, self.len as u64)?;
+            unsafe {
+                let block_ptr = self.data_ptr.offset((block_id * block_size) as isize);
+                ptr::copy_nonoverlapping(block.as_ptr(), block_ptr, block.len());
+            }
+            self.fetched_blocks[block_id].store(true, Ordering::Release);
+        }
+        Ok(())
+    }
+}
+
+/// Slice of an object of the `ObjectStoreDirectory`.
+///
+/// The data is fetched, through range requests, when the slice
+/// is read. As tantivy's read path is infallible, a failing
+/// range request panics.
+#[derive(Clone)]
+pub struct LazySlice {
+    object: Arc<LazyObject>,
+    start: usize,
+    len: usize,
+}
+
+impl LazySlice {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn as_slice(&self) -> &[u8] {
+        let end = self.start + self.len;
+        if let Err(e) = self.object.fetch(self.start, end) {
+            panic!("Failed to fetch {:?} from the object store: {:?}", self.object.key, e);
+        }
+        unsafe { slice::from_raw_parts(self.object.data_ptr.offset(self.start as isize), self.len) }
+    }
+
+    pub(crate) fn slice(&self, from_offset: usize, to_offset: usize) -> LazySlice {
+        assert!(from_offset <= to_offset && to_offset <= self.len);
+        LazySlice {
+            object: Arc::clone(&self.object),
+            start: self.start + from_offset,
+            len: to_offset - from_offset,
+        }
+    }
+}
+
+/// Writer associated with the `ObjectStoreDirectory`
+///
+/// Object stores do not support appends, so the
+/// data is buffered, and the whole object is uploaded
+/// upon each flush.
+struct ObjectWriter {
+    key: String,
+    directory: ObjectStoreDirectory,
+    data: Cursor<Vec<u8>>,
+}
+
+impl Seek for ObjectWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.data.seek(pos)
+    }
+}
+
+impl Write for ObjectWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.directory.put(&self.key, self.data.get_ref())
+    }
+}
+
+/// Directory reading and writing index files in an object store.
+///
+/// Files are read via range requests of `block_size` bytes, and
+/// the blocks are kept in a local `BlockCache`. Opening a file
+/// does not fetch anything: only the blocks covering the slices
+/// that are actually read are fetched.
+///
+/// This directory is meant to serve mostly static indexes.
+/// It is only available with the `object_store` feature.
+#[derive(Clone)]
+pub struct ObjectStoreDirectory {
+    object_store: Arc<ObjectStore>,
+    block_cache: Arc<BlockCache>,
+    prefix: String,
+    block_size: u64,
+}
+
+impl fmt::Debug for ObjectStoreDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ObjectStoreDirectory({:?})", self.prefix)
+    }
+}
+
+impl ObjectStoreDirectory {
+    /// Creates a directory storing its files in `object_store`,
+    /// under the keys starting with `prefix`.
+    pub fn new<S: ObjectStore, C: BlockCache>(
+        object_store: S,
+        prefix: &str,
+        block_cache: C,
+    ) -> ObjectStoreDirectory {
+        ObjectStoreDirectory {
+            object_store: Arc::new(object_store),
+            block_cache: Arc::new(block_cache),
+            prefix: prefix.to_string(),
+            block_size: DEFAULT_BLOCK_SIZE,
+        }
+    }
+
+    /// Sets the size of the range requests sent to the object store.
+    ///
+    /// # Panics
+    /// If `block_size` is 0.
+    pub fn set_block_size(&mut self, block_size: u64) {
+        assert!(block_size > 0, "The block size must be strictly positive.");
+        self.block_size = block_size;
+    }
+
+    fn key(&self, path: &Path) -> String {
+        format!("{}{}", self.prefix, path.to_string_lossy())
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+        self.block_cache.invalidate(key);
+        self.object_store.put(key, data)
+    }
+
+    fn read_block(&self, key: &str, block_id: u64, object_len: u64) -> io::Result<Arc<Vec<u8>>> {
+        if let Some(block) = self.block_cache.get(key, block_id) {
+            return Ok(block);
+        }
+        let start = block_id * self.block_size;
+        let end = ::std::cmp::min(start + self.block_size, object_len);
+        let block = Arc::new(self.object_store.get_range(key, start..end)?);
+        if block.len() as u64 != end - start {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("Range request on {} returned a truncated block", key),
+            ));
+        }
+        self.block_cache.put(key, block_id, Arc::clone(&block));
+        Ok(block)
+    }
+
+    fn open_object(&self, key: &str) -> io::Result<Option<ReadOnlySource>> {
+        let object_len = match self.object_store.object_len(key)? {
+            Some(object_len) => object_len as usize,
+            None => {
+                return Ok(None);
+            }
+        };
+        let lazy_object = LazyObject::new(self.clone(), key.to_string(), object_len);
+        Ok(Some(ReadOnlySource::Lazy(LazySlice {
+            object: Arc::new(lazy_object),
+            start: 0,
+            len: object_len,
+        })))
+    }
+
+    fn read_object(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+        let object_len = match self.object_store.object_len(key)? {
+            Some(object_len) => object_len,
+            None => {
+                return Ok(None);
+            }
+        };
+        let num_blocks = (object_len + self.block_size - 1) / self.block_size;
+        let mut data = Vec::with_capacity(object_len as usize);
+        for block_id in 0..num_blocks {
+            let block = self.read_block(key, block_id, object_len)?;
+            data.extend_from_slice(&block[..]);
+        }
+        Ok(Some(data))
+    }
+}
+
+impl Directory for ObjectStoreDirectory {
+    fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
+        debug!("Open Read {:?}", path);
+        self.open_object(&self.key(path))
+            .map_err(|e| IOError::with_path(path.to_owned(), e))?
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_owned()))
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        debug!("Deleting file {:?}", path);
+        let key = self.key(path);
+        self.block_cache.invalidate(&key);
+        let existed = self.object_store
+            .delete(&key)
+            .map_err(|e| IOError::with_path(path.to_owned(), e))?;
+        if existed {
+            Ok(())
+        } else {
+            Err(DeleteError::FileDoesNotExist(path.to_owned()))
+        }
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        match self.object_store.object_len(&self.key(path)) {
+            Ok(object_len_opt) => object_len_opt.is_some(),
+            Err(e) => {
+                warn!("Failed to check if {:?} exists: {:?}", path, e);
+                false
+            }
+        }
+    }
+
+    fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        debug!("Open Write {:?}", path);
+        if self.exists(path) {
+            return Err(OpenWriteError::FileAlreadyExists(PathBuf::from(path)));
+        }
+        let key = self.key(path);
+        // force the creation of the file to mimic the MMap directory.
+        self.put(&key, &[]).map_err(|e| IOError::with_path(path.to_owned(), e))?;
+        let object_writer = ObjectWriter {
+            key,
+            directory: self.clone(),
+            data: Cursor::new(Vec::new()),
+        };
+        Ok(BufWriter::new(Box::new(object_writer)))
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        self.read_object(&self.key(path))
+            .map_err(|e| IOError::with_path(path.to_owned(), e))?
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_owned()))
+    }
+
+    fn atomic_write(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
+        debug!("Atomic Write {:?}", path);
+        // Object stores replace objects atomically.
+        self.put(&self.key(path), data)
+    }
+
+    fn box_clone(&self) -> Box<Directory> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use common::HasLen;
+    use Index;
+    use schema::{SchemaBuilder, TEXT};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Clone, Default)]
+    struct InMemoryObjectStore {
+        objects: Arc<Mutex<HashMap<String, Vec<u8>>>>,
+        num_range_requests: Arc<AtomicUsize>,
+    }
+
+    impl ObjectStore for InMemoryObjectStore {
+        fn object_len(&self, key: &str) -> io::Result<Option<u64>> {
+            let objects = self.objects.lock().unwrap();
+            Ok(objects.get(key).map(|data| data.len() as u64))
+        }
+
+        fn get_range(&self, key: &str, range: Range<u64>) -> io::Result<Vec<u8>> {
+            self.num_range_requests.fetch_add(1, Ordering::SeqCst);
+            let objects = self.objects.lock().unwrap();
+            let data = objects
+                .get(key)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, key.to_string()))?;
+            Ok(data[range.start as usize..range.end as usize].to_vec())
+        }
+
+        fn put(&self, key: &str, data: &[u8]) -> io::Result<()> {
+            let mut objects = self.objects.lock().unwrap();
+            objects.insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> io::Result<bool> {
+            let mut objects = self.objects.lock().unwrap();
+            Ok(objects.remove(key).is_some())
+        }
+    }
+
+    #[test]
+    fn test_object_store_directory() {
+        let object_store = InMemoryObjectStore::default();
+        let mut directory = ObjectStoreDirectory::new(
+            object_store.clone(),
+            "index/",
+            MemoryBlockCache::with_capacity(1_000),
+        );
+        directory.set_block_size(4);
+        let path = PathBuf::from("test");
+        {
+            let mut w = directory.open_write(&path).unwrap();
+            w.write_all(&[1, 2, 3, 4, 5, 6, 7, 8, 9]).unwrap();
+            w.flush().unwrap();
+        }
+        assert!(object_store.objects.lock().unwrap().contains_key("index/test"));
+        assert!(directory.open_write(&path).is_err());
+        let data = [1u8, 2, 3, 4, 5, 6, 7, 8, 9];
+        let source = directory.open_read(&path).unwrap();
+        assert_eq!(source.len(), 9);
+        assert_eq!(object_store.num_range_requests.load(Ordering::SeqCst), 0);
+        // only the block covering the slice is fetched.
+        assert_eq!(source.slice(5, 7).as_slice(), &data[5..7]);
+        assert_eq!(object_store.num_range_requests.load(Ordering::SeqCst), 1);
+        assert_eq!(source.as_slice(), &data);
+        assert_eq!(object_store.num_range_requests.load(Ordering::SeqCst), 3);
+        // the second read is served by the cache.
+        assert_eq!(directory.open_read(&path).unwrap().as_slice(), &data);
+        assert_eq!(object_store.num_range_requests.load(Ordering::SeqCst), 3);
+        directory.atomic_write(&path, &[3]).unwrap();
+        assert_eq!(directory.atomic_read(&path).unwrap(), vec![3]);
+        directory.delete(&path).unwrap();
+        assert!(!directory.exists(&path));
+    }
+
+    #[test]
+    fn test_memory_block_cache_eviction() {
+        let block_cache = MemoryBlockCache::with_capacity(10);
+        block_cache.put("a", 0, Arc::new(vec![0u8; 6]));
+        block_cache.put("a", 1, Arc::new(vec![0u8; 4]));
+        assert!(block_cache.get("a", 0).is_some());
+        block_cache.put("b", 0, Arc::new(vec![0u8; 4]));
+        assert!(block_cache.get("a", 0).is_none());
+        assert!(block_cache.get("a", 1).is_some());
+        block_cache.invalidate("a");
+        assert!(block_cache.get("a", 1).is_none());
+        assert!(block_cache.get("b", 0).is_some());
+    }
+
+    #[test]
+    fn test_index_in_object_store() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let object_store = InMemoryObjectStore::default();
+        let directory = ObjectStoreDirectory::new(
+            object_store.clone(),
+            "index/",
+            MemoryBlockCache::with_capacity(1_000_000),
+        );
+        {
+            let managed_directory = ::directory::ManagedDirectory::new(directory).unwrap();
+            let index = Index::from_directory(managed_directory, schema_builder.build()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a"));
+            index_writer.commit().unwrap();
+        }
+        let directory = ObjectStoreDirectory::new(
+            object_store,
+            "index/",
+            MemoryBlockCache::with_capacity(1_000_000),
+        );
+        let index = Index::open_directory(directory).unwrap();
+        assert_eq!(index.searcher().num_docs(), 1);
+    }
+}
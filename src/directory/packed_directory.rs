@@ -0,0 +1,321 @@
+use common::{BinarySerializable, CountingWriter, HasLen};
+use core::META_FILEPATH;
+use directory::{Directory, ReadOnlySource, WritePtr};
+use error::DataCorruption;
+use directory::error::{read_only_error, DeleteError, IOError, OpenReadError,
+                       OpenWriteError};
+#[cfg(feature = "mmap")]
+use fst::raw::MmapReadOnly;
+use std::collections::HashMap;
+use std::fmt;
+#[cfg(feature = "mmap")]
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::result;
+use std::sync::Arc;
+
+/// Magic bytes opening and closing a packed index.
+const MAGIC_BYTES: &[u8; 8] = b"TANTVPAK";
+
+/// Version of the packed index format.
+const FORMAT_VERSION: u32 = 1;
+
+/// Length of the trailer: the offset of the file table, followed by the magic bytes.
+const TRAILER_LEN: usize = 8 + 8;
+
+fn invalid_data_err(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+const READ_ONLY_REASON: &str = "A packed index cannot be modified.";
+
+/// Writes a set of files into a single packed file.
+///
+/// A packed file is made of
+/// - magic bytes and a format version,
+/// - the content of all of the files, one after the other,
+/// - a table with, for each file, its path, its offset and its length,
+/// - the offset of the table, followed by the magic bytes again.
+///
+/// Because the table is written last, files can be streamed
+/// into the output without knowing them all in advance.
+pub(crate) struct PackWriter<W: Write> {
+    writer: CountingWriter<W>,
+    files: Vec<(PathBuf, u64, u64)>,
+}
+
+impl<W: Write> PackWriter<W> {
+    pub fn new(writer: W) -> io::Result<PackWriter<W>> {
+        let mut writer = CountingWriter::wrap(writer);
+        writer.write_all(MAGIC_BYTES)?;
+        FORMAT_VERSION.serialize(&mut writer)?;
+        Ok(PackWriter {
+            writer,
+            files: Vec::new(),
+        })
+    }
+
+    /// Appends a file to the pack.
+    pub fn add_file(&mut self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let offset = self.writer.written_bytes() as u64;
+        self.writer.write_all(data)?;
+        self.files
+            .push((path.to_owned(), offset, data.len() as u64));
+        Ok(())
+    }
+
+    /// Writes the file table and flushes the pack.
+    pub fn finish(mut self) -> io::Result<W> {
+        let table_offset = self.writer.written_bytes() as u64;
+        (self.files.len() as u64).serialize(&mut self.writer)?;
+        for &(ref path, offset, len) in &self.files {
+            let path_str = path.to_str().ok_or_else(|| {
+                invalid_data_err(format!("Path {:?} is not valid utf-8", path))
+            })?;
+            path_str.to_string().serialize(&mut self.writer)?;
+            offset.serialize(&mut self.writer)?;
+            len.serialize(&mut self.writer)?;
+        }
+        table_offset.serialize(&mut self.writer)?;
+        self.writer.write_all(MAGIC_BYTES)?;
+        let (mut writer, _) = self.writer.finish()?;
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+/// Read-only `Directory` over an index packed into a single file.
+///
+/// Packing an index makes it possible to distribute it as one
+/// artifact, which is convenient to ship it to mobile or embedded
+/// devices, or to serve it from a CDN. Packs are produced by
+/// `IndexSnapshot::pack_to(...)`, and opened with `Index::open_packed(...)`.
+///
+/// The files are not copied: they are served as slices of the
+/// packed file, which is mmapped when opened with `.open(...)`.
+#[derive(Clone)]
+pub struct PackedDirectory {
+    source: ReadOnlySource,
+    files: Arc<HashMap<PathBuf, (usize, usize)>>,
+}
+
+impl PackedDirectory {
+    /// Opens a packed file by mmapping it.
+    #[cfg(feature = "mmap")]
+    pub fn open<P: AsRef<Path>>(path: P) -> result::Result<PackedDirectory, OpenReadError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| {
+            if e.kind() == io::ErrorKind::NotFound {
+                OpenReadError::FileDoesNotExist(path.to_owned())
+            } else {
+                OpenReadError::IOError(IOError::with_path(path.to_owned(), e))
+            }
+        })?;
+        let mmap = MmapReadOnly::open(&file)
+            .map_err(|e| IOError::with_path(path.to_owned(), e))?;
+        let packed_directory = PackedDirectory::from_source(ReadOnlySource::Mmap(mmap))
+            .map_err(|e| IOError::with_path(path.to_owned(), e))?;
+        Ok(packed_directory)
+    }
+
+    /// Creates a `PackedDirectory` from the content of a packed file.
+    pub fn from_source(source: ReadOnlySource) -> io::Result<PackedDirectory> {
+        let files = {
+            let data = source.as_slice();
+            if data.len() < MAGIC_BYTES.len() + 4 + TRAILER_LEN
+                || &data[..MAGIC_BYTES.len()] != MAGIC_BYTES
+                || &data[data.len() - MAGIC_BYTES.len()..] != MAGIC_BYTES
+            {
+                return Err(invalid_data_err("The data is not a packed index".to_string()));
+            }
+            let format_version = u32::deserialize(&mut &data[MAGIC_BYTES.len()..])?;
+            if format_version != FORMAT_VERSION {
+                return Err(invalid_data_err(format!(
+                    "Unsupported packed index format version {}",
+                    format_version
+                )));
+            }
+            let table_end = data.len() - TRAILER_LEN;
+            let table_offset = u64::deserialize(&mut &data[table_end..])?;
+            if table_offset > table_end as u64 {
+                return Err(DataCorruption::comment_only("The file table is corrupted").into());
+            }
+            let table_offset = table_offset as usize;
+            let mut cursor = &data[table_offset..table_end];
+            let num_files = u64::deserialize(&mut cursor)?;
+            let mut files = HashMap::new();
+            for _ in 0..num_files {
+                let path = PathBuf::from(String::deserialize(&mut cursor)?);
+                let offset = u64::deserialize(&mut cursor)?;
+                let len = u64::deserialize(&mut cursor)?;
+                // the files are stored before the file table.
+                let end = match offset.checked_add(len) {
+                    Some(end) if end <= table_offset as u64 => end,
+                    _ => {
+                        let msg = format!("File {:?} is out of the bounds of the pack", path);
+                        return Err(DataCorruption::comment_only(msg).into());
+                    }
+                };
+                files.insert(path, (offset as usize, end as usize));
+            }
+            files
+        };
+        Ok(PackedDirectory {
+            source,
+            files: Arc::new(files),
+        })
+    }
+
+    /// Returns the paths of the files of the pack, sorted.
+    pub fn files(&self) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self.files.keys().map(|path| path.as_path()).collect();
+        paths.sort();
+        paths
+    }
+
+    /// Extracts all of the files of the pack into a directory.
+    ///
+    /// The `meta.json` file is written last, so that
+    /// the target directory only contains a valid index
+    /// once all of the files are extracted.
+    ///
+    /// Files are extracted as is, checksum footer included.
+    /// The target should therefore not be a managed directory.
+    pub fn unpack_to(&self, target: &mut Directory) -> io::Result<()> {
+        for path in self.files() {
+            if path == META_FILEPATH.as_path() {
+                continue;
+            }
+            let (start, end) = self.files[path];
+            let mut target_write = target
+                .open_write(path)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+            target_write.write_all(&self.source.as_slice()[start..end])?;
+            target_write.flush()?;
+        }
+        if let Some(&(start, end)) = self.files.get(&*META_FILEPATH) {
+            target.atomic_write(&META_FILEPATH, &self.source.as_slice()[start..end])?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for PackedDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PackedDirectory(num_files={}, len={})",
+            self.files.len(),
+            self.source.len()
+        )
+    }
+}
+
+impl Directory for PackedDirectory {
+    fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
+        let &(start, end) = self.files
+            .get(path)
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_owned()))?;
+        Ok(self.source.slice(start, end))
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        Err(DeleteError::IOError(IOError::with_path(
+            path.to_owned(),
+            read_only_error(READ_ONLY_REASON),
+        )))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::IOError(IOError::with_path(
+            path.to_owned(),
+            read_only_error(READ_ONLY_REASON),
+        )))
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        let source = self.open_read(path)?;
+        Ok(source.as_slice().to_owned())
+    }
+
+    fn atomic_write(&mut self, _: &Path, _: &[u8]) -> io::Result<()> {
+        Err(read_only_error(READ_ONLY_REASON))
+    }
+
+    fn box_clone(&self) -> Box<Directory> {
+        box self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use directory::RAMDirectory;
+    use std::path::Path;
+
+    fn pack(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut pack_writer = PackWriter::new(Vec::new()).unwrap();
+        for &(path, data) in files {
+            pack_writer.add_file(Path::new(path), data).unwrap();
+        }
+        pack_writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_packed_directory() {
+        let packed_data = pack(&[("a", b"hello"), ("meta.json", b"{}"), ("b", b"")]);
+        let mut directory =
+            PackedDirectory::from_source(ReadOnlySource::from(packed_data)).unwrap();
+        assert_eq!(
+            directory.files(),
+            vec![Path::new("a"), Path::new("b"), Path::new("meta.json")]
+        );
+        assert_eq!(directory.open_read(Path::new("a")).unwrap().as_slice(), b"hello");
+        assert_eq!(directory.open_read(Path::new("b")).unwrap().as_slice(), b"");
+        assert_eq!(&directory.atomic_read(Path::new("meta.json")).unwrap()[..], b"{}");
+        assert!(!directory.exists(Path::new("c")));
+        assert!(directory.open_read(Path::new("c")).is_err());
+        assert!(directory.open_write(Path::new("c")).is_err());
+        assert!(directory.delete(Path::new("a")).is_err());
+
+        let mut ram_directory = RAMDirectory::create();
+        directory.unpack_to(&mut ram_directory).unwrap();
+        assert_eq!(&ram_directory.atomic_read(Path::new("a")).unwrap()[..], b"hello");
+        assert_eq!(&ram_directory.atomic_read(Path::new("meta.json")).unwrap()[..], b"{}");
+    }
+
+    #[test]
+    fn test_packed_directory_invalid() {
+        assert!(PackedDirectory::from_source(ReadOnlySource::from(b"hello".to_vec())).is_err());
+        let mut packed_data = pack(&[("a", b"hello")]);
+        let len = packed_data.len();
+        packed_data.truncate(len - 1);
+        assert!(PackedDirectory::from_source(ReadOnlySource::from(packed_data)).is_err());
+    }
+
+    #[test]
+    fn test_packed_directory_out_of_bounds() {
+        let packed_data = pack(&[("a", b"hello")]);
+        let len = packed_data.len();
+        // the offset of the file is followed by its length,
+        // the offset of the table and the magic bytes.
+        let offset_start = len - 8 - TRAILER_LEN - 8;
+        let mut overflowing_data = packed_data.clone();
+        for b in &mut overflowing_data[offset_start..offset_start + 8] {
+            *b = 255u8;
+        }
+        let err = PackedDirectory::from_source(ReadOnlySource::from(overflowing_data))
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let mut out_of_bounds_data = packed_data.clone();
+        out_of_bounds_data[offset_start] = 100u8;
+        assert!(PackedDirectory::from_source(ReadOnlySource::from(out_of_bounds_data)).is_err());
+    }
+}
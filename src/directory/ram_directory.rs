@@ -1,15 +1,27 @@
 use std::collections::HashMap;
 use std::fmt;
-use std::io::{self, BufWriter, Cursor, Seek, SeekFrom, Write};
+use std::io::{self, BufWriter, Cursor, Read, Seek, SeekFrom, Write};
 use std::path::{Path, PathBuf};
 use std::result;
 use std::sync::{Arc, RwLock};
 use common::make_io_err;
+use common::BinarySerializable;
+use core::LOCKFILE_FILEPATH;
 use directory::{Directory, ReadOnlySource};
 use directory::error::{DeleteError, IOError, OpenReadError, OpenWriteError};
 use directory::WritePtr;
 use super::shared_vec_slice::SharedVecSlice;
 
+/// Magic bytes opening a serialized `RAMDirectory`.
+const MAGIC_BYTES: &[u8; 8] = b"TANTVRAM";
+
+/// Version of the serialization format of the `RAMDirectory`.
+const FORMAT_VERSION: u32 = 1;
+
+fn invalid_data_err(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
 /// Writer associated with the `RAMDirectory`
 ///
 /// The Writer just writes a buffer.
@@ -134,6 +146,18 @@ impl InnerDirectory {
             .expect("Failed to get read lock directory.")
             .contains_key(path)
     }
+
+    fn files(&self) -> io::Result<Vec<(PathBuf, Arc<Vec<u8>>)>> {
+        let readable_map = self.0.read().map_err(|_| {
+            make_io_err("Failed to acquire read lock for the directory.".to_string())
+        })?;
+        let mut files: Vec<(PathBuf, Arc<Vec<u8>>)> = readable_map
+            .iter()
+            .map(|(path, data)| (path.clone(), Arc::clone(data)))
+            .collect();
+        files.sort_by(|left, right| left.0.cmp(&right.0));
+        Ok(files)
+    }
 }
 
 impl fmt::Debug for RAMDirectory {
@@ -159,6 +183,67 @@ impl RAMDirectory {
             fs: InnerDirectory::new(),
         }
     }
+
+    /// Serializes the content of the directory into a single buffer.
+    ///
+    /// The buffer starts with magic bytes and a format version,
+    /// followed by the number of files, and for each file,
+    /// its path and its content.
+    ///
+    /// The lockfile of the `IndexWriter` is not serialized.
+    pub fn to_bytes(&self) -> io::Result<Vec<u8>> {
+        let files: Vec<(PathBuf, Arc<Vec<u8>>)> = self.fs
+            .files()?
+            .into_iter()
+            .filter(|&(ref path, _)| path != &*LOCKFILE_FILEPATH)
+            .collect();
+        let mut buffer = Vec::new();
+        buffer.write_all(MAGIC_BYTES)?;
+        FORMAT_VERSION.serialize(&mut buffer)?;
+        (files.len() as u64).serialize(&mut buffer)?;
+        for (path, data) in files {
+            let path_str = path.to_str().ok_or_else(|| {
+                invalid_data_err(format!("Path {:?} is not valid utf-8", path))
+            })?;
+            path_str.to_string().serialize(&mut buffer)?;
+            (data.len() as u64).serialize(&mut buffer)?;
+            buffer.write_all(&data[..])?;
+        }
+        Ok(buffer)
+    }
+
+    /// Creates a `RAMDirectory` from a buffer produced
+    /// by `.to_bytes()`.
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<RAMDirectory> {
+        let mut cursor = bytes;
+        let mut magic_bytes = [0u8; 8];
+        cursor.read_exact(&mut magic_bytes)?;
+        if &magic_bytes != MAGIC_BYTES {
+            return Err(invalid_data_err(
+                "The buffer is not a serialized RAMDirectory".to_string(),
+            ));
+        }
+        let format_version = u32::deserialize(&mut cursor)?;
+        if format_version != FORMAT_VERSION {
+            return Err(invalid_data_err(format!(
+                "Unsupported RAMDirectory format version {}",
+                format_version
+            )));
+        }
+        let directory = RAMDirectory::create();
+        let num_files = u64::deserialize(&mut cursor)?;
+        for _ in 0..num_files {
+            let path = PathBuf::from(String::deserialize(&mut cursor)?);
+            let len = u64::deserialize(&mut cursor)? as usize;
+            if len > cursor.len() {
+                return Err(invalid_data_err(format!("File {:?} is truncated", path)));
+            }
+            let (data, remaining) = cursor.split_at(len);
+            directory.fs.write(path, data)?;
+            cursor = remaining;
+        }
+        Ok(directory)
+    }
 }
 
 impl Directory for RAMDirectory {
@@ -208,3 +293,51 @@ impl Directory for RAMDirectory {
         Box::new(self.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::RAMDirectory;
+    use Index;
+    use directory::{Directory, ManagedDirectory};
+    use schema::{SchemaBuilder, TEXT};
+    use std::io::Write;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ram_directory_to_from_bytes() {
+        let mut directory = RAMDirectory::create();
+        let path = PathBuf::from("test");
+        {
+            let mut w = directory.open_write(&path).unwrap();
+            w.write_all(&[4, 3, 7]).unwrap();
+            w.flush().unwrap();
+        }
+        directory.atomic_write(&PathBuf::from("meta"), b"{}").unwrap();
+        let bytes = directory.to_bytes().unwrap();
+        let reloaded = RAMDirectory::from_bytes(&bytes).unwrap();
+        assert_eq!(reloaded.open_read(&path).unwrap().as_slice(), &[4, 3, 7]);
+        assert_eq!(reloaded.atomic_read(&PathBuf::from("meta")).unwrap(), b"{}");
+        assert!(RAMDirectory::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(RAMDirectory::from_bytes(b"not a directory").is_err());
+    }
+
+    #[test]
+    fn test_ram_directory_index_to_from_bytes() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let directory = RAMDirectory::create();
+        let bytes = {
+            let managed_directory = ManagedDirectory::new(directory.clone()).unwrap();
+            let index = Index::from_directory(managed_directory, schema_builder.build()).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a"));
+            index_writer.commit().unwrap();
+            directory.to_bytes().unwrap()
+        };
+        let index = Index::open_directory(RAMDirectory::from_bytes(&bytes).unwrap()).unwrap();
+        assert_eq!(index.searcher().num_docs(), 1);
+        // the lockfile was not serialized.
+        assert!(index.writer(40_000_000).is_ok());
+    }
+}
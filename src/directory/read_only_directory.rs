@@ -0,0 +1,100 @@
+use directory::{Directory, ReadOnlySource, WritePtr};
+use directory::error::{read_only_error, DeleteError, IOError, OpenReadError,
+                       OpenWriteError};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::result;
+
+const READ_ONLY_REASON: &str = "The directory was opened in read-only mode.";
+
+/// Wraps a `Directory` and rejects all of the operations
+/// that would modify it.
+///
+/// This makes it possible to open an index stored on
+/// an immutable medium, with the guarantee that tantivy
+/// will never try to write or delete any file.
+pub struct ReadOnlyDirectory {
+    directory: Box<Directory>,
+}
+
+impl ReadOnlyDirectory {
+    /// Wraps a directory.
+    pub fn wrap<Dir: Directory>(directory: Dir) -> ReadOnlyDirectory {
+        ReadOnlyDirectory {
+            directory: box directory,
+        }
+    }
+}
+
+impl fmt::Debug for ReadOnlyDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ReadOnlyDirectory({:?})", self.directory)
+    }
+}
+
+impl Clone for ReadOnlyDirectory {
+    fn clone(&self) -> ReadOnlyDirectory {
+        ReadOnlyDirectory {
+            directory: self.directory.box_clone(),
+        }
+    }
+}
+
+impl Directory for ReadOnlyDirectory {
+    fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
+        self.directory.open_read(path)
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        Err(DeleteError::IOError(IOError::with_path(
+            path.to_owned(),
+            read_only_error(READ_ONLY_REASON),
+        )))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.directory.exists(path)
+    }
+
+    fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::IOError(IOError::with_path(
+            path.to_owned(),
+            read_only_error(READ_ONLY_REASON),
+        )))
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        self.directory.atomic_read(path)
+    }
+
+    fn atomic_write(&mut self, _: &Path, _: &[u8]) -> io::Result<()> {
+        Err(read_only_error(READ_ONLY_REASON))
+    }
+
+    fn box_clone(&self) -> Box<Directory> {
+        box self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use directory::RAMDirectory;
+    use std::path::Path;
+
+    #[test]
+    fn test_read_only_directory() {
+        let path = Path::new("test");
+        let mut ram_directory = RAMDirectory::create();
+        ram_directory.atomic_write(path, b"hello").unwrap();
+        let mut directory = ReadOnlyDirectory::wrap(ram_directory);
+        assert!(directory.exists(path));
+        assert_eq!(&directory.atomic_read(path).unwrap()[..], b"hello");
+        assert!(directory.atomic_write(path, b"bye").is_err());
+        assert!(directory.open_write(Path::new("other")).is_err());
+        assert!(directory.delete(path).is_err());
+        assert_eq!(&directory.atomic_read(path).unwrap()[..], b"hello");
+    }
+}
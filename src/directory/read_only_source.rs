@@ -1,8 +1,12 @@
+#[cfg(feature = "mmap")]
 use fst::raw::MmapReadOnly;
 use std::ops::Deref;
 use super::shared_vec_slice::SharedVecSlice;
+#[cfg(feature = "object_store")]
+use super::object_store_directory::LazySlice;
 use common::HasLen;
 use std::slice;
+use std::sync::Arc;
 use std::io::{self, Read};
 use stable_deref_trait::{CloneStableDeref, StableDeref};
 
@@ -14,9 +18,16 @@ use stable_deref_trait::{CloneStableDeref, StableDeref};
 /// hold by this object should never be altered or destroyed.
 pub enum ReadOnlySource {
     /// Mmap source of data
+    #[cfg(feature = "mmap")]
     Mmap(MmapReadOnly),
     /// Wrapping a `Vec<u8>`
     Anonymous(SharedVecSlice),
+    /// Wrapping a static slice, for instance embedded
+    /// in the binary with `include_bytes!`
+    Static(&'static [u8]),
+    /// Slice of an object store file, fetched on demand
+    #[cfg(feature = "object_store")]
+    Lazy(LazySlice),
 }
 
 unsafe impl StableDeref for ReadOnlySource {}
@@ -39,8 +50,12 @@ impl ReadOnlySource {
     /// Returns the data underlying the ReadOnlySource object.
     pub fn as_slice(&self) -> &[u8] {
         match *self {
+            #[cfg(feature = "mmap")]
             ReadOnlySource::Mmap(ref mmap_read_only) => unsafe { mmap_read_only.as_slice() },
             ReadOnlySource::Anonymous(ref shared_vec) => shared_vec.as_slice(),
+            ReadOnlySource::Static(data) => data,
+            #[cfg(feature = "object_store")]
+            ReadOnlySource::Lazy(ref lazy_slice) => lazy_slice.as_slice(),
         }
     }
 
@@ -64,6 +79,7 @@ impl ReadOnlySource {
     /// are retained in memory.
     pub fn slice(&self, from_offset: usize, to_offset: usize) -> ReadOnlySource {
         match *self {
+            #[cfg(feature = "mmap")]
             ReadOnlySource::Mmap(ref mmap_read_only) => {
                 let sliced_mmap = mmap_read_only.range(from_offset, to_offset - from_offset);
                 ReadOnlySource::Mmap(sliced_mmap)
@@ -71,6 +87,11 @@ impl ReadOnlySource {
             ReadOnlySource::Anonymous(ref shared_vec) => {
                 ReadOnlySource::Anonymous(shared_vec.slice(from_offset, to_offset))
             }
+            ReadOnlySource::Static(data) => ReadOnlySource::Static(&data[from_offset..to_offset]),
+            #[cfg(feature = "object_store")]
+            ReadOnlySource::Lazy(ref lazy_slice) => {
+                ReadOnlySource::Lazy(lazy_slice.slice(from_offset, to_offset))
+            }
         }
     }
 
@@ -94,7 +115,12 @@ impl ReadOnlySource {
 
 impl HasLen for ReadOnlySource {
     fn len(&self) -> usize {
-        self.as_slice().len()
+        match *self {
+            // does not fetch the data.
+            #[cfg(feature = "object_store")]
+            ReadOnlySource::Lazy(ref lazy_slice) => lazy_slice.len(),
+            _ => self.as_slice().len(),
+        }
     }
 }
 
@@ -111,7 +137,20 @@ impl From<Vec<u8>> for ReadOnlySource {
     }
 }
 
+impl From<Arc<Vec<u8>>> for ReadOnlySource {
+    fn from(data: Arc<Vec<u8>>) -> ReadOnlySource {
+        ReadOnlySource::Anonymous(SharedVecSlice::new(data))
+    }
+}
+
+impl From<&'static [u8]> for ReadOnlySource {
+    fn from(data: &'static [u8]) -> ReadOnlySource {
+        ReadOnlySource::Static(data)
+    }
+}
+
 /// Acts as a owning cursor over the data backed up by a `ReadOnlySource`
+#[derive(Clone)]
 pub(crate) struct SourceRead {
     _data_owner: ReadOnlySource,
     cursor: &'static [u8],
@@ -0,0 +1,146 @@
+use directory::{Directory, ReadOnlySource, WritePtr};
+use directory::error::{read_only_error, DeleteError, IOError, OpenReadError,
+                       OpenWriteError};
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::result;
+
+const READ_ONLY_REASON: &str = "A static directory cannot be modified.";
+
+/// Read-only `Directory` serving files from byte slices held in memory.
+///
+/// The files are never copied. They typically are `&'static [u8]`
+/// embedded in the binary with `include_bytes!`, which makes it possible
+/// to query a pre-built index without any filesystem,
+/// for instance on embedded targets.
+///
+/// ```rust,ignore
+/// let mut directory = StaticDirectory::default();
+/// directory.add_file("meta.json", &include_bytes!("index/meta.json")[..]);
+/// // ... one call per file of the index.
+/// let index = Index::open_directory_read_only(directory)?;
+/// ```
+///
+/// A whole index packed into a single file can also be embedded,
+/// by opening it with `PackedDirectory::from_source(...)`.
+#[derive(Clone, Default)]
+pub struct StaticDirectory {
+    files: HashMap<PathBuf, ReadOnlySource>,
+}
+
+impl StaticDirectory {
+    /// Registers a file.
+    ///
+    /// `data` can be a `&'static [u8]` or an `Arc<Vec<u8>>`.
+    /// If a file was already registered under the same path, it is replaced.
+    pub fn add_file<P: Into<PathBuf>, D: Into<ReadOnlySource>>(&mut self, path: P, data: D) {
+        self.files.insert(path.into(), data.into());
+    }
+}
+
+impl fmt::Debug for StaticDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StaticDirectory(num_files={})", self.files.len())
+    }
+}
+
+impl Directory for StaticDirectory {
+    fn open_read(&self, path: &Path) -> result::Result<ReadOnlySource, OpenReadError> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| OpenReadError::FileDoesNotExist(path.to_owned()))
+    }
+
+    fn delete(&self, path: &Path) -> result::Result<(), DeleteError> {
+        Err(DeleteError::IOError(IOError::with_path(
+            path.to_owned(),
+            read_only_error(READ_ONLY_REASON),
+        )))
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn open_write(&mut self, path: &Path) -> result::Result<WritePtr, OpenWriteError> {
+        Err(OpenWriteError::IOError(IOError::with_path(
+            path.to_owned(),
+            read_only_error(READ_ONLY_REASON),
+        )))
+    }
+
+    fn atomic_read(&self, path: &Path) -> result::Result<Vec<u8>, OpenReadError> {
+        let source = self.open_read(path)?;
+        Ok(source.as_slice().to_owned())
+    }
+
+    fn atomic_write(&mut self, _: &Path, _: &[u8]) -> io::Result<()> {
+        Err(read_only_error(READ_ONLY_REASON))
+    }
+
+    fn box_clone(&self) -> Box<Directory> {
+        box self.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use Index;
+    use Term;
+    use core::META_FILEPATH;
+    use directory::RAMDirectory;
+    use schema::{SchemaBuilder, TEXT};
+    use std::path::Path;
+    use std::sync::Arc;
+
+    static HELLO: &'static [u8] = b"hello";
+
+    #[test]
+    fn test_static_directory() {
+        let mut directory = StaticDirectory::default();
+        directory.add_file("a", HELLO);
+        directory.add_file("b", Arc::new(b"world".to_vec()));
+        let source = directory.open_read(Path::new("a")).unwrap();
+        assert_eq!(source.as_slice().as_ptr(), HELLO.as_ptr());
+        assert_eq!(source.slice(1, 3).as_slice(), b"el");
+        assert_eq!(&directory.atomic_read(Path::new("b")).unwrap()[..], b"world");
+        assert!(!directory.exists(Path::new("c")));
+        assert!(directory.open_read(Path::new("c")).is_err());
+        assert!(directory.open_write(Path::new("c")).is_err());
+        assert!(directory.atomic_write(Path::new("a"), b"bye").is_err());
+        assert!(directory.delete(Path::new("a")).is_err());
+    }
+
+    #[test]
+    fn test_static_directory_index() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a b"));
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+
+        let snapshot = index.snapshot().unwrap();
+        let mut ram_directory = RAMDirectory::create();
+        snapshot.copy_to(&mut ram_directory).unwrap();
+        let mut directory = StaticDirectory::default();
+        for path in snapshot.files().iter().chain(Some(&*META_FILEPATH)) {
+            let data = ram_directory.atomic_read(path).unwrap();
+            // leaked to emulate data embedded with `include_bytes!`.
+            let static_data: &'static [u8] = Box::leak(data.into_boxed_slice());
+            directory.add_file(path.clone(), static_data);
+        }
+
+        let static_index = Index::open_directory_read_only(directory).unwrap();
+        let searcher = static_index.searcher();
+        assert_eq!(searcher.num_docs(), 2);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "a")), 1);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "b")), 2);
+    }
+}
@@ -0,0 +1,108 @@
+use core::META_FILEPATH;
+use crc::crc32;
+use directory::Directory;
+use std::io;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// Delay between two checks of the meta file, in milliseconds.
+const POLLING_INTERVAL_MS: u64 = 500;
+
+/// Callback called when the meta file of a directory changes.
+pub type WatchCallback = Box<Fn() + Send + Sync>;
+
+/// Handle of a watch over the meta file of a directory.
+///
+/// The callback is not called anymore once the handle is dropped.
+pub struct WatchHandle {
+    stop_sender: Option<Sender<()>>,
+    join_handle: Option<JoinHandle<()>>,
+}
+
+impl Drop for WatchHandle {
+    fn drop(&mut self) {
+        // disconnecting the channel wakes up and stops the watcher thread.
+        self.stop_sender.take();
+        if let Some(join_handle) = self.join_handle.take() {
+            // the handle may be dropped by the callback itself.
+            if join_handle.thread().id() != thread::current().id() {
+                if join_handle.join().is_err() {
+                    error!("The meta watcher thread panicked.");
+                }
+            }
+        }
+    }
+}
+
+/// Returns a checksum of the meta file, or `None` if it cannot be read.
+fn meta_checksum(directory: &Directory) -> Option<u32> {
+    directory
+        .atomic_read(&META_FILEPATH)
+        .ok()
+        .map(|meta_data| crc32::checksum_ieee(&meta_data))
+}
+
+/// Watches the meta file of a directory by periodically
+/// checking whether its content has changed.
+///
+/// This works with any `Directory`, as long as changes made by
+/// other processes are visible through `atomic_read`.
+pub fn watch_meta_by_polling(
+    directory: Box<Directory>,
+    callback: WatchCallback,
+) -> io::Result<WatchHandle> {
+    let (stop_sender, stop_receiver) = mpsc::channel::<()>();
+    let mut last_checksum = meta_checksum(&*directory);
+    let join_handle = thread::Builder::new()
+        .name("meta-watcher".to_string())
+        .spawn(move || {
+            let polling_interval = Duration::from_millis(POLLING_INTERVAL_MS);
+            while let Err(RecvTimeoutError::Timeout) = stop_receiver.recv_timeout(polling_interval)
+            {
+                let checksum = meta_checksum(&*directory);
+                if checksum.is_some() && checksum != last_checksum {
+                    last_checksum = checksum;
+                    callback();
+                }
+            }
+        })?;
+    Ok(WatchHandle {
+        stop_sender: Some(stop_sender),
+        join_handle: Some(join_handle),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::META_FILEPATH;
+    use directory::{Directory, RAMDirectory};
+    use std::sync::Mutex;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_watch() {
+        let mut directory = RAMDirectory::create();
+        directory.atomic_write(&META_FILEPATH, b"1").unwrap();
+        let (sender, receiver) = mpsc::channel();
+        let sender = Mutex::new(sender);
+        let watch_handle = directory
+            .watch(Box::new(move || {
+                sender.lock().unwrap().send(()).unwrap();
+            }))
+            .unwrap();
+        // the timeout only prevents the test from hanging forever.
+        let timeout = Duration::from_secs(30);
+        directory.atomic_write(&META_FILEPATH, b"2").unwrap();
+        assert!(receiver.recv_timeout(timeout).is_ok());
+        directory.atomic_write(&META_FILEPATH, b"3").unwrap();
+        assert!(receiver.recv_timeout(timeout).is_ok());
+        // dropping the handle joins the watcher thread,
+        // and disconnects the channel.
+        drop(watch_handle);
+        directory.atomic_write(&META_FILEPATH, b"4").unwrap();
+        assert_eq!(receiver.recv(), Err(mpsc::RecvError));
+    }
+}
@@ -17,6 +17,21 @@ pub enum SkipResult {
 }
 
 /// Represents an iterable set of sorted doc ids.
+///
+/// Implementations are expected to abide by the following contract,
+/// on which intersections, unions and collectors rely:
+///
+/// - doc ids are segment-local, and returned in strictly increasing order.
+/// - a `DocSet` starts before its first document: `.advance()` or
+///   `.skip_next(...)` must be called before `.doc()`.
+/// - `.doc()` may only be called while the `DocSet` is positioned on a
+///   document, that is after `.advance()` returned true, or after
+///   `.skip_next(...)` returned `Reached` or `OverStep`.
+/// - once `.advance()` returned false, or `.skip_next(...)` returned `End`,
+///   the `DocSet` is exhausted, and should not be used anymore.
+///
+/// Doc ids computed outside of tantivy can be turned into
+/// a `DocSet` with `query::DocSetFromSortedIter`.
 pub trait DocSet {
     /// Goes to the next element.
     /// `.advance(...)` needs to be called a first time to point to the correct
@@ -104,6 +119,18 @@ pub trait DocSet {
         }
         count
     }
+
+    /// Returns the documents of the bucket `bucket`, that is from
+    /// `bucket * 64` to `(bucket + 1) * 64`, that come after the current
+    /// document, as a 64 bits mask.
+    ///
+    /// Only the `DocSet`s reading their documents from a bitset
+    /// support it, so that intersections can and their buckets directly.
+    /// Others return `None`, as well as the `DocSet`s past their last bucket.
+    /// The `DocSet` is not advanced.
+    fn bitset_bucket(&self, _bucket: u32) -> Option<u64> {
+        None
+    }
 }
 
 
@@ -138,6 +165,11 @@ impl<TDocSet: DocSet + ?Sized> DocSet for Box<TDocSet> {
         let unboxed: &mut TDocSet = self.borrow_mut();
         unboxed.append_to_bitset(bitset);
     }
+
+    fn bitset_bucket(&self, bucket: u32) -> Option<u64> {
+        let unboxed: &TDocSet = self.borrow();
+        unboxed.bitset_bucket(bucket)
+    }
 }
 
 
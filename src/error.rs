@@ -1,26 +1,77 @@
 //! Definition of Tantivy's error and result.
 
+use std::error::Error as StdError;
+use std::fmt;
 use std::io;
 
 use std::path::PathBuf;
 use std::sync::PoisonError;
-use directory::error::{IOError, OpenDirectoryError, OpenReadError, OpenWriteError};
+use directory::error::{DeleteError, DirectoryError, IOError, OpenDirectoryError, OpenReadError,
+                       OpenWriteError};
+use indexer::IndexingError;
 use query;
 use schema;
 use fastfield::FastFieldNotAvailableError;
 use serde_json;
 
+/// The bytes of a file do not follow the format they are expected to,
+/// for instance because the file was truncated.
+///
+/// Decoders return a `DataCorruption` error rather than
+/// panicking on malformed bytes.
+#[derive(Debug)]
+pub struct DataCorruption {
+    comment: String,
+}
+
+impl DataCorruption {
+    /// Creates a `DataCorruption` error, given a description of the problem.
+    pub fn comment_only<S: ToString>(comment: S) -> DataCorruption {
+        DataCorruption {
+            comment: comment.to_string(),
+        }
+    }
+
+    /// Returns the description of the problem.
+    pub fn comment(&self) -> &str {
+        &self.comment
+    }
+}
+
+impl fmt::Display for DataCorruption {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "data corruption: {}", self.comment)
+    }
+}
+
+impl StdError for DataCorruption {
+    fn description(&self) -> &str {
+        "the data is corrupted"
+    }
+}
+
+/// Decoders reading from an `io::Read` return the `DataCorruption`
+/// as an `InvalidData` io error. It is extracted back
+/// when the io error is converted into an `Error`.
+impl From<DataCorruption> for io::Error {
+    fn from(data_corruption: DataCorruption) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, data_corruption)
+    }
+}
+
 error_chain!(
     errors {
-        /// Path does not exist.
-        PathDoesNotExist(buf: PathBuf) {
-            description("path does not exist")
-            display("path does not exist: '{:?}'", buf)
+        /// An operation on the directory failed.
+        ///
+        /// For instance, a file does not exist, or already exists.
+        DirectoryError(err: DirectoryError) {
+            description("a directory operation failed")
+            display("a directory operation failed: {}", err)
         }
-        /// File already exists, this is a problem when we try to write into a new file.
-        FileAlreadyExists(buf: PathBuf) {
-            description("file already exists")
-            display("file already exists: '{:?}'", buf)
+        /// The index is locked by another `IndexWriter`.
+        IndexLocked(reason: String) {
+            description("the index is locked by another index writer")
+            display("the index is locked by another index writer: {}", reason)
         }
         /// IO Error.
         IOError(err: IOError) {
@@ -34,6 +85,26 @@ error_chain!(
             description("file contains corrupted data")
             display("file contains corrupted data: '{:?}'", buf)
         }
+        /// The bytes of a file are malformed.
+        ///
+        /// Unlike `CorruptedFile`, the offending file is not known.
+        DataCorruption(err: DataCorruption) {
+            description("the data is corrupted")
+            display("{}", err)
+        }
+        /// The index, or one of its segments, was written in a format
+        /// more recent than the one supported by this version of tantivy,
+        /// or older than `MIN_READABLE_FORMAT_VERSION`.
+        IncompatibleIndexVersion(found: u32, supported: u32) {
+            description("the index was written by an incompatible version of tantivy")
+            display(
+                "the index format version is {}, while this version of tantivy \
+                 only supports versions {} to {}",
+                found,
+                ::core::MIN_READABLE_FORMAT_VERSION,
+                supported
+            )
+        }
         /// A thread holding the locked panicked and poisoned the lock.
         Poisoned {
             description("a thread holding the locked panicked and poisoned the lock")
@@ -43,15 +114,21 @@ error_chain!(
             description("an invalid argument was passed")
             display("an invalid argument was passed: '{}'", arg)
         }
-        /// An Error happened in one of the thread.
-        ErrorInThread(err: String) {
-            description("an error occurred in a thread")
-            display("an error occurred in a thread: '{}'", err)
+        /// The query could not be parsed.
+        QueryParserError(err: query::QueryParserError) {
+            description("the query is invalid")
+            display("the query is invalid: {}", err)
+        }
+        /// An error occurred while indexing documents, or merging segments.
+        IndexingError(err: IndexingError) {
+            description("an indexing error occurred")
+            display("an indexing error occurred: {}", err)
         }
-        /// An Error appeared related to the lack of a field.
-        SchemaError(field: String) {
-            description("a schema field is missing")
-            display("a schema field is missing: '{}'", field)
+        /// A field is missing from the schema, or is not
+        /// configured as required by the operation.
+        SchemaError(err: schema::SchemaError) {
+            description("the schema does not suit the operation")
+            display("the schema does not suit the operation: {}", err)
         }
         /// Tried to access a fastfield reader for a field not configured accordingly.
         FastFieldError(err: FastFieldNotAvailableError) {
@@ -75,13 +152,49 @@ impl From<IOError> for Error {
 
 impl From<io::Error> for Error {
     fn from(io_error: io::Error) -> Error {
+        let is_data_corruption = io_error
+            .get_ref()
+            .map(|inner| inner.is::<DataCorruption>())
+            .unwrap_or(false);
+        if is_data_corruption {
+            let data_corruption = io_error
+                .into_inner()
+                .expect("The io error was checked to have an inner error")
+                .downcast::<DataCorruption>()
+                .expect("The inner error was checked to be a DataCorruption");
+            return ErrorKind::DataCorruption(*data_corruption).into();
+        }
         ErrorKind::IOError(io_error.into()).into()
     }
 }
 
+impl From<DataCorruption> for Error {
+    fn from(data_corruption: DataCorruption) -> Error {
+        ErrorKind::DataCorruption(data_corruption).into()
+    }
+}
+
 impl From<query::QueryParserError> for Error {
     fn from(parsing_error: query::QueryParserError) -> Error {
-        ErrorKind::InvalidArgument(format!("Query is invalid. {:?}", parsing_error)).into()
+        ErrorKind::QueryParserError(parsing_error).into()
+    }
+}
+
+impl From<IndexingError> for Error {
+    fn from(indexing_error: IndexingError) -> Error {
+        ErrorKind::IndexingError(indexing_error).into()
+    }
+}
+
+impl From<schema::SchemaError> for Error {
+    fn from(schema_error: schema::SchemaError) -> Error {
+        ErrorKind::SchemaError(schema_error).into()
+    }
+}
+
+impl From<DirectoryError> for Error {
+    fn from(directory_error: DirectoryError) -> Error {
+        ErrorKind::DirectoryError(directory_error).into()
     }
 }
 
@@ -93,40 +206,31 @@ impl<Guard> From<PoisonError<Guard>> for Error {
 
 impl From<OpenReadError> for Error {
     fn from(error: OpenReadError) -> Error {
-        match error {
-            OpenReadError::FileDoesNotExist(filepath) => {
-                ErrorKind::PathDoesNotExist(filepath).into()
-            }
-            OpenReadError::IOError(io_error) => ErrorKind::IOError(io_error).into(),
-        }
+        DirectoryError::from(error).into()
     }
 }
 
 impl From<schema::DocParsingError> for Error {
     fn from(error: schema::DocParsingError) -> Error {
-        ErrorKind::InvalidArgument(format!("Failed to parse document {:?}", error)).into()
+        IndexingError::InvalidDocument(error).into()
     }
 }
 
 impl From<OpenWriteError> for Error {
     fn from(error: OpenWriteError) -> Error {
-        match error {
-            OpenWriteError::FileAlreadyExists(filepath) => ErrorKind::FileAlreadyExists(filepath),
-            OpenWriteError::IOError(io_error) => ErrorKind::IOError(io_error),
-        }.into()
+        DirectoryError::from(error).into()
     }
 }
 
 impl From<OpenDirectoryError> for Error {
     fn from(error: OpenDirectoryError) -> Error {
-        match error {
-            OpenDirectoryError::DoesNotExist(directory_path) => {
-                ErrorKind::PathDoesNotExist(directory_path).into()
-            }
-            OpenDirectoryError::NotADirectory(directory_path) => ErrorKind::InvalidArgument(
-                format!("{:?} is not a directory", directory_path),
-            ).into(),
-        }
+        DirectoryError::from(error).into()
+    }
+}
+
+impl From<DeleteError> for Error {
+    fn from(error: DeleteError) -> Error {
+        DirectoryError::from(error).into()
     }
 }
 
@@ -0,0 +1,80 @@
+mod writer;
+mod reader;
+
+pub use self::writer::BytesFastFieldWriter;
+pub use self::reader::BytesFastFieldReader;
+
+#[cfg(test)]
+mod tests {
+
+    use futures::Future;
+    use schema::{SchemaBuilder, Term, FAST, INT_INDEXED};
+    use std::collections::BTreeMap;
+    use Index;
+
+    #[test]
+    fn test_bytes() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_bytes_field("bytesfield");
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(field=>vec![0u8, 1, 2, 3]));
+        index_writer.add_document(doc!(field=>Vec::<u8>::new()));
+        index_writer.add_document(doc!());
+        index_writer.add_document(doc!(field=>vec![255u8]));
+        index_writer.add_document(doc!(field=>vec![1u8, 3, 5, 7, 9]));
+        index_writer.add_document(doc!(field=>vec![0u8; 1000]));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let reader = searcher.segment_reader(0);
+        let bytes_reader = reader.bytes_fast_field_reader(field).unwrap();
+
+        assert_eq!(bytes_reader.get_val(0), &[0u8, 1, 2, 3]);
+        assert!(bytes_reader.get_val(1).is_empty());
+        assert!(bytes_reader.get_val(2).is_empty());
+        assert_eq!(bytes_reader.get_val(3), &[255u8]);
+        assert_eq!(bytes_reader.get_val(4), &[1u8, 3, 5, 7, 9]);
+        let long = vec![0u8; 1000];
+        assert_eq!(bytes_reader.get_val(5), long.as_slice());
+    }
+
+    #[test]
+    fn test_bytes_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let field = schema_builder.add_bytes_field("bytesfield");
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>0u64, field=>vec![0u8, 1]));
+        index_writer.add_document(doc!(id_field=>1u64));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field=>2u64, field=>vec![2u8]));
+        index_writer.add_document(doc!(id_field=>3u64, field=>vec![3u8, 4, 5]));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id_field, 2u64));
+        index_writer.commit().unwrap();
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let reader = searcher.segment_reader(0);
+        assert_eq!(reader.num_docs(), 3);
+        let id_reader = reader.fast_field_reader::<u64>(id_field).unwrap();
+        let bytes_reader = reader.bytes_fast_field_reader(field).unwrap();
+        let mut bytes_per_id = BTreeMap::new();
+        for doc in 0..reader.max_doc() {
+            bytes_per_id.insert(id_reader.get(doc), bytes_reader.get_val(doc).to_vec());
+        }
+        assert_eq!(bytes_per_id[&0], vec![0u8, 1]);
+        assert!(bytes_per_id[&1].is_empty());
+        assert_eq!(bytes_per_id[&3], vec![3u8, 4, 5]);
+        assert!(!bytes_per_id.contains_key(&2));
+    }
+}
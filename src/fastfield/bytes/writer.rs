@@ -0,0 +1,89 @@
+use std::io;
+
+use fastfield::FastFieldSerializer;
+use schema::{Document, Field, Value};
+use DocId;
+
+/// Writer for byte array (as in, any number of bytes per document) fast fields
+///
+/// This `BytesFastFieldWriter` is only useful for advanced user.
+/// The normal way to get your associated bytes in your index
+/// is to
+/// - declare your field with `SchemaBuilder::add_bytes_field(...)`
+/// - add your document simply by calling `.add_document(...)`.
+///
+/// The `BytesFastFieldWriter` can be acquired from the
+/// fast field writer by calling
+/// [`.get_bytes_writer(...)`](./struct.FastFieldsWriter.html#method.get_bytes_writer).
+///
+/// Once acquired, writing is done by calling `.add_document_val(&[u8])`
+/// once per document, even if there are no bytes associated to it.
+pub struct BytesFastFieldWriter {
+    field: Field,
+    vals: Vec<u8>,
+    doc_index: Vec<u64>,
+}
+
+impl BytesFastFieldWriter {
+    /// Creates a new `BytesFastFieldWriter`
+    pub fn new(field: Field) -> Self {
+        BytesFastFieldWriter {
+            field,
+            vals: Vec::new(),
+            doc_index: Vec::new(),
+        }
+    }
+
+    /// Access the field associated to the `BytesFastFieldWriter`
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Shift to the next document and adds
+    /// all of the matching field values present in the document.
+    ///
+    /// Only the first value of the field is taken in account.
+    pub fn add_document(&mut self, doc: &Document) {
+        match doc.get_first(self.field) {
+            Some(&Value::Bytes(ref bytes)) => self.add_document_val(bytes),
+            Some(value) => panic!(
+                "Bytes field {:?} contained a non-bytes value {:?}",
+                self.field, value
+            ),
+            None => self.add_document_val(&[]),
+        }
+    }
+
+    /// Register the bytes associated to a document.
+    ///
+    /// The method returns the `DocId` of the document that was
+    /// just written.
+    pub fn add_document_val(&mut self, val: &[u8]) -> DocId {
+        let doc = self.doc_index.len() as DocId;
+        self.doc_index.push(self.vals.len() as u64);
+        self.vals.extend_from_slice(val);
+        doc
+    }
+
+    /// Serializes the fast field values by pushing them to the `FastFieldSerializer`.
+    pub fn serialize(&self, serializer: &mut FastFieldSerializer) -> io::Result<()> {
+        // writing the offset index
+        let num_bytes = self.vals.len() as u64;
+        serializer.write_u64_fast_field_with_idx(
+            self.field,
+            0,
+            0,
+            num_bytes,
+            |doc_index_serializer| {
+                for &offset in &self.doc_index {
+                    doc_index_serializer.add_val(offset)?;
+                }
+                doc_index_serializer.add_val(num_bytes)
+            },
+        )?;
+        // writing the values themselves
+        let mut value_serializer = serializer.new_bytes_fast_field_with_idx(self.field, 1);
+        value_serializer.write_all(&self.vals)?;
+        value_serializer.flush()
+    }
+}
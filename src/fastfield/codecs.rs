@@ -0,0 +1,1083 @@
+/*!
+Codecs used to encode the values of a fast field.
+
+All of the codecs bitpack the values, after removing what
+can be predicted about them:
+
+- `Bitpacked` removes the minimum value, and divides the result
+by the greatest common divisor of the values. This is the codec of choice for
+values that are not sorted, and it is good at dealing with values that are all
+multiple of a given step (e.g. timestamps in milliseconds, rounded to the second).
+- `Linear` removes a line going through the first and the last value.
+Auto-incremented ids, or other values with a constant stride, end up
+taking no space at all.
+- `BlockwiseLinear` does the same as `Linear`, but over blocks of `512` values.
+It does a better job for values that are mostly sorted (e.g. timestamps of
+documents indexed as they arrive) or for values spanning several orders
+of magnitude.
+
+The serializer estimates the size of the field with each codec
+and picks the most compact one. The statistics it needs are gathered
+in two passes over the values, before a last pass writes them,
+so that the values of a field never have to be buffered.
+
+Fields for which some documents do not have any value use the `Sparse`
+codec. It starts with a bitmap of the documents having a value, followed
+by the values of these documents only, encoded with one of the codecs above.
+A column that is only populated for a small fraction of the documents
+therefore does not pay for the other documents, and readers can tell
+a missing value from a `0`.
+
+A fast field starts with a header made of
+
+- the id of the codec (`u8`),
+- the minimum value (`u64`),
+- the amplitude, that is the maximum value minus the minimum value (`u64`),
+- the number of values, or the number of documents for the `Sparse` codec (`u64`),
+
+followed by the data of the codec.
+The bitpacked data is always followed by 7 bytes of padding.
+
+The codec ids, and the layout of the data of each codec, are part of the
+format of the segments. Adding a codec, or changing the layout of one,
+requires bumping `INDEX_FORMAT_VERSION`, so that older versions of tantivy
+reject the segment rather than failing to decode it. Likewise, the decoder
+of a codec can only be removed along with raising `MIN_READABLE_FORMAT_VERSION`.
+
+Fast fields written before `CODEC_FORMAT_VERSION` do not have any codec id.
+They are bitpacked, and their header is only made of their minimum
+value and their amplitude: their number of values is not recorded.
+*/
+
+use byteorder::ByteOrder;
+use common::bitpacker::{BitPacker, BitUnpacker};
+use common::{compute_num_bits, BinarySerializable, Endianness, FixedSize};
+use directory::ReadOnlySource;
+use error::DataCorruption;
+use owning_ref::OwningRef;
+use std::cmp;
+use std::io::{self, Read, Write};
+
+/// First version of the index format whose fast fields start with
+/// the id of their codec, see the module documentation.
+pub(crate) const CODEC_FORMAT_VERSION: u32 = 1;
+
+const BITPACKED_CODEC_ID: u8 = 0u8;
+const LINEAR_CODEC_ID: u8 = 1u8;
+const BLOCKWISE_LINEAR_CODEC_ID: u8 = 2u8;
+const SPARSE_CODEC_ID: u8 = 3u8;
+
+/// Number of values in a block of the `BlockwiseLinear` codec.
+const BLOCK_SIZE: usize = 512;
+
+/// Number of 64-bit words of the presence bitmap sharing an entry of
+/// the rank table of the `Sparse` codec.
+const RANK_BLOCK_NUM_WORDS: usize = 8;
+
+/// Size of a block of the presence bitmap: its rank (`u32`), followed by its words.
+const RANK_BLOCK_NUM_BYTES: usize = 4 + 8 * RANK_BLOCK_NUM_WORDS;
+
+type FastFieldBitUnpacker = BitUnpacker<OwningRef<ReadOnlySource, [u8]>>;
+
+/// Number of bytes required to bitpack `num_vals` values of `num_bits` bits,
+/// padding excluded.
+fn num_bytes(num_bits: u8, num_vals: usize) -> usize {
+    (num_bits as usize * num_vals + 7) / 8
+}
+
+/// Checks that `data_len` bytes are enough to hold `num_vals` values
+/// bitpacked over `num_bits` bits, padding included.
+///
+/// The bitunpacker reads 8 bytes at a time, without any bound checks,
+/// so that this must be checked before reading the values of a fast field.
+fn check_bitpacked_len(num_bits: u8, num_vals: usize, data_len: usize) -> io::Result<()> {
+    if num_bits > 64 {
+        return Err(DataCorruption::comment_only("Fast field has more than 64 bits.").into());
+    }
+    let required_len = (num_bits as usize)
+        .checked_mul(num_vals)
+        .and_then(|num_bits_total| num_bits_total.checked_add(7))
+        .map(|num_bits_total| num_bits_total / 8 + 7);
+    match required_len {
+        Some(required_len) if required_len <= data_len => Ok(()),
+        _ => Err(DataCorruption::comment_only("Fast field values are truncated.").into()),
+    }
+}
+
+fn gcd(mut left: u64, mut right: u64) -> u64 {
+    while right != 0 {
+        let remainder = left % right;
+        left = right;
+        right = remainder;
+    }
+    left
+}
+
+/// Number of values in the block `block_id` of the `BlockwiseLinear` codec.
+fn block_num_vals(num_vals: usize, block_id: usize) -> usize {
+    cmp::min(BLOCK_SIZE, num_vals - block_id * BLOCK_SIZE)
+}
+
+/// Line going through the first and the last of a list of values.
+///
+/// All of the computations wrap, so that any list of `u64` can be encoded,
+/// however far from the line its values may be.
+#[derive(Clone, Copy, Debug)]
+struct Line {
+    first_val: u64,
+    slope: f64,
+}
+
+impl Line {
+    fn through(first_val: u64, last_val: u64, num_vals: usize) -> Line {
+        let slope = if num_vals > 1 {
+            (last_val.wrapping_sub(first_val) as i64) as f64 / (num_vals - 1) as f64
+        } else {
+            0f64
+        };
+        Line { first_val, slope }
+    }
+
+    fn eval(&self, idx: usize) -> u64 {
+        let delta = self.slope * idx as f64;
+        // casting an out of range float is undefined behavior.
+        let delta = if delta >= i64::max_value() as f64 {
+            i64::max_value()
+        } else if delta <= i64::min_value() as f64 {
+            i64::min_value()
+        } else {
+            delta as i64
+        };
+        self.first_val.wrapping_add(delta as u64)
+    }
+}
+
+/// Parameters of the `Linear` codec, for the whole field or for a block.
+#[derive(Clone, Copy, Debug, Default)]
+struct LinearParams {
+    first_val: u64,
+    last_val: u64,
+    /// Smallest difference between a value and the line.
+    offset: u64,
+    num_bits: u8,
+}
+
+impl LinearParams {
+    fn compute(vals: &[u64]) -> LinearParams {
+        if vals.is_empty() {
+            return LinearParams::default();
+        }
+        let mut linear_stats = LinearStats::new(vals[0], vals[vals.len() - 1], vals.len());
+        for &val in vals {
+            linear_stats.push(val);
+        }
+        linear_stats.params()
+    }
+
+    fn line(&self, num_vals: usize) -> Line {
+        Line::through(self.first_val, self.last_val, num_vals)
+    }
+
+    /// Returns the bitpacked residual of the value at the position `idx`.
+    fn residual(&self, line: &Line, idx: usize, val: u64) -> u64 {
+        val.wrapping_sub(line.eval(idx)).wrapping_sub(self.offset)
+    }
+
+    /// Number of bytes required to encode `num_vals` values
+    /// with these parameters, parameters included.
+    fn num_bytes(&self, num_vals: usize) -> usize {
+        LinearParams::SIZE_IN_BYTES + num_bytes(self.num_bits, num_vals)
+    }
+}
+
+impl BinarySerializable for LinearParams {
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.first_val.serialize(writer)?;
+        self.last_val.serialize(writer)?;
+        self.offset.serialize(writer)?;
+        self.num_bits.serialize(writer)
+    }
+
+    fn deserialize<R: Read>(reader: &mut R) -> io::Result<LinearParams> {
+        let first_val = u64::deserialize(reader)?;
+        let last_val = u64::deserialize(reader)?;
+        let offset = u64::deserialize(reader)?;
+        let num_bits = u8::deserialize(reader)?;
+        Ok(LinearParams {
+            first_val,
+            last_val,
+            offset,
+            num_bits,
+        })
+    }
+}
+
+impl FixedSize for LinearParams {
+    const SIZE_IN_BYTES: usize = 8 + 8 + 8 + 1;
+}
+
+/// Gathers the parameters of the `Linear` codec, once the line going
+/// through the first and the last value is known.
+#[derive(Clone, Debug)]
+pub(crate) struct LinearStats {
+    line: Line,
+    first_val: u64,
+    last_val: u64,
+    num_vals: usize,
+    min_residual: i64,
+    max_residual: i64,
+}
+
+impl LinearStats {
+    fn new(first_val: u64, last_val: u64, num_vals: usize) -> LinearStats {
+        LinearStats {
+            line: Line::through(first_val, last_val, num_vals),
+            first_val,
+            last_val,
+            num_vals: 0,
+            min_residual: i64::max_value(),
+            max_residual: i64::min_value(),
+        }
+    }
+
+    /// Records the next value.
+    pub fn push(&mut self, val: u64) {
+        let residual = val.wrapping_sub(self.line.eval(self.num_vals)) as i64;
+        self.min_residual = cmp::min(self.min_residual, residual);
+        self.max_residual = cmp::max(self.max_residual, residual);
+        self.num_vals += 1;
+    }
+
+    fn params(&self) -> LinearParams {
+        if self.num_vals == 0 {
+            return LinearParams::default();
+        }
+        let amplitude = (self.max_residual as u64).wrapping_sub(self.min_residual as u64);
+        LinearParams {
+            first_val: self.first_val,
+            last_val: self.last_val,
+            offset: self.min_residual as u64,
+            num_bits: compute_num_bits(amplitude),
+        }
+    }
+}
+
+/// Returns the bitmask of the values within `[low, low + amplitude]`.
+///
+/// The comparisons are done without any branch,
+/// so that the compiler can vectorize them.
+#[inline]
+fn range_mask(vals: &[u64], low: u64, amplitude: u64) -> u64 {
+    debug_assert!(vals.len() <= 64);
+    let mut mask = 0u64;
+    for (bit, &val) in vals.iter().enumerate() {
+        mask |= ((val.wrapping_sub(low) <= amplitude) as u64) << bit;
+    }
+    mask
+}
+
+/// Records which documents have a value, one bit per document.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct PresenceBitmap {
+    words: Vec<u64>,
+    num_docs: usize,
+    num_present: usize,
+}
+
+impl PresenceBitmap {
+    /// Records the next document.
+    pub fn push(&mut self, present: bool) {
+        if self.num_docs % 64 == 0 {
+            self.words.push(0u64);
+        }
+        if present {
+            let last_word = self.words.len() - 1;
+            self.words[last_word] |= 1u64 << (self.num_docs % 64);
+            self.num_present += 1;
+        }
+        self.num_docs += 1;
+    }
+
+    pub fn contains(&self, doc: usize) -> bool {
+        self.words[doc / 64] & (1u64 << (doc % 64)) != 0
+    }
+
+    pub fn num_docs(&self) -> usize {
+        self.num_docs
+    }
+
+    /// Returns true if all of the documents have a value.
+    pub fn is_dense(&self) -> bool {
+        self.num_present == self.num_docs
+    }
+}
+
+/// Statistics of the values of a fast field, gathered in a first pass
+/// over the values, in order to pick the codec.
+pub(crate) struct FieldStats {
+    min_value: u64,
+    max_value: u64,
+    presence: PresenceBitmap,
+    first_val: u64,
+    last_val: u64,
+    /// Greatest common divisor of the values, once `min_value` has been subtracted.
+    gcd: u64,
+    blocks_params: Vec<LinearParams>,
+    /// Values of the last block, until it is full.
+    block: Vec<u64>,
+}
+
+impl FieldStats {
+    /// All of the values are expected to be within `[min_value, max_value]`.
+    pub fn new(min_value: u64, max_value: u64) -> FieldStats {
+        assert!(min_value <= max_value);
+        FieldStats {
+            min_value,
+            max_value,
+            presence: PresenceBitmap::default(),
+            first_val: 0u64,
+            last_val: 0u64,
+            gcd: 0u64,
+            blocks_params: Vec::new(),
+            block: Vec::with_capacity(BLOCK_SIZE),
+        }
+    }
+
+    /// Records the value of the next document,
+    /// or `None` if the document does not have any value.
+    pub fn push(&mut self, val_opt: Option<u64>) {
+        self.presence.push(val_opt.is_some());
+        let val = match val_opt {
+            Some(val) => val,
+            None => return,
+        };
+        debug_assert!(self.min_value <= val && val <= self.max_value);
+        if self.presence.num_present == 1 {
+            self.first_val = val;
+        }
+        self.last_val = val;
+        if self.gcd != 1 {
+            self.gcd = gcd(self.gcd, val - self.min_value);
+        }
+        self.block.push(val);
+        if self.block.len() == BLOCK_SIZE {
+            self.flush_block();
+        }
+    }
+
+    fn flush_block(&mut self) {
+        if !self.block.is_empty() {
+            self.blocks_params.push(LinearParams::compute(&self.block));
+            self.block.clear();
+        }
+    }
+
+    /// Returns the statistics of the `Linear` codec,
+    /// to be gathered in a second pass over the values.
+    pub fn linear_stats(&self) -> LinearStats {
+        LinearStats::new(self.first_val, self.last_val, self.presence.num_present)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Encoding {
+    Bitpacked { gcd: u64, num_bits: u8 },
+    Linear { params: LinearParams, line: Line },
+    BlockwiseLinear {
+        blocks_params: Vec<LinearParams>,
+        /// Line of the current block.
+        line: Line,
+    },
+}
+
+/// Writes the values of a fast field as they are pushed, in a last
+/// pass over the values, with the most compact codec.
+pub(crate) struct FieldEncoder {
+    min_value: u64,
+    num_docs: usize,
+    num_vals: usize,
+    encoding: Encoding,
+    bit_packer: BitPacker,
+    num_pushed_docs: usize,
+    num_pushed_vals: usize,
+}
+
+impl FieldEncoder {
+    /// Picks the codec from the statistics of the values,
+    /// and writes the header of the field.
+    ///
+    /// If some of the documents do not have any value, the field
+    /// is serialized with the `Sparse` codec.
+    pub fn open<W: Write>(
+        write: &mut W,
+        mut stats: FieldStats,
+        linear_stats: &LinearStats,
+    ) -> io::Result<FieldEncoder> {
+        stats.flush_block();
+        let min_value = stats.min_value;
+        let amplitude = stats.max_value - stats.min_value;
+        let num_docs = stats.presence.num_docs;
+        let num_vals = stats.presence.num_present;
+        assert_eq!(
+            linear_stats.num_vals, num_vals,
+            "The values of the fast field changed from one pass to the other."
+        );
+
+        let vals_gcd = cmp::max(stats.gcd, 1);
+        let bitpacked_num_bits = compute_num_bits(amplitude / vals_gcd);
+        let bitpacked_num_bytes = 8 + num_bytes(bitpacked_num_bits, num_vals);
+
+        let linear_params = linear_stats.params();
+        let linear_num_bytes = linear_params.num_bytes(num_vals);
+
+        let blockwise_num_bytes: usize = stats
+            .blocks_params
+            .iter()
+            .enumerate()
+            .map(|(block_id, block_params)| {
+                block_params.num_bytes(block_num_vals(num_vals, block_id))
+            })
+            .sum();
+
+        let (codec_id, encoding) =
+            if bitpacked_num_bytes <= cmp::min(linear_num_bytes, blockwise_num_bytes) {
+                let encoding = Encoding::Bitpacked {
+                    gcd: vals_gcd,
+                    num_bits: bitpacked_num_bits,
+                };
+                (BITPACKED_CODEC_ID, encoding)
+            } else if linear_num_bytes <= blockwise_num_bytes {
+                let encoding = Encoding::Linear {
+                    params: linear_params,
+                    line: linear_params.line(num_vals),
+                };
+                (LINEAR_CODEC_ID, encoding)
+            } else {
+                let encoding = Encoding::BlockwiseLinear {
+                    blocks_params: stats.blocks_params,
+                    line: Line::through(0u64, 0u64, 0),
+                };
+                (BLOCKWISE_LINEAR_CODEC_ID, encoding)
+            };
+
+        if !stats.presence.is_dense() {
+            SPARSE_CODEC_ID.serialize(write)?;
+            min_value.serialize(write)?;
+            amplitude.serialize(write)?;
+            (num_docs as u64).serialize(write)?;
+            let mut rank = 0u32;
+            for block in stats.presence.words.chunks(RANK_BLOCK_NUM_WORDS) {
+                rank.serialize(write)?;
+                for &word in block {
+                    word.serialize(write)?;
+                    rank += word.count_ones();
+                }
+            }
+        }
+        codec_id.serialize(write)?;
+        min_value.serialize(write)?;
+        amplitude.serialize(write)?;
+        (num_vals as u64).serialize(write)?;
+        match encoding {
+            Encoding::Bitpacked { gcd, .. } => gcd.serialize(write)?,
+            Encoding::Linear { ref params, .. } => params.serialize(write)?,
+            Encoding::BlockwiseLinear {
+                ref blocks_params, ..
+            } => for block_params in blocks_params {
+                block_params.serialize(write)?;
+            },
+        }
+        Ok(FieldEncoder {
+            min_value,
+            num_docs,
+            num_vals,
+            encoding,
+            bit_packer: BitPacker::new(),
+            num_pushed_docs: 0,
+            num_pushed_vals: 0,
+        })
+    }
+
+    /// Writes the value of the next document,
+    /// or `None` if the document does not have any value.
+    pub fn push<W: Write>(&mut self, val_opt: Option<u64>, write: &mut W) -> io::Result<()> {
+        self.num_pushed_docs += 1;
+        let val = match val_opt {
+            Some(val) => val,
+            None => return Ok(()),
+        };
+        let idx = self.num_pushed_vals;
+        assert!(
+            idx < self.num_vals,
+            "The values of the fast field changed from one pass to the other."
+        );
+        match self.encoding {
+            Encoding::Bitpacked { gcd, num_bits } => {
+                self.bit_packer
+                    .write((val - self.min_value) / gcd, num_bits, write)?;
+            }
+            Encoding::Linear {
+                ref params,
+                ref line,
+            } => {
+                self.bit_packer
+                    .write(params.residual(line, idx, val), params.num_bits, write)?;
+            }
+            Encoding::BlockwiseLinear {
+                ref blocks_params,
+                ref mut line,
+            } => {
+                let block_id = idx / BLOCK_SIZE;
+                let block_params = &blocks_params[block_id];
+                if idx % BLOCK_SIZE == 0 {
+                    *line = block_params.line(block_num_vals(self.num_vals, block_id));
+                }
+                let residual = block_params.residual(line, idx % BLOCK_SIZE, val);
+                self.bit_packer
+                    .write(residual, block_params.num_bits, write)?;
+                if (idx + 1) % BLOCK_SIZE == 0 {
+                    // blocks start on a byte boundary.
+                    self.bit_packer.flush(write)?;
+                }
+            }
+        }
+        self.num_pushed_vals += 1;
+        Ok(())
+    }
+
+    pub fn close<W: Write>(mut self, write: &mut W) -> io::Result<()> {
+        assert_eq!(
+            (self.num_pushed_docs, self.num_pushed_vals),
+            (self.num_docs, self.num_vals),
+            "The values of the fast field changed from one pass to the other."
+        );
+        self.bit_packer.close(write)
+    }
+}
+
+/// Presence bitmap of the `Sparse` codec, as serialized
+/// by `FieldEncoder`.
+#[derive(Clone)]
+struct PresenceReader {
+    data: ReadOnlySource,
+}
+
+impl PresenceReader {
+    fn num_bytes(num_docs: usize) -> usize {
+        let num_words = (num_docs + 63) / 64;
+        let num_blocks = (num_words + RANK_BLOCK_NUM_WORDS - 1) / RANK_BLOCK_NUM_WORDS;
+        num_blocks * 4 + num_words * 8
+    }
+
+    fn word(&self, word_id: usize) -> u64 {
+        let offset = (word_id / RANK_BLOCK_NUM_WORDS) * RANK_BLOCK_NUM_BYTES + 4
+            + (word_id % RANK_BLOCK_NUM_WORDS) * 8;
+        Endianness::read_u64(&self.data.as_slice()[offset..])
+    }
+
+    /// Returns the position of the value of the document amongst the values
+    /// of the field, or `None` if the document does not have any value.
+    fn rank(&self, doc: usize) -> Option<usize> {
+        let word_id = doc / 64;
+        let word = self.word(word_id);
+        let doc_bit = 1u64 << (doc % 64);
+        if word & doc_bit == 0 {
+            return None;
+        }
+        let block_id = word_id / RANK_BLOCK_NUM_WORDS;
+        let block_rank =
+            Endianness::read_u32(&self.data.as_slice()[block_id * RANK_BLOCK_NUM_BYTES..]);
+        let mut rank = block_rank as usize;
+        for previous_word_id in block_id * RANK_BLOCK_NUM_WORDS..word_id {
+            rank += self.word(previous_word_id).count_ones() as usize;
+        }
+        rank += (word & (doc_bit - 1)).count_ones() as usize;
+        Some(rank)
+    }
+}
+
+#[derive(Clone)]
+struct LinearReader {
+    line: Line,
+    offset: u64,
+    bit_unpacker: FastFieldBitUnpacker,
+}
+
+impl LinearReader {
+    fn open(params: &LinearParams, num_vals: usize, data: ReadOnlySource) -> LinearReader {
+        LinearReader {
+            line: Line::through(params.first_val, params.last_val, num_vals),
+            offset: params.offset,
+            bit_unpacker: BitUnpacker::new(OwningRef::new(data), params.num_bits),
+        }
+    }
+
+    fn get(&self, idx: usize) -> u64 {
+        self.line
+            .eval(idx)
+            .wrapping_add(self.offset)
+            .wrapping_add(self.bit_unpacker.get(idx))
+    }
+}
+
+#[derive(Clone)]
+enum Codec {
+    Bitpacked {
+        gcd: u64,
+        bit_unpacker: FastFieldBitUnpacker,
+    },
+    Linear(LinearReader),
+    BlockwiseLinear(Vec<LinearReader>),
+    Sparse {
+        presence: PresenceReader,
+        vals: Box<CodecReader>,
+    },
+}
+
+/// Decodes the values of a fast field, whatever its codec.
+#[derive(Clone)]
+pub(crate) struct CodecReader {
+    min_value: u64,
+    max_value: u64,
+    num_vals: usize,
+    codec: Codec,
+}
+
+impl CodecReader {
+    pub fn open(data: ReadOnlySource) -> io::Result<CodecReader> {
+        let mut cursor = data.as_slice();
+        let codec_id = u8::deserialize(&mut cursor)?;
+        let min_value = u64::deserialize(&mut cursor)?;
+        let amplitude = u64::deserialize(&mut cursor)?;
+        let num_vals = u64::deserialize(&mut cursor)? as usize;
+        let codec = match codec_id {
+            BITPACKED_CODEC_ID => {
+                let gcd = u64::deserialize(&mut cursor)?;
+                if gcd == 0 {
+                    return Err(DataCorruption::comment_only("Invalid fast field gcd").into());
+                }
+                let num_bits = compute_num_bits(amplitude / gcd);
+                check_bitpacked_len(num_bits, num_vals, cursor.len())?;
+                let vals_data = data.slice_from(data.len() - cursor.len());
+                Codec::Bitpacked {
+                    gcd,
+                    bit_unpacker: BitUnpacker::new(OwningRef::new(vals_data), num_bits),
+                }
+            }
+            LINEAR_CODEC_ID => {
+                let params = LinearParams::deserialize(&mut cursor)?;
+                check_bitpacked_len(params.num_bits, num_vals, cursor.len())?;
+                let vals_data = data.slice_from(data.len() - cursor.len());
+                Codec::Linear(LinearReader::open(&params, num_vals, vals_data))
+            }
+            BLOCKWISE_LINEAR_CODEC_ID => {
+                // checked first, so that computing the number of blocks cannot overflow.
+                if num_vals / BLOCK_SIZE > cursor.len() / LinearParams::SIZE_IN_BYTES {
+                    return Err(DataCorruption::comment_only(
+                        "Fast field block parameters are truncated.",
+                    ).into());
+                }
+                let num_blocks = (num_vals + BLOCK_SIZE - 1) / BLOCK_SIZE;
+                let mut blocks_params = Vec::with_capacity(num_blocks);
+                for _ in 0..num_blocks {
+                    blocks_params.push(LinearParams::deserialize(&mut cursor)?);
+                }
+                let mut block_offset = data.len() - cursor.len();
+                let mut blocks = Vec::with_capacity(num_blocks);
+                for (block_id, block_params) in blocks_params.iter().enumerate() {
+                    let num_block_vals = block_num_vals(num_vals, block_id);
+                    if block_offset > data.len() {
+                        return Err(DataCorruption::comment_only(
+                            "Fast field block out of bounds",
+                        ).into());
+                    }
+                    // the padding is only written after the last block, but the data
+                    // of the following blocks is at least as long as the padding.
+                    check_bitpacked_len(
+                        block_params.num_bits,
+                        num_block_vals,
+                        data.len() - block_offset,
+                    )?;
+                    let block_data = data.slice_from(block_offset);
+                    blocks.push(LinearReader::open(block_params, num_block_vals, block_data));
+                    block_offset += num_bytes(block_params.num_bits, num_block_vals);
+                }
+                Codec::BlockwiseLinear(blocks)
+            }
+            SPARSE_CODEC_ID => {
+                // checked first, so that computing the size of the bitmap cannot overflow.
+                if num_vals / 8 > cursor.len() {
+                    return Err(DataCorruption::comment_only(
+                        "Fast field presence bitmap out of bounds",
+                    ).into());
+                }
+                let presence_start = data.len() - cursor.len();
+                let presence_end = presence_start + PresenceReader::num_bytes(num_vals);
+                if presence_end > data.len() {
+                    return Err(DataCorruption::comment_only(
+                        "Fast field presence bitmap out of bounds",
+                    ).into());
+                }
+                let vals = CodecReader::open(data.slice_from(presence_end))?;
+                if let Codec::Sparse { .. } = vals.codec {
+                    return Err(DataCorruption::comment_only("Nested sparse fast field").into());
+                }
+                Codec::Sparse {
+                    presence: PresenceReader {
+                        data: data.slice(presence_start, presence_end),
+                    },
+                    vals: Box::new(vals),
+                }
+            }
+            _ => {
+                return Err(DataCorruption::comment_only(
+                    format!("Unknown fast field codec {}", codec_id),
+                ).into());
+            }
+        };
+        let max_value = min_value
+            .checked_add(amplitude)
+            .ok_or_else(|| DataCorruption::comment_only("Fast field amplitude overflows"))?;
+        Ok(CodecReader {
+            min_value,
+            max_value,
+            num_vals,
+            codec,
+        })
+    }
+
+    /// Opens a fast field written before `CODEC_FORMAT_VERSION`,
+    /// holding `num_vals` values.
+    pub fn open_legacy(data: ReadOnlySource, num_vals: usize) -> io::Result<CodecReader> {
+        let mut cursor = data.as_slice();
+        let min_value = u64::deserialize(&mut cursor)?;
+        let amplitude = u64::deserialize(&mut cursor)?;
+        let num_bits = compute_num_bits(amplitude);
+        check_bitpacked_len(num_bits, num_vals, cursor.len())?;
+        let max_value = min_value
+            .checked_add(amplitude)
+            .ok_or_else(|| DataCorruption::comment_only("Fast field amplitude overflows"))?;
+        let vals_data = data.slice_from(data.len() - cursor.len());
+        Ok(CodecReader {
+            min_value,
+            max_value,
+            num_vals,
+            codec: Codec::Bitpacked {
+                gcd: 1u64,
+                bit_unpacker: BitUnpacker::new(OwningRef::new(vals_data), num_bits),
+            },
+        })
+    }
+
+    pub fn min_value(&self) -> u64 {
+        self.min_value
+    }
+
+    pub fn max_value(&self) -> u64 {
+        self.max_value
+    }
+
+    pub fn num_vals(&self) -> usize {
+        self.num_vals
+    }
+
+    /// Returns the value at the position `idx`.
+    ///
+    /// Documents without any value get `0`.
+    pub fn get(&self, idx: usize) -> u64 {
+        match self.codec {
+            Codec::Bitpacked {
+                gcd,
+                ref bit_unpacker,
+            } => self.min_value
+                .wrapping_add(gcd.wrapping_mul(bit_unpacker.get(idx))),
+            Codec::Linear(ref linear_reader) => linear_reader.get(idx),
+            Codec::BlockwiseLinear(ref blocks) => {
+                blocks[idx / BLOCK_SIZE].get(idx % BLOCK_SIZE)
+            }
+            Codec::Sparse { .. } => self.get_opt(idx).unwrap_or(0u64),
+        }
+    }
+
+    /// Calls `callback` for each block of 64 positions holding at least one
+    /// value within `[low, high]`, with the id of the block and the bitmask of
+    /// these positions within the block. Blocks are visited in increasing order.
+    ///
+    /// Positions without any value never match.
+    pub fn scan_range<F: FnMut(usize, u64)>(&self, low: u64, high: u64, mut callback: F) {
+        if low > high || high < self.min_value || low > self.max_value {
+            return;
+        }
+        let amplitude = high - low;
+        let num_blocks = (self.num_vals + 63) / 64;
+        match self.codec {
+            Codec::Sparse {
+                ref presence,
+                ref vals,
+            } => {
+                let mut block_vals = [0u64; 64];
+                for block_id in 0..num_blocks {
+                    let mut remaining = presence.word(block_id);
+                    if remaining == 0 {
+                        continue;
+                    }
+                    let first_doc = block_id * 64 + remaining.trailing_zeros() as usize;
+                    let first_rank = presence
+                        .rank(first_doc)
+                        .expect("The first doc of the block should be present");
+                    let num_block_vals = remaining.count_ones() as usize;
+                    vals.get_range(
+                        first_rank as u32,
+                        &mut block_vals[..num_block_vals],
+                        0u64,
+                    );
+                    let vals_mask = range_mask(&block_vals[..num_block_vals], low, amplitude);
+                    // spreads the mask of the values over the positions of the documents.
+                    let mut mask = 0u64;
+                    for val_ord in 0..num_block_vals {
+                        let bit = remaining.trailing_zeros();
+                        remaining &= remaining - 1;
+                        mask |= ((vals_mask >> val_ord) & 1u64) << bit;
+                    }
+                    if mask != 0 {
+                        callback(block_id, mask);
+                    }
+                }
+            }
+            _ => {
+                let mut block_vals = [0u64; 64];
+                for block_id in 0..num_blocks {
+                    let start = block_id * 64;
+                    let num_block_vals = cmp::min(64, self.num_vals - start);
+                    self.get_range(start as u32, &mut block_vals[..num_block_vals], 0u64);
+                    let mask = range_mask(&block_vals[..num_block_vals], low, amplitude);
+                    if mask != 0 {
+                        callback(block_id, mask);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Returns the value at the position `idx`,
+    /// or `None` if the document does not have any value.
+    pub fn get_opt(&self, idx: usize) -> Option<u64> {
+        match self.codec {
+            Codec::Sparse {
+                ref presence,
+                ref vals,
+            } => match presence.rank(idx) {
+                // a rank out of the values can only come from a corrupted bitmap.
+                Some(rank) if rank < vals.num_vals() => Some(vals.get(rank)),
+                _ => None,
+            },
+            _ => Some(self.get(idx)),
+        }
+    }
+
+    /// Reads the values going from `start` to `start + output.len()`.
+    ///
+    /// Documents without any value get `val_if_missing`.
+    pub fn get_range(&self, start: u32, output: &mut [u64], val_if_missing: u64) {
+        match self.codec {
+            Codec::Bitpacked {
+                gcd,
+                ref bit_unpacker,
+            } => {
+                bit_unpacker.get_range(start, output);
+                for out in output.iter_mut() {
+                    *out = self.min_value.wrapping_add(gcd.wrapping_mul(*out));
+                }
+            }
+            Codec::Sparse { .. } => {
+                for (idx, out) in (start as usize..).zip(output.iter_mut()) {
+                    *out = self.get_opt(idx).unwrap_or(val_if_missing);
+                }
+            }
+            _ => {
+                for (idx, out) in (start as usize..).zip(output.iter_mut()) {
+                    *out = self.get(idx);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use common::test::fixed_size_test;
+
+    /// Serializes the values in three passes, as the `FastFieldSerializer` does.
+    fn serialize_opt_vals(vals: &[Option<u64>], min_value: u64, max_value: u64) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut stats = FieldStats::new(min_value, max_value);
+        for &val in vals {
+            stats.push(val);
+        }
+        let mut linear_stats = stats.linear_stats();
+        for val in vals.iter().filter_map(|&val| val) {
+            linear_stats.push(val);
+        }
+        let mut encoder = FieldEncoder::open(&mut data, stats, &linear_stats).unwrap();
+        for &val in vals {
+            encoder.push(val, &mut data).unwrap();
+        }
+        encoder.close(&mut data).unwrap();
+        data
+    }
+
+    fn serialize_vals(vals: &[u64], min_value: u64, max_value: u64) -> Vec<u8> {
+        let opt_vals: Vec<Option<u64>> = vals.iter().cloned().map(Some).collect();
+        serialize_opt_vals(&opt_vals, min_value, max_value)
+    }
+
+    fn serialize_and_open(vals: &[u64]) -> (u8, usize, CodecReader) {
+        let min_value = vals.iter().cloned().min().unwrap_or(0u64);
+        let max_value = vals.iter().cloned().max().unwrap_or(0u64);
+        let data = serialize_vals(vals, min_value, max_value);
+        let codec_id = data[0];
+        let num_bytes = data.len();
+        let codec_reader = CodecReader::open(ReadOnlySource::from(data)).unwrap();
+        assert_eq!(codec_reader.num_vals(), vals.len());
+        assert_eq!(codec_reader.min_value(), min_value);
+        assert_eq!(codec_reader.max_value(), max_value);
+        for (idx, &val) in vals.iter().enumerate() {
+            assert_eq!(codec_reader.get(idx), val);
+        }
+        if !vals.is_empty() {
+            let mut output = vec![0u64; vals.len() - 1];
+            codec_reader.get_range(1, &mut output, 0u64);
+            assert_eq!(&output[..], &vals[1..]);
+        }
+        (codec_id, num_bytes, codec_reader)
+    }
+
+    #[test]
+    fn test_linear_params_fixed_size() {
+        fixed_size_test::<LinearParams>();
+    }
+
+    #[test]
+    fn test_codec_gcd() {
+        let vals: Vec<u64> = (0u64..1_000u64)
+            .map(|i| 1_500_000_000_000u64 + ((i * 7_919) % 1_000) * 1_000)
+            .collect();
+        let (codec_id, num_bytes, _) = serialize_and_open(&vals);
+        assert_eq!(codec_id, BITPACKED_CODEC_ID);
+        // 10 bits per value
+        assert_eq!(num_bytes, 25 + 8 + 1_250 + 7);
+    }
+
+    #[test]
+    fn test_codec_linear() {
+        let vals: Vec<u64> = (0u64..10_000u64).map(|i| 1_000 + i * 3).collect();
+        let (codec_id, num_bytes, _) = serialize_and_open(&vals);
+        assert_eq!(codec_id, LINEAR_CODEC_ID);
+        assert_eq!(num_bytes, 25 + LinearParams::SIZE_IN_BYTES + 7);
+    }
+
+    #[test]
+    fn test_codec_blockwise_linear() {
+        let mut vals: Vec<u64> = (0u64..5_000u64).map(|i| i * 10 + i % 3).collect();
+        vals.extend((0u64..5_000u64).map(|i| 1u64 << 60 | (i * 10 + i % 3)));
+        let (codec_id, _, _) = serialize_and_open(&vals);
+        assert_eq!(codec_id, BLOCKWISE_LINEAR_CODEC_ID);
+    }
+
+    #[test]
+    fn test_codec_wrapping() {
+        let vals = vec![u64::max_value(), 0u64, 3u64, u64::max_value() - 1, 1u64 << 63];
+        serialize_and_open(&vals);
+        let vals: Vec<u64> = (0u64..2_000u64)
+            .map(|i| if i % 2 == 0 { u64::max_value() - i } else { i })
+            .collect();
+        serialize_and_open(&vals);
+    }
+
+    #[test]
+    fn test_codec_legacy() {
+        let vals: Vec<u64> = (0u64..1_000u64).map(|i| 100 + (i * 7_919) % 1_000).collect();
+        let mut data = Vec::new();
+        100u64.serialize(&mut data).unwrap();
+        999u64.serialize(&mut data).unwrap();
+        let mut bit_packer = BitPacker::new();
+        for &val in &vals {
+            bit_packer.write(val - 100, 10, &mut data).unwrap();
+        }
+        bit_packer.close(&mut data).unwrap();
+        let codec_reader = CodecReader::open_legacy(ReadOnlySource::from(data.clone()), 1_000)
+            .unwrap();
+        assert_eq!(codec_reader.num_vals(), 1_000);
+        assert_eq!(codec_reader.min_value(), 100);
+        assert_eq!(codec_reader.max_value(), 1_099);
+        for (idx, &val) in vals.iter().enumerate() {
+            assert_eq!(codec_reader.get(idx), val);
+        }
+        assert!(CodecReader::open_legacy(ReadOnlySource::from(data), 1_001).is_err());
+    }
+
+    #[test]
+    fn test_codec_sparse() {
+        let vals: Vec<Option<u64>> = (0u64..10_000u64)
+            .map(|doc| {
+                if doc % 97 == 3 || (doc >= 4_000 && doc < 4_200) {
+                    Some(doc * 2)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let num_present = vals.iter().filter(|val| val.is_some()).count();
+        let data = serialize_opt_vals(&vals, 6, 19_998);
+        assert_eq!(data[0], SPARSE_CODEC_ID);
+        // the presence bitmap takes less than 0.14 bytes per document.
+        assert!(data.len() < 25 + 1_400 + 25 + num_present * 2);
+        let codec_reader = CodecReader::open(ReadOnlySource::from(data)).unwrap();
+        assert_eq!(codec_reader.num_vals(), 10_000);
+        assert_eq!(codec_reader.min_value(), 6);
+        assert_eq!(codec_reader.max_value(), 19_998);
+        for (doc, &val) in vals.iter().enumerate() {
+            assert_eq!(codec_reader.get_opt(doc), val);
+            assert_eq!(codec_reader.get(doc), val.unwrap_or(0u64));
+        }
+        let mut output = vec![0u64; 5];
+        codec_reader.get_range(3_999, &mut output, 1u64);
+        assert_eq!(&output[..], &[1u64, 8_000, 8_002, 8_004, 8_006]);
+    }
+
+    #[test]
+    fn test_codec_sparse_empty() {
+        let data = serialize_opt_vals(&[None], 0, 0);
+        let codec_reader = CodecReader::open(ReadOnlySource::from(data)).unwrap();
+        assert_eq!(codec_reader.num_vals(), 1);
+        assert_eq!(codec_reader.get_opt(0), None);
+    }
+
+    #[test]
+    fn test_codec_truncated() {
+        let vals_list: Vec<Vec<u64>> = vec![
+            (0u64..1_000u64).map(|i| (i * 7_919) % 1_000).collect(),
+            (0u64..1_000u64).map(|i| 1_000 + i * 3).collect(),
+            (0u64..2_000u64).map(|i| if i < 1_000 { i } else { 1u64 << 60 | i }).collect(),
+        ];
+        for vals in &vals_list {
+            let max_value = vals.iter().cloned().max().unwrap();
+            let data = serialize_vals(vals, 0u64, max_value);
+            for len in 0..data.len() {
+                let truncated_data = ReadOnlySource::from(data[..len].to_vec());
+                assert!(CodecReader::open(truncated_data).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn test_codec_edge_cases() {
+        serialize_and_open(&[]);
+        serialize_and_open(&[17u64]);
+        let (codec_id, num_bytes, _) = serialize_and_open(&vec![3u64; 10_000]);
+        assert_eq!(codec_id, BITPACKED_CODEC_ID);
+        assert_eq!(num_bytes, 25 + 8 + 7);
+    }
+}
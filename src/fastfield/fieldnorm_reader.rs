@@ -0,0 +1,159 @@
+use std::cmp;
+use DocId;
+use super::FastFieldReader;
+
+/// Fieldnorms below this value are encoded exactly.
+const NUM_EXACT_FIELDNORMS: u32 = 24;
+
+/// Encodes a fieldnorm into a single byte.
+///
+/// Small fieldnorms are encoded exactly. Larger ones are encoded
+/// as a tiny float, with a 3-bit mantissa, which keeps a relative
+/// precision of about 11%. The encoding is monotonic, and decoding
+/// an id returns the largest fieldnorm that is lower or equal
+/// to the original fieldnorm.
+pub fn fieldnorm_to_id(fieldnorm: u32) -> u8 {
+    // beyond `i32::max_value()`, the id would not fit in a byte.
+    let fieldnorm = cmp::min(fieldnorm, i32::max_value() as u32);
+    if fieldnorm < NUM_EXACT_FIELDNORMS {
+        return fieldnorm as u8;
+    }
+    let val = fieldnorm - NUM_EXACT_FIELDNORMS;
+    let num_bits = 32 - val.leading_zeros();
+    let encoded = if num_bits < 4 {
+        val
+    } else {
+        let shift = num_bits - 4;
+        // the highest bit is implicit.
+        ((val >> shift) & 0x07) | ((shift + 1) << 3)
+    };
+    (NUM_EXACT_FIELDNORMS + encoded) as u8
+}
+
+/// Decodes a fieldnorm encoded with `fieldnorm_to_id`.
+pub fn id_to_fieldnorm(id: u8) -> u32 {
+    let id = u32::from(id);
+    if id < NUM_EXACT_FIELDNORMS {
+        return id;
+    }
+    let encoded = id - NUM_EXACT_FIELDNORMS;
+    let bits = encoded & 0x07;
+    let shift = encoded >> 3;
+    let val = if shift == 0 {
+        bits
+    } else {
+        (bits | 0x08) << (shift - 1)
+    };
+    NUM_EXACT_FIELDNORMS + val
+}
+
+/// Reader for the fieldnorms of a field, in a given segment.
+///
+/// The fieldnorm of a document is the number of tokens of its field.
+/// Custom similarities, or rerankers running outside of tantivy, can
+/// use it to take the length of the documents in account.
+///
+/// Fieldnorms are stored exactly, as a fast field of the `.fieldnorm`
+/// file of the segment. They can also be read as a one-byte
+/// encoded value, for scoring models that only need an approximate length
+/// or want to keep them in compact lookup tables.
+/// See `fieldnorm_to_id` for the details of the encoding.
+///
+/// Documents that do not have any token for the field
+/// have a fieldnorm of `0`.
+#[derive(Clone)]
+pub struct FieldNormReader {
+    fieldnorms: FastFieldReader<u64>,
+}
+
+impl FieldNormReader {
+    pub(crate) fn new(fieldnorms: FastFieldReader<u64>) -> FieldNormReader {
+        FieldNormReader { fieldnorms }
+    }
+
+    /// Returns the number of documents covered by the reader.
+    pub fn num_docs(&self) -> usize {
+        self.fieldnorms.capacity()
+    }
+
+    /// Returns the fieldnorm of a document, that is the number
+    /// of tokens of the field in the document.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `doc` is greater than the segment's `max_doc`.
+    pub fn fieldnorm(&self, doc: DocId) -> u32 {
+        cmp::min(self.fieldnorms.get(doc), u64::from(u32::max_value())) as u32
+    }
+
+    /// Returns the fieldnorm of a document, encoded into a single byte.
+    ///
+    /// The approximate fieldnorm can be recovered
+    /// with `id_to_fieldnorm`.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `doc` is greater than the segment's `max_doc`.
+    pub fn fieldnorm_id(&self, doc: DocId) -> u8 {
+        fieldnorm_to_id(self.fieldnorm(doc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use Index;
+    use schema::{SchemaBuilder, STRING, TEXT};
+
+    #[test]
+    fn test_fieldnorm_id_encoding() {
+        for fieldnorm in 0u32..NUM_EXACT_FIELDNORMS + 8 {
+            assert_eq!(id_to_fieldnorm(fieldnorm_to_id(fieldnorm)), fieldnorm);
+        }
+        assert_eq!(fieldnorm_to_id(i32::max_value() as u32), 255u8);
+        assert_eq!(fieldnorm_to_id(u32::max_value()), 255u8);
+        let mut previous_id = 0u8;
+        for fieldnorm in (0u32..1_000_000u32).chain(Some(u32::max_value())) {
+            let id = fieldnorm_to_id(fieldnorm);
+            assert!(id >= previous_id);
+            let decoded = id_to_fieldnorm(id);
+            assert!(decoded <= fieldnorm);
+            // the relative precision is of 1/9th at worst.
+            let clamped_fieldnorm = cmp::min(fieldnorm, i32::max_value() as u32);
+            assert!(u64::from(decoded) * 9 + 8 >= u64::from(clamped_fieldnorm) * 8);
+            previous_id = id;
+        }
+        for id in 0u8..255u8 {
+            assert!(id_to_fieldnorm(id) < id_to_fieldnorm(id + 1));
+            assert_eq!(fieldnorm_to_id(id_to_fieldnorm(id)), id);
+        }
+    }
+
+    #[test]
+    fn test_fieldnorm_reader() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let tag_field = schema_builder.add_text_field("tag", STRING);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        let long_text = vec!["a"; 100].join(" ");
+        index_writer.add_document(doc!(text_field=>"a b c", tag_field=>"x"));
+        index_writer.add_document(doc!(tag_field=>"y"));
+        index_writer.add_document(doc!(text_field=>long_text));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let fieldnorm_reader = segment_reader.fieldnorm_reader(text_field).unwrap();
+        assert_eq!(fieldnorm_reader.num_docs(), 3);
+        assert_eq!(fieldnorm_reader.fieldnorm(0), 3);
+        assert_eq!(fieldnorm_reader.fieldnorm(1), 0);
+        assert_eq!(fieldnorm_reader.fieldnorm(2), 100);
+        assert_eq!(fieldnorm_reader.fieldnorm_id(0), 3);
+        assert_eq!(id_to_fieldnorm(fieldnorm_reader.fieldnorm_id(2)), 96);
+        let tag_fieldnorm_reader = segment_reader.fieldnorm_reader(tag_field).unwrap();
+        assert_eq!(tag_fieldnorm_reader.fieldnorm(1), 1);
+    }
+}
@@ -18,9 +18,17 @@ supported.
 
 They are stored in a bit-packed fashion so that their
 memory usage is directly linear with the amplitude of the
-values stored.
+values stored. Depending on the values, a line or the greatest
+common divisor of the values is removed first, so that for instance
+timestamps or auto-incremented ids take very little space.
 
 Read access performance is comparable to that of an array lookup.
+
+Documents without any value for a single-valued fast field are
+recorded as missing. `FastFieldReader::get_opt(...)` makes it possible
+to tell them apart from documents whose value is `0`. Columns
+populated for only a fraction of the documents are stored sparsely,
+behind a presence bitmap.
 */
 
 use common;
@@ -29,20 +37,33 @@ use schema::FieldType;
 use schema::Value;
 pub use self::delete::DeleteBitSet;
 pub use self::delete::write_delete_bitset;
+pub use self::updates::{write_fast_field_updates, FastFieldUpdates, FieldUpdates};
 pub use self::error::{FastFieldNotAvailableError, Result};
 pub use self::facet_reader::FacetReader;
+pub use self::fieldnorm_reader::{fieldnorm_to_id, id_to_fieldnorm, FieldNormReader};
+pub use self::str_reader::StrFastFieldReader;
 pub use self::multivalued::MultiValueIntFastFieldReader;
+pub use self::bytes::{BytesFastFieldReader, BytesFastFieldWriter};
+pub use self::vector::{VectorFastFieldReader, VectorFastFieldWriter};
+pub(crate) use self::vector::build_hnsw;
 pub use self::reader::FastFieldReader;
+pub(crate) use self::reader::open_fast_field_column;
 pub use self::serializer::FastFieldSerializer;
 pub use self::writer::{FastFieldsWriter, IntFastFieldWriter};
 
+mod codecs;
 mod reader;
 mod writer;
 mod serializer;
 mod error;
 mod delete;
+mod updates;
 mod facet_reader;
+mod fieldnorm_reader;
+mod str_reader;
 mod multivalued;
+mod bytes;
+mod vector;
 
 /// Trait for types that are allowed for fast fields: (u64 or i64).
 pub trait FastValue: Default + Clone + Copy {
@@ -170,9 +191,9 @@ mod tests {
             let write: WritePtr = directory.open_write(Path::new("test")).unwrap();
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
-            fast_field_writers.add_document(&doc!(*FIELD=>13u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>14u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>2u64));
+            fast_field_writers.add_document(&doc!(*FIELD=>13u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>14u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>2u64)).unwrap();
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
                 .unwrap();
@@ -180,12 +201,12 @@ mod tests {
         }
         let source = directory.open_read(&path).unwrap();
         {
-            assert_eq!(source.len(), 36 as usize);
+            assert_eq!(source.len(), 53 as usize);
         }
         {
             let composite_file = CompositeFile::open(&source).unwrap();
             let field_source = composite_file.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(field_source);
+            let fast_field_reader = FastFieldReader::<u64>::open(field_source).unwrap();
             assert_eq!(fast_field_reader.get(0), 13u64);
             assert_eq!(fast_field_reader.get(1), 14u64);
             assert_eq!(fast_field_reader.get(2), 2u64);
@@ -200,15 +221,15 @@ mod tests {
             let write: WritePtr = directory.open_write(Path::new("test")).unwrap();
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
-            fast_field_writers.add_document(&doc!(*FIELD=>4u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>14_082_001u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>3_052u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>9_002u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>15_001u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>777u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>1_002u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>1_501u64));
-            fast_field_writers.add_document(&doc!(*FIELD=>215u64));
+            fast_field_writers.add_document(&doc!(*FIELD=>4u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>14_082_001u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>3_052u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>9_002u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>15_001u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>777u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>1_002u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>1_501u64)).unwrap();
+            fast_field_writers.add_document(&doc!(*FIELD=>215u64)).unwrap();
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
                 .unwrap();
@@ -216,12 +237,12 @@ mod tests {
         }
         let source = directory.open_read(&path).unwrap();
         {
-            assert_eq!(source.len(), 61 as usize);
+            assert_eq!(source.len(), 78 as usize);
         }
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
             assert_eq!(fast_field_reader.get(0), 4u64);
             assert_eq!(fast_field_reader.get(1), 14_082_001u64);
             assert_eq!(fast_field_reader.get(2), 3_052u64);
@@ -244,7 +265,7 @@ mod tests {
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
             for _ in 0..10_000 {
-                fast_field_writers.add_document(&doc!(*FIELD=>100_000u64));
+                fast_field_writers.add_document(&doc!(*FIELD=>100_000u64)).unwrap();
             }
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
@@ -253,12 +274,12 @@ mod tests {
         }
         let source = directory.open_read(&path).unwrap();
         {
-            assert_eq!(source.len(), 34 as usize);
+            assert_eq!(source.len(), 51 as usize);
         }
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
             for doc in 0..10_000 {
                 assert_eq!(fast_field_reader.get(doc), 100_000u64);
             }
@@ -275,9 +296,11 @@ mod tests {
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
             // forcing the amplitude to be high
-            fast_field_writers.add_document(&doc!(*FIELD=>0u64));
+            fast_field_writers.add_document(&doc!(*FIELD=>0u64)).unwrap();
             for i in 0u64..10_000u64 {
-                fast_field_writers.add_document(&doc!(*FIELD=>5_000_000_000_000_000_000u64 + i));
+                fast_field_writers
+                    .add_document(&doc!(*FIELD=>5_000_000_000_000_000_000u64 + i))
+                    .unwrap();
             }
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
@@ -286,12 +309,12 @@ mod tests {
         }
         let source = directory.open_read(&path).unwrap();
         {
-            assert_eq!(source.len(), 80042 as usize);
+            assert_eq!(source.len(), 4639 as usize);
         }
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
             assert_eq!(fast_field_reader.get(0), 0u64);
             for doc in 1..10_001 {
                 assert_eq!(
@@ -317,7 +340,7 @@ mod tests {
             for i in -100i64..10_000i64 {
                 let mut doc = Document::default();
                 doc.add_i64(i64_field, i);
-                fast_field_writers.add_document(&doc);
+                fast_field_writers.add_document(&doc).unwrap();
             }
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
@@ -326,12 +349,12 @@ mod tests {
         }
         let source = directory.open_read(&path).unwrap();
         {
-            assert_eq!(source.len(), 17709 as usize);
+            assert_eq!(source.len(), 68 as usize);
         }
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(i64_field).unwrap();
-            let fast_field_reader = FastFieldReader::<i64>::open(data);
+            let fast_field_reader = FastFieldReader::<i64>::open(data).unwrap();
 
             assert_eq!(fast_field_reader.min_value(), -100i64);
             assert_eq!(fast_field_reader.max_value(), 9_999i64);
@@ -359,7 +382,7 @@ mod tests {
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&schema);
             let doc = Document::default();
-            fast_field_writers.add_document(&doc);
+            fast_field_writers.add_document(&doc).unwrap();
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
                 .unwrap();
@@ -370,9 +393,149 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(i64_field).unwrap();
-            let fast_field_reader = FastFieldReader::<i64>::open(data);
+            let fast_field_reader = FastFieldReader::<i64>::open(data).unwrap();
             assert_eq!(fast_field_reader.get(0u32), 0i64);
+            assert_eq!(fast_field_reader.get_opt(0u32), None);
+        }
+    }
+
+    #[test]
+    fn test_sparse_intfastfield() {
+        use schema::INT_INDEXED;
+        use Index;
+        use Term;
+
+        let mut schema_builder = SchemaBuilder::new();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let price_field = schema_builder.add_i64_field("price", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for id in 0u64..1_000u64 {
+            if id % 100 == 7 {
+                index_writer.add_document(doc!(id_field=>id, price_field=>-(id as i64)));
+            } else if id == 500 {
+                index_writer.add_document(doc!(id_field=>id, price_field=>0i64));
+            } else {
+                index_writer.add_document(doc!(id_field=>id));
+            }
+            if id == 600 {
+                index_writer.commit().unwrap();
+            }
+        }
+        index_writer.delete_term(Term::from_field_u64(id_field, 107));
+        index_writer.delete_term(Term::from_field_u64(id_field, 108));
+        index_writer.commit().unwrap();
+
+        let check = |index: &Index| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let mut num_vals = 0;
+            for segment_reader in searcher.segment_readers() {
+                let id_reader = segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+                let price_reader = segment_reader.fast_field_reader::<i64>(price_field).unwrap();
+                assert!(price_reader.min_value() < 0i64);
+                assert!(price_reader.max_value() <= 0i64);
+                for doc in 0..segment_reader.max_doc() {
+                    if segment_reader.is_deleted(doc) {
+                        continue;
+                    }
+                    let id = id_reader.get(doc);
+                    let expected = if id % 100 == 7 {
+                        Some(-(id as i64))
+                    } else if id == 500 {
+                        Some(0i64)
+                    } else {
+                        None
+                    };
+                    assert_eq!(price_reader.get_opt(doc), expected);
+                    assert_eq!(price_reader.get(doc), expected.unwrap_or(0i64));
+                    if expected.is_some() {
+                        num_vals += 1;
+                    }
+                }
+            }
+            assert_eq!(num_vals, 10);
+        };
+        check(&index);
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        check(&index);
+    }
+
+    #[test]
+    fn test_get_range_docids() {
+        use common::BitSet;
+        use std::sync::Arc;
+
+        let vals: Vec<i64> = (0i64..1_000i64).map(|i| (i * 37) % 201 - 100).collect();
+        let fast_field_reader = FastFieldReader::<i64>::from(vals.clone());
+        let matching_docs = |fast_field_reader: &FastFieldReader<i64>, min: i64, max: i64| {
+            let mut bitset = BitSet::with_max_value(1_000);
+            fast_field_reader.get_range_docids(min, max, &mut bitset);
+            (0u32..1_000u32)
+                .filter(|&doc| bitset.contains(doc))
+                .collect::<Vec<u32>>()
+        };
+        let expected_docs = |min: i64, max: i64| {
+            (0u32..1_000u32)
+                .filter(|&doc| min <= vals[doc as usize] && vals[doc as usize] <= max)
+                .collect::<Vec<u32>>()
+        };
+        for &(min, max) in &[(-100i64, 100i64), (-3, 5), (0, 0), (7, 7), (101, 200), (5, -5)] {
+            assert_eq!(matching_docs(&fast_field_reader, min, max), expected_docs(min, max));
+        }
+
+        let mut updates = FastFieldUpdates::empty();
+        updates.update(Field(0), 3u32, ::common::i64_to_u64(1_000i64));
+        for (doc, _) in vals.iter().enumerate().filter(|&(_, &val)| val == 0i64) {
+            updates.update(Field(0), doc as u32, ::common::i64_to_u64(0i64));
+        }
+        let field_updates = updates.field_updates(Field(0)).unwrap();
+        let updated_reader = fast_field_reader.clone().with_updates(field_updates);
+        assert_eq!(updated_reader.max_value(), 1_000i64);
+        let mut bitset = BitSet::with_max_value(1_000);
+        updated_reader.get_range_docids(0i64, 0i64, &mut bitset);
+        assert_eq!(bitset.len(), 0);
+        updated_reader.get_range_docids(900i64, 1_000i64, &mut bitset);
+        assert_eq!(bitset.len(), 1);
+        assert!(bitset.contains(3u32));
+    }
+
+    #[test]
+    fn test_get_range_docids_sparse() {
+        use common::BitSet;
+
+        let path = Path::new("test");
+        let mut directory: RAMDirectory = RAMDirectory::create();
+        {
+            let write: WritePtr = directory.open_write(path).unwrap();
+            let mut serializer = FastFieldSerializer::from_write(write).unwrap();
+            let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
+            for doc in 0u64..1_000u64 {
+                if doc % 10 == 3 {
+                    fast_field_writers.add_document(&doc!(*FIELD=>doc % 100)).unwrap();
+                } else {
+                    fast_field_writers.add_document(&Document::default()).unwrap();
+                }
+            }
+            fast_field_writers
+                .serialize(&mut serializer, &HashMap::new())
+                .unwrap();
+            serializer.close().unwrap();
         }
+        let source = directory.open_read(path).unwrap();
+        let fast_fields_composite = CompositeFile::open(&source).unwrap();
+        let data = fast_fields_composite.open_read(*FIELD).unwrap();
+        let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
+        let mut bitset = BitSet::with_max_value(1_000);
+        fast_field_reader.get_range_docids(0u64, 20u64, &mut bitset);
+        let docs: Vec<u32> = (0u32..1_000u32).filter(|&doc| bitset.contains(doc)).collect();
+        let expected: Vec<u32> = (0u32..1_000u32)
+            .filter(|&doc| doc % 10 == 3 && doc % 100 <= 20)
+            .collect();
+        assert_eq!(docs, expected);
     }
 
     fn generate_permutation() -> Vec<u64> {
@@ -394,7 +557,7 @@ mod tests {
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
             for &x in &permutation {
-                fast_field_writers.add_document(&doc!(*FIELD=>x));
+                fast_field_writers.add_document(&doc!(*FIELD=>x)).unwrap();
             }
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
@@ -405,7 +568,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
 
             let mut a = 0u64;
             for _ in 0..n {
@@ -451,7 +614,7 @@ mod tests {
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
             for &x in &permutation {
-                fast_field_writers.add_document(&doc!(*FIELD=>x));
+                fast_field_writers.add_document(&doc!(*FIELD=>x)).unwrap();
             }
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
@@ -462,7 +625,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
 
             b.iter(|| {
                 let n = test::black_box(7000u32);
@@ -475,6 +638,20 @@ mod tests {
         }
     }
 
+    #[bench]
+    fn bench_intfastfield_range_docids(b: &mut Bencher) {
+        use common::BitSet;
+
+        let permutation = generate_permutation();
+        let n = permutation.len() as u64;
+        let fast_field_reader = FastFieldReader::<u64>::from(permutation);
+        b.iter(|| {
+            let mut bitset = BitSet::with_max_value(n as u32);
+            fast_field_reader.get_range_docids(n / 4, n / 2, &mut bitset);
+            bitset.len()
+        });
+    }
+
     #[bench]
     fn bench_intfastfield_fflookup(b: &mut Bencher) {
         let path = Path::new("test");
@@ -485,7 +662,7 @@ mod tests {
             let mut serializer = FastFieldSerializer::from_write(write).unwrap();
             let mut fast_field_writers = FastFieldsWriter::from_schema(&SCHEMA);
             for &x in &permutation {
-                fast_field_writers.add_document(&doc!(*FIELD=>x));
+                fast_field_writers.add_document(&doc!(*FIELD=>x)).unwrap();
             }
             fast_field_writers
                 .serialize(&mut serializer, &HashMap::new())
@@ -496,7 +673,7 @@ mod tests {
         {
             let fast_fields_composite = CompositeFile::open(&source).unwrap();
             let data = fast_fields_composite.open_read(*FIELD).unwrap();
-            let fast_field_reader = FastFieldReader::<u64>::open(data);
+            let fast_field_reader = FastFieldReader::<u64>::open(data).unwrap();
 
             b.iter(|| {
                 let n = test::black_box(1000u32);
@@ -10,6 +10,9 @@ mod tests {
     use schema::SchemaBuilder;
     use schema::Cardinality;
     use schema::IntOptions;
+    use schema::{Term, FAST, INT_INDEXED};
+    use std::collections::BTreeMap;
+    use futures::Future;
     use Index;
 
     #[test]
@@ -86,4 +89,47 @@ mod tests {
             assert_eq!(&vals, &[-5i64, -20i64, 1i64]);
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_multivalued_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let field = schema_builder.add_i64_field(
+            "multifield",
+            IntOptions::default().set_fast(Cardinality::MultiValues)
+        );
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 3_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>0u64, field=>1i64, field=>-3i64));
+        index_writer.add_document(doc!(id_field=>1u64));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field=>2u64, field=>7i64));
+        index_writer.add_document(doc!(id_field=>3u64, field=>-5i64, field=>20i64));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id_field, 2u64));
+        index_writer.commit().unwrap();
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let reader = searcher.segment_reader(0);
+        assert_eq!(reader.num_docs(), 3);
+        let id_reader = reader.fast_field_reader::<u64>(id_field).unwrap();
+        let multi_value_reader = reader.multi_fast_field_reader::<i64>(field).unwrap();
+        let mut vals_per_id = BTreeMap::new();
+        for doc in 0..reader.max_doc() {
+            let mut vals = Vec::new();
+            multi_value_reader.get_vals(doc, &mut vals);
+            vals_per_id.insert(id_reader.get(doc), vals);
+        }
+        assert_eq!(vals_per_id[&0], vec![1i64, -3i64]);
+        assert!(vals_per_id[&1].is_empty());
+        assert_eq!(vals_per_id[&3], vec![-5i64, 20i64]);
+        assert!(!vals_per_id.contains_key(&2));
+    }
+}
@@ -35,6 +35,16 @@ impl<Item: FastValue> MultiValueIntFastFieldReader<Item> {
         vals.resize(len, Item::default());
         self.vals_reader.get_range(start, &mut vals[..]);
     }
+
+    /// Returns the column associating each document to the index of its first value.
+    pub(crate) fn idx_reader(&self) -> &FastFieldReader<u64> {
+        &self.idx_reader
+    }
+
+    /// Returns the column of the concatenated values.
+    pub(crate) fn vals_reader(&self) -> &FastFieldReader<Item> {
+        &self.vals_reader
+    }
 }
 
 #[cfg(test)]
@@ -1,5 +1,4 @@
 use fastfield::FastFieldSerializer;
-use fastfield::serializer::FastSingleFieldSerializer;
 use fastfield::value_to_u64;
 use std::collections::HashMap;
 use postings::UnorderedTermId;
@@ -12,17 +11,21 @@ pub struct MultiValueIntFastFieldWriter {
     field: Field,
     vals: Vec<u64>,
     doc_index: Vec<u64>,
-    is_facet: bool
+    is_term_ord: bool
 }
 
 impl MultiValueIntFastFieldWriter {
-    /// Creates a new `IntFastFieldWriter`
-    pub fn new(field: Field, is_facet: bool) -> Self {
+    /// Creates a new `MultiValueIntFastFieldWriter`
+    ///
+    /// If `is_term_ord` is true, the values are the `UnorderedTermId`s of the terms
+    /// of the field (facets or str fast fields). They are pushed by the `SegmentWriter`
+    /// as the terms are indexed, and remapped to term ordinals upon serialization.
+    pub fn new(field: Field, is_term_ord: bool) -> Self {
         MultiValueIntFastFieldWriter {
             field,
             vals: Vec::new(),
             doc_index: Vec::new(),
-            is_facet
+            is_term_ord
         }
     }
 
@@ -44,7 +47,7 @@ impl MultiValueIntFastFieldWriter {
     }
 
     pub fn add_document(&mut self, doc: &Document) {
-        if !self.is_facet {
+        if !self.is_term_ord {
             for field_value in doc.field_values() {
                 if field_value.field() == self.field {
                     self.add_val(value_to_u64(field_value.value()));
@@ -70,40 +73,55 @@ impl MultiValueIntFastFieldWriter {
         serializer: &mut FastFieldSerializer,
         mapping_opt: Option<&HashMap<UnorderedTermId, usize>>,
     ) -> io::Result<()> {
-        {
-            // writing the offset index
-            let mut doc_index_serializer =
-                serializer.new_u64_fast_field_with_idx(self.field, 0, self.vals.len() as u64, 0)?;
-            for &offset in &self.doc_index {
-                doc_index_serializer.add_val(offset)?;
-            }
-            doc_index_serializer.add_val(self.vals.len() as u64)?;
-            doc_index_serializer.close_field()?;
-        }
-        {
-            // writing the values themselves.
-            let mut value_serializer: FastSingleFieldSerializer<_>;
-            match mapping_opt {
-                Some(mapping) => {
-                    value_serializer =
-                        serializer.new_u64_fast_field_with_idx(self.field, 0u64, mapping.len() as u64, 1)?;
-                    for val in &self.vals {
-                        let remapped_val = *mapping.get(val).expect("Missing term ordinal") as u64;
-                        value_serializer.add_val(remapped_val)?;
-                    }
-                }
-                None => {
-                    let val_min_max = self.vals.iter().cloned().minmax();
-                    let (val_min, val_max) = val_min_max.into_option().unwrap_or((0u64, 0));
-                    value_serializer =
-                        serializer.new_u64_fast_field_with_idx(self.field, val_min, val_max, 1)?;
-                    for &val in &self.vals {
-                        value_serializer.add_val(val)?;
-                    }
+        // writing the offset index
+        let num_vals = self.vals.len() as u64;
+        serializer.write_u64_fast_field_with_idx(
+            self.field,
+            0,
+            0,
+            num_vals,
+            |doc_index_serializer| {
+                for &offset in &self.doc_index {
+                    doc_index_serializer.add_val(offset)?;
                 }
+                doc_index_serializer.add_val(num_vals)
+            },
+        )?;
+        // writing the values themselves.
+        match mapping_opt {
+            Some(mapping) => {
+                let max_ord = mapping.len() as u64;
+                serializer.write_u64_fast_field_with_idx(
+                    self.field,
+                    1,
+                    0u64,
+                    max_ord,
+                    |value_serializer| {
+                        for val in &self.vals {
+                            let remapped_val =
+                                *mapping.get(val).expect("Missing term ordinal") as u64;
+                            value_serializer.add_val(remapped_val)?;
+                        }
+                        Ok(())
+                    },
+                )
+            }
+            None => {
+                let val_min_max = self.vals.iter().cloned().minmax();
+                let (val_min, val_max) = val_min_max.into_option().unwrap_or((0u64, 0));
+                serializer.write_u64_fast_field_with_idx(
+                    self.field,
+                    1,
+                    val_min,
+                    val_max,
+                    |value_serializer| {
+                        for &val in &self.vals {
+                            value_serializer.add_val(val)?;
+                        }
+                        Ok(())
+                    },
+                )
             }
-            value_serializer.close_field()?;
         }
-        Ok(())
     }
 }
@@ -1,19 +1,21 @@
-use common::BinarySerializable;
-use common::bitpacker::BitUnpacker;
-use common::CompositeFile;
-use common::compute_num_bits;
+use common::{BitSet, CompositeFile};
 use directory::{Directory, RAMDirectory, WritePtr};
 use directory::ReadOnlySource;
 use DocId;
+use error::DataCorruption;
 use fastfield::{FastFieldSerializer, FastFieldsWriter};
-use owning_ref::OwningRef;
+use fastfield::codecs::{CodecReader, CODEC_FORMAT_VERSION};
 use schema::FAST;
+use schema::Field;
 use schema::SchemaBuilder;
-use std::collections::HashMap;
+use std::cmp;
+use std::io;
 use std::marker::PhantomData;
 use std::mem;
 use std::path::Path;
+use std::sync::Arc;
 use super::FastValue;
+use super::FieldUpdates;
 
 /// Trait for accessing a fastfield.
 ///
@@ -21,37 +23,65 @@ use super::FastValue;
 /// fast field is required.
 #[derive(Clone)]
 pub struct FastFieldReader<Item: FastValue> {
-    bit_unpacker: BitUnpacker<OwningRef<ReadOnlySource, [u8]>>,
-    min_value_u64: u64,
-    max_value_u64: u64,
+    codec_reader: Arc<CodecReader>,
+    updates: Option<Arc<FieldUpdates>>,
     _phantom: PhantomData<Item>
 }
 
 impl<Item: FastValue> FastFieldReader<Item> {
 
     /// Opens a fast field given a source.
-    pub fn open(data: ReadOnlySource) -> Self {
-        let min_value: u64;
-        let amplitude: u64;
-        {
-            let mut cursor = data.as_slice();
-            min_value =
-                u64::deserialize(&mut cursor).expect("Failed to read the min_value of fast field.");
-            amplitude =
-                u64::deserialize(&mut cursor).expect("Failed to read the amplitude of fast field.");
-        }
-        let max_value = min_value + amplitude;
-        let num_bits = compute_num_bits(amplitude);
-        let owning_ref = OwningRef::new(data).map(|data| &data[16..]);
-        let bit_unpacker = BitUnpacker::new(owning_ref, num_bits);
+    ///
+    /// Returns a `DataCorruption` error if the header of the fast field
+    /// is invalid, or if its values are truncated.
+    pub fn open(data: ReadOnlySource) -> io::Result<Self> {
+        let codec_reader = CodecReader::open(data)?;
+        Ok(FastFieldReader {
+            codec_reader: Arc::new(codec_reader),
+            updates: None,
+            _phantom: PhantomData
+        })
+    }
+
+    /// Opens a fast field written before `CODEC_FORMAT_VERSION`,
+    /// holding `num_vals` values.
+    pub(crate) fn open_legacy(data: ReadOnlySource, num_vals: usize) -> io::Result<Self> {
+        let codec_reader = CodecReader::open_legacy(data, num_vals)?;
+        Ok(FastFieldReader {
+            codec_reader: Arc::new(codec_reader),
+            updates: None,
+            _phantom: PhantomData
+        })
+    }
+
+    /// Reinterprets the fast field as a fast field of another type.
+    ///
+    /// Both `u64` and `i64` are encoded as `u64`, so that an opened
+    /// fast field can be shared regardless of its type.
+    pub(crate) fn cast<TargetItem: FastValue>(self) -> FastFieldReader<TargetItem> {
         FastFieldReader {
-            min_value_u64: min_value,
-            max_value_u64: max_value,
-            bit_unpacker,
+            codec_reader: self.codec_reader,
+            updates: self.updates,
             _phantom: PhantomData
         }
     }
 
+    /// Stacks updated values over the values of the fast field.
+    ///
+    /// See `FastFieldUpdates`.
+    pub(crate) fn with_updates(mut self, updates: Arc<FieldUpdates>) -> Self {
+        self.updates = Some(updates);
+        self
+    }
+
+    /// Returns the number of documents the fast field
+    /// holds values for.
+    ///
+    /// A fast field holding less values than the number of documents
+    /// of its segment is corrupted.
+    pub fn capacity(&self) -> usize {
+        self.codec_reader.num_vals()
+    }
 
     /// Return the value associated to the given document.
     ///
@@ -61,43 +91,167 @@ impl<Item: FastValue> FastFieldReader<Item> {
     ///
     /// May panic if `doc` is greater than the segment
     // `maxdoc`.
+    ///
+    /// Documents without any value get the default value
+    /// of `Item`, that is `0`.
     pub fn get(&self, doc: DocId) -> Item {
-        Item::from_u64(self.min_value_u64 + self.bit_unpacker.get(doc as usize))
+        self.get_opt(doc).unwrap_or_default()
+    }
+
+    /// Return the value associated to the given document,
+    /// or `None` if the document does not have any value.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `doc` is greater than the segment
+    // `maxdoc`.
+    pub fn get_opt(&self, doc: DocId) -> Option<Item> {
+        if let Some(ref updates) = self.updates {
+            if let Some(val) = updates.get(doc) {
+                return Some(Item::from_u64(val));
+            }
+        }
+        self.codec_reader.get_opt(doc as usize).map(Item::from_u64)
     }
 
     /// Fills an output buffer with the fast field values
     /// associated with the `DocId` going from
     /// `start` to `start + output.len()`.
     ///
+    /// Documents without any value get the default value of `Item`.
+    ///
     /// # Panics
     ///
     /// May panic if `start + output.len()` is greater than
     /// the segment's `maxdoc`.
     pub fn get_range(&self, start: u32, output: &mut [Item])  {
         let output_u64: &mut [u64] = unsafe { mem::transmute(output) };
-        self.bit_unpacker.get_range(start, output_u64);
+        self.codec_reader
+            .get_range(start, output_u64, Item::default().to_u64());
         for out in output_u64.iter_mut() {
-            *out = Item::from_u64(*out + self.min_value_u64).as_u64();
+            *out = Item::from_u64(*out).as_u64();
+        }
+        if let Some(ref updates) = self.updates {
+            for (doc, out) in (start..).zip(output_u64.iter_mut()) {
+                if let Some(val) = updates.get(doc) {
+                    *out = Item::from_u64(val).as_u64();
+                }
+            }
+        }
+    }
+
+    /// Inserts in `output` the documents whose value is within
+    /// `[min_value, max_value]`, bounds included.
+    ///
+    /// The column is scanned by blocks of 64 documents, and the values of a
+    /// block are compared without any branch, which lets the compiler
+    /// use SIMD instructions. Filtering documents this way is much cheaper
+    /// than calling `.get(...)` for each of them.
+    ///
+    /// Documents without any value never match.
+    ///
+    /// # Panics
+    ///
+    /// May panic if `output` cannot hold all of the `DocId`s of the segment,
+    /// that is if its `max_value` is lower than the segment's `max_doc`.
+    pub fn get_range_docids(&self, min_value: Item, max_value: Item, output: &mut BitSet) {
+        let low = min_value.to_u64();
+        let high = max_value.to_u64();
+        let mut updated_docs: Vec<(DocId, u64)> = self.updates
+            .as_ref()
+            .map(|updates| updates.iter().map(|(&doc, &val)| (doc, val)).collect())
+            .unwrap_or_default();
+        updated_docs.sort();
+        let mut updated_cursor = 0;
+        self.codec_reader.scan_range(low, high, |block_id, mut mask| {
+            // the stored values of the updated documents are ignored.
+            let block_start = block_id as DocId * 64;
+            while updated_cursor < updated_docs.len()
+                && updated_docs[updated_cursor].0 < block_start + 64
+            {
+                let doc = updated_docs[updated_cursor].0;
+                if doc >= block_start {
+                    mask &= !(1u64 << (doc - block_start));
+                }
+                updated_cursor += 1;
+            }
+            output.insert_mask(block_id as u32, mask);
+        });
+        for &(doc, val) in &updated_docs {
+            if low <= val && val <= high {
+                output.insert(doc);
+            }
         }
     }
 
     /// Returns the minimum value for this fast field.
     ///
-    /// The max value does not take in account of possible
+    /// Documents without any value are ignored.
+    /// The min value does not take in account of possible
     /// deleted document, and should be considered as an upper bound
     /// of the actual maximum value.
     pub fn min_value(&self) -> Item {
-        Item::from_u64(self.min_value_u64)
+        let updated_min_value = self.updates
+            .as_ref()
+            .and_then(|updates| updates.min_value());
+        match updated_min_value {
+            Some(val) => Item::from_u64(cmp::min(val, self.codec_reader.min_value())),
+            None => Item::from_u64(self.codec_reader.min_value()),
+        }
     }
 
     /// Returns the maximum value for this fast field.
     ///
+    /// Documents without any value are ignored.
     /// The max value does not take in account of possible
     /// deleted document, and should be considered as an upper bound
     /// of the actual maximum value.
     pub fn max_value(&self) -> Item {
-        Item::from_u64(self.max_value_u64)
+        let updated_max_value = self.updates
+            .as_ref()
+            .and_then(|updates| updates.max_value());
+        match updated_max_value {
+            Some(val) => Item::from_u64(cmp::max(val, self.codec_reader.max_value())),
+            None => Item::from_u64(self.codec_reader.max_value()),
+        }
+    }
+}
+
+/// Opens the fast field column stored at the index `idx` of a composite file
+/// (fast fields or field norms) of a segment, for the given field.
+///
+/// `format_version` is the version of the format of the segment, and `max_doc`
+/// its number of documents. The columns written before `CODEC_FORMAT_VERSION`
+/// do not record their number of values: a column holds a value per document,
+/// except the index column of a multivalued field (its column `0`), holding one
+/// more value, which is the number of values of its values column (its column `1`).
+///
+/// Returns `None` if the column does not exist.
+pub(crate) fn open_fast_field_column(
+    composite_file: &CompositeFile,
+    field: Field,
+    idx: usize,
+    format_version: u32,
+    max_doc: DocId,
+) -> Option<io::Result<FastFieldReader<u64>>> {
+    let source = composite_file.open_read_with_idx(field, idx)?;
+    if format_version >= CODEC_FORMAT_VERSION {
+        return Some(FastFieldReader::open(source));
     }
+    let is_multivalued = composite_file.open_read_with_idx(field, 1).is_some();
+    let num_vals_res: io::Result<usize> = match idx {
+        0 if is_multivalued => Ok(max_doc as usize + 1),
+        0 => Ok(max_doc as usize),
+        _ => match open_fast_field_column(composite_file, field, 0, format_version, max_doc) {
+            Some(idx_reader_res) => {
+                idx_reader_res.map(|idx_reader| idx_reader.get(max_doc) as usize)
+            }
+            None => {
+                Err(DataCorruption::comment_only("Fast field index column is missing.").into())
+            }
+        },
+    };
+    Some(num_vals_res.and_then(|num_vals| FastFieldReader::open_legacy(source, num_vals)))
 }
 
 impl<Item: FastValue> From<Vec<Item>> for FastFieldReader<Item> {
@@ -134,7 +288,7 @@ impl<Item: FastValue> From<Vec<Item>> for FastFieldReader<Item> {
         let field_source = composite_file
             .open_read(field)
             .expect("File component not found");
-        FastFieldReader::open(field_source)
+        FastFieldReader::open(field_source).expect("Failed to open the fast field")
     }
 }
 
@@ -1,31 +1,28 @@
-use common::BinarySerializable;
 use directory::WritePtr;
 use schema::Field;
-use common::bitpacker::BitPacker;
-use common::compute_num_bits;
 use common::CountingWriter;
 use common::CompositeWrite;
+use fastfield::codecs::{FieldEncoder, FieldStats, LinearStats};
 use std::io::{self, Write};
 
 /// `FastFieldSerializer` is in charge of serializing
 /// fastfields on disk.
 ///
-/// Fast fields are encoded using bit-packing.
+/// Fast fields are encoded using bit-packing, with the codec
+/// that is the most compact for their values.
+/// See the `codecs` module.
 ///
 /// `FastFieldWriter`s are in charge of pushing the data to
-/// the serializer.
-/// The serializer expects to receive the following calls.
+/// the serializer, through the following calls.
 ///
-/// * `new_u64_fast_field(...)`
-/// * `add_val(...)`
-/// * `add_val(...)`
-/// * `add_val(...)`
-/// * ...
-/// * `close_field()`
-/// * `new_u64_fast_field(...)`
-/// * `add_val(...)`
-/// * ...
-/// * `close_field()`
+/// * `write_u64_fast_field(...)`, with a closure calling
+///     * `add_val(...)`
+///     * `add_missing()`
+///     * `add_val(...)`
+///     * ...
+/// * `write_u64_fast_field(...)`, with a closure calling
+///     * `add_val(...)`
+///     * ...
 /// * `close()`
 pub struct FastFieldSerializer {
     composite_write: CompositeWrite<WritePtr>,
@@ -41,26 +38,70 @@ impl FastFieldSerializer {
         })
     }
 
-    /// Start serializing a new u64 fast field
-    pub fn new_u64_fast_field(
+    /// Serializes a new u64 fast field.
+    ///
+    /// See `write_u64_fast_field_with_idx`.
+    pub fn write_u64_fast_field<F, E>(
         &mut self,
         field: Field,
         min_value: u64,
         max_value: u64,
-    ) -> io::Result<FastSingleFieldSerializer<CountingWriter<WritePtr>>> {
-        self.new_u64_fast_field_with_idx(field, min_value, max_value, 0)
+        push_vals: F,
+    ) -> Result<(), E>
+    where
+        F: FnMut(&mut FastSingleFieldSerializer) -> Result<(), E>,
+        E: From<io::Error>,
+    {
+        self.write_u64_fast_field_with_idx(field, 0, min_value, max_value, push_vals)
     }
 
-    /// Start serializing a new u64 fast field
-    pub fn new_u64_fast_field_with_idx(
+    /// Serializes a new u64 fast field, in the composite file slot `idx`.
+    ///
+    /// `push_vals` pushes the value of each document, all within
+    /// `[min_value, max_value]`, to the `FastSingleFieldSerializer` it is given.
+    /// It is called three times, and must push the same values every time:
+    /// the first two calls gather the statistics the codec is picked from,
+    /// and the last one writes the values. This way, the values are never
+    /// buffered by the serializer.
+    pub fn write_u64_fast_field_with_idx<F, E>(
         &mut self,
         field: Field,
+        idx: usize,
         min_value: u64,
         max_value: u64,
+        mut push_vals: F,
+    ) -> Result<(), E>
+    where
+        F: FnMut(&mut FastSingleFieldSerializer) -> Result<(), E>,
+        E: From<io::Error>,
+    {
+        let mut stats = FieldStats::new(min_value, max_value);
+        push_vals(&mut FastSingleFieldSerializer {
+            pass: Pass::Stats(&mut stats),
+        })?;
+        let mut linear_stats = stats.linear_stats();
+        push_vals(&mut FastSingleFieldSerializer {
+            pass: Pass::LinearStats(&mut linear_stats),
+        })?;
+        let field_write = self.composite_write.for_field_with_idx(field, idx);
+        let mut encoder = FieldEncoder::open(field_write, stats, &linear_stats)?;
+        push_vals(&mut FastSingleFieldSerializer {
+            pass: Pass::Write(&mut encoder, &mut *field_write),
+        })?;
+        encoder.close(field_write)?;
+        Ok(())
+    }
+
+    /// Start serializing a new bytes fast field.
+    ///
+    /// The bytes are written as is, in the composite file slot `idx`.
+    pub fn new_bytes_fast_field_with_idx(
+        &mut self,
+        field: Field,
         idx: usize,
-    ) -> io::Result<FastSingleFieldSerializer<CountingWriter<WritePtr>>> {
+    ) -> FastBytesFieldSerializer<CountingWriter<WritePtr>> {
         let field_write = self.composite_write.for_field_with_idx(field, idx);
-        FastSingleFieldSerializer::open(field_write, min_value, max_value)
+        FastBytesFieldSerializer { write: field_write }
     }
 
     /// Closes the serializer
@@ -71,41 +112,61 @@ impl FastFieldSerializer {
     }
 }
 
-pub struct FastSingleFieldSerializer<'a, W: Write + 'a> {
-    bit_packer: BitPacker,
-    write: &'a mut W,
-    min_value: u64,
-    num_bits: u8,
+enum Pass<'a> {
+    Stats(&'a mut FieldStats),
+    LinearStats(&'a mut LinearStats),
+    Write(&'a mut FieldEncoder, &'a mut CountingWriter<WritePtr>),
 }
 
-impl<'a, W: Write> FastSingleFieldSerializer<'a, W> {
-    fn open(
-        write: &'a mut W,
-        min_value: u64,
-        max_value: u64,
-    ) -> io::Result<FastSingleFieldSerializer<'a, W>> {
-        min_value.serialize(write)?;
-        let amplitude = max_value - min_value;
-        amplitude.serialize(write)?;
-        let num_bits = compute_num_bits(amplitude);
-        let bit_packer = BitPacker::new();
-        Ok(FastSingleFieldSerializer {
-            write,
-            bit_packer,
-            min_value,
-            num_bits,
-        })
+/// Receives the values of a single fast field, one document after the other.
+///
+/// See `FastFieldSerializer::write_u64_fast_field_with_idx`.
+pub struct FastSingleFieldSerializer<'a> {
+    pass: Pass<'a>,
+}
+
+impl<'a> FastSingleFieldSerializer<'a> {
+    fn push(&mut self, val_opt: Option<u64>) -> io::Result<()> {
+        match self.pass {
+            Pass::Stats(ref mut stats) => stats.push(val_opt),
+            Pass::LinearStats(ref mut linear_stats) => {
+                if let Some(val) = val_opt {
+                    linear_stats.push(val);
+                }
+            }
+            Pass::Write(ref mut encoder, ref mut write) => {
+                encoder.push(val_opt, &mut **write)?;
+            }
+        }
+        Ok(())
     }
 
-    /// Pushes a new value to the currently open u64 fast field.
+    /// Pushes the value of the next document.
     pub fn add_val(&mut self, val: u64) -> io::Result<()> {
-        let val_to_write: u64 = val - self.min_value;
-        self.bit_packer
-            .write(val_to_write, self.num_bits, &mut self.write)?;
-        Ok(())
+        self.push(Some(val))
+    }
+
+    /// Records that the next document does not have any value.
+    ///
+    /// If some of the documents do not have any value, the field
+    /// is serialized with the sparse codec.
+    pub fn add_missing(&mut self) -> io::Result<()> {
+        self.push(None)
+    }
+}
+
+/// Serializes the raw bytes of a bytes fast field.
+pub struct FastBytesFieldSerializer<'a, W: Write + 'a> {
+    write: &'a mut W,
+}
+
+impl<'a, W: Write> FastBytesFieldSerializer<'a, W> {
+    /// Appends bytes to the field.
+    pub fn write_all(&mut self, vals: &[u8]) -> io::Result<()> {
+        self.write.write_all(vals)
     }
 
-    pub fn close_field(mut self) -> io::Result<()> {
-        self.bit_packer.close(&mut self.write)
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
     }
 }
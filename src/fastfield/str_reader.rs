@@ -0,0 +1,158 @@
+use super::MultiValueIntFastFieldReader;
+use DocId;
+use termdict::TermOrdinal;
+use termdict::{TermDictionary, TermDictionaryImpl};
+
+/// The str fast field reader makes it possible to access the terms
+/// of a text fast field for a given document, in a specific segment.
+///
+/// Terms are exposed in the form of term ordinals, that is their position
+/// in the sorted term dictionary of the field. This ordinal is segment
+/// local and only makes sense for a given segment. Comparing
+/// ordinals is equivalent to comparing the terms themselves, which
+/// makes them suited to sort documents or to aggregate them by term.
+///
+/// An ordinal can then be translated into its term via `.ord_to_term(...)`.
+pub struct StrFastFieldReader {
+    term_ords: MultiValueIntFastFieldReader<u64>,
+    term_dict: TermDictionaryImpl,
+}
+
+impl StrFastFieldReader {
+    /// Creates a new `StrFastFieldReader`.
+    pub(crate) fn new(
+        term_ords: MultiValueIntFastFieldReader<u64>,
+        term_dict: TermDictionaryImpl,
+    ) -> StrFastFieldReader {
+        StrFastFieldReader {
+            term_ords,
+            term_dict,
+        }
+    }
+
+    /// Returns the number of distinct terms of the field in the segment.
+    /// This does not take in account the documents that may be marked
+    /// as deleted.
+    ///
+    /// Term ordinals range from `0` to `num_terms() - 1`.
+    pub fn num_terms(&self) -> usize {
+        self.term_dict.num_terms()
+    }
+
+    /// Accessor for the term dictionary of the field.
+    pub fn term_dict(&self) -> &TermDictionaryImpl {
+        &self.term_dict
+    }
+
+    /// Returns the list of term ordinals associated to a document,
+    /// in the order of the tokens of the document.
+    pub fn term_ords(&self, doc: DocId, output: &mut Vec<TermOrdinal>) {
+        self.term_ords.get_vals(doc, output);
+    }
+
+    /// Given a term ordinal, writes the bytes of its term in `output`.
+    ///
+    /// Returns false if the ordinal is out of bounds.
+    pub fn ord_to_term(&self, term_ord: TermOrdinal, output: &mut Vec<u8>) -> bool {
+        self.term_dict.ord_to_term(term_ord, output)
+    }
+
+    /// Given a term ordinal, writes its term in `output`.
+    ///
+    /// Returns false if the ordinal is out of bounds,
+    /// or if the term is not valid utf-8.
+    pub fn ord_to_str(&self, term_ord: TermOrdinal, output: &mut String) -> bool {
+        let mut bytes = Vec::new();
+        if !self.ord_to_term(term_ord, &mut bytes) {
+            return false;
+        }
+        match String::from_utf8(bytes) {
+            Ok(text) => {
+                *output = text;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use schema::{SchemaBuilder, FAST, INT_INDEXED, STRING};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_str_fast_field() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_text_field("tag", STRING.set_fast());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(field=>"zebra", field=>"apple"));
+        index_writer.add_document(doc!());
+        index_writer.add_document(doc!(field=>"mango"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+
+        let searcher = index.searcher();
+        let str_reader = searcher
+            .segment_reader(0)
+            .str_fast_field_reader(field)
+            .unwrap();
+        assert_eq!(str_reader.num_terms(), 3);
+        let mut ords = Vec::new();
+        str_reader.term_ords(0, &mut ords);
+        assert_eq!(&ords[..], &[2, 0]);
+        str_reader.term_ords(1, &mut ords);
+        assert!(ords.is_empty());
+        str_reader.term_ords(2, &mut ords);
+        assert_eq!(&ords[..], &[1]);
+        let mut term = String::new();
+        assert!(str_reader.ord_to_str(1, &mut term));
+        assert_eq!(term, "mango");
+        assert!(!str_reader.ord_to_str(3, &mut term));
+    }
+
+    #[test]
+    fn test_str_fast_field_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let field = schema_builder.add_text_field("tag", STRING.set_fast());
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>0u64, field=>"zebra", field=>"apple"));
+        index_writer.add_document(doc!(id_field=>1u64));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field=>2u64, field=>"mango", field=>"apple"));
+        index_writer.commit().unwrap();
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let reader = searcher.segment_reader(0);
+        let id_reader = reader.fast_field_reader::<u64>(id_field).unwrap();
+        let str_reader = reader.str_fast_field_reader(field).unwrap();
+        assert_eq!(str_reader.num_terms(), 3);
+        let mut terms_per_id = BTreeMap::new();
+        for doc in 0..reader.max_doc() {
+            let mut ords = Vec::new();
+            str_reader.term_ords(doc, &mut ords);
+            let terms: Vec<String> = ords.into_iter()
+                .map(|ord| {
+                    let mut term = String::new();
+                    assert!(str_reader.ord_to_str(ord, &mut term));
+                    term
+                })
+                .collect();
+            terms_per_id.insert(id_reader.get(doc), terms);
+        }
+        assert_eq!(terms_per_id[&0], vec!["zebra", "apple"]);
+        assert!(terms_per_id[&1].is_empty());
+        assert_eq!(terms_per_id[&2], vec!["mango", "apple"]);
+    }
+}
@@ -0,0 +1,227 @@
+use common::BinarySerializable;
+use common::HasLen;
+use directory::ReadOnlySource;
+use directory::WritePtr;
+use error::DataCorruption;
+use schema::Field;
+use std::cmp;
+use std::collections::hash_map;
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::sync::Arc;
+use DocId;
+
+/// Write the `FastFieldUpdates` of a segment.
+///
+/// The updated values of each field are preceded by a header
+/// holding their number, and their exact min and max values.
+pub fn write_fast_field_updates(
+    fast_field_updates: &FastFieldUpdates,
+    writer: &mut WritePtr,
+) -> io::Result<()> {
+    (fast_field_updates.fields.len() as u32).serialize(writer)?;
+    let mut fields: Vec<&Field> = fast_field_updates.fields.keys().collect();
+    fields.sort();
+    for field in fields {
+        let field_updates = &fast_field_updates.fields[field];
+        let mut doc_vals: Vec<(&DocId, &u64)> = field_updates.iter().collect();
+        doc_vals.sort();
+        let min_value = doc_vals.iter().map(|&(_, &val)| val).min().unwrap_or(0u64);
+        let max_value = doc_vals.iter().map(|&(_, &val)| val).max().unwrap_or(0u64);
+        field.serialize(writer)?;
+        (doc_vals.len() as u64).serialize(writer)?;
+        min_value.serialize(writer)?;
+        max_value.serialize(writer)?;
+        for (doc, val) in doc_vals {
+            doc.serialize(writer)?;
+            val.serialize(writer)?;
+        }
+    }
+    writer.flush()
+}
+
+/// Updated values of a fast field, keyed by `DocId`.
+///
+/// The min and max values are maintained as values are updated,
+/// so that they can be read in constant time. When a value is
+/// overwritten, they may only be looser than the actual bounds.
+#[derive(Clone, Debug)]
+pub struct FieldUpdates {
+    vals: HashMap<DocId, u64>,
+    min_value: u64,
+    max_value: u64,
+}
+
+impl Default for FieldUpdates {
+    fn default() -> FieldUpdates {
+        FieldUpdates {
+            vals: HashMap::new(),
+            min_value: u64::max_value(),
+            max_value: u64::min_value(),
+        }
+    }
+}
+
+impl FieldUpdates {
+    fn insert(&mut self, doc: DocId, val: u64) {
+        self.vals.insert(doc, val);
+        self.min_value = cmp::min(self.min_value, val);
+        self.max_value = cmp::max(self.max_value, val);
+    }
+
+    /// Returns the updated value of `doc`, if any.
+    pub fn get(&self, doc: DocId) -> Option<u64> {
+        self.vals.get(&doc).cloned()
+    }
+
+    /// Iterates over the updated documents, and their values.
+    pub fn iter(&self) -> hash_map::Iter<DocId, u64> {
+        self.vals.iter()
+    }
+
+    /// Returns a lower bound of the updated values, or `None`
+    /// if there are no updated values.
+    pub fn min_value(&self) -> Option<u64> {
+        if self.vals.is_empty() {
+            None
+        } else {
+            Some(self.min_value)
+        }
+    }
+
+    /// Returns an upper bound of the updated values, or `None`
+    /// if there are no updated values.
+    pub fn max_value(&self) -> Option<u64> {
+        if self.vals.is_empty() {
+            None
+        } else {
+            Some(self.max_value)
+        }
+    }
+}
+
+impl HasLen for FieldUpdates {
+    fn len(&self) -> usize {
+        self.vals.len()
+    }
+}
+
+/// Fast field values overriding the values stored in the
+/// `.fast` file of a segment.
+///
+/// Updating a fast field does not require to reindex the
+/// document. The updated values are stored in a small file
+/// stacked over the segment's fast fields, and they
+/// only get folded into the `.fast` file when the segment is merged.
+///
+/// Values are stored in their `u64` encoding.
+#[derive(Clone, Default)]
+pub struct FastFieldUpdates {
+    fields: HashMap<Field, Arc<FieldUpdates>>,
+}
+
+impl FastFieldUpdates {
+    /// Opens the fast field updates given their data source.
+    ///
+    /// The min and max values of each field are read from its header.
+    pub fn open(data: ReadOnlySource) -> io::Result<FastFieldUpdates> {
+        let mut fast_field_updates = FastFieldUpdates::empty();
+        let mut cursor = data.as_slice();
+        let num_fields = u32::deserialize(&mut cursor)?;
+        for _ in 0..num_fields {
+            let field = Field::deserialize(&mut cursor)?;
+            let num_vals = u64::deserialize(&mut cursor)?;
+            let min_value = u64::deserialize(&mut cursor)?;
+            let max_value = u64::deserialize(&mut cursor)?;
+            // each value takes 12 bytes.
+            if num_vals > (cursor.len() / 12) as u64 {
+                let msg = "Fast field updates are truncated.";
+                return Err(DataCorruption::comment_only(msg).into());
+            }
+            let mut vals = HashMap::with_capacity(num_vals as usize);
+            for _ in 0..num_vals {
+                let doc = DocId::deserialize(&mut cursor)?;
+                let val = u64::deserialize(&mut cursor)?;
+                vals.insert(doc, val);
+            }
+            let field_updates = FieldUpdates {
+                vals,
+                min_value,
+                max_value,
+            };
+            fast_field_updates.fields.insert(field, Arc::new(field_updates));
+        }
+        Ok(fast_field_updates)
+    }
+
+    /// Returns an empty set of updates.
+    pub fn empty() -> FastFieldUpdates {
+        FastFieldUpdates::default()
+    }
+
+    /// Sets the value of the fast field `field` for the document `doc`.
+    ///
+    /// `val` is expected to be the `u64` encoding of the value.
+    pub fn update(&mut self, field: Field, doc: DocId, val: u64) {
+        let field_updates = self.fields.entry(field).or_insert_with(Default::default);
+        Arc::make_mut(field_updates).insert(doc, val);
+    }
+
+    /// Stacks `other` over the current updates.
+    pub fn extend(&mut self, other: &FastFieldUpdates) {
+        for (&field, field_updates) in &other.fields {
+            for (&doc, &val) in field_updates.iter() {
+                self.update(field, doc, val);
+            }
+        }
+    }
+
+    /// Returns the updated values of a given field, if any.
+    pub fn field_updates(&self, field: Field) -> Option<Arc<FieldUpdates>> {
+        self.fields.get(&field).cloned()
+    }
+}
+
+impl HasLen for FastFieldUpdates {
+    fn len(&self) -> usize {
+        self.fields
+            .values()
+            .map(|field_updates| field_updates.vals.len())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use directory::*;
+    use schema::Field;
+    use super::*;
+
+    #[test]
+    fn test_fast_field_updates() {
+        let test_path = PathBuf::from("test");
+        let mut directory = RAMDirectory::create();
+        let mut fast_field_updates = FastFieldUpdates::empty();
+        fast_field_updates.update(Field(1), 3, 13);
+        fast_field_updates.update(Field(0), 7, 4);
+        fast_field_updates.update(Field(1), 3, 14);
+        assert_eq!(fast_field_updates.len(), 2);
+        {
+            let mut writer = directory.open_write(&*test_path).unwrap();
+            write_fast_field_updates(&fast_field_updates, &mut writer).unwrap();
+        }
+        let source = directory.open_read(&test_path).unwrap();
+        let reloaded = FastFieldUpdates::open(source).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        let field_updates = reloaded.field_updates(Field(1)).unwrap();
+        assert_eq!(field_updates.get(3), Some(14));
+        // the bounds of the reloaded updates are exact.
+        assert_eq!(field_updates.min_value(), Some(14));
+        assert_eq!(field_updates.max_value(), Some(14));
+        assert_eq!(reloaded.field_updates(Field(0)).unwrap().get(7), Some(4));
+        assert!(reloaded.field_updates(Field(2)).is_none());
+        // in memory, overwritten values only loosen the bounds.
+        assert_eq!(fast_field_updates.field_updates(Field(1)).unwrap().min_value(), Some(13));
+    }
+}
@@ -0,0 +1,414 @@
+//! Hierarchical Navigable Small World graphs, used as the approximate
+//! nearest neighbor index of vector fields.
+//!
+//! The graph is built over the ordinals of the vectors of a segment.
+//! Each node lives on all of the layers from `0` to its own level,
+//! with the level drawn from a geometric distribution. The upper layers
+//! are sparse and make it possible to quickly reach the neighborhood of the
+//! query vector, which is then explored on the dense layer `0`.
+//!
+//! # Serialization
+//!
+//! All integers are `u32`, written in little endian.
+//!
+//! - header: `num_nodes`, `entry_point`, `max_level`
+//! - for each node: its `DocId`, its level, and the offset of its neighbor lists
+//!   relative to the beginning of the neighbor lists.
+//! - for each node and each of its layers, from `0` to its level: the number of
+//!   neighbors, followed by the neighbors' ordinals.
+
+use std::cmp::{self, Ordering};
+use std::collections::{BinaryHeap, HashSet};
+
+use byteorder::ByteOrder;
+use common::Endianness;
+use directory::ReadOnlySource;
+use schema::VectorSimilarity;
+use DocId;
+
+/// Maximum number of neighbors of a node on the upper layers.
+const MAX_NEIGHBORS: usize = 16;
+/// Maximum number of neighbors of a node on the layer `0`.
+const MAX_NEIGHBORS_LAYER_0: usize = 2 * MAX_NEIGHBORS;
+/// Number of candidates explored while inserting a node.
+const EF_CONSTRUCTION: usize = 100;
+
+const HEADER_NUM_BYTES: usize = 12;
+const NODE_NUM_BYTES: usize = 12;
+
+#[derive(Clone, Copy)]
+struct Candidate {
+    similarity: f32,
+    ord: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Candidate) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Candidate) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // Candidates are ordered by similarity, and in case of a tie
+    // the lowest ordinal is considered the greatest.
+    fn cmp(&self, other: &Candidate) -> Ordering {
+        self.similarity
+            .partial_cmp(&other.similarity)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.ord.cmp(&self.ord))
+    }
+}
+
+/// Explores a layer of the graph, starting from `entry_points`.
+///
+/// Returns up to `ef` candidates, sorted by decreasing similarity.
+fn search_layer<TSimilarity, TNeighbors>(
+    entry_points: &[Candidate],
+    ef: usize,
+    similarity_to: &TSimilarity,
+    neighbors_of: &TNeighbors,
+) -> Vec<Candidate>
+where
+    TSimilarity: Fn(u32) -> f32,
+    TNeighbors: Fn(u32, &mut Vec<u32>),
+{
+    let mut visited: HashSet<u32> = entry_points.iter().map(|candidate| candidate.ord).collect();
+    // best candidates first
+    let mut candidates: BinaryHeap<Candidate> = entry_points.iter().cloned().collect();
+    // worst results first
+    let mut results: BinaryHeap<cmp::Reverse<Candidate>> =
+        entry_points.iter().cloned().map(cmp::Reverse).collect();
+    let mut neighbors = Vec::new();
+    while let Some(candidate) = candidates.pop() {
+        if results.len() >= ef {
+            if let Some(&cmp::Reverse(worst)) = results.peek() {
+                if candidate < worst {
+                    break;
+                }
+            }
+        }
+        neighbors_of(candidate.ord, &mut neighbors);
+        for &neighbor in &neighbors {
+            if !visited.insert(neighbor) {
+                continue;
+            }
+            let neighbor_candidate = Candidate {
+                similarity: similarity_to(neighbor),
+                ord: neighbor,
+            };
+            let is_better = results.len() < ef || results
+                .peek()
+                .map(|&cmp::Reverse(worst)| neighbor_candidate > worst)
+                .unwrap_or(true);
+            if is_better {
+                candidates.push(neighbor_candidate);
+                results.push(cmp::Reverse(neighbor_candidate));
+                if results.len() > ef {
+                    results.pop();
+                }
+            }
+        }
+    }
+    let mut results: Vec<Candidate> = results
+        .into_iter()
+        .map(|cmp::Reverse(candidate)| candidate)
+        .collect();
+    results.sort_by(|left, right| right.cmp(left));
+    results
+}
+
+/// Draws the level of a node from a geometric distribution.
+///
+/// The level only depends on the ordinal of the node, so that
+/// building a graph is deterministic.
+fn node_level(ord: u32) -> usize {
+    // splitmix64
+    let mut z = u64::from(ord).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    // uniform in `]0, 1]`
+    let uniform = ((z >> 11) + 1) as f64 / (1u64 << 53) as f64;
+    let level_multiplier = 1f64 / (MAX_NEIGHBORS as f64).ln();
+    (-uniform.ln() * level_multiplier) as usize
+}
+
+fn max_neighbors(level: usize) -> usize {
+    if level == 0 {
+        MAX_NEIGHBORS_LAYER_0
+    } else {
+        MAX_NEIGHBORS
+    }
+}
+
+fn vector_at(vals: &[f32], dimension: usize, ord: u32) -> &[f32] {
+    let start = ord as usize * dimension;
+    &vals[start..start + dimension]
+}
+
+/// Builds the HNSW graph of the vectors of a segment,
+/// and returns its serialized form.
+///
+/// `vals` is the concatenation of the vectors, in the order of their ordinals,
+/// and `docs` associates each ordinal to its `DocId`.
+pub(crate) fn build_hnsw(
+    vals: &[f32],
+    dimension: usize,
+    docs: &[DocId],
+    similarity: VectorSimilarity,
+) -> Vec<u8> {
+    let num_nodes = docs.len();
+    // for each node, its neighbors on each of its layers.
+    let mut graph: Vec<Vec<Vec<u32>>> = Vec::with_capacity(num_nodes);
+    let mut entry_point: Option<(u32, usize)> = None;
+    for ord in 0..num_nodes as u32 {
+        let level = node_level(ord);
+        graph.push(vec![Vec::new(); level + 1]);
+        let (entry_ord, max_level) = match entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                entry_point = Some((ord, level));
+                continue;
+            }
+        };
+        let query = vector_at(vals, dimension, ord);
+        let similarity_to = |other: u32| similarity.compute(query, vector_at(vals, dimension, other));
+        let mut entry_points = vec![
+            Candidate {
+                similarity: similarity_to(entry_ord),
+                ord: entry_ord,
+            },
+        ];
+        for layer in (0..max_level + 1).rev() {
+            let ef = if layer > level { 1 } else { EF_CONSTRUCTION };
+            let found = {
+                let neighbors_of = |node: u32, output: &mut Vec<u32>| {
+                    output.clear();
+                    output.extend_from_slice(&graph[node as usize][layer]);
+                };
+                search_layer(&entry_points, ef, &similarity_to, &neighbors_of)
+            };
+            if layer <= level {
+                let layer_max_neighbors = max_neighbors(layer);
+                let neighbors: Vec<u32> = found
+                    .iter()
+                    .take(layer_max_neighbors)
+                    .map(|candidate| candidate.ord)
+                    .collect();
+                for &neighbor in &neighbors {
+                    let neighbor_neighbors = &mut graph[neighbor as usize][layer];
+                    neighbor_neighbors.push(ord);
+                    if neighbor_neighbors.len() > layer_max_neighbors {
+                        // keeps the closest neighbors only.
+                        let neighbor_vector = vector_at(vals, dimension, neighbor);
+                        let mut scored: Vec<Candidate> = neighbor_neighbors
+                            .iter()
+                            .map(|&other| Candidate {
+                                similarity: similarity.compute(neighbor_vector, vector_at(vals, dimension, other)),
+                                ord: other,
+                            })
+                            .collect();
+                        scored.sort_by(|left, right| right.cmp(left));
+                        neighbor_neighbors.clear();
+                        neighbor_neighbors.extend(
+                            scored
+                                .into_iter()
+                                .take(layer_max_neighbors)
+                                .map(|candidate| candidate.ord),
+                        );
+                    }
+                }
+                graph[ord as usize][layer] = neighbors;
+            }
+            entry_points = found;
+        }
+        if level > max_level {
+            entry_point = Some((ord, level));
+        }
+    }
+    serialize_graph(&graph, docs, entry_point)
+}
+
+fn push_u32(output: &mut Vec<u8>, val: u32) {
+    let mut buffer = [0u8; 4];
+    Endianness::write_u32(&mut buffer, val);
+    output.extend_from_slice(&buffer);
+}
+
+fn serialize_graph(
+    graph: &[Vec<Vec<u32>>],
+    docs: &[DocId],
+    entry_point: Option<(u32, usize)>,
+) -> Vec<u8> {
+    let mut output = Vec::new();
+    let (entry_ord, max_level) = entry_point.unwrap_or((0, 0));
+    push_u32(&mut output, graph.len() as u32);
+    push_u32(&mut output, entry_ord);
+    push_u32(&mut output, max_level as u32);
+    let mut offset = 0u32;
+    for (layers, &doc) in graph.iter().zip(docs) {
+        push_u32(&mut output, doc);
+        push_u32(&mut output, (layers.len() - 1) as u32);
+        push_u32(&mut output, offset);
+        let num_vals: usize = layers.iter().map(|neighbors| neighbors.len() + 1).sum();
+        offset += (num_vals * 4) as u32;
+    }
+    for layers in graph {
+        for neighbors in layers {
+            push_u32(&mut output, neighbors.len() as u32);
+            for &neighbor in neighbors {
+                push_u32(&mut output, neighbor);
+            }
+        }
+    }
+    output
+}
+
+/// Reader of a serialized HNSW graph.
+///
+/// The graph is read directly from its `ReadOnlySource`,
+/// without being deserialized first.
+#[derive(Clone)]
+pub(crate) struct HnswIndex {
+    data: ReadOnlySource,
+}
+
+impl HnswIndex {
+    pub fn open(data: ReadOnlySource) -> HnswIndex {
+        HnswIndex { data }
+    }
+
+    fn read_u32(&self, offset: usize) -> u32 {
+        Endianness::read_u32(&self.data.as_slice()[offset..offset + 4])
+    }
+
+    fn num_nodes(&self) -> usize {
+        self.read_u32(0) as usize
+    }
+
+    fn node_offset(&self, ord: u32) -> usize {
+        HEADER_NUM_BYTES + ord as usize * NODE_NUM_BYTES
+    }
+
+    fn doc(&self, ord: u32) -> DocId {
+        self.read_u32(self.node_offset(ord))
+    }
+
+    fn neighbors(&self, ord: u32, layer: usize, output: &mut Vec<u32>) {
+        output.clear();
+        let node_offset = self.node_offset(ord);
+        if layer > self.read_u32(node_offset + 4) as usize {
+            return;
+        }
+        let neighbors_start = HEADER_NUM_BYTES + self.num_nodes() * NODE_NUM_BYTES;
+        let mut offset = neighbors_start + self.read_u32(node_offset + 8) as usize;
+        for _ in 0..layer {
+            let num_neighbors = self.read_u32(offset) as usize;
+            offset += (num_neighbors + 1) * 4;
+        }
+        let num_neighbors = self.read_u32(offset) as usize;
+        for i in 0..num_neighbors {
+            output.push(self.read_u32(offset + (i + 1) * 4));
+        }
+    }
+
+    /// Returns up to `ef` approximate nearest neighbors of the query,
+    /// as pairs of `DocId` and similarity sorted by decreasing similarity.
+    ///
+    /// `similarity_to` computes the similarity between the query and
+    /// the vector of a given ordinal.
+    pub fn search<TSimilarity>(&self, ef: usize, similarity_to: TSimilarity) -> Vec<(DocId, f32)>
+    where
+        TSimilarity: Fn(u32) -> f32,
+    {
+        if self.num_nodes() == 0 || ef == 0 {
+            return Vec::new();
+        }
+        let entry_ord = self.read_u32(4);
+        let max_level = self.read_u32(8) as usize;
+        let mut entry_points = vec![
+            Candidate {
+                similarity: similarity_to(entry_ord),
+                ord: entry_ord,
+            },
+        ];
+        for layer in (0..max_level + 1).rev() {
+            let layer_ef = if layer == 0 { ef } else { 1 };
+            let neighbors_of = |ord: u32, output: &mut Vec<u32>| self.neighbors(ord, layer, output);
+            entry_points = search_layer(&entry_points, layer_ef, &similarity_to, &neighbors_of);
+        }
+        entry_points
+            .into_iter()
+            .map(|candidate| (self.doc(candidate.ord), candidate.similarity))
+            .collect()
+    }
+}
+
+#[cfg(all(test, feature = "mmap"))]
+mod tests {
+
+    use super::*;
+
+    fn brute_force(vals: &[f32], dimension: usize, query: &[f32], k: usize) -> Vec<u32> {
+        let mut candidates: Vec<Candidate> = vals.chunks(dimension)
+            .enumerate()
+            .map(|(ord, vector)| Candidate {
+                similarity: VectorSimilarity::Cosine.compute(query, vector),
+                ord: ord as u32,
+            })
+            .collect();
+        candidates.sort_by(|left, right| right.cmp(left));
+        candidates.into_iter().take(k).map(|candidate| candidate.ord).collect()
+    }
+
+    #[test]
+    fn test_hnsw_empty() {
+        let data = build_hnsw(&[], 2, &[], VectorSimilarity::Cosine);
+        let hnsw = HnswIndex::open(ReadOnlySource::from(data));
+        assert!(hnsw.search(10, |_| 0f32).is_empty());
+    }
+
+    #[test]
+    fn test_hnsw_recall() {
+        let dimension = 4;
+        let num_vectors = 2_000u32;
+        // points on a pseudo-random walk, so that the neighborhoods are non-trivial.
+        let mut vals = Vec::new();
+        let mut state = 1u32;
+        for _ in 0..num_vectors * dimension as u32 {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            vals.push(((state >> 16) % 1_000) as f32 / 1_000f32 - 0.5f32);
+        }
+        let docs: Vec<DocId> = (0..num_vectors).map(|ord| ord * 2).collect();
+        let data = build_hnsw(&vals, dimension, &docs, VectorSimilarity::Cosine);
+        let hnsw = HnswIndex::open(ReadOnlySource::from(data));
+        let mut num_found = 0;
+        let num_queries = 20;
+        for query_ord in 0..num_queries {
+            let query = &vals[query_ord * dimension..(query_ord + 1) * dimension];
+            let expected = brute_force(&vals, dimension, query, 10);
+            let results = hnsw.search(50, |ord| {
+                let start = ord as usize * dimension;
+                VectorSimilarity::Cosine.compute(query, &vals[start..start + dimension])
+            });
+            assert!(results.len() >= 10);
+            for window in results.windows(2) {
+                assert!(window[0].1 >= window[1].1);
+            }
+            num_found += expected
+                .iter()
+                .filter(|&&ord| results[..10].iter().any(|&(doc, _)| doc == ord * 2))
+                .count();
+        }
+        // recall@10 should be close to 1.
+        assert!(num_found * 10 >= num_queries * 10 * 9);
+    }
+}
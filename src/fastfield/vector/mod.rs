@@ -0,0 +1,112 @@
+mod writer;
+mod reader;
+mod hnsw;
+
+pub use self::writer::VectorFastFieldWriter;
+pub use self::reader::VectorFastFieldReader;
+pub(crate) use self::hnsw::build_hnsw;
+
+#[cfg(test)]
+mod tests {
+
+    use error::{Error, ErrorKind};
+    use fastfield::FastFieldsWriter;
+    use futures::Future;
+    use schema::{SchemaBuilder, Term, FAST, INT_INDEXED};
+    use std::collections::BTreeMap;
+    use Index;
+
+    #[test]
+    fn test_vector() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_vector_field("embedding", 2);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(field=>vec![1f32, 2f32]));
+        index_writer.add_document(doc!());
+        index_writer.add_document(doc!(field=>vec![-0.5f32, 0f32]));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let reader = searcher.segment_reader(0);
+        let vector_reader = reader.vector_fast_field_reader(field).unwrap();
+        assert_eq!(vector_reader.dimension(), 2);
+
+        let mut vector = Vec::new();
+        assert!(vector_reader.has_val(0));
+        assert!(vector_reader.get_val(0, &mut vector));
+        assert_eq!(&vector[..], &[1f32, 2f32]);
+        assert!(!vector_reader.has_val(1));
+        assert!(!vector_reader.get_val(1, &mut vector));
+        assert!(vector.is_empty());
+        assert!(vector_reader.get_val(2, &mut vector));
+        assert_eq!(&vector[..], &[-0.5f32, 0f32]);
+    }
+
+    #[test]
+    fn test_vector_dimension_mismatch() {
+        let mut schema_builder = SchemaBuilder::default();
+        let field = schema_builder.add_vector_field("embedding", 2);
+        let schema = schema_builder.build();
+        let mut fast_field_writers = FastFieldsWriter::from_schema(&schema);
+        match fast_field_writers.add_document(&doc!(field=>vec![1f32, 2f32, 3f32])) {
+            Err(Error(ErrorKind::InvalidArgument(_), _)) => {}
+            _ => panic!("Expected InvalidArgument error"),
+        }
+        {
+            let vector_writer = fast_field_writers.get_vector_writer(field).unwrap();
+            assert!(vector_writer.add_document_val(Some(&[1f32][..])).is_err());
+            // the rejected documents were not registered.
+            assert_eq!(vector_writer.add_document_val(Some(&[1f32, 2f32][..])).unwrap(), 0);
+        }
+
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(field=>vec![1f32]));
+        match index_writer.commit() {
+            Err(Error(ErrorKind::InvalidArgument(_), _)) => {}
+            _ => panic!("Expected InvalidArgument error"),
+        }
+    }
+
+    #[test]
+    fn test_vector_merge() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let field = schema_builder.add_vector_field("embedding", 2);
+        let schema = schema_builder.build();
+        let index = Index::create_in_ram(schema);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field=>0u64, field=>vec![0f32, 1f32]));
+        index_writer.add_document(doc!(id_field=>1u64));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field=>2u64, field=>vec![2f32, 2f32]));
+        index_writer.add_document(doc!(id_field=>3u64, field=>vec![3f32, 4f32]));
+        index_writer.commit().unwrap();
+        index_writer.delete_term(Term::from_field_u64(id_field, 2u64));
+        index_writer.commit().unwrap();
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let reader = searcher.segment_reader(0);
+        assert_eq!(reader.num_docs(), 3);
+        let id_reader = reader.fast_field_reader::<u64>(id_field).unwrap();
+        let vector_reader = reader.vector_fast_field_reader(field).unwrap();
+        let mut vectors_per_id = BTreeMap::new();
+        for doc in 0..reader.max_doc() {
+            let mut vector = Vec::new();
+            vector_reader.get_val(doc, &mut vector);
+            vectors_per_id.insert(id_reader.get(doc), vector);
+        }
+        assert_eq!(vectors_per_id[&0], vec![0f32, 1f32]);
+        assert!(vectors_per_id[&1].is_empty());
+        assert_eq!(vectors_per_id[&3], vec![3f32, 4f32]);
+        assert!(!vectors_per_id.contains_key(&2));
+    }
+}
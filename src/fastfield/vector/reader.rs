@@ -0,0 +1,122 @@
+use byteorder::ByteOrder;
+use std::cell::RefCell;
+use common::Endianness;
+use directory::ReadOnlySource;
+use fastfield::FastFieldReader;
+use schema::VectorSimilarity;
+use super::hnsw::HnswIndex;
+use DocId;
+
+/// Reader for vector fast fields
+///
+/// The reader is implemented as a `u64` fast field and a separate collection of `f32`.
+///
+/// The `ords` fast field associates, for each document, the ordinal of its vector,
+/// and is missing for the documents without any vector.
+/// The `values` are the concatenation of the components of all of the vectors,
+/// in the order of their ordinal.
+///
+/// If the field has an approximate nearest neighbor index, the reader
+/// also gives access to the HNSW graph of the segment.
+#[derive(Clone)]
+pub struct VectorFastFieldReader {
+    dimension: usize,
+    ords: FastFieldReader<u64>,
+    values: ReadOnlySource,
+    ann_index: Option<(VectorSimilarity, HnswIndex)>,
+}
+
+impl VectorFastFieldReader {
+    pub(crate) fn open(
+        dimension: usize,
+        ords: FastFieldReader<u64>,
+        values_source: ReadOnlySource,
+    ) -> VectorFastFieldReader {
+        VectorFastFieldReader {
+            dimension,
+            ords,
+            values: values_source,
+            ann_index: None,
+        }
+    }
+
+    pub(crate) fn with_ann_index(
+        mut self,
+        similarity: VectorSimilarity,
+        hnsw_source: ReadOnlySource,
+    ) -> VectorFastFieldReader {
+        self.ann_index = Some((similarity, HnswIndex::open(hnsw_source)));
+        self
+    }
+
+    /// Returns the number of components of the vectors of the field.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns true iff the given `doc` has a vector.
+    pub fn has_val(&self, doc: DocId) -> bool {
+        self.ords.get_opt(doc).is_some()
+    }
+
+    /// Writes the vector associated to the given `doc` in `output`.
+    ///
+    /// Returns false, and leaves `output` empty, if the document
+    /// does not have any vector.
+    pub fn get_val(&self, doc: DocId, output: &mut Vec<f32>) -> bool {
+        output.clear();
+        match self.ords.get_opt(doc) {
+            Some(ord) => {
+                self.get_val_by_ord(ord as u32, output);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn get_val_by_ord(&self, ord: u32, output: &mut Vec<f32>) {
+        output.clear();
+        let num_bytes = self.dimension * 4;
+        let start = ord as usize * num_bytes;
+        let bytes = &self.values.as_slice()[start..start + num_bytes];
+        output.extend(bytes.chunks(4).map(Endianness::read_f32));
+    }
+
+    /// Returns true iff the field has an approximate nearest neighbor index.
+    pub fn has_ann_index(&self) -> bool {
+        self.ann_index.is_some()
+    }
+
+    /// Searches the approximate nearest neighbor index for the
+    /// vectors that are the most similar to `query`.
+    ///
+    /// `num_candidates` is the number of candidates kept while exploring
+    /// the graph, which is also the maximum number of documents returned.
+    /// Increasing it improves the recall, at the expense of speed.
+    ///
+    /// Returns pairs of `DocId` and similarity, sorted by decreasing similarity,
+    /// or `None` if the field does not have an approximate nearest neighbor index.
+    /// Documents marked as deleted are not filtered out.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `query` does not have the dimension of the field.
+    pub fn nearest_neighbors(
+        &self,
+        query: &[f32],
+        num_candidates: usize,
+    ) -> Option<Vec<(DocId, f32)>> {
+        assert_eq!(query.len(), self.dimension);
+        let (similarity, hnsw) = match self.ann_index {
+            Some((similarity, ref hnsw)) => (similarity, hnsw),
+            None => return None,
+        };
+        let vector = RefCell::new(Vec::with_capacity(self.dimension));
+        let hits = hnsw.search(num_candidates, |ord| {
+            let mut vector = vector.borrow_mut();
+            self.get_val_by_ord(ord, &mut vector);
+            similarity.compute(query, &vector)
+        });
+        Some(hits)
+    }
+}
@@ -0,0 +1,163 @@
+use std::io;
+
+use Result;
+use byteorder::ByteOrder;
+use common::Endianness;
+use error::ErrorKind;
+use fastfield::FastFieldSerializer;
+use fastfield::codecs::PresenceBitmap;
+use schema::{Document, Field, Value, VectorOptions};
+use super::hnsw::build_hnsw;
+use DocId;
+
+/// Writer for vector fast fields.
+///
+/// This `VectorFastFieldWriter` is only useful for advanced user.
+/// The normal way to get your vectors in your index
+/// is to
+/// - declare your field with `SchemaBuilder::add_vector_field(...)`
+/// - add your document simply by calling `.add_document(...)`.
+///
+/// The `VectorFastFieldWriter` can be acquired from the
+/// fast field writer by calling
+/// [`.get_vector_writer(...)`](./struct.FastFieldsWriter.html#method.get_vector_writer).
+///
+/// Once acquired, writing is done by calling `.add_document_val(...)`
+/// once per document, even if there is no vector associated to it.
+///
+/// If the field has an approximate nearest neighbor index,
+/// it is built upon serialization.
+pub struct VectorFastFieldWriter {
+    field: Field,
+    options: VectorOptions,
+    vals: Vec<f32>,
+    presence: PresenceBitmap,
+}
+
+impl VectorFastFieldWriter {
+    /// Creates a new `VectorFastFieldWriter`
+    pub fn new(field: Field, options: VectorOptions) -> Self {
+        VectorFastFieldWriter {
+            field,
+            options,
+            vals: Vec::new(),
+            presence: PresenceBitmap::default(),
+        }
+    }
+
+    /// Access the field associated to the `VectorFastFieldWriter`
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    fn check_dimension(&self, components: &[f32]) -> Result<()> {
+        if components.len() != self.options.dimension() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Vector field {:?} expects vectors of dimension {}, got {} components",
+                self.field,
+                self.options.dimension(),
+                components.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that the vector present in the document, if any,
+    /// has the dimension of the field.
+    pub fn check_document(&self, doc: &Document) -> Result<()> {
+        match doc.get_first(self.field) {
+            Some(&Value::Vector(ref vector)) => self.check_dimension(vector.as_slice()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Shift to the next document and adds
+    /// the vector present in the document, if any.
+    ///
+    /// Only the first value of the field is taken in account.
+    ///
+    /// Returns an `InvalidArgument` error if the vector does not
+    /// have the dimension of the field.
+    pub fn add_document(&mut self, doc: &Document) -> Result<()> {
+        match doc.get_first(self.field) {
+            Some(&Value::Vector(ref vector)) => {
+                self.add_document_val(Some(vector.as_slice()))?;
+            }
+            Some(value) => panic!(
+                "Vector field {:?} contained a non-vector value {:?}",
+                self.field, value
+            ),
+            None => {
+                self.add_document_val(None)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Register the vector associated to a document.
+    ///
+    /// The method returns the `DocId` of the document that was
+    /// just written.
+    ///
+    /// Returns an `InvalidArgument` error, and does not register
+    /// the document, if the vector does not have the dimension of the field.
+    pub fn add_document_val(&mut self, val: Option<&[f32]>) -> Result<DocId> {
+        let doc = self.presence.num_docs() as DocId;
+        match val {
+            Some(components) => {
+                self.check_dimension(components)?;
+                self.vals.extend_from_slice(components);
+                self.presence.push(true);
+            }
+            None => {
+                self.presence.push(false);
+            }
+        }
+        Ok(doc)
+    }
+
+    /// Serializes the fast field values by pushing them to the `FastFieldSerializer`.
+    pub fn serialize(&self, serializer: &mut FastFieldSerializer) -> io::Result<()> {
+        let docs: Vec<DocId> = (0..self.presence.num_docs())
+            .filter(|&doc| self.presence.contains(doc))
+            .map(|doc| doc as DocId)
+            .collect();
+        // writing the ordinal of the vector of each document
+        let max_ord = (docs.len() as u64).saturating_sub(1);
+        serializer.write_u64_fast_field_with_idx(
+            self.field,
+            0,
+            0,
+            max_ord,
+            |ord_serializer| -> io::Result<()> {
+                let mut ord = 0u64;
+                for doc in 0..self.presence.num_docs() {
+                    if self.presence.contains(doc) {
+                        ord_serializer.add_val(ord)?;
+                        ord += 1;
+                    } else {
+                        ord_serializer.add_missing()?;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+        {
+            // writing the components themselves
+            let mut value_serializer = serializer.new_bytes_fast_field_with_idx(self.field, 1);
+            let mut buffer = [0u8; 4];
+            for &component in &self.vals {
+                Endianness::write_f32(&mut buffer, component);
+                value_serializer.write_all(&buffer)?;
+            }
+            value_serializer.flush()?;
+        }
+        if let Some(similarity) = self.options.ann_index() {
+            let hnsw = build_hnsw(&self.vals, self.options.dimension(), &docs, similarity);
+            let mut hnsw_serializer = serializer.new_bytes_fast_field_with_idx(self.field, 2);
+            hnsw_serializer.write_all(&hnsw)?;
+            hnsw_serializer.flush()?;
+        }
+        Ok(())
+    }
+}
@@ -1,19 +1,24 @@
+use Result;
 use schema::{Cardinality, Document, Field, Schema};
 use fastfield::FastFieldSerializer;
 use std::io;
 use DocId;
 use schema::FieldType;
-use common;
 use common::VInt;
 use std::collections::HashMap;
 use postings::UnorderedTermId;
 use super::multivalued::MultiValueIntFastFieldWriter;
+use super::bytes::BytesFastFieldWriter;
+use super::vector::VectorFastFieldWriter;
 use common::BinarySerializable;
+use super::codecs::PresenceBitmap;
 
 /// The fastfieldswriter regroup all of the fast field writers.
 pub struct FastFieldsWriter {
     single_value_writers: Vec<IntFastFieldWriter>,
     multi_values_writers: Vec<MultiValueIntFastFieldWriter>,
+    bytes_value_writers: Vec<BytesFastFieldWriter>,
+    vector_value_writers: Vec<VectorFastFieldWriter>,
 }
 
 impl FastFieldsWriter {
@@ -21,20 +26,16 @@ impl FastFieldsWriter {
     pub fn from_schema(schema: &Schema) -> FastFieldsWriter {
         let mut single_value_writers = Vec::new();
         let mut multi_values_writers = Vec::new();
+        let mut bytes_value_writers = Vec::new();
+        let mut vector_value_writers = Vec::new();
 
         for (field_id, field_entry) in schema.fields().iter().enumerate() {
             let field = Field(field_id as u32);
-            let default_value = if let FieldType::I64(_) = *field_entry.field_type() {
-                common::i64_to_u64(0i64)
-            } else {
-                0u64
-            };
             match *field_entry.field_type() {
                 FieldType::I64(ref int_options) | FieldType::U64(ref int_options) => {
                     match int_options.get_fastfield_cardinality() {
                         Some(Cardinality::SingleValue) => {
-                            let mut fast_field_writer = IntFastFieldWriter::new(field);
-                            fast_field_writer.set_val_if_missing(default_value);
+                            let fast_field_writer = IntFastFieldWriter::new(field);
                             single_value_writers.push(fast_field_writer);
                         }
                         Some(Cardinality::MultiValues) => {
@@ -48,12 +49,28 @@ impl FastFieldsWriter {
                     let fast_field_writer = MultiValueIntFastFieldWriter::new(field, true);
                     multi_values_writers.push(fast_field_writer);
                 }
+                FieldType::Str(ref text_options)
+                    if text_options.is_fast() && text_options.get_indexing_options().is_some() =>
+                {
+                    let fast_field_writer = MultiValueIntFastFieldWriter::new(field, true);
+                    multi_values_writers.push(fast_field_writer);
+                }
+                FieldType::Bytes => {
+                    let fast_field_writer = BytesFastFieldWriter::new(field);
+                    bytes_value_writers.push(fast_field_writer);
+                }
+                FieldType::Vector(ref vector_options) => {
+                    let fast_field_writer = VectorFastFieldWriter::new(field, vector_options.clone());
+                    vector_value_writers.push(fast_field_writer);
+                }
                 _ => {}
             }
         }
         FastFieldsWriter {
             single_value_writers,
-            multi_values_writers
+            multi_values_writers,
+            bytes_value_writers,
+            vector_value_writers,
         }
     }
 
@@ -63,6 +80,8 @@ impl FastFieldsWriter {
         FastFieldsWriter {
             single_value_writers: fields.into_iter().map(IntFastFieldWriter::new).collect(),
             multi_values_writers: vec![],
+            bytes_value_writers: vec![],
+            vector_value_writers: vec![],
         }
     }
 
@@ -89,8 +108,36 @@ impl FastFieldsWriter {
             .find(|multivalue_writer| multivalue_writer.field() == field)
     }
 
+    /// Returns the bytes fast field writer for the given field.
+    ///
+    /// Returns None if the field does not exist, or is not
+    /// configured as a bytes fastfield in the schema.
+    pub fn get_bytes_writer(&mut self, field: Field) -> Option<&mut BytesFastFieldWriter> {
+        // TODO optimize
+        self.bytes_value_writers
+            .iter_mut()
+            .find(|field_writer| field_writer.field() == field)
+    }
+
+    /// Returns the vector fast field writer for the given field.
+    ///
+    /// Returns None if the field does not exist, or is not
+    /// configured as a vector field in the schema.
+    pub fn get_vector_writer(&mut self, field: Field) -> Option<&mut VectorFastFieldWriter> {
+        // TODO optimize
+        self.vector_value_writers
+            .iter_mut()
+            .find(|field_writer| field_writer.field() == field)
+    }
+
     /// Indexes all of the fastfields of a new document.
-    pub fn add_document(&mut self, doc: &Document) {
+    ///
+    /// Returns an `InvalidArgument` error, and leaves the writers untouched,
+    /// if a vector of the document does not have the dimension of its field.
+    pub fn add_document(&mut self, doc: &Document) -> Result<()> {
+        for field_writer in &self.vector_value_writers {
+            field_writer.check_document(doc)?;
+        }
         for field_writer in &mut self.single_value_writers {
             field_writer.add_document(doc);
         }
@@ -98,6 +145,13 @@ impl FastFieldsWriter {
             field_writer.next_doc();
             field_writer.add_document(doc);
         }
+        for field_writer in &mut self.bytes_value_writers {
+            field_writer.add_document(doc);
+        }
+        for field_writer in &mut self.vector_value_writers {
+            field_writer.add_document(doc)?;
+        }
+        Ok(())
     }
 
     /// Serializes all of the `FastFieldWriter`s by pushing them in
@@ -114,6 +168,12 @@ impl FastFieldsWriter {
             let field = field_writer.field();
             field_writer.serialize(serializer, mapping.get(&field))?;
         }
+        for field_writer in &self.bytes_value_writers {
+            field_writer.serialize(serializer)?;
+        }
+        for field_writer in &self.vector_value_writers {
+            field_writer.serialize(serializer)?;
+        }
         Ok(())
     }
 
@@ -143,11 +203,13 @@ impl FastFieldsWriter {
 /// Both u64, and i64 use the same writer.
 /// i64 are just remapped to the `0..2^64 - 1`
 /// using `common::i64_to_u64`.
+///
+/// Documents without any value are recorded as missing
+/// rather than as a default value.
 pub struct IntFastFieldWriter {
     field: Field,
     vals: Vec<u8>,
-    val_count: usize,
-    val_if_missing: u64,
+    presence: PresenceBitmap,
     val_min: u64,
     val_max: u64,
 }
@@ -158,8 +220,7 @@ impl IntFastFieldWriter {
         IntFastFieldWriter {
             field,
             vals: Vec::new(),
-            val_count: 0,
-            val_if_missing: 0u64,
+            presence: PresenceBitmap::default(),
             val_min: u64::max_value(),
             val_max: 0,
         }
@@ -170,24 +231,15 @@ impl IntFastFieldWriter {
         self.field
     }
 
-    /// Sets the default value.
-    ///
-    /// This default value is recorded for documents if
-    /// a document does not have any value.
-    fn set_val_if_missing(&mut self, val_if_missing: u64) {
-        self.val_if_missing = val_if_missing;
-    }
-
     /// Ensures all of the fast field writer have
     /// reached `doc`. (included)
     ///
     /// The missing values will be filled with 0.
     fn fill_val_up_to(&mut self, doc: DocId) {
         let target = doc as usize + 1;
-        debug_assert!(self.val_count <= target);
-        let val_if_missing = self.val_if_missing;
-        while self.val_count < target {
-            self.add_val(val_if_missing);
+        debug_assert!(self.presence.num_docs() <= target);
+        while self.presence.num_docs() < target {
+            self.add_val(0u64);
         }
     }
 
@@ -208,33 +260,29 @@ impl IntFastFieldWriter {
             self.val_min = val;
         }
 
-        self.val_count += 1;
+        self.presence.push(true);
+    }
+
+    /// Records that the next document does not have any value.
+    pub fn add_missing(&mut self) {
+        self.presence.push(false);
     }
 
-    /// Extract the value associated to the fast field for
-    /// this document.
+    /// Extract the fast field value from the document
+    /// and records it.
     ///
     /// i64 are remapped to u64 using the logic
     /// in `common::i64_to_u64`.
     ///
-    /// If the value is missing, then the default value is used
-    /// instead.
     /// If the document has more than one value for the given field,
     /// only the first one is taken in account.
-    fn extract_val(&self, doc: &Document) -> u64 {
+    pub fn add_document(&mut self, doc: &Document) {
         match doc.get_first(self.field) {
-            Some(v) => super::value_to_u64(v),
-            None => self.val_if_missing,
+            Some(value) => self.add_val(super::value_to_u64(value)),
+            None => self.add_missing(),
         }
     }
 
-    /// Extract the fast field value from the document
-    /// (or use the default value) and records it.
-    pub fn add_document(&mut self, doc: &Document) {
-        let val = self.extract_val(doc);
-        self.add_val(val);
-    }
-
     /// Push the fast fields value to the `FastFieldWriter`.
     pub fn serialize(&self, serializer: &mut FastFieldSerializer) -> io::Result<()> {
         let (min, max) = if self.val_min > self.val_max {
@@ -243,13 +291,17 @@ impl IntFastFieldWriter {
             (self.val_min, self.val_max)
         };
 
-        let mut single_field_serializer = serializer.new_u64_fast_field(self.field, min, max)?;
-
-        let mut cursor = self.vals.as_slice();
-        while let Ok(VInt(val)) = VInt::deserialize(&mut cursor) {
-            single_field_serializer.add_val(val)?;
-        }
-
-        single_field_serializer.close_field()
+        serializer.write_u64_fast_field(self.field, min, max, |single_field_serializer| {
+            let mut cursor = self.vals.as_slice();
+            for doc in 0..self.presence.num_docs() {
+                if self.presence.contains(doc) {
+                    let VInt(val) = VInt::deserialize(&mut cursor)?;
+                    single_field_serializer.add_val(val)?;
+                } else {
+                    single_field_serializer.add_missing()?;
+                }
+            }
+            Ok(())
+        })
     }
 }
@@ -0,0 +1,151 @@
+use schema::{Document, Value};
+use std::time::{Duration, Instant};
+
+/// Returns a rough estimate of the number of bytes
+/// of a document.
+fn approximate_num_bytes(document: &Document) -> usize {
+    document
+        .field_values()
+        .iter()
+        .map(|field_value| match *field_value.value() {
+            Value::Str(ref text) => text.len(),
+            Value::U64(_) | Value::I64(_) => 8,
+            Value::Facet(ref facet) => facet.encoded_bytes().len(),
+            Value::Bytes(ref bytes) => bytes.len(),
+            Value::Vector(ref vector) => vector.dimension() * 4,
+        })
+        .sum()
+}
+
+/// The `AutoCommitPolicy` defines when an `IndexWriter` should
+/// commit on its own.
+///
+/// A commit is triggered as soon as one of the configured
+/// limits is reached:
+/// - the number of documents added since the last commit,
+/// - the (approximate) number of bytes of the documents added since the last commit,
+/// - the delay elapsed since the last commit.
+///
+/// The `IndexWriter` cannot be sent to another thread, so that
+/// the checks are performed upon calls to `add_document(...)`
+/// and `delete_term(...)`. In particular, the delay-based limit will
+/// not trigger a commit if no operation is sent to the writer.
+///
+/// By default, no limit is set, and the `IndexWriter`
+/// never commits on its own.
+#[derive(Clone, Debug, Default)]
+pub struct AutoCommitPolicy {
+    max_num_docs: Option<u64>,
+    max_num_bytes: Option<usize>,
+    max_delay: Option<Duration>,
+}
+
+impl AutoCommitPolicy {
+    /// Commit once `max_num_docs` documents have been added
+    /// since the last commit.
+    pub fn set_max_num_docs(&mut self, max_num_docs: u64) {
+        self.max_num_docs = Some(max_num_docs);
+    }
+
+    /// Commit once the documents added since the last commit
+    /// reach approximately `max_num_bytes` bytes.
+    pub fn set_max_num_bytes(&mut self, max_num_bytes: usize) {
+        self.max_num_bytes = Some(max_num_bytes);
+    }
+
+    /// Commit once `max_delay` has elapsed since the last commit.
+    pub fn set_max_delay(&mut self, max_delay: Duration) {
+        self.max_delay = Some(max_delay);
+    }
+
+    fn should_commit(&self, stats: &UncommittedStats) -> bool {
+        if let Some(max_num_docs) = self.max_num_docs {
+            if stats.num_docs >= max_num_docs {
+                return true;
+            }
+        }
+        if let Some(max_num_bytes) = self.max_num_bytes {
+            if stats.num_bytes >= max_num_bytes {
+                return true;
+            }
+        }
+        if let Some(max_delay) = self.max_delay {
+            if stats.last_commit.elapsed() >= max_delay {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Keeps track of the operations that happened since the
+/// last commit, in order to apply an `AutoCommitPolicy`.
+pub(crate) struct UncommittedStats {
+    num_docs: u64,
+    num_bytes: usize,
+    last_commit: Instant,
+}
+
+impl Default for UncommittedStats {
+    fn default() -> UncommittedStats {
+        UncommittedStats {
+            num_docs: 0u64,
+            num_bytes: 0,
+            last_commit: Instant::now(),
+        }
+    }
+}
+
+impl UncommittedStats {
+    /// Records a new document.
+    pub fn record_document(&mut self, document: &Document) {
+        self.num_docs += 1;
+        self.num_bytes += approximate_num_bytes(document);
+    }
+
+    /// Resets the statistics after a commit.
+    pub fn reset(&mut self) {
+        *self = UncommittedStats::default();
+    }
+
+    /// Returns true iff the policy requires a commit.
+    pub fn should_commit(&self, auto_commit_policy: &AutoCommitPolicy) -> bool {
+        auto_commit_policy.should_commit(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use schema::SchemaBuilder;
+    use schema::TEXT;
+    use std::time::Duration;
+
+    #[test]
+    fn test_auto_commit_policy() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let mut stats = UncommittedStats::default();
+        let mut num_docs_policy = AutoCommitPolicy::default();
+        num_docs_policy.set_max_num_docs(2);
+        let mut num_bytes_policy = AutoCommitPolicy::default();
+        num_bytes_policy.set_max_num_bytes(8);
+        let mut delay_policy = AutoCommitPolicy::default();
+        delay_policy.set_max_delay(Duration::from_secs(3_600));
+
+        assert!(!stats.should_commit(&AutoCommitPolicy::default()));
+        stats.record_document(&doc!(text_field => "abcdef"));
+        assert!(!stats.should_commit(&num_docs_policy));
+        assert!(!stats.should_commit(&num_bytes_policy));
+        stats.record_document(&doc!(text_field => "gh"));
+        assert!(stats.should_commit(&num_docs_policy));
+        assert!(stats.should_commit(&num_bytes_policy));
+        assert!(!stats.should_commit(&delay_policy));
+        stats.reset();
+        assert!(!stats.should_commit(&num_docs_policy));
+
+        delay_policy.set_max_delay(Duration::from_secs(0));
+        assert!(stats.should_commit(&delay_policy));
+    }
+}
@@ -0,0 +1,225 @@
+use Result;
+use core::SegmentId;
+use error::ErrorKind;
+use futures::Future;
+use indexer::{IndexingError, MergePolicy, NoMergePolicy};
+use indexer::index_writer::HEAP_SIZE_LIMIT;
+use schema::Document;
+use std::thread::JoinHandle;
+use super::IndexWriter;
+
+/// A `BulkLoader` is an `IndexWriter` switched in a mode
+/// optimized for the initial indexing of a large number of documents.
+///
+/// While the bulk load is running
+/// * the merge policy is replaced by a `NoMergePolicy`, so that
+/// no merge competes with indexing for CPU and IO.
+/// * pre-partitioned document streams can be indexed in parallel, each
+/// of them in its own thread, via `.index_partition(...)`.
+/// The flush thresholds of the partition threads can be raised
+/// independently of those of the regular indexing threads, with
+/// `.set_partition_heap_size(...)` and `.set_max_docs_per_segment(...)`,
+/// so as to pre-size the segments and limit the stitching work.
+///
+/// Calling `.finish()` commits all of the documents, and stitches
+/// all of the resulting segments together in a single segment.
+///
+/// Like `PreparedCommit`, the `BulkLoader` borrows the `IndexWriter`
+/// mutably. Dropping it without calling `finish()` simply restores
+/// the merge policy, leaving the documents uncommitted.
+pub struct BulkLoader<'a> {
+    index_writer: &'a mut IndexWriter,
+    former_merge_policy: Option<Box<MergePolicy>>,
+    partition_workers: Vec<JoinHandle<Result<usize>>>,
+    partition_heap_size: usize,
+    max_docs_per_segment: Option<usize>,
+}
+
+impl<'a> BulkLoader<'a> {
+    pub(crate) fn new(index_writer: &'a mut IndexWriter, partition_heap_size: usize) -> BulkLoader {
+        let former_merge_policy = index_writer.get_merge_policy();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        BulkLoader {
+            index_writer,
+            former_merge_policy: Some(former_merge_policy),
+            partition_workers: Vec::new(),
+            partition_heap_size,
+            max_docs_per_segment: None,
+        }
+    }
+
+    /// Sets the size of the heap of the partition threads spawned afterwards.
+    ///
+    /// A partition thread flushes its segment when its heap is full.
+    /// Defaults to the heap size per thread of the `IndexWriter`.
+    ///
+    /// # Errors
+    /// Returns `ErrorKind::InvalidArgument` if the heap size is lower
+    /// than the minimum heap size per thread.
+    pub fn set_partition_heap_size(&mut self, heap_size_in_bytes: usize) -> Result<()> {
+        if heap_size_in_bytes < HEAP_SIZE_LIMIT as usize {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The heap size per partition needs to be at least {}.",
+                HEAP_SIZE_LIMIT
+            )));
+        }
+        self.partition_heap_size = heap_size_in_bytes;
+        Ok(())
+    }
+
+    /// Makes the partition threads spawned afterwards also flush their
+    /// segment once it reaches `max_docs` documents.
+    ///
+    /// By default, segments are only flushed when the heap is full.
+    pub fn set_max_docs_per_segment(&mut self, max_docs: usize) {
+        self.max_docs_per_segment = Some(max_docs);
+    }
+
+    /// Adds a document to the regular indexing queue.
+    ///
+    /// See `IndexWriter::add_document`.
+    pub fn add_document(&mut self, document: Document) -> u64 {
+        self.index_writer.add_document(document)
+    }
+
+    /// Indexes a partition of documents in a dedicated thread.
+    ///
+    /// This call does not block. Partitions are indexed in
+    /// parallel with one another, and with the documents
+    /// sent via `.add_document(...)`.
+    pub fn index_partition<I>(&mut self, partition: I) -> Result<()>
+    where
+        I: IntoIterator<Item = Document> + Send + 'static,
+    {
+        let join_handle = self.index_writer.add_partition_worker(
+            partition,
+            self.partition_heap_size,
+            self.max_docs_per_segment,
+        )?;
+        self.partition_workers.push(join_handle);
+        Ok(())
+    }
+
+    /// Waits for all of the partitions indexed so far.
+    ///
+    /// Returns the number of segments they were flushed in.
+    pub fn join_partitions(&mut self) -> Result<usize> {
+        let mut num_segments = 0;
+        for join_handle in self.partition_workers.drain(..) {
+            num_segments += join_handle
+                .join()
+                .map_err(|_| IndexingError::ThreadPanicked("partition indexing thread".into()))??;
+        }
+        Ok(num_segments)
+    }
+
+    /// Waits for all of the partitions to be indexed, commits,
+    /// and merges all of the segments of the index into a single one.
+    ///
+    /// Returns the opstamp of the commit.
+    pub fn finish(mut self) -> Result<u64> {
+        self.join_partitions()?;
+        let opstamp = self.index_writer.commit()?;
+        let segment_ids: Vec<SegmentId> = self.index_writer.index().searchable_segment_ids()?;
+        if segment_ids.len() > 1 {
+            info!("Stitching {} segments after bulk load", segment_ids.len());
+            self.index_writer
+                .merge(&segment_ids)
+                .wait()
+                .map_err(|_| IndexingError::MergeCancelled(segment_ids.clone()))?;
+        }
+        Ok(opstamp)
+    }
+}
+
+impl<'a> Drop for BulkLoader<'a> {
+    fn drop(&mut self) {
+        if let Some(merge_policy) = self.former_merge_policy.take() {
+            self.index_writer.set_merge_policy(merge_policy);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use core::{SegmentId, SegmentMeta};
+    use indexer::{LogMergePolicy, MergePolicy};
+    use schema::{self, Document};
+    use Index;
+    use Term;
+
+    #[test]
+    fn test_bulk_load() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut merge_policy = LogMergePolicy::default();
+        merge_policy.set_min_merge_size(3);
+        {
+            let mut index_writer = index.writer_with_num_threads(2, 2 * 30_000_000).unwrap();
+            index_writer.set_merge_policy(box merge_policy.clone());
+            {
+                let mut bulk_loader = index_writer.bulk_load();
+                for i in 0..3 {
+                    let partition: Vec<Document> = (0..100)
+                        .map(|_| doc!(text_field => format!("p{}", i)))
+                        .collect();
+                    bulk_loader.index_partition(partition).unwrap();
+                }
+                bulk_loader.add_document(doc!(text_field => "queue"));
+                assert_eq!(bulk_loader.finish().unwrap(), 301u64);
+            }
+            // the restored merge policy merges as soon as
+            // `min_merge_size` segments are on the same level.
+            let segment_metas: Vec<SegmentMeta> = (0..merge_policy.get_min_merge_size())
+                .map(|_| SegmentMeta::new(SegmentId::generate_random()))
+                .collect();
+            let restored_merge_policy = index_writer.get_merge_policy();
+            assert_eq!(
+                restored_merge_policy
+                    .compute_merge_candidates(&segment_metas)
+                    .len(),
+                1
+            );
+            assert!(
+                restored_merge_policy
+                    .compute_merge_candidates(&segment_metas[1..])
+                    .is_empty()
+            );
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.num_docs(), 301);
+        assert_eq!(searcher.segment_readers().len(), 1);
+        for i in 0..3 {
+            let term = Term::from_field_text(text_field, &format!("p{}", i));
+            assert_eq!(searcher.doc_freq(&term), 100);
+        }
+        let term = Term::from_field_text(text_field, "queue");
+        assert_eq!(searcher.doc_freq(&term), 1);
+    }
+
+    #[test]
+    fn test_bulk_load_flush_thresholds() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+            let mut bulk_loader = index_writer.bulk_load();
+            assert!(bulk_loader.set_partition_heap_size(1_000).is_err());
+            bulk_loader.set_partition_heap_size(60_000_000).unwrap();
+            bulk_loader.set_max_docs_per_segment(30);
+            let partition: Vec<Document> =
+                (0..100).map(|_| doc!(text_field => "p")).collect();
+            bulk_loader.index_partition(partition).unwrap();
+            assert_eq!(bulk_loader.join_partitions().unwrap(), 4);
+            assert_eq!(bulk_loader.finish().unwrap(), 100u64);
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.num_docs(), 100);
+        assert_eq!(searcher.segment_readers().len(), 1);
+    }
+}
@@ -257,6 +257,7 @@ mod tests {
             DeleteOperation {
                 opstamp: i as u64,
                 term: Term::from_field_u64(field, i as u64),
+                fast_field_update: None,
             }
         };
 
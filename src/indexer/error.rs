@@ -0,0 +1,52 @@
+use core::SegmentId;
+use schema::DocParsingError;
+use std::error::Error as StdError;
+use std::fmt;
+
+/// Error that may occur while indexing documents, or merging segments.
+#[derive(Debug)]
+pub enum IndexingError {
+    /// A document could not be parsed.
+    InvalidDocument(DocParsingError),
+    /// `ThreadPanicked(thread: String)`
+    /// One of the threads of the indexer panicked.
+    ThreadPanicked(String),
+    /// `ThreadFailed(thread: String)`
+    /// One of the threads of the indexer returned an error.
+    /// The error of the thread is chained as the cause of the `Error`.
+    ThreadFailed(String),
+    /// The merge of the given segments was cancelled.
+    MergeCancelled(Vec<SegmentId>),
+    /// The thread of the `IndexWriter` driven by an
+    /// `IndexWriterHandle` was terminated.
+    WriterTerminated,
+}
+
+impl fmt::Display for IndexingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            IndexingError::InvalidDocument(ref err) => write!(f, "invalid document: {}", err),
+            IndexingError::ThreadPanicked(ref thread) => write!(f, "the {} panicked", thread),
+            IndexingError::ThreadFailed(ref thread) => write!(f, "the {} failed", thread),
+            IndexingError::MergeCancelled(ref segment_ids) => {
+                write!(f, "the merge of the segments {:?} was cancelled", segment_ids)
+            }
+            IndexingError::WriterTerminated => {
+                write!(f, "the thread of the index writer was terminated")
+            }
+        }
+    }
+}
+
+impl StdError for IndexingError {
+    fn description(&self) -> &str {
+        "error occurred while indexing"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            IndexingError::InvalidDocument(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
@@ -3,34 +3,43 @@ use chan;
 use core::Index;
 use core::Segment;
 use core::SegmentComponent;
-use core::SegmentId;
+use core::{SegmentId, SegmentIdSequence};
 use core::SegmentMeta;
 use core::SegmentReader;
+use common::HasLen;
 use indexer::stamper::Stamper;
 use datastruct::stacker::Heap;
 use directory::FileProtection;
-use error::{Error, ErrorKind, Result, ResultExt};
-use fastfield::write_delete_bitset;
+use directory::error::OpenReadError;
+use error::{ErrorKind, Result, ResultExt};
+use fastfield::{write_delete_bitset, write_fast_field_updates, FastFieldUpdates, FastValue};
 use indexer::delete_queue::{DeleteCursor, DeleteQueue};
 use futures::Canceled;
 use datastruct::stacker::hashmap::split_memory;
 use futures::Future;
 use indexer::doc_opstamp_mapping::DocToOpstampMapping;
-use indexer::MergePolicy;
-use indexer::operation::DeleteOperation;
+use indexer::{IndexingError, MergeHook, MergePolicy, RetentionPolicy};
+use indexer::operation::{DeleteOperation, FastFieldUpdate};
 use indexer::SegmentEntry;
 use indexer::SegmentWriter;
 use docset::DocSet;
 use schema::IndexRecordOption;
+use schema::{Cardinality, Field, SchemaError};
 use schema::Document;
 use schema::Term;
+use std::cmp::max;
+use std::io::Write;
 use std::mem;
 use std::mem::swap;
+use std::sync::Arc;
 use std::thread::JoinHandle;
-use indexer::DirectoryLock;
+use directory::DirectoryLock;
 use super::operation::AddOperation;
 use super::segment_updater::SegmentUpdater;
 use super::PreparedCommit;
+use super::BulkLoader;
+use super::AutoCommitPolicy;
+use super::auto_commit_policy::UncommittedStats;
 use std::thread;
 
 // Size of the margin for the heap. A segment is closed when the remaining memory
@@ -44,6 +53,17 @@ pub const HEAP_SIZE_LIMIT: u32 = MARGIN_IN_BYTES * 3u32;
 // reaches `PIPELINE_MAX_SIZE_IN_DOCS`
 const PIPELINE_MAX_SIZE_IN_DOCS: usize = 10_000;
 
+/// Settings of a deterministic `IndexWriter`.
+///
+/// See `Index::deterministic_writer(...)`.
+#[derive(Clone)]
+pub(crate) struct DeterministicSettings {
+    /// A segment is flushed as soon as it reaches this number of documents.
+    pub max_docs_per_segment: usize,
+    /// Sequence the ids of the new segments are taken from.
+    pub segment_id_sequence: Arc<SegmentIdSequence>,
+}
+
 type DocumentSender = chan::Sender<AddOperation>;
 type DocumentReceiver = chan::Receiver<AddOperation>;
 
@@ -79,6 +99,11 @@ pub struct IndexWriter {
 
     stamper: Stamper,
     committed_opstamp: u64,
+
+    auto_commit_policy: AutoCommitPolicy,
+    uncommitted_stats: UncommittedStats,
+
+    deterministic_settings: Option<DeterministicSettings>,
 }
 
 // IndexWriter cannot be sent to another thread.
@@ -96,7 +121,7 @@ impl !Sync for IndexWriter {}
 /// `num_threads` specifies the number of indexing workers that
 /// should work at the same time.
 /// # Errors
-/// If the lockfile already exists, returns `Error::FileAlreadyExists`.
+/// If the lockfile is held by a living process, returns `ErrorKind::IndexLocked`.
 /// # Panics
 /// If the heap size per thread is too small, panics.
 pub fn open_index_writer(
@@ -104,6 +129,7 @@ pub fn open_index_writer(
     num_threads: usize,
     heap_size_in_bytes_per_thread: usize,
     directory_lock: DirectoryLock,
+    deterministic_settings: Option<DeterministicSettings>,
 ) -> Result<IndexWriter> {
     if heap_size_in_bytes_per_thread < HEAP_SIZE_LIMIT as usize {
         panic!(format!(
@@ -120,8 +146,15 @@ pub fn open_index_writer(
 
     let stamper = Stamper::new(current_opstamp);
 
-    let segment_updater =
-        SegmentUpdater::new(index.clone(), stamper.clone(), &delete_queue.cursor())?;
+    let segment_id_sequence = deterministic_settings
+        .as_ref()
+        .map(|settings| Arc::clone(&settings.segment_id_sequence));
+    let segment_updater = SegmentUpdater::new(
+        index.clone(),
+        stamper.clone(),
+        &delete_queue.cursor(),
+        segment_id_sequence,
+    )?;
 
     let mut index_writer = IndexWriter {
         _directory_lock: Some(directory_lock),
@@ -145,13 +178,24 @@ pub fn open_index_writer(
         generation: 0,
 
         worker_id: 0,
+
+        auto_commit_policy: AutoCommitPolicy::default(),
+        uncommitted_stats: UncommittedStats::default(),
+
+        deterministic_settings,
     };
     index_writer.start_workers()?;
     Ok(index_writer)
 }
 
+/// Applies the operations of the delete queue up to the target opstamp.
+///
+/// Deletes are recorded in `delete_bitset`, and fast field updates
+/// in `fast_field_updates`. Returns true iff some document
+/// might have been deleted.
 pub fn compute_deleted_bitset(
     delete_bitset: &mut BitSet,
+    fast_field_updates: &mut FastFieldUpdates,
     segment_reader: &SegmentReader,
     delete_cursor: &mut DeleteCursor,
     doc_opstamps: &DocToOpstampMapping,
@@ -173,13 +217,20 @@ pub fn compute_deleted_bitset(
                 let limit_doc = doc_opstamps.compute_doc_limit(delete_op.opstamp);
                 let inverted_index = segment_reader.inverted_index(delete_op.term.field());
                 if let Some(mut docset) =
-                    inverted_index.read_postings(&delete_op.term, IndexRecordOption::Basic)
+                    inverted_index.read_postings(&delete_op.term, IndexRecordOption::Basic)?
                 {
                     while docset.advance() {
-                        let deleted_doc = docset.doc();
-                        if deleted_doc < limit_doc {
-                            delete_bitset.insert(deleted_doc as usize);
-                            might_have_changed = true;
+                        let doc = docset.doc();
+                        if doc < limit_doc {
+                            match delete_op.fast_field_update {
+                                Some(FastFieldUpdate { field, value }) => {
+                                    fast_field_updates.update(field, doc, value);
+                                }
+                                None => {
+                                    delete_bitset.insert(doc as usize);
+                                    might_have_changed = true;
+                                }
+                            }
                         }
                     }
                 }
@@ -192,21 +243,23 @@ pub fn compute_deleted_bitset(
     Ok(might_have_changed)
 }
 
-/// Advance delete for the given segment up
+/// Advance delete and fast field updates for the given segment up
 /// to the target opstamp.
 pub fn advance_deletes(
     mut segment: Segment,
     segment_entry: &mut SegmentEntry,
     target_opstamp: u64,
-) -> Result<Option<FileProtection>> {
-    let mut file_protect: Option<FileProtection> = None;
+) -> Result<Vec<FileProtection>> {
+    let mut file_protections: Vec<FileProtection> = vec![];
 
     {
-        if let Some(previous_opstamp) = segment_entry.meta().delete_opstamp() {
-            // We are already up-to-date here.
-            if target_opstamp == previous_opstamp {
-                return Ok(file_protect);
-            }
+        let previous_opstamp = max(
+            segment_entry.meta().delete_opstamp(),
+            segment_entry.meta().fast_field_updates_opstamp(),
+        );
+        // We are already up-to-date here.
+        if previous_opstamp == Some(target_opstamp) {
+            return Ok(file_protections);
         }
         let segment_reader = SegmentReader::open(&segment)?;
         let max_doc = segment_reader.max_doc();
@@ -216,10 +269,19 @@ pub fn advance_deletes(
             None => BitSet::with_capacity(max_doc as usize),
         };
 
+        // Updates of the file are more recent than the ones
+        // kept in the segment entry.
+        let mut fast_field_updates: FastFieldUpdates = segment_entry
+            .fast_field_updates()
+            .cloned()
+            .unwrap_or_default();
+        fast_field_updates.extend(segment_reader.fast_field_updates());
+
         let delete_cursor = segment_entry.delete_cursor();
 
         compute_deleted_bitset(
             &mut delete_bitset,
+            &mut fast_field_updates,
             &segment_reader,
             delete_cursor,
             &DocToOpstampMapping::None,
@@ -235,14 +297,24 @@ pub fn advance_deletes(
         let num_deleted_docs = delete_bitset.len();
         if num_deleted_docs > 0 {
             segment.set_delete_meta(num_deleted_docs as u32, target_opstamp);
-            file_protect = Some(segment.protect_from_delete(SegmentComponent::DELETE));
+            file_protections.push(segment.protect_from_delete(SegmentComponent::DELETE));
             let mut delete_file = segment.open_write(SegmentComponent::DELETE)?;
             write_delete_bitset(&delete_bitset, &mut delete_file)?;
         }
+
+        let num_updated_values = fast_field_updates.len();
+        if num_updated_values > 0 {
+            segment.set_fast_field_updates_meta(num_updated_values as u32, target_opstamp);
+            file_protections
+                .push(segment.protect_from_delete(SegmentComponent::FASTFIELDUPDATES));
+            let mut fast_field_updates_file =
+                segment.open_write(SegmentComponent::FASTFIELDUPDATES)?;
+            write_fast_field_updates(&fast_field_updates, &mut fast_field_updates_file)?;
+        }
     }
     segment_entry.set_meta(segment.meta().clone());
 
-    Ok(file_protect)
+    Ok(file_protections)
 }
 
 fn index_documents(
@@ -253,6 +325,7 @@ fn index_documents(
     document_iterator: &mut Iterator<Item = AddOperation>,
     segment_updater: &mut SegmentUpdater,
     mut delete_cursor: DeleteCursor,
+    max_docs_per_segment: Option<usize>,
 ) -> Result<bool> {
     heap.clear();
     let schema = segment.schema();
@@ -283,6 +356,17 @@ fn index_documents(
             );
             break;
         }
+        // Deterministic writers, and bulk loads, may also flush
+        // their segments after a fixed number of documents.
+        if let Some(max_docs) = max_docs_per_segment {
+            if segment_writer.max_doc() as usize >= max_docs {
+                info!(
+                    "Max docs per segment reached, flushing segment with maxdoc={}.",
+                    segment_writer.max_doc()
+                );
+                break;
+            }
+        }
     }
 
     if !segment_updater.is_alive() {
@@ -295,6 +379,7 @@ fn index_documents(
     // the worker thread.
     assert!(num_docs > 0);
 
+    let _span = span!("flush", segment_id = segment_id, num_docs = num_docs);
     let doc_opstamps: Vec<u64> = segment_writer.finalize()?;
 
     let mut segment_meta = SegmentMeta::new(segment_id);
@@ -305,21 +390,26 @@ fn index_documents(
     let doc_to_opstamps = DocToOpstampMapping::from(doc_opstamps);
     let segment_reader = SegmentReader::open(segment)?;
     let mut deleted_bitset = BitSet::with_capacity(num_docs as usize);
+    let mut fast_field_updates = FastFieldUpdates::empty();
     let may_have_deletes = compute_deleted_bitset(
         &mut deleted_bitset,
+        &mut fast_field_updates,
         &segment_reader,
         &mut delete_cursor,
         &doc_to_opstamps,
         last_docstamp,
     )?;
 
-    let segment_entry = SegmentEntry::new(segment_meta, delete_cursor, {
+    let mut segment_entry = SegmentEntry::new(segment_meta, delete_cursor, {
         if may_have_deletes {
             Some(deleted_bitset)
         } else {
             None
         }
     });
+    if !fast_field_updates.is_empty() {
+        segment_entry.set_fast_field_updates(fast_field_updates);
+    }
 
     Ok(segment_updater.add_segment(generation, segment_entry))
 }
@@ -336,13 +426,19 @@ impl IndexWriter {
             join_handle
                 .join()
                 .expect("Indexing Worker thread panicked")
-                .chain_err(|| ErrorKind::ErrorInThread("Error in indexing worker thread.".into()))?;
+                .chain_err(|| {
+                    ErrorKind::IndexingError(IndexingError::ThreadFailed(
+                        "indexing worker thread".into(),
+                    ))
+                })?;
         }
         drop(self.workers_join_handle);
 
         let result = self.segment_updater
             .wait_merging_thread()
-            .chain_err(|| ErrorKind::ErrorInThread("Failed to join merging thread.".into()));
+            .chain_err(|| {
+                ErrorKind::IndexingError(IndexingError::ThreadFailed("merging thread".into()))
+            });
 
         if let Err(ref e) = result {
             error!("Some merging thread failed {:?}", e);
@@ -359,6 +455,71 @@ impl IndexWriter {
             .add_segment(self.generation, segment_entry);
     }
 
+    /// *Experimental & Advanced API* Imports a segment that was
+    /// built offline into the index.
+    ///
+    /// The segment may belong to any other index sharing the exact same
+    /// schema, for instance an index built on another machine and
+    /// opened via `Index::open`. This makes it possible to build an
+    /// index in a map-reduce fashion.
+    ///
+    /// The files of the segment are copied into the index directory
+    /// under a new `SegmentId`. The imported segment is only published
+    /// upon the next `commit()`, and will be discarded by a `rollback()`.
+    ///
+    /// The deletes and fast field updates already applied to the segment
+    /// are kept. Their opstamps belong to the other index, so they are
+    /// restamped with the opstamp of the import: the delete operations
+    /// of this index sent after the import apply to the imported documents,
+    /// and those sent before do not.
+    ///
+    /// # Errors
+    /// Returns an `InvalidArgument` error if the schema of the segment
+    /// differs from the schema of the index.
+    pub fn import_segment(&mut self, segment: &Segment) -> Result<SegmentMeta> {
+        if segment.schema() != self.index.schema() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "Cannot import segment {:?}: its schema differs from the index schema.",
+                segment.id()
+            )));
+        }
+        let source_meta = segment.meta();
+
+        // `new_segment` marks the segment as being written,
+        // which protects its files from garbage collection
+        // until it is added to the segment updater.
+        let mut target_segment = self.new_segment();
+        let import_opstamp = self.stamper.stamp();
+        if source_meta.delete_opstamp().is_some() {
+            target_segment.set_delete_meta(source_meta.num_deleted_docs(), import_opstamp);
+        }
+        if source_meta.fast_field_updates_opstamp().is_some() {
+            target_segment.set_fast_field_updates_meta(
+                source_meta.num_updated_fast_field_values(),
+                import_opstamp,
+            );
+        }
+        for component in SegmentComponent::iterator() {
+            let source_data = match segment.open_read(*component) {
+                Ok(source_data) => source_data,
+                Err(OpenReadError::FileDoesNotExist(_)) => {
+                    continue;
+                }
+                Err(e) => {
+                    return Err(From::from(e));
+                }
+            };
+            let mut target_write = target_segment.open_write(*component)?;
+            target_write.write_all(&*source_data)?;
+            target_write.flush()?;
+        }
+
+        let mut segment_meta = target_segment.meta().clone();
+        segment_meta.set_max_doc(source_meta.max_doc());
+        self.add_segment(segment_meta.clone());
+        Ok(segment_meta)
+    }
+
     /// *Experimental & Advanced API* Creates a new segment.
     /// and marks it as currently in write.
     ///
@@ -381,6 +542,9 @@ impl IndexWriter {
         let generation = self.generation;
 
         let mut delete_cursor = self.delete_queue.cursor();
+        let max_docs_per_segment = self.deterministic_settings
+            .as_ref()
+            .map(|settings| settings.max_docs_per_segment);
 
         let join_handle: JoinHandle<Result<()>> = thread::Builder::new()
             .name(format!(
@@ -416,6 +580,7 @@ impl IndexWriter {
                         &mut document_iterator,
                         &mut segment_updater,
                         delete_cursor.clone(),
+                        max_docs_per_segment,
                     )?;
                 }
             })?;
@@ -424,6 +589,78 @@ impl IndexWriter {
         Ok(())
     }
 
+    /// Spawns a new thread indexing all of the documents
+    /// of a given partition.
+    ///
+    /// Unlike regular indexing workers, this thread does not consume
+    /// the shared document queue. It stamps the documents of its
+    /// partition as it consumes them, and terminates once the partition
+    /// is exhausted, returning the number of segments it flushed.
+    pub(crate) fn add_partition_worker<I>(
+        &mut self,
+        partition: I,
+        heap_size_in_bytes: usize,
+        max_docs_per_segment: Option<usize>,
+    ) -> Result<JoinHandle<Result<usize>>>
+    where
+        I: IntoIterator<Item = Document> + Send + 'static,
+    {
+        let mut segment_updater = self.segment_updater.clone();
+        let stamper = self.stamper.clone();
+        let (heap_size, table_size) = split_memory(heap_size_in_bytes);
+        let generation = self.generation;
+        let mut delete_cursor = self.delete_queue.cursor();
+
+        let join_handle: JoinHandle<Result<usize>> = thread::Builder::new()
+            .name(format!(
+                "partition indexing thread {} for gen {}",
+                self.worker_id, generation
+            ))
+            .spawn(move || {
+                let mut heap = Heap::with_capacity(heap_size);
+                let mut num_segments = 0;
+                let mut document_iterator = partition
+                    .into_iter()
+                    .map(|document| AddOperation {
+                        opstamp: stamper.stamp(),
+                        document,
+                    })
+                    .peekable();
+                loop {
+                    if let Some(operation) = document_iterator.peek() {
+                        delete_cursor.skip_to(operation.opstamp);
+                    } else {
+                        // The partition is exhausted.
+                        return Ok(num_segments);
+                    }
+                    let segment = segment_updater.new_segment();
+                    if index_documents(
+                        &mut heap,
+                        table_size,
+                        &segment,
+                        generation,
+                        &mut document_iterator,
+                        &mut segment_updater,
+                        delete_cursor.clone(),
+                        max_docs_per_segment,
+                    )? {
+                        num_segments += 1;
+                    }
+                }
+            })?;
+        self.worker_id += 1;
+        Ok(join_handle)
+    }
+
+    /// *Experimental & Advanced API* Switches the `IndexWriter`
+    /// in bulk-load mode.
+    ///
+    /// See [`BulkLoader`](struct.BulkLoader.html).
+    pub fn bulk_load(&mut self) -> BulkLoader {
+        let heap_size_in_bytes = self.heap_size_in_bytes_per_thread;
+        BulkLoader::new(self, heap_size_in_bytes)
+    }
+
     /// Accessor to the merge policy.
     pub fn get_merge_policy(&self) -> Box<MergePolicy> {
         self.segment_updater.get_merge_policy()
@@ -434,6 +671,70 @@ impl IndexWriter {
         self.segment_updater.set_merge_policy(merge_policy);
     }
 
+    /// Sets a hook transforming the content of the segments
+    /// as they get merged.
+    ///
+    /// See [`MergeHook`](merge_policy/trait.MergeHook.html).
+    pub fn set_merge_hook(&self, merge_hook: Box<MergeHook>) {
+        self.segment_updater.set_merge_hook(Some(Arc::from(merge_hook)));
+    }
+
+    /// Removes the merge hook, if any.
+    pub fn remove_merge_hook(&self) {
+        self.segment_updater.set_merge_hook(None);
+    }
+
+    /// Accessor to the retention policy.
+    pub fn get_retention_policy(&self) -> Option<RetentionPolicy> {
+        self.segment_updater.get_retention_policy()
+    }
+
+    /// Sets the retention policy, which drops the expired
+    /// segments upon commit.
+    ///
+    /// See [`RetentionPolicy`](struct.RetentionPolicy.html).
+    ///
+    /// # Errors
+    /// If the date field of the policy is not a single-valued
+    /// integer fast field, returns `ErrorKind::SchemaError`.
+    pub fn set_retention_policy(&self, retention_policy: RetentionPolicy) -> Result<()> {
+        retention_policy.validate(&self.index.schema())?;
+        self.segment_updater.set_retention_policy(Some(retention_policy));
+        Ok(())
+    }
+
+    /// Removes the retention policy, if any.
+    pub fn remove_retention_policy(&self) {
+        self.segment_updater.set_retention_policy(None);
+    }
+
+    /// Accessor to the auto-commit policy.
+    pub fn get_auto_commit_policy(&self) -> AutoCommitPolicy {
+        self.auto_commit_policy.clone()
+    }
+
+    /// Set the auto-commit policy.
+    ///
+    /// See [`AutoCommitPolicy`](struct.AutoCommitPolicy.html).
+    pub fn set_auto_commit_policy(&mut self, auto_commit_policy: AutoCommitPolicy) {
+        self.auto_commit_policy = auto_commit_policy;
+    }
+
+    /// Commits if the auto-commit policy requires it.
+    ///
+    /// Errors are logged, and the commit will be
+    /// attempted again upon the next operation.
+    fn auto_commit_if_needed(&mut self) {
+        if self.uncommitted_stats
+            .should_commit(&self.auto_commit_policy)
+        {
+            info!("Auto-commit");
+            if let Err(e) = self.commit() {
+                error!("Auto-commit failed: {:?}", e);
+            }
+        }
+    }
+
     fn start_workers(&mut self) -> Result<()> {
         for _ in 0..self.num_threads {
             self.add_indexing_worker()?;
@@ -495,12 +796,20 @@ impl IndexWriter {
             .take()
             .expect("The IndexWriter does not have any lock. This is a bug, please report.");
 
-        let new_index_writer: IndexWriter = open_index_writer(
+        let mut new_index_writer: IndexWriter = open_index_writer(
             &self.index,
             self.num_threads,
             self.heap_size_in_bytes_per_thread,
             directory_lock,
+            self.deterministic_settings.clone(),
         )?;
+        new_index_writer.set_auto_commit_policy(self.get_auto_commit_policy());
+        new_index_writer
+            .segment_updater
+            .set_merge_hook(self.segment_updater.get_merge_hook());
+        new_index_writer
+            .segment_updater
+            .set_retention_policy(self.get_retention_policy());
 
         // the current `self` is dropped right away because of this call.
         //
@@ -551,7 +860,24 @@ impl IndexWriter {
         // This will move uncommitted segments to the state of
         // committed segments.
         info!("Preparing commit");
+        let mut span = span!("prepare_commit");
+
+        self.flush_workers()?;
 
+        self.uncommitted_stats.reset();
+
+        let commit_opstamp = self.stamper.stamp();
+        span.record("opstamp", commit_opstamp);
+        let prepared_commit = PreparedCommit::new(self, commit_opstamp);
+        info!("Prepared commit {}", commit_opstamp);
+        Ok(prepared_commit)
+    }
+
+    /// Forces all of the indexing workers to flush the
+    /// documents they received so far into segments.
+    ///
+    /// The workers are then replaced by fresh workers.
+    fn flush_workers(&mut self) -> Result<()> {
         // this will drop the current document channel
         // and recreate a new one channels.
         self.recreate_document_channel();
@@ -565,17 +891,49 @@ impl IndexWriter {
         for worker_handle in former_workers_join_handle {
             let indexing_worker_result = worker_handle
                 .join()
-                .map_err(|e| Error::from_kind(ErrorKind::ErrorInThread(format!("{:?}", e))))?;
+                .map_err(|_| IndexingError::ThreadPanicked("indexing worker thread".into()))?;
 
             indexing_worker_result?;
             // add a new worker for the next generation.
             self.add_indexing_worker()?;
         }
+        Ok(())
+    }
 
-        let commit_opstamp = self.stamper.stamp();
-        let prepared_commit = PreparedCommit::new(self, commit_opstamp);
-        info!("Prepared commit {}", commit_opstamp);
-        Ok(prepared_commit)
+    /// *Experimental & Advanced API* Makes all of the pending changes
+    /// searchable, without committing them.
+    ///
+    /// Like for a commit, all of the pending documents are flushed
+    /// into segments. A new generation of searchers is then published
+    /// over both the committed and the uncommitted segments, with
+    /// all of the pending deletes applied.
+    ///
+    /// Unlike a commit, the `meta.json` file is left untouched:
+    /// the changes are not persisted, and will be lost upon a `rollback()`
+    /// or a crash. Calling `Index::load_searchers()` will
+    /// also go back to the last committed state.
+    ///
+    /// This is much cheaper than a commit, and makes it possible
+    /// to reduce the latency between indexing and search.
+    ///
+    /// Returns the opstamp up to which the changes are visible.
+    pub fn soft_commit(&mut self) -> Result<u64> {
+        info!("Soft commit");
+        self.flush_workers()?;
+        let soft_commit_opstamp = self.stamper.stamp();
+        let segment_metas = self.segment_updater
+            .soft_commit_segment_metas(soft_commit_opstamp)?;
+        // the delete files written by the soft commit are not referenced by the
+        // segment manager: they are protected for as long as the readers are alive.
+        let segment_readers: Vec<SegmentReader> = segment_metas
+            .into_iter()
+            .map(|(segment_meta, file_protections)| {
+                let segment = self.index.segment(segment_meta);
+                SegmentReader::open_with_file_protections(&segment, file_protections)
+            })
+            .collect::<Result<_>>()?;
+        self.index.publish_segment_readers(segment_readers);
+        Ok(soft_commit_opstamp)
     }
 
     /// Commits all of the pending changes
@@ -600,6 +958,10 @@ impl IndexWriter {
         &self.segment_updater
     }
 
+    pub(crate) fn index(&self) -> &Index {
+        &self.index
+    }
+
     /// Delete all documents containing a given term.
     ///
     /// Delete operation only affects documents that
@@ -610,11 +972,60 @@ impl IndexWriter {
     /// only after calling `commit()`.
     pub fn delete_term(&mut self, term: Term) -> u64 {
         let opstamp = self.stamper.stamp();
-        let delete_operation = DeleteOperation { opstamp, term };
+        let delete_operation = DeleteOperation {
+            opstamp,
+            term,
+            fast_field_update: None,
+        };
         self.delete_queue.push(delete_operation);
+        self.auto_commit_if_needed();
         opstamp
     }
 
+    /// Updates the value of a fast field for all documents
+    /// containing a given term, without reindexing them.
+    ///
+    /// Only single-valued `u64` and `i64` fast fields can be updated.
+    /// The updated values are stored in a separate file, stacked over
+    /// the segment's fast fields, until the segment gets merged.
+    ///
+    /// Like deletes, the update only affects documents that
+    /// were added in previous commits, and documents
+    /// that were added previously in the same commit.
+    /// It will be visible only after calling `commit()`.
+    ///
+    /// # Errors
+    /// If `field` is not a single-valued fast field of type `Item`,
+    /// returns `ErrorKind::SchemaError`.
+    pub fn update_fast_field<Item: FastValue>(
+        &mut self,
+        term: Term,
+        field: Field,
+        value: Item,
+    ) -> Result<u64> {
+        let schema = self.index.schema();
+        let field_entry = schema.get_field_entry(field);
+        let cardinality = Item::fast_field_cardinality(field_entry.field_type());
+        if cardinality != Some(Cardinality::SingleValue) {
+            bail!(ErrorKind::SchemaError(SchemaError::IncompatibleFieldType(
+                field_entry.name().to_string(),
+                "a single-valued fast field of the requested type".to_string(),
+            )));
+        }
+        let opstamp = self.stamper.stamp();
+        let delete_operation = DeleteOperation {
+            opstamp,
+            term,
+            fast_field_update: Some(FastFieldUpdate {
+                field,
+                value: value.to_u64(),
+            }),
+        };
+        self.delete_queue.push(delete_operation);
+        self.auto_commit_if_needed();
+        Ok(opstamp)
+    }
+
     /// Returns the opstamp of the last successful commit.
     ///
     /// This is, for instance, the opstamp the index will
@@ -636,10 +1047,20 @@ impl IndexWriter {
     ///
     /// Currently it represents the number of documents that
     /// have been added since the creation of the index.
+    ///
+    /// Depending on the `AutoCommitPolicy`, this call
+    /// may trigger a commit.
+    ///
+    /// A document with a vector that does not have the dimension
+    /// of its field is rejected by the indexing worker, and the
+    /// `InvalidArgument` error is returned by the next commit.
+    /// Documents parsed with `Schema::parse_document` are checked upfront.
     pub fn add_document(&mut self, document: Document) -> u64 {
         let opstamp = self.stamper.stamp();
+        self.uncommitted_stats.record_document(&document);
         let add_operation = AddOperation { opstamp, document };
         self.document_sender.send(add_operation);
+        self.auto_commit_if_needed();
         opstamp
     }
 }
@@ -648,11 +1069,15 @@ impl IndexWriter {
 mod tests {
 
     use indexer::NoMergePolicy;
+    use indexer::AutoCommitPolicy;
+    use core::SegmentComponent;
+    use std::path::PathBuf;
     use schema::{self, Document};
     use Index;
     use Term;
     use error::*;
     use env_logger;
+    use futures::Future;
 
     #[test]
     fn test_lockfile_stops_duplicates() {
@@ -660,8 +1085,8 @@ mod tests {
         let index = Index::create_in_ram(schema_builder.build());
         let _index_writer = index.writer(40_000_000).unwrap();
         match index.writer(40_000_000) {
-            Err(Error(ErrorKind::FileAlreadyExists(_), _)) => {}
-            _ => panic!("Expected FileAlreadyExists error"),
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected IndexLocked error"),
         }
     }
 
@@ -841,4 +1266,264 @@ mod tests {
         assert_eq!(num_docs_containing("b"), 100);
     }
 
+    #[test]
+    fn test_auto_commit() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        let mut auto_commit_policy = AutoCommitPolicy::default();
+        auto_commit_policy.set_max_num_docs(3);
+        index_writer.set_auto_commit_policy(auto_commit_policy);
+        for _ in 0..7 {
+            index_writer.add_document(doc!(text_field => "a"));
+        }
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 6);
+        index_writer.rollback().unwrap();
+        for _ in 0..3 {
+            index_writer.add_document(doc!(text_field => "a"));
+        }
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 9);
+    }
+
+    #[test]
+    fn test_soft_commit() {
+        use directory::Directory;
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let num_docs_containing = |s: &str| {
+            let searcher = index.searcher();
+            let term = Term::from_field_text(text_field, s);
+            searcher.doc_freq(&term)
+        };
+        let mut index_writer = index.writer_with_num_threads(2, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.add_document(doc!(text_field => "b"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+
+        index_writer.add_document(doc!(text_field => "c"));
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        assert_eq!(index_writer.soft_commit().unwrap(), 5u64);
+        assert_eq!(index.searcher().num_docs(), 2);
+        assert_eq!(num_docs_containing("c"), 1);
+        assert_eq!(index.load_metas().unwrap().opstamp, 2u64);
+
+        // the delete files of the soft commit are not garbage collected
+        // as long as its searcher is alive.
+        let searcher = index.searcher();
+        index_writer.segment_updater().garbage_collect_files().unwrap();
+        let delete_paths: Vec<PathBuf> = searcher
+            .segment_readers()
+            .iter()
+            .filter(|segment_reader| segment_reader.segment_meta().has_deletes())
+            .map(|segment_reader| {
+                segment_reader
+                    .segment_meta()
+                    .relative_path(SegmentComponent::DELETE)
+            })
+            .collect();
+        assert_eq!(delete_paths.len(), 1);
+        assert!(index.directory().exists(&delete_paths[0]));
+        drop(searcher);
+
+        // the changes are not persisted.
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 2);
+        assert_eq!(num_docs_containing("c"), 0);
+
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 2);
+        assert_eq!(num_docs_containing("c"), 1);
+    }
+
+    #[test]
+    fn test_import_segment() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT);
+        let schema = schema_builder.build();
+
+        let offline_index = Index::create_in_ram(schema.clone());
+        {
+            let mut index_writer = offline_index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a"));
+            index_writer.add_document(doc!(text_field => "b"));
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_text(text_field, "b"));
+            index_writer.commit().unwrap();
+        }
+        let offline_segments = offline_index.searchable_segments().unwrap();
+        assert_eq!(offline_segments.len(), 1);
+
+        let index = Index::create_in_ram(schema);
+        let num_docs_containing = |s: &str| {
+            let searcher = index.searcher();
+            let term = Term::from_field_text(text_field, s);
+            searcher.doc_freq(&term)
+        };
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field => "a"));
+        index_writer.commit().unwrap();
+        let segment_meta = index_writer.import_segment(&offline_segments[0]).unwrap();
+        assert_eq!(segment_meta.max_doc(), 2);
+        assert_eq!(segment_meta.num_docs(), 1);
+
+        // the imported segment is not visible before the commit.
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 1);
+
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 2);
+        assert_eq!(num_docs_containing("a"), 2);
+
+        // the deletes of the other index are restamped in the opstamps of this index,
+        // and the deletes sent after the import apply to the imported segment.
+        let metas = index.load_metas().unwrap();
+        let imported_meta = metas
+            .segments
+            .iter()
+            .find(|meta| meta.id() == segment_meta.id())
+            .unwrap();
+        assert!(imported_meta.delete_opstamp().unwrap() <= metas.opstamp);
+        index_writer.delete_term(Term::from_field_text(text_field, "a"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 0);
+    }
+
+    #[test]
+    fn test_import_segment_schema_mismatch() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        schema_builder.add_text_field("text", schema::TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+
+        let mut other_schema_builder = schema::SchemaBuilder::default();
+        other_schema_builder.add_text_field("title", schema::TEXT);
+        let other_index = Index::create_in_ram(other_schema_builder.build());
+
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        match index_writer.import_segment(&other_index.new_segment()) {
+            Err(Error(ErrorKind::InvalidArgument(_), _)) => {}
+            _ => panic!("Expected InvalidArgument error"),
+        }
+    }
+
+    #[test]
+    fn test_update_fast_field() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", schema::INT_INDEXED);
+        let likes_field = schema_builder.add_u64_field("likes", schema::FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let all_likes = || {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let mut likes: Vec<u64> = vec![];
+            for segment_reader in searcher.segment_readers() {
+                let likes_reader = segment_reader.fast_field_reader::<u64>(likes_field).unwrap();
+                for doc in 0..segment_reader.max_doc() {
+                    likes.push(likes_reader.get(doc));
+                }
+            }
+            likes.sort();
+            likes
+        };
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        index_writer.add_document(doc!(id_field=>1u64, likes_field=>10u64));
+        index_writer.add_document(doc!(id_field=>2u64, likes_field=>20u64));
+        index_writer.commit().unwrap();
+
+        index_writer
+            .update_fast_field(Term::from_field_u64(id_field, 1), likes_field, 11u64)
+            .unwrap();
+        // documents added after the update are not affected.
+        index_writer.add_document(doc!(id_field=>1u64, likes_field=>5u64));
+        index_writer.commit().unwrap();
+        assert_eq!(all_likes(), vec![5, 11, 20]);
+
+        index_writer
+            .update_fast_field(Term::from_field_u64(id_field, 2), likes_field, 21u64)
+            .unwrap();
+        index_writer.commit().unwrap();
+        assert_eq!(all_likes(), vec![5, 11, 21]);
+        let num_updated_values: u32 = index
+            .searchable_segment_metas()
+            .unwrap()
+            .iter()
+            .map(|segment_meta| segment_meta.num_updated_fast_field_values())
+            .sum();
+        assert_eq!(num_updated_values, 2);
+
+        assert!(
+            index_writer
+                .update_fast_field(Term::from_field_u64(id_field, 2), likes_field, 3i64)
+                .is_err()
+        );
+
+        // the updates are folded into the merged segment.
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        assert_eq!(all_likes(), vec![5, 11, 21]);
+        let segment_metas = index.searchable_segment_metas().unwrap();
+        assert_eq!(segment_metas.len(), 1);
+        assert!(!segment_metas[0].has_fast_field_updates());
+    }
+
+    #[test]
+    fn test_deterministic_writer() {
+        use core::META_FILEPATH;
+        use directory::Directory;
+        use indexer::merge_policy::tests::MergeWheneverPossible;
+
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT | schema::STORED);
+        let schema = schema_builder.build();
+        let build_index = || {
+            let index = Index::create_in_ram(schema.clone());
+            let mut index_writer = index.deterministic_writer(40_000_000, 3).unwrap();
+            index_writer.set_merge_policy(box NoMergePolicy);
+            for i in 0..10 {
+                index_writer.add_document(doc!(text_field => format!("doc {}", i)));
+            }
+            index_writer.commit().unwrap();
+            let segment_metas = index.searchable_segment_metas().unwrap();
+            let mut num_docs: Vec<u32> = segment_metas
+                .iter()
+                .map(|segment_meta| segment_meta.max_doc())
+                .collect();
+            num_docs.sort();
+            assert_eq!(num_docs, vec![1, 3, 3, 3]);
+
+            // merges are over when commit returns.
+            index_writer.set_merge_policy(box MergeWheneverPossible);
+            index_writer.delete_term(Term::from_field_text(text_field, "3"));
+            index_writer.add_document(doc!(text_field => "doc 10"));
+            index_writer.commit().unwrap();
+            let segment_metas = index.searchable_segment_metas().unwrap();
+            assert_eq!(segment_metas.len(), 1);
+            assert_eq!(segment_metas[0].num_docs(), 10);
+            index
+        };
+        let index_a = build_index();
+        let index_b = build_index();
+        assert_eq!(
+            index_a.directory().atomic_read(&META_FILEPATH).unwrap(),
+            index_b.directory().atomic_read(&META_FILEPATH).unwrap()
+        );
+        let segment_metas = index_a.searchable_segment_metas().unwrap();
+        for path in segment_metas[0].list_files() {
+            if !index_a.directory().exists(&path) {
+                continue;
+            }
+            let data_a = index_a.directory().open_read(&path).unwrap();
+            let data_b = index_b.directory().open_read(&path).unwrap();
+            assert_eq!(data_a.as_slice(), data_b.as_slice());
+        }
+    }
 }
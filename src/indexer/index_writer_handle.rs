@@ -0,0 +1,224 @@
+use Result;
+use error::Error;
+use core::Index;
+use futures::{Future, Sink, Stream};
+use futures::sync::{mpsc, oneshot};
+use schema::{Document, Term};
+use std::sync::mpsc as std_mpsc;
+use std::thread;
+use super::{IndexWriter, IndexingError};
+
+enum WriterCommand {
+    AddDocument(Document, oneshot::Sender<u64>),
+    DeleteTerm(Term, oneshot::Sender<u64>),
+    Commit(oneshot::Sender<Result<u64>>),
+    Rollback(oneshot::Sender<Result<()>>),
+}
+
+fn writer_thread_terminated() -> Error {
+    IndexingError::WriterTerminated.into()
+}
+
+/// Asynchronous front-end to an `IndexWriter`, for
+/// ingestion services running on an event loop.
+///
+/// The `IndexWriter` lives in a dedicated thread, which processes
+/// the operations sent by the handles through a bounded queue.
+/// Every operation returns a future, that resolves once the
+/// writer has processed it. When the indexing pipeline is full,
+/// the writer thread blocks, the queue fills up, and the futures
+/// returned by `.add_document(...)` do not resolve until some room
+/// is available: the event loop is never blocked, and producers
+/// get backpressure.
+///
+/// Handles are cheap to clone, and can be sent to other threads.
+/// The writer thread terminates when all of the handles are dropped,
+/// dropping the `IndexWriter` along with its uncommitted documents.
+///
+/// A handle is created with `Index::writer_handle(...)`.
+#[derive(Clone)]
+pub struct IndexWriterHandle {
+    command_sender: mpsc::Sender<WriterCommand>,
+}
+
+impl IndexWriterHandle {
+    pub(crate) fn spawn(
+        index: &Index,
+        num_threads: usize,
+        heap_size_in_bytes: usize,
+        queue_capacity: usize,
+    ) -> Result<IndexWriterHandle> {
+        let (command_sender, command_receiver) = mpsc::channel(queue_capacity);
+        let (open_result_sender, open_result_receiver) = std_mpsc::channel();
+        let index = index.clone();
+        thread::Builder::new()
+            .name("index writer handle".to_string())
+            .spawn(move || {
+                // `IndexWriter` cannot be sent to another thread,
+                // so that it is opened by the thread that owns it.
+                let mut index_writer = match index
+                    .writer_with_num_threads(num_threads, heap_size_in_bytes)
+                {
+                    Ok(index_writer) => {
+                        let _ = open_result_sender.send(Ok(()));
+                        index_writer
+                    }
+                    Err(e) => {
+                        let _ = open_result_sender.send(Err(e));
+                        return;
+                    }
+                };
+                // the stream ends when all of the handles are dropped.
+                for command in command_receiver.wait() {
+                    let command = match command {
+                        Ok(command) => command,
+                        Err(()) => break,
+                    };
+                    process_command(&mut index_writer, command);
+                }
+            })?;
+        open_result_receiver
+            .recv()
+            .map_err(|_| writer_thread_terminated())??;
+        Ok(IndexWriterHandle { command_sender })
+    }
+
+    fn request<T>(
+        &self,
+        command: WriterCommand,
+        reply_receiver: oneshot::Receiver<T>,
+    ) -> impl Future<Item = T, Error = Error> {
+        self.command_sender
+            .clone()
+            .send(command)
+            .map_err(|_| writer_thread_terminated())
+            .and_then(move |_| reply_receiver.map_err(|_| writer_thread_terminated()))
+    }
+
+    /// Adds a document.
+    ///
+    /// The future resolves to the opstamp of the document once
+    /// it has been handed to the `IndexWriter`.
+    /// See `IndexWriter::add_document`.
+    pub fn add_document(&self, document: Document) -> impl Future<Item = u64, Error = Error> {
+        let (reply_sender, reply_receiver) = oneshot::channel();
+        self.request(
+            WriterCommand::AddDocument(document, reply_sender),
+            reply_receiver,
+        )
+    }
+
+    /// Deletes all of the documents containing a given term.
+    ///
+    /// See `IndexWriter::delete_term`.
+    pub fn delete_term(&self, term: Term) -> impl Future<Item = u64, Error = Error> {
+        let (reply_sender, reply_receiver) = oneshot::channel();
+        self.request(WriterCommand::DeleteTerm(term, reply_sender), reply_receiver)
+    }
+
+    /// Commits all of the operations sent before the commit,
+    /// by this handle or by any of its clones.
+    ///
+    /// See `IndexWriter::commit`.
+    pub fn commit(&self) -> impl Future<Item = u64, Error = Error> {
+        let (reply_sender, reply_receiver) = oneshot::channel();
+        self.request(WriterCommand::Commit(reply_sender), reply_receiver)
+            .and_then(|commit_result| commit_result)
+    }
+
+    /// Rollbacks to the last commit.
+    ///
+    /// See `IndexWriter::rollback`.
+    pub fn rollback(&self) -> impl Future<Item = (), Error = Error> {
+        let (reply_sender, reply_receiver) = oneshot::channel();
+        self.request(WriterCommand::Rollback(reply_sender), reply_receiver)
+            .and_then(|rollback_result| rollback_result)
+    }
+}
+
+fn process_command(index_writer: &mut IndexWriter, command: WriterCommand) {
+    // the caller may have dropped the future,
+    // in which case the reply is simply discarded.
+    match command {
+        WriterCommand::AddDocument(document, reply_sender) => {
+            let _ = reply_sender.send(index_writer.add_document(document));
+        }
+        WriterCommand::DeleteTerm(term, reply_sender) => {
+            let _ = reply_sender.send(index_writer.delete_term(term));
+        }
+        WriterCommand::Commit(reply_sender) => {
+            let _ = reply_sender.send(index_writer.commit());
+        }
+        WriterCommand::Rollback(reply_sender) => {
+            let _ = reply_sender.send(index_writer.rollback());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use error::{Error, ErrorKind};
+    use futures::Future;
+    use futures::future::join_all;
+    use schema::{SchemaBuilder, TEXT};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_index_writer_handle() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let handle = index.writer_handle(1, 40_000_000, 4).unwrap();
+        match index.writer_with_num_threads(1, 40_000_000) {
+            Err(Error(ErrorKind::IndexLocked(_), _)) => {}
+            _ => panic!("Expected IndexLocked error"),
+        }
+
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let handle = handle.clone();
+                thread::spawn(move || {
+                    let opstamps = (0..50)
+                        .map(|_| handle.add_document(doc!(text_field => "a b")))
+                        .collect::<Vec<_>>();
+                    join_all(opstamps).wait().unwrap()
+                })
+            })
+            .collect();
+        for producer in producers {
+            assert_eq!(producer.join().unwrap().len(), 50);
+        }
+        handle.add_document(doc!(text_field => "c")).wait().unwrap();
+        let commit_opstamp = handle.commit().wait().unwrap();
+        assert_eq!(commit_opstamp, 201);
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 201);
+
+        handle
+            .delete_term(Term::from_field_text(text_field, "c"))
+            .wait()
+            .unwrap();
+        handle.add_document(doc!(text_field => "d")).wait().unwrap();
+        handle.rollback().wait().unwrap();
+        handle
+            .delete_term(Term::from_field_text(text_field, "a"))
+            .wait()
+            .unwrap();
+        handle.commit().wait().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 1);
+
+        // dropping the handles releases the lock.
+        drop(handle);
+        let mut retries = 0;
+        while index.writer_with_num_threads(1, 40_000_000).is_err() {
+            retries += 1;
+            assert!(retries < 1_000);
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
@@ -21,17 +21,33 @@ impl LogMergePolicy {
         cmp::max(self.min_layer_size, size)
     }
 
+    /// Returns the minimum number of segment that may be merge together.
+    pub fn get_min_merge_size(&self) -> usize {
+        self.min_merge_size
+    }
+
     /// Set the minimum number of segment that may be merge together.
     pub fn set_min_merge_size(&mut self, min_merge_size: usize) {
         self.min_merge_size = min_merge_size;
     }
 
+    /// Returns the minimum segment size under which all segment belong
+    /// to the same level.
+    pub fn get_min_layer_size(&self) -> u32 {
+        self.min_layer_size
+    }
+
     /// Set the minimum segment size under which all segment belong
     /// to the same level.
     pub fn set_min_layer_size(&mut self, min_layer_size: u32) {
         self.min_layer_size = min_layer_size;
     }
 
+    /// Returns the ratio between two consecutive levels.
+    pub fn get_level_log_size(&self) -> f64 {
+        self.level_log_size
+    }
+
     /// Set the ratio between two consecutive levels.
     ///
     /// Segment are group in levels according to their sizes.
@@ -109,6 +125,14 @@ mod tests {
         log_merge_policy
     }
 
+    #[test]
+    fn test_log_merge_policy_accessors() {
+        let log_merge_policy = test_merge_policy();
+        assert_eq!(log_merge_policy.get_min_merge_size(), 3);
+        assert_eq!(log_merge_policy.get_min_layer_size(), 2);
+        assert_eq!(log_merge_policy.get_level_log_size(), DEFAULT_LEVEL_LOG_SIZE);
+    }
+
     #[test]
     fn test_log_merge_policy_empty() {
         let y = Vec::new();
@@ -0,0 +1,51 @@
+use schema::{Field, Term};
+use std::fmt::Debug;
+use std::marker;
+
+/// A `MergeHook` transforms the content of the segments as they get merged.
+///
+/// It makes it possible to scrub data gradually, for instance to erase the
+/// content of a field for legal reasons, without reindexing: the segments
+/// are cleaned up as the merge policy picks them, or right away by calling
+/// `IndexWriter::merge(...)` on all of the segments of the index.
+///
+/// The hook is set with `IndexWriter::set_merge_hook(...)`, and is only
+/// applied by the merges that start after it was set. Segments written
+/// by the indexing threads are not affected.
+///
+/// All of the methods have a default implementation that leaves
+/// the segments untouched.
+pub trait MergeHook: marker::Send + marker::Sync + Debug {
+    /// Returns true if all of the values of `field`
+    /// should be removed from the merged segment.
+    ///
+    /// The field is removed from the inverted index, the doc store, the fast
+    /// fields, the completions, and the term vectors.
+    /// Its fieldnorms are set to zero.
+    fn drop_field(&self, _field: Field) -> bool {
+        false
+    }
+
+    /// Rewrites a value of an integer fast field,
+    /// single-valued or multivalued, of the merged segment.
+    ///
+    /// `i64` values are given in their `u64` representation.
+    /// See `i64_to_u64`.
+    ///
+    /// The terms of the inverted index and the values of the
+    /// doc store are left untouched.
+    fn rewrite_fast_field_value(&self, _field: Field, value: u64) -> u64 {
+        value
+    }
+
+    /// Returns true if `term` should be removed from the
+    /// inverted index of the merged segment.
+    ///
+    /// The term is also removed from the values of the facet and of the
+    /// string fast fields, and from the term vectors. The doc store and
+    /// the fieldnorms are left untouched, so that purging a term from
+    /// a stored field does not erase it from the stored documents.
+    fn purge_term(&self, _term: &Term) -> bool {
+        false
+    }
+}
@@ -3,25 +3,53 @@ use core::SegmentReader;
 use core::Segment;
 use DocId;
 use core::SerializableSegment;
-use indexer::SegmentSerializer;
+use indexer::{MergeHook, SegmentSerializer};
 use postings::InvertedIndexSerializer;
 use itertools::Itertools;
 use postings::Postings;
 use docset::DocSet;
 use fastfield::DeleteBitSet;
-use schema::{Field, Schema};
-use termdict::TermMerger;
+use schema::{Cardinality, Field, FieldType, Schema, SchemaError, Term};
+use termdict::{TermMerger, TermOrdinal};
 use fastfield::FastFieldSerializer;
 use fastfield::FastFieldReader;
 use store::StoreWriter;
 use std::cmp::{max, min};
+use std::ops::Range;
+use std::sync::Arc;
 use termdict::TermDictionary;
 use termdict::TermStreamer;
+use byteorder::ByteOrder;
+use common::Endianness;
+use fastfield::build_hnsw;
+use common::CompositeWrite;
+use directory::WritePtr;
+use completion::{completion_fields, merge_completion_indexes, CompletionIndex};
+use termvector::{term_vector_fields, FieldTermVectorsWriter};
+use fastfield::MultiValueIntFastFieldReader;
+use std::collections::HashMap;
+
+// Number of values decoded at once when a column is merged as a whole.
+const MERGE_BLOCK_LEN: usize = 1_024;
+
+/// Maps the term ordinals of each merged segment
+/// to the term ordinals of the resulting segment.
+type TermOrdinalMapping = Vec<Vec<Option<TermOrdinal>>>;
+
+// Facets and str fast fields store term ordinals in their fast fields.
+fn has_term_ord_fast_field(field_type: &FieldType) -> bool {
+    match *field_type {
+        FieldType::Str(ref options) => options.is_fast(),
+        FieldType::HierarchicalFacet => true,
+        _ => false,
+    }
+}
 
 pub struct IndexMerger {
     schema: Schema,
     readers: Vec<SegmentReader>,
     max_doc: u32,
+    merge_hook: Option<Arc<MergeHook>>,
 }
 
 fn compute_min_max_val(
@@ -40,7 +68,7 @@ fn compute_min_max_val(
         // we need to recompute the max / min
         (0..max_doc)
             .filter(|doc_id| !delete_bitset.is_deleted(*doc_id))
-            .map(|doc_id| u64_reader.get(doc_id))
+            .filter_map(|doc_id| u64_reader.get_opt(doc_id))
             .minmax()
             .into_option()
     }
@@ -60,6 +88,33 @@ fn extract_fast_field_reader(
     segment_reader.fast_field_reader(field).ok()
 }
 
+/// Calls `f` on the first `num_vals` values of `column`,
+/// decoded by blocks of `MERGE_BLOCK_LEN` values.
+fn for_each_block<F>(column: &FastFieldReader<u64>, num_vals: u32, mut f: F) -> Result<()>
+where
+    F: FnMut(&[u64]) -> Result<()>,
+{
+    let mut block = vec![0u64; MERGE_BLOCK_LEN];
+    let mut start = 0u32;
+    while start < num_vals {
+        let block_len = min(MERGE_BLOCK_LEN as u32, num_vals - start) as usize;
+        column.get_range(start, &mut block[..block_len]);
+        f(&block[..block_len])?;
+        start += block_len as u32;
+    }
+    Ok(())
+}
+
+struct MultiValueColumn<'a> {
+    segment_ord: usize,
+    reader: &'a SegmentReader,
+    multi_reader: MultiValueIntFastFieldReader<u64>,
+    num_vals: u32,
+    // true if the segment has no deleted documents, and none of its
+    // values are removed, so that its columns can be merged as a whole.
+    is_merged_as_a_whole: bool,
+}
+
 struct DeltaComputer {
     buffer: Vec<u32>,
 }
@@ -82,6 +137,22 @@ impl DeltaComputer {
         }
         &self.buffer[..positions.len()]
     }
+
+    // Interleaves the position deltas with the offsets,
+    // as expected by `FieldSerializer::write_doc`.
+    fn compute_delta_with_offsets(&mut self, positions: &[u32], offsets: &[Range<u32>]) -> &[u32] {
+        self.buffer.clear();
+        let mut last_pos = 0u32;
+        let mut last_offset_from = 0u32;
+        for (&cur_pos, offset) in positions.iter().zip(offsets) {
+            self.buffer.push(cur_pos - last_pos);
+            self.buffer.push(offset.start.wrapping_sub(last_offset_from));
+            self.buffer.push(offset.end - offset.start);
+            last_pos = cur_pos;
+            last_offset_from = offset.start;
+        }
+        &self.buffer[..]
+    }
 }
 
 impl IndexMerger {
@@ -99,9 +170,38 @@ impl IndexMerger {
             schema,
             readers,
             max_doc,
+            merge_hook: None,
         })
     }
 
+    /// Sets a hook transforming the content of the merged segment.
+    pub fn set_merge_hook(&mut self, merge_hook: Arc<MergeHook>) {
+        self.merge_hook = Some(merge_hook);
+    }
+
+    fn is_dropped(&self, field: Field) -> bool {
+        match self.merge_hook {
+            Some(ref merge_hook) => merge_hook.drop_field(field),
+            None => false,
+        }
+    }
+
+    fn is_purged(&self, field: Field, term_bytes: &[u8]) -> bool {
+        match self.merge_hook {
+            Some(ref merge_hook) => {
+                merge_hook.purge_term(&Term::from_field_bytes(field, term_bytes))
+            }
+            None => false,
+        }
+    }
+
+    fn rewrite_fast_field_value(&self, field: Field, val: u64) -> u64 {
+        match self.merge_hook {
+            Some(ref merge_hook) => merge_hook.rewrite_fast_field_value(field, val),
+            None => val,
+        }
+    }
+
     fn write_fieldnorms(&self, fast_field_serializer: &mut FastFieldSerializer) -> Result<()> {
         let fieldnorm_fastfields: Vec<Field> = self.schema
             .fields()
@@ -113,33 +213,382 @@ impl IndexMerger {
         self.generic_write_fast_field(
             fieldnorm_fastfields,
             &extract_fieldnorm_reader,
+            false,
             fast_field_serializer,
         )
     }
 
-    fn write_fast_fields(&self, fast_field_serializer: &mut FastFieldSerializer) -> Result<()> {
-        let fast_fields: Vec<Field> = self.schema
-            .fields()
-            .iter()
-            .enumerate()
-            .filter(|&(_, field_entry)| field_entry.is_int_fast())
-            .map(|(field_id, _)| Field(field_id as u32))
-            .collect();
+    fn write_fast_fields(
+        &self,
+        fast_field_serializer: &mut FastFieldSerializer,
+        term_ord_mappings: &HashMap<Field, TermOrdinalMapping>,
+    ) -> Result<()> {
+        let mut single_value_fields = vec![];
+        let mut multi_value_fields = vec![];
+        let mut term_ord_fields = vec![];
+        let mut bytes_fields = vec![];
+        let mut vector_fields = vec![];
+        for (field_id, field_entry) in self.schema.fields().iter().enumerate() {
+            let field = Field(field_id as u32);
+            match *field_entry.field_type() {
+                FieldType::U64(ref options) | FieldType::I64(ref options) => {
+                    match options.get_fastfield_cardinality() {
+                        Some(Cardinality::SingleValue) => single_value_fields.push(field),
+                        Some(Cardinality::MultiValues) => multi_value_fields.push(field),
+                        None => {}
+                    }
+                }
+                ref field_type if has_term_ord_fast_field(field_type) => {
+                    term_ord_fields.push(field)
+                }
+                FieldType::Bytes => bytes_fields.push(field),
+                FieldType::Vector(_) => vector_fields.push(field),
+                _ => {}
+            }
+        }
         self.generic_write_fast_field(
-            fast_fields,
+            single_value_fields,
             &extract_fast_field_reader,
+            true,
+            fast_field_serializer,
+        )?;
+        for field in multi_value_fields {
+            let is_dropped = self.is_dropped(field);
+            self.write_multi_fast_field(
+                field,
+                &|_, val| {
+                    if is_dropped {
+                        None
+                    } else {
+                        Some(self.rewrite_fast_field_value(field, val))
+                    }
+                },
+                fast_field_serializer,
+            )?;
+        }
+        for field in term_ord_fields {
+            let term_ord_mapping = term_ord_mappings.get(&field);
+            self.write_term_ord_fast_field(field, term_ord_mapping, fast_field_serializer)?;
+        }
+        for field in bytes_fields {
+            self.write_bytes_fast_field(field, fast_field_serializer)?;
+        }
+        for field in vector_fields {
+            self.write_vector_fast_field(field, fast_field_serializer)?;
+        }
+        Ok(())
+    }
+
+    // A bytes fast field is made of an offsets index, associating each document
+    // to the position of its first byte, and of the concatenation of all of the bytes.
+    fn write_bytes_fast_field(
+        &self,
+        field: Field,
+        fast_field_serializer: &mut FastFieldSerializer,
+    ) -> Result<()> {
+        let is_dropped = self.is_dropped(field);
+        let mut bytes_readers = Vec::with_capacity(self.readers.len());
+        let mut total_num_bytes = 0u64;
+        for reader in &self.readers {
+            let bytes_reader = reader.bytes_fast_field_reader(field)?;
+            for doc_id in 0..reader.max_doc() {
+                if !reader.is_deleted(doc_id) && !is_dropped {
+                    total_num_bytes += bytes_reader.get_val(doc_id).len() as u64;
+                }
+            }
+            bytes_readers.push((reader, bytes_reader));
+        }
+
+        fast_field_serializer.write_u64_fast_field_with_idx(
+            field,
+            0,
+            0,
+            total_num_bytes,
+            |idx_serializer| -> Result<()> {
+                let mut offset = 0u64;
+                for &(reader, ref bytes_reader) in &bytes_readers {
+                    for doc_id in 0..reader.max_doc() {
+                        if !reader.is_deleted(doc_id) {
+                            idx_serializer.add_val(offset)?;
+                            if !is_dropped {
+                                offset += bytes_reader.get_val(doc_id).len() as u64;
+                            }
+                        }
+                    }
+                }
+                idx_serializer.add_val(offset)?;
+                Ok(())
+            },
+        )?;
+
+        let mut value_serializer = fast_field_serializer.new_bytes_fast_field_with_idx(field, 1);
+        for &(reader, ref bytes_reader) in &bytes_readers {
+            for doc_id in 0..reader.max_doc() {
+                if !reader.is_deleted(doc_id) && !is_dropped {
+                    value_serializer.write_all(bytes_reader.get_val(doc_id))?;
+                }
+            }
+        }
+        value_serializer.flush()?;
+        Ok(())
+    }
+
+    // A vector fast field is made of a sparse column, associating each document
+    // to the ordinal of its vector, and of the concatenation of all of the vectors.
+    fn write_vector_fast_field(
+        &self,
+        field: Field,
+        fast_field_serializer: &mut FastFieldSerializer,
+    ) -> Result<()> {
+        let is_dropped = self.is_dropped(field);
+        let mut vector_readers = Vec::with_capacity(self.readers.len());
+        let mut num_vectors = 0u64;
+        for reader in &self.readers {
+            let vector_reader = reader.vector_fast_field_reader(field)?;
+            for doc_id in 0..reader.max_doc() {
+                if !reader.is_deleted(doc_id) && !is_dropped && vector_reader.has_val(doc_id) {
+                    num_vectors += 1;
+                }
+            }
+            vector_readers.push((reader, vector_reader));
+        }
+
+        fast_field_serializer.write_u64_fast_field_with_idx(
+            field,
+            0,
+            0,
+            num_vectors.saturating_sub(1),
+            |ord_serializer| -> Result<()> {
+                let mut ord = 0u64;
+                for &(reader, ref vector_reader) in &vector_readers {
+                    for doc_id in 0..reader.max_doc() {
+                        if reader.is_deleted(doc_id) {
+                            continue;
+                        }
+                        if !is_dropped && vector_reader.has_val(doc_id) {
+                            ord_serializer.add_val(ord)?;
+                            ord += 1;
+                        } else {
+                            ord_serializer.add_missing()?;
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )?;
+
+        let (dimension, ann_index) = match *self.schema.get_field_entry(field).field_type() {
+            FieldType::Vector(ref options) => (options.dimension(), options.ann_index()),
+            _ => (0, None),
+        };
+        // the approximate nearest neighbor index is rebuilt from scratch
+        // over the merged vectors.
+        let mut merged_vals = Vec::new();
+        let mut merged_docs = Vec::new();
+        {
+            let mut value_serializer =
+                fast_field_serializer.new_bytes_fast_field_with_idx(field, 1);
+            let mut vector = Vec::new();
+            let mut buffer = [0u8; 4];
+            let mut merged_doc_id = 0u32;
+            for &(reader, ref vector_reader) in &vector_readers {
+                for doc_id in 0..reader.max_doc() {
+                    if reader.is_deleted(doc_id) {
+                        continue;
+                    }
+                    if !is_dropped && vector_reader.get_val(doc_id, &mut vector) {
+                        for &component in &vector {
+                            Endianness::write_f32(&mut buffer, component);
+                            value_serializer.write_all(&buffer)?;
+                        }
+                        if ann_index.is_some() {
+                            merged_vals.extend_from_slice(&vector);
+                            merged_docs.push(merged_doc_id);
+                        }
+                    }
+                    merged_doc_id += 1;
+                }
+            }
+            value_serializer.flush()?;
+        }
+        if let Some(similarity) = ann_index {
+            let hnsw = build_hnsw(&merged_vals, dimension, &merged_docs, similarity);
+            let mut hnsw_serializer = fast_field_serializer.new_bytes_fast_field_with_idx(field, 2);
+            hnsw_serializer.write_all(&hnsw)?;
+            hnsw_serializer.flush()?;
+        }
+        Ok(())
+    }
+
+    // Facets and str fast fields store segment-local term ordinals.
+    // They need to be remapped to the ordinals of the merged term dictionary.
+    //
+    // The mapping is computed while writing the postings, as the terms
+    // whose documents are all deleted are not part of the merged term
+    // dictionary. Dropped fields do not have any mapping.
+    fn write_term_ord_fast_field(
+        &self,
+        field: Field,
+        term_ord_mapping: Option<&TermOrdinalMapping>,
+        fast_field_serializer: &mut FastFieldSerializer,
+    ) -> Result<()> {
+        self.write_multi_fast_field(
+            field,
+            &|segment_ord, term_ord| {
+                term_ord_mapping
+                    .and_then(|term_ord_mapping| term_ord_mapping[segment_ord][term_ord as usize])
+            },
             fast_field_serializer,
         )
     }
 
+    // A multivalued fast field is made of an offsets index, associating each document
+    // to the position of its first value, and of the array of all of the values.
+    //
+    // `map_val` is applied to each value, given the ordinal of its segment.
+    // The values it maps to `None` are removed.
+    //
+    // The columns of the segments without any deleted document, nor any
+    // removed value, are read by blocks rather than document by document:
+    // their offsets are simply shifted, and their values mapped one after the other.
+    fn write_multi_fast_field(
+        &self,
+        field: Field,
+        map_val: &Fn(usize, u64) -> Option<u64>,
+        fast_field_serializer: &mut FastFieldSerializer,
+    ) -> Result<()> {
+        let mut columns = Vec::with_capacity(self.readers.len());
+        let mut vals = Vec::new();
+        let mut num_vals = 0u64;
+        let mut min_val = u64::max_value();
+        let mut max_val = u64::min_value();
+        for (segment_ord, reader) in self.readers.iter().enumerate() {
+            let multi_reader = reader.multi_fast_field_reader_u64(field)?;
+            let segment_num_vals = multi_reader.idx_reader().get(reader.max_doc()) as u32;
+            let has_deletes = reader.delete_bitset().has_deletes();
+            let mut removes_vals = false;
+            {
+                let mut add_vals = |segment_vals: &[u64]| {
+                    for &val in segment_vals {
+                        match map_val(segment_ord, val) {
+                            Some(val) => {
+                                num_vals += 1;
+                                min_val = min(min_val, val);
+                                max_val = max(max_val, val);
+                            }
+                            None => removes_vals = true,
+                        }
+                    }
+                };
+                if has_deletes {
+                    for doc_id in 0..reader.max_doc() {
+                        if !reader.is_deleted(doc_id) {
+                            multi_reader.get_vals(doc_id, &mut vals);
+                            add_vals(&vals[..]);
+                        }
+                    }
+                } else {
+                    for_each_block(multi_reader.vals_reader(), segment_num_vals, |block| {
+                        add_vals(block);
+                        Ok(())
+                    })?;
+                }
+            }
+            columns.push(MultiValueColumn {
+                segment_ord,
+                reader,
+                multi_reader,
+                num_vals: segment_num_vals,
+                is_merged_as_a_whole: !has_deletes && !removes_vals,
+            });
+        }
+
+        if num_vals == 0 {
+            min_val = 0;
+            max_val = 0;
+        }
+
+        fast_field_serializer.write_u64_fast_field_with_idx(
+            field,
+            0,
+            0,
+            num_vals,
+            |idx_serializer| -> Result<()> {
+                let mut offset = 0u64;
+                for column in &columns {
+                    let reader = column.reader;
+                    if column.is_merged_as_a_whole {
+                        let start_offset = offset;
+                        let idx_reader = column.multi_reader.idx_reader();
+                        for_each_block(idx_reader, reader.max_doc(), |block| {
+                            for &segment_offset in block {
+                                idx_serializer.add_val(start_offset + segment_offset)?;
+                            }
+                            Ok(())
+                        })?;
+                        offset += u64::from(column.num_vals);
+                        continue;
+                    }
+                    for doc_id in 0..reader.max_doc() {
+                        if !reader.is_deleted(doc_id) {
+                            idx_serializer.add_val(offset)?;
+                            column.multi_reader.get_vals(doc_id, &mut vals);
+                            offset += vals.iter()
+                                .filter_map(|&val| map_val(column.segment_ord, val))
+                                .count() as u64;
+                        }
+                    }
+                }
+                idx_serializer.add_val(offset)?;
+                Ok(())
+            },
+        )?;
+        fast_field_serializer.write_u64_fast_field_with_idx(
+            field,
+            1,
+            min_val,
+            max_val,
+            |vals_serializer| -> Result<()> {
+                for column in &columns {
+                    let segment_ord = column.segment_ord;
+                    if column.is_merged_as_a_whole {
+                        let vals_reader = column.multi_reader.vals_reader();
+                        for_each_block(vals_reader, column.num_vals, |block| {
+                            for val in block.iter().filter_map(|&val| map_val(segment_ord, val)) {
+                                vals_serializer.add_val(val)?;
+                            }
+                            Ok(())
+                        })?;
+                        continue;
+                    }
+                    let reader = column.reader;
+                    for doc_id in 0..reader.max_doc() {
+                        if !reader.is_deleted(doc_id) {
+                            column.multi_reader.get_vals(doc_id, &mut vals);
+                            for val in vals.iter().filter_map(|&val| map_val(segment_ord, val)) {
+                                vals_serializer.add_val(val)?;
+                            }
+                        }
+                    }
+                }
+                Ok(())
+            },
+        )
+    }
+
     // used both to merge field norms and regular u64 fast fields.
+    //
+    // If `rewrite_vals` is true, the values are rewritten by the merge hook.
     fn generic_write_fast_field(
         &self,
         fields: Vec<Field>,
         field_reader_extractor: &Fn(&SegmentReader, Field) -> Option<FastFieldReader<u64>>,
+        rewrite_vals: bool,
         fast_field_serializer: &mut FastFieldSerializer,
     ) -> Result<()> {
         for field in fields {
+            let is_dropped = self.is_dropped(field);
+            let rewrite_vals = rewrite_vals && self.merge_hook.is_some();
             let mut u64_readers = vec![];
             let mut min_val = u64::max_value();
             let mut max_val = u64::min_value();
@@ -147,61 +596,97 @@ impl IndexMerger {
             for reader in &self.readers {
                 match field_reader_extractor(reader, field) {
                     Some(u64_reader) => {
-                        if let Some((seg_min_val, seg_max_val)) = compute_min_max_val(
-                            &u64_reader,
-                            reader.max_doc(),
-                            reader.delete_bitset(),
-                        ) {
-                            // the segment has some non-deleted documents
-                            min_val = min(min_val, seg_min_val);
-                            max_val = max(max_val, seg_max_val);
-                            u64_readers.push((
+                        let seg_min_max_val = if rewrite_vals {
+                            // the rewritten values may not be within the former bounds.
+                            (0..reader.max_doc())
+                                .filter(|&doc_id| !reader.is_deleted(doc_id))
+                                .filter_map(|doc_id| u64_reader.get_opt(doc_id))
+                                .map(|val| self.rewrite_fast_field_value(field, val))
+                                .minmax()
+                                .into_option()
+                        } else {
+                            compute_min_max_val(
+                                &u64_reader,
                                 reader.max_doc(),
-                                u64_reader,
                                 reader.delete_bitset(),
-                            ));
+                            )
+                        };
+                        if let Some((seg_min_val, seg_max_val)) = seg_min_max_val {
+                            // the segment has some non-deleted documents with a value
+                            min_val = min(min_val, seg_min_val);
+                            max_val = max(max_val, seg_max_val);
                         }
+                        u64_readers.push((
+                            reader.max_doc(),
+                            u64_reader,
+                            reader.delete_bitset(),
+                        ));
                     }
                     None => {
-                        let error_msg =
-                            format!("Failed to find a u64_reader for field {:?}", field);
-                        error!("{}", error_msg);
-                        bail!(ErrorKind::SchemaError(error_msg));
+                        let field_name = self.schema.get_field_entry(field).name().to_string();
+                        error!("Failed to find a u64_reader for field {:?}", field_name);
+                        bail!(ErrorKind::SchemaError(SchemaError::IncompatibleFieldType(
+                            field_name,
+                            "a u64 fast field".to_string(),
+                        )));
                     }
                 }
             }
 
-            if u64_readers.is_empty() {
-                // we have actually zero documents.
+            if min_val > max_val || is_dropped {
+                // we have actually zero values.
                 min_val = 0;
                 max_val = 0;
             }
 
             assert!(min_val <= max_val);
 
-            let mut fast_single_field_serializer =
-                fast_field_serializer.new_u64_fast_field(field, min_val, max_val)?;
-            for (max_doc, u64_reader, delete_bitset) in u64_readers {
-                for doc_id in 0..max_doc {
-                    if !delete_bitset.is_deleted(doc_id) {
-                        let val = u64_reader.get(doc_id);
-                        fast_single_field_serializer.add_val(val)?;
+            fast_field_serializer.write_u64_fast_field(
+                field,
+                min_val,
+                max_val,
+                |fast_single_field_serializer| -> Result<()> {
+                    for &(max_doc, ref u64_reader, delete_bitset) in &u64_readers {
+                        for doc_id in 0..max_doc {
+                            if delete_bitset.is_deleted(doc_id) {
+                                continue;
+                            }
+                            let val_opt = if is_dropped {
+                                None
+                            } else {
+                                u64_reader.get_opt(doc_id)
+                            };
+                            match val_opt {
+                                Some(val) if rewrite_vals => fast_single_field_serializer
+                                    .add_val(self.rewrite_fast_field_value(field, val))?,
+                                Some(val) => fast_single_field_serializer.add_val(val)?,
+                                None => fast_single_field_serializer.add_missing()?,
+                            }
+                        }
                     }
-                }
-            }
-
-            fast_single_field_serializer.close_field()?;
+                    Ok(())
+                },
+            )?;
         }
         Ok(())
     }
 
-    fn write_postings(&self, serializer: &mut InvertedIndexSerializer) -> Result<()> {
+    // Returns, for the fields with term ordinal fast fields, the mapping
+    // from the term ordinals of the segments to those of the merged segment.
+    fn write_postings(
+        &self,
+        serializer: &mut InvertedIndexSerializer,
+    ) -> Result<HashMap<Field, TermOrdinalMapping>> {
         let mut delta_computer = DeltaComputer::new();
+        let mut term_ord_mappings = HashMap::new();
 
         let mut indexed_fields = vec![];
         for (field_ord, field_entry) in self.schema.fields().iter().enumerate() {
-            if field_entry.is_indexed() {
-                indexed_fields.push(Field(field_ord as u32));
+            let field = Field(field_ord as u32);
+            // dropped fields do not get any postings, as in a segment
+            // in which none of the documents have the field.
+            if field_entry.is_indexed() && !self.is_dropped(field) {
+                indexed_fields.push(field);
             }
         }
 
@@ -219,6 +704,19 @@ impl IndexMerger {
             let mut merged_terms = TermMerger::new(field_term_streams);
             let mut max_doc = 0;
 
+            let field_type = self.schema.get_field_entry(indexed_field).field_type();
+            let mut term_ord_mapping_opt: Option<TermOrdinalMapping> =
+                if has_term_ord_fast_field(field_type) {
+                    let term_ord_mapping = field_readers
+                        .iter()
+                        .map(|field_reader| vec![None; field_reader.terms().num_terms()])
+                        .collect();
+                    Some(term_ord_mapping)
+                } else {
+                    None
+                };
+            let mut new_term_ord: TermOrdinal = 0;
+
             // map from segment doc ids to the resulting merged segment doc id.
             let mut merged_doc_id_map: Vec<Vec<Option<DocId>>> =
                 Vec::with_capacity(self.readers.len());
@@ -260,25 +758,23 @@ impl IndexMerger {
 
             while merged_terms.advance() {
                 let term_bytes: &[u8] = merged_terms.key();
+                if self.is_purged(indexed_field, term_bytes) {
+                    continue;
+                }
 
                 // Let's compute the list of non-empty posting lists
-                let segment_postings: Vec<_> = merged_terms
-                    .current_kvs()
-                    .iter()
-                    .flat_map(|heap_item| {
-                        let segment_ord = heap_item.segment_ord;
-                        let term_info = heap_item.streamer.value();
-                        let segment_reader = &self.readers[heap_item.segment_ord];
-                        let inverted_index = segment_reader.inverted_index(indexed_field);
-                        let mut segment_postings = inverted_index
-                            .read_postings_from_terminfo(term_info, segment_postings_option);
-                        if segment_postings.advance() {
-                            Some((segment_ord, segment_postings))
-                        } else {
-                            None
-                        }
-                    })
-                    .collect();
+                let mut segment_postings = vec![];
+                for heap_item in merged_terms.current_kvs() {
+                    let segment_ord = heap_item.segment_ord;
+                    let term_info = heap_item.streamer.value();
+                    let segment_reader = &self.readers[heap_item.segment_ord];
+                    let inverted_index = segment_reader.inverted_index(indexed_field);
+                    let mut postings = inverted_index
+                        .read_postings_from_terminfo(term_info, segment_postings_option)?;
+                    if postings.advance() {
+                        segment_postings.push((segment_ord, postings));
+                    }
+                }
 
                 // At this point, `segment_postings` contains the posting list
                 // of all of the segments containing the given term.
@@ -291,6 +787,14 @@ impl IndexMerger {
                     // We know that there is at least one document containing
                     // the term, so we add it.
                     field_serializer.new_term(term_bytes)?;
+                    if let Some(ref mut term_ord_mapping) = term_ord_mapping_opt {
+                        for heap_item in merged_terms.current_kvs() {
+                            let old_term_ord = heap_item.streamer.term_ord() as usize;
+                            term_ord_mapping[heap_item.segment_ord][old_term_ord] =
+                                Some(new_term_ord);
+                        }
+                    }
+                    new_term_ord += 1;
 
                     // We can now serialize this postings, by pushing each document to the
                     // postings serializer.
@@ -306,7 +810,12 @@ impl IndexMerger {
                                 // there is at least one document.
                                 let positions: &[u32] = segment_postings.positions();
                                 let term_freq = segment_postings.term_freq();
-                                let delta_positions = delta_computer.compute_delta(positions);
+                                let delta_positions = if segment_postings_option.has_offsets() {
+                                    let offsets = segment_postings.offsets();
+                                    delta_computer.compute_delta_with_offsets(positions, offsets)
+                                } else {
+                                    delta_computer.compute_delta(positions)
+                                };
                                 field_serializer.write_doc(
                                     remapped_doc_id,
                                     term_freq,
@@ -325,19 +834,77 @@ impl IndexMerger {
             }
 
             field_serializer.close()?;
+            if let Some(term_ord_mapping) = term_ord_mapping_opt {
+                term_ord_mappings.insert(indexed_field, term_ord_mapping);
+            }
+        }
+        Ok(term_ord_mappings)
+    }
+
+    fn write_completions(&self, serializer: &mut CompositeWrite<WritePtr>) -> Result<()> {
+        for field in completion_fields(&self.schema) {
+            let completion_indexes: Vec<CompletionIndex> = if self.is_dropped(field) {
+                vec![]
+            } else {
+                self.readers
+                    .iter()
+                    .filter_map(|reader| reader.completion_index(field))
+                    .collect()
+            };
+            merge_completion_indexes(field, &completion_indexes, serializer)?;
+        }
+        Ok(())
+    }
+
+    fn write_term_vectors(&self, serializer: &mut CompositeWrite<WritePtr>) -> Result<()> {
+        for field in term_vector_fields(&self.schema) {
+            let is_dropped = self.is_dropped(field);
+            let mut field_writer = FieldTermVectorsWriter::new();
+            for reader in &self.readers {
+                let term_vector_reader_opt = if is_dropped {
+                    None
+                } else {
+                    reader.term_vector_reader(field)
+                };
+                for doc_id in 0..reader.max_doc() {
+                    if reader.is_deleted(doc_id) {
+                        continue;
+                    }
+                    if let Some(ref term_vector_reader) = term_vector_reader_opt {
+                        if self.merge_hook.is_some() {
+                            let mut term_vector = term_vector_reader.term_vector(doc_id)?;
+                            term_vector
+                                .retain(|entry| !self.is_purged(field, entry.text.as_bytes()));
+                            field_writer.add_term_vector(&term_vector)?;
+                        } else {
+                            field_writer
+                                .add_raw_term_vector(term_vector_reader.raw_term_vector(doc_id)?);
+                        }
+                    }
+                    field_writer.end_document();
+                }
+            }
+            field_writer.serialize(serializer.for_field(field))?;
         }
         Ok(())
     }
 
     fn write_storable_fields(&self, store_writer: &mut StoreWriter) -> Result<()> {
+        let dropped_fields: Vec<Field> = self.schema
+            .fields()
+            .iter()
+            .enumerate()
+            .filter(|&(_, field_entry)| field_entry.is_stored())
+            .map(|(field_id, _)| Field(field_id as u32))
+            .filter(|&field| self.is_dropped(field))
+            .collect();
         for reader in &self.readers {
             let store_reader = reader.get_store_reader();
-            if reader.num_deleted_docs() > 0 {
-                for doc_id in 0..reader.max_doc() {
-                    if !reader.is_deleted(doc_id) {
-                        let doc = store_reader.get(doc_id)?;
-                        store_writer.store(&doc)?;
-                    }
+            if reader.num_deleted_docs() > 0 || !dropped_fields.is_empty() {
+                for doc_res in reader.iter_documents() {
+                    let (_, mut doc) = doc_res?;
+                    doc.filter_fields(|field| !dropped_fields.contains(&field));
+                    store_writer.store(&doc)?;
                 }
             } else {
                 store_writer.stack(store_reader)?;
@@ -349,9 +916,11 @@ impl IndexMerger {
 
 impl SerializableSegment for IndexMerger {
     fn write(&self, mut serializer: SegmentSerializer) -> Result<u32> {
-        self.write_postings(serializer.get_postings_serializer())?;
+        let term_ord_mappings = self.write_postings(serializer.get_postings_serializer())?;
         self.write_fieldnorms(serializer.get_fieldnorms_serializer())?;
-        self.write_fast_fields(serializer.get_fast_field_serializer())?;
+        self.write_fast_fields(serializer.get_fast_field_serializer(), &term_ord_mappings)?;
+        self.write_completions(serializer.get_completions_serializer())?;
+        self.write_term_vectors(serializer.get_term_vectors_serializer())?;
         self.write_storable_fields(serializer.get_store_writer())?;
         serializer.close()?;
         Ok(self.max_doc)
@@ -374,7 +943,11 @@ mod tests {
     use query::BooleanQuery;
     use schema::IndexRecordOption;
     use schema::Cardinality;
+    use schema::Facet;
     use futures::Future;
+    use collector::FacetCollector;
+    use indexer::MergeHook;
+    use query::AllQuery;
 
     #[test]
     fn test_index_merger_no_deletes() {
@@ -805,4 +1378,199 @@ mod tests {
             assert_eq!(searcher.num_docs(), 0);
         }
     }
+
+    #[test]
+    fn test_index_merger_multivalued_columns() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", schema::INT_INDEXED | schema::FAST);
+        let vals_field = schema_builder.add_u64_field(
+            "vals",
+            schema::IntOptions::default().set_fast(Cardinality::MultiValues),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        // each segment has more values than `MERGE_BLOCK_LEN`.
+        for id in 0u64..600u64 {
+            index_writer.add_document(doc!(id_field => id, vals_field => id, vals_field => id + 1));
+        }
+        index_writer.commit().unwrap();
+        for id in 600u64..1_200u64 {
+            index_writer.add_document(doc!(id_field => id, vals_field => id, vals_field => id + 1));
+        }
+        index_writer.commit().unwrap();
+        // only the first segment is merged as a whole.
+        index_writer.delete_term(Term::from_field_u64(id_field, 700u64));
+        index_writer.commit().unwrap();
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(segment_reader.num_docs(), 1_199);
+        let id_reader = segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+        let vals_reader = segment_reader.multi_fast_field_reader::<u64>(vals_field).unwrap();
+        let mut vals = Vec::new();
+        for doc_id in 0..segment_reader.max_doc() {
+            let id = id_reader.get(doc_id);
+            assert_ne!(id, 700u64);
+            vals_reader.get_vals(doc_id, &mut vals);
+            assert_eq!(vals, vec![id, id + 1]);
+        }
+    }
+
+    #[test]
+    fn test_index_merger_term_ords_with_deletes() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", schema::INT_INDEXED | schema::FAST);
+        let facet_field = schema_builder.add_facet_field("facet");
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(id_field => 0u64, facet_field => Facet::from("/a")));
+        index_writer.add_document(doc!(id_field => 1u64, facet_field => Facet::from("/b")));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field => 2u64, facet_field => Facet::from("/c")));
+        index_writer.commit().unwrap();
+        // `/b` is not part of the merged term dictionary anymore.
+        index_writer.delete_term(Term::from_field_u64(id_field, 1u64));
+        index_writer.commit().unwrap();
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let segment_reader = searcher.segment_reader(0);
+        assert_eq!(segment_reader.max_doc(), 2);
+        let id_reader = segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+        let mut facet_reader = segment_reader.facet_reader(facet_field).unwrap();
+        let mut facet_ords = Vec::new();
+        let mut facets = Vec::new();
+        for doc_id in 0..segment_reader.max_doc() {
+            facet_reader.facet_ords(doc_id, &mut facet_ords);
+            let doc_facets: Vec<Facet> = facet_ords
+                .iter()
+                .map(|&facet_ord| {
+                    let mut facet = Facet::root();
+                    facet_reader.facet_from_ord(facet_ord, &mut facet);
+                    facet
+                })
+                .collect();
+            facets.push((id_reader.get(doc_id), doc_facets));
+        }
+        assert_eq!(
+            facets,
+            vec![
+                (0u64, vec![Facet::from("/a")]),
+                (2u64, vec![Facet::from("/c")]),
+            ]
+        );
+    }
+
+    #[derive(Debug)]
+    struct ScrubbingHook {
+        text_field: Field,
+        secret_field: Field,
+        score_field: Field,
+        facet_field: Field,
+    }
+
+    impl MergeHook for ScrubbingHook {
+        fn drop_field(&self, field: Field) -> bool {
+            field == self.secret_field
+        }
+
+        fn rewrite_fast_field_value(&self, field: Field, value: u64) -> u64 {
+            if field == self.score_field {
+                value * 10
+            } else {
+                value
+            }
+        }
+
+        fn purge_term(&self, term: &Term) -> bool {
+            *term == Term::from_field_text(self.text_field, "purged")
+                || *term == Term::from_facet(self.facet_field, &Facet::from("/cat/purged"))
+        }
+    }
+
+    #[test]
+    fn test_index_merger_merge_hook() {
+        let mut schema_builder = schema::SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", schema::TEXT | schema::STORED);
+        let secret_field = schema_builder.add_text_field("secret", schema::TEXT | schema::STORED);
+        let score_field = schema_builder.add_u64_field("score", schema::FAST);
+        let facet_field = schema_builder.add_facet_field("facet");
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(
+            text_field => "a purged",
+            secret_field => "s1",
+            score_field => 1u64,
+            facet_field => Facet::from("/cat/kept")
+        ));
+        index_writer.add_document(doc!(
+            text_field => "a",
+            secret_field => "s2",
+            score_field => 2u64,
+            facet_field => Facet::from("/cat/purged")
+        ));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(
+            text_field => "b",
+            secret_field => "s3",
+            score_field => 3u64,
+            facet_field => Facet::from("/cat/kept")
+        ));
+        index_writer.commit().unwrap();
+
+        index_writer.set_merge_hook(box ScrubbingHook {
+            text_field,
+            secret_field,
+            score_field,
+            facet_field,
+        });
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "a")), 2);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(text_field, "purged")), 0);
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(secret_field, "s1")), 0);
+        for doc_id in 0..3 {
+            let doc = searcher.doc(&DocAddress(0, doc_id)).unwrap();
+            assert!(doc.get_first(text_field).is_some());
+            assert!(doc.get_first(secret_field).is_none());
+        }
+        // purged terms remain in the doc store.
+        let doc = searcher.doc(&DocAddress(0, 0)).unwrap();
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "a purged");
+
+        let segment_reader = searcher.segment_reader(0);
+        let score_reader = segment_reader.fast_field_reader::<u64>(score_field).unwrap();
+        assert_eq!(
+            (0..3).map(|doc_id| score_reader.get(doc_id)).collect::<Vec<u64>>(),
+            vec![10, 20, 30]
+        );
+        let fieldnorm_reader = segment_reader.fieldnorm_reader(secret_field).unwrap();
+        assert_eq!(fieldnorm_reader.fieldnorm(0), 0);
+
+        let mut facet_collector = FacetCollector::for_field(facet_field);
+        facet_collector.add_facet("/cat");
+        searcher.search(&AllQuery, &mut facet_collector).unwrap();
+        let facet_counts: Vec<(String, u64)> = facet_collector
+            .harvest()
+            .get("/cat")
+            .map(|(facet, count)| (facet.to_string(), count))
+            .collect();
+        assert_eq!(facet_counts, vec![("/cat/kept".to_string(), 2)]);
+    }
 }
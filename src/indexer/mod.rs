@@ -1,29 +1,45 @@
 pub mod index_writer;
+mod index_writer_handle;
 pub mod segment_serializer;
 pub mod merger;
 mod merge_policy;
+mod merge_hook;
 mod log_merge_policy;
 mod segment_register;
 mod segment_writer;
 mod segment_manager;
 pub mod delete_queue;
 pub mod segment_updater;
-mod directory_lock;
 mod segment_entry;
 mod doc_opstamp_mapping;
 pub mod operation;
 mod stamper;
 mod prepared_commit;
+mod bulk_loader;
+mod auto_commit_policy;
+mod reindexer;
+mod retention_policy;
+mod multi_index_writer;
+mod upgrade;
+mod error;
 
 pub use self::prepared_commit::PreparedCommit;
+pub use self::bulk_loader::BulkLoader;
+pub use self::auto_commit_policy::AutoCommitPolicy;
+pub use self::reindexer::{ReindexProgress, Reindexer};
+pub use self::retention_policy::RetentionPolicy;
+pub use self::multi_index_writer::MultiIndexWriter;
+pub use self::upgrade::upgrade_index;
+pub use self::error::IndexingError;
 pub use self::segment_entry::{SegmentEntry, SegmentState};
 pub use self::segment_serializer::SegmentSerializer;
 pub use self::segment_writer::SegmentWriter;
 pub use self::index_writer::IndexWriter;
+pub use self::index_writer_handle::IndexWriterHandle;
 pub use self::log_merge_policy::LogMergePolicy;
 pub use self::merge_policy::{MergeCandidate, MergePolicy, NoMergePolicy};
+pub use self::merge_hook::MergeHook;
 pub use self::segment_manager::SegmentManager;
-pub(crate) use self::directory_lock::DirectoryLock;
 
 /// Alias for the default merge policy, which is the `LogMergePolicy`.
 pub type DefaultMergePolicy = LogMergePolicy;
@@ -0,0 +1,236 @@
+use Result;
+use core::{Index, Searcher};
+use error::ErrorKind;
+use schema::{Document, Term};
+use std::collections::BTreeMap;
+use super::IndexWriter;
+
+struct Partition {
+    index: Index,
+    index_writer: IndexWriter,
+}
+
+/// Writes documents to several indexes, routing each document
+/// to the index of its partition.
+///
+/// Partitioning an index makes it possible to search, or to drop, a subset
+/// of the documents without touching the others. Log indexes, for instance,
+/// are typically partitioned by day, so that the old days can be
+/// dropped as a whole, and tenants of a multi-tenant application
+/// can get an index of their own.
+///
+/// The partition of a document is a string computed by a user-supplied
+/// `router`, from the value of one of its fields for instance. The index of
+/// a partition is supplied by the `index_provider` the first time a document
+/// is routed to it, and is written to by an `IndexWriter` of its own.
+/// All of the indexes are expected to share the same schema.
+///
+/// The partitions are searched together with `.searcher()`, or some of
+/// them with `.searcher_for_partitions(...)`.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate tantivy;
+/// # use tantivy::{Index, MultiIndexWriter};
+/// # use tantivy::schema::*;
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let tenant = schema_builder.add_text_field("tenant", STRING | STORED);
+/// let schema = schema_builder.build();
+/// let mut multi_index_writer = MultiIndexWriter::new(
+///     move |doc: &Document| doc.get_first(tenant).unwrap().text().to_string(),
+///     move |_partition: &str| Ok(Index::create_in_ram(schema.clone())),
+///     40_000_000,
+/// );
+/// multi_index_writer.add_document(doc!(tenant => "acme")).unwrap();
+/// multi_index_writer.commit().unwrap();
+/// assert_eq!(multi_index_writer.searcher().unwrap().num_docs(), 1);
+/// # }
+/// ```
+pub struct MultiIndexWriter {
+    router: Box<Fn(&Document) -> String>,
+    index_provider: Box<FnMut(&str) -> Result<Index>>,
+    heap_size_in_bytes: usize,
+    partitions: BTreeMap<String, Partition>,
+}
+
+impl MultiIndexWriter {
+    /// Creates a `MultiIndexWriter`.
+    ///
+    /// - `router` returns the partition of a document,
+    /// - `index_provider` returns the index of a partition, given its name,
+    /// - `heap_size_in_bytes` is the heap size of each of the `IndexWriter`s.
+    ///   Every writer uses a single indexing thread.
+    pub fn new<R, P>(router: R, index_provider: P, heap_size_in_bytes: usize) -> MultiIndexWriter
+    where
+        R: Fn(&Document) -> String + 'static,
+        P: FnMut(&str) -> Result<Index> + 'static,
+    {
+        MultiIndexWriter {
+            router: box router,
+            index_provider: box index_provider,
+            heap_size_in_bytes,
+            partitions: BTreeMap::new(),
+        }
+    }
+
+    fn partition_writer(&mut self, partition_name: &str) -> Result<&mut IndexWriter> {
+        if !self.partitions.contains_key(partition_name) {
+            let index = (self.index_provider)(partition_name)?;
+            let index_writer = index.writer_with_num_threads(1, self.heap_size_in_bytes)?;
+            self.partitions.insert(
+                partition_name.to_string(),
+                Partition {
+                    index,
+                    index_writer,
+                },
+            );
+        }
+        Ok(&mut self.partitions
+            .get_mut(partition_name)
+            .expect("The partition was just inserted.")
+            .index_writer)
+    }
+
+    /// Adds a document to the index of its partition.
+    ///
+    /// Returns the opstamp of the document, as given
+    /// by the `IndexWriter` of the partition.
+    ///
+    /// # Errors
+    /// If the index of a new partition cannot be provided, or
+    /// if its writer cannot be opened, returns the error.
+    pub fn add_document(&mut self, document: Document) -> Result<u64> {
+        let partition_name = (self.router)(&document);
+        let index_writer = self.partition_writer(&partition_name)?;
+        Ok(index_writer.add_document(document))
+    }
+
+    /// Deletes all of the documents containing a given term,
+    /// in all of the partitions.
+    pub fn delete_term(&mut self, term: &Term) {
+        for partition in self.partitions.values_mut() {
+            partition.index_writer.delete_term(term.clone());
+        }
+    }
+
+    /// Commits all of the partitions.
+    ///
+    /// The partitions are committed one after the other, so that
+    /// the commit is not atomic across partitions.
+    pub fn commit(&mut self) -> Result<()> {
+        for partition in self.partitions.values_mut() {
+            partition.index_writer.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the names of the partitions, in alphabetical order.
+    pub fn partition_names(&self) -> Vec<&str> {
+        self.partitions.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Returns the index of a partition, if it exists.
+    pub fn partition_index(&self, partition_name: &str) -> Option<&Index> {
+        self.partitions
+            .get(partition_name)
+            .map(|partition| &partition.index)
+    }
+
+    /// Removes a partition from the `MultiIndexWriter`.
+    ///
+    /// The writer of the partition is dropped, along with its
+    /// uncommitted documents, and its index is returned, so that
+    /// it can be deleted, or archived.
+    /// Documents routed to the partition afterwards are
+    /// written to the index supplied by the `index_provider`.
+    pub fn remove_partition(&mut self, partition_name: &str) -> Option<Index> {
+        self.partitions
+            .remove(partition_name)
+            .map(|partition| partition.index)
+    }
+
+    /// Returns a searcher over all of the partitions,
+    /// as of their last commit.
+    pub fn searcher(&self) -> Result<Searcher> {
+        let partition_names = self.partition_names();
+        self.searcher_for_partitions(&partition_names)
+    }
+
+    /// Returns a searcher over the given partitions,
+    /// as of their last commit.
+    ///
+    /// Segment ordinals in `DocAddress`es are attributed
+    /// following the order of `partition_names`.
+    ///
+    /// # Errors
+    /// If one of the partitions does not exist, or if their schemas
+    /// differ, returns `ErrorKind::InvalidArgument`.
+    pub fn searcher_for_partitions(&self, partition_names: &[&str]) -> Result<Searcher> {
+        let mut indexes = Vec::with_capacity(partition_names.len());
+        for &partition_name in partition_names {
+            match self.partition_index(partition_name) {
+                Some(index) => {
+                    index.load_searchers()?;
+                    indexes.push(index);
+                }
+                None => bail!(ErrorKind::InvalidArgument(format!(
+                    "Unknown partition {:?}",
+                    partition_name
+                ))),
+            }
+        }
+        Searcher::for_indexes(&indexes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use schema::{Document, SchemaBuilder, STORED, STRING, TEXT};
+    use super::MultiIndexWriter;
+
+    #[test]
+    fn test_multi_index_writer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let tenant_field = schema_builder.add_text_field("tenant", STRING | STORED);
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let mut multi_index_writer = MultiIndexWriter::new(
+            move |doc: &Document| doc.get_first(tenant_field).unwrap().text().to_string(),
+            move |_: &str| Ok(Index::create_in_ram(schema.clone())),
+            40_000_000,
+        );
+        for &(tenant, text) in &[("b", "hello"), ("a", "hello"), ("b", "happy"), ("c", "hello")] {
+            multi_index_writer
+                .add_document(doc!(tenant_field => tenant, text_field => text))
+                .unwrap();
+        }
+        multi_index_writer.commit().unwrap();
+        assert_eq!(multi_index_writer.partition_names(), vec!["a", "b", "c"]);
+        let b_index = multi_index_writer.partition_index("b").unwrap().clone();
+        b_index.load_searchers().unwrap();
+        assert_eq!(b_index.searcher().num_docs(), 2);
+
+        let hello_term = Term::from_field_text(text_field, "hello");
+        let searcher = multi_index_writer.searcher().unwrap();
+        assert_eq!(searcher.num_docs(), 4);
+        assert_eq!(searcher.doc_freq(&hello_term), 3);
+        let searcher = multi_index_writer
+            .searcher_for_partitions(&["b", "c"])
+            .unwrap();
+        assert_eq!(searcher.doc_freq(&hello_term), 2);
+        assert!(multi_index_writer.searcher_for_partitions(&["d"]).is_err());
+
+        multi_index_writer.delete_term(&hello_term);
+        multi_index_writer.commit().unwrap();
+        assert_eq!(multi_index_writer.searcher().unwrap().num_docs(), 1);
+
+        assert!(multi_index_writer.remove_partition("b").is_some());
+        assert!(multi_index_writer.remove_partition("b").is_none());
+        assert_eq!(multi_index_writer.partition_names(), vec!["a", "c"]);
+        assert_eq!(multi_index_writer.searcher().unwrap().num_docs(), 0);
+    }
+}
@@ -1,11 +1,23 @@
 use schema::Document;
+use schema::Field;
 use schema::Term;
 
+/// New value for a fast field, in its `u64` encoding.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub struct FastFieldUpdate {
+    pub field: Field,
+    pub value: u64,
+}
+
 /// Timestamped Delete operation.
+///
+/// If `fast_field_update` is set, the documents matching
+/// the term are not deleted, but get their fast field updated.
 #[derive(Clone, Eq, PartialEq, Debug)]
 pub struct DeleteOperation {
     pub opstamp: u64,
     pub term: Term,
+    pub fast_field_update: Option<FastFieldUpdate>,
 }
 
 /// Timestamped Add operation.
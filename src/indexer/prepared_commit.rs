@@ -31,6 +31,7 @@ impl<'a> PreparedCommit<'a> {
 
     pub fn commit(self) -> Result<u64> {
         info!("committing {}", self.opstamp);
+        let _span = span!("commit", opstamp = self.opstamp);
         self.index_writer
             .segment_updater()
             .commit(self.opstamp, self.payload)?;
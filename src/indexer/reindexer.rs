@@ -0,0 +1,212 @@
+use Result;
+use core::Index;
+use schema::Document;
+use super::IndexWriter;
+
+/// Default number of documents indexed between two commits
+/// of a `Reindexer`.
+const DEFAULT_BATCH_SIZE: usize = 100_000;
+
+/// Progress of a `Reindexer`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ReindexProgress {
+    /// Number of documents in the source index that are not deleted.
+    pub total_num_docs: u64,
+    /// Number of documents read from the source index so far.
+    pub num_docs_read: u64,
+    /// Number of documents added to the target index so far.
+    ///
+    /// Documents discarded by the transform are not counted.
+    pub num_docs_indexed: u64,
+    /// Opstamp of the last commit of the target index, if any.
+    pub last_commit_opstamp: Option<u64>,
+}
+
+/// Copies all of the documents of an index into another one,
+/// transforming them on the way.
+///
+/// Changing the schema or the tokenizers of an index requires indexing
+/// all of its documents again. The `Reindexer` scans the doc store
+/// of every segment of the source index, one block after the other,
+/// and hands every document that is not deleted to a user-supplied
+/// transform, which returns the document to add to the target index,
+/// or `None` to discard it.
+///
+/// As documents are read from the doc store, only their stored fields
+/// are available to the transform: a field that is not stored
+/// cannot be carried over to the target index.
+///
+/// The target index is committed every time a batch of documents has
+/// been added, and once all of the documents have been read. A progress
+/// callback, if any, is called after every commit.
+///
+/// The source index is read from the segments it had when the reindexing
+/// started, so that it is fine to keep on writing to it in the meantime,
+/// although those documents are not carried over to the target index.
+pub struct Reindexer<'a> {
+    source_index: &'a Index,
+    batch_size: usize,
+    progress_callback: Option<Box<FnMut(&ReindexProgress) + 'a>>,
+}
+
+impl<'a> Reindexer<'a> {
+    /// Creates a `Reindexer` reading its documents from `source_index`.
+    pub fn new(source_index: &'a Index) -> Reindexer<'a> {
+        Reindexer {
+            source_index,
+            batch_size: DEFAULT_BATCH_SIZE,
+            progress_callback: None,
+        }
+    }
+
+    /// Sets the number of documents added to the target
+    /// index between two commits.
+    ///
+    /// # Panics
+    /// If `batch_size` is 0, panics.
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        assert!(batch_size > 0, "The batch size must be strictly positive.");
+        self.batch_size = batch_size;
+    }
+
+    /// Sets a callback, called with the progress of the
+    /// reindexing after every commit of the target index.
+    pub fn set_progress_callback<F>(&mut self, progress_callback: F)
+    where
+        F: FnMut(&ReindexProgress) + 'a,
+    {
+        self.progress_callback = Some(box progress_callback);
+    }
+
+    fn commit(
+        &mut self,
+        target_writer: &mut IndexWriter,
+        progress: &mut ReindexProgress,
+    ) -> Result<()> {
+        progress.last_commit_opstamp = Some(target_writer.commit()?);
+        if let Some(ref mut progress_callback) = self.progress_callback {
+            progress_callback(progress);
+        }
+        Ok(())
+    }
+
+    /// Adds all of the documents of the source index to the index of
+    /// `target_writer`, after transforming them with `transform`.
+    ///
+    /// Returns the final progress of the reindexing.
+    ///
+    /// If an error occurs, the documents of the batches that were
+    /// committed before the error remain in the target index.
+    pub fn run<F>(
+        &mut self,
+        target_writer: &mut IndexWriter,
+        mut transform: F,
+    ) -> Result<ReindexProgress>
+    where
+        F: FnMut(Document) -> Option<Document>,
+    {
+        let segment_readers = self.source_index.open_searchable_segment_readers()?;
+        let mut progress = ReindexProgress {
+            total_num_docs: segment_readers
+                .iter()
+                .map(|segment_reader| u64::from(segment_reader.num_docs()))
+                .sum(),
+            ..ReindexProgress::default()
+        };
+        let mut num_docs_in_batch = 0;
+        for segment_reader in &segment_readers {
+            for doc_res in segment_reader.iter_documents() {
+                let (_, document) = doc_res?;
+                progress.num_docs_read += 1;
+                if let Some(target_document) = transform(document) {
+                    target_writer.add_document(target_document);
+                    progress.num_docs_indexed += 1;
+                    num_docs_in_batch += 1;
+                    if num_docs_in_batch == self.batch_size {
+                        self.commit(target_writer, &mut progress)?;
+                        num_docs_in_batch = 0;
+                    }
+                }
+            }
+        }
+        if num_docs_in_batch > 0 || progress.last_commit_opstamp.is_none() {
+            self.commit(target_writer, &mut progress)?;
+        }
+        Ok(progress)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use schema::{Document, SchemaBuilder, INT_INDEXED, INT_STORED, STORED, STRING, TEXT};
+    use super::{ReindexProgress, Reindexer};
+
+    #[test]
+    fn test_reindex() {
+        let mut source_schema_builder = SchemaBuilder::default();
+        let source_title = source_schema_builder.add_text_field("title", STRING | STORED);
+        let source_id = source_schema_builder.add_u64_field("id", INT_INDEXED | INT_STORED);
+        let source_index = Index::create_in_ram(source_schema_builder.build());
+        {
+            let mut index_writer = source_index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for id in 0u64..25u64 {
+                index_writer.add_document(doc!(
+                    source_title => format!("Title {}", id),
+                    source_id => id
+                ));
+            }
+            index_writer.commit().unwrap();
+            index_writer.delete_term(Term::from_field_u64(source_id, 3u64));
+            index_writer.commit().unwrap();
+        }
+
+        // the title becomes a tokenized field, and odd ids are discarded.
+        let mut target_schema_builder = SchemaBuilder::default();
+        let target_title = target_schema_builder.add_text_field("title", TEXT | STORED);
+        let target_id = target_schema_builder.add_u64_field("id", INT_INDEXED | INT_STORED);
+        let target_index = Index::create_in_ram(target_schema_builder.build());
+        let mut reported_progress = vec![];
+        let progress = {
+            let mut index_writer = target_index.writer_with_num_threads(1, 40_000_000).unwrap();
+            let mut reindexer = Reindexer::new(&source_index);
+            reindexer.set_batch_size(5);
+            reindexer.set_progress_callback(|progress| reported_progress.push(*progress));
+            reindexer
+                .run(&mut index_writer, |source_doc| {
+                    let id = source_doc.get_first(source_id).unwrap().u64_value();
+                    if id % 2 == 1 {
+                        return None;
+                    }
+                    let title = source_doc.get_first(source_title).unwrap().text();
+                    let mut target_doc = Document::default();
+                    target_doc.add_text(target_title, title);
+                    target_doc.add_u64(target_id, id);
+                    Some(target_doc)
+                })
+                .unwrap()
+        };
+        assert_eq!(progress.total_num_docs, 24);
+        assert_eq!(progress.num_docs_read, 24);
+        assert_eq!(progress.num_docs_indexed, 13);
+        assert_eq!(reported_progress.len(), 3);
+        assert_eq!(reported_progress[2], progress);
+        assert_eq!(
+            reported_progress
+                .iter()
+                .map(|progress: &ReindexProgress| progress.num_docs_indexed)
+                .collect::<Vec<_>>(),
+            vec![5, 10, 13]
+        );
+
+        target_index.load_searchers().unwrap();
+        let searcher = target_index.searcher();
+        assert_eq!(searcher.num_docs(), 13);
+        let title_term = Term::from_field_text(target_title, "title");
+        assert_eq!(searcher.doc_freq(&title_term), 13);
+        let id_term = Term::from_field_u64(target_id, 24u64);
+        assert_eq!(searcher.doc_freq(&id_term), 1);
+    }
+}
@@ -0,0 +1,170 @@
+use Result;
+use DocId;
+use error::ErrorKind;
+use core::SegmentReader;
+use fastfield::{FastFieldReader, FastValue};
+use schema::{Cardinality, Field, FieldType, Schema, SchemaError};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The `RetentionPolicy` defines how long documents are kept in the index.
+///
+/// Every document is expected to hold its date in a single-valued `i64` or
+/// `u64` fast field, expressed as a number of seconds since the unix epoch.
+/// Upon commit, the segments in which all of the documents are older than
+/// the `ttl` are dropped, as part of the commit: this is much cheaper
+/// than deleting the expired documents and merging the segments.
+///
+/// The policy is applied to whole segments. A segment is kept as long as
+/// one of its documents is not expired, or does not have any date.
+/// With time-ordered data, such as logs, the segments are expired one
+/// after the other.
+///
+/// The policy is set with `IndexWriter::set_retention_policy(...)`.
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    field: Field,
+    ttl: Duration,
+}
+
+impl RetentionPolicy {
+    /// Creates a new `RetentionPolicy`, expiring the documents
+    /// for which the date of `field` is older than `ttl`.
+    pub fn new(field: Field, ttl: Duration) -> RetentionPolicy {
+        RetentionPolicy { field, ttl }
+    }
+
+    /// Returns the date field.
+    pub fn field(&self) -> Field {
+        self.field
+    }
+
+    /// Returns the time to live of the documents.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// Checks that the date field is a single-valued integer fast field.
+    pub(crate) fn validate(&self, schema: &Schema) -> Result<()> {
+        let field_entry = schema.get_field_entry(self.field);
+        match *field_entry.field_type() {
+            FieldType::I64(ref options) | FieldType::U64(ref options)
+                if options.get_fastfield_cardinality() == Some(Cardinality::SingleValue) =>
+            {
+                Ok(())
+            }
+            _ => bail!(ErrorKind::SchemaError(SchemaError::IncompatibleFieldType(
+                field_entry.name().to_string(),
+                "a single-valued i64 or u64 fast field".to_string(),
+            ))),
+        }
+    }
+
+    /// Returns true if all of the documents of the segment
+    /// are expired at the date `now`.
+    pub(crate) fn is_expired(
+        &self,
+        segment_reader: &SegmentReader,
+        now: SystemTime,
+    ) -> Result<bool> {
+        let now_secs = now.duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let cutoff = now_secs.saturating_sub(self.ttl.as_secs());
+        match *segment_reader
+            .schema()
+            .get_field_entry(self.field)
+            .field_type()
+        {
+            FieldType::I64(_) => {
+                let cutoff = if cutoff > i64::max_value() as u64 {
+                    i64::max_value()
+                } else {
+                    cutoff as i64
+                };
+                is_segment_expired(segment_reader, self.field, cutoff)
+            }
+            _ => is_segment_expired(segment_reader, self.field, cutoff),
+        }
+    }
+}
+
+fn is_segment_expired<Item: FastValue + PartialOrd>(
+    segment_reader: &SegmentReader,
+    field: Field,
+    cutoff: Item,
+) -> Result<bool> {
+    let fast_field_reader: FastFieldReader<Item> = segment_reader.fast_field_reader(field)?;
+    // recent documents are usually found at the end of the segment,
+    // so that the scan stops early for the segments that are not expired.
+    Ok((0..segment_reader.max_doc())
+        .rev()
+        .filter(|&doc: &DocId| !segment_reader.is_deleted(doc))
+        .all(|doc| match fast_field_reader.get_opt(doc) {
+            Some(date) => date < cutoff,
+            None => false,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use indexer::NoMergePolicy;
+    use schema::{SchemaBuilder, FAST, STRING};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+    use super::RetentionPolicy;
+
+    #[test]
+    fn test_retention_policy() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_text_field("id", STRING);
+        let date_field = schema_builder.add_i64_field("date", FAST);
+        let index = Index::create_in_ram(schema_builder.build());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs() as i64;
+        let one_day = Duration::from_secs(24 * 3_600);
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+
+        let text_policy = RetentionPolicy::new(id_field, one_day);
+        assert!(index_writer.set_retention_policy(text_policy).is_err());
+
+        // a segment of expired documents, a segment mixing expired
+        // and recent documents, and a segment of recent documents.
+        index_writer.add_document(doc!(id_field => "a", date_field => 1_000i64));
+        index_writer.add_document(doc!(id_field => "b", date_field => now - 3 * 24 * 3_600));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field => "c", date_field => 1_000i64));
+        index_writer.add_document(doc!(id_field => "d", date_field => now));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(id_field => "e", date_field => now));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 5);
+
+        index_writer
+            .set_retention_policy(RetentionPolicy::new(date_field, one_day))
+            .unwrap();
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().segment_readers().len(), 2);
+        assert_eq!(index.searcher().num_docs(), 3);
+
+        // the segment is expired once its recent document is deleted.
+        index_writer.delete_term(Term::from_field_text(id_field, "d"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().segment_readers().len(), 1);
+        let searcher = index.searcher();
+        assert_eq!(searcher.doc_freq(&Term::from_field_text(id_field, "e")), 1);
+
+        index_writer.remove_retention_policy();
+        index_writer.add_document(doc!(id_field => "f", date_field => 1_000i64));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        assert_eq!(index.searcher().num_docs(), 2);
+    }
+}
@@ -1,5 +1,6 @@
 use core::SegmentMeta;
 use bit_set::BitSet;
+use fastfield::FastFieldUpdates;
 use indexer::delete_queue::DeleteCursor;
 use core::SegmentId;
 use std::fmt;
@@ -29,6 +30,8 @@ impl SegmentState {
 /// - `delete_bitset` is a bitset describing
 /// documents that were deleted during the commit
 /// itself.
+/// - `fast_field_updates` are the fast field values
+/// that were updated during the commit itself.
 /// - `delete_cursor` is the position in the delete queue.
 /// Deletes happening before the cursor are reflected either
 /// in the .del file or in the `delete_bitset`.
@@ -37,6 +40,7 @@ pub struct SegmentEntry {
     meta: SegmentMeta,
     state: SegmentState,
     delete_bitset: Option<BitSet>,
+    fast_field_updates: Option<FastFieldUpdates>,
     delete_cursor: DeleteCursor,
 }
 
@@ -51,6 +55,7 @@ impl SegmentEntry {
             meta: segment_meta,
             state: SegmentState::Ready,
             delete_bitset,
+            fast_field_updates: None,
             delete_cursor,
         }
     }
@@ -62,6 +67,17 @@ impl SegmentEntry {
         self.delete_bitset.as_ref()
     }
 
+    /// Return a reference to the fast field values updated
+    /// during the commit, if any.
+    pub fn fast_field_updates(&self) -> Option<&FastFieldUpdates> {
+        self.fast_field_updates.as_ref()
+    }
+
+    /// Set the fast field values updated during the commit.
+    pub fn set_fast_field_updates(&mut self, fast_field_updates: FastFieldUpdates) {
+        self.fast_field_updates = Some(fast_field_updates);
+    }
+
     /// Set the `SegmentMeta` for this segment.
     pub fn set_meta(&mut self, segment_meta: SegmentMeta) {
         self.meta = segment_meta;
@@ -1,7 +1,7 @@
 use super::segment_register::SegmentRegister;
 use std::sync::RwLock;
 use core::SegmentMeta;
-use core::{LOCKFILE_FILEPATH, META_FILEPATH};
+use core::{LOCKFILE_FILEPATH, META_FILEPATH, PREVIOUS_META_FILEPATH};
 use core::SegmentId;
 use indexer::SegmentEntry;
 use std::path::PathBuf;
@@ -79,6 +79,7 @@ impl SegmentManager {
         let registers_lock = self.read();
         let mut files = HashSet::new();
         files.insert(META_FILEPATH.clone());
+        files.insert(PREVIOUS_META_FILEPATH.clone());
         files.insert(LOCKFILE_FILEPATH.clone());
 
         let segment_metas: Vec<SegmentMeta> = registers_lock
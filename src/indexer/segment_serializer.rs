@@ -2,6 +2,8 @@ use Result;
 
 use core::Segment;
 use core::SegmentComponent;
+use common::CompositeWrite;
+use directory::WritePtr;
 use fastfield::FastFieldSerializer;
 use store::StoreWriter;
 use postings::InvertedIndexSerializer;
@@ -13,12 +15,23 @@ pub struct SegmentSerializer {
     fast_field_serializer: FastFieldSerializer,
     fieldnorms_serializer: FastFieldSerializer,
     postings_serializer: InvertedIndexSerializer,
+    completions_serializer: CompositeWrite<WritePtr>,
+    term_vectors_serializer: CompositeWrite<WritePtr>,
 }
 
 impl SegmentSerializer {
     /// Creates a new `SegmentSerializer`.
     pub fn for_segment(segment: &mut Segment) -> Result<SegmentSerializer> {
         let store_write = segment.open_write(SegmentComponent::STORE)?;
+        let store_writer = {
+            let settings = segment.index().settings();
+            StoreWriter::with_layout(
+                store_write,
+                settings.docstore_compression.clone(),
+                settings.docstore_blocksize,
+                settings.docstore_layout,
+            )
+        };
 
         let fast_field_write = segment.open_write(SegmentComponent::FASTFIELDS)?;
         let fast_field_serializer = FastFieldSerializer::from_write(fast_field_write)?;
@@ -26,10 +39,15 @@ impl SegmentSerializer {
         let fieldnorms_write = segment.open_write(SegmentComponent::FIELDNORMS)?;
         let fieldnorms_serializer = FastFieldSerializer::from_write(fieldnorms_write)?;
 
+        let completions_write = segment.open_write(SegmentComponent::COMPLETIONS)?;
+        let term_vectors_write = segment.open_write(SegmentComponent::TERMVECTORS)?;
+
         let postings_serializer = InvertedIndexSerializer::open(segment)?;
         Ok(SegmentSerializer {
             postings_serializer,
-            store_writer: StoreWriter::new(store_write),
+            completions_serializer: CompositeWrite::wrap(completions_write),
+            term_vectors_serializer: CompositeWrite::wrap(term_vectors_write),
+            store_writer,
             fast_field_serializer,
             fieldnorms_serializer,
         })
@@ -55,12 +73,24 @@ impl SegmentSerializer {
         &mut self.store_writer
     }
 
+    /// Accessor to the serializer of the completion indexes.
+    pub fn get_completions_serializer(&mut self) -> &mut CompositeWrite<WritePtr> {
+        &mut self.completions_serializer
+    }
+
+    /// Accessor to the serializer of the term vectors.
+    pub fn get_term_vectors_serializer(&mut self) -> &mut CompositeWrite<WritePtr> {
+        &mut self.term_vectors_serializer
+    }
+
     /// Finalize the segment serialization.
     pub fn close(self) -> Result<()> {
         self.fast_field_serializer.close()?;
         self.postings_serializer.close()?;
         self.store_writer.close()?;
         self.fieldnorms_serializer.close()?;
+        self.completions_serializer.close()?;
+        self.term_vectors_serializer.close()?;
         Ok(())
     }
 }
@@ -1,8 +1,9 @@
 use core::Index;
-use core::IndexMeta;
-use core::META_FILEPATH;
+use core::{IndexMeta, INDEX_FORMAT_VERSION};
+use core::IndexSettings;
+use core::{META_FILEPATH, PREVIOUS_META_FILEPATH};
 use core::Segment;
-use core::SegmentId;
+use core::{SegmentId, SegmentIdSequence};
 use core::SegmentMeta;
 use core::SerializableSegment;
 use directory::Directory;
@@ -13,7 +14,7 @@ use futures::Future;
 use futures::Canceled;
 use futures::oneshot;
 use directory::FileProtection;
-use indexer::{DefaultMergePolicy, MergePolicy};
+use indexer::{DefaultMergePolicy, IndexingError, MergeHook, MergePolicy, RetentionPolicy};
 use indexer::index_writer::advance_deletes;
 use indexer::MergeCandidate;
 use indexer::merger::IndexMerger;
@@ -34,6 +35,8 @@ use std::sync::atomic::Ordering;
 use std::sync::RwLock;
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::SystemTime;
+use core::SegmentReader;
 use super::segment_manager::{get_mergeable_segments, SegmentManager};
 
 /// Save the index meta file.
@@ -45,8 +48,46 @@ use super::segment_manager::{get_mergeable_segments, SegmentManager};
 /// and flushed.
 ///
 /// This method is not part of tantivy's public API
-pub fn save_new_metas(schema: Schema, opstamp: u64, directory: &mut Directory) -> Result<()> {
-    save_metas(vec![], schema, opstamp, None, directory)
+pub fn save_new_metas(
+    schema: Schema,
+    settings: IndexSettings,
+    opstamp: u64,
+    directory: &mut Directory,
+) -> Result<()> {
+    let metas = IndexMeta {
+        segments: vec![],
+        schema,
+        settings,
+        opstamp,
+        index_format_version: INDEX_FORMAT_VERSION,
+        payload: None,
+    };
+    write_metas(&metas, directory)
+}
+
+/// Writes the meta file.
+///
+/// The directory's `atomic_write` guarantees that `meta.json`
+/// is either left untouched or entirely written.
+fn write_metas(metas: &IndexMeta, directory: &mut Directory) -> Result<()> {
+    let mut buffer = serde_json::to_vec_pretty(metas)?;
+    write!(&mut buffer, "\n")?;
+    directory.atomic_write(&META_FILEPATH, &buffer[..])?;
+    debug!("Saved metas {:?}", serde_json::to_string_pretty(metas));
+    Ok(())
+}
+
+/// Copies the current `meta.json`, if it is valid, to `meta.prev.json`.
+///
+/// The previous meta file is used as a fallback when opening an index
+/// whose `meta.json` is corrupted.
+fn backup_metas(directory: &mut Directory) -> Result<()> {
+    if let Ok(meta_data) = directory.atomic_read(&META_FILEPATH) {
+        if serde_json::from_slice::<IndexMeta>(&meta_data).is_ok() {
+            directory.atomic_write(&PREVIOUS_META_FILEPATH, &meta_data[..])?;
+        }
+    }
+    Ok(())
 }
 
 /// Save the index meta file.
@@ -57,10 +98,13 @@ pub fn save_new_metas(schema: Schema, opstamp: u64, directory: &mut Directory) -
 /// - it success, and `meta.json` is written
 /// and flushed.
 ///
+/// The former `meta.json` is kept as `meta.prev.json`.
+///
 /// This method is not part of tantivy's public API
 pub fn save_metas(
     segment_metas: Vec<SegmentMeta>,
     schema: Schema,
+    settings: IndexSettings,
     opstamp: u64,
     payload: Option<String>,
     directory: &mut Directory,
@@ -68,14 +112,13 @@ pub fn save_metas(
     let metas = IndexMeta {
         segments: segment_metas,
         schema,
+        settings,
         opstamp,
+        index_format_version: INDEX_FORMAT_VERSION,
         payload,
     };
-    let mut buffer = serde_json::to_vec_pretty(&metas)?;
-    write!(&mut buffer, "\n")?;
-    directory.atomic_write(&META_FILEPATH, &buffer[..])?;
-    debug!("Saved metas {:?}", serde_json::to_string_pretty(&metas));
-    Ok(())
+    backup_metas(directory)?;
+    write_metas(&metas, directory)
 }
 
 // The segment update runner is in charge of processing all
@@ -94,6 +137,7 @@ fn perform_merge(
 ) -> Result<SegmentEntry> {
     // first we need to apply deletes to our segment.
     info!("Start merge: {:?}", segment_ids);
+    let mut span = span!("merge", segment_ids = segment_ids);
 
     let index = &segment_updater.0.index;
     let schema = index.schema();
@@ -105,11 +149,11 @@ fn perform_merge(
         if let Some(mut segment_entry) = segment_updater.0.segment_manager.segment_entry(segment_id)
         {
             let segment = index.segment(segment_entry.meta().clone());
-            if let Some(file_protection) =
-                advance_deletes(segment, &mut segment_entry, target_opstamp)?
-            {
-                file_protections.push(file_protection);
-            }
+            file_protections.extend(advance_deletes(
+                segment,
+                &mut segment_entry,
+                target_opstamp,
+            )?);
             segment_entries.push(segment_entry);
         } else {
             error!("Error, had to abort merge as some of the segment is not managed anymore.");
@@ -129,7 +173,10 @@ fn perform_merge(
         .collect();
 
     // An IndexMerger is like a "view" of our merged segments.
-    let merger: IndexMerger = IndexMerger::open(schema, &segments[..])?;
+    let mut merger: IndexMerger = IndexMerger::open(schema, &segments[..])?;
+    if let Some(merge_hook) = segment_updater.get_merge_hook() {
+        merger.set_merge_hook(merge_hook);
+    }
 
     // ... we just serialize this index merger in our new segment
     // to merge the two segments.
@@ -140,6 +187,7 @@ fn perform_merge(
     let num_docs = merger
         .write(segment_serializer)
         .expect("Serializing merged index failed");
+    span.record("num_docs", num_docs);
     let mut segment_meta = SegmentMeta::new(merged_segment.id());
     segment_meta.set_max_doc(num_docs);
 
@@ -152,11 +200,15 @@ struct InnerSegmentUpdater {
     index: Index,
     segment_manager: SegmentManager,
     merge_policy: RwLock<Box<MergePolicy>>,
+    merge_hook: RwLock<Option<Arc<MergeHook>>>,
+    retention_policy: RwLock<Option<RetentionPolicy>>,
     merging_thread_id: AtomicUsize,
     merging_threads: RwLock<HashMap<usize, JoinHandle<Result<()>>>>,
     generation: AtomicUsize,
     killed: AtomicBool,
     stamper: Stamper,
+    // only set for deterministic writers.
+    segment_id_sequence: Option<Arc<SegmentIdSequence>>,
 }
 
 impl SegmentUpdater {
@@ -164,6 +216,7 @@ impl SegmentUpdater {
         index: Index,
         stamper: Stamper,
         delete_cursor: &DeleteCursor,
+        segment_id_sequence: Option<Arc<SegmentIdSequence>>,
     ) -> Result<SegmentUpdater> {
         let segments = index.searchable_segment_metas()?;
         let segment_manager = SegmentManager::from_segments(segments, delete_cursor);
@@ -172,16 +225,41 @@ impl SegmentUpdater {
             index,
             segment_manager,
             merge_policy: RwLock::new(box DefaultMergePolicy::default()),
+            merge_hook: RwLock::new(None),
+            retention_policy: RwLock::new(None),
             merging_thread_id: AtomicUsize::default(),
             merging_threads: RwLock::new(HashMap::new()),
             generation: AtomicUsize::default(),
             killed: AtomicBool::new(false),
             stamper,
+            segment_id_sequence,
         })))
     }
 
+    /// Returns true iff the segment updater belongs to
+    /// a deterministic writer.
+    fn is_deterministic(&self) -> bool {
+        self.0.segment_id_sequence.is_some()
+    }
+
     pub fn new_segment(&self) -> Segment {
-        let new_segment = self.0.index.new_segment();
+        let new_segment = match self.0.segment_id_sequence {
+            Some(ref segment_id_sequence) => {
+                let directory = self.0.index.directory();
+                loop {
+                    let segment_meta = SegmentMeta::new(segment_id_sequence.next());
+                    // skips the ids of the segments left behind by a rollback.
+                    let is_free = segment_meta
+                        .list_files()
+                        .iter()
+                        .all(|path| !directory.exists(path));
+                    if is_free {
+                        break self.0.index.segment(segment_meta);
+                    }
+                }
+            }
+            None => self.0.index.new_segment(),
+        };
         let segment_id = new_segment.id();
         self.0.segment_manager.write_segment(segment_id);
         new_segment
@@ -195,6 +273,22 @@ impl SegmentUpdater {
         *self.0.merge_policy.write().unwrap() = merge_policy;
     }
 
+    pub fn get_merge_hook(&self) -> Option<Arc<MergeHook>> {
+        self.0.merge_hook.read().unwrap().clone()
+    }
+
+    pub fn set_merge_hook(&self, merge_hook: Option<Arc<MergeHook>>) {
+        *self.0.merge_hook.write().unwrap() = merge_hook;
+    }
+
+    pub fn get_retention_policy(&self) -> Option<RetentionPolicy> {
+        self.0.retention_policy.read().unwrap().clone()
+    }
+
+    pub fn set_retention_policy(&self, retention_policy: Option<RetentionPolicy>) {
+        *self.0.retention_policy.write().unwrap() = retention_policy;
+    }
+
     fn get_merging_thread_id(&self) -> usize {
         self.0.merging_thread_id.fetch_add(1, Ordering::SeqCst)
     }
@@ -241,6 +335,29 @@ impl SegmentUpdater {
         Ok(segment_entries)
     }
 
+    /// Returns the metas of all of the segments (committed or uncommitted),
+    /// with deletes applied up to the target opstamp.
+    ///
+    /// Unlike a commit, the segment manager is left untouched.
+    /// The delete files that may have been written are only
+    /// protected from garbage collection as long as the
+    /// `FileProtection`s returned along with their segment are alive.
+    pub fn soft_commit_segment_metas(
+        &self,
+        opstamp: u64,
+    ) -> Result<Vec<(SegmentMeta, Vec<FileProtection>)>> {
+        self.run_async(move |segment_updater| -> Result<_> {
+            let mut segment_entries = segment_updater.0.segment_manager.segment_entries();
+            let mut segment_metas = Vec::with_capacity(segment_entries.len());
+            for segment_entry in &mut segment_entries {
+                let segment = segment_updater.0.index.segment(segment_entry.meta().clone());
+                let file_protections = advance_deletes(segment, segment_entry, opstamp)?;
+                segment_metas.push((segment_entry.meta().clone(), file_protections));
+            }
+            Ok(segment_metas)
+        }).wait()?
+    }
+
     pub fn save_metas(&self, opstamp: u64, commit_message: Option<String>) {
         if self.is_alive() {
             let index = &self.0.index;
@@ -248,6 +365,7 @@ impl SegmentUpdater {
             save_metas(
                 self.0.segment_manager.committed_segment_metas(),
                 index.schema(),
+                index.settings().clone(),
                 opstamp,
                 commit_message,
                 directory.box_clone().borrow_mut(),
@@ -263,24 +381,86 @@ impl SegmentUpdater {
 
     fn garbage_collect_files_exec(&self) {
         info!("Running garbage collection");
+        let _span = span!("garbage_collect");
         let mut index = self.0.index.clone();
         index
             .directory_mut()
             .garbage_collect(|| self.0.segment_manager.list_files());
     }
 
+    /// Removes the segments expired according to the retention policy, if any.
+    ///
+    /// Segments that cannot be read are kept.
+    fn remove_expired_segments(&self, segment_entries: &mut Vec<SegmentEntry>) {
+        let retention_policy = match self.get_retention_policy() {
+            Some(retention_policy) => retention_policy,
+            None => return,
+        };
+        let now = SystemTime::now();
+        let index = &self.0.index;
+        segment_entries.retain(|segment_entry| {
+            let segment = index.segment(segment_entry.meta().clone());
+            let is_expired = SegmentReader::open(&segment)
+                .and_then(|segment_reader| retention_policy.is_expired(&segment_reader, now));
+            match is_expired {
+                Ok(true) => {
+                    info!("Dropping expired segment {:?}", segment_entry.segment_id());
+                    false
+                }
+                Ok(false) => true,
+                Err(e) => {
+                    error!(
+                        "Failed to check the expiration of segment {:?}: {:?}",
+                        segment_entry.segment_id(),
+                        e
+                    );
+                    true
+                }
+            }
+        });
+    }
+
     pub fn commit(&self, opstamp: u64, payload: Option<String>) -> Result<()> {
         self.run_async(move |segment_updater| {
             if segment_updater.is_alive() {
-                let segment_entries = segment_updater
+                let mut segment_entries = segment_updater
                     .purge_deletes(opstamp)
                     .expect("Failed purge deletes");
+                segment_updater.remove_expired_segments(&mut segment_entries);
                 segment_updater.0.segment_manager.commit(segment_entries);
                 segment_updater.save_metas(opstamp, payload);
                 segment_updater.garbage_collect_files_exec();
                 segment_updater.consider_merge_options();
             }
-        }).wait()
+        }).wait()?;
+        if self.is_deterministic() {
+            self.merge_sequentially()?;
+        }
+        Ok(())
+    }
+
+    /// Runs the merges suggested by the merge policy one after the other,
+    /// until it does not suggest any.
+    ///
+    /// Segments are submitted to the merge policy sorted by id, so that
+    /// the merges of a deterministic writer only depend on its operations.
+    fn merge_sequentially(&self) -> Result<()> {
+        while self.is_alive() {
+            let (mut committed_segments, _) = get_mergeable_segments(&self.0.segment_manager);
+            committed_segments.sort_by_key(|segment_meta| segment_meta.id());
+            let merge_candidates = self.get_merge_policy()
+                .compute_merge_candidates(&committed_segments);
+            let segment_ids = match merge_candidates.into_iter().next() {
+                Some(MergeCandidate(ref segment_ids)) if !segment_ids.is_empty() => {
+                    segment_ids.clone()
+                }
+                _ => return Ok(()),
+            };
+            self.start_merge(&segment_ids)
+                .wait()
+                .map_err(|_| IndexingError::MergeCancelled(segment_ids.clone()))?;
+        }
+        Ok(())
     }
 
     pub fn start_merge(
@@ -352,6 +532,10 @@ impl SegmentUpdater {
     }
 
     fn consider_merge_options(&self) {
+        if self.is_deterministic() {
+            // merges are run upon commit. See `.merge_sequentially()`.
+            return;
+        }
         let (committed_segments, uncommitted_segments) =
             get_mergeable_segments(&self.0.segment_manager);
         // Committed segments cannot be merged with uncommitted_segments.
@@ -383,7 +567,7 @@ impl SegmentUpdater {
         self.run_async(move |segment_updater| {
             info!("End merge {:?}", after_merge_segment_entry.meta());
             let mut delete_cursor = after_merge_segment_entry.delete_cursor().clone();
-            let mut _file_protection_opt = None;
+            let mut _file_protections = vec![];
             if let Some(delete_operation) = delete_cursor.get() {
                 let committed_opstamp = segment_updater
                     .0
@@ -399,8 +583,8 @@ impl SegmentUpdater {
                         &mut after_merge_segment_entry,
                         committed_opstamp,
                     ) {
-                        Ok(file_protection_opt_res) => {
-                            _file_protection_opt = file_protection_opt_res;
+                        Ok(file_protections) => {
+                            _file_protections = file_protections;
                         }
                         Err(e) => {
                             error!(
@@ -462,7 +646,7 @@ impl SegmentUpdater {
                 merging_thread_handle
                     .join()
                     .map(|_| ())
-                    .map_err(|_| ErrorKind::ErrorInThread("Merging thread failed.".into()))?;
+                    .map_err(|_| IndexingError::ThreadPanicked("merging thread".into()))?;
             }
             // Our merging thread may have queued their completed
             self.run_async(move |_| {}).wait()?;
@@ -482,7 +666,41 @@ mod tests {
 
     use Index;
     use schema::*;
+    #[cfg(feature = "mmap")]
+    use core::META_FILEPATH;
     use indexer::merge_policy::tests::MergeWheneverPossible;
+    #[cfg(feature = "mmap")]
+    use directory::{Directory, MmapDirectory};
+    #[cfg(feature = "mmap")]
+    use tempdir::TempDir;
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn test_corrupted_meta_falls_back_to_previous_commit() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let schema = schema_builder.build();
+        let tempdir = TempDir::new("corrupted_meta").unwrap();
+        {
+            let index = Index::create(tempdir.path(), schema).unwrap();
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field=>"a"));
+            index_writer.commit().unwrap();
+            index_writer.add_document(doc!(text_field=>"b"));
+            index_writer.commit().unwrap();
+        }
+        {
+            // simulates a partially written `meta.json`
+            let mut directory = MmapDirectory::open(tempdir.path()).unwrap();
+            let meta_data = directory.atomic_read(&META_FILEPATH).unwrap();
+            directory
+                .atomic_write(&META_FILEPATH, &meta_data[..meta_data.len() / 2])
+                .unwrap();
+        }
+        let index = Index::open(tempdir.path()).unwrap();
+        assert_eq!(index.load_metas().unwrap().opstamp, 1u64);
+        assert_eq!(index.searcher().num_docs(), 1);
+    }
 
     #[test]
     fn test_delete_during_merge() {
@@ -1,6 +1,5 @@
 use Result;
 use DocId;
-use std::io;
 use std::str;
 use schema::Schema;
 use schema::Term;
@@ -9,6 +8,7 @@ use core::SerializableSegment;
 use fastfield::FastFieldsWriter;
 use schema::Field;
 use schema::FieldType;
+use schema::DEFAULT_POSITION_GAP;
 use indexer::segment_serializer::SegmentSerializer;
 use std::collections::HashMap;
 use datastruct::stacker::Heap;
@@ -19,6 +19,8 @@ use tokenizer::BoxedTokenizer;
 use tokenizer::FacetTokenizer;
 use tokenizer::{TokenStream, Tokenizer};
 use schema::Value;
+use completion::CompletionsWriter;
+use termvector::{RecordingTokenStream, TermVectorsWriter};
 
 /// A `SegmentWriter` is in charge of creating segment index from a
 /// documents.
@@ -32,6 +34,8 @@ pub struct SegmentWriter<'a> {
     segment_serializer: SegmentSerializer,
     fast_field_writers: FastFieldsWriter,
     fieldnorms_writer: FastFieldsWriter,
+    completions_writer: CompletionsWriter,
+    term_vectors_writer: TermVectorsWriter,
     doc_opstamps: Vec<u64>,
     tokenizers: Vec<Option<Box<BoxedTokenizer>>>,
 }
@@ -86,6 +90,8 @@ impl<'a> SegmentWriter<'a> {
             fieldnorms_writer: create_fieldnorms_writer(schema),
             segment_serializer,
             fast_field_writers: FastFieldsWriter::from_schema(schema),
+            completions_writer: CompletionsWriter::from_schema(schema),
+            term_vectors_writer: TermVectorsWriter::from_schema(schema),
             doc_opstamps: Vec::with_capacity(1_000),
             tokenizers,
         })
@@ -100,6 +106,8 @@ impl<'a> SegmentWriter<'a> {
             &self.multifield_postings,
             &self.fast_field_writers,
             &self.fieldnorms_writer,
+            &self.completions_writer,
+            &self.term_vectors_writer,
             self.segment_serializer,
         )?;
         Ok(self.doc_opstamps)
@@ -126,12 +134,17 @@ impl<'a> SegmentWriter<'a> {
     /// Indexes a new document
     ///
     /// As a user, you should rather use `IndexWriter`'s add_document.
-    pub fn add_document(&mut self, add_operation: AddOperation, schema: &Schema) -> io::Result<()> {
+    ///
+    /// Returns an `InvalidArgument` error, and leaves the segment untouched,
+    /// if a vector of the document does not have the dimension of its field.
+    pub fn add_document(&mut self, add_operation: AddOperation, schema: &Schema) -> Result<()> {
         let doc_id = self.max_doc;
         let mut doc = add_operation.document;
+        // first, so that a rejected document is not partially indexed.
+        self.fast_field_writers.add_document(&doc)?;
         self.doc_opstamps.push(add_operation.opstamp);
 
-        self.fast_field_writers.add_document(&doc);
+        self.completions_writer.add_document(&doc);
 
         for (field, field_values) in doc.get_sorted_field_values() {
             let field_options = schema.get_field_entry(field);
@@ -168,7 +181,8 @@ impl<'a> SegmentWriter<'a> {
                         }
                     }
                 }
-                FieldType::Str(_) => {
+                FieldType::Str(ref text_options) => {
+                    let mut term_ids = Vec::new();
                     let num_tokens = if let Some(ref mut tokenizer) =
                         self.tokenizers[field.0 as usize]
                     {
@@ -182,9 +196,37 @@ impl<'a> SegmentWriter<'a> {
                         if texts.is_empty() {
                             0
                         } else {
-                            let mut token_stream = tokenizer.token_stream_texts(&texts[..]);
-                            self.multifield_postings
-                                .index_text(doc_id, field, &mut token_stream)
+                            let position_gap = text_options
+                                .get_indexing_options()
+                                .map(|indexing_options| indexing_options.position_gap())
+                                .unwrap_or(DEFAULT_POSITION_GAP);
+                            let mut token_stream =
+                                tokenizer.token_stream_texts_with_gap(&texts[..], position_gap);
+                            let term_ids_opt = if text_options.is_fast() {
+                                Some(&mut term_ids)
+                            } else {
+                                None
+                            };
+                            if self.term_vectors_writer.has_field(field) {
+                                let mut recording_token_stream =
+                                    RecordingTokenStream::new(&mut token_stream);
+                                let num_tokens = self.multifield_postings.index_text(
+                                    doc_id,
+                                    field,
+                                    &mut recording_token_stream,
+                                    term_ids_opt,
+                                );
+                                self.term_vectors_writer
+                                    .add_tokens(field, recording_token_stream.tokens())?;
+                                num_tokens
+                            } else {
+                                self.multifield_postings.index_text(
+                                    doc_id,
+                                    field,
+                                    &mut token_stream,
+                                    term_ids_opt,
+                                )
+                            }
                         }
                     } else {
                         0
@@ -194,6 +236,14 @@ impl<'a> SegmentWriter<'a> {
                         .map(|field_norms_writer| {
                             field_norms_writer.add_val(u64::from(num_tokens))
                         });
+                    if !term_ids.is_empty() {
+                        let multivalue_writer = self.fast_field_writers
+                            .get_multivalue_writer(field)
+                            .expect("multivalued writer for str fast field missing");
+                        for term_id in term_ids {
+                            multivalue_writer.add_val(term_id);
+                        }
+                    }
                 }
                 FieldType::U64(ref int_option) => {
                     if int_option.is_indexed() {
@@ -217,9 +267,13 @@ impl<'a> SegmentWriter<'a> {
                         }
                     }
                 }
+                FieldType::Bytes | FieldType::Vector(_) => {
+                    // Do nothing. Bytes and vectors only support fast fields.
+                }
             }
         }
         self.fieldnorms_writer.fill_val_up_to(doc_id);
+        self.term_vectors_writer.end_document();
         doc.filter_fields(|field| schema.get_field_entry(field).is_stored());
         let doc_writer = self.segment_serializer.get_store_writer();
         doc_writer.store(&doc)?;
@@ -253,11 +307,15 @@ fn write(
     multifield_postings: &MultiFieldPostingsWriter,
     fast_field_writers: &FastFieldsWriter,
     fieldnorms_writer: &FastFieldsWriter,
+    completions_writer: &CompletionsWriter,
+    term_vectors_writer: &TermVectorsWriter,
     mut serializer: SegmentSerializer,
 ) -> Result<()> {
     let term_ord_map = multifield_postings.serialize(serializer.get_postings_serializer())?;
     fast_field_writers.serialize(serializer.get_fast_field_serializer(), &term_ord_map)?;
     fieldnorms_writer.serialize(serializer.get_fieldnorms_serializer(), &HashMap::new())?;
+    completions_writer.serialize(serializer.get_completions_serializer())?;
+    term_vectors_writer.serialize(serializer.get_term_vectors_serializer())?;
     serializer.close()?;
 
     Ok(())
@@ -270,6 +328,8 @@ impl<'a> SerializableSegment for SegmentWriter<'a> {
             &self.multifield_postings,
             &self.fast_field_writers,
             &self.fieldnorms_writer,
+            &self.completions_writer,
+            &self.term_vectors_writer,
             serializer,
         )?;
         Ok(max_doc)
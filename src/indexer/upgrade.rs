@@ -0,0 +1,249 @@
+use Result;
+use core::{Index, SegmentId, INDEX_FORMAT_VERSION};
+use futures::Future;
+use indexer::{IndexingError, NoMergePolicy};
+use indexer::index_writer::HEAP_SIZE_LIMIT;
+
+/// Rewrites the segments of an index written in an older format
+/// in the current `INDEX_FORMAT_VERSION`.
+///
+/// Segments down to `MIN_READABLE_FORMAT_VERSION` remain readable, so that
+/// upgrading is not required to open an index. This includes the segments
+/// written before format versions were recorded, at version `0`.
+/// Upgrading them before `MIN_READABLE_FORMAT_VERSION` is raised
+/// in a later release avoids having to reindex the documents.
+///
+/// Every outdated segment is merged on its own, so that the number of
+/// segments of the index is unchanged, and its deleted documents are
+/// purged along the way. The segments are merged one after the other,
+/// by an `IndexWriter` acquiring the lock of the index.
+///
+/// Returns the number of segments that were upgraded.
+pub fn upgrade_index(index: &Index) -> Result<usize> {
+    let metas = index.load_metas()?;
+    let outdated_segment_ids: Vec<SegmentId> = metas
+        .segments
+        .iter()
+        .filter(|segment_meta| segment_meta.format_version() < INDEX_FORMAT_VERSION)
+        .map(|segment_meta| segment_meta.id())
+        .collect();
+    if outdated_segment_ids.is_empty() {
+        return Ok(0);
+    }
+    let mut index_writer = index.writer_with_num_threads(1, HEAP_SIZE_LIMIT as usize)?;
+    index_writer.set_merge_policy(box NoMergePolicy);
+    for &segment_id in &outdated_segment_ids {
+        info!("Upgrading segment {:?}", segment_id);
+        index_writer
+            .merge(&[segment_id])
+            .wait()
+            .map_err(|_| IndexingError::MergeCancelled(vec![segment_id]))?;
+    }
+    index_writer.wait_merging_threads()?;
+    Ok(outdated_segment_ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use DocAddress;
+    use Index;
+    use collector::CountCollector;
+    use common::{BinarySerializable, CompositeFile, CompositeWrite};
+    use common::bitpacker::BitPacker;
+    use common::compute_num_bits;
+    use core::{SegmentComponent, INDEX_FORMAT_VERSION, MANAGED_FILEPATH, META_FILEPATH};
+    use directory::{Directory, RAMDirectory, ReadOnlySource};
+    use docset::{DocSet, SkipResult};
+    use fastfield::FastFieldReader;
+    use postings::{remove_skip_entries, Postings, BLOCKS_CODEC};
+    use query::TermQuery;
+    use schema::{Field, IndexRecordOption, Schema, SchemaBuilder, Term, FAST, STORED, TEXT};
+    use serde_json;
+    use std::io::Write;
+    use std::path::Path;
+    use super::upgrade_index;
+
+    const NUM_DOCS: u32 = 4_096;
+
+    // Rewrites the columns of a composite file, for the fields of `schema`.
+    fn rewrite_composite_file<F>(source: &ReadOnlySource, schema: &Schema, rewrite: F) -> Vec<u8>
+    where
+        F: Fn(ReadOnlySource) -> Vec<u8>,
+    {
+        let composite_file = CompositeFile::open(source).unwrap();
+        let path = Path::new("composite");
+        let mut directory = RAMDirectory::create();
+        {
+            let mut composite_write = CompositeWrite::wrap(directory.open_write(path).unwrap());
+            for field_id in 0..schema.fields().len() {
+                let field = Field(field_id as u32);
+                if let Some(column) = composite_file.open_read(field) {
+                    composite_write
+                        .for_field(field)
+                        .write_all(&rewrite(column))
+                        .unwrap();
+                }
+            }
+            composite_write.close().unwrap();
+        }
+        directory.open_read(path).unwrap().as_slice().to_vec()
+    }
+
+    // Bitpacks the `num_vals` values of a fast field column,
+    // after their minimum value and their amplitude.
+    fn legacy_column(column: ReadOnlySource, num_vals: u32) -> Vec<u8> {
+        let fast_field_reader: FastFieldReader<u64> = FastFieldReader::open(column).unwrap();
+        let vals: Vec<u64> = (0..num_vals).map(|doc| fast_field_reader.get(doc)).collect();
+        let min_value = vals.iter().cloned().min().unwrap_or(0u64);
+        let amplitude = vals.iter().cloned().max().unwrap_or(0u64) - min_value;
+        let num_bits = compute_num_bits(amplitude);
+        let mut data = Vec::new();
+        min_value.serialize(&mut data).unwrap();
+        amplitude.serialize(&mut data).unwrap();
+        let mut bit_packer = BitPacker::new();
+        for val in vals {
+            bit_packer.write(val - min_value, num_bits, &mut data).unwrap();
+        }
+        bit_packer.close(&mut data).unwrap();
+        data
+    }
+
+    // Removes the codec and the skip entries preceding the blocks
+    // of the single posting list of a field, made of two full blocks.
+    fn legacy_postings(postings: ReadOnlySource) -> Vec<u8> {
+        let data = postings.as_slice();
+        assert_eq!(data[0], BLOCKS_CODEC);
+        remove_skip_entries(&data[1..], 2, true)
+    }
+
+    // Creates an index made of a single segment written in the format of
+    // version `0`, before format versions were recorded: its files do not
+    // have any checksum footer, its posting lists do not have any skip entries,
+    // and its fast fields and field norms do not have any codec id.
+    //
+    // The term `a` of the text field is in every 16th document, so that its
+    // posting list is made of two full blocks, and the `num` fast field
+    // holds the id of each document.
+    fn create_v0_index(schema: &Schema, text_field: Field, num_field: Field) -> Index {
+        let index = Index::create_in_ram(schema.clone());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for doc_id in 0..NUM_DOCS {
+                if doc_id % 16 == 0 {
+                    index_writer.add_document(doc!(text_field => "a", num_field => doc_id as u64));
+                } else {
+                    index_writer.add_document(doc!(num_field => doc_id as u64));
+                }
+            }
+            index_writer.commit().unwrap();
+            index_writer.wait_merging_threads().unwrap();
+        }
+        let mut directory = RAMDirectory::create();
+        let metas = index.load_metas().unwrap();
+        for segment_meta in &metas.segments {
+            let max_doc = segment_meta.max_doc();
+            for &component in SegmentComponent::iterator() {
+                let path = segment_meta.relative_path(component);
+                // `open_read` removes the footer of the file.
+                let source = match index.directory().open_read(&path) {
+                    Ok(source) => source,
+                    Err(_) => continue,
+                };
+                let data = match component {
+                    SegmentComponent::POSTINGS => {
+                        rewrite_composite_file(&source, schema, legacy_postings)
+                    }
+                    SegmentComponent::FASTFIELDS | SegmentComponent::FIELDNORMS => {
+                        rewrite_composite_file(&source, schema, |column| {
+                            legacy_column(column, max_doc)
+                        })
+                    }
+                    SegmentComponent::COMPLETIONS | SegmentComponent::TERMVECTORS => continue,
+                    _ => source.as_slice().to_vec(),
+                };
+                directory.atomic_write(&path, &data).unwrap();
+            }
+        }
+        let managed_data = index.directory().atomic_read(&MANAGED_FILEPATH).unwrap();
+        directory.atomic_write(&MANAGED_FILEPATH, &managed_data).unwrap();
+        let meta_data = index.directory().atomic_read(&META_FILEPATH).unwrap();
+        let mut meta_json: serde_json::Value = serde_json::from_slice(&meta_data).unwrap();
+        meta_json
+            .as_object_mut()
+            .unwrap()
+            .remove("index_format_version");
+        for segment_json in meta_json["segments"].as_array_mut().unwrap() {
+            segment_json
+                .as_object_mut()
+                .unwrap()
+                .remove("format_version");
+        }
+        let meta_data = serde_json::to_vec(&meta_json).unwrap();
+        directory.atomic_write(&META_FILEPATH, &meta_data).unwrap();
+        Index::open_directory(directory).unwrap()
+    }
+
+    fn check_index(index: &Index, text_field: Field, num_field: Field) {
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.num_docs(), NUM_DOCS);
+        let term = Term::from_field_text(text_field, "a");
+        {
+            let term_query = TermQuery::new(term.clone(), IndexRecordOption::WithFreqsAndPositions);
+            let mut count_collector = CountCollector::default();
+            searcher.search(&term_query, &mut count_collector).unwrap();
+            assert_eq!(count_collector.count(), (NUM_DOCS / 16) as usize);
+        }
+        let segment_reader = searcher.segment_reader(0);
+        {
+            let mut postings = segment_reader
+                .inverted_index(text_field)
+                .read_postings(&term, IndexRecordOption::WithFreqs)
+                .unwrap()
+                .unwrap();
+            assert_eq!(postings.skip_next(1_000), SkipResult::OverStep);
+            assert_eq!(postings.doc(), 1_008);
+            assert_eq!(postings.term_freq(), 1);
+            assert_eq!(postings.skip_next(NUM_DOCS - 16), SkipResult::Reached);
+            assert!(!postings.advance());
+        }
+        let fast_field_reader: FastFieldReader<u64> =
+            segment_reader.fast_field_reader(num_field).unwrap();
+        for doc_id in 0..NUM_DOCS {
+            assert_eq!(fast_field_reader.get(doc_id), u64::from(doc_id));
+        }
+        let fieldnorms_reader = segment_reader.get_fieldnorms_reader(text_field).unwrap();
+        assert_eq!(fieldnorms_reader.get(32), 1);
+        assert_eq!(fieldnorms_reader.get(33), 0);
+        let doc = searcher.doc(&DocAddress(0, 32)).unwrap();
+        assert_eq!(doc.get_first(text_field).unwrap().text(), "a");
+    }
+
+    #[test]
+    fn test_upgrade_v0_index() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT | STORED);
+        let num_field = schema_builder.add_u64_field("num", FAST);
+        let schema = schema_builder.build();
+        let index = create_v0_index(&schema, text_field, num_field);
+        let metas = index.load_metas().unwrap();
+        assert_eq!(metas.index_format_version, 0);
+        assert_eq!(metas.segments.len(), 1);
+        assert_eq!(metas.segments[0].format_version(), 0);
+
+        // the segment is readable without being upgraded.
+        check_index(&index, text_field, num_field);
+
+        assert_eq!(upgrade_index(&index).unwrap(), 1);
+        let metas = index.load_metas().unwrap();
+        assert_eq!(metas.index_format_version, INDEX_FORMAT_VERSION);
+        assert_eq!(metas.segments.len(), 1);
+        assert_eq!(metas.segments[0].format_version(), INDEX_FORMAT_VERSION);
+        assert_eq!(metas.segments[0].max_doc(), NUM_DOCS);
+        check_index(&index, text_field, num_field);
+
+        // the index is now up to date.
+        assert_eq!(upgrade_index(&index).unwrap(), 0);
+    }
+}
@@ -7,6 +7,7 @@
 #![feature(collections_range)]
 #![feature(integer_atomics)]
 #![feature(drain_filter)]
+#![cfg_attr(feature = "runtime-simd", feature(stdsimd))]
 #![cfg_attr(test, feature(test))]
 #![cfg_attr(test, feature(iterator_step_by))]
 #![doc(test(attr(allow(unused_variables), deny(warnings))))]
@@ -129,16 +130,18 @@ extern crate log;
 #[macro_use]
 extern crate error_chain;
 
-extern crate atomicwrites;
 extern crate bit_set;
 extern crate byteorder;
 extern crate chan;
 extern crate combine;
+extern crate crc;
 extern crate crossbeam;
 extern crate fst;
 extern crate futures;
 extern crate futures_cpupool;
 extern crate itertools;
+extern crate libc;
+#[cfg(feature = "lz4-compression")]
 extern crate lz4;
 extern crate num_cpus;
 extern crate owning_ref;
@@ -147,6 +150,7 @@ extern crate rust_stemmers;
 extern crate serde;
 extern crate serde_json;
 extern crate stable_deref_trait;
+#[cfg(any(test, feature = "mmap"))]
 extern crate tempdir;
 extern crate tempfile;
 extern crate time;
@@ -159,12 +163,12 @@ extern crate matches;
 #[cfg(test)]
 extern crate env_logger;
 
-#[cfg(feature = "simdcompression")]
-extern crate libc;
-
 #[cfg(windows)]
 extern crate winapi;
 
+#[cfg(feature = "zstd-compression")]
+extern crate zstd;
+
 #[cfg(test)]
 extern crate rand;
 #[cfg(test)]
@@ -181,7 +185,7 @@ mod functional_test;
 #[macro_use]
 mod macros;
 
-pub use error::{Error, ErrorKind, ResultExt};
+pub use error::{DataCorruption, Error, ErrorKind, ResultExt};
 
 /// Tantivy result.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -195,6 +199,8 @@ mod common;
 mod error;
 pub mod tokenizer;
 mod datastruct;
+mod completion;
+mod termvector;
 
 pub mod termdict;
 pub mod store;
@@ -204,15 +210,30 @@ pub mod collector;
 pub mod postings;
 pub mod schema;
 pub mod fastfield;
+pub mod snippet;
 
 mod docset;
 pub use self::docset::{DocSet, SkipResult};
 
 pub use directory::Directory;
-pub use core::{Index, Searcher, Segment, SegmentId, SegmentMeta};
-pub use indexer::IndexWriter;
+pub use core::{AsyncSearcher, FilterCache, Index, IndexReader, IndexReaderBuilder, IndexSettings,
+               IndexSnapshot, MemoryBudget, ReloadPolicy, Searcher, Segment, SegmentId,
+               SegmentMeta, Warmer};
+pub use indexer::{AutoCommitPolicy, IndexWriter, IndexWriterHandle, ReindexProgress, Reindexer};
+pub use indexer::{upgrade_index, IndexingError, MultiIndexWriter, RetentionPolicy};
 pub use schema::{Document, Term};
-pub use core::{InvertedIndexReader, SegmentReader};
+pub use store::LazyDocument;
+pub use core::{FieldStatistics, InvertedIndexReader, SegmentReader, TermPostingsStream};
+pub use core::{ComponentInfo, IndexInfo, SegmentInfo, TermSuggestion};
+pub use core::{FieldTermStream, FieldTerms};
+pub use core::{ExportedDoc, FastFieldExport};
+pub use core::{SearchPermissions, StatisticsProvider, TermStatistics};
+pub use core::{ReplicatedFile, ReplicationManifest};
+pub use core::{ComponentSpaceUsage, FieldSpaceUsage, SegmentSpaceUsage, SpaceUsage};
+pub use core::check_crash_consistency;
+pub use core::{INDEX_FORMAT_VERSION, MIN_READABLE_FORMAT_VERSION};
+pub use completion::Completion;
+pub use termvector::{TermVector, TermVectorEntry};
 pub use self::common::TimerTree;
 
 pub use postings::Postings;
@@ -236,6 +257,7 @@ pub mod merge_policy {
     pub use indexer::LogMergePolicy;
     pub use indexer::NoMergePolicy;
     pub use indexer::DefaultMergePolicy;
+    pub use indexer::MergeHook;
 }
 
 /// A `u32` identifying a document within a segment.
@@ -326,6 +348,7 @@ mod tests {
         sample_with_seed(n, ratio, 4)
     }
 
+    #[cfg(feature = "mmap")]
     #[test]
     fn test_indexing() {
         let mut schema_builder = SchemaBuilder::default();
@@ -482,11 +505,13 @@ mod tests {
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             {
                 let mut postings = inverted_index
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 5);
@@ -495,6 +520,7 @@ mod tests {
             {
                 let mut postings = inverted_index
                     .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 3);
@@ -526,11 +552,13 @@ mod tests {
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             {
                 let mut postings = inverted_index
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 5);
@@ -539,6 +567,7 @@ mod tests {
             {
                 let mut postings = inverted_index
                     .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 3);
@@ -569,17 +598,20 @@ mod tests {
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             {
                 let mut postings = inverted_index
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(!postings.advance());
             }
             {
                 let mut postings = inverted_index
                     .read_postings(&term_b, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 3);
@@ -590,6 +622,7 @@ mod tests {
             {
                 let mut postings = inverted_index
                     .read_postings(&term_c, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert!(postings.advance());
                 assert_eq!(postings.doc(), 4);
@@ -615,6 +648,7 @@ mod tests {
             .segment_reader(0)
             .inverted_index(term.field())
             .read_postings(&term, IndexRecordOption::Basic)
+            .unwrap()
             .unwrap();
         assert!(postings.advance());
         assert_eq!(postings.doc(), 0);
@@ -639,6 +673,7 @@ mod tests {
             .segment_reader(0)
             .inverted_index(term.field())
             .read_postings(&term, IndexRecordOption::Basic)
+            .unwrap()
             .unwrap();
         assert!(postings.advance());
         assert_eq!(postings.doc(), 0);
@@ -705,11 +740,13 @@ mod tests {
             assert!(
                 inverted_index
                     .read_postings(&term_abcd, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .is_none()
             );
             let term_af = Term::from_field_text(text_field, "af");
             let mut postings = inverted_index
                 .read_postings(&term_af, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
                 .unwrap();
             assert!(postings.advance());
             assert_eq!(postings.doc(), 0);
@@ -2,6 +2,27 @@ macro_rules! get(
     ($e:expr) => (match $e { Some(e) => e, None => return None })
 );
 
+/// Enters a `Span`, timing an operation until it is dropped.
+///
+/// ```ignore
+/// let mut span = span!("merge", num_segments = segment_ids.len());
+/// span.record("num_docs", num_docs);
+/// ```
+#[cfg(feature = "instrumentation")]
+macro_rules! span(
+    ($name:expr $(, $key:ident = $value:expr)*) => (
+        $crate::common::Span::enter($name, vec![$((stringify!($key), format!("{:?}", $value))),*])
+    )
+);
+
+#[cfg(not(feature = "instrumentation"))]
+macro_rules! span(
+    ($name:expr $(, $key:ident = $value:expr)*) => ({
+        $(let _ = &$value;)*
+        $crate::common::Span::disabled()
+    })
+);
+
 /// `doc!` is a shortcut that helps building `Document`
 /// objects.
 ///
@@ -0,0 +1,324 @@
+use byteorder::ByteOrder;
+use std::cmp;
+use common::{BitSet, Endianness, TinySet};
+use compression::{BlockEncoder, VIntEncoder, COMPRESSION_BLOCK_SIZE};
+use directory::SourceRead;
+use error::DataCorruption;
+use std::io::{self, Write};
+use postings::skip::skip_data_num_bytes;
+use DocId;
+
+/// Posting lists with at least `COMPRESSION_BLOCK_SIZE` documents
+/// start with a byte identifying how their documents are encoded.
+///
+/// `BLOCKS_CODEC` stands for the delta-encoded and bitpacked blocks
+/// (see `SkipSerializer`), while `BITSET_CODEC` stands for a bitset
+/// (see `BitSetDocs`), preceded by the blocks of term frequencies.
+pub(crate) const BLOCKS_CODEC: u8 = 0u8;
+pub(crate) const BITSET_CODEC: u8 = 1u8;
+
+/// Returns the number of bytes taken by the sorted `docs` encoded as a bitset.
+fn bitset_num_bytes(docs: &[DocId]) -> usize {
+    let num_buckets = docs.last().map(|&last_doc| last_doc as usize / 64 + 1).unwrap_or(0);
+    num_buckets * 8
+}
+
+/// Returns true iff the sorted `docs` take fewer bytes encoded
+/// as a bitset than as blocks, along with the skip entries of the blocks.
+///
+/// The term frequencies are encoded the same way in both cases.
+/// The codec of a term is chosen from the documents of its first block,
+/// so that its posting list does not have to be buffered.
+pub(crate) fn is_dense(docs: &[DocId], has_freqs: bool, block_encoder: &mut BlockEncoder) -> bool {
+    let bitset_num_bytes = bitset_num_bytes(docs);
+    let num_blocks = docs.len() / COMPRESSION_BLOCK_SIZE;
+    let skip_num_bytes = skip_data_num_bytes(num_blocks, has_freqs);
+    // a block takes at most 4 bytes per document and its number of bits,
+    // and a variable byte encoded document at most 5 bytes.
+    if bitset_num_bytes > skip_num_bytes + num_blocks + docs.len() * 5 {
+        return false;
+    }
+    let mut blocks_num_bytes = skip_num_bytes;
+    let mut offset = 0u32;
+    for block_docs in docs.chunks(COMPRESSION_BLOCK_SIZE) {
+        blocks_num_bytes += if block_docs.len() == COMPRESSION_BLOCK_SIZE {
+            block_encoder.compress_block_sorted(block_docs, offset).len()
+        } else {
+            block_encoder.compress_vint_sorted(block_docs, offset).len()
+        };
+        if blocks_num_bytes > bitset_num_bytes {
+            return true;
+        }
+        offset = block_docs[block_docs.len() - 1];
+    }
+    false
+}
+
+/// Bitset of the documents of a posting list,
+/// built as the documents are pushed.
+///
+/// The bitset is made of its buckets, as `u64`, up to the bucket of its last
+/// document. The bucket `i` contains the documents from `i * 64` to `(i + 1) * 64`.
+/// The number of buckets is not recorded: it is given by the number of
+/// documents of the posting list, see `BitSetDocs::open`.
+///
+/// It takes at most one bit per document of the segment,
+/// however long the posting list is.
+#[derive(Default)]
+pub(crate) struct BitSetDocsWriter {
+    buckets: Vec<u64>,
+}
+
+impl BitSetDocsWriter {
+    /// Inserts a document, greater or equal to the documents inserted before.
+    pub fn insert(&mut self, doc: DocId) {
+        let bucket = (doc / 64) as usize;
+        if bucket >= self.buckets.len() {
+            self.buckets.resize(bucket + 1, 0u64);
+        }
+        self.buckets[bucket] |= 1u64 << (doc % 64);
+    }
+
+    /// Writes the buckets of the bitset.
+    pub fn write<W: Write>(&self, output: &mut W) -> io::Result<()> {
+        let mut buffer = [0u8; 8];
+        for &bucket in &self.buckets {
+            Endianness::write_u64(&mut buffer, bucket);
+            output.write_all(&buffer)?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+    }
+}
+
+// Number of buckets covering all of the `DocId`s.
+const MAX_NUM_BUCKETS: u32 = (std::u32::MAX / 64) + 1;
+
+/// Cursor over the documents of a posting list encoded as a bitset.
+pub(crate) struct BitSetDocs {
+    buckets: SourceRead,
+    num_buckets: u32,
+    // bucket of the documents in `current`.
+    bucket: u32,
+    // documents of the current bucket that have not been consumed yet.
+    current: TinySet,
+}
+
+impl BitSetDocs {
+    /// Opens the bitset of the `doc_freq` documents at the beginning
+    /// of `data`, and advances `data` to the end of the bitset.
+    ///
+    /// The bitset ends with the bucket holding its `doc_freq`-th document.
+    /// Returns a `DataCorruption` error if the bitset does not fit in `data`,
+    /// or if its last bucket holds more documents than `doc_freq`.
+    pub fn open(data: &mut SourceRead, doc_freq: usize) -> io::Result<BitSetDocs> {
+        let mut num_buckets = 0u32;
+        let mut num_docs = 0usize;
+        {
+            let mut buckets_data: &[u8] = data.as_ref();
+            while num_docs < doc_freq {
+                if buckets_data.len() < 8 || num_buckets >= MAX_NUM_BUCKETS {
+                    let comment = "Postings bitset is truncated.";
+                    return Err(DataCorruption::comment_only(comment).into());
+                }
+                num_docs += Endianness::read_u64(buckets_data).count_ones() as usize;
+                buckets_data = &buckets_data[8..];
+                num_buckets += 1;
+            }
+        }
+        if num_docs != doc_freq {
+            return Err(DataCorruption::comment_only(format!(
+                "Postings bitset has {} documents instead of {}.",
+                num_docs, doc_freq
+            )).into());
+        }
+        let buckets = data.clone();
+        data.advance(num_buckets as usize * 8);
+        let mut bitset_docs = BitSetDocs {
+            buckets,
+            num_buckets,
+            bucket: 0,
+            current: TinySet::empty(),
+        };
+        bitset_docs.current = bitset_docs.read_bucket(0);
+        Ok(bitset_docs)
+    }
+
+    fn read_bucket(&self, bucket: u32) -> TinySet {
+        if bucket < self.num_buckets {
+            let start = bucket as usize * 8;
+            TinySet::from(Endianness::read_u64(&self.buckets.as_ref()[start..]))
+        } else {
+            TinySet::empty()
+        }
+    }
+
+    /// Returns the remaining documents of the bucket `bucket`,
+    /// or `None` if it is past the last bucket of the bitset.
+    pub fn remaining_bucket(&self, bucket: u32) -> Option<TinySet> {
+        if bucket >= self.num_buckets {
+            None
+        } else if bucket < self.bucket {
+            Some(TinySet::empty())
+        } else if bucket == self.bucket {
+            Some(self.current)
+        } else {
+            Some(self.read_bucket(bucket))
+        }
+    }
+
+    // Moves to the next non-empty bucket.
+    //
+    // Returns false if there are no remaining documents.
+    fn fill_current(&mut self) -> bool {
+        while self.current.is_empty() {
+            if self.bucket + 1 >= self.num_buckets {
+                return false;
+            }
+            self.bucket += 1;
+            self.current = self.read_bucket(self.bucket);
+        }
+        true
+    }
+
+    /// Writes the next `output.len()` documents in `output`.
+    ///
+    /// The number of documents of the bitset is checked when it is opened,
+    /// so that it cannot run out of documents as long as no more than
+    /// `doc_freq` documents are read. Past them, `output` is left untouched.
+    pub fn read(&mut self, output: &mut [DocId]) {
+        for doc in output.iter_mut() {
+            if !self.fill_current() {
+                return;
+            }
+            if let Some(lowest) = self.current.pop_lowest() {
+                *doc = self.bucket * 64 + lowest;
+            }
+        }
+    }
+
+    /// Skips the next `num_docs` documents.
+    pub fn skip(&mut self, mut num_docs: usize) {
+        while num_docs > 0 && self.fill_current() {
+            let len = self.current.len() as usize;
+            if len <= num_docs {
+                num_docs -= len;
+                self.current.clear();
+            } else {
+                for _ in 0..num_docs {
+                    self.current.pop_lowest();
+                }
+                num_docs = 0;
+            }
+        }
+    }
+
+    /// Returns the number of remaining documents strictly lower than `target`.
+    pub fn count_lower_than(&self, target: DocId) -> usize {
+        let target_bucket = target / 64;
+        if target_bucket < self.bucket {
+            return 0;
+        }
+        if target_bucket == self.bucket {
+            return self.current
+                .intersect(TinySet::range_lower(target % 64))
+                .len() as usize;
+        }
+        let mut count = self.current.len() as usize;
+        for bucket in self.bucket + 1..cmp::min(target_bucket, self.num_buckets) {
+            count += self.read_bucket(bucket).len() as usize;
+        }
+        count + self.read_bucket(target_bucket)
+            .intersect(TinySet::range_lower(target % 64))
+            .len() as usize
+    }
+
+    /// Inserts all of the remaining documents in `bitset`,
+    /// and consumes them.
+    pub fn append_to_bitset(&mut self, bitset: &mut BitSet) {
+        while self.fill_current() {
+            bitset.insert_mask(self.bucket, self.current.into());
+            self.current.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use directory::ReadOnlySource;
+
+    fn write_bitset(docs: &[DocId]) -> Vec<u8> {
+        let mut bitset_writer = BitSetDocsWriter::default();
+        for &doc in docs {
+            bitset_writer.insert(doc);
+        }
+        let mut data = Vec::new();
+        bitset_writer.write(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_bitset_docs() {
+        let docs: Vec<DocId> = (0..1_000u32).filter(|doc| doc % 3 != 1).collect();
+        let mut data = write_bitset(&docs);
+        data.extend_from_slice(b"tail");
+        let mut source = SourceRead::from(ReadOnlySource::from(data));
+        let mut bitset_docs = BitSetDocs::open(&mut source, docs.len()).unwrap();
+        assert_eq!(source.as_ref(), b"tail");
+
+        assert_eq!(bitset_docs.count_lower_than(0), 0);
+        assert_eq!(bitset_docs.count_lower_than(200), 133);
+        let mut output = [0u32; 10];
+        bitset_docs.read(&mut output);
+        assert_eq!(&output[..], &docs[..10]);
+        bitset_docs.skip(100);
+        assert_eq!(bitset_docs.count_lower_than(200), 23);
+        bitset_docs.read(&mut output);
+        assert_eq!(&output[..], &docs[110..120]);
+
+        let mut bitset = BitSet::with_max_value(1_000);
+        bitset_docs.append_to_bitset(&mut bitset);
+        assert_eq!(bitset.len(), docs.len() - 120);
+        assert!(!bitset.contains(docs[119]));
+        assert!(bitset.contains(docs[120]));
+        assert!(bitset.contains(999));
+    }
+
+    #[test]
+    fn test_bitset_docs_corrupted() {
+        let docs: Vec<DocId> = (0..1_000u32).filter(|doc| doc % 3 != 1).collect();
+        let data = write_bitset(&docs);
+        let open = |data: &[u8], doc_freq: usize| {
+            let mut source = SourceRead::from(ReadOnlySource::from(data.to_owned()));
+            BitSetDocs::open(&mut source, doc_freq).map(|_| ())
+        };
+        assert!(open(&data, docs.len()).is_ok());
+        assert!(open(&data, docs.len() + 1).is_err());
+        assert!(open(&data[..data.len() - 1], docs.len()).is_err());
+        assert!(open(&data[..3], docs.len()).is_err());
+        assert!(open(&[255u8; 8], 64).is_ok());
+        assert!(open(&[255u8; 16], 100).is_err());
+    }
+
+    #[test]
+    fn test_is_dense() {
+        let mut block_encoder = BlockEncoder::new();
+        let mut is_dense_docs = |docs: &[DocId]| is_dense(docs, true, &mut block_encoder);
+        let docs: Vec<DocId> = (0..1_000u32).collect();
+        assert!(is_dense_docs(&docs));
+        // 2 bits per document in the blocks, as many as in the bitset,
+        // that does not need any skip data.
+        let docs: Vec<DocId> = (0..1_000u32).map(|doc| doc * 2).collect();
+        assert!(is_dense_docs(&docs));
+        // 6 bits per document in the blocks, and 32 in the bitset.
+        let docs: Vec<DocId> = (0..1_000u32).map(|doc| doc * 32).collect();
+        assert!(!is_dense_docs(&docs));
+        let docs: Vec<DocId> = (0..1_000u32).map(|doc| doc * 1_000).collect();
+        assert!(!is_dense_docs(&docs));
+    }
+}
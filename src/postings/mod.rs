@@ -13,21 +13,29 @@ mod serializer;
 mod postings_writer;
 mod term_info;
 mod segment_postings;
+mod skip;
+mod bitset_docs;
 
-use self::recorder::{NothingRecorder, Recorder, TFAndPositionRecorder, TermFrequencyRecorder};
+use self::recorder::{NothingRecorder, Recorder, TFAndPositionRecorder, TFPositionAndOffsetRecorder,
+                     TermFrequencyRecorder};
 pub use self::serializer::{FieldSerializer, InvertedIndexSerializer};
 pub(crate) use self::postings_writer::MultiFieldPostingsWriter;
 
 pub use self::term_info::TermInfo;
 pub use self::postings::Postings;
 
-pub use self::segment_postings::{BlockSegmentPostings, SegmentPostings};
+pub use self::segment_postings::{BlockSegmentPostings, PositionIterator, SegmentPostings};
+#[cfg(test)]
+pub(crate) use self::skip::remove_skip_entries;
+#[cfg(test)]
+pub(crate) use self::bitset_docs::BLOCKS_CODEC;
 
 pub use common::HasLen;
 
 pub(crate) type UnorderedTermId = u64;
 
 #[allow(enum_variant_names)]
+#[derive(Debug, PartialEq, Eq)]
 pub(crate) enum FreqReadingOption {
     NoFreq,
     SkipFreq,
@@ -43,7 +51,9 @@ pub mod tests {
     use Score;
     use query::Intersection;
     use query::Scorer;
-    use schema::{Document, SchemaBuilder, Term, INT_INDEXED, STRING, TEXT};
+    use schema::{Document, SchemaBuilder, Term, INT_INDEXED, INT_STORED, STORED, STRING, TEXT};
+    use schema::{TextFieldIndexing, TextOptions};
+    use futures::Future;
     use core::SegmentComponent;
     use indexer::SegmentWriter;
     use core::SegmentReader;
@@ -57,6 +67,7 @@ pub mod tests {
     use indexer::operation::AddOperation;
     use tests;
     use rand::{Rng, SeedableRng, XorShiftRng};
+    use DocAddress;
 
     #[test]
     pub fn test_position_write() {
@@ -82,6 +93,45 @@ pub mod tests {
         assert!(read.len() <= 140);
     }
 
+    #[test]
+    pub fn test_stored_only_fields_leave_no_trace() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let payload_field = schema_builder.add_text_field("payload", STORED);
+        let id_field = schema_builder.add_u64_field("id", INT_STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for id in 0u64..10u64 {
+                index_writer.add_document(doc!(
+                    text_field => "a b",
+                    payload_field => "large payload",
+                    id_field => id
+                ));
+            }
+            index_writer.commit().unwrap();
+        }
+        let space_usage = index.space_usage().unwrap();
+        assert!(space_usage.field_num_bytes(text_field) > 0);
+        for component_usage in &space_usage.segments[0].components {
+            assert!(
+                component_usage
+                    .fields
+                    .iter()
+                    .all(|field_usage| field_usage.field == text_field)
+            );
+        }
+        index.load_searchers().unwrap();
+        let doc = index.searcher().doc(&DocAddress(0, 3)).unwrap();
+        assert_eq!(doc.get_first(payload_field).unwrap().text(), "large payload");
+        assert_eq!(doc.get_first(id_field).unwrap().u64_value(), 3u64);
+
+        let mut segment = index.new_segment();
+        let mut posting_serializer = InvertedIndexSerializer::open(&mut segment).unwrap();
+        assert!(posting_serializer.new_field(payload_field).is_err());
+        assert!(posting_serializer.new_field(id_field).is_err());
+    }
+
     #[test]
     pub fn test_skip_positions() {
         let mut schema_builder = SchemaBuilder::new();
@@ -147,6 +197,56 @@ pub mod tests {
         }
     }
 
+    #[test]
+    pub fn test_offsets() {
+        let mut schema_builder = SchemaBuilder::new();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+        );
+        let title = schema_builder.add_text_field("title", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 30_000_000).unwrap();
+        index_writer.add_document(doc!(title => "abc be abc"));
+        for _ in 0..1_000 {
+            index_writer.add_document(doc!(title => "xx abc"));
+        }
+        // the offsets of the values of a field are those of their concatenation.
+        index_writer.add_document(doc!(title => "abc", title => "be abc"));
+        index_writer.commit().unwrap();
+        let check_postings = |index: &Index| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let term = Term::from_field_text(title, "abc");
+            let inverted_index = searcher.segment_reader(0u32).inverted_index(title);
+            let mut postings = inverted_index
+                .read_postings(&term, IndexRecordOption::WithFreqsAndPositionsAndOffsets)
+                .unwrap()
+                .unwrap();
+            assert!(postings.advance());
+            assert_eq!(postings.positions(), &[0, 2]);
+            assert_eq!(postings.offsets(), &[0..3, 7..10]);
+            assert!(postings.advance());
+            assert_eq!(postings.offsets(), &[3..6]);
+            assert_eq!(postings.skip_next(1_001), SkipResult::Reached);
+            assert_eq!(postings.positions(), &[0, 3]);
+            assert_eq!(postings.offsets(), &[0..3, 6..9]);
+            // offsets are not decoded without positions.
+            let mut postings = inverted_index
+                .read_postings(&term, IndexRecordOption::WithFreqs)
+                .unwrap()
+                .unwrap();
+            assert!(postings.advance());
+            assert!(postings.offsets().is_empty());
+        };
+        check_postings(&index);
+        // offsets are preserved by merges.
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        check_postings(&index);
+    }
+
     #[test]
     pub fn test_position_and_fieldnorm1() {
         let mut schema_builder = SchemaBuilder::default();
@@ -208,6 +308,7 @@ pub mod tests {
                     segment_reader
                         .inverted_index(term_a.field())
                         .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                        .unwrap()
                         .is_none()
                 );
             }
@@ -216,6 +317,7 @@ pub mod tests {
                 let mut postings_a = segment_reader
                     .inverted_index(term_a.field())
                     .read_postings(&term_a, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert_eq!(postings_a.len(), 1000);
                 assert!(postings_a.advance());
@@ -239,6 +341,7 @@ pub mod tests {
                 let mut postings_e = segment_reader
                     .inverted_index(term_e.field())
                     .read_postings(&term_e, IndexRecordOption::WithFreqsAndPositions)
+                    .unwrap()
                     .unwrap();
                 assert_eq!(postings_e.len(), 1000 - 2);
                 for i in 2u32..1000u32 {
@@ -329,6 +432,7 @@ pub mod tests {
                 let mut segment_postings = segment_reader
                     .inverted_index(term_2.field())
                     .read_postings(&term_2, IndexRecordOption::Basic)
+                    .unwrap()
                     .unwrap();
 
                 assert_eq!(segment_postings.skip_next(i), SkipResult::Reached);
@@ -343,6 +447,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             // check that `skip_next` advances the iterator
@@ -364,6 +469,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_0.field())
                 .read_postings(&term_0, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             for i in 0..num_docs / 2 {
@@ -374,6 +480,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_0.field())
                 .read_postings(&term_0, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             for i in 0..num_docs / 2 - 1 {
@@ -399,6 +506,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             if i % 2 == 0 {
@@ -415,6 +523,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             let mut last = 2; // start from 5 to avoid seeking to 3 twice
@@ -451,6 +560,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             assert_eq!(segment_postings.skip_next(0), SkipResult::End);
@@ -458,6 +568,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(term_2.field())
                 .read_postings(&term_2, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
 
             assert_eq!(segment_postings.skip_next(num_docs), SkipResult::End);
@@ -525,6 +636,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(TERM_A.field())
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             while segment_postings.advance() {}
         });
@@ -538,18 +650,22 @@ pub mod tests {
             let segment_postings_a = segment_reader
                 .inverted_index(TERM_A.field())
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let segment_postings_b = segment_reader
                 .inverted_index(TERM_B.field())
                 .read_postings(&*TERM_B, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let segment_postings_c = segment_reader
                 .inverted_index(TERM_C.field())
                 .read_postings(&*TERM_C, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let segment_postings_d = segment_reader
                 .inverted_index(TERM_D.field())
                 .read_postings(&*TERM_D, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let mut intersection = Intersection::from(vec![
                 segment_postings_a,
@@ -569,6 +685,7 @@ pub mod tests {
         let mut segment_postings = segment_reader
             .inverted_index(TERM_A.field())
             .read_postings(&*TERM_A, IndexRecordOption::Basic)
+            .unwrap()
             .unwrap();
 
         let mut existing_docs = Vec::new();
@@ -586,6 +703,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(TERM_A.field())
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             for doc in &existing_docs {
                 if segment_postings.skip_next(*doc) == SkipResult::End {
@@ -624,6 +742,7 @@ pub mod tests {
             let mut segment_postings = segment_reader
                 .inverted_index(TERM_A.field())
                 .read_postings(&*TERM_A, IndexRecordOption::Basic)
+                .unwrap()
                 .unwrap();
             let mut s = 0u32;
             while segment_postings.advance() {
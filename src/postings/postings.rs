@@ -1,5 +1,7 @@
 use docset::DocSet;
 
+use std::ops::Range;
+
 /// Postings (also called inverted list)
 ///
 /// For a given term, it is the list of doc ids of the doc
@@ -16,4 +18,13 @@ pub trait Postings: DocSet {
     /// Returns the list of positions of the term, expressed as a list of
     /// token ordinals.
     fn positions(&self) -> &[u32];
+    /// Returns the byte ranges of the occurrences of the term in the
+    /// text of the document, in the same order as `.positions()`.
+    ///
+    /// Offsets are only available if the field was indexed with
+    /// `IndexRecordOption::WithFreqsAndPositionsAndOffsets`, and if positions
+    /// were requested. Otherwise, this returns an empty slice.
+    fn offsets(&self) -> &[Range<u32>] {
+        &[]
+    }
 }
@@ -15,15 +15,17 @@ const POSITION_END: u32 = std::u32::MAX;
 ///   * the document id
 ///   * the term frequency
 ///   * the term positions
+///   * the term offsets
 pub trait Recorder: HeapAllocable {
     /// Returns the current document
     fn current_doc(&self) -> u32;
     /// Starts recording information about a new document
     /// This method shall only be called if the term is within the document.
     fn new_doc(&mut self, doc: DocId, heap: &Heap);
-    /// Record the position of a term. For each document,
+    /// Record the position of a term, and its offsets in the text
+    /// as a `(offset_from, offset_to)` pair. For each document,
     /// this method will be called `term_freq` times.
-    fn record_position(&mut self, position: u32, heap: &Heap);
+    fn record_position(&mut self, position: u32, offsets: (u32, u32), heap: &Heap);
     /// Close the document. It will help record the term frequency.
     fn close_doc(&mut self, heap: &Heap);
     /// Pushes the postings information to the serializer.
@@ -60,7 +62,7 @@ impl Recorder for NothingRecorder {
         self.stack.push(doc, heap);
     }
 
-    fn record_position(&mut self, _position: u32, _heap: &Heap) {}
+    fn record_position(&mut self, _position: u32, _offsets: (u32, u32), _heap: &Heap) {}
 
     fn close_doc(&mut self, _heap: &Heap) {}
 
@@ -104,7 +106,7 @@ impl Recorder for TermFrequencyRecorder {
         self.stack.push(doc, heap);
     }
 
-    fn record_position(&mut self, _position: u32, _heap: &Heap) {
+    fn record_position(&mut self, _position: u32, _offsets: (u32, u32), _heap: &Heap) {
         self.current_tf += 1;
     }
 
@@ -161,7 +163,7 @@ impl Recorder for TFAndPositionRecorder {
         self.stack.push(doc, heap);
     }
 
-    fn record_position(&mut self, position: u32, heap: &Heap) {
+    fn record_position(&mut self, position: u32, _offsets: (u32, u32), heap: &Heap) {
         self.stack.push(position, heap);
     }
 
@@ -193,3 +195,80 @@ impl Recorder for TFAndPositionRecorder {
         Ok(())
     }
 }
+
+/// Recorder encoding term frequencies, positions, and offsets.
+///
+/// Each occurrence of the term is serialized as three values,
+/// interleaved in the positions stream:
+/// the position delta, the delta of `offset_from` with the previous
+/// occurrence, and the length of the token in the text.
+pub struct TFPositionAndOffsetRecorder {
+    stack: ExpUnrolledLinkedList,
+    current_doc: DocId,
+}
+
+impl HeapAllocable for TFPositionAndOffsetRecorder {
+    fn with_addr(addr: u32) -> TFPositionAndOffsetRecorder {
+        TFPositionAndOffsetRecorder {
+            stack: ExpUnrolledLinkedList::with_addr(addr),
+            current_doc: u32::max_value(),
+        }
+    }
+}
+
+impl Recorder for TFPositionAndOffsetRecorder {
+    fn current_doc(&self) -> DocId {
+        self.current_doc
+    }
+
+    fn new_doc(&mut self, doc: DocId, heap: &Heap) {
+        self.current_doc = doc;
+        self.stack.push(doc, heap);
+    }
+
+    fn record_position(&mut self, position: u32, offsets: (u32, u32), heap: &Heap) {
+        let (offset_from, offset_to) = offsets;
+        self.stack.push(position, heap);
+        self.stack.push(offset_from, heap);
+        self.stack.push(offset_to, heap);
+    }
+
+    fn close_doc(&mut self, heap: &Heap) {
+        self.stack.push(POSITION_END, heap);
+    }
+
+    fn serialize(
+        &self,
+        self_addr: u32,
+        serializer: &mut FieldSerializer,
+        heap: &Heap,
+    ) -> io::Result<()> {
+        let mut doc_positions = Vec::with_capacity(300);
+        let mut positions_iter = self.stack.iter(self_addr, heap);
+        while let Some(doc) = positions_iter.next() {
+            let mut prev_position = 0;
+            let mut prev_offset_from = 0u32;
+            doc_positions.clear();
+            while let Some(position) = positions_iter.next() {
+                if position == POSITION_END {
+                    break;
+                }
+                let offset_from = positions_iter
+                    .next()
+                    .expect("The IndexWriter recorded a position without offsets.");
+                let offset_to = positions_iter
+                    .next()
+                    .expect("The IndexWriter recorded a position without offsets.");
+                doc_positions.push(position - prev_position);
+                // offsets are not necessarily increasing.
+                doc_positions.push(offset_from.wrapping_sub(prev_offset_from));
+                doc_positions.push(offset_to.wrapping_sub(offset_from));
+                prev_position = position;
+                prev_offset_from = offset_from;
+            }
+            let term_freq = (doc_positions.len() / 3) as u32;
+            serializer.write_doc(doc, term_freq, &doc_positions)?;
+        }
+        Ok(())
+    }
+}
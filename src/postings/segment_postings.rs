@@ -3,61 +3,188 @@ use DocId;
 
 use common::BitSet;
 use common::HasLen;
+use common::galloping_search;
 use postings::Postings;
 use docset::{DocSet, SkipResult};
 use std::cmp;
 use fst::Streamer;
-use compression::compressed_block_size;
+use compression::{compressed_block_size, compressed_vint_num_bytes};
 use fastfield::DeleteBitSet;
 use std::cell::UnsafeCell;
+use std::ops::Range;
 use directory::{ReadOnlySource, SourceRead};
 use postings::FreqReadingOption;
+use core::INDEX_FORMAT_VERSION;
 use postings::serializer::PostingsSerializer;
+use postings::skip::{skip_entry_num_bytes, SkipEntry, SKIP_DATA_FORMAT_VERSION};
+use postings::bitset_docs::{BitSetDocs, BITSET_CODEC, BLOCKS_CODEC};
+use error::DataCorruption;
+use schema::IndexRecordOption;
+use std::io;
 
 const EMPTY_POSITIONS: [u32; 0] = [0u32; 0];
 
+#[derive(Clone, Copy, Debug)]
+enum PositionState {
+    // no document has been reached yet.
+    Unstarted,
+    // the positions of the current document start after
+    // this number of ints in the stream.
+    ToSkip(usize),
+    // this number of positions of the current document have been
+    // decoded, and the stream is positioned right after them.
+    Decoded(usize),
+}
+
 struct PositionComputer {
-    // store the amount of position int
-    // before reading positions.
-    //
-    // if none, position are already loaded in
-    // the positions vec.
-    position_to_skip: Option<usize>,
+    state: PositionState,
+    // positions of the current document decoded so far.
     positions: Vec<u32>,
     positions_stream: CompressedIntStream,
+    // if the field is indexed with offsets, each position
+    // is followed by two ints encoding its offsets.
+    offsets_enabled: bool,
+    offsets: Vec<Range<u32>>,
+    buffer: Vec<u32>,
 }
 
 impl PositionComputer {
-    pub fn new(positions_stream: CompressedIntStream) -> PositionComputer {
+    pub fn new(positions_stream: CompressedIntStream, offsets_enabled: bool) -> PositionComputer {
         PositionComputer {
-            position_to_skip: None,
+            state: PositionState::Unstarted,
             positions: vec![],
             positions_stream,
+            offsets_enabled,
+            offsets: vec![],
+            buffer: vec![],
+        }
+    }
+
+    // number of ints encoded for each position.
+    fn num_ints_per_position(&self) -> usize {
+        if self.offsets_enabled {
+            3
+        } else {
+            1
         }
     }
 
+    /// Skips `num_skip` positions. The first call following the
+    /// decoding of the current document only skips its remaining positions.
     pub fn add_skip(&mut self, num_skip: usize) {
-        self.position_to_skip = Some(
-            self.position_to_skip
-                .map(|prev_skip| prev_skip + num_skip)
-                .unwrap_or(0),
-        );
+        let num_ints_per_position = self.num_ints_per_position();
+        self.state = match self.state {
+            PositionState::Unstarted => PositionState::ToSkip(0),
+            PositionState::ToSkip(num_ints) => {
+                PositionState::ToSkip(num_ints + num_skip * num_ints_per_position)
+            }
+            PositionState::Decoded(num_decoded) => {
+                PositionState::ToSkip(num_skip.saturating_sub(num_decoded) * num_ints_per_position)
+            }
+        };
+    }
+
+    /// Decodes the positions of the current document,
+    /// up to its `num_positions`-th position.
+    fn decode_up_to(&mut self, num_positions: usize) {
+        let num_decoded = match self.state {
+            PositionState::Unstarted => 0,
+            PositionState::ToSkip(num_ints) => {
+                self.positions_stream.skip(num_ints);
+                self.positions.clear();
+                self.offsets.clear();
+                0
+            }
+            PositionState::Decoded(num_decoded) => num_decoded,
+        };
+        if num_decoded < num_positions {
+            let num_ints_per_position = self.num_ints_per_position();
+            let num_ints = (num_positions - num_decoded) * num_ints_per_position;
+            self.buffer.resize(num_ints, 0u32);
+            self.positions_stream.read(&mut self.buffer[..num_ints]);
+            let mut cum = self.positions.last().cloned().unwrap_or(0u32);
+            let mut offset_from = self.offsets
+                .last()
+                .map(|offset| offset.start)
+                .unwrap_or(0u32);
+            for vals in self.buffer[..num_ints].chunks(num_ints_per_position) {
+                cum += vals[0];
+                self.positions.push(cum);
+                if self.offsets_enabled {
+                    offset_from = offset_from.wrapping_add(vals[1]);
+                    self.offsets.push(offset_from..offset_from + vals[2]);
+                }
+            }
+            self.state = PositionState::Decoded(num_positions);
+        } else {
+            self.state = PositionState::Decoded(num_decoded);
+        }
     }
 
     pub fn positions(&mut self, term_freq: usize) -> &[u32] {
-        if let Some(num_skip) = self.position_to_skip {
-            self.positions.resize(term_freq, 0u32);
-            self.positions_stream.skip(num_skip);
-            self.positions_stream.read(&mut self.positions[..term_freq]);
+        self.decode_up_to(term_freq);
+        &self.positions[..term_freq]
+    }
+
+    pub fn offsets(&mut self, term_freq: usize) -> &[Range<u32>] {
+        if !self.offsets_enabled {
+            return &[];
+        }
+        self.decode_up_to(term_freq);
+        &self.offsets[..term_freq]
+    }
+}
 
-            let mut cum = 0u32;
-            for i in 0..term_freq as usize {
-                cum += self.positions[i];
-                self.positions[i] = cum;
+/// Iterator over the positions of the current document of a `SegmentPostings`.
+///
+/// Positions are decoded one at a time, as the iterator advances,
+/// so that scorers that only need the first positions of a document
+/// do not pay for the decoding of the others.
+///
+/// It is created by calling `SegmentPostings::positions_iter()`.
+pub struct PositionIterator<'a> {
+    position_computer: Option<&'a mut PositionComputer>,
+    term_freq: usize,
+    idx: usize,
+}
+
+impl<'a> PositionIterator<'a> {
+    /// Advances to the first position greater or equal to `target`,
+    /// and returns it.
+    ///
+    /// Returns `None` if there is no such position.
+    /// The returned position is consumed: the next call to `.next()`
+    /// returns the position that follows it.
+    pub fn skip_to_position(&mut self, target: u32) -> Option<u32> {
+        while let Some(position) = self.next() {
+            if position >= target {
+                return Some(position);
             }
-            self.position_to_skip = None;
         }
-        &self.positions[..term_freq]
+        None
+    }
+}
+
+impl<'a> Iterator for PositionIterator<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.idx >= self.term_freq {
+            return None;
+        }
+        let position_computer = self.position_computer.as_mut()?;
+        position_computer.decode_up_to(self.idx + 1);
+        let position = position_computer.positions[self.idx];
+        self.idx += 1;
+        Some(position)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let num_remaining = match self.position_computer {
+            Some(_) => self.term_freq - self.idx,
+            None => 0,
+        };
+        (num_remaining, Some(num_remaining))
     }
 }
 
@@ -96,8 +223,14 @@ impl SegmentPostings {
             docs.len(),
             SourceRead::from(data),
             FreqReadingOption::NoFreq,
-        );
-        SegmentPostings::from_block_postings(block_segment_postings, DeleteBitSet::empty(), None)
+            INDEX_FORMAT_VERSION,
+        ).expect("The postings were just serialized");
+        SegmentPostings::from_block_postings(
+            block_segment_postings,
+            DeleteBitSet::empty(),
+            None,
+            false,
+        )
     }
 
     /// Reads a Segment postings from an &[u8]
@@ -106,13 +239,16 @@ impl SegmentPostings {
     /// * `data` - data array. The complete data is not necessarily used.
     /// * `freq_handler` - the freq handler is in charge of decoding
     ///   frequencies and/or positions
+    /// * `offsets_enabled` - true iff the positions stream also encodes offsets
     pub fn from_block_postings(
         segment_block_postings: BlockSegmentPostings,
         delete_bitset: DeleteBitSet,
         positions_stream_opt: Option<CompressedIntStream>,
+        offsets_enabled: bool,
     ) -> SegmentPostings {
-        let position_computer =
-            positions_stream_opt.map(|stream| UnsafeCell::new(PositionComputer::new(stream)));
+        let position_computer = positions_stream_opt.map(|stream| {
+            UnsafeCell::new(PositionComputer::new(stream, offsets_enabled))
+        });
         SegmentPostings {
             block_cursor: segment_block_postings,
             cur: COMPRESSION_BLOCK_SIZE, // cursor within the block
@@ -140,6 +276,97 @@ impl SegmentPostings {
             }
         }
     }
+
+    /// Returns an iterator over the positions of the current document,
+    /// decoding them lazily.
+    ///
+    /// Unlike `Postings::positions()`, it does not decode all of
+    /// the positions of the document upfront. Both can be mixed:
+    /// positions decoded by the iterator are not decoded twice.
+    ///
+    /// The iterator is empty if positions were not requested
+    /// when the postings were opened.
+    pub fn positions_iter(&mut self) -> PositionIterator {
+        let term_freq = self.term_freq() as usize;
+        PositionIterator {
+            position_computer: self.position_computer
+                .as_mut()
+                .map(|position_computer| unsafe { &mut *position_computer.get() }),
+            term_freq,
+            idx: 0,
+        }
+    }
+
+    /// Returns an upper bound of the term frequencies
+    /// of the documents of the current block.
+    ///
+    /// Together with `.skip_block(...)`, it makes it possible
+    /// for top-k scorers to skip the blocks of documents
+    /// that cannot reach a given score.
+    pub fn block_max_term_freq(&self) -> u32 {
+        self.block_cursor.block_max_term_freq()
+    }
+
+    /// Skips the remaining documents of the current block, as well
+    /// as the following blocks whose maximum term frequency is lower than
+    /// `min_term_freq`, and goes to the first non-deleted document
+    /// of the next block.
+    ///
+    /// Whenever possible, the skipped blocks are not decoded.
+    ///
+    /// Returns false iff there are no remaining documents.
+    pub fn skip_block(&mut self, min_term_freq: u32) -> bool {
+        loop {
+            // skip the positions of the current document,
+            // and of the remaining documents of the block.
+            self.position_add_skip(|| self.term_freq() as usize);
+            if self.cur + 1 < self.block_cursor.block_len() {
+                self.position_add_skip(|| {
+                    let freqs_skipped = &self.block_cursor.freqs()[self.cur + 1..];
+                    let sum_freqs: u32 = freqs_skipped.iter().sum();
+                    sum_freqs as usize
+                });
+            }
+            let sum_freqs_skipped = self.block_cursor.skip_low_term_freq_blocks(min_term_freq);
+            self.position_add_skip(|| sum_freqs_skipped as usize);
+            if !self.block_cursor.advance() {
+                self.cur = COMPRESSION_BLOCK_SIZE;
+                return false;
+            }
+            self.cur = 0;
+            if self.block_cursor.block_max_term_freq() < min_term_freq {
+                continue;
+            }
+            if self.delete_bitset.is_deleted(self.doc()) {
+                return self.advance();
+            }
+            return true;
+        }
+    }
+
+    /// Calls `callback` with the `DocId` and the term frequency of
+    /// the remaining documents that are not deleted, going through
+    /// the decoded blocks directly.
+    ///
+    /// The positions are not decoded, and are no longer available
+    /// afterwards. The postings are exhausted after this call.
+    pub(crate) fn for_each_remaining<F: FnMut(DocId, u32)>(&mut self, mut callback: F) {
+        let mut start = self.cur + 1;
+        loop {
+            for idx in start..self.block_cursor.block_len() {
+                let doc = self.block_cursor.doc(idx);
+                if !self.delete_bitset.is_deleted(doc) {
+                    callback(doc, self.block_cursor.freq(idx));
+                }
+            }
+            if !self.block_cursor.advance() {
+                break;
+            }
+            start = 0;
+        }
+        self.cur = COMPRESSION_BLOCK_SIZE;
+        self.position_computer = None;
+    }
 }
 
 impl DocSet for SegmentPostings {
@@ -191,6 +418,11 @@ impl DocSet for SegmentPostings {
                     sum_freq as usize
                 });
 
+                // jump over the blocks that end before the target
+                // without decoding them.
+                let sum_freqs_skipped = self.block_cursor.skip_blocks(target);
+                self.position_add_skip(|| sum_freqs_skipped as usize);
+
                 if !self.block_cursor.advance() {
                     return SkipResult::End;
                 }
@@ -207,40 +439,15 @@ impl DocSet for SegmentPostings {
             }
         }
         {
-            // we're in the right block now, start with an exponential search
+            // we're in the right block now, search the target
+            // with an exponential search.
             let block_docs = self.block_cursor.docs();
             let block_len = block_docs.len();
 
             debug_assert!(target >= block_docs[self.cur]);
             debug_assert!(target <= block_docs[block_len - 1]);
 
-            let mut start = self.cur;
-            let mut end = block_len;
-            let mut count = 1;
-            loop {
-                let new = start + count;
-                if new < end && block_docs[new] < target {
-                    start = new;
-                    count *= 2;
-                } else {
-                    break;
-                }
-            }
-            end = cmp::min(start + count, end);
-
-            // now do a binary search
-            let mut count = end - start;
-            while count > 0 {
-                let step = count / 2;
-                let mid = start + step;
-                let doc = block_docs[mid];
-                if doc < target {
-                    start = mid + 1;
-                    count -= step + 1;
-                } else {
-                    count = step;
-                }
-            }
+            let start = self.cur + galloping_search(&block_docs[self.cur..], target);
 
             // `doc` is now >= `target`
             let doc = block_docs[start];
@@ -290,12 +497,29 @@ impl DocSet for SegmentPostings {
                 bitset.insert(doc);
             }
             // ... iterate through the remaining blocks.
-            while self.block_cursor.advance() {
-                for &doc in self.block_cursor.docs() {
-                    bitset.insert(doc);
-                }
+            self.block_cursor.append_remaining_to_bitset(bitset);
+        }
+    }
+
+    fn bitset_bucket(&self, bucket: u32) -> Option<u64> {
+        // the deleted documents would have to be removed from the bucket.
+        if self.delete_bitset.has_deletes() {
+            return None;
+        }
+        let bitset_docs = self.block_cursor.bitset_docs.as_ref()?;
+        let mut bucket_docs = bitset_docs.remaining_bucket(bucket)?;
+        // the documents of the current block were already read from the bitset.
+        let block_docs = self.block_cursor.docs();
+        if self.cur + 1 < block_docs.len() {
+            for &doc in block_docs[self.cur + 1..]
+                .iter()
+                .skip_while(|&&doc| doc / 64 < bucket)
+                .take_while(|&&doc| doc / 64 == bucket)
+            {
+                bucket_docs.insert_mut(doc % 64);
             }
         }
+        Some(bucket_docs.into())
     }
 }
 
@@ -319,6 +543,16 @@ impl Postings for SegmentPostings {
             })
             .unwrap_or(&EMPTY_POSITIONS[..])
     }
+
+    fn offsets(&self) -> &[Range<u32>] {
+        let term_freq = self.term_freq();
+        self.position_computer
+            .as_ref()
+            .map(|position_computer| unsafe {
+                (&mut *position_computer.get()).offsets(term_freq as usize)
+            })
+            .unwrap_or(&[])
+    }
 }
 
 /// `BlockSegmentPostings` is a cursor iterating over blocks
@@ -338,26 +572,183 @@ pub struct BlockSegmentPostings {
     num_bitpacked_blocks: usize,
     num_vint_docs: usize,
     remaining_data: SourceRead,
+    // false if the posting lists do not have any header,
+    // nor any skip entries, see `SKIP_DATA_FORMAT_VERSION`.
+    has_skip_data: bool,
+    // docs of the posting list, if it is encoded as a bitset.
+    bitset_docs: Option<BitSetDocs>,
+    block_max_term_freq: u32,
+}
+
+fn corrupted_postings<T>(comment: &str) -> io::Result<T> {
+    Err(DataCorruption::comment_only(comment).into())
+}
+
+// Reads the codec of the postings data of a term, and advances `data` past it.
+//
+// Posting lists without any full block, or written before
+// `SKIP_DATA_FORMAT_VERSION`, do not have any codec: `None` is returned.
+// Their full blocks are not preceded by any skip entry.
+fn read_codec(
+    doc_freq: usize,
+    data: &mut SourceRead,
+    has_skip_data: bool,
+) -> io::Result<Option<u8>> {
+    if doc_freq < COMPRESSION_BLOCK_SIZE || !has_skip_data {
+        return Ok(None);
+    }
+    let codec = match data.as_ref().first() {
+        Some(&codec) => codec,
+        None => return corrupted_postings("Postings header is missing."),
+    };
+    data.advance(1);
+    match codec {
+        BITSET_CODEC | BLOCKS_CODEC => Ok(Some(codec)),
+        _ => corrupted_postings("Postings codec is unknown."),
+    }
+}
+
+// Checks that the full blocks of a posting list, followed by its
+// variable byte encoded integers, fit in `data`, so that decoding
+// them cannot read out of bounds.
+//
+// The documents are not part of the blocks if the posting list is
+// encoded as a bitset. If the blocks are preceded by skip entries,
+// the number of bytes of every block must match its skip entry.
+//
+// Returns the number of bytes of the blocks and of the
+// variable byte encoded integers.
+fn check_blocks(
+    data: &[u8],
+    doc_freq: usize,
+    has_doc_blocks: bool,
+    has_freqs: bool,
+    has_skip_entries: bool,
+) -> io::Result<usize> {
+    let num_blocks_per_entry = has_doc_blocks as usize + has_freqs as usize;
+    let mut offset = 0;
+    for _ in 0..doc_freq / COMPRESSION_BLOCK_SIZE {
+        let mut skip_entry = None;
+        if has_skip_entries {
+            if data.len() - offset < skip_entry_num_bytes(has_freqs) {
+                return corrupted_postings("Postings skip entry is truncated.");
+            }
+            skip_entry = Some(SkipEntry::read(&data[offset..], has_freqs));
+            offset += skip_entry_num_bytes(has_freqs);
+        }
+        let block_start = offset;
+        for _ in 0..num_blocks_per_entry {
+            match data.get(offset) {
+                Some(&num_bits) if num_bits <= 32 => {
+                    offset += compressed_block_size(num_bits);
+                }
+                Some(_) => return corrupted_postings("Postings block has more than 32 bits."),
+                None => return corrupted_postings("Postings block is truncated."),
+            }
+        }
+        if offset > data.len() {
+            return corrupted_postings("Postings block is truncated.");
+        }
+        if let Some(skip_entry) = skip_entry {
+            if skip_entry.block_num_bytes != offset - block_start {
+                return corrupted_postings("Postings skip entry does not match its block.");
+            }
+        }
+    }
+    let num_vint_docs = doc_freq % COMPRESSION_BLOCK_SIZE;
+    for _ in 0..num_blocks_per_entry {
+        match compressed_vint_num_bytes(&data[offset..], num_vint_docs) {
+            Some(num_bytes) => offset += num_bytes,
+            None => return corrupted_postings("Postings variable byte integers are truncated."),
+        }
+    }
+    Ok(offset)
+}
+
+// Reads the codec of the postings data of a term, and checks the blocks
+// that follow it, see `read_codec` and `check_blocks`.
+//
+// Returns the bitset of the documents if the posting list is encoded
+// as a bitset, after its blocks of term frequencies, along with the blocks.
+fn open_postings_data(
+    doc_freq: usize,
+    mut data: SourceRead,
+    has_freqs: bool,
+    has_skip_data: bool,
+) -> io::Result<(Option<BitSetDocs>, SourceRead)> {
+    let codec = read_codec(doc_freq, &mut data, has_skip_data)?;
+    let is_bitset = codec == Some(BITSET_CODEC);
+    let blocks_num_bytes = check_blocks(
+        data.as_ref(),
+        doc_freq,
+        !is_bitset,
+        has_freqs,
+        codec == Some(BLOCKS_CODEC),
+    )?;
+    let mut bitset_docs = None;
+    if is_bitset {
+        let mut bitset_data = data.clone();
+        bitset_data.advance(blocks_num_bytes);
+        bitset_docs = Some(BitSetDocs::open(&mut bitset_data, doc_freq)?);
+    }
+    Ok((bitset_docs, data))
 }
 
 impl BlockSegmentPostings {
+    /// Opens the posting list of `doc_freq` documents serialized
+    /// at the beginning of `data`, in the current index format.
+    ///
+    /// `record_option` is the option the posting list was indexed with.
+    /// This method is only exposed for fuzzing.
+    #[doc(hidden)]
+    pub fn open(
+        doc_freq: usize,
+        data: ReadOnlySource,
+        record_option: IndexRecordOption,
+    ) -> io::Result<BlockSegmentPostings> {
+        let freq_reading_option = if record_option.has_freq() {
+            FreqReadingOption::ReadFreq
+        } else {
+            FreqReadingOption::NoFreq
+        };
+        BlockSegmentPostings::from_data(
+            doc_freq,
+            SourceRead::from(data),
+            freq_reading_option,
+            INDEX_FORMAT_VERSION,
+        )
+    }
+
+    // `format_version` is the version of the format of the segment
+    // the posting list belongs to.
+    //
+    // Returns a `DataCorruption` error if the posting list
+    // does not fit in `data`.
     pub(crate) fn from_data(
         doc_freq: usize,
         data: SourceRead,
         freq_reading_option: FreqReadingOption,
-    ) -> BlockSegmentPostings {
+        format_version: u32,
+    ) -> io::Result<BlockSegmentPostings> {
         let num_bitpacked_blocks: usize = (doc_freq as usize) / COMPRESSION_BLOCK_SIZE;
         let num_vint_docs = (doc_freq as usize) - COMPRESSION_BLOCK_SIZE * num_bitpacked_blocks;
-        BlockSegmentPostings {
+        let has_freqs = freq_reading_option != FreqReadingOption::NoFreq;
+        let has_skip_data = format_version >= SKIP_DATA_FORMAT_VERSION;
+        let (bitset_docs, remaining_data) =
+            open_postings_data(doc_freq, data, has_freqs, has_skip_data)?;
+        Ok(BlockSegmentPostings {
             num_bitpacked_blocks,
             num_vint_docs,
             doc_decoder: BlockDecoder::new(),
             freq_decoder: BlockDecoder::with_val(1),
             freq_reading_option,
-            remaining_data: data,
+            remaining_data,
+            has_skip_data,
+            bitset_docs,
+            block_max_term_freq: 0,
             doc_offset: 0,
             doc_freq,
-        }
+        })
     }
 
     // Resets the block segment postings on another position
@@ -370,14 +761,24 @@ impl BlockSegmentPostings {
     // # Warning
     //
     // This does not reset the positions list.
-    pub(crate) fn reset(&mut self, doc_freq: usize, postings_data: SourceRead) {
+    //
+    // Returns a `DataCorruption` error if the posting list does not fit
+    // in `postings_data`, in which case the block segment postings is
+    // left untouched.
+    pub(crate) fn reset(&mut self, doc_freq: usize, postings_data: SourceRead) -> io::Result<()> {
         let num_binpacked_blocks: usize = doc_freq / COMPRESSION_BLOCK_SIZE;
         let num_vint_docs = doc_freq & (COMPRESSION_BLOCK_SIZE - 1);
+        let has_freqs = self.freq_reading_option != FreqReadingOption::NoFreq;
+        let (bitset_docs, remaining_data) =
+            open_postings_data(doc_freq, postings_data, has_freqs, self.has_skip_data)?;
         self.num_bitpacked_blocks = num_binpacked_blocks;
         self.num_vint_docs = num_vint_docs;
-        self.remaining_data = postings_data;
+        self.remaining_data = remaining_data;
+        self.bitset_docs = bitset_docs;
+        self.block_max_term_freq = 0;
         self.doc_offset = 0;
         self.doc_freq = doc_freq;
+        Ok(())
     }
 
     /// Returns the document frequency associated to this block postings.
@@ -430,9 +831,19 @@ impl BlockSegmentPostings {
     /// Returns false iff there was no remaining blocks.
     pub fn advance(&mut self) -> bool {
         if self.num_bitpacked_blocks > 0 {
-            let num_consumed_bytes = self.doc_decoder
-                .uncompress_block_sorted(self.remaining_data.as_ref(), self.doc_offset);
-            self.remaining_data.advance(num_consumed_bytes);
+            let mut skip_max_term_freq = None;
+            if let Some(ref mut bitset_docs) = self.bitset_docs {
+                bitset_docs.read(&mut self.doc_decoder.output[..COMPRESSION_BLOCK_SIZE]);
+                self.doc_decoder.output_len = COMPRESSION_BLOCK_SIZE;
+            } else {
+                if self.has_skip_data {
+                    skip_max_term_freq = Some(self.next_skip_entry().max_term_freq);
+                    self.remaining_data.advance(skip_entry_num_bytes(self.has_freqs()));
+                }
+                let num_consumed_bytes = self.doc_decoder
+                    .uncompress_block_sorted(self.remaining_data.as_ref(), self.doc_offset);
+                self.remaining_data.advance(num_consumed_bytes);
+            }
             match self.freq_reading_option {
                 FreqReadingOption::NoFreq => {}
                 FreqReadingOption::SkipFreq => {
@@ -448,14 +859,21 @@ impl BlockSegmentPostings {
             // it will be used as the next offset.
             self.doc_offset = self.doc_decoder.output(COMPRESSION_BLOCK_SIZE - 1);
             self.num_bitpacked_blocks -= 1;
+            self.block_max_term_freq =
+                skip_max_term_freq.unwrap_or_else(|| self.decoded_max_term_freq());
             true
         } else if self.num_vint_docs > 0 {
-            let num_compressed_bytes = self.doc_decoder.uncompress_vint_sorted(
-                self.remaining_data.as_ref(),
-                self.doc_offset,
-                self.num_vint_docs,
-            );
-            self.remaining_data.advance(num_compressed_bytes);
+            if let Some(ref mut bitset_docs) = self.bitset_docs {
+                bitset_docs.read(&mut self.doc_decoder.output[..self.num_vint_docs]);
+                self.doc_decoder.output_len = self.num_vint_docs;
+            } else {
+                let num_compressed_bytes = self.doc_decoder.uncompress_vint_sorted(
+                    self.remaining_data.as_ref(),
+                    self.doc_offset,
+                    self.num_vint_docs,
+                );
+                self.remaining_data.advance(num_compressed_bytes);
+            }
             match self.freq_reading_option {
                 FreqReadingOption::NoFreq | FreqReadingOption::SkipFreq => {}
                 FreqReadingOption::ReadFreq => {
@@ -464,12 +882,149 @@ impl BlockSegmentPostings {
                 }
             }
             self.num_vint_docs = 0;
+            self.block_max_term_freq = self.decoded_max_term_freq();
             true
         } else {
             false
         }
     }
 
+    fn has_freqs(&self) -> bool {
+        self.freq_reading_option != FreqReadingOption::NoFreq
+    }
+
+    // Skip entry of the next full block, at the beginning of the remaining data.
+    //
+    // Only the full blocks of delta-encoded posting lists
+    // with skip data are preceded by a skip entry.
+    fn next_skip_entry(&self) -> SkipEntry {
+        SkipEntry::read(self.remaining_data.as_ref(), self.has_freqs())
+    }
+
+    // Skips the next full block, along with its skip entry, without decoding it.
+    fn skip_next_block(&mut self, skip_entry: &SkipEntry) {
+        let num_bytes = skip_entry_num_bytes(self.has_freqs()) + skip_entry.block_num_bytes;
+        self.remaining_data.advance(num_bytes);
+        self.doc_offset = skip_entry.last_doc;
+        self.num_bitpacked_blocks -= 1;
+    }
+
+    // Maximum of the term frequencies of the current block,
+    // computed from the decoded term frequencies.
+    fn decoded_max_term_freq(&self) -> u32 {
+        match self.freq_reading_option {
+            FreqReadingOption::ReadFreq => self.freqs().iter().cloned().max().unwrap_or(1u32),
+            FreqReadingOption::NoFreq | FreqReadingOption::SkipFreq => 1u32,
+        }
+    }
+
+    /// Returns an upper bound of the term frequencies of the current block.
+    ///
+    /// For the full blocks of delta-encoded postings, it is read from the skip data,
+    /// without having to decode the term frequencies.
+    pub fn block_max_term_freq(&self) -> u32 {
+        self.block_max_term_freq
+    }
+
+    /// Skips, without decoding them, the following full blocks whose
+    /// maximum term frequency is lower than `min_term_freq`.
+    ///
+    /// Blocks can only be skipped if the postings are delta-encoded,
+    /// with skip data, and the term frequencies are recorded.
+    /// After this call, `.advance()` goes to the first block
+    /// that may contain a document with a term frequency of at
+    /// least `min_term_freq`, or to the last incomplete block.
+    ///
+    /// Returns the sum of the term frequencies of the skipped blocks.
+    pub(crate) fn skip_low_term_freq_blocks(&mut self, min_term_freq: u32) -> u32 {
+        let mut sum_term_freqs = 0u32;
+        if self.bitset_docs.is_some() || !self.has_skip_data
+            || self.freq_reading_option == FreqReadingOption::NoFreq
+        {
+            return sum_term_freqs;
+        }
+        while self.num_bitpacked_blocks > 0 {
+            let skip_entry = self.next_skip_entry();
+            if skip_entry.max_term_freq >= min_term_freq {
+                break;
+            }
+            self.skip_next_block(&skip_entry);
+            sum_term_freqs = sum_term_freqs.wrapping_add(skip_entry.sum_term_freqs);
+        }
+        sum_term_freqs
+    }
+
+    /// Skips, without decoding their documents, the blocks
+    /// whose last document is lower than `target`.
+    ///
+    /// After this call, `.advance()` goes to the first block
+    /// that may contain `target`. The current block should not be
+    /// used until then.
+    ///
+    /// Returns the sum of the term frequencies of the skipped blocks,
+    /// or `0` if term frequencies were not recorded.
+    pub(crate) fn skip_blocks(&mut self, target: DocId) -> u32 {
+        let mut sum_term_freqs = 0u32;
+        if let Some(ref mut bitset_docs) = self.bitset_docs {
+            let num_blocks = cmp::min(
+                bitset_docs.count_lower_than(target) / COMPRESSION_BLOCK_SIZE,
+                self.num_bitpacked_blocks,
+            );
+            bitset_docs.skip(num_blocks * COMPRESSION_BLOCK_SIZE);
+            for _ in 0..num_blocks {
+                match self.freq_reading_option {
+                    FreqReadingOption::NoFreq => {}
+                    FreqReadingOption::SkipFreq => {
+                        let num_bytes_to_skip =
+                            compressed_block_size(self.remaining_data.as_ref()[0]);
+                        self.remaining_data.advance(num_bytes_to_skip);
+                    }
+                    FreqReadingOption::ReadFreq => {
+                        let num_consumed_bytes = self.freq_decoder
+                            .uncompress_block_unsorted(self.remaining_data.as_ref());
+                        self.remaining_data.advance(num_consumed_bytes);
+                        sum_term_freqs = self.freq_decoder
+                            .output_array()
+                            .iter()
+                            .fold(sum_term_freqs, |sum, &freq| sum.wrapping_add(freq));
+                    }
+                }
+            }
+            self.num_bitpacked_blocks -= num_blocks;
+            return sum_term_freqs;
+        }
+        if !self.has_skip_data {
+            return sum_term_freqs;
+        }
+        while self.num_bitpacked_blocks > 0 {
+            let skip_entry = self.next_skip_entry();
+            if skip_entry.last_doc >= target {
+                break;
+            }
+            self.skip_next_block(&skip_entry);
+            sum_term_freqs = sum_term_freqs.wrapping_add(skip_entry.sum_term_freqs);
+        }
+        sum_term_freqs
+    }
+
+    /// Inserts the documents of the remaining blocks in `bitset`.
+    ///
+    /// If the posting list is encoded as a bitset, its buckets are
+    /// directly merged into `bitset`.
+    pub(crate) fn append_remaining_to_bitset(&mut self, bitset: &mut BitSet) {
+        if let Some(ref mut bitset_docs) = self.bitset_docs {
+            bitset_docs.append_to_bitset(bitset);
+            self.num_bitpacked_blocks = 0;
+            self.num_vint_docs = 0;
+            return;
+        }
+        while self.advance() {
+            for &doc in self.docs() {
+                bitset.insert(doc);
+            }
+        }
+    }
+
     /// Returns an empty segment postings object
     pub fn empty() -> BlockSegmentPostings {
         BlockSegmentPostings {
@@ -481,6 +1036,9 @@ impl BlockSegmentPostings {
             freq_reading_option: FreqReadingOption::NoFreq,
 
             remaining_data: From::from(ReadOnlySource::empty()),
+            has_skip_data: false,
+            bitset_docs: None,
+            block_max_term_freq: 0,
             doc_offset: 0,
             doc_freq: 0,
         }
@@ -502,16 +1060,27 @@ impl<'b> Streamer<'b> for BlockSegmentPostings {
 #[cfg(test)]
 mod tests {
 
-    use docset::DocSet;
+    use docset::{DocSet, SkipResult};
+    use postings::tests::test_skip_against_unoptimized;
     use super::SegmentPostings;
     use schema::SchemaBuilder;
     use core::Index;
-    use schema::INT_INDEXED;
+    use schema::{INT_INDEXED, TEXT};
     use schema::Term;
     use fst::Streamer;
+    use postings::Postings;
     use schema::IndexRecordOption;
     use common::HasLen;
     use super::BlockSegmentPostings;
+    use common::BitSet;
+    use query::Intersection;
+    use compression::COMPRESSION_BLOCK_SIZE;
+    use directory::{ReadOnlySource, SourceRead};
+    use fastfield::DeleteBitSet;
+    use postings::FreqReadingOption;
+    use postings::serializer::PostingsSerializer;
+    use postings::skip::{remove_skip_entries, SKIP_DATA_FORMAT_VERSION};
+    use core::INDEX_FORMAT_VERSION;
 
     #[test]
     fn test_empty_segment_postings() {
@@ -528,6 +1097,265 @@ mod tests {
         assert_eq!(postings.doc_freq(), 0);
     }
 
+    #[test]
+    fn test_skip_next_over_blocks() {
+        let docs: Vec<u32> = (0..10_000u32).map(|i| i * 5).collect();
+        let mut postings = SegmentPostings::create_from_docs(&docs);
+        assert_eq!(postings.skip_next(1_500), SkipResult::Reached);
+        assert_eq!(postings.doc(), 1_500);
+        assert_eq!(postings.skip_next(20_002), SkipResult::OverStep);
+        assert_eq!(postings.doc(), 20_005);
+        // the last docs are not bitpacked.
+        assert_eq!(postings.skip_next(49_995), SkipResult::Reached);
+        assert!(!postings.advance());
+        test_skip_against_unoptimized(
+            || box SegmentPostings::create_from_docs(&docs),
+            vec![0, 1, 639, 640, 641, 1_279, 5_000, 49_000, 49_995, 50_000],
+        );
+    }
+
+    #[test]
+    fn test_postings_without_skip_data() {
+        let docs: Vec<u32> = (0..1_000u32).map(|i| i * 7).collect();
+        let mut buffer = Vec::new();
+        {
+            let mut postings_serializer = PostingsSerializer::new(&mut buffer, false);
+            for &doc in &docs {
+                postings_serializer.write_doc(doc, 1u32).unwrap();
+            }
+            postings_serializer.close_term().unwrap();
+        }
+        // strips the codec byte and the skip entries, as in the
+        // posting lists written before `SKIP_DATA_FORMAT_VERSION`.
+        let num_blocks = docs.len() / COMPRESSION_BLOCK_SIZE;
+        let legacy_buffer = remove_skip_entries(&buffer[1..], num_blocks, false);
+        let legacy_postings = || {
+            let block_postings = BlockSegmentPostings::from_data(
+                docs.len(),
+                SourceRead::from(ReadOnlySource::from(legacy_buffer.clone())),
+                FreqReadingOption::NoFreq,
+                SKIP_DATA_FORMAT_VERSION - 1,
+            ).unwrap();
+            SegmentPostings::from_block_postings(
+                block_postings,
+                DeleteBitSet::empty(),
+                None,
+                false,
+            )
+        };
+        let mut postings = legacy_postings();
+        let mut num_docs = 0;
+        while postings.advance() {
+            assert_eq!(postings.doc(), docs[num_docs]);
+            num_docs += 1;
+        }
+        assert_eq!(num_docs, docs.len());
+        test_skip_against_unoptimized(
+            || box legacy_postings(),
+            vec![0, 1, 895, 896, 897, 3_500, 6_986, 6_993, 7_000],
+        );
+    }
+
+    #[test]
+    fn test_truncated_postings() {
+        let sparse_docs: Vec<u32> = (0..300u32).map(|i| i * 37).collect();
+        let dense_docs: Vec<u32> = (0..300u32).collect();
+        for docs in &[sparse_docs, dense_docs] {
+            for &has_freqs in &[false, true] {
+                let mut buffer = Vec::new();
+                {
+                    let mut postings_serializer = PostingsSerializer::new(&mut buffer, has_freqs);
+                    for &doc in docs.iter() {
+                        postings_serializer.write_doc(doc, doc % 5 + 1).unwrap();
+                    }
+                    postings_serializer.close_term().unwrap();
+                }
+                let open = |len: usize| {
+                    let freq_reading_option = if has_freqs {
+                        FreqReadingOption::ReadFreq
+                    } else {
+                        FreqReadingOption::NoFreq
+                    };
+                    BlockSegmentPostings::from_data(
+                        docs.len(),
+                        SourceRead::from(ReadOnlySource::from(buffer[..len].to_vec())),
+                        freq_reading_option,
+                        INDEX_FORMAT_VERSION,
+                    )
+                };
+                assert!(open(buffer.len()).is_ok());
+                for len in 0..buffer.len() {
+                    assert!(open(len).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_bitset_segment_postings() {
+        // dense enough to be encoded as a bitset.
+        let docs: Vec<u32> = (0..30_000u32).filter(|doc| doc % 3 != 1).collect();
+        {
+            let mut postings = SegmentPostings::create_from_docs(&docs);
+            let mut num_docs = 0;
+            while postings.advance() {
+                assert_eq!(postings.doc(), docs[num_docs]);
+                num_docs += 1;
+            }
+            assert_eq!(num_docs, docs.len());
+        }
+        {
+            let mut postings = SegmentPostings::create_from_docs(&docs);
+            assert_eq!(postings.skip_next(10_000), SkipResult::OverStep);
+            assert_eq!(postings.doc(), 10_001);
+            let mut bitset = BitSet::with_max_value(30_000);
+            postings.append_to_bitset(&mut bitset);
+            assert_eq!(bitset.len(), docs.len() - 6_668);
+            assert!(!bitset.contains(10_001));
+            assert!(bitset.contains(10_002));
+            assert!(bitset.contains(29_999));
+        }
+        test_skip_against_unoptimized(
+            || box SegmentPostings::create_from_docs(&docs),
+            vec![0, 1, 191, 192, 193, 10_000, 29_000, 29_999, 30_000],
+        );
+    }
+
+    #[test]
+    fn test_bitset_intersection() {
+        let left_docs: Vec<u32> = (0..30_000u32).filter(|doc| doc % 3 != 1).collect();
+        let right_docs: Vec<u32> = (0..30_000u32).filter(|doc| doc % 5 != 2).collect();
+        let expected_docs: Vec<u32> = left_docs
+            .iter()
+            .cloned()
+            .filter(|doc| doc % 5 != 2)
+            .collect();
+        let first_bucket = (0..64u32)
+            .filter(|doc| doc % 3 != 1)
+            .fold(0u64, |bucket, doc| bucket | (1u64 << doc));
+        let mut left = SegmentPostings::create_from_docs(&left_docs);
+        let right = SegmentPostings::create_from_docs(&right_docs);
+        assert_eq!(left.bitset_bucket(0), Some(first_bucket));
+        assert!(left.advance());
+        assert_eq!(left.doc(), 0);
+        assert_eq!(left.bitset_bucket(0), Some(first_bucket & !1u64));
+        assert_eq!(left.bitset_bucket(469), None);
+        assert_eq!(
+            SegmentPostings::create_from_docs(&(0..1_000u32).collect::<Vec<_>>()).bitset_bucket(0),
+            Some(!0u64)
+        );
+        assert_eq!(SegmentPostings::create_from_docs(&[1, 3]).bitset_bucket(0), None);
+        let mut intersection = Intersection::from(vec![
+            SegmentPostings::create_from_docs(&left_docs),
+            right,
+        ]);
+        for &expected_doc in &expected_docs {
+            assert!(intersection.advance());
+            assert_eq!(intersection.doc(), expected_doc);
+        }
+        assert!(!intersection.advance());
+    }
+
+    #[test]
+    fn test_skip_block() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        // `a` appears in one document out of five, three times within
+        // the fifth block of its postings, and once otherwise.
+        for doc in 0..5_000 {
+            let text = if doc % 5 != 0 {
+                "b"
+            } else if doc >= 512 * 5 && doc < 640 * 5 {
+                "a a a"
+            } else {
+                "a"
+            };
+            index_writer.add_document(doc!(text_field=>text));
+        }
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let inverted_index = segment_reader.inverted_index(text_field);
+        let term = Term::from_field_text(text_field, "a");
+        let read_postings = || {
+            inverted_index
+                .read_postings(&term, IndexRecordOption::WithFreqsAndPositions)
+                .unwrap()
+                .unwrap()
+        };
+        {
+            let mut postings = read_postings();
+            assert!(postings.skip_block(2));
+            assert_eq!(postings.doc(), 2_560);
+            assert_eq!(postings.block_max_term_freq(), 3);
+            assert_eq!(postings.positions(), &[0, 1, 2]);
+            assert!(postings.advance());
+            assert_eq!(postings.doc(), 2_565);
+            assert!(!postings.skip_block(2));
+        }
+        {
+            let mut postings = read_postings();
+            assert!(postings.advance());
+            assert_eq!(postings.block_max_term_freq(), 1);
+            assert!(postings.skip_block(1));
+            assert_eq!(postings.doc(), 640);
+            assert_eq!(postings.skip_next(2_565), SkipResult::Reached);
+            assert!(postings.skip_block(1));
+            assert_eq!(postings.doc(), 3_200);
+            assert_eq!(postings.term_freq(), 1);
+            assert_eq!(postings.positions(), &[0]);
+        }
+    }
+
+    #[test]
+    fn test_positions_iter() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a b a c a"));
+        index_writer.add_document(doc!(text_field=>"a x a"));
+        index_writer.add_document(doc!(text_field=>"b c"));
+        index_writer.add_document(doc!(text_field=>"c a"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let inverted_index = searcher.segment_reader(0).inverted_index(text_field);
+        let mut postings = inverted_index
+            .read_postings(
+                &Term::from_field_text(text_field, "a"),
+                IndexRecordOption::WithFreqsAndPositions,
+            )
+            .unwrap()
+            .unwrap();
+        assert!(postings.advance());
+        {
+            let mut positions = postings.positions_iter();
+            assert_eq!(positions.size_hint(), (3, Some(3)));
+            assert_eq!(positions.skip_to_position(1), Some(2));
+            assert_eq!(positions.next(), Some(4));
+            assert_eq!(positions.next(), None);
+        }
+        assert_eq!(postings.positions(), &[0, 2, 4]);
+        assert!(postings.advance());
+        // only the first position of the document is decoded.
+        assert_eq!(postings.positions_iter().next(), Some(0));
+        assert!(postings.advance());
+        assert_eq!(postings.doc(), 3);
+        assert_eq!(postings.positions_iter().collect::<Vec<u32>>(), vec![1]);
+        assert_eq!(postings.positions(), &[1]);
+
+        let mut postings = inverted_index
+            .read_postings(&Term::from_field_text(text_field, "a"), IndexRecordOption::Basic)
+            .unwrap()
+            .unwrap();
+        assert!(postings.advance());
+        assert_eq!(postings.positions_iter().next(), None);
+    }
+
     #[test]
     fn test_block_segment_postings() {
         let mut schema_builder = SchemaBuilder::default();
@@ -546,8 +1374,9 @@ mod tests {
         let inverted_index = segment_reader.inverted_index(int_field);
         let term = Term::from_field_u64(int_field, 0u64);
         let term_info = inverted_index.get_term_info(&term).unwrap();
-        let mut block_segments =
-            inverted_index.read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic);
+        let mut block_segments = inverted_index
+            .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic)
+            .unwrap();
         let mut offset: u32 = 0u32;
         // checking that the block before calling advance is empty
         assert!(block_segments.docs().is_empty());
@@ -585,7 +1414,8 @@ mod tests {
             let inverted_index = segment_reader.inverted_index(int_field);
             let term_info = inverted_index.get_term_info(&term).unwrap();
             block_segments = inverted_index
-                .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic);
+                .read_block_postings_from_terminfo(&term_info, IndexRecordOption::Basic)
+                .unwrap();
         }
         assert!(block_segments.advance());
         assert_eq!(block_segments.docs(), &[0, 2, 4]);
@@ -593,7 +1423,9 @@ mod tests {
             let term = Term::from_field_u64(int_field, 1u64);
             let inverted_index = segment_reader.inverted_index(int_field);
             let term_info = inverted_index.get_term_info(&term).unwrap();
-            inverted_index.reset_block_postings_from_terminfo(&term_info, &mut block_segments);
+            inverted_index
+                .reset_block_postings_from_terminfo(&term_info, &mut block_segments)
+                .unwrap();
         }
         assert!(block_segments.advance());
         assert_eq!(block_segments.docs(), &[1, 3, 5]);
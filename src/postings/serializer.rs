@@ -14,6 +14,8 @@ use compression::VIntEncoder;
 use common::CountingWriter;
 use common::CompositeWrite;
 use termdict::TermDictionaryBuilder;
+use super::skip::SkipSerializer;
+use super::bitset_docs::{self, BitSetDocsWriter};
 
 /// `PostingsSerializer` is in charge of serializing
 /// postings on disk, in the
@@ -84,8 +86,21 @@ impl InvertedIndexSerializer {
     /// a given field.
     ///
     /// Loads the indexing options for the given field.
+    ///
+    /// Fields that are not indexed, such as stored-only fields,
+    /// must not leave any trace in the inverted index: an error is
+    /// returned for them.
     pub fn new_field(&mut self, field: Field) -> io::Result<FieldSerializer> {
         let field_entry: &FieldEntry = self.schema.get_field_entry(field);
+        if !field_entry.is_indexed() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Field {:?} is not indexed, and cannot be serialized in the inverted index.",
+                    field_entry.name()
+                ),
+            ));
+        }
         let term_dictionary_write = self.terms_write.for_field(field);
         let postings_write = self.postings_write.for_field(field);
         let positions_write = self.positions_write.for_field(field);
@@ -190,6 +205,10 @@ impl<'a> FieldSerializer<'a> {
     /// For instance, if the positions are `2, 3, 17`,
     /// `position_deltas` is `2, 1, 14`
     ///
+    /// If the field is indexed with offsets, each position delta is followed by
+    /// the delta of its `offset_from` with the previous one, and by the
+    /// length of the token (See `TFPositionAndOffsetRecorder`).
+    ///
     /// Term frequencies and positions may be ignored by the serializer depending
     /// on the configuration of the field in the `Schema`.
     pub fn write_doc(
@@ -232,13 +251,34 @@ impl<'a> FieldSerializer<'a> {
     }
 }
 
+/// Serializes the postings of the terms of a field.
+///
+/// The postings of a term are written block after block, as soon as
+/// `COMPRESSION_BLOCK_SIZE` documents are buffered, so that the memory used
+/// does not grow with the length of the posting list.
+/// Posting lists of less than `COMPRESSION_BLOCK_SIZE` documents are
+/// simply variable byte encoded. Longer posting lists start with the id
+/// of their codec, chosen from their first block (see `bitset_docs::is_dense`).
+/// They are either encoded as delta-encoded bitpacked blocks, each preceded
+/// by its skip entry (see `SkipSerializer`), or, if they are dense enough,
+/// as the blocks of term frequencies followed by a bitset of the documents.
+/// The bitset is written once the term is closed, and takes at most one bit
+/// per document of the segment.
 pub struct PostingsSerializer<W: Write> {
     postings_write: CountingWriter<W>,
-    last_doc_id_encoded: u32,
 
     block_encoder: BlockEncoder,
+    // docs and term frequencies of the block being filled.
     doc_ids: Vec<DocId>,
     term_freqs: Vec<u32>,
+    // last doc of the previous full block of the term.
+    last_doc_id_encoded: DocId,
+    // codec of the term, chosen upon its first full block.
+    codec: Option<u8>,
+
+    doc_block_buffer: Vec<u8>,
+    skip_serializer: SkipSerializer,
+    bitset_writer: BitSetDocsWriter,
 
     termfreq_enabled: bool,
 }
@@ -249,10 +289,15 @@ impl<W: Write> PostingsSerializer<W> {
             postings_write: CountingWriter::wrap(write),
 
             block_encoder: BlockEncoder::new(),
-            doc_ids: vec![],
-            term_freqs: vec![],
-
+            doc_ids: Vec::with_capacity(COMPRESSION_BLOCK_SIZE),
+            term_freqs: Vec::with_capacity(COMPRESSION_BLOCK_SIZE),
             last_doc_id_encoded: 0u32,
+            codec: None,
+
+            doc_block_buffer: vec![],
+            skip_serializer: SkipSerializer::new(termfreq_enabled),
+            bitset_writer: BitSetDocsWriter::default(),
+
             termfreq_enabled,
         }
     }
@@ -263,47 +308,106 @@ impl<W: Write> PostingsSerializer<W> {
             self.term_freqs.push(term_freq as u32);
         }
         if self.doc_ids.len() == COMPRESSION_BLOCK_SIZE {
-            {
-                // encode the doc ids
-                let block_encoded: &[u8] = self.block_encoder
-                    .compress_block_sorted(&self.doc_ids, self.last_doc_id_encoded);
-                self.last_doc_id_encoded = self.doc_ids[self.doc_ids.len() - 1];
-                self.postings_write.write_all(block_encoded)?;
+            self.write_block()?;
+        }
+        Ok(())
+    }
+
+    // Writes the full block of docs and term frequencies buffered.
+    //
+    // The codec of the term is written before its first block.
+    fn write_block(&mut self) -> io::Result<()> {
+        let codec = match self.codec {
+            Some(codec) => codec,
+            None => {
+                let is_dense = bitset_docs::is_dense(
+                    &self.doc_ids,
+                    self.termfreq_enabled,
+                    &mut self.block_encoder,
+                );
+                let codec = if is_dense {
+                    bitset_docs::BITSET_CODEC
+                } else {
+                    bitset_docs::BLOCKS_CODEC
+                };
+                self.postings_write.write_all(&[codec])?;
+                self.codec = Some(codec);
+                codec
+            }
+        };
+        if codec == bitset_docs::BITSET_CODEC {
+            for &doc_id in &self.doc_ids {
+                self.bitset_writer.insert(doc_id);
             }
             if self.termfreq_enabled {
-                // encode the term_freqs
                 let block_encoded: &[u8] =
                     self.block_encoder.compress_block_unsorted(&self.term_freqs);
                 self.postings_write.write_all(block_encoded)?;
-                self.term_freqs.clear();
             }
-            self.doc_ids.clear();
+        } else {
+            self.write_doc_block()?;
+        }
+        self.last_doc_id_encoded = self.doc_ids[COMPRESSION_BLOCK_SIZE - 1];
+        self.doc_ids.clear();
+        self.term_freqs.clear();
+        Ok(())
+    }
+
+    // Writes the skip entry of the full block buffered, followed by
+    // the block itself: its delta-encoded docs and its term frequencies.
+    fn write_doc_block(&mut self) -> io::Result<()> {
+        self.doc_block_buffer.clear();
+        self.doc_block_buffer.extend_from_slice(
+            self.block_encoder
+                .compress_block_sorted(&self.doc_ids, self.last_doc_id_encoded),
+        );
+        let mut sum_term_freqs = 0u32;
+        let mut max_term_freq = 1u32;
+        let mut freqs_encoded: &[u8] = &[];
+        if self.termfreq_enabled {
+            sum_term_freqs = self.term_freqs.iter().sum();
+            max_term_freq = self.term_freqs.iter().cloned().max().unwrap_or(1u32);
+            freqs_encoded = self.block_encoder.compress_block_unsorted(&self.term_freqs);
         }
+        self.skip_serializer.clear();
+        self.skip_serializer.write_block(
+            self.doc_ids[COMPRESSION_BLOCK_SIZE - 1],
+            self.doc_block_buffer.len() + freqs_encoded.len(),
+            sum_term_freqs,
+            max_term_freq,
+        );
+        self.postings_write.write_all(self.skip_serializer.data())?;
+        self.postings_write.write_all(&self.doc_block_buffer)?;
+        self.postings_write.write_all(freqs_encoded)?;
         Ok(())
     }
 
     pub fn close_term(&mut self) -> io::Result<()> {
-        if !self.doc_ids.is_empty() {
+        let is_bitset = self.codec == Some(bitset_docs::BITSET_CODEC);
+        if is_bitset {
+            for &doc_id in &self.doc_ids {
+                self.bitset_writer.insert(doc_id);
+            }
+        } else if !self.doc_ids.is_empty() {
             // we have doc ids waiting to be written
             // this happens when the number of doc ids is
             // not a perfect multiple of our block size.
             //
             // In that case, the remaining part is encoded
             // using variable int encoding.
-            {
-                let block_encoded = self.block_encoder
-                    .compress_vint_sorted(&self.doc_ids, self.last_doc_id_encoded);
-                self.postings_write.write_all(block_encoded)?;
-                self.doc_ids.clear();
-            }
-            // ... Idem for term frequencies
-            if self.termfreq_enabled {
-                let block_encoded = self.block_encoder
-                    .compress_vint_unsorted(&self.term_freqs[..]);
-                self.postings_write.write_all(block_encoded)?;
-                self.term_freqs.clear();
-            }
+            let block_encoded = self.block_encoder
+                .compress_vint_sorted(&self.doc_ids, self.last_doc_id_encoded);
+            self.postings_write.write_all(block_encoded)?;
+        }
+        // ... Idem for term frequencies
+        if self.termfreq_enabled && !self.term_freqs.is_empty() {
+            let block_encoded = self.block_encoder.compress_vint_unsorted(&self.term_freqs);
+            self.postings_write.write_all(block_encoded)?;
         }
+        if is_bitset {
+            self.bitset_writer.write(&mut self.postings_write)?;
+        }
+        self.clear();
         Ok(())
     }
 
@@ -318,7 +422,9 @@ impl<W: Write> PostingsSerializer<W> {
     fn clear(&mut self) {
         self.doc_ids.clear();
         self.term_freqs.clear();
-        self.last_doc_id_encoded = 0;
+        self.last_doc_id_encoded = 0u32;
+        self.codec = None;
+        self.bitset_writer.clear();
     }
 }
 
@@ -368,3 +474,31 @@ impl<W: Write> PositionSerializer<W> {
         self.write.flush()
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::PostingsSerializer;
+    use compression::COMPRESSION_BLOCK_SIZE;
+    use DocId;
+
+    #[test]
+    fn test_postings_serializer_writes_full_blocks() {
+        let mut buffer = Vec::new();
+        let mut postings_serializer = PostingsSerializer::new(&mut buffer, true);
+        let block_len = COMPRESSION_BLOCK_SIZE as DocId;
+        for doc in 0..block_len - 1 {
+            postings_serializer.write_doc(doc * 7, 1).unwrap();
+        }
+        assert_eq!(postings_serializer.addr(), 0);
+        // the codec, the skip entry and the block are written
+        // as soon as the block is full.
+        postings_serializer.write_doc((block_len - 1) * 7, 1).unwrap();
+        let num_bytes = postings_serializer.addr();
+        assert!(num_bytes > 0);
+        postings_serializer.write_doc(block_len * 7, 1).unwrap();
+        assert_eq!(postings_serializer.addr(), num_bytes);
+        postings_serializer.close_term().unwrap();
+        assert!(postings_serializer.addr() > num_bytes);
+    }
+}
@@ -0,0 +1,177 @@
+use byteorder::ByteOrder;
+use common::Endianness;
+use DocId;
+
+/// First version of the index format whose posting lists start with a header,
+/// the id of their codec, and whose full blocks carry their skip data
+/// (or whose documents are encoded as a bitset, see `BitSetDocs`).
+///
+/// The posting lists of the segments written in an older format
+/// do not have any header, and are read block after block.
+pub(crate) const SKIP_DATA_FORMAT_VERSION: u32 = 1;
+
+/// Skip data of a postings list.
+///
+/// Each full block of `COMPRESSION_BLOCK_SIZE` documents of a posting list
+/// is preceded by its skip entry, made of
+///
+/// * the last `DocId` of the block,
+/// * the number of bytes of the block (doc ids and term frequencies),
+/// * if term frequencies are recorded, the sum of the term frequencies of the block,
+///   and the maximum term frequency of the block.
+///
+/// All of the values are `u32`, written in little endian.
+/// The skip entries make it possible to jump over the blocks that cannot contain
+/// a target document without decoding them. As an entry is written along with
+/// its block, the serializer only has to buffer a single block.
+pub(crate) struct SkipSerializer {
+    buffer: Vec<u8>,
+    has_freqs: bool,
+}
+
+fn write_u32(buffer: &mut Vec<u8>, val: u32) {
+    let mut bytes = [0u8; 4];
+    Endianness::write_u32(&mut bytes, val);
+    buffer.extend_from_slice(&bytes);
+}
+
+impl SkipSerializer {
+    pub fn new(has_freqs: bool) -> SkipSerializer {
+        SkipSerializer {
+            buffer: Vec::new(),
+            has_freqs,
+        }
+    }
+
+    pub fn write_block(
+        &mut self,
+        last_doc: DocId,
+        num_bytes: usize,
+        sum_term_freqs: u32,
+        max_term_freq: u32,
+    ) {
+        write_u32(&mut self.buffer, last_doc);
+        write_u32(&mut self.buffer, num_bytes as u32);
+        if self.has_freqs {
+            write_u32(&mut self.buffer, sum_term_freqs);
+            write_u32(&mut self.buffer, max_term_freq);
+        }
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+/// Returns the number of bytes of the skip entry of a block.
+pub(crate) fn skip_entry_num_bytes(has_freqs: bool) -> usize {
+    if has_freqs {
+        16
+    } else {
+        8
+    }
+}
+
+/// Returns the number of bytes of the skip entries
+/// of a posting list with `num_blocks` full blocks.
+pub(crate) fn skip_data_num_bytes(num_blocks: usize, has_freqs: bool) -> usize {
+    num_blocks * skip_entry_num_bytes(has_freqs)
+}
+
+/// Skip entry of a full block of a posting list.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct SkipEntry {
+    /// Last `DocId` of the block.
+    pub last_doc: DocId,
+    /// Number of bytes of the block, excluding its skip entry.
+    pub block_num_bytes: usize,
+    /// Sum of the term frequencies of the block,
+    /// or `0` if term frequencies are not recorded.
+    pub sum_term_freqs: u32,
+    /// Maximum term frequency of the block,
+    /// or `1` if term frequencies are not recorded.
+    pub max_term_freq: u32,
+}
+
+impl SkipEntry {
+    /// Reads the skip entry at the beginning of `data`.
+    ///
+    /// `data` must hold at least `skip_entry_num_bytes(has_freqs)` bytes.
+    pub fn read(data: &[u8], has_freqs: bool) -> SkipEntry {
+        let read_u32 = |idx: usize| Endianness::read_u32(&data[idx * 4..]);
+        let (sum_term_freqs, max_term_freq) = if has_freqs {
+            (read_u32(2), read_u32(3))
+        } else {
+            (0, 1)
+        };
+        SkipEntry {
+            last_doc: read_u32(0),
+            block_num_bytes: read_u32(1) as usize,
+            sum_term_freqs,
+            max_term_freq,
+        }
+    }
+}
+
+/// Removes the skip entries preceding the `num_blocks` full blocks at the
+/// beginning of `data`, as in the posting lists written before
+/// `SKIP_DATA_FORMAT_VERSION`.
+#[cfg(test)]
+pub(crate) fn remove_skip_entries(
+    mut data: &[u8],
+    num_blocks: usize,
+    has_freqs: bool,
+) -> Vec<u8> {
+    let mut legacy_data = Vec::new();
+    for _ in 0..num_blocks {
+        let block_start = skip_entry_num_bytes(has_freqs);
+        let block_end = block_start + SkipEntry::read(data, has_freqs).block_num_bytes;
+        legacy_data.extend_from_slice(&data[block_start..block_end]);
+        data = &data[block_end..];
+    }
+    legacy_data.extend_from_slice(data);
+    legacy_data
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_skip_data() {
+        for &has_freqs in &[false, true] {
+            let mut skip_serializer = SkipSerializer::new(has_freqs);
+            skip_serializer.write_block(127, 100, 300, 12);
+            skip_serializer.write_block(1_000, 85, 128, 1);
+            let data = skip_serializer.data().to_vec();
+            assert_eq!(data.len(), skip_data_num_bytes(2, has_freqs));
+            let skip_entry = SkipEntry::read(&data, has_freqs);
+            assert_eq!(skip_entry.last_doc, 127);
+            assert_eq!(skip_entry.block_num_bytes, 100);
+            assert_eq!(skip_entry.sum_term_freqs, if has_freqs { 300 } else { 0 });
+            assert_eq!(skip_entry.max_term_freq, if has_freqs { 12 } else { 1 });
+            let skip_entry = SkipEntry::read(&data[skip_entry_num_bytes(has_freqs)..], has_freqs);
+            assert_eq!(skip_entry.last_doc, 1_000);
+            assert_eq!(skip_entry.block_num_bytes, 85);
+            assert_eq!(skip_entry.sum_term_freqs, if has_freqs { 128 } else { 0 });
+        }
+    }
+
+    #[test]
+    fn test_remove_skip_entries() {
+        let mut data = Vec::new();
+        for &(last_doc, block) in &[(127u32, &b"abc"[..]), (255u32, &b"de"[..])] {
+            let mut skip_serializer = SkipSerializer::new(false);
+            skip_serializer.write_block(last_doc, block.len(), 0, 1);
+            data.extend_from_slice(skip_serializer.data());
+            data.extend_from_slice(block);
+        }
+        data.extend_from_slice(b"tail");
+        assert_eq!(remove_skip_entries(&data, 2, false), b"abcdetail".to_vec());
+    }
+}
@@ -7,6 +7,7 @@ use schema::Term;
 use query::TermQuery;
 use schema::IndexRecordOption;
 use query::Occur;
+use std::collections::BTreeSet;
 
 /// The boolean query combines a set of queries
 ///
@@ -34,11 +35,21 @@ impl Query for BooleanQuery {
         let sub_weights = self.subqueries
             .iter()
             .map(|&(ref occur, ref subquery)| {
-                Ok((*occur, subquery.weight(searcher, scoring_enabled)?))
+                Ok((*occur, searcher.create_weight(&**subquery, scoring_enabled)?))
             })
             .collect::<Result<_>>()?;
         Ok(box BooleanWeight::new(sub_weights, scoring_enabled))
     }
+
+    /// The terms of the `MustNot` subqueries are not extracted,
+    /// as they never appear in the matching documents.
+    fn query_terms(&self, term_set: &mut BTreeSet<Term>) {
+        for &(occur, ref subquery) in &self.subqueries {
+            if occur != Occur::MustNot {
+                subquery.query_terms(term_set);
+            }
+        }
+    }
 }
 
 impl BooleanQuery {
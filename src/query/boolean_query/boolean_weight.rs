@@ -56,11 +56,30 @@ impl BooleanWeight {
         &self,
         reader: &SegmentReader,
     ) -> Result<Box<Scorer>> {
+        // The clauses are sorted by increasing cost, so that the cheapest
+        // clauses lead the intersections. Clauses that cannot match any
+        // document are skipped, and a required one spares us from
+        // building the other scorers altogether.
+        let mut weights_with_cost = Vec::with_capacity(self.weights.len());
+        for &(occur, ref subweight) in &self.weights {
+            weights_with_cost.push((subweight.cost(reader)?, occur, subweight));
+        }
+        weights_with_cost.sort_by_key(|&(cost, _, _)| cost);
+        if weights_with_cost
+            .iter()
+            .any(|&(cost, occur, _)| cost == 0 && occur == Occur::Must)
+        {
+            return Ok(box EmptyScorer);
+        }
+
         let mut per_occur_scorers: HashMap<Occur, Vec<Box<Scorer>>> = HashMap::new();
-        for &(ref occur, ref subweight) in &self.weights {
+        for (cost, occur, subweight) in weights_with_cost {
+            if cost == 0 {
+                continue;
+            }
             let sub_scorer: Box<Scorer> = subweight.scorer(reader)?;
             per_occur_scorers
-                .entry(*occur)
+                .entry(occur)
                 .or_insert_with(Vec::new)
                 .push(sub_scorer);
         }
@@ -139,4 +158,25 @@ impl Weight for BooleanWeight {
             self.complex_scorer::<DoNothingCombiner>(reader)
         }
     }
+
+    /// The cost of the required clauses is that of the cheapest of them,
+    /// as it leads the intersection. Without required clauses, the cost
+    /// is the sum of the costs of the optional clauses.
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        let mut must_cost: Option<u64> = None;
+        let mut should_cost = 0u64;
+        for &(occur, ref subweight) in &self.weights {
+            match occur {
+                Occur::Must => {
+                    let cost = subweight.cost(reader)?;
+                    must_cost = Some(must_cost.map_or(cost, |must_cost| must_cost.min(cost)));
+                }
+                Occur::Should => {
+                    should_cost += subweight.cost(reader)?;
+                }
+                Occur::MustNot => {}
+            }
+        }
+        Ok(must_cost.unwrap_or(should_cost))
+    }
 }
@@ -12,6 +12,7 @@ mod tests {
     use query::TermQuery;
     use query::Intersection;
     use query::Scorer;
+    use query::EmptyScorer;
     use query::term_query::TermScorer;
     use collector::tests::TestCollector;
     use Index;
@@ -114,6 +115,40 @@ mod tests {
         }
     }
 
+    #[test]
+    pub fn test_boolean_cost() {
+        let (index, text_field) = aux_test_helper();
+        let query_parser = QueryParser::for_index(&index, vec![text_field]);
+        let searcher = index.searcher();
+        let cost = |query: &str| {
+            query_parser
+                .parse_query(query)
+                .unwrap()
+                .cost(&*searcher)
+                .unwrap()
+        };
+        assert_eq!(cost("a"), 3);
+        assert_eq!(cost("+a +c"), 3);
+        assert_eq!(cost("+c +d"), 2);
+        assert_eq!(cost("a d"), 5);
+        assert_eq!(cost("+a b -d"), 3);
+        assert_eq!(cost("+a +e"), 0);
+        assert_eq!(cost("\"a b\" c"), 7);
+        {
+            let query = query_parser.parse_query("+a +e").unwrap();
+            let weight = query.weight(&*searcher, true).unwrap();
+            let scorer = weight.scorer(searcher.segment_reader(0u32)).unwrap();
+            assert!(Downcast::<EmptyScorer>::is_type(&*scorer));
+        }
+        {
+            let query = query_parser.parse_query("+a e").unwrap();
+            let weight = query.weight(&*searcher, true).unwrap();
+            let scorer = weight.scorer(searcher.segment_reader(0u32)).unwrap();
+            assert!(Downcast::<TermScorer>::is_type(&*scorer));
+            assert_eq!(query.count(&*searcher).unwrap(), 3);
+        }
+    }
+
     #[test]
     pub fn test_boolean_query() {
 
@@ -0,0 +1,153 @@
+use core::{SegmentReader, Searcher};
+use docset::{DocSet, SkipResult};
+use query::{Query, Scorer, Weight};
+use schema::Term;
+use std::collections::BTreeSet;
+use DocId;
+use Result;
+use Score;
+
+/// `BoostQuery` multiplies the score of the documents
+/// matching its underlying query by a given boost.
+///
+/// It matches the same documents as its underlying query.
+/// It is typically used to give more importance to some of the
+/// fields of a multi-field query, for instance the title of a document
+/// over its body.
+#[derive(Debug)]
+pub struct BoostQuery {
+    query: Box<Query>,
+    boost: Score,
+}
+
+impl BoostQuery {
+    /// Creates a new `BoostQuery`.
+    pub fn new(query: Box<Query>, boost: Score) -> BoostQuery {
+        BoostQuery { query, boost }
+    }
+
+    /// Returns the boost of the query.
+    pub fn boost(&self) -> Score {
+        self.boost
+    }
+}
+
+impl Query for BoostQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let weight = searcher.create_weight(&*self.query, scoring_enabled)?;
+        if scoring_enabled {
+            Ok(box BoostWeight {
+                weight,
+                boost: self.boost,
+            })
+        } else {
+            Ok(weight)
+        }
+    }
+
+    fn query_terms(&self, term_set: &mut BTreeSet<Term>) {
+        self.query.query_terms(term_set);
+    }
+}
+
+struct BoostWeight {
+    weight: Box<Weight>,
+    boost: Score,
+}
+
+impl Weight for BoostWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        Ok(box BoostScorer {
+            scorer: self.weight.scorer(reader)?,
+            boost: self.boost,
+        })
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.weight.count(reader)
+    }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        self.weight.cost(reader)
+    }
+}
+
+struct BoostScorer {
+    scorer: Box<Scorer>,
+    boost: Score,
+}
+
+impl DocSet for BoostScorer {
+    fn advance(&mut self) -> bool {
+        self.scorer.advance()
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        self.scorer.skip_next(target)
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for BoostScorer {
+    fn score(&mut self) -> Score {
+        self.scorer.score() * self.boost
+    }
+
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        if self.boost > 0f32 {
+            self.scorer.set_min_competitive_score(min_score / self.boost);
+        }
+    }
+
+    fn for_each(&mut self, callback: &mut FnMut(DocId, Score)) {
+        let boost = self.boost;
+        self.scorer.for_each(&mut |doc, score| callback(doc, score * boost));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use collector::TopCollector;
+    use query::{BoostQuery, Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, TEXT};
+
+    #[test]
+    fn test_boost_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a b"));
+        index_writer.add_document(doc!(text_field=>"c"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let term_query = || -> Box<Query> {
+            box TermQuery::new(
+                Term::from_field_text(text_field, "a"),
+                IndexRecordOption::Basic,
+            )
+        };
+        let top_score = |query: &Query| {
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher.search(query, &mut top_collector).unwrap();
+            let score_docs = top_collector.score_docs();
+            assert_eq!(score_docs.len(), 1);
+            score_docs[0].0
+        };
+        let score = top_score(&*term_query());
+        let boost_query = BoostQuery::new(term_query(), 3f32);
+        assert!((top_score(&boost_query) - 3f32 * score).abs() < 0.0001f32);
+        assert_eq!(boost_query.count(&searcher).unwrap(), 1);
+    }
+}
@@ -0,0 +1,140 @@
+use common::BitSet;
+use core::{FilterCache, SegmentReader, Searcher};
+use docset::DocSet;
+use query::{BitSetDocSet, ConstScorer, Query, Scorer, Weight};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use Result;
+
+/// `CachedFilterQuery` matches the documents of a filter query,
+/// and caches them in the `FilterCache` of the searcher.
+///
+/// All of the documents are scored `1`.
+/// It is meant for filters that are expensive to compute and repeated
+/// over many requests, for instance a constraint on a tenant id.
+///
+/// The cache is keyed by a hash of the `Debug` representation of the filter,
+/// which identifies the built-in queries.
+///
+/// If the searcher does not have any filter cache
+/// (see `IndexReaderBuilder::filter_cache_capacity`),
+/// the filter is simply computed for every search.
+#[derive(Debug)]
+pub struct CachedFilterQuery {
+    filter: Box<Query>,
+    filter_hash: u64,
+}
+
+impl CachedFilterQuery {
+    /// Creates a new `CachedFilterQuery` over the given filter.
+    pub fn new(filter: Box<Query>) -> CachedFilterQuery {
+        let mut hasher = DefaultHasher::new();
+        format!("{:?}", filter).hash(&mut hasher);
+        CachedFilterQuery {
+            filter,
+            filter_hash: hasher.finish(),
+        }
+    }
+}
+
+impl Query for CachedFilterQuery {
+    fn weight(&self, searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        Ok(box CachedFilterWeight {
+            filter_weight: searcher.create_weight(&*self.filter, false)?,
+            filter_hash: self.filter_hash,
+            filter_cache: searcher.filter_cache(),
+        })
+    }
+}
+
+struct CachedFilterWeight {
+    filter_weight: Box<Weight>,
+    filter_hash: u64,
+    filter_cache: Option<Arc<FilterCache>>,
+}
+
+impl Weight for CachedFilterWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let filter_cache = match self.filter_cache {
+            Some(ref filter_cache) => filter_cache,
+            None => {
+                return Ok(box ConstScorer::new(self.filter_weight.scorer(reader)?));
+            }
+        };
+        let mut span = span!("filter_cache", segment_id = reader.segment_id());
+        let mut cache_hit = true;
+        let doc_bitset = filter_cache.get_or_compute(self.filter_hash, reader.segment_id(), || {
+            cache_hit = false;
+            let mut doc_bitset = BitSet::with_max_value(reader.max_doc());
+            self.filter_weight
+                .scorer(reader)?
+                .append_to_bitset(&mut doc_bitset);
+            Ok(doc_bitset)
+        })?;
+        span.record("cache_hit", cache_hit);
+        let docset = BitSetDocSet::from(BitSet::clone(&doc_bitset));
+        Ok(box ConstScorer::new(docset))
+    }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        self.filter_weight.cost(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::CachedFilterQuery;
+    use Index;
+    use query::{Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, Term, TEXT};
+
+    #[test]
+    fn test_cached_filter_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let tenant_field = schema_builder.add_text_field("tenant", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for i in 0..100 {
+            let tenant = if i % 3 == 0 { "a" } else { "b" };
+            index_writer.add_document(doc!(tenant_field=>tenant));
+        }
+        index_writer.commit().unwrap();
+        let reader = index
+            .reader_builder()
+            .filter_cache_capacity(10)
+            .try_into()
+            .unwrap();
+        let term_query = TermQuery::new(
+            Term::from_field_text(tenant_field, "a"),
+            IndexRecordOption::Basic,
+        );
+        let query = CachedFilterQuery::new(box term_query);
+
+        let searcher = reader.searcher();
+        let filter_cache = searcher.filter_cache().unwrap();
+        assert!(filter_cache.is_empty());
+        assert_eq!(query.count(&*searcher).unwrap(), 34);
+        assert_eq!(filter_cache.len(), 1);
+        assert_eq!(query.count(&*searcher).unwrap(), 34);
+        assert_eq!(filter_cache.len(), 1);
+
+        index_writer.add_document(doc!(tenant_field=>"a"));
+        index_writer.commit().unwrap();
+        reader.reload().unwrap();
+        let searcher = reader.searcher();
+        assert!(searcher.filter_cache().unwrap().is_empty());
+        assert_eq!(query.count(&*searcher).unwrap(), 35);
+        assert_eq!(
+            searcher.filter_cache().unwrap().len(),
+            searcher.segment_readers().len()
+        );
+
+        // without any filter cache.
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert!(searcher.filter_cache().is_none());
+        assert_eq!(query.count(&*searcher).unwrap(), 35);
+    }
+}
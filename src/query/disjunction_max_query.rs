@@ -0,0 +1,276 @@
+use core::{SegmentReader, Searcher};
+use docset::DocSet;
+use query::{EmptyScorer, Query, Scorer, Weight};
+use schema::Term;
+use std::collections::BTreeSet;
+use DocId;
+use Result;
+use Score;
+
+/// `DisjunctionMaxQuery` matches the documents matching any of its
+/// subqueries, and scores them by the best of their subqueries' scores.
+///
+/// The score of a document is the maximum score of the subqueries it
+/// matches, plus `tie_breaker` times the sum of the scores of the other
+/// subqueries it matches. With a `tie_breaker` of `0`, only the best
+/// subquery counts, and with a `tie_breaker` of `1`, the scores are summed
+/// as in a `BooleanQuery`.
+///
+/// It is typically used to search a term in several fields:
+/// a document matching the term in its title and in its body should
+/// not score much higher than a document matching it in its title only.
+#[derive(Debug)]
+pub struct DisjunctionMaxQuery {
+    subqueries: Vec<Box<Query>>,
+    tie_breaker: Score,
+}
+
+impl DisjunctionMaxQuery {
+    /// Creates a new `DisjunctionMaxQuery`.
+    pub fn new(subqueries: Vec<Box<Query>>, tie_breaker: Score) -> DisjunctionMaxQuery {
+        DisjunctionMaxQuery {
+            subqueries,
+            tie_breaker,
+        }
+    }
+}
+
+impl Query for DisjunctionMaxQuery {
+    fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
+        let weights = self.subqueries
+            .iter()
+            .map(|subquery| searcher.create_weight(&**subquery, scoring_enabled))
+            .collect::<Result<_>>()?;
+        Ok(box DisjunctionMaxWeight {
+            weights,
+            tie_breaker: self.tie_breaker,
+        })
+    }
+
+    fn query_terms(&self, term_set: &mut BTreeSet<Term>) {
+        for subquery in &self.subqueries {
+            subquery.query_terms(term_set);
+        }
+    }
+}
+
+struct DisjunctionMaxWeight {
+    weights: Vec<Box<Weight>>,
+    tie_breaker: Score,
+}
+
+impl Weight for DisjunctionMaxWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let mut scorers = self.weights
+            .iter()
+            .map(|weight| weight.scorer(reader))
+            .collect::<Result<Vec<_>>>()?;
+        match scorers.len() {
+            0 => Ok(box EmptyScorer),
+            1 => Ok(scorers.pop().unwrap()),
+            _ => Ok(box DisjunctionMaxScorer::new(scorers, self.tie_breaker)),
+        }
+    }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        let mut cost = 0u64;
+        for weight in &self.weights {
+            cost += weight.cost(reader)?;
+        }
+        Ok(cost)
+    }
+}
+
+/// Scorer associated to the `DisjunctionMaxQuery`.
+///
+/// The number of subqueries is expected to be small, typically
+/// the number of fields searched, so that the scorers are simply
+/// scanned to find the next document.
+struct DisjunctionMaxScorer {
+    // the scorers that are not exhausted yet.
+    scorers: Vec<Box<Scorer>>,
+    started: bool,
+    doc: DocId,
+    tie_breaker: Score,
+    score_cache: Option<Score>,
+}
+
+impl DisjunctionMaxScorer {
+    fn new(scorers: Vec<Box<Scorer>>, tie_breaker: Score) -> DisjunctionMaxScorer {
+        DisjunctionMaxScorer {
+            scorers,
+            started: false,
+            doc: 0u32,
+            tie_breaker,
+            score_cache: None,
+        }
+    }
+}
+
+impl DocSet for DisjunctionMaxScorer {
+    fn advance(&mut self) -> bool {
+        self.score_cache = None;
+        let mut i = 0;
+        while i < self.scorers.len() {
+            let is_on_doc = self.started && self.scorers[i].doc() == self.doc;
+            if (!self.started || is_on_doc) && !self.scorers[i].advance() {
+                self.scorers.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        self.started = true;
+        if let Some(doc) = self.scorers.iter().map(|scorer| scorer.doc()).min() {
+            self.doc = doc;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorers
+            .iter()
+            .map(|scorer| scorer.size_hint())
+            .max()
+            .unwrap_or(0u32)
+    }
+}
+
+impl Scorer for DisjunctionMaxScorer {
+    fn score(&mut self) -> Score {
+        if let Some(score) = self.score_cache {
+            return score;
+        }
+        let doc = self.doc;
+        let mut max_score = 0f32;
+        let mut sum_score = 0f32;
+        for scorer in &mut self.scorers {
+            if scorer.doc() == doc {
+                let score = scorer.score();
+                max_score = max_score.max(score);
+                sum_score += score;
+            }
+        }
+        let score = max_score + self.tie_breaker * (sum_score - max_score);
+        self.score_cache = Some(score);
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use collector::TopCollector;
+    use docset::DocSet;
+    use query::{DisjunctionMaxQuery, Query, Scorer, TermQuery, VecDocSet};
+    use schema::{Field, IndexRecordOption, SchemaBuilder, TEXT};
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use super::DisjunctionMaxScorer;
+    use DocId;
+    use Score;
+
+    struct CountingScorer {
+        docset: VecDocSet,
+        num_score_calls: Rc<Cell<usize>>,
+    }
+
+    impl DocSet for CountingScorer {
+        fn advance(&mut self) -> bool {
+            self.docset.advance()
+        }
+
+        fn doc(&self) -> DocId {
+            self.docset.doc()
+        }
+
+        fn size_hint(&self) -> u32 {
+            self.docset.size_hint()
+        }
+    }
+
+    impl Scorer for CountingScorer {
+        fn score(&mut self) -> Score {
+            self.num_score_calls.set(self.num_score_calls.get() + 1);
+            1f32
+        }
+    }
+
+    #[test]
+    fn test_disjunction_max_scorer_caches_score() {
+        let num_score_calls = Rc::new(Cell::new(0));
+        let scorers: Vec<Box<Scorer>> = vec![vec![1, 3], vec![3, 5]]
+            .into_iter()
+            .map(|docs| -> Box<Scorer> {
+                box CountingScorer {
+                    docset: VecDocSet::from(docs),
+                    num_score_calls: Rc::clone(&num_score_calls),
+                }
+            })
+            .collect();
+        let mut scorer = DisjunctionMaxScorer::new(scorers, 0.5f32);
+        let mut doc_scores = vec![];
+        while scorer.advance() {
+            let score = scorer.score();
+            assert_eq!(scorer.score(), score);
+            doc_scores.push((scorer.doc(), score));
+        }
+        assert_eq!(doc_scores, vec![(1, 1f32), (3, 1.5f32), (5, 1f32)]);
+        assert_eq!(num_score_calls.get(), 4);
+    }
+
+    #[test]
+    fn test_disjunction_max_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title_field = schema_builder.add_text_field("title", TEXT);
+        let body_field = schema_builder.add_text_field("body", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(title_field=>"a", body_field=>"b"));
+        index_writer.add_document(doc!(title_field=>"b", body_field=>"a"));
+        index_writer.add_document(doc!(title_field=>"a", body_field=>"a"));
+        index_writer.add_document(doc!(title_field=>"c", body_field=>"c"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let term_query = |field: Field| -> Box<Query> {
+            box TermQuery::new(Term::from_field_text(field, "a"), IndexRecordOption::Basic)
+        };
+        let doc_scores = |query: &Query| -> Vec<(DocId, Score)> {
+            let mut top_collector = TopCollector::with_limit(10);
+            searcher.search(query, &mut top_collector).unwrap();
+            let mut doc_scores: Vec<(DocId, Score)> = top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(score, doc_address)| (doc_address.doc(), score))
+                .collect();
+            doc_scores.sort_by_key(|&(doc, _)| doc);
+            doc_scores
+        };
+        let title_scores = doc_scores(&*term_query(title_field));
+        let body_scores = doc_scores(&*term_query(body_field));
+        let (title_score, body_score) = (title_scores[0].1, body_scores[0].1);
+        for &tie_breaker in &[0f32, 0.5f32] {
+            let query = DisjunctionMaxQuery::new(
+                vec![term_query(title_field), term_query(body_field)],
+                tie_breaker,
+            );
+            let scores = doc_scores(&query);
+            let docs: Vec<DocId> = scores.iter().map(|&(doc, _)| doc).collect();
+            assert_eq!(docs, vec![0, 1, 2]);
+            assert!((scores[0].1 - title_score).abs() < 0.0001f32);
+            assert!((scores[1].1 - body_score).abs() < 0.0001f32);
+            let expected_score =
+                title_score.max(body_score) + tie_breaker * title_score.min(body_score);
+            assert!((scores[2].1 - expected_score).abs() < 0.0001f32);
+            assert_eq!(query.count(&searcher).unwrap(), 3);
+        }
+    }
+}
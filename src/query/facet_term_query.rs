@@ -0,0 +1,142 @@
+use core::{SegmentReader, Searcher};
+use query::{ConstScorer, Query, Scorer, TermQuery, Weight};
+use query::term_query::TermWeight;
+use schema::{Facet, Field, IndexRecordOption, Term};
+use std::collections::BTreeSet;
+use Result;
+
+/// `FacetTermQuery` matches all of the documents associated
+/// with a facet, or with any of its descendants.
+///
+/// For instance, the facet `/category/electronics` matches the documents
+/// associated with `/category/electronics/phones`.
+///
+/// Along with the `FacetCollector`, it makes it possible to drill down
+/// into a hierarchy of facets: once a facet has been picked among the
+/// facet counts, the search is narrowed down to this facet with a
+/// `FacetTermQuery`, and the counts of its children are collected.
+///
+/// As facets are indexed along with all of their ancestors,
+/// the query only needs to look up the term of the facet.
+///
+/// Matched documents all get a constant `Score` of one.
+#[derive(Debug)]
+pub struct FacetTermQuery {
+    term: Term,
+}
+
+impl FacetTermQuery {
+    /// Creates a new `FacetTermQuery` over a facet field.
+    pub fn new(field: Field, facet: &Facet) -> FacetTermQuery {
+        FacetTermQuery {
+            term: Term::from_facet(field, facet),
+        }
+    }
+}
+
+impl Query for FacetTermQuery {
+    fn weight(&self, searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        let term_query = TermQuery::new(self.term.clone(), IndexRecordOption::Basic);
+        Ok(box FacetTermWeight {
+            term_weight: term_query.specialized_weight(searcher, false),
+        })
+    }
+
+    fn query_terms(&self, term_set: &mut BTreeSet<Term>) {
+        term_set.insert(self.term.clone());
+    }
+}
+
+struct FacetTermWeight {
+    term_weight: TermWeight,
+}
+
+impl Weight for FacetTermWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let term_scorer = self.term_weight.specialized_scorer(reader)?;
+        Ok(box ConstScorer::new(term_scorer))
+    }
+
+    fn count(&self, reader: &SegmentReader) -> Result<u32> {
+        self.term_weight.count(reader)
+    }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        self.term_weight.cost(reader)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use collector::{CountCollector, FacetCollector, MultiCollector, TopCollector};
+    use query::Query;
+    use schema::{Facet, SchemaBuilder};
+    use super::FacetTermQuery;
+
+    #[test]
+    fn test_facet_term_query_drilldown() {
+        let mut schema_builder = SchemaBuilder::new();
+        let facet_field = schema_builder.add_facet_field("facet");
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for facet in &[
+            "/category/electronics/phones",
+            "/category/electronics/phones",
+            "/category/electronics/tv",
+            "/category/electronics",
+            "/category/books",
+            "/category/electronicsandmore",
+        ] {
+            index_writer.add_document(doc!(facet_field => Facet::from(*facet)));
+        }
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        let count = |facet: &str| {
+            FacetTermQuery::new(facet_field, &Facet::from(facet))
+                .count(&*searcher)
+                .unwrap()
+        };
+        assert_eq!(count("/category"), 6);
+        assert_eq!(count("/category/electronics"), 4);
+        assert_eq!(count("/category/electronics/phones"), 2);
+        assert_eq!(count("/category/electronics/phones/android"), 0);
+        assert_eq!(count("/category/elec"), 0);
+
+        let query = FacetTermQuery::new(facet_field, &Facet::from("/category/electronics"));
+        let mut facet_collector = FacetCollector::for_field(facet_field);
+        facet_collector.add_facet("/category/electronics");
+        let mut top_collector = TopCollector::with_limit(10);
+        let mut count_collector = CountCollector::default();
+        {
+            let mut multi_collector = MultiCollector::from(vec![
+                &mut facet_collector,
+                &mut top_collector,
+                &mut count_collector,
+            ]);
+            query.search(&*searcher, &mut multi_collector).unwrap();
+        }
+        assert_eq!(count_collector.count(), 4);
+        assert!(
+            top_collector
+                .score_docs()
+                .iter()
+                .all(|&(score, _)| score == 1f32)
+        );
+        let facet_counts: Vec<(String, u64)> = facet_collector
+            .harvest()
+            .get("/category/electronics")
+            .map(|(facet, count)| (facet.to_string(), count))
+            .collect();
+        assert_eq!(
+            facet_counts,
+            vec![
+                ("/category/electronics/phones".to_string(), 2),
+                ("/category/electronics/tv".to_string(), 1),
+            ]
+        );
+    }
+}
@@ -4,6 +4,9 @@ use DocId;
 use Score;
 
 /// Creates a `DocSet` that iterator through the intersection of two `DocSet`s.
+///
+/// Intersections of two or three `DocSet`s, which are the most common,
+/// are advanced by specialized implementations.
 pub struct Intersection<TDocSet: DocSet> {
     docsets: Vec<TDocSet>,
     finished: bool,
@@ -22,6 +25,92 @@ impl<TDocSet: DocSet> From<Vec<TDocSet>> for Intersection<TDocSet> {
     }
 }
 
+// Advances `left` and `right` to their next common document,
+// which is returned.
+//
+// `left` and `right` must be positioned on `doc`, or not started.
+fn advance_two<TDocSet: DocSet>(
+    left: &mut TDocSet,
+    right: &mut TDocSet,
+    doc: DocId,
+) -> Option<DocId> {
+    if left.skip_next(doc) == SkipResult::End {
+        return None;
+    }
+    let mut candidate = left.doc();
+    loop {
+        // `left` is on the candidate, `right` is before it.
+        match right.skip_next(candidate) {
+            SkipResult::End => return None,
+            SkipResult::Reached => return Some(candidate),
+            SkipResult::OverStep => candidate = right.doc(),
+        }
+        // `right` is on the candidate, `left` is before it.
+        match left.skip_next(candidate) {
+            SkipResult::End => return None,
+            SkipResult::Reached => return Some(candidate),
+            SkipResult::OverStep => candidate = left.doc(),
+        }
+    }
+}
+
+// Advances `a`, `b` and `c` to their next common document,
+// which is returned.
+//
+// `a`, `b` and `c` must be positioned on `doc`, or not started.
+fn advance_three<TDocSet: DocSet>(
+    a: &mut TDocSet,
+    b: &mut TDocSet,
+    c: &mut TDocSet,
+    doc: DocId,
+) -> Option<DocId> {
+    if a.skip_next(doc) == SkipResult::End {
+        return None;
+    }
+    let mut candidate = a.doc();
+    let mut c_on_candidate = false;
+    loop {
+        // `a` is on the candidate, `b` is before it.
+        match b.skip_next(candidate) {
+            SkipResult::End => return None,
+            SkipResult::Reached => {}
+            SkipResult::OverStep => {
+                candidate = b.doc();
+                c_on_candidate = false;
+                match a.skip_next(candidate) {
+                    SkipResult::End => return None,
+                    SkipResult::Reached => {}
+                    SkipResult::OverStep => {
+                        candidate = a.doc();
+                        continue;
+                    }
+                }
+            }
+        }
+        if c_on_candidate {
+            return Some(candidate);
+        }
+        // `a` and `b` are on the candidate, `c` is before it.
+        match c.skip_next(candidate) {
+            SkipResult::End => return None,
+            SkipResult::Reached => return Some(candidate),
+            SkipResult::OverStep => {
+                candidate = c.doc();
+                match a.skip_next(candidate) {
+                    SkipResult::End => return None,
+                    SkipResult::Reached => {
+                        c_on_candidate = true;
+                    }
+                    SkipResult::OverStep => {
+                        candidate = a.doc();
+                        c_on_candidate = false;
+                    }
+                }
+            }
+        }
+    }
+}
+
 impl<TDocSet: DocSet> Intersection<TDocSet> {
     /// Returns an array to the underlying `DocSet`s of the intersection.
     /// These `DocSet` are in the same position as the `IntersectionDocSet`,
@@ -29,6 +118,44 @@ impl<TDocSet: DocSet> Intersection<TDocSet> {
     pub fn docsets(&self) -> &[TDocSet] {
         &self.docsets[..]
     }
+
+    // Goes bucket by bucket through the `DocSet`s reading their
+    // documents from bitsets, and-ing their buckets directly.
+    //
+    // Returns `None` as soon as one of the `DocSet`s cannot provide its
+    // bucket, in which case the `DocSet`s are left where they were.
+    fn advance_bitsets(&mut self) -> Option<DocId> {
+        let mut bucket = self.doc / 64;
+        loop {
+            let mut bucket_docs = !0u64;
+            for docset in &self.docsets {
+                bucket_docs &= docset.bitset_bucket(bucket)?;
+            }
+            if bucket_docs != 0u64 {
+                let doc = bucket * 64 + bucket_docs.trailing_zeros();
+                for docset in &mut self.docsets {
+                    let skip_result = docset.skip_next(doc);
+                    debug_assert_eq!(skip_result, SkipResult::Reached);
+                }
+                return Some(doc);
+            }
+            bucket += 1;
+        }
+    }
+
+    fn advance_specialized(&mut self) -> Option<DocId> {
+        let doc = self.doc;
+        let (first, others) = self.docsets
+            .split_first_mut()
+            .expect("An intersection has at least two docsets");
+        let (second, others) = others
+            .split_first_mut()
+            .expect("An intersection has at least two docsets");
+        match others.first_mut() {
+            None => advance_two(first, second, doc),
+            Some(third) => advance_three(first, second, third, doc),
+        }
+    }
 }
 
 impl<TDocSet: DocSet> DocSet for Intersection<TDocSet> {
@@ -38,6 +165,24 @@ impl<TDocSet: DocSet> DocSet for Intersection<TDocSet> {
             return false;
         }
 
+        if let Some(doc) = self.advance_bitsets() {
+            self.doc = doc;
+            return true;
+        }
+
+        if self.docsets.len() <= 3 {
+            match self.advance_specialized() {
+                Some(doc) => {
+                    self.doc = doc;
+                    return true;
+                }
+                None => {
+                    self.finished = true;
+                    return false;
+                }
+            }
+        }
+
         let mut candidate_doc = self.doc;
         let mut candidate_ord = self.docsets.len();
 
@@ -211,6 +356,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_intersection_specialized() {
+        let docs: Vec<Vec<u32>> = (2u32..6u32)
+            .map(|modulo| (0u32..1_000u32).filter(|doc| doc % modulo != 1).collect())
+            .collect();
+        for num_docsets in 2..5 {
+            let expected: Vec<u32> = (0u32..1_000u32)
+                .filter(|doc| docs[..num_docsets].iter().all(|docs| docs.contains(doc)))
+                .collect();
+            let docsets: Vec<VecDocSet> = docs[..num_docsets]
+                .iter()
+                .cloned()
+                .map(VecDocSet::from)
+                .collect();
+            let mut intersection = Intersection::from(docsets);
+            let mut intersected = Vec::new();
+            while intersection.advance() {
+                intersected.push(intersection.doc());
+            }
+            assert_eq!(intersected, expected);
+        }
+    }
+
     #[test]
     fn test_intersection_empty() {
         let a = VecDocSet::from(vec![1, 3]);
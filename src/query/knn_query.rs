@@ -0,0 +1,213 @@
+use std::cmp;
+
+use core::SegmentReader;
+use core::Searcher;
+use docset::DocSet;
+use error::ErrorKind;
+use query::{Query, Scorer, Weight};
+use schema::Field;
+use DocId;
+use Result;
+use Score;
+
+/// Minimum number of candidates explored in the approximate
+/// nearest neighbor index of each segment.
+const MIN_NUM_CANDIDATES: usize = 100;
+
+/// `KnnQuery` matches the `k` documents whose vectors are the
+/// most similar to the query vector, and scores them by their similarity.
+///
+/// The field must have been declared with an approximate nearest
+/// neighbor index (see `VectorOptions::set_ann_index`), and the similarity
+/// used for the scores is the one of this index.
+///
+/// # Implementation
+///
+/// The HNSW graph of each segment is searched for its `k` nearest neighbors,
+/// so that up to `k` documents are matched per segment. Collecting
+/// the `k` best documents, with a `TopCollector`, gives the nearest
+/// neighbors over the whole index.
+///
+/// The results are approximate: some of the true nearest neighbors may be missed.
+/// Increasing the number of candidates improves the recall,
+/// at the expense of speed.
+#[derive(Debug)]
+pub struct KnnQuery {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    num_candidates: usize,
+}
+
+impl KnnQuery {
+    /// Creates a new `KnnQuery` retrieving the `k` nearest
+    /// neighbors of `vector` in the vector field `field`.
+    pub fn new(field: Field, vector: Vec<f32>, k: usize) -> KnnQuery {
+        KnnQuery {
+            field,
+            vector,
+            k,
+            num_candidates: cmp::max(k, MIN_NUM_CANDIDATES),
+        }
+    }
+
+    /// Sets the number of candidates explored in the graph of each segment.
+    ///
+    /// It is always at least `k`.
+    pub fn set_num_candidates(&mut self, num_candidates: usize) {
+        self.num_candidates = cmp::max(self.k, num_candidates);
+    }
+}
+
+impl Query for KnnQuery {
+    fn weight(&self, _searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        Ok(box KnnWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            k: self.k,
+            num_candidates: self.num_candidates,
+        })
+    }
+}
+
+struct KnnWeight {
+    field: Field,
+    vector: Vec<f32>,
+    k: usize,
+    num_candidates: usize,
+}
+
+impl Weight for KnnWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let vector_reader = reader.vector_fast_field_reader(self.field)?;
+        if vector_reader.dimension() != self.vector.len() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The query vector has a dimension of {}, while the field {:?} \
+                 has a dimension of {}.",
+                self.vector.len(),
+                self.field,
+                vector_reader.dimension()
+            )));
+        }
+        let neighbors = vector_reader
+            .nearest_neighbors(&self.vector, self.num_candidates)
+            .ok_or_else(|| {
+                ErrorKind::InvalidArgument(format!(
+                    "The field {:?} does not have an approximate nearest neighbor index.",
+                    self.field
+                ))
+            })?;
+        let mut hits: Vec<(DocId, Score)> = neighbors
+            .into_iter()
+            .filter(|&(doc, _)| !reader.is_deleted(doc))
+            .take(self.k)
+            .collect();
+        hits.sort_by_key(|&(doc, _)| doc);
+        Ok(box KnnScorer { hits, cursor: None })
+    }
+}
+
+/// Scorer over the nearest neighbors found in a segment,
+/// sorted by `DocId`.
+struct KnnScorer {
+    hits: Vec<(DocId, Score)>,
+    cursor: Option<usize>,
+}
+
+impl DocSet for KnnScorer {
+    fn advance(&mut self) -> bool {
+        let cursor = self.cursor.map(|cursor| cursor + 1).unwrap_or(0);
+        self.cursor = Some(cursor);
+        cursor < self.hits.len()
+    }
+
+    fn doc(&self) -> DocId {
+        self.hits[self.cursor.expect("advance() was never called")].0
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.hits.len() as u32
+    }
+}
+
+impl Scorer for KnnScorer {
+    fn score(&mut self) -> Score {
+        self.hits[self.cursor.expect("advance() was never called")].1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use Index;
+    use collector::TopCollector;
+    use futures::Future;
+    use schema::{SchemaBuilder, Term, VectorOptions, VectorSimilarity, FAST, INT_INDEXED};
+
+    #[test]
+    fn test_knn_query() {
+        let mut schema_builder = SchemaBuilder::default();
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED | FAST);
+        let vector_options =
+            VectorOptions::with_dimension(2).set_ann_index(VectorSimilarity::Cosine);
+        let embedding_field =
+            schema_builder.add_vector_field_with_options("embedding", vector_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        // vectors on the unit circle, with an angle of `id` degrees.
+        for id in 0u64..360u64 {
+            let angle = (id as f32).to_radians();
+            index_writer.add_document(doc!(
+                id_field=>id,
+                embedding_field=>vec![angle.cos(), angle.sin()]
+            ));
+            if id % 90 == 89 {
+                index_writer.commit().unwrap();
+            }
+        }
+        index_writer.add_document(doc!(id_field=>360u64));
+        index_writer.commit().unwrap();
+
+        let top_ids = |index: &Index| {
+            index.load_searchers().unwrap();
+            let searcher = index.searcher();
+            let angle = 90.4f32.to_radians();
+            let query = KnnQuery::new(embedding_field, vec![angle.cos(), angle.sin()], 3);
+            let mut top_collector = TopCollector::with_limit(3);
+            searcher.search(&query, &mut top_collector).unwrap();
+            top_collector
+                .score_docs()
+                .into_iter()
+                .map(|(_, doc_address)| {
+                    let segment_reader = searcher.segment_reader(doc_address.segment_ord());
+                    let id_reader = segment_reader.fast_field_reader::<u64>(id_field).unwrap();
+                    id_reader.get(doc_address.doc())
+                })
+                .collect::<Vec<u64>>()
+        };
+        assert_eq!(top_ids(&index), vec![90, 91, 89]);
+
+        index_writer.delete_term(Term::from_field_u64(id_field, 90u64));
+        index_writer.commit().unwrap();
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index_writer.wait_merging_threads().unwrap();
+        assert_eq!(top_ids(&index), vec![91, 89, 92]);
+    }
+
+    #[test]
+    fn test_knn_query_without_ann_index() {
+        let mut schema_builder = SchemaBuilder::default();
+        let embedding_field = schema_builder.add_vector_field("embedding", 2);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(embedding_field=>vec![1f32, 0f32]));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = KnnQuery::new(embedding_field, vec![1f32, 0f32], 1);
+        let mut top_collector = TopCollector::with_limit(1);
+        assert!(searcher.search(&query, &mut top_collector).is_err());
+    }
+}
@@ -17,6 +17,14 @@ mod exclude;
 mod union;
 mod intersection;
 mod reqopt_scorer;
+mod vector_similarity_query;
+mod knn_query;
+mod cached_filter_query;
+mod profile;
+mod boost_query;
+mod disjunction_max_query;
+mod facet_term_query;
+mod sorted_iter_docset;
 
 #[cfg(test)]
 mod vec_docset;
@@ -32,10 +40,16 @@ pub use self::vec_docset::VecDocSet;
 pub use self::reqopt_scorer::RequiredOptionalScorer;
 pub use self::exclude::Exclude;
 pub use self::bitset::BitSetDocSet;
+pub use self::sorted_iter_docset::DocSetFromSortedIter;
+pub use common::BitSet;
 pub use self::boolean_query::BooleanQuery;
+pub use self::boost_query::BoostQuery;
+pub use self::disjunction_max_query::DisjunctionMaxQuery;
+pub use self::facet_term_query::FacetTermQuery;
 pub use self::occur::Occur;
 pub use self::phrase_query::PhraseQuery;
 pub use self::query_parser::QueryParserError;
+pub use self::query_parser::QueryParserWarning;
 pub use self::query_parser::QueryParser;
 pub use self::query::Query;
 pub use self::scorer::EmptyScorer;
@@ -44,4 +58,10 @@ pub use self::term_query::TermQuery;
 pub use self::weight::Weight;
 pub use self::all_query::{AllQuery, AllScorer, AllWeight};
 pub use self::range_query::RangeQuery;
+pub use self::vector_similarity_query::VectorSimilarityQuery;
+pub use self::knn_query::KnnQuery;
+pub use self::cached_filter_query::CachedFilterQuery;
+pub use self::profile::{CallStats, QueryProfile};
+pub(crate) use self::profile::QueryProfiler;
+pub use schema::VectorSimilarity;
 pub use self::scorer::ConstScorer;
@@ -11,7 +11,7 @@ mod tests {
 
     use super::*;
     use core::Index;
-    use schema::{SchemaBuilder, Term, TEXT};
+    use schema::{IndexRecordOption, SchemaBuilder, Term, TextFieldIndexing, TextOptions, TEXT};
     use collector::tests::TestCollector;
 
     #[test]
@@ -117,4 +117,79 @@ mod tests {
         assert_eq!(test_query(vec!["a", "b"]), vec![1]);
         assert_eq!(test_query(vec!["b", "a"]), vec![2]);
     }
+
+    #[test]
+    pub fn test_phrase_query_position_gap() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+            .set_position_gap(0);
+        let concatenated_field = schema_builder.add_text_field(
+            "concatenated",
+            TextOptions::default().set_indexing_options(text_field_indexing),
+        );
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(
+                text_field => "a b",
+                text_field => "c d",
+                concatenated_field => "a b",
+                concatenated_field => "c d"
+            ));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let test_query = |field, texts: Vec<&str>| {
+            let mut test_collector = TestCollector::default();
+            let terms: Vec<Term> = texts
+                .iter()
+                .map(|text| Term::from_field_text(field, text))
+                .collect();
+            let phrase_query = PhraseQuery::from(terms);
+            searcher
+                .search(&phrase_query, &mut test_collector)
+                .expect("search should succeed");
+            test_collector.docs()
+        };
+        assert_eq!(test_query(text_field, vec!["a", "b"]), vec![0]);
+        assert_eq!(test_query(text_field, vec!["c", "d"]), vec![0]);
+        assert!(test_query(text_field, vec!["b", "c"]).is_empty());
+        assert_eq!(test_query(concatenated_field, vec!["b", "c"]), vec![0]);
+        assert_eq!(test_query(concatenated_field, vec!["a", "b", "c", "d"]), vec![0]);
+    }
+
+    #[test]
+    pub fn test_phrase_query_with_offset() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(text_field => "a b c d"));
+            index_writer.add_document(doc!(text_field => "a c b d"));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let test_query = |terms: Vec<(usize, &str)>| {
+            let mut test_collector = TestCollector::default();
+            let terms: Vec<(usize, Term)> = terms
+                .into_iter()
+                .map(|(offset, text)| (offset, Term::from_field_text(text_field, text)))
+                .collect();
+            let phrase_query = PhraseQuery::new_with_offset(terms);
+            searcher
+                .search(&phrase_query, &mut test_collector)
+                .expect("search should succeed");
+            test_collector.docs()
+        };
+        assert_eq!(test_query(vec![(0, "a"), (2, "c")]), vec![0]);
+        assert_eq!(test_query(vec![(0, "a"), (1, "c")]), vec![1]);
+        assert_eq!(test_query(vec![(3, "d"), (1, "b")]), vec![0]);
+        assert_eq!(test_query(vec![(0, "a"), (1, "b"), (3, "d")]), vec![0]);
+        assert_eq!(test_query(vec![(0, "a"), (3, "d")]), vec![0, 1]);
+    }
 }
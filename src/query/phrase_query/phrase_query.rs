@@ -4,6 +4,7 @@ use core::searcher::Searcher;
 use super::PhraseWeight;
 use query::Weight;
 use Result;
+use std::collections::BTreeSet;
 
 /// `PhraseQuery` matches a specific sequence of words.
 ///
@@ -21,7 +22,30 @@ use Result;
 ///
 #[derive(Debug)]
 pub struct PhraseQuery {
-    phrase_terms: Vec<Term>,
+    phrase_terms: Vec<(usize, Term)>,
+}
+
+impl PhraseQuery {
+    /// Creates a `PhraseQuery` given its terms, along with
+    /// their position offset within the phrase.
+    ///
+    /// Offsets that are not consecutive leave positions between the
+    /// terms, that any term can fill. They make it possible to search
+    /// phrases from which stop words were removed, in a field indexed
+    /// with the positions of the stop words left empty.
+    /// (See `StopWordFilter`.)
+    ///
+    /// # Panics
+    /// If there are less than two terms, panics.
+    pub fn new_with_offset(mut phrase_terms: Vec<(usize, Term)>) -> PhraseQuery {
+        assert!(phrase_terms.len() > 1);
+        phrase_terms.sort_by_key(|&(offset, _)| offset);
+        let first_offset = phrase_terms[0].0;
+        for &mut (ref mut offset, _) in &mut phrase_terms {
+            *offset -= first_offset;
+        }
+        PhraseQuery { phrase_terms }
+    }
 }
 
 impl Query for PhraseQuery {
@@ -34,11 +58,14 @@ impl Query for PhraseQuery {
             scoring_enabled,
         ))
     }
+
+    fn query_terms(&self, term_set: &mut BTreeSet<Term>) {
+        term_set.extend(self.phrase_terms.iter().map(|&(_, ref term)| term.clone()));
+    }
 }
 
 impl From<Vec<Term>> for PhraseQuery {
     fn from(phrase_terms: Vec<Term>) -> PhraseQuery {
-        assert!(phrase_terms.len() > 1);
-        PhraseQuery { phrase_terms }
+        PhraseQuery::new_with_offset(phrase_terms.into_iter().enumerate().collect())
     }
 }
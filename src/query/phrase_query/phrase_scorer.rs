@@ -4,14 +4,14 @@ use postings::{Postings, SegmentPostings};
 use query::{Intersection, Scorer};
 
 struct PostingsWithOffset {
-    offset: u32,
+    ord: usize,
     segment_postings: SegmentPostings,
 }
 
 impl PostingsWithOffset {
-    pub fn new(segment_postings: SegmentPostings, offset: u32) -> PostingsWithOffset {
+    pub fn new(segment_postings: SegmentPostings, ord: usize) -> PostingsWithOffset {
         PostingsWithOffset {
-            offset,
+            ord,
             segment_postings,
         }
     }
@@ -47,17 +47,30 @@ impl DocSet for PostingsWithOffset {
 
 pub struct PhraseScorer {
     intersection_docset: Intersection<PostingsWithOffset>,
+    // position offset of each term within the phrase,
+    // the first term being at offset 0.
+    offsets: Vec<u32>,
 }
 
 impl PhraseScorer {
-    pub fn new(term_postings: Vec<SegmentPostings>) -> PhraseScorer {
+    /// Creates a `PhraseScorer` given the postings of the terms of
+    /// the phrase, along with their position offset within the phrase.
+    ///
+    /// Terms must be sorted by offset.
+    pub fn new(term_postings: Vec<(usize, SegmentPostings)>) -> PhraseScorer {
+        let first_offset = term_postings[0].0;
+        let offsets: Vec<u32> = term_postings
+            .iter()
+            .map(|&(offset, _)| (offset - first_offset) as u32)
+            .collect();
         let postings_with_offsets: Vec<_> = term_postings
             .into_iter()
             .enumerate()
-            .map(|(offset, postings)| PostingsWithOffset::new(postings, offset as u32))
+            .map(|(ord, (_, postings))| PostingsWithOffset::new(postings, ord))
             .collect();
         PhraseScorer {
             intersection_docset: Intersection::from(postings_with_offsets),
+            offsets,
         }
     }
 
@@ -70,7 +83,7 @@ impl PhraseScorer {
         let docsets = self.intersection_docset.docsets();
         let mut positions_arr: Vec<&[u32]> = vec![&[]; docsets.len()];
         for docset in docsets {
-            positions_arr[docset.offset as usize] = docset.positions();
+            positions_arr[docset.ord] = docset.positions();
         }
 
         let num_postings = positions_arr.len() as u32;
@@ -82,7 +95,7 @@ impl PhraseScorer {
 
         #[cfg_attr(feature = "cargo-clippy", allow(never_loop))]
         'outer: loop {
-            let target = pos_candidate + ord;
+            let target = pos_candidate + self.offsets[ord as usize];
             let positions = positions_arr[ord as usize];
             for (i, pos_i) in positions.iter().cloned().enumerate() {
                 if pos_i < target {
@@ -95,7 +108,7 @@ impl PhraseScorer {
                     }
                 } else if pos_i > target {
                     count_matching = 1;
-                    pos_candidate = positions[i] - ord;
+                    pos_candidate = positions[i] - self.offsets[ord as usize];
                     positions_arr[ord as usize] = &(positions_arr[ord as usize])[(i + 1)..];
                 }
                 ord += 1;
@@ -8,7 +8,7 @@ use query::EmptyScorer;
 use Result;
 
 pub struct PhraseWeight {
-    phrase_terms: Vec<Term>,
+    phrase_terms: Vec<(usize, Term)>,
 }
 
 impl PhraseWeight {
@@ -19,7 +19,7 @@ impl PhraseWeight {
     // TODO use the scoring disable information to avoid compute the
     // phrase freq in that case, and compute the phrase freq when scoring is enabled.
     // Right now we never compute it :|
-    pub fn new(phrase_terms: Vec<Term>, _scoring_enabled: bool) -> PhraseWeight {
+    pub fn new(phrase_terms: Vec<(usize, Term)>, _scoring_enabled: bool) -> PhraseWeight {
         PhraseWeight { phrase_terms }
     }
 }
@@ -27,16 +27,30 @@ impl PhraseWeight {
 impl Weight for PhraseWeight {
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
         let mut term_postings_list = Vec::new();
-        for term in &self.phrase_terms {
+        for &(offset, ref term) in &self.phrase_terms {
             if let Some(postings) = reader
                 .inverted_index(term.field())
-                .read_postings(term, IndexRecordOption::WithFreqsAndPositions)
+                .read_postings(term, IndexRecordOption::WithFreqsAndPositions)?
             {
-                term_postings_list.push(postings);
+                term_postings_list.push((offset, postings));
             } else {
                 return Ok(box EmptyScorer);
             }
         }
         Ok(box PhraseScorer::new(term_postings_list))
     }
+
+    /// The phrase scorer is driven by its rarest term.
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        let mut cost = u64::from(reader.max_doc());
+        for &(_, ref term) in &self.phrase_terms {
+            let doc_freq = reader
+                .inverted_index(term.field())
+                .get_term_info(term)
+                .map(|term_info| term_info.doc_freq)
+                .unwrap_or(0);
+            cost = cost.min(u64::from(doc_freq));
+        }
+        Ok(cost)
+    }
 }
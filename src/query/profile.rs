@@ -0,0 +1,293 @@
+use Result;
+use Score;
+use DocId;
+use core::{Searcher, SegmentReader};
+use docset::{DocSet, SkipResult};
+use query::{Query, Scorer, Weight};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Number of calls to a method of the scorers of a query node,
+/// and time spent in these calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallStats {
+    /// Number of calls.
+    pub num_calls: u64,
+    /// Overall time spent in the calls.
+    pub time: Duration,
+}
+
+impl CallStats {
+    fn record(&mut self, start: Instant) {
+        self.num_calls += 1;
+        self.time += start.elapsed();
+    }
+
+    fn merge(&mut self, other: &CallStats) {
+        self.num_calls += other.num_calls;
+        self.time += other.time;
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct NodeStats {
+    weight_time: Duration,
+    scorer_time: Duration,
+    advance: CallStats,
+    skip_next: CallStats,
+    score: CallStats,
+}
+
+/// Timings of a node of a query, returned by `Searcher::search_with_profile(...)`.
+///
+/// The timings of a node include the time spent in its children.
+#[derive(Clone, Debug)]
+pub struct QueryProfile {
+    /// Debug representation of the query.
+    pub query: String,
+    /// Time spent creating the `Weight` of the query.
+    pub weight_time: Duration,
+    /// Time spent creating the `Scorer`s of the query, over all of the segments.
+    pub scorer_time: Duration,
+    /// Calls to `DocSet::advance`.
+    pub advance: CallStats,
+    /// Calls to `DocSet::skip_next`.
+    pub skip_next: CallStats,
+    /// Calls to `Scorer::score`.
+    pub score: CallStats,
+    /// Profiles of the sub-queries.
+    pub children: Vec<QueryProfile>,
+}
+
+impl QueryProfile {
+    /// Returns the overall time spent in the query node.
+    pub fn total_time(&self) -> Duration {
+        self.weight_time + self.scorer_time + self.advance.time + self.skip_next.time
+            + self.score.time
+    }
+}
+
+struct ProfileNode {
+    query: String,
+    stats: Mutex<NodeStats>,
+    children: Mutex<Vec<Arc<ProfileNode>>>,
+}
+
+impl ProfileNode {
+    fn stats(&self) -> MutexGuard<NodeStats> {
+        self.stats.lock().expect("Profile lock poisoned")
+    }
+
+    fn to_profile(&self) -> QueryProfile {
+        let stats = self.stats().clone();
+        let children = self.children
+            .lock()
+            .expect("Profile lock poisoned")
+            .iter()
+            .map(|child| child.to_profile())
+            .collect();
+        QueryProfile {
+            query: self.query.clone(),
+            weight_time: stats.weight_time,
+            scorer_time: stats.scorer_time,
+            advance: stats.advance,
+            skip_next: stats.skip_next,
+            score: stats.score,
+            children,
+        }
+    }
+}
+
+/// Builds the tree of the weights created while a query is being profiled.
+///
+/// The weight of a query is created within the creation of the weight
+/// of its parent, so that the node on top of the stack is the parent
+/// of the next weight.
+#[derive(Default)]
+pub(crate) struct QueryProfiler {
+    stack: Mutex<Vec<Arc<ProfileNode>>>,
+    root: Mutex<Option<Arc<ProfileNode>>>,
+}
+
+impl QueryProfiler {
+    pub fn weight(
+        &self,
+        searcher: &Searcher,
+        query: &Query,
+        scoring_enabled: bool,
+    ) -> Result<Box<Weight>> {
+        let node = Arc::new(ProfileNode {
+            query: format!("{:?}", query),
+            stats: Mutex::default(),
+            children: Mutex::default(),
+        });
+        {
+            let mut stack = self.stack.lock().expect("Profile lock poisoned");
+            match stack.last() {
+                Some(parent) => parent
+                    .children
+                    .lock()
+                    .expect("Profile lock poisoned")
+                    .push(Arc::clone(&node)),
+                None => {
+                    *self.root.lock().expect("Profile lock poisoned") = Some(Arc::clone(&node));
+                }
+            }
+            stack.push(Arc::clone(&node));
+        }
+        let start = Instant::now();
+        let weight_res = query.weight(searcher, scoring_enabled);
+        node.stats().weight_time = start.elapsed();
+        self.stack.lock().expect("Profile lock poisoned").pop();
+        Ok(box ProfiledWeight {
+            weight: weight_res?,
+            node,
+        })
+    }
+
+    /// Returns the profile of the root query.
+    pub fn profile(&self) -> Option<QueryProfile> {
+        self.root
+            .lock()
+            .expect("Profile lock poisoned")
+            .as_ref()
+            .map(|root| root.to_profile())
+    }
+}
+
+struct ProfiledWeight {
+    weight: Box<Weight>,
+    node: Arc<ProfileNode>,
+}
+
+impl Weight for ProfiledWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let start = Instant::now();
+        let scorer = self.weight.scorer(reader)?;
+        self.node.stats().scorer_time += start.elapsed();
+        Ok(box ProfiledScorer {
+            scorer,
+            node: Arc::clone(&self.node),
+            advance: CallStats::default(),
+            skip_next: CallStats::default(),
+            score: CallStats::default(),
+        })
+    }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        self.weight.cost(reader)
+    }
+}
+
+/// Scorer timing the calls to the scorer it wraps.
+///
+/// Timings are accumulated locally, and added
+/// to the profile node when the scorer is dropped.
+struct ProfiledScorer {
+    scorer: Box<Scorer>,
+    node: Arc<ProfileNode>,
+    advance: CallStats,
+    skip_next: CallStats,
+    score: CallStats,
+}
+
+impl DocSet for ProfiledScorer {
+    fn advance(&mut self) -> bool {
+        let start = Instant::now();
+        let has_next = self.scorer.advance();
+        self.advance.record(start);
+        has_next
+    }
+
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        let start = Instant::now();
+        let skip_result = self.scorer.skip_next(target);
+        self.skip_next.record(start);
+        skip_result
+    }
+
+    fn doc(&self) -> DocId {
+        self.scorer.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.scorer.size_hint()
+    }
+}
+
+impl Scorer for ProfiledScorer {
+    fn score(&mut self) -> Score {
+        let start = Instant::now();
+        let score = self.scorer.score();
+        self.score.record(start);
+        score
+    }
+
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        self.scorer.set_min_competitive_score(min_score);
+    }
+}
+
+impl Drop for ProfiledScorer {
+    fn drop(&mut self) {
+        let mut stats = self.node.stats();
+        stats.advance.merge(&self.advance);
+        stats.skip_next.merge(&self.skip_next);
+        stats.score.merge(&self.score);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use Term;
+    use collector::CountCollector;
+    use query::{BooleanQuery, Occur, Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, TEXT};
+
+    #[test]
+    fn test_search_with_profile() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(text_field=>"a b"));
+        index_writer.add_document(doc!(text_field=>"a"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(text_field=>"b c"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+
+        let term_query = |text: &str| -> Box<Query> {
+            box TermQuery::new(
+                Term::from_field_text(text_field, text),
+                IndexRecordOption::Basic,
+            )
+        };
+        let query = BooleanQuery::from(vec![
+            (Occur::Should, term_query("a")),
+            (Occur::Should, term_query("b")),
+        ]);
+        let mut count_collector = CountCollector::default();
+        let profile = searcher
+            .search_with_profile(&query, &mut count_collector)
+            .unwrap();
+        assert_eq!(count_collector.count(), 3);
+        assert_eq!(profile.query, format!("{:?}", query));
+        // one call per matching document, plus the last call of each segment.
+        assert_eq!(profile.advance.num_calls, 5);
+        assert_eq!(profile.children.len(), 2);
+        let (a_profile, b_profile) = (&profile.children[0], &profile.children[1]);
+        assert!(a_profile.children.is_empty());
+        assert!(a_profile.advance.num_calls >= 2);
+        assert!(b_profile.advance.num_calls >= 2);
+        assert!(profile.total_time() >= a_profile.advance.time);
+
+        // profiling does not leak into the searcher.
+        let mut count_collector = CountCollector::default();
+        searcher.search(&query, &mut count_collector).unwrap();
+        assert_eq!(count_collector.count(), 3);
+    }
+}
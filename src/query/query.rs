@@ -4,6 +4,8 @@ use core::searcher::Searcher;
 use common::TimerTree;
 use SegmentLocalId;
 use super::Weight;
+use schema::Term;
+use std::collections::BTreeSet;
 use std::fmt;
 
 /// The `Query` trait defines a set of documents and a scoring method
@@ -48,9 +50,27 @@ pub trait Query: fmt::Debug {
     /// See [`Weight`](./trait.Weight.html).
     fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>>;
 
+    /// Extracts the terms of the query into `term_set`.
+    ///
+    /// This is used to highlight the matches of the query,
+    /// (see `SnippetGenerator`). Queries that are not expressed as a set
+    /// of terms, such as range queries, do not add any term.
+    fn query_terms(&self, _term_set: &mut BTreeSet<Term>) {}
+
+    /// Returns the terms of the query.
+    ///
+    /// In a sharded deployment, their statistics can be gathered
+    /// from all of the shards beforehand, to score the query consistently
+    /// on each shard. See `Searcher::with_statistics_provider`.
+    fn extract_terms(&self) -> BTreeSet<Term> {
+        let mut term_set = BTreeSet::new();
+        self.query_terms(&mut term_set);
+        term_set
+    }
+
     /// Returns the number of documents matching the query.
     fn count(&self, searcher: &Searcher) -> Result<usize> {
-        let weight = self.weight(searcher, false)?;
+        let weight = searcher.restrict_weight(self.weight(searcher, false)?);
         let mut result = 0;
         for reader in searcher.segment_readers() {
             result += weight.count(reader)? as usize;
@@ -58,6 +78,21 @@ pub trait Query: fmt::Debug {
         Ok(result)
     }
 
+    /// Returns an estimate of the cost of the query, as the number
+    /// of documents its scorers will go through, over all of the segments.
+    ///
+    /// It is cheap to compute, and makes it possible to reject
+    /// queries that would be too expensive before running them.
+    /// See `Weight::cost`.
+    fn cost(&self, searcher: &Searcher) -> Result<u64> {
+        let weight = searcher.restrict_weight(self.weight(searcher, false)?);
+        let mut result = 0;
+        for reader in searcher.segment_readers() {
+            result += weight.cost(reader)?;
+        }
+        Ok(result)
+    }
+
     /// Search works as follows :
     ///
     /// First the weight object associated to the query is created.
@@ -69,20 +104,33 @@ pub trait Query: fmt::Debug {
     ///
     fn search(&self, searcher: &Searcher, collector: &mut Collector) -> Result<TimerTree> {
         let mut timer_tree = TimerTree::default();
+        let _span = span!("search", num_segments = searcher.segment_readers().len());
         let scoring_enabled = collector.requires_scoring();
-        let weight = self.weight(searcher, scoring_enabled)?;
+        let weight = searcher.restrict_weight(self.weight(searcher, scoring_enabled)?);
         {
             let mut search_timer = timer_tree.open("search");
             for (segment_ord, segment_reader) in searcher.segment_readers().iter().enumerate() {
                 let mut segment_search_timer = search_timer.open("segment_search");
+                let _segment_span = span!(
+                    "search_segment",
+                    segment_id = segment_reader.segment_id(),
+                    max_doc = segment_reader.max_doc()
+                );
                 {
                     let _ = segment_search_timer.open("set_segment");
                     collector.set_segment(segment_ord as SegmentLocalId, segment_reader)?;
                 }
-                let mut scorer = weight.scorer(segment_reader)?;
                 {
                     let _collection_timer = segment_search_timer.open("collection");
-                    scorer.collect(collector);
+                    if collector.has_min_competitive_score() {
+                        // the scorer is driven one document at a time, so that the
+                        // collector can push its minimum competitive score down to it.
+                        weight.scorer(segment_reader)?.collect(collector);
+                    } else {
+                        weight.for_each(segment_reader, &mut |doc, score| {
+                            collector.collect(doc, score)
+                        })?;
+                    }
                 }
             }
         }
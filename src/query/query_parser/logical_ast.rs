@@ -1,17 +1,28 @@
 use std::fmt;
-use schema::Term;
+use std::collections::Bound;
+use schema::{Facet, Field, Term};
 use query::Occur;
+use Score;
 
 #[derive(Clone)]
 pub enum LogicalLiteral {
     Term(Term),
-    Phrase(Vec<Term>),
+    /// Terms of a phrase, along with their position offset.
+    Phrase(Vec<(usize, Term)>),
+    Facet(Field, Facet),
+    Range {
+        field: Field,
+        lower: Bound<Term>,
+        upper: Bound<Term>,
+    },
 }
 
 #[derive(Clone)]
 pub enum LogicalAST {
     Clause(Vec<(Occur, LogicalAST)>),
     Leaf(Box<LogicalLiteral>),
+    Boost(Box<LogicalAST>, Score),
+    DisjunctionMax(Vec<LogicalAST>, Score),
 }
 
 fn occur_letter(occur: Occur) -> &'static str {
@@ -39,6 +50,17 @@ impl fmt::Debug for LogicalAST {
                 Ok(())
             }
             LogicalAST::Leaf(ref literal) => write!(formatter, "{:?}", literal),
+            LogicalAST::Boost(ref ast, boost) => write!(formatter, "({:?})^{}", ast, boost),
+            LogicalAST::DisjunctionMax(ref asts, tie_breaker) => {
+                write!(formatter, "DisMax(")?;
+                for (i, ast) in asts.iter().enumerate() {
+                    if i > 0 {
+                        formatter.write_str(" ")?;
+                    }
+                    write!(formatter, "{:?}", ast)?;
+                }
+                write!(formatter, ")~{}", tie_breaker)
+            }
         }
     }
 }
@@ -53,7 +75,28 @@ impl fmt::Debug for LogicalLiteral {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
         match *self {
             LogicalLiteral::Term(ref term) => write!(formatter, "{:?}", term),
-            LogicalLiteral::Phrase(ref terms) => write!(formatter, "\"{:?}\"", terms),
+            LogicalLiteral::Phrase(ref terms) => {
+                let terms: Vec<&Term> = terms.iter().map(|&(_, ref term)| term).collect();
+                write!(formatter, "\"{:?}\"", terms)
+            }
+            LogicalLiteral::Facet(_, ref facet) => write!(formatter, "{:?}", facet),
+            LogicalLiteral::Range {
+                ref lower,
+                ref upper,
+                ..
+            } => {
+                match *lower {
+                    Bound::Included(ref term) => write!(formatter, "[{:?}", term)?,
+                    Bound::Excluded(ref term) => write!(formatter, "{{{:?}", term)?,
+                    Bound::Unbounded => write!(formatter, "{{*")?,
+                }
+                formatter.write_str(" TO ")?;
+                match *upper {
+                    Bound::Included(ref term) => write!(formatter, "{:?}]", term),
+                    Bound::Excluded(ref term) => write!(formatter, "{:?}}}", term),
+                    Bound::Unbounded => write!(formatter, "*}}"),
+                }
+            }
         }
     }
 }
@@ -5,3 +5,4 @@ mod user_input_ast;
 pub mod logical_ast;
 pub use self::query_parser::QueryParser;
 pub use self::query_parser::QueryParserError;
+pub use self::query_parser::QueryParserWarning;
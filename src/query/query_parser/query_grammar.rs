@@ -2,31 +2,76 @@ use combine::*;
 use combine::char::*;
 use super::user_input_ast::*;
 
+/// Characters with a special meaning in the query syntax.
+///
+/// Outside of phrases, they must be escaped with a backslash to be part of a word.
+/// Within phrases, only the quote and the backslash must be escaped.
+pub(crate) const SPECIAL_CHARS: &'static str = "\\+-()[]{}\":<>*";
+
+fn is_special_char(c: char) -> bool {
+    SPECIAL_CHARS.contains(c)
+}
+
+/// Any character preceded by a backslash is taken literally.
+fn escaped_char<I>(input: I) -> ParseResult<char, I>
+where
+    I: Stream<Item = char>,
+{
+    (char('\\'), any()).map(|(_, c)| c).parse_stream(input)
+}
+
+fn field<I>(input: I) -> ParseResult<String, I>
+where
+    I: Stream<Item = char>,
+{
+    (
+        letter(),
+        many(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
+    ).map(|(s1, s2): (char, String)| format!("{}{}", s1, s2))
+        .parse_stream(input)
+}
+
 fn literal<I>(input: I) -> ParseResult<UserInputAST, I>
 where
     I: Stream<Item = char>,
 {
     let term_val = || {
-        let word = many1(satisfy(|c: char| c.is_alphanumeric()));
-        let phrase = (char('"'), many1(satisfy(|c| c != '"')), char('"')).map(|(_, s, _)| s);
+        let word = many1(
+            parser(escaped_char)
+                .or(satisfy(|c: char| !c.is_whitespace() && !is_special_char(c))),
+        );
+        let phrase = (
+            char('"'),
+            many1(parser(escaped_char).or(satisfy(|c| c != '"' && c != '\\'))),
+            char('"'),
+        ).map(|(_, s, _)| s);
         phrase.or(word)
     };
 
-    let negative_numbers = (char('-'), many1(satisfy(|c: char| c.is_numeric())))
-        .map(|(s1, s2): (char, String)| format!("{}{}", s1, s2));
-
-    let field = (
-        letter(),
-        many(satisfy(|c: char| c.is_alphanumeric() || c == '_')),
-    ).map(|(s1, s2): (char, String)| format!("{}{}", s1, s2));
+    // numbers and dates, such as `-3`, `2.5` or `2017-01-01T10:00:00Z`.
+    //
+    // They must be followed by a whitespace, a closing parenthesis,
+    // or the end of the query, so that values such as `3/4` are parsed as words.
+    let numeric_val = (
+        optional(char('-')),
+        satisfy(|c: char| c.is_numeric()),
+        many(parser(escaped_char).or(satisfy(|c: char| {
+            c.is_alphanumeric() || c == '-' || c == ':' || c == '.'
+        }))),
+        look_ahead(space().or(char(')')).map(|_| ()).or(eof())),
+    ).map(|(sign, s1, s2, _): (Option<char>, char, String, ())| match sign {
+        Some(sign) => format!("{}{}{}", sign, s1, s2),
+        None => format!("{}{}", s1, s2),
+    });
 
-    let term_val_with_field = negative_numbers.or(term_val());
+    let term_val_with_field = try(numeric_val).or(term_val());
 
-    let term_query =
-        (field, char(':'), term_val_with_field).map(|(field_name, _, phrase)| UserInputLiteral {
+    let term_query = (parser(field), char(':'), term_val_with_field).map(
+        |(field_name, _, phrase)| UserInputLiteral {
             field_name: Some(field_name),
             phrase,
-        });
+        },
+    );
     let term_default_field = term_val().map(|phrase| UserInputLiteral {
         field_name: None,
         phrase,
@@ -37,6 +82,60 @@ where
         .parse_stream(input)
 }
 
+fn range<I>(input: I) -> ParseResult<UserInputAST, I>
+where
+    I: Stream<Item = char>,
+{
+    // an unescaped `*` stands for an unbounded range.
+    let range_val = || {
+        many1(
+            parser(escaped_char)
+                .map(|c| (c, true))
+                .or(satisfy(|c: char| !c.is_whitespace() && !"[]{}()\"\\".contains(c))
+                    .map(|c| (c, false))),
+        ).map(|chars: Vec<(char, bool)>| {
+            if chars == [('*', false)] {
+                None
+            } else {
+                Some(chars.into_iter().map(|(c, _)| c).collect::<String>())
+            }
+        })
+    };
+    let make_bound = |val: Option<String>, inclusive: bool| match val {
+        None => UserInputBound::Unbounded,
+        Some(val) => if inclusive {
+            UserInputBound::Inclusive(val)
+        } else {
+            UserInputBound::Exclusive(val)
+        },
+    };
+    let lower = (one_of("[{".chars()), spaces(), range_val())
+        .map(|(boundary, _, val): (char, _, Option<String>)| make_bound(val, boundary == '['));
+    let upper = (range_val(), spaces(), one_of("]}".chars()))
+        .map(|(val, _, boundary): (Option<String>, _, char)| make_bound(val, boundary == ']'));
+    // e.g. `[10 TO 100}`
+    let interval = (lower, spaces(), string("TO"), spaces(), upper)
+        .map(|(lower, _, _, _, upper)| (lower, upper));
+    // e.g. `>=10`
+    let comparison = (one_of("<>".chars()), optional(char('=')), range_val()).map(
+        |(comparator, equal, val): (char, Option<char>, Option<String>)| {
+            let bound = make_bound(val, equal.is_some());
+            if comparator == '>' {
+                (bound, UserInputBound::Unbounded)
+            } else {
+                (UserInputBound::Unbounded, bound)
+            }
+        },
+    );
+    (parser(field), char(':'), interval.or(comparison))
+        .map(|(field_name, _, (lower, upper))| UserInputAST::Range {
+            field_name,
+            lower,
+            upper,
+        })
+        .parse_stream(input)
+}
+
 fn leaf<I>(input: I) -> ParseResult<UserInputAST, I>
 where
     I: Stream<Item = char>,
@@ -44,6 +143,7 @@ where
     (char('-'), parser(leaf)).map(|(_, expr)| UserInputAST::Not(box expr))
         .or((char('+'), parser(leaf)).map(|(_, expr)| UserInputAST::Must(box expr)))
         .or((char('('), parser(parse_to_ast), char(')')).map(|(_, expr, _)| expr))
+        .or(try(parser(range)))
         .or(parser(literal))
         .parse_stream(input)
 }
@@ -90,6 +190,42 @@ mod test {
         test_parse_query_to_ast_helper("-abc:toto", "-(abc:\"toto\")");
         test_parse_query_to_ast_helper("abc:a b", "(abc:\"a\" \"b\")");
         test_parse_query_to_ast_helper("abc:\"a b\"", "abc:\"a b\"");
+        test_parse_query_to_ast_helper("abc:-3", "abc:\"-3\"");
+        test_parse_query_to_ast_helper("abc:2017-01-01", "abc:\"2017-01-01\"");
+        test_parse_query_to_ast_helper("(abc:-3)", "abc:\"-3\"");
+        test_parse_query_to_ast_helper("title:3/4", "title:\"3/4\"");
+        test_parse_query_to_ast_helper("title:1,000 a", "(title:\"1,000\" \"a\")");
         test_is_parse_err("abc +    ");
     }
+
+    #[test]
+    fn test_parse_range_to_ast() {
+        test_parse_query_to_ast_helper("price:[10 TO 100]", "price:[\"10\" TO \"100\"]");
+        test_parse_query_to_ast_helper("price:{10 TO 100]", "price:{\"10\" TO \"100\"]");
+        test_parse_query_to_ast_helper("price:[ -1.5 TO * }", "price:[\"-1.5\" TO *}");
+        test_parse_query_to_ast_helper(
+            "date:>2017-01-01T10:00:00Z",
+            "date:{\"2017-01-01T10:00:00Z\" TO *}",
+        );
+        test_parse_query_to_ast_helper("price:>=10", "price:[\"10\" TO *}");
+        test_parse_query_to_ast_helper("price:<10", "price:{* TO \"10\"}");
+        test_parse_query_to_ast_helper("price:<=10", "price:{* TO \"10\"]");
+        test_parse_query_to_ast_helper(
+            "+price:[10 TO 100] -a",
+            "(+(price:[\"10\" TO \"100\"]) -(\"a\"))",
+        );
+        test_is_parse_err("price:[10 TO 100");
+        test_parse_query_to_ast_helper("price:[\\* TO \\]]", "price:[\"*\" TO \"]\"]");
+    }
+
+    #[test]
+    fn test_parse_escaped_query_to_ast() {
+        test_parse_query_to_ast_helper("a\\:b", "\"a:b\"");
+        test_parse_query_to_ast_helper("abc:\\-a\\ b", "abc:\"-a b\"");
+        test_parse_query_to_ast_helper("\\+\\(a\\)", "\"+(a)\"");
+        test_parse_query_to_ast_helper("abc:2017\\-01", "abc:\"2017-01\"");
+        test_parse_query_to_ast_helper("\"a \\\"b\\\" \\\\c\"", "\"a \"b\" \\c\"");
+        test_parse_query_to_ast_helper("a@b.com", "\"a@b.com\"");
+        test_is_parse_err("a\\");
+    }
 }
@@ -1,29 +1,40 @@
 use schema::{Field, Schema};
 use query::Query;
 use query::BooleanQuery;
+use query::{BoostQuery, DisjunctionMaxQuery};
 use super::logical_ast::*;
 use super::user_input_ast::*;
-use super::query_grammar::parse_to_ast;
+use super::query_grammar::{parse_to_ast, SPECIAL_CHARS};
 use query::Occur;
 use query::TermQuery;
 use schema::IndexRecordOption;
 use query::PhraseQuery;
-use schema::{FieldType, Term};
+use query::FacetTermQuery;
+use query::RangeQuery;
+use schema::{Facet, FieldType, Term};
+use std::collections::Bound;
+use std::collections::HashMap;
 use std::str::FromStr;
 use tokenizer::TokenizerManager;
+use std::error::Error as StdError;
+use std::fmt;
 use std::num::ParseIntError;
 use core::Index;
+use Score;
+use time;
 
 /// Possible error that may happen when parsing a query.
 #[derive(Debug, PartialEq, Eq)]
 pub enum QueryParserError {
-    /// Error in the query syntax
-    SyntaxError,
+    /// `SyntaxError(position: usize)`
+    /// Error in the query syntax. The position is the byte offset
+    /// within the query at which the error was detected.
+    SyntaxError(usize),
     /// `FieldDoesNotExist(field_name: String)`
     /// The query references a field that is not in the schema
     FieldDoesNotExist(String),
-    /// The query contains a term for a `u64`-field, but the value
-    /// is not a u64.
+    /// The query contains a term for an `i64` or a `u64`-field, but the value
+    /// is neither an integer of this type nor a date.
     ExpectedInt(ParseIntError),
     /// It is forbidden queries that are only "excluding". (e.g. -title:pop)
     AllButQueryForbidden,
@@ -36,6 +47,70 @@ pub enum QueryParserError {
     /// The tokenizer for the given field is unknown
     /// The two argument strings are the name of the field, the name of the tokenizer
     UnknownTokenizer(String, String),
+    /// The query contains a range over a field whose values
+    /// cannot be ordered, such as a facet field.
+    /// The argument is the name of the field.
+    RangeNotSupported(String),
+}
+
+/// Part of a query that was reinterpreted or ignored by
+/// `QueryParser::parse_query_lenient`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum QueryParserWarning {
+    /// The query had a syntax error, and was parsed as the
+    /// given repaired query instead.
+    ///
+    /// Unbalanced parentheses and quotes, as well as dangling operators,
+    /// are removed. If this is not sufficient, the query is reduced to its words.
+    SyntaxRepaired(String),
+    /// A clause of the query was dropped because of the given error.
+    ClauseDropped(QueryParserError),
+}
+
+impl fmt::Display for QueryParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            QueryParserError::SyntaxError(position) => {
+                write!(f, "syntax error at position {} of the query", position)
+            }
+            QueryParserError::FieldDoesNotExist(ref field_name) => {
+                write!(f, "the field '{}' does not exist", field_name)
+            }
+            QueryParserError::ExpectedInt(ref err) => {
+                write!(f, "expected an integer or a date: {}", err)
+            }
+            QueryParserError::AllButQueryForbidden => {
+                write!(f, "a query cannot only exclude documents")
+            }
+            QueryParserError::NoDefaultFieldDeclared => {
+                write!(f, "no field was specified, and no default field is declared")
+            }
+            QueryParserError::FieldNotIndexed(ref field_name) => {
+                write!(f, "the field '{}' is not indexed", field_name)
+            }
+            QueryParserError::UnknownTokenizer(ref field_name, ref tokenizer_name) => write!(
+                f,
+                "the tokenizer '{}' of the field '{}' is not registered",
+                tokenizer_name, field_name
+            ),
+            QueryParserError::RangeNotSupported(ref field_name) => {
+                write!(f, "the field '{}' does not support range queries", field_name)
+            }
+        }
+    }
+}
+
+impl StdError for QueryParserError {
+    fn description(&self) -> &str {
+        "the query is invalid"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            QueryParserError::ExpectedInt(ref err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl From<ParseIntError> for QueryParserError {
@@ -63,8 +138,12 @@ impl From<ParseIntError> for QueryParserError {
 ///   by relevance : The user typically just scans through the first few
 ///   documents in order of decreasing relevance and will stop when the documents
 ///   are not relevant anymore.
-///   Making it possible to make this behavior customizable is tracked in
-///   [issue #27](https://github.com/fulmicoton/tantivy/issues/27).
+///   Terms can be handled as an `AND` instead by calling `.set_conjunction_by_default()`.
+///
+///   The fields can be given different weights with `.set_field_boost(...)`.
+///   By default, the scores of a term in the different default fields are
+///   summed. After calling `.set_tie_breaker(...)`, they are combined
+///   with a `DisjunctionMaxQuery` instead.
 ///
 /// * negative terms: By prepending a term by a `-`, a term can be excluded
 ///   from the search. This is useful for disambiguating a query.
@@ -72,11 +151,36 @@ impl From<ParseIntError> for QueryParserError {
 ///
 /// * must terms: By prepending a term by a `+`, a term can be made required for the search.
 ///
+/// * facets: `category:/electronics/phones` matches the documents associated with
+///   the facet `/electronics/phones`, or with any of its descendants.
+///   See `FacetTermQuery`.
+///
+/// * ranges: `price:[10 TO 100]` matches the documents whose `price` is within
+///   the given range. Square brackets include the bound, and curly brackets exclude it,
+///   as in `price:{10 TO 100]`. A bound can be left open with `*`.
+///   Ranges can also be expressed as comparisons: `price:>10`, `price:>=10`,
+///   `price:<10` and `price:<=10`. The bounds of a range over a text field are
+///   not tokenized.
+///
+/// * dates: The values of the `i64` and `u64` fields can be given as dates,
+///   either `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SSZ`, e.g. `timestamp:>2017-01-01`.
+///   Dates are converted into unix timestamps, in seconds, and are expected to have been
+///   indexed as such.
+///
+/// * escaping: The characters `\ + - ( ) [ ] { } " : < > *` have a special meaning,
+///   and must be preceded by a backslash to be searched, e.g. `title:c\+\+`.
+///   Within a phrase, only `"` and `\` need to be escaped. Any character preceded
+///   by a backslash is taken literally. `QueryParser::escape(...)` escapes
+///   a text for it to be safely embedded in a query.
+///
 pub struct QueryParser {
     schema: Schema,
     default_fields: Vec<Field>,
     conjunction_by_default: bool,
     tokenizer_manager: TokenizerManager,
+    field_tokenizers: HashMap<Field, String>,
+    field_boosts: HashMap<Field, Score>,
+    tie_breaker: Option<Score>,
 }
 
 impl QueryParser {
@@ -94,6 +198,9 @@ impl QueryParser {
             default_fields,
             tokenizer_manager,
             conjunction_by_default: false,
+            field_tokenizers: HashMap::new(),
+            field_boosts: HashMap::new(),
+            tie_breaker: None,
         }
     }
 
@@ -114,25 +221,159 @@ impl QueryParser {
         self.conjunction_by_default = true;
     }
 
+    /// Sets the boost of a field.
+    ///
+    /// The score of the documents matching a term of the query in
+    /// `field` gets multiplied by `boost`, whether the field was explicitely
+    /// targetted by the query or is a default field.
+    pub fn set_field_boost(&mut self, field: Field, boost: Score) {
+        self.field_boosts.insert(field, boost);
+    }
+
+    /// Combines the scores of a term searched in the different default
+    /// fields with a `DisjunctionMaxQuery` rather than summing them.
+    ///
+    /// The score of the term is the score of the best field, plus
+    /// `tie_breaker` times the scores of the other fields. A small
+    /// `tie_breaker`, such as `0.1`, prevents terms matching in many
+    /// fields from overwhelming the terms matching in the most important one.
+    pub fn set_tie_breaker(&mut self, tie_breaker: Score) {
+        self.tie_breaker = Some(tie_breaker);
+    }
+
+    /// Sets the tokenizer used to analyze the query terms of a text field,
+    /// instead of the tokenizer the field was indexed with.
+    ///
+    /// `tokenizer_name` is looked up in the `TokenizerManager` of the parser.
+    /// Asymmetric analysis makes it possible, for instance, to expand
+    /// synonyms at query time only, or to search a stemmed field
+    /// without stemming the query.
+    ///
+    /// Tokens emitted at the same position, as synonym filters do,
+    /// are searched as alternatives.
+    pub fn set_field_tokenizer(&mut self, field: Field, tokenizer_name: &str) {
+        self.field_tokenizers
+            .insert(field, tokenizer_name.to_string());
+    }
+
+    /// Escapes the special characters of `text`, so that it can be embedded
+    /// in a query without altering its structure.
+    ///
+    /// Whitespaces are not escaped: the words of `text` are still searched as
+    /// separate terms, unless the escaped text is put within quotes, as in
+    /// `format!("title:\"{}\"", QueryParser::escape(text))`.
+    pub fn escape(text: &str) -> String {
+        let mut escaped_text = String::with_capacity(text.len());
+        for c in text.chars() {
+            if SPECIAL_CHARS.contains(c) {
+                escaped_text.push('\\');
+            }
+            escaped_text.push(c);
+        }
+        escaped_text
+    }
+
     /// Parse a query
     ///
     /// Note that `parse_query` returns an error if the input
     /// is not a valid query.
     ///
-    /// There is currently no lenient mode for the query parser
-    /// which makes it a bad choice for a public/broad user search engine.
-    ///
-    /// Implementing a lenient mode for this query parser is tracked
-    /// in [Issue 5](https://github.com/fulmicoton/tantivy/issues/5)
+    /// Public search engines should rather rely on `.parse_query_lenient(...)`,
+    /// which never fails.
     pub fn parse_query(&self, query: &str) -> Result<Box<Query>, QueryParserError> {
         let logical_ast = self.parse_query_to_logical_ast(query)?;
         Ok(convert_to_query(logical_ast))
     }
 
+    /// Parse a query, degrading gracefully on errors.
+    ///
+    /// Syntax errors are repaired, and the clauses that would make
+    /// `.parse_query(...)` fail, such as the clauses targetting a field that
+    /// does not exist, are dropped. A query excluding documents only
+    /// is dropped entirely. Each of these is reported as a warning,
+    /// along with the resulting query.
+    ///
+    /// If every clause is dropped, the query matches no document.
+    pub fn parse_query_lenient(&self, query: &str) -> (Box<Query>, Vec<QueryParserWarning>) {
+        let mut warnings = vec![];
+        let user_input_ast = parse_to_ast_lenient(query, &mut warnings);
+        let logical_ast = match self.prune_invalid_clauses(user_input_ast, &mut warnings) {
+            Some(user_input_ast) => match self.compute_logical_ast(user_input_ast) {
+                Ok(logical_ast) => logical_ast,
+                Err(error) => {
+                    warnings.push(QueryParserWarning::ClauseDropped(error));
+                    LogicalAST::Clause(vec![])
+                }
+            },
+            None => LogicalAST::Clause(vec![]),
+        };
+        (convert_to_query(logical_ast), warnings)
+    }
+
+    /// Removes the leaves of the query for which
+    /// no logical AST can be computed.
+    fn prune_invalid_clauses(
+        &self,
+        user_input_ast: UserInputAST,
+        warnings: &mut Vec<QueryParserWarning>,
+    ) -> Option<UserInputAST> {
+        match user_input_ast {
+            UserInputAST::Clause(sub_queries) => {
+                let sub_queries: Vec<Box<UserInputAST>> = sub_queries
+                    .into_iter()
+                    .filter_map(|sub_query| self.prune_invalid_clauses(*sub_query, warnings))
+                    .map(Box::new)
+                    .collect();
+                // as in the grammar, a clause with a single sub query is unwrapped.
+                match sub_queries.len() {
+                    0 => None,
+                    1 => sub_queries.into_iter().next().map(|sub_query| *sub_query),
+                    _ => Some(UserInputAST::Clause(sub_queries)),
+                }
+            }
+            UserInputAST::Not(sub_query) => self.prune_invalid_clauses(*sub_query, warnings)
+                .map(|sub_query| UserInputAST::Not(box sub_query)),
+            UserInputAST::Must(sub_query) => self.prune_invalid_clauses(*sub_query, warnings)
+                .map(|sub_query| UserInputAST::Must(box sub_query)),
+            UserInputAST::Range {
+                field_name,
+                lower,
+                upper,
+            } => match self.compute_logical_ast_for_range(&field_name, &lower, &upper) {
+                Ok(_) => Some(UserInputAST::Range {
+                    field_name,
+                    lower,
+                    upper,
+                }),
+                Err(error) => {
+                    warnings.push(QueryParserWarning::ClauseDropped(error));
+                    None
+                }
+            },
+            UserInputAST::Leaf(literal) => match self.compute_logical_ast_for_literal(&literal) {
+                Ok(Some(_)) => Some(UserInputAST::Leaf(literal)),
+                // the literal does not contain any term, e.g. it only has punctuation.
+                Ok(None) => None,
+                Err(error) => {
+                    warnings.push(QueryParserWarning::ClauseDropped(error));
+                    None
+                }
+            },
+        }
+    }
+
     /// Parse the user query into an AST.
     fn parse_query_to_logical_ast(&self, query: &str) -> Result<LogicalAST, QueryParserError> {
-        let (user_input_ast, _remaining) =
-            parse_to_ast(query).map_err(|_| QueryParserError::SyntaxError)?;
+        let (user_input_ast, remaining) = parse_to_ast(query).map_err(|error| {
+            // the position of the errors of a `&str` stream is
+            // the address of the character they occurred at.
+            let position = error.into_inner().position - query.as_ptr() as usize;
+            QueryParserError::SyntaxError(position)
+        })?;
+        let remaining = remaining.into_inner();
+        if !remaining.is_empty() {
+            return Err(QueryParserError::SyntaxError(query.len() - remaining.len()));
+        }
         self.compute_logical_ast(user_input_ast)
     }
 
@@ -146,17 +387,18 @@ impl QueryParser {
         &self,
         user_input_ast: UserInputAST,
     ) -> Result<LogicalAST, QueryParserError> {
-        let (occur, ast) = self.compute_logical_ast_with_occur(user_input_ast)?;
-        if occur == Occur::MustNot {
-            return Err(QueryParserError::AllButQueryForbidden);
+        match self.compute_logical_ast_with_occur(user_input_ast)? {
+            Some((Occur::MustNot, _)) => Err(QueryParserError::AllButQueryForbidden),
+            Some((_, ast)) => Ok(ast),
+            // the query does not contain any term: it matches no document.
+            None => Ok(LogicalAST::Clause(vec![])),
         }
-        Ok(ast)
     }
     fn compute_logical_ast_for_leaf(
         &self,
         field: Field,
         phrase: &str
-    ) -> Result<Option<LogicalLiteral>, QueryParserError> {
+    ) -> Result<Option<LogicalAST>, QueryParserError> {
         let field_entry = self.schema.get_field_entry(field);
         let field_type = field_entry.field_type();
         if !field_type.is_indexed() {
@@ -165,40 +407,43 @@ impl QueryParser {
         }
         match *field_type {
             FieldType::I64(_) => {
-                let val: i64 = i64::from_str(phrase)?;
-                let term = Term::from_field_i64(field, val);
-                Ok(Some(LogicalLiteral::Term(term)))
+                let term = Term::from_field_i64(field, parse_i64(phrase)?);
+                Ok(Some(LogicalLiteral::Term(term).into()))
             }
             FieldType::U64(_) => {
-                let val: u64 = u64::from_str(phrase)?;
-                let term = Term::from_field_u64(field, val);
-                Ok(Some(LogicalLiteral::Term(term)))
+                let term = Term::from_field_u64(field, parse_u64(phrase)?);
+                Ok(Some(LogicalLiteral::Term(term).into()))
             }
             FieldType::Str(ref str_options) => {
                 if let Some(option) = str_options.get_indexing_options() {
+                    let tokenizer_name = self.field_tokenizers
+                        .get(&field)
+                        .map(|tokenizer_name| &tokenizer_name[..])
+                        .unwrap_or_else(|| option.tokenizer());
                     let mut tokenizer = self.tokenizer_manager
-                        .get(option.tokenizer())
+                        .get(tokenizer_name)
                         .ok_or_else(|| {
                             QueryParserError::UnknownTokenizer(
                                 field_entry.name().to_string(),
-                                option.tokenizer().to_string(),
+                                tokenizer_name.to_string(),
                             )
                         })?;
-                    let mut terms: Vec<Term> = Vec::new();
+                    // terms are grouped by position.
+                    let mut positions: Vec<(usize, Vec<Term>)> = Vec::new();
+                    let mut last_position = None;
                     let mut token_stream = tokenizer.token_stream(phrase);
                     token_stream.process(&mut |token| {
                         let term = Term::from_field_text(field, &token.text);
-                        terms.push(term);
+                        if last_position == Some(token.position) {
+                            if let Some(&mut (_, ref mut alternatives)) = positions.last_mut() {
+                                alternatives.push(term);
+                            }
+                        } else {
+                            last_position = Some(token.position);
+                            positions.push((token.position, vec![term]));
+                        }
                     });
-                    if terms.is_empty() {
-                        Ok(None)
-                    } else if terms.len() == 1 {
-                        Ok(Some(LogicalLiteral::Term(
-                            terms.into_iter().next().unwrap(),
-                        )))
-                    } else {
-                        Ok(Some(LogicalLiteral::Phrase(terms)))
-                    }
+                    Ok(logical_ast_for_positions(positions))
                 } else {
                     // This should have been seen earlier really.
                     Err(QueryParserError::FieldNotIndexed(
@@ -207,9 +452,68 @@ impl QueryParser {
                 }
             }
             FieldType::HierarchicalFacet => {
-                let term = Term::from_field_text(field, phrase);
-                Ok(Some(LogicalLiteral::Term(term)))
+                // the leading slash of the facet path is optional.
+                let facet = if phrase.starts_with('/') {
+                    Facet::from_text(phrase)
+                } else {
+                    Facet::from_text(&format!("/{}", phrase))
+                };
+                Ok(Some(LogicalLiteral::Facet(field, facet).into()))
+            }
+            FieldType::Bytes | FieldType::Vector(_) => Err(QueryParserError::FieldNotIndexed(
+                field_entry.name().to_string(),
+            )),
+        }
+    }
+
+    /// Returns the term bounding a range over `field`.
+    ///
+    /// Contrary to the terms of a literal, it is not tokenized.
+    fn compute_boundary_term(&self, field: Field, phrase: &str) -> Result<Term, QueryParserError> {
+        let field_entry = self.schema.get_field_entry(field);
+        match *field_entry.field_type() {
+            FieldType::I64(_) => Ok(Term::from_field_i64(field, parse_i64(phrase)?)),
+            FieldType::U64(_) => Ok(Term::from_field_u64(field, parse_u64(phrase)?)),
+            FieldType::Str(_) => Ok(Term::from_field_text(field, phrase)),
+            FieldType::HierarchicalFacet | FieldType::Bytes | FieldType::Vector(_) => Err(
+                QueryParserError::RangeNotSupported(field_entry.name().to_string()),
+            ),
+        }
+    }
+
+    fn compute_logical_ast_for_range(
+        &self,
+        field_name: &str,
+        lower: &UserInputBound,
+        upper: &UserInputBound,
+    ) -> Result<LogicalAST, QueryParserError> {
+        let field = self.resolve_field_name(field_name)?;
+        if !self.schema.get_field_entry(field).is_indexed() {
+            return Err(QueryParserError::FieldNotIndexed(field_name.to_string()));
+        }
+        let compute_bound = |bound: &UserInputBound| -> Result<Bound<Term>, QueryParserError> {
+            match *bound {
+                UserInputBound::Inclusive(ref phrase) => {
+                    Ok(Bound::Included(self.compute_boundary_term(field, phrase)?))
+                }
+                UserInputBound::Exclusive(ref phrase) => {
+                    Ok(Bound::Excluded(self.compute_boundary_term(field, phrase)?))
+                }
+                UserInputBound::Unbounded => Ok(Bound::Unbounded),
             }
+        };
+        let range = LogicalLiteral::Range {
+            field,
+            lower: compute_bound(lower)?,
+            upper: compute_bound(upper)?,
+        };
+        Ok(self.boost_field_ast(field, range.into()))
+    }
+
+    fn boost_field_ast(&self, field: Field, ast: LogicalAST) -> LogicalAST {
+        match self.field_boosts.get(&field) {
+            Some(&boost) => LogicalAST::Boost(box ast, boost),
+            None => ast,
         }
     }
 
@@ -221,63 +525,282 @@ impl QueryParser {
         }
     }
 
+    /// Returns `None` if the query does not contain any term,
+    /// e.g. if it only has punctuation. Such queries are ignored.
     fn compute_logical_ast_with_occur(
         &self,
         user_input_ast: UserInputAST,
-    ) -> Result<(Occur, LogicalAST), QueryParserError> {
+    ) -> Result<Option<(Occur, LogicalAST)>, QueryParserError> {
         match user_input_ast {
             UserInputAST::Clause(sub_queries) => {
                 let default_occur = self.default_occur();
                 let mut logical_sub_queries: Vec<(Occur, LogicalAST)> = Vec::new();
                 for sub_query in sub_queries {
-                    let (occur, sub_ast) = self.compute_logical_ast_with_occur(*sub_query)?;
-                    let new_occur = compose_occur(default_occur, occur);
-                    logical_sub_queries.push((new_occur, sub_ast));
+                    let logical_sub_query = self.compute_logical_ast_with_occur(*sub_query)?;
+                    if let Some((occur, sub_ast)) = logical_sub_query {
+                        let new_occur = compose_occur(default_occur, occur);
+                        logical_sub_queries.push((new_occur, sub_ast));
+                    }
                 }
-                Ok((Occur::Should, LogicalAST::Clause(logical_sub_queries)))
+                Ok(Some((Occur::Should, LogicalAST::Clause(logical_sub_queries))))
             }
-            UserInputAST::Not(subquery) => {
-                let (occur, logical_sub_queries) = self.compute_logical_ast_with_occur(*subquery)?;
-                Ok((compose_occur(Occur::MustNot, occur), logical_sub_queries))
+            UserInputAST::Not(subquery) => Ok(self.compute_logical_ast_with_occur(*subquery)?
+                .map(|(occur, sub_ast)| (compose_occur(Occur::MustNot, occur), sub_ast))),
+            UserInputAST::Must(subquery) => Ok(self.compute_logical_ast_with_occur(*subquery)?
+                .map(|(occur, sub_ast)| (compose_occur(Occur::Must, occur), sub_ast))),
+            UserInputAST::Range {
+                field_name,
+                lower,
+                upper,
+            } => {
+                let result_ast = self.compute_logical_ast_for_range(&field_name, &lower, &upper)?;
+                Ok(Some((Occur::Should, result_ast)))
             }
-            UserInputAST::Must(subquery) => {
-                let (occur, logical_sub_queries) = self.compute_logical_ast_with_occur(*subquery)?;
-                Ok((compose_occur(Occur::Must, occur), logical_sub_queries))
+            UserInputAST::Leaf(literal) => Ok(self.compute_logical_ast_for_literal(&literal)?
+                .map(|result_ast| (Occur::Should, result_ast))),
+        }
+    }
+
+    fn compute_logical_ast_for_literal(
+        &self,
+        literal: &UserInputLiteral,
+    ) -> Result<Option<LogicalAST>, QueryParserError> {
+        let term_phrases: Vec<(Field, String)> = match literal.field_name {
+            Some(ref field_name) => {
+                let field = self.resolve_field_name(field_name)?;
+                vec![(field, literal.phrase.clone())]
+            }
+            None => {
+                if self.default_fields.is_empty() {
+                    return Err(QueryParserError::NoDefaultFieldDeclared);
+                } else {
+                    self.default_fields
+                        .iter()
+                        .map(|default_field| (*default_field, literal.phrase.clone()))
+                        .collect::<Vec<(Field, String)>>()
+                }
+            }
+        };
+        let mut asts: Vec<LogicalAST> = Vec::new();
+        for (field, phrase) in term_phrases {
+            if let Some(ast) = self.compute_logical_ast_for_leaf(field, &phrase)? {
+                asts.push(self.boost_field_ast(field, ast));
             }
-            UserInputAST::Leaf(literal) => {
-                let term_phrases: Vec<(Field, String)> = match literal.field_name {
-                    Some(ref field_name) => {
-                        let field = self.resolve_field_name(field_name)?;
-                        vec![(field, literal.phrase.clone())]
+        }
+        let result_ast = if asts.is_empty() {
+            return Ok(None);
+        } else if asts.len() == 1 {
+            asts[0].clone()
+        } else if let Some(tie_breaker) = self.tie_breaker {
+            LogicalAST::DisjunctionMax(asts, tie_breaker)
+        } else {
+            LogicalAST::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
+        };
+        Ok(Some(result_ast))
+    }
+}
+
+/// Date formats accepted by the parser, along with the length of the dates.
+///
+/// The length is checked as `strptime` ignores trailing characters.
+const DATE_FORMATS: [(&'static str, usize); 2] = [("%Y-%m-%dT%H:%M:%SZ", 20), ("%Y-%m-%d", 10)];
+
+/// Parses a UTC date into a unix timestamp, in seconds.
+fn parse_date(phrase: &str) -> Option<i64> {
+    DATE_FORMATS
+        .iter()
+        .filter(|&&(_, len)| phrase.len() == len)
+        .filter_map(|&(format, _)| time::strptime(phrase, format).ok())
+        .map(|tm| tm.to_timespec().sec)
+        .next()
+}
+
+/// Parses the value of an `i64` field, given as an integer or as a date.
+fn parse_i64(phrase: &str) -> Result<i64, QueryParserError> {
+    i64::from_str(phrase).or_else(|err| parse_date(phrase).ok_or_else(|| err.into()))
+}
+
+/// Parses the value of a `u64` field, given as an integer or as a date.
+fn parse_u64(phrase: &str) -> Result<u64, QueryParserError> {
+    u64::from_str(phrase).or_else(|err| match parse_date(phrase) {
+        Some(timestamp) if timestamp >= 0 => Ok(timestamp as u64),
+        _ => Err(err.into()),
+    })
+}
+
+/// Parses a query that is expected to be entirely valid.
+fn parse_entirely(query: &str) -> Option<UserInputAST> {
+    match parse_to_ast(query) {
+        Ok((user_input_ast, remaining)) if remaining.trim().is_empty() => Some(user_input_ast),
+        _ => None,
+    }
+}
+
+/// Parses a query, repairing its syntax errors if needed.
+fn parse_to_ast_lenient(query: &str, warnings: &mut Vec<QueryParserWarning>) -> UserInputAST {
+    let query = query.trim();
+    if let Some(user_input_ast) = parse_entirely(query) {
+        return user_input_ast;
+    }
+    let mut repaired_query = repair_query_syntax(query);
+    let user_input_ast = parse_entirely(&repaired_query).unwrap_or_else(|| {
+        // only the words of the query are kept.
+        repaired_query = query
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .collect::<Vec<&str>>()
+            .join(" ");
+        parse_entirely(&repaired_query).unwrap_or_else(|| UserInputAST::Clause(vec![]))
+    });
+    warnings.push(QueryParserWarning::SyntaxRepaired(repaired_query));
+    user_input_ast
+}
+
+/// Returns, for each character of a query, whether it is escaped by a backslash.
+fn escaped_positions(chars: &[char]) -> Vec<bool> {
+    let mut escaped = vec![false; chars.len()];
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            escaped[i + 1] = true;
+            i += 2;
+        } else {
+            i += 1;
+        }
+    }
+    escaped
+}
+
+/// Removes the unbalanced quotes and parentheses of a query, the empty parentheses,
+/// the trailing backslash, as well as the operators and field separators
+/// not followed by a value.
+///
+/// Escaped characters are left untouched.
+fn repair_query_syntax(query: &str) -> String {
+    let mut chars: Vec<char> = query.chars().collect();
+    if chars.last() == Some(&'\\') && !escaped_positions(&chars)[chars.len() - 1] {
+        chars.pop();
+    }
+    let escaped = escaped_positions(&chars);
+    let quote_positions: Vec<usize> = (0..chars.len())
+        .filter(|&i| chars[i] == '"' && !escaped[i])
+        .collect();
+    if quote_positions.len() % 2 == 1 {
+        chars.remove(quote_positions[quote_positions.len() - 1]);
+    }
+    let escaped = escaped_positions(&chars);
+    let mut in_phrase = false;
+    // open parentheses, along with whether they are empty so far.
+    let mut open_parentheses: Vec<(usize, bool)> = vec![];
+    for i in 0..chars.len() {
+        if chars[i].is_whitespace() {
+            continue;
+        }
+        if !escaped[i] {
+            match chars[i] {
+                '"' => {
+                    in_phrase = !in_phrase;
+                }
+                '(' if !in_phrase => {
+                    open_parentheses.push((i, true));
+                    continue;
+                }
+                ')' if !in_phrase => match open_parentheses.pop() {
+                    Some((_, false)) => {}
+                    Some((open_position, true)) => {
+                        chars[open_position] = ' ';
+                        chars[i] = ' ';
+                        continue;
                     }
                     None => {
-                        if self.default_fields.is_empty() {
-                            return Err(QueryParserError::NoDefaultFieldDeclared);
-                        } else {
-                            self.default_fields
-                                .iter()
-                                .map(|default_field| (*default_field, literal.phrase.clone()))
-                                .collect::<Vec<(Field, String)>>()
-                        }
+                        chars[i] = ' ';
+                        continue;
                     }
-                };
-                let mut asts: Vec<LogicalAST> = Vec::new();
-                for (field, phrase) in term_phrases {
-                    if let Some(ast) = self.compute_logical_ast_for_leaf(field, &phrase)? {
-                        asts.push(LogicalAST::Leaf(box ast));
-                    }
-                }
-                let result_ast = if asts.is_empty() {
-                    // this should never happen
-                    return Err(QueryParserError::SyntaxError);
-                } else if asts.len() == 1 {
-                    asts[0].clone()
-                } else {
-                    LogicalAST::Clause(asts.into_iter().map(|ast| (Occur::Should, ast)).collect())
-                };
-                Ok((Occur::Should, result_ast))
+                },
+                _ => {}
             }
         }
+        if let Some(open_parenthesis) = open_parentheses.last_mut() {
+            open_parenthesis.1 = false;
+        }
+    }
+    for (i, _) in open_parentheses {
+        chars[i] = ' ';
+    }
+    // operators and field separators are checked from right to left,
+    // so that removing one of them is taken in account by the previous ones.
+    let mut chars: Vec<char> = normalize_whitespaces(chars.into_iter().collect())
+        .chars()
+        .collect();
+    let escaped = escaped_positions(&chars);
+    for i in (0..chars.len()).rev() {
+        if escaped[i] || (chars[i] != '+' && chars[i] != '-' && chars[i] != ':') {
+            continue;
+        }
+        let is_followed_by_value = chars
+            .get(i + 1)
+            .map(|&c| !c.is_whitespace() && c != ')')
+            .unwrap_or(false);
+        let is_field_separator_without_field = chars[i] == ':'
+            && chars[..i]
+                .last()
+                .map(|&c| !c.is_alphanumeric() && c != '_')
+                .unwrap_or(true);
+        if !is_followed_by_value || is_field_separator_without_field {
+            chars[i] = ' ';
+        }
+    }
+    normalize_whitespaces(chars.into_iter().collect())
+}
+
+fn normalize_whitespaces(text: String) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Builds the AST of a tokenized phrase, given its terms grouped by position.
+///
+/// Terms sharing a position are alternatives. A phrase containing alternatives
+/// cannot be expressed as a `PhraseQuery`, and is searched as the conjunction
+/// of its positions instead.
+///
+/// The terms of a `PhraseQuery` keep the gaps between their positions,
+/// such as the ones left by a `StopWordFilter`.
+fn logical_ast_for_positions(positions: Vec<(usize, Vec<Term>)>) -> Option<LogicalAST> {
+    if positions.iter().all(|&(_, ref alternatives)| alternatives.len() == 1) {
+        let mut terms: Vec<(usize, Term)> = positions
+            .into_iter()
+            .flat_map(|(position, terms)| terms.into_iter().map(move |term| (position, term)))
+            .collect();
+        return match terms.len() {
+            0 => None,
+            1 => Some(LogicalLiteral::Term(terms.pop().unwrap().1).into()),
+            _ => Some(LogicalLiteral::Phrase(terms).into()),
+        };
+    }
+    let mut position_asts: Vec<LogicalAST> = positions
+        .into_iter()
+        .map(|(_, mut alternatives)| {
+            if alternatives.len() == 1 {
+                LogicalLiteral::Term(alternatives.pop().unwrap()).into()
+            } else {
+                LogicalAST::Clause(
+                    alternatives
+                        .into_iter()
+                        .map(|term| (Occur::Should, LogicalLiteral::Term(term).into()))
+                        .collect(),
+                )
+            }
+        })
+        .collect();
+    if position_asts.len() == 1 {
+        position_asts.pop()
+    } else {
+        Some(LogicalAST::Clause(
+            position_asts
+                .into_iter()
+                .map(|ast| (Occur::Must, ast))
+                .collect(),
+        ))
     }
 }
 
@@ -305,7 +828,13 @@ fn compose_occur(left: Occur, right: Occur) -> Occur {
 fn convert_literal_to_query(logical_literal: LogicalLiteral) -> Box<Query> {
     match logical_literal {
         LogicalLiteral::Term(term) => box TermQuery::new(term, IndexRecordOption::WithFreqs),
-        LogicalLiteral::Phrase(terms) => box PhraseQuery::from(terms),
+        LogicalLiteral::Phrase(terms) => box PhraseQuery::new_with_offset(terms),
+        LogicalLiteral::Facet(field, facet) => box FacetTermQuery::new(field, &facet),
+        LogicalLiteral::Range {
+            field,
+            lower,
+            upper,
+        } => box RangeQuery::new_term_bounds(field, lower, upper),
     }
 }
 
@@ -319,6 +848,11 @@ fn convert_to_query(logical_ast: LogicalAST) -> Box<Query> {
             box BooleanQuery::from(occur_subqueries)
         }
         LogicalAST::Leaf(logical_literal) => convert_literal_to_query(*logical_literal),
+        LogicalAST::Boost(ast, boost) => box BoostQuery::new(convert_to_query(*ast), boost),
+        LogicalAST::DisjunctionMax(asts, tie_breaker) => {
+            let subqueries = asts.into_iter().map(convert_to_query).collect();
+            box DisjunctionMaxQuery::new(subqueries, tie_breaker)
+        }
     }
 }
 
@@ -331,9 +865,11 @@ mod test {
     use schema::{TextOptions, TextFieldIndexing, IndexRecordOption};
     use super::QueryParser;
     use super::QueryParserError;
+    use super::QueryParserWarning;
     use Index;
-    use tokenizer::SimpleTokenizer;
+    use tokenizer::{SimpleTokenizer, StopWordFilter, Token, TokenStream, Tokenizer};
     use super::super::logical_ast::*;
+    use collector::CountCollector;
 
     fn make_query_parser() -> QueryParser {
         let mut schema_builder = SchemaBuilder::default();
@@ -510,6 +1046,24 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_query_parser_syntax_error_position() {
+        let query_parser = make_query_parser();
+        assert_eq!(
+            query_parser.parse_query("(a -) b").err(),
+            Some(QueryParserError::SyntaxError(4))
+        );
+        // the input that is not consumed by the parser is an error.
+        assert_eq!(
+            query_parser.parse_query("a b) c").err(),
+            Some(QueryParserError::SyntaxError(3))
+        );
+        assert_eq!(
+            format!("{}", QueryParserError::SyntaxError(4)),
+            "syntax error at position 4 of the query"
+        );
+    }
+
     #[test]
     pub fn test_unknown_tokenizer() {
         let mut schema_builder = SchemaBuilder::default();
@@ -543,6 +1097,249 @@ mod test {
         assert!(query_parser.parse_query("title:\"happy tax\"").is_ok());
     }
 
+    #[test]
+    pub fn test_query_parser_stop_word_phrase() {
+        let mut schema_builder = SchemaBuilder::default();
+        let mut fields = vec![];
+        for &tokenizer_name in &["stop_with_gaps", "stop_without_gaps"] {
+            let text_field_indexing = TextFieldIndexing::default()
+                .set_tokenizer(tokenizer_name)
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions);
+            let text_options = TextOptions::default().set_indexing_options(text_field_indexing);
+            fields.push(schema_builder.add_text_field(tokenizer_name, text_options));
+        }
+        let index = Index::create_in_ram(schema_builder.build());
+        let stop_word_filter = StopWordFilter::remove(vec!["of".to_string(), "the".to_string()]);
+        index.tokenizers().register(
+            "stop_with_gaps",
+            SimpleTokenizer.filter(stop_word_filter.clone()),
+        );
+        index.tokenizers().register(
+            "stop_without_gaps",
+            SimpleTokenizer.filter(stop_word_filter.keep_position_gaps(false)),
+        );
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for text in &["end of the world", "end world", "end of world"] {
+                index_writer.add_document(doc!(fields[0] => *text, fields[1] => *text));
+            }
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let count = |field, query: &str| {
+            let query = QueryParser::for_index(&index, vec![field])
+                .parse_query(query)
+                .unwrap();
+            query.count(&*searcher).unwrap()
+        };
+        assert_eq!(count(fields[0], "\"end of the world\""), 1);
+        assert_eq!(count(fields[0], "\"end the world\""), 1);
+        assert_eq!(count(fields[0], "\"end world\""), 1);
+        assert_eq!(count(fields[0], "\"the world\""), 3);
+        assert_eq!(count(fields[1], "\"end of the world\""), 3);
+    }
+
+    #[derive(Clone)]
+    struct TvSynonymTokenizer;
+
+    struct TvSynonymTokenStream {
+        tokens: Vec<Token>,
+        cursor: usize,
+    }
+
+    impl<'a> Tokenizer<'a> for TvSynonymTokenizer {
+        type TokenStreamImpl = TvSynonymTokenStream;
+
+        fn token_stream(&self, text: &'a str) -> TvSynonymTokenStream {
+            let mut tokens = vec![];
+            for (position, word) in text.split_whitespace().enumerate() {
+                if word == "tv" {
+                    tokens.push(Token {
+                        position,
+                        text: "television".to_string(),
+                        ..Token::default()
+                    });
+                }
+                tokens.push(Token {
+                    position,
+                    text: word.to_string(),
+                    ..Token::default()
+                });
+            }
+            TvSynonymTokenStream { tokens, cursor: 0 }
+        }
+    }
+
+    impl TokenStream for TvSynonymTokenStream {
+        fn advance(&mut self) -> bool {
+            if self.cursor < self.tokens.len() {
+                self.cursor += 1;
+                true
+            } else {
+                false
+            }
+        }
+
+        fn token(&self) -> &Token {
+            &self.tokens[self.cursor - 1]
+        }
+
+        fn token_mut(&mut self) -> &mut Token {
+            &mut self.tokens[self.cursor - 1]
+        }
+    }
+
+    #[test]
+    pub fn test_query_parser_field_tokenizer() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field_indexing = TextFieldIndexing::default()
+            .set_tokenizer("en_stem")
+            .set_index_option(IndexRecordOption::Basic);
+        let text_options = TextOptions::default().set_indexing_options(text_field_indexing);
+        let title = schema_builder.add_text_field("title", text_options);
+        let schema = schema_builder.build();
+        let tokenizer_manager = TokenizerManager::default();
+        tokenizer_manager.register("tv_synonyms", TvSynonymTokenizer);
+        let mut query_parser = QueryParser::new(schema, vec![title], tokenizer_manager);
+        let parse_to_logical_ast_str = |query_parser: &QueryParser, query: &str| {
+            format!("{:?}", query_parser.parse_query_to_logical_ast(query).unwrap())
+        };
+        assert_eq!(
+            parse_to_logical_ast_str(&query_parser, "title:running"),
+            format!("{:?}", Term::from_field_text(title, "run"))
+        );
+        query_parser.set_field_tokenizer(title, "default");
+        assert_eq!(
+            parse_to_logical_ast_str(&query_parser, "running"),
+            format!("{:?}", Term::from_field_text(title, "running"))
+        );
+        query_parser.set_field_tokenizer(title, "tv_synonyms");
+        let television_term = Term::from_field_text(title, "television");
+        let tv_term = Term::from_field_text(title, "tv");
+        let set_term = Term::from_field_text(title, "set");
+        assert_eq!(
+            parse_to_logical_ast_str(&query_parser, "title:tv"),
+            format!("({:?} {:?})", television_term, tv_term)
+        );
+        assert_eq!(
+            parse_to_logical_ast_str(&query_parser, "title:\"tv set\""),
+            format!("(+({:?} {:?}) +{:?})", television_term, tv_term, set_term)
+        );
+        query_parser.set_field_tokenizer(title, "nonexistingtokenizer");
+        assert_matches!(
+            query_parser.parse_query("title:tv"),
+            Err(QueryParserError::UnknownTokenizer(_, _))
+        );
+    }
+
+    #[test]
+    pub fn test_parse_query_lenient() {
+        let query_parser = make_query_parser();
+        let parse_lenient = |query: &str| {
+            let (query, warnings) = query_parser.parse_query_lenient(query);
+            (format!("{:?}", query), warnings)
+        };
+        let parse_strict = |query: &str| format!("{:?}", query_parser.parse_query(query).unwrap());
+        assert_eq!(parse_lenient("title:a +b"), (parse_strict("title:a +b"), vec![]));
+        assert_eq!(
+            parse_lenient("(title:a b"),
+            (
+                parse_strict("title:a b"),
+                vec![QueryParserWarning::SyntaxRepaired("title:a b".to_string())],
+            )
+        );
+        assert_eq!(
+            parse_lenient("title:\"a b c) -"),
+            (
+                parse_strict("title:a b c"),
+                vec![QueryParserWarning::SyntaxRepaired("title:a b c".to_string())],
+            )
+        );
+        assert_eq!(
+            parse_lenient("a boujou:b unsigned:c"),
+            (
+                parse_strict("a"),
+                vec![
+                    QueryParserWarning::ClauseDropped(QueryParserError::FieldDoesNotExist(
+                        "boujou".to_string(),
+                    )),
+                    QueryParserWarning::ClauseDropped(QueryParserError::ExpectedInt(
+                        "c".parse::<u64>().unwrap_err(),
+                    )),
+                ],
+            )
+        );
+        let (_, warnings) = parse_lenient("-title:a");
+        assert_eq!(
+            warnings,
+            vec![QueryParserWarning::ClauseDropped(QueryParserError::AllButQueryForbidden)]
+        );
+        let (_, warnings) = parse_lenient("+( ))");
+        assert_eq!(warnings, vec![QueryParserWarning::SyntaxRepaired("".to_string())]);
+    }
+
+    #[test]
+    pub fn test_query_parser_escape() {
+        assert_eq!(
+            QueryParser::escape("title:c++ (-a) \"b\\"),
+            "title\\:c\\+\\+ \\(\\-a\\) \\\"b\\\\"
+        );
+        let query_parser = make_query_parser();
+        let parse = |query: &str| format!("{:?}", query_parser.parse_query(query).unwrap());
+        assert_eq!(parse("title\\:toto"), parse("\"title toto\""));
+        assert_eq!(
+            parse(&format!("title:\"{}\"", QueryParser::escape("a \"b\" c"))),
+            parse("title:\"a b c\"")
+        );
+        for text in &[
+            "-a", "+a", "a:b", "\\", "\"", "(", ")", "[1 TO 2]", ">3", "a* -", ":", "\\\"",
+        ] {
+            let escaped_text = QueryParser::escape(text);
+            assert!(query_parser.parse_query(&escaped_text).is_ok());
+            let (_, warnings) = query_parser.parse_query_lenient(&escaped_text);
+            assert!(warnings.is_empty());
+        }
+        let (_, warnings) = query_parser.parse_query_lenient("a\\");
+        assert_eq!(warnings, vec![QueryParserWarning::SyntaxRepaired("a".to_string())]);
+        let (_, warnings) = query_parser.parse_query_lenient("title:\\\" a\\(b \"");
+        assert_eq!(
+            warnings,
+            vec![QueryParserWarning::SyntaxRepaired("title:\\\" a\\(b".to_string())]
+        );
+    }
+
+    #[test]
+    pub fn test_query_parser_boosts_and_tie_breaker() {
+        let mut query_parser = make_query_parser();
+        let title = Field(0);
+        let text = Field(1);
+        let term_str =
+            |field: Field, word: &str| format!("{:?}", Term::from_field_text(field, word));
+        query_parser.set_field_boost(title, 2f32);
+        assert_eq!(
+            format!("{:?}", query_parser.parse_query_to_logical_ast("a").unwrap()),
+            format!("(({})^2 {})", term_str(title, "a"), term_str(text, "a"))
+        );
+        assert_eq!(
+            format!("{:?}", query_parser.parse_query_to_logical_ast("title:a").unwrap()),
+            format!("({})^2", term_str(title, "a"))
+        );
+        query_parser.set_tie_breaker(0.1f32);
+        query_parser.set_conjunction_by_default();
+        assert_eq!(
+            format!("{:?}", query_parser.parse_query_to_logical_ast("a b").unwrap()),
+            format!(
+                "(+DisMax(({})^2 {})~0.1 +DisMax(({})^2 {})~0.1)",
+                term_str(title, "a"),
+                term_str(text, "a"),
+                term_str(title, "b"),
+                term_str(text, "b")
+            )
+        );
+        assert!(query_parser.parse_query("a b").is_ok());
+    }
+
     #[test]
     pub fn test_query_parser_expected_int() {
         let query_parser = make_query_parser();
@@ -557,6 +1354,115 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_parse_query_facet() {
+        let mut schema_builder = SchemaBuilder::default();
+        schema_builder.add_facet_field("category");
+        let schema = schema_builder.build();
+        let query_parser = QueryParser::new(schema, vec![], TokenizerManager::default());
+        let parse = |query: &str| {
+            let logical_ast = query_parser.parse_query_to_logical_ast(query).unwrap();
+            format!("{:?}", logical_ast)
+        };
+        assert_eq!(parse("category:/electronics/phones"), "Facet(/electronics/phones)");
+        assert_eq!(parse("category:electronics/phones"), "Facet(/electronics/phones)");
+        assert_eq!(parse("category:\"/books/sci-fi\""), "Facet(/books/sci-fi)");
+    }
+
+    #[test]
+    pub fn test_parse_query_ranges() {
+        let unsigned_term = |val: u64| Term::from_field_u64(Field(3u32), val);
+        let signed_term = |val: i64| Term::from_field_i64(Field(2u32), val);
+        test_parse_query_to_logical_ast_helper(
+            "unsigned:[10 TO 100]",
+            &format!("[{:?} TO {:?}]", unsigned_term(10), unsigned_term(100)),
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "unsigned:{10 TO *]",
+            &format!("{{{:?} TO *}}", unsigned_term(10)),
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "signed:>=-5",
+            &format!("[{:?} TO *}}", signed_term(-5)),
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "signed:<2017-01-01",
+            &format!("{{* TO {:?}}}", signed_term(1_483_228_800)),
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "signed:2017-01-01T10:00:00Z",
+            &format!("{:?}", signed_term(1_483_264_800)),
+            false,
+        );
+        test_parse_query_to_logical_ast_helper(
+            "title:{Toto TO titi]",
+            &format!(
+                "{{{:?} TO {:?}]",
+                Term::from_field_text(Field(0u32), "Toto"),
+                Term::from_field_text(Field(0u32), "titi")
+            ),
+            false,
+        );
+        let query_parser = make_query_parser();
+        assert_matches!(
+            query_parser.parse_query("unsigned:[a TO 3]"),
+            Err(QueryParserError::ExpectedInt(_))
+        );
+        assert_matches!(
+            query_parser.parse_query("unsigned:<1969-12-31"),
+            Err(QueryParserError::ExpectedInt(_))
+        );
+        assert_matches!(
+            query_parser.parse_query("signed:>2017-13-01"),
+            Err(QueryParserError::ExpectedInt(_))
+        );
+        assert_matches!(
+            query_parser.parse_query("notindexed_u64:>3"),
+            Err(QueryParserError::FieldNotIndexed(_))
+        );
+        assert_matches!(
+            query_parser.parse_query("nofield:[1 TO 3]"),
+            Err(QueryParserError::FieldDoesNotExist(_))
+        );
+    }
+
+    #[test]
+    pub fn test_query_parser_range_search() {
+        let mut schema_builder = SchemaBuilder::default();
+        let timestamp = schema_builder.add_i64_field("timestamp", INT_INDEXED);
+        schema_builder.add_facet_field("facet");
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        for day in 0i64..10i64 {
+            // 2017-01-01 + `day` days
+            index_writer.add_document(doc!(timestamp => 1_483_228_800i64 + day * 86_400));
+        }
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query_parser = QueryParser::for_index(&index, vec![]);
+        let count = |query: &str| {
+            let query = query_parser.parse_query(query).unwrap();
+            let mut count_collector = CountCollector::default();
+            query.search(&*searcher, &mut count_collector).unwrap();
+            count_collector.count()
+        };
+        assert_eq!(count("timestamp:[2017-01-03 TO 2017-01-05]"), 3);
+        assert_eq!(count("timestamp:{2017-01-03 TO 2017-01-05}"), 1);
+        assert_eq!(count("timestamp:>2017-01-08"), 1);
+        assert_eq!(count("timestamp:<=2017-01-02T00:00:00Z"), 2);
+        assert_eq!(count("timestamp:[* TO *] -timestamp:>=2017-01-03"), 2);
+        assert_eq!(count("timestamp:\"2017-01-04\""), 1);
+        assert_matches!(
+            query_parser.parse_query("facet:[/a TO /b]"),
+            Err(QueryParserError::RangeNotSupported(_))
+        );
+    }
+
     #[test]
     pub fn test_parse_query_to_ast_conjunction() {
         test_parse_query_to_logical_ast_helper(
@@ -14,11 +14,40 @@ impl fmt::Debug for UserInputLiteral {
     }
 }
 
+pub enum UserInputBound {
+    Inclusive(String),
+    Exclusive(String),
+    Unbounded,
+}
+
+impl UserInputBound {
+    fn display_lower(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            UserInputBound::Inclusive(ref word) => write!(formatter, "[\"{}\"", word),
+            UserInputBound::Exclusive(ref word) => write!(formatter, "{{\"{}\"", word),
+            UserInputBound::Unbounded => write!(formatter, "{{*"),
+        }
+    }
+
+    fn display_upper(&self, formatter: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match *self {
+            UserInputBound::Inclusive(ref word) => write!(formatter, "\"{}\"]", word),
+            UserInputBound::Exclusive(ref word) => write!(formatter, "\"{}\"}}", word),
+            UserInputBound::Unbounded => write!(formatter, "*}}"),
+        }
+    }
+}
+
 pub enum UserInputAST {
     Clause(Vec<Box<UserInputAST>>),
     Not(Box<UserInputAST>),
     Must(Box<UserInputAST>),
     Leaf(Box<UserInputLiteral>),
+    Range {
+        field_name: String,
+        lower: UserInputBound,
+        upper: UserInputBound,
+    },
 }
 
 impl From<UserInputLiteral> for UserInputAST {
@@ -46,6 +75,16 @@ impl fmt::Debug for UserInputAST {
             }
             UserInputAST::Not(ref subquery) => write!(formatter, "-({:?})", subquery),
             UserInputAST::Leaf(ref subquery) => write!(formatter, "{:?}", subquery),
+            UserInputAST::Range {
+                ref field_name,
+                ref lower,
+                ref upper,
+            } => {
+                write!(formatter, "{}:", field_name)?;
+                lower.display_lower(formatter)?;
+                formatter.write_str(" TO ")?;
+                upper.display_upper(formatter)
+            }
         }
     }
 }
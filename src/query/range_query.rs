@@ -113,6 +113,22 @@ impl RangeQuery {
         }
     }
 
+    /// Create a new `RangeQuery` given the terms bounding the range.
+    ///
+    /// The terms are expected to belong to `field`.
+    pub(crate) fn new_term_bounds(
+        field: Field,
+        left_bound: Bound<Term>,
+        right_bound: Bound<Term>,
+    ) -> RangeQuery {
+        let make_term_val = |term: Term| term.value_bytes().to_owned();
+        RangeQuery {
+            field,
+            left_bound: map_bound(left_bound, &make_term_val),
+            right_bound: map_bound(right_bound, &make_term_val),
+        }
+    }
+
     /// Create a new `RangeQuery` over a `Str` field.
     pub fn new_str<'b, TRangeArgument: RangeArgument<&'b str>>(
         field: Field,
@@ -175,7 +191,7 @@ impl Weight for RangeWeight {
         while term_range.advance() {
             let term_info = term_range.value();
             let mut block_segment_postings = inverted_index
-                .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic);
+                .read_block_postings_from_terminfo(term_info, IndexRecordOption::Basic)?;
             while block_segment_postings.advance() {
                 for &doc in block_segment_postings.docs() {
                     doc_bitset.insert(doc);
@@ -185,6 +201,20 @@ impl Weight for RangeWeight {
         let doc_bitset = BitSetDocSet::from(doc_bitset);
         Ok(box ConstScorer::new(doc_bitset))
     }
+
+    /// The cost is the sum of the document frequencies of
+    /// the terms within the range, which are all read to build the
+    /// scorer's `BitSet`.
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        let inverted_index = reader.inverted_index(self.field);
+        let term_dict = inverted_index.terms();
+        let mut term_range = self.term_range(term_dict);
+        let mut cost = 0u64;
+        while term_range.advance() {
+            cost += u64::from(term_range.value().doc_freq);
+        }
+        Ok(cost)
+    }
 }
 
 #[cfg(test)]
@@ -53,6 +53,12 @@ where
         self.req_scorer.advance()
     }
 
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        // the optional scorer is only aligned lazily, upon scoring.
+        self.score_cache = None;
+        self.req_scorer.skip_next(target)
+    }
+
     fn doc(&self) -> DocId {
         self.req_scorer.doc()
     }
@@ -105,7 +111,7 @@ mod tests {
     use super::RequiredOptionalScorer;
     use query::VecDocSet;
     use query::ConstScorer;
-    use docset::DocSet;
+    use docset::{DocSet, SkipResult};
     use postings::tests::test_skip_against_unoptimized;
     use query::Scorer;
     use query::score_combiner::{DoNothingCombiner, SumCombiner};
@@ -191,4 +197,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reqopt_scorer_skip_then_score() {
+        let mut reqoptscorer: RequiredOptionalScorer<_, _, SumCombiner> =
+            RequiredOptionalScorer::new(
+                ConstScorer::new(VecDocSet::from(vec![1, 3, 7, 8, 9, 10, 13, 15])),
+                ConstScorer::new(VecDocSet::from(vec![1, 2, 7, 11, 12, 15])),
+            );
+        assert!(reqoptscorer.advance());
+        assert_eq!(reqoptscorer.score(), 2f32);
+        assert_eq!(reqoptscorer.skip_next(7), SkipResult::Reached);
+        assert_eq!(reqoptscorer.score(), 2f32);
+        assert_eq!(reqoptscorer.skip_next(11), SkipResult::OverStep);
+        assert_eq!(reqoptscorer.doc(), 13);
+        assert_eq!(reqoptscorer.score(), 1f32);
+        assert_eq!(reqoptscorer.skip_next(15), SkipResult::Reached);
+        assert_eq!(reqoptscorer.score(), 2f32);
+        assert_eq!(reqoptscorer.skip_next(16), SkipResult::End);
+    }
+
 }
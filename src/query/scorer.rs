@@ -10,16 +10,65 @@ use downcast;
 ///
 /// See [`Query`](./trait.Query.html).
 pub trait Scorer: downcast::Any + DocSet + 'static {
-    /// Returns the score.
+    /// Returns the score of the current document.
     ///
-    /// This method will perform a bit of computation and is not cached.
+    /// It may only be called when the scorer is positioned on a document,
+    /// that is after `.advance()` returned true, or after `.skip_next(...)`
+    /// returned `Reached` or `OverStep`.
+    ///
+    /// It may be called any number of times for the same document, for
+    /// instance by the collectors of a `MultiCollector`, and must return
+    /// the same score every time. Computing the score of a single scorer
+    /// is cheap, but scorers combining the scores of several child scorers,
+    /// such as disjunctions, cache it for the current document, so that
+    /// the children are only scored once per document.
     fn score(&mut self) -> Score;
 
+    /// Informs the scorer that documents with a score lower than
+    /// `min_score` are not of interest anymore.
+    ///
+    /// Scorers are free to ignore it. Those that know an upper bound
+    /// of the scores of their upcoming documents, such as the block max
+    /// scores of a term, may use it to skip the documents that cannot
+    /// reach `min_score`. Documents that can are never skipped.
+    ///
+    /// It is expected to be called with increasing scores,
+    /// as a top-k collector raises its bar.
+    fn set_min_competitive_score(&mut self, _min_score: Score) {}
+
+    /// Consumes the remaining documents of the `DocSet`, and calls
+    /// `callback` with each of them along with its score.
+    ///
+    /// Scorers may override it to go through their documents
+    /// a block at a time, rather than with one call
+    /// to `.advance()`, `.doc()` and `.score()` per document.
+    fn for_each(&mut self, callback: &mut FnMut(DocId, Score)) {
+        while self.advance() {
+            callback(self.doc(), self.score());
+        }
+    }
+
     /// Consumes the complete `DocSet` and
     /// push the scored documents to the collector.
+    ///
+    /// If the collector may report a minimum competitive score, it is
+    /// pushed down to the scorer, as it rises.
+    /// Otherwise, the documents go through `.for_each(...)`.
     fn collect(&mut self, collector: &mut Collector) {
+        if !collector.has_min_competitive_score() {
+            self.for_each(&mut |doc, score| collector.collect(doc, score));
+            return;
+        }
+        let mut min_competitive_score = None;
         while self.advance() {
             collector.collect(self.doc(), self.score());
+            let collector_min_score = collector.min_competitive_score();
+            if collector_min_score != min_competitive_score {
+                if let Some(min_score) = collector_min_score {
+                    self.set_min_competitive_score(min_score);
+                }
+                min_competitive_score = collector_min_score;
+            }
         }
     }
 }
@@ -34,6 +83,14 @@ impl Scorer for Box<Scorer> {
         self.deref_mut().score()
     }
 
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        self.deref_mut().set_min_competitive_score(min_score);
+    }
+
+    fn for_each(&mut self, callback: &mut FnMut(DocId, Score)) {
+        self.deref_mut().for_each(callback);
+    }
+
     fn collect(&mut self, collector: &mut Collector) {
         let scorer = self.deref_mut();
         scorer.collect(collector);
@@ -126,8 +183,67 @@ impl<TDocSet: DocSet + 'static> Scorer for ConstScorer<TDocSet> {
 
 #[cfg(test)]
 mod tests {
-    use super::EmptyScorer;
+    use super::{EmptyScorer, Scorer};
+    use collector::{CountCollector, TopCollector};
+    use query::VecDocSet;
+    use DocId;
     use DocSet;
+    use Score;
+
+    /// Scorer recording whether its documents went through `.for_each(...)`.
+    struct ForEachScorer {
+        docset: VecDocSet,
+        used_for_each: bool,
+    }
+
+    impl DocSet for ForEachScorer {
+        fn advance(&mut self) -> bool {
+            self.docset.advance()
+        }
+
+        fn doc(&self) -> DocId {
+            self.docset.doc()
+        }
+
+        fn size_hint(&self) -> u32 {
+            self.docset.size_hint()
+        }
+    }
+
+    impl Scorer for ForEachScorer {
+        fn score(&mut self) -> Score {
+            1f32
+        }
+
+        fn for_each(&mut self, callback: &mut FnMut(DocId, Score)) {
+            self.used_for_each = true;
+            while self.advance() {
+                callback(self.doc(), 1f32);
+            }
+        }
+    }
+
+    fn for_each_scorer() -> ForEachScorer {
+        ForEachScorer {
+            docset: VecDocSet::from(vec![1, 3, 5]),
+            used_for_each: false,
+        }
+    }
+
+    #[test]
+    fn test_collect_for_each_without_min_competitive_score() {
+        let mut scorer = for_each_scorer();
+        let mut count_collector = CountCollector::default();
+        scorer.collect(&mut count_collector);
+        assert!(scorer.used_for_each);
+        assert_eq!(count_collector.count(), 3);
+
+        let mut scorer = for_each_scorer();
+        let mut top_collector = TopCollector::with_limit(2);
+        scorer.collect(&mut top_collector);
+        assert!(!scorer.used_for_each);
+        assert_eq!(top_collector.docs().len(), 2);
+    }
 
     #[test]
     fn test_empty_scorer() {
@@ -0,0 +1,159 @@
+use DocId;
+use docset::DocSet;
+use std::cmp;
+
+/// Adapts an iterator over sorted doc ids into a `DocSet`.
+///
+/// It makes it possible to inject doc ids computed outside of tantivy,
+/// for instance by an access control service or by an external
+/// vector index, into the search of a segment: wrapped into a
+/// `ConstScorer`, the `DocSet` can be returned by a `Weight` of your own,
+/// and the associated query used as a filter clause of a `BooleanQuery`.
+///
+/// The doc ids must be segment-local, and strictly increasing.
+/// They are taken as they are: deleted documents are not filtered out.
+///
+/// ```rust
+/// # #[macro_use]
+/// # extern crate tantivy;
+/// # use std::collections::HashMap;
+/// # use tantivy::{DocId, Index, Result, Searcher, SegmentId, SegmentReader};
+/// # use tantivy::query::{BooleanQuery, ConstScorer, DocSetFromSortedIter, Occur, Query};
+/// # use tantivy::query::{Scorer, TermQuery, Weight};
+/// # use tantivy::schema::*;
+/// // doc ids returned by an access control service, for each segment.
+/// #[derive(Debug)]
+/// struct AllowedDocsQuery {
+///     allowed_docs: HashMap<SegmentId, Vec<DocId>>,
+/// }
+///
+/// impl Query for AllowedDocsQuery {
+///     fn weight(&self, _: &Searcher, _: bool) -> Result<Box<Weight>> {
+///         Ok(Box::new(AllowedDocsWeight {
+///             allowed_docs: self.allowed_docs.clone(),
+///         }))
+///     }
+/// }
+///
+/// struct AllowedDocsWeight {
+///     allowed_docs: HashMap<SegmentId, Vec<DocId>>,
+/// }
+///
+/// impl Weight for AllowedDocsWeight {
+///     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+///         let doc_ids = self.allowed_docs
+///             .get(&reader.segment_id())
+///             .cloned()
+///             .unwrap_or_default();
+///         Ok(Box::new(ConstScorer::new(DocSetFromSortedIter::new(doc_ids))))
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut schema_builder = SchemaBuilder::default();
+/// let title = schema_builder.add_text_field("title", TEXT);
+/// let index = Index::create_in_ram(schema_builder.build());
+/// let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+/// for _ in 0..4 {
+///     index_writer.add_document(doc!(title => "hello"));
+/// }
+/// index_writer.commit().unwrap();
+/// index.load_searchers().unwrap();
+/// let searcher = index.searcher();
+///
+/// let mut allowed_docs = HashMap::new();
+/// allowed_docs.insert(searcher.segment_reader(0).segment_id(), vec![0, 2]);
+/// let hello = Term::from_field_text(title, "hello");
+/// let term_query: Box<Query> = Box::new(TermQuery::new(hello, IndexRecordOption::Basic));
+/// let filter_query: Box<Query> = Box::new(AllowedDocsQuery { allowed_docs });
+/// let query = BooleanQuery::from(vec![(Occur::Must, term_query), (Occur::Must, filter_query)]);
+/// assert_eq!(query.count(&*searcher).unwrap(), 2);
+/// # }
+/// ```
+pub struct DocSetFromSortedIter<TIter: Iterator<Item = DocId>> {
+    iter: TIter,
+    started: bool,
+    doc: DocId,
+    size_hint: u32,
+}
+
+impl<TIter: Iterator<Item = DocId>> DocSetFromSortedIter<TIter> {
+    /// Creates a `DocSet` from sorted doc ids.
+    ///
+    /// Its size hint is the lower bound of the size hint of the iterator.
+    pub fn new<T>(doc_ids: T) -> DocSetFromSortedIter<TIter>
+    where
+        T: IntoIterator<Item = DocId, IntoIter = TIter>,
+    {
+        let iter = doc_ids.into_iter();
+        let size_hint = cmp::min(iter.size_hint().0, u32::max_value() as usize) as u32;
+        DocSetFromSortedIter {
+            iter,
+            started: false,
+            doc: 0u32,
+            size_hint,
+        }
+    }
+}
+
+impl<TIter: Iterator<Item = DocId>> DocSet for DocSetFromSortedIter<TIter> {
+    /// # Panics
+    /// If the doc ids are not strictly increasing, panics.
+    fn advance(&mut self) -> bool {
+        match self.iter.next() {
+            Some(doc) => {
+                assert!(
+                    !self.started || doc > self.doc,
+                    "The doc ids of a DocSetFromSortedIter must be strictly increasing. \
+                     Got {} after {}.",
+                    doc,
+                    self.doc
+                );
+                self.started = true;
+                self.doc = doc;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn doc(&self) -> DocId {
+        self.doc
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.size_hint
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use DocId;
+    use docset::DocSet;
+    use postings::tests::test_skip_against_unoptimized;
+    use super::DocSetFromSortedIter;
+
+    #[test]
+    fn test_docset_from_sorted_iter() {
+        let mut docset = DocSetFromSortedIter::new((0..10u32).map(|doc| doc * 3));
+        assert_eq!(docset.size_hint(), 10);
+        let mut docs = vec![];
+        while docset.advance() {
+            docs.push(docset.doc());
+        }
+        assert_eq!(docs, vec![0, 3, 6, 9, 12, 15, 18, 21, 24, 27]);
+        test_skip_against_unoptimized(
+            || box DocSetFromSortedIter::new(vec![1u32, 2, 5, 130, 131, 1_000]),
+            vec![0, 1, 3, 5, 129, 131, 999, 1_000, 1_001],
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_docset_from_unsorted_iter() {
+        let doc_ids: Vec<DocId> = vec![1, 5, 3];
+        let mut docset = DocSetFromSortedIter::new(doc_ids);
+        while docset.advance() {}
+    }
+}
@@ -18,11 +18,14 @@ mod tests {
     use schema::*;
     use schema::IndexRecordOption;
     use fastfield::FastFieldReader;
+    use collector::TopCollector;
+    use DocId;
 
     fn abs_diff(left: f32, right: f32) -> f32 {
         (right - left).abs()
     }
 
+    #[cfg(feature = "mmap")]
     #[test]
     pub fn test_term_query_no_freq() {
         let mut schema_builder = SchemaBuilder::default();
@@ -63,9 +66,142 @@ mod tests {
             idf: 0.30685282,
             fieldnorm_reader_opt: Some(left_fieldnorms),
             postings: left,
+            min_competitive_score: 0f32,
         };
         left_scorer.advance();
         assert!(abs_diff(left_scorer.score(), 0.15342641) < 0.001f32);
     }
 
+    #[test]
+    pub fn test_term_weight_for_each() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let id_field = schema_builder.add_u64_field("id", INT_INDEXED);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for id in 0u64..300u64 {
+                let text = if id % 3 == 0 { "a a b" } else { "a" };
+                index_writer.add_document(doc!(text_field => text, id_field => id));
+            }
+            assert!(index_writer.commit().is_ok());
+            index_writer.delete_term(Term::from_field_u64(id_field, 5u64));
+            index_writer.delete_term(Term::from_field_u64(id_field, 200u64));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let segment_reader = searcher.segment_reader(0);
+        let term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        let term_weight = term_query.weight(&*searcher, true).unwrap();
+
+        let mut expected = vec![];
+        let mut term_scorer = term_weight.scorer(segment_reader).unwrap();
+        while term_scorer.advance() {
+            expected.push((term_scorer.doc(), term_scorer.score()));
+        }
+        assert_eq!(expected.len(), 298);
+
+        let mut doc_scores = vec![];
+        term_weight
+            .for_each(segment_reader, &mut |doc, score| doc_scores.push((doc, score)))
+            .unwrap();
+        assert_eq!(doc_scores, expected);
+
+        // `for_each` goes through the remaining documents only.
+        let mut term_scorer = term_weight.scorer(segment_reader).unwrap();
+        assert!(term_scorer.advance());
+        assert!(term_scorer.advance());
+        let mut doc_scores = vec![];
+        term_scorer.for_each(&mut |doc, score| doc_scores.push((doc, score)));
+        assert_eq!(&doc_scores[..], &expected[2..]);
+    }
+
+    #[test]
+    pub fn test_term_scorer_min_competitive_score() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_field = schema_builder.add_text_field("text", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            // the third block of the postings of `a` is the only one
+            // with a term frequency greater than one.
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            for doc in 0..1_000 {
+                let text = if doc >= 256 && doc < 384 { "a a a a" } else { "a" };
+                index_writer.add_document(doc!(text_field => text));
+            }
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let mut term_query = TermQuery::new(
+            Term::from_field_text(text_field, "a"),
+            IndexRecordOption::WithFreqs,
+        );
+        term_query.set_length_normalization(false);
+        let term_weight = term_query.specialized_weight(&*searcher, true);
+        let mut term_scorer = term_weight
+            .specialized_scorer(searcher.segment_reader(0))
+            .unwrap();
+        assert!(term_scorer.advance());
+        let low_score = term_scorer.score();
+        term_scorer.set_min_competitive_score(low_score * 1.5f32);
+        let mut docs = vec![];
+        while term_scorer.advance() {
+            docs.push(term_scorer.doc());
+        }
+        assert_eq!(docs, (256..384).collect::<Vec<DocId>>());
+
+        let mut top_collector = TopCollector::with_limit(3);
+        searcher.search(&term_query, &mut top_collector).unwrap();
+        let mut top_docs: Vec<DocId> = top_collector
+            .docs()
+            .into_iter()
+            .map(|doc_address| doc_address.doc())
+            .collect();
+        top_docs.sort();
+        assert_eq!(top_docs, vec![256, 257, 258]);
+    }
+
+    #[test]
+    pub fn test_term_query_length_normalization() {
+        let mut schema_builder = SchemaBuilder::default();
+        let tags_field = schema_builder.add_text_field("tags", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(tags_field => "rust"));
+            index_writer.add_document(doc!(tags_field => "rust search engine library"));
+            assert!(index_writer.commit().is_ok());
+        }
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let scores = |term_query: &TermQuery| -> Vec<f32> {
+            let term_weight = term_query.specialized_weight(&*searcher, true);
+            let mut term_scorer = term_weight
+                .specialized_scorer(searcher.segment_reader(0))
+                .unwrap();
+            let mut scores = vec![];
+            while term_scorer.advance() {
+                scores.push(term_scorer.score());
+            }
+            scores
+        };
+        let mut term_query = TermQuery::new(
+            Term::from_field_text(tags_field, "rust"),
+            IndexRecordOption::WithFreqs,
+        );
+        let normalized_scores = scores(&term_query);
+        assert_eq!(normalized_scores.len(), 2);
+        assert!(normalized_scores[0] > normalized_scores[1]);
+
+        term_query.set_length_normalization(false);
+        let scores = scores(&term_query);
+        assert_eq!(scores.len(), 2);
+        assert!(abs_diff(scores[0], scores[1]) < 0.001f32);
+        assert!(abs_diff(scores[0], normalized_scores[0]) < 0.001f32);
+    }
 }
@@ -5,6 +5,7 @@ use query::Query;
 use query::Weight;
 use schema::IndexRecordOption;
 use Searcher;
+use std::collections::BTreeSet;
 
 /// A Term query matches all of the documents
 /// containing a specific term.
@@ -15,10 +16,14 @@ use Searcher;
 /// * `idf`        - inverse document frequency.
 /// * `term_freq`  - number of occurrences of the term in the field
 /// * `field norm` - number of tokens in the field.
+///
+/// The normalization by the field norm can be disabled with
+/// `.set_length_normalization(false)`.
 #[derive(Debug)]
 pub struct TermQuery {
     term: Term,
     index_record_option: IndexRecordOption,
+    length_normalization: bool,
 }
 
 impl TermQuery {
@@ -27,9 +32,22 @@ impl TermQuery {
         TermQuery {
             term,
             index_record_option: segment_postings_options,
+            length_normalization: true,
         }
     }
 
+    /// Enables or disables the normalization of the score
+    /// by the number of tokens of the field. It is enabled by default.
+    ///
+    /// Without it, the score only depends on the term frequency, so that
+    /// documents with many values in a field, such as a list of tags,
+    /// are not penalized.
+    ///
+    /// Fields without field norms are never normalized.
+    pub fn set_length_normalization(&mut self, length_normalization: bool) {
+        self.length_normalization = length_normalization;
+    }
+
     /// Returns a weight object.
     ///
     /// While `.weight(...)` returns a boxed trait object,
@@ -41,11 +59,13 @@ impl TermQuery {
         } else {
             IndexRecordOption::Basic
         };
+        let statistics_provider = searcher.statistics_provider();
         TermWeight {
-            num_docs: searcher.num_docs(),
-            doc_freq: searcher.doc_freq(&self.term),
+            num_docs: statistics_provider.total_num_docs(),
+            doc_freq: statistics_provider.doc_freq(&self.term),
             term: self.term.clone(),
             index_record_option,
+            length_normalization: self.length_normalization,
         }
     }
 }
@@ -54,4 +74,8 @@ impl Query for TermQuery {
     fn weight(&self, searcher: &Searcher, scoring_enabled: bool) -> Result<Box<Weight>> {
         Ok(box self.specialized_weight(searcher, scoring_enabled))
     }
+
+    fn query_terms(&self, term_set: &mut BTreeSet<Term>) {
+        term_set.insert(self.term.clone());
+    }
 }
@@ -10,17 +10,63 @@ pub struct TermScorer {
     pub idf: Score,
     pub fieldnorm_reader_opt: Option<FastFieldReader<u64>>,
     pub postings: SegmentPostings,
+    /// Blocks whose documents cannot reach this
+    /// score are skipped by `.advance()`.
+    pub min_competitive_score: Score,
 }
 
 impl TermScorer {
     pub fn postings(&self) -> &SegmentPostings {
         &self.postings
     }
+
+    /// Returns an upper bound of the scores of the
+    /// documents of the current block.
+    ///
+    /// As the term frequency of a document is lower than its field norm,
+    /// the bound only depends on the term frequencies if
+    /// the field norms are not used.
+    pub fn block_max_score(&self) -> Score {
+        let tf = match self.fieldnorm_reader_opt {
+            Some(_) => 1f32,
+            None => self.postings.block_max_term_freq() as f32,
+        };
+        self.idf * tf.sqrt()
+    }
+
+    /// Skips the remaining documents of the current block, as well as
+    /// the following blocks whose documents cannot reach a score of `min_score`.
+    ///
+    /// Returns false iff there are no remaining documents.
+    pub fn skip_block(&mut self, min_score: Score) -> bool {
+        let min_term_freq = if self.fieldnorm_reader_opt.is_some() || self.idf <= 0f32 {
+            1u32
+        } else {
+            // score = idf * sqrt(tf)
+            let ratio = min_score / self.idf;
+            if ratio <= 1f32 {
+                1u32
+            } else if ratio * ratio >= u32::max_value() as f32 {
+                u32::max_value()
+            } else {
+                (ratio * ratio).ceil() as u32
+            }
+        };
+        self.postings.skip_block(min_term_freq)
+    }
 }
 
 impl DocSet for TermScorer {
     fn advance(&mut self) -> bool {
-        self.postings.advance()
+        if !self.postings.advance() {
+            return false;
+        }
+        let min_score = self.min_competitive_score;
+        if min_score > 0f32 && self.block_max_score() < min_score {
+            self.skip_block(min_score)
+        } else {
+            true
+        }
     }
 
     fn doc(&self) -> DocId {
@@ -34,18 +80,48 @@ impl DocSet for TermScorer {
     fn skip_next(&mut self, target: DocId) -> SkipResult {
         self.postings.skip_next(target)
     }
+
+    fn bitset_bucket(&self, bucket: u32) -> Option<u64> {
+        self.postings.bitset_bucket(bucket)
+    }
+}
+
+fn term_score(
+    idf: Score,
+    fieldnorm_reader_opt: &Option<FastFieldReader<u64>>,
+    doc: DocId,
+    term_freq: u32,
+) -> Score {
+    let tf = match *fieldnorm_reader_opt {
+        Some(ref fieldnorm_reader) => {
+            let field_norm = fieldnorm_reader.get(doc);
+            (term_freq as f32 / field_norm as f32)
+        }
+        None => term_freq as f32,
+    };
+    idf * tf.sqrt()
 }
 
 impl Scorer for TermScorer {
     fn score(&mut self) -> Score {
         let doc = self.postings.doc();
-        let tf = match self.fieldnorm_reader_opt {
-            Some(ref fieldnorm_reader) => {
-                let field_norm = fieldnorm_reader.get(doc);
-                (self.postings.term_freq() as f32 / field_norm as f32)
-            }
-            None => self.postings.term_freq() as f32,
-        };
-        self.idf * tf.sqrt()
+        term_score(
+            self.idf,
+            &self.fieldnorm_reader_opt,
+            doc,
+            self.postings.term_freq(),
+        )
+    }
+
+    fn set_min_competitive_score(&mut self, min_score: Score) {
+        self.min_competitive_score = min_score;
+    }
+
+    fn for_each(&mut self, callback: &mut FnMut(DocId, Score)) {
+        let idf = self.idf;
+        let fieldnorm_reader_opt = &self.fieldnorm_reader_opt;
+        self.postings.for_each_remaining(|doc, term_freq| {
+            callback(doc, term_score(idf, fieldnorm_reader_opt, doc, term_freq));
+        });
     }
 }
@@ -9,10 +9,11 @@ use super::term_scorer::TermScorer;
 use Result;
 
 pub struct TermWeight {
-    pub(crate) num_docs: u32,
-    pub(crate) doc_freq: u32,
+    pub(crate) num_docs: u64,
+    pub(crate) doc_freq: u64,
     pub(crate) term: Term,
     pub(crate) index_record_option: IndexRecordOption,
+    pub(crate) length_normalization: bool,
 }
 
 impl Weight for TermWeight {
@@ -33,6 +34,15 @@ impl Weight for TermWeight {
             Ok(self.specialized_scorer(reader)?.count())
         }
     }
+
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        let field = self.term.field();
+        Ok(reader
+            .inverted_index(field)
+            .get_term_info(&self.term)
+            .map(|term_info| u64::from(term_info.doc_freq))
+            .unwrap_or(0))
+    }
 }
 
 impl TermWeight {
@@ -44,20 +54,26 @@ impl TermWeight {
     pub fn specialized_scorer(&self, reader: &SegmentReader) -> Result<TermScorer> {
         let field = self.term.field();
         let inverted_index = reader.inverted_index(field);
-        let fieldnorm_reader_opt = reader.get_fieldnorms_reader(field);
+        let fieldnorm_reader_opt = if self.length_normalization {
+            reader.get_fieldnorms_reader(field)
+        } else {
+            None
+        };
         let postings_opt: Option<SegmentPostings> =
-            inverted_index.read_postings(&self.term, self.index_record_option);
+            inverted_index.read_postings(&self.term, self.index_record_option)?;
         if let Some(segment_postings) = postings_opt {
             Ok(TermScorer {
                 idf: self.idf(),
                 fieldnorm_reader_opt,
                 postings: segment_postings,
+                min_competitive_score: 0f32,
             })
         } else {
             Ok(TermScorer {
                 idf: 1f32,
                 fieldnorm_reader_opt: None,
                 postings: SegmentPostings::empty(),
+                min_competitive_score: 0f32,
             })
         }
     }
@@ -17,6 +17,8 @@ pub struct Union<TScorer, TScoreCombiner = DoNothingCombiner> {
     cursor: usize,
     offset: DocId,
     doc: DocId,
+    // combined score of the current doc, computed once
+    // as the doc gets popped out of the buffer.
     score: Score,
 }
 
@@ -251,7 +253,8 @@ mod tests {
     use postings::tests::test_skip_against_unoptimized;
     use query::VecDocSet;
     use query::ConstScorer;
-    use query::score_combiner::DoNothingCombiner;
+    use query::Scorer;
+    use query::score_combiner::{DoNothingCombiner, SumCombiner};
 
     fn aux_test_union(vals: Vec<Vec<u32>>) {
         let mut val_set: BTreeSet<u32> = BTreeSet::new();
@@ -282,6 +285,21 @@ mod tests {
         assert_eq!(count, make_union().count());
     }
 
+    #[test]
+    fn test_union_repeated_score() {
+        let mut union: Union<_, SumCombiner> = Union::from(vec![
+            ConstScorer::new(VecDocSet::from(vec![1, 3])),
+            ConstScorer::new(VecDocSet::from(vec![3, 5])),
+        ]);
+        let mut doc_scores = vec![];
+        while union.advance() {
+            let score = union.score();
+            assert_eq!(union.score(), score);
+            doc_scores.push((union.doc(), score));
+        }
+        assert_eq!(doc_scores, vec![(1, 1f32), (3, 2f32), (5, 1f32)]);
+    }
+
     #[test]
     fn test_union() {
         aux_test_union(vec![
@@ -1,8 +1,8 @@
 #![allow(dead_code)]
 
 use DocId;
-use docset::DocSet;
-use common::HasLen;
+use docset::{DocSet, SkipResult};
+use common::{galloping_search, HasLen};
 use std::num::Wrapping;
 
 const EMPTY_ARRAY: [u32; 0] = [];
@@ -32,6 +32,21 @@ impl DocSet for VecDocSet {
         self.doc_ids.len() > self.cursor.0
     }
 
+    fn skip_next(&mut self, target: DocId) -> SkipResult {
+        if !self.advance() {
+            return SkipResult::End;
+        }
+        let start = self.cursor.0;
+        self.cursor += Wrapping(galloping_search(&self.doc_ids[start..], target));
+        if self.cursor.0 >= self.doc_ids.len() {
+            SkipResult::End
+        } else if self.doc() == target {
+            SkipResult::Reached
+        } else {
+            SkipResult::OverStep
+        }
+    }
+
     fn doc(&self) -> DocId {
         self.doc_ids[self.cursor.0]
     }
@@ -53,6 +68,7 @@ pub mod tests {
     use super::*;
     use DocId;
     use docset::{DocSet, SkipResult};
+    use postings::tests::test_skip_against_unoptimized;
 
     #[test]
     pub fn test_vec_postings() {
@@ -85,4 +101,15 @@ pub mod tests {
         assert_eq!(postings.fill_buffer(&mut buffer[..]), 9);
     }
 
+    #[test]
+    pub fn test_vec_docset_skip_against_unoptimized() {
+        test_skip_against_unoptimized(
+            || {
+                let doc_ids: Vec<DocId> = (0u32..300u32).map(|e| e * e).collect();
+                box VecDocSet::from(doc_ids)
+            },
+            vec![0, 1, 2, 4, 5, 1_000, 1_024, 50_000, 89_401, 89_402],
+        );
+    }
+
 }
@@ -0,0 +1,236 @@
+use core::SegmentReader;
+use core::Searcher;
+use docset::DocSet;
+use error::ErrorKind;
+use fastfield::{DeleteBitSet, VectorFastFieldReader};
+use query::{AllWeight, Query, Scorer, Weight};
+use schema::{Field, VectorSimilarity};
+use DocId;
+use Result;
+use Score;
+
+/// `VectorSimilarityQuery` matches all of the documents that have a vector
+/// for the given vector field, and scores them by their similarity to the
+/// query vector.
+///
+/// The query can be restricted to the documents matching a filter query.
+/// The score of the filter is ignored.
+///
+/// # Implementation
+///
+/// The similarity is computed by brute force, for each candidate document.
+/// The cost of the query is therefore linear in the number of candidates,
+/// and it is best used together with a selective filter, or on small indexes.
+#[derive(Debug)]
+pub struct VectorSimilarityQuery {
+    field: Field,
+    vector: Vec<f32>,
+    similarity: VectorSimilarity,
+    filter: Option<Box<Query>>,
+}
+
+impl VectorSimilarityQuery {
+    /// Creates a new `VectorSimilarityQuery` over the vector field `field`.
+    ///
+    /// The dimension of `vector` must match the dimension of the field.
+    pub fn new(
+        field: Field,
+        vector: Vec<f32>,
+        similarity: VectorSimilarity,
+    ) -> VectorSimilarityQuery {
+        VectorSimilarityQuery {
+            field,
+            vector,
+            similarity,
+            filter: None,
+        }
+    }
+
+    /// Restricts the query to the documents matching `filter`.
+    pub fn with_filter(mut self, filter: Box<Query>) -> VectorSimilarityQuery {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+impl Query for VectorSimilarityQuery {
+    fn weight(&self, searcher: &Searcher, _scoring_enabled: bool) -> Result<Box<Weight>> {
+        let filter_weight = match self.filter {
+            Some(ref filter) => Some(searcher.create_weight(&**filter, false)?),
+            None => None,
+        };
+        Ok(box VectorSimilarityWeight {
+            field: self.field,
+            vector: self.vector.clone(),
+            similarity: self.similarity,
+            filter_weight,
+        })
+    }
+}
+
+struct VectorSimilarityWeight {
+    field: Field,
+    vector: Vec<f32>,
+    similarity: VectorSimilarity,
+    filter_weight: Option<Box<Weight>>,
+}
+
+impl Weight for VectorSimilarityWeight {
+    fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>> {
+        let vector_reader = reader.vector_fast_field_reader(self.field)?;
+        if vector_reader.dimension() != self.vector.len() {
+            bail!(ErrorKind::InvalidArgument(format!(
+                "The query vector has a dimension of {}, while the field {:?} \
+                 has a dimension of {}.",
+                self.vector.len(),
+                self.field,
+                vector_reader.dimension()
+            )));
+        }
+        let candidates = match self.filter_weight {
+            Some(ref filter_weight) => filter_weight.scorer(reader)?,
+            None => AllWeight.scorer(reader)?,
+        };
+        Ok(box VectorSimilarityScorer {
+            candidates,
+            vector_reader,
+            delete_bitset: reader.delete_bitset().clone(),
+            vector: self.vector.clone(),
+            similarity: self.similarity,
+            doc_vector: Vec::with_capacity(self.vector.len()),
+        })
+    }
+}
+
+struct VectorSimilarityScorer {
+    candidates: Box<Scorer>,
+    vector_reader: VectorFastFieldReader,
+    delete_bitset: DeleteBitSet,
+    vector: Vec<f32>,
+    similarity: VectorSimilarity,
+    // vector of the current document.
+    doc_vector: Vec<f32>,
+}
+
+impl DocSet for VectorSimilarityScorer {
+    fn advance(&mut self) -> bool {
+        while self.candidates.advance() {
+            let doc = self.candidates.doc();
+            if !self.delete_bitset.is_deleted(doc)
+                && self.vector_reader.get_val(doc, &mut self.doc_vector)
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    fn doc(&self) -> DocId {
+        self.candidates.doc()
+    }
+
+    fn size_hint(&self) -> u32 {
+        self.candidates.size_hint()
+    }
+}
+
+impl Scorer for VectorSimilarityScorer {
+    fn score(&mut self) -> Score {
+        self.similarity.compute(&self.vector, &self.doc_vector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use Index;
+    use collector::{CountCollector, TopCollector};
+    use query::TermQuery;
+    use schema::{IndexRecordOption, SchemaBuilder, Term, STRING};
+
+    fn create_index() -> (Index, Field, Field) {
+        let mut schema_builder = SchemaBuilder::default();
+        let tag_field = schema_builder.add_text_field("tag", STRING);
+        let embedding_field = schema_builder.add_vector_field("embedding", 2);
+        let index = Index::create_in_ram(schema_builder.build());
+        {
+            let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+            index_writer.add_document(doc!(tag_field=>"a", embedding_field=>vec![1f32, 0f32]));
+            index_writer.add_document(doc!(tag_field=>"b", embedding_field=>vec![0f32, 3f32]));
+            index_writer.add_document(doc!(tag_field=>"a"));
+            index_writer.add_document(doc!(tag_field=>"a", embedding_field=>vec![2f32, 2f32]));
+            index_writer.add_document(doc!(tag_field=>"b", embedding_field=>vec![0f32, 0f32]));
+            index_writer.commit().unwrap();
+        }
+        index.load_searchers().unwrap();
+        (index, tag_field, embedding_field)
+    }
+
+    fn top_docs(index: &Index, query: &Query) -> Vec<(DocId, Score)> {
+        let searcher = index.searcher();
+        let mut top_collector = TopCollector::with_limit(10);
+        searcher.search(query, &mut top_collector).unwrap();
+        top_collector
+            .score_docs()
+            .into_iter()
+            .map(|(score, doc_address)| (doc_address.doc(), score))
+            .collect()
+    }
+
+    #[test]
+    fn test_vector_similarity_query_dot() {
+        let (index, _, embedding_field) = create_index();
+        let query =
+            VectorSimilarityQuery::new(embedding_field, vec![1f32, 1f32], VectorSimilarity::Dot);
+        assert_eq!(
+            top_docs(&index, &query),
+            vec![(3, 4f32), (1, 3f32), (0, 1f32), (4, 0f32)]
+        );
+    }
+
+    #[test]
+    fn test_vector_similarity_query_cosine() {
+        let (index, _, embedding_field) = create_index();
+        let query = VectorSimilarityQuery::new(
+            embedding_field,
+            vec![1f32, 2f32],
+            VectorSimilarity::Cosine,
+        );
+        let results = top_docs(&index, &query);
+        let docs: Vec<DocId> = results.iter().map(|&(doc, _)| doc).collect();
+        assert_eq!(docs, vec![3, 1, 0, 4]);
+        assert!((results[0].1 - 3f32 / 10f32.sqrt()).abs() < 1e-6);
+        assert!((results[1].1 - 2f32 / 5f32.sqrt()).abs() < 1e-6);
+        assert!((results[2].1 - 1f32 / 5f32.sqrt()).abs() < 1e-6);
+        assert_eq!(results[3].1, 0f32);
+    }
+
+    #[test]
+    fn test_vector_similarity_query_filter() {
+        let (index, tag_field, embedding_field) = create_index();
+        let filter = TermQuery::new(Term::from_field_text(tag_field, "a"), IndexRecordOption::Basic);
+        let query =
+            VectorSimilarityQuery::new(embedding_field, vec![1f32, 1f32], VectorSimilarity::Dot)
+                .with_filter(box filter);
+        assert_eq!(top_docs(&index, &query), vec![(3, 4f32), (0, 1f32)]);
+        let mut count_collector = CountCollector::default();
+        index
+            .searcher()
+            .search(&query, &mut count_collector)
+            .unwrap();
+        assert_eq!(count_collector.count(), 2);
+    }
+
+    #[test]
+    fn test_vector_similarity_query_invalid() {
+        let (index, tag_field, embedding_field) = create_index();
+        let searcher = index.searcher();
+        let query =
+            VectorSimilarityQuery::new(embedding_field, vec![1f32], VectorSimilarity::Dot);
+        let mut count_collector = CountCollector::default();
+        assert!(searcher.search(&query, &mut count_collector).is_err());
+        let query = VectorSimilarityQuery::new(tag_field, vec![1f32, 1f32], VectorSimilarity::Dot);
+        assert!(searcher.search(&query, &mut count_collector).is_err());
+    }
+}
@@ -1,12 +1,17 @@
 use super::Scorer;
+use DocId;
 use Result;
+use Score;
 use core::SegmentReader;
 
 /// A Weight is the specialization of a Query
 /// for a given set of segments.
 ///
 /// See [`Query`](./trait.Query.html).
-pub trait Weight {
+///
+/// Weights are `Send` and `Sync`, so that the segments
+/// can be searched in parallel.
+pub trait Weight: Send + Sync {
     /// Returns the scorer for the given segment.
     /// See [`Query`](./trait.Query.html).
     fn scorer(&self, reader: &SegmentReader) -> Result<Box<Scorer>>;
@@ -15,4 +20,30 @@ pub trait Weight {
     fn count(&self, reader: &SegmentReader) -> Result<u32> {
         Ok(self.scorer(reader)?.count())
     }
+
+    /// Calls `callback` with all of the documents matching
+    /// within the given `SegmentReader`, along with their score.
+    ///
+    /// This is the bulk counterpart of iterating over the scorer.
+    /// See `Scorer::for_each`.
+    fn for_each(&self, reader: &SegmentReader, callback: &mut FnMut(DocId, Score)) -> Result<()> {
+        self.scorer(reader)?.for_each(callback);
+        Ok(())
+    }
+
+    /// Returns an estimate of the cost of scoring the given segment,
+    /// expressed as the number of documents the scorer will go through.
+    ///
+    /// The estimate is computed from statistics, such as document
+    /// frequencies, without reading any posting list, so that it is
+    /// cheap to compute ahead of the search. Deleted documents are
+    /// included, as scorers go through them as well.
+    ///
+    /// A cost of 0 means that the scorer does not match any document.
+    ///
+    /// The default implementation assumes that all of the documents
+    /// of the segment are visited.
+    fn cost(&self, reader: &SegmentReader) -> Result<u64> {
+        Ok(u64::from(reader.max_doc()))
+    }
 }
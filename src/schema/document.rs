@@ -44,6 +44,19 @@ impl Document {
         Document::default()
     }
 
+    /// Build a document from any value implementing serde's `Serialize`,
+    /// typically a struct whose fields match the fields of the schema.
+    ///
+    /// Keys that do not belong to the schema result in a
+    /// `DocParsingError::NoSuchFieldInSchema` error.
+    /// See `Schema::doc_from_serde` to ignore them instead.
+    pub fn from_serde<T: ::serde::Serialize>(
+        schema: &Schema,
+        value: &T,
+    ) -> Result<Document, DocParsingError> {
+        schema.doc_from_serde(value, UnknownFieldPolicy::Error)
+    }
+
     /// Returns the number of `(field, value)` pairs.
     pub fn len(&self) -> usize {
         self.field_values.len()
@@ -87,6 +100,16 @@ impl Document {
         self.add(FieldValue::new(field, Value::I64(value)));
     }
 
+    /// Add a bytes field
+    pub fn add_bytes(&mut self, field: Field, value: Vec<u8>) {
+        self.add(FieldValue::new(field, Value::Bytes(value)));
+    }
+
+    /// Add a vector field
+    pub fn add_vector(&mut self, field: Field, components: Vec<f32>) {
+        self.add(FieldValue::new(field, Value::from(components)));
+    }
+
     /// Add a field value
     pub fn add(&mut self, field_value: FieldValue) {
         self.field_values.push(field_value);
@@ -163,4 +186,47 @@ mod tests {
         assert_eq!(doc.field_values().len(), 1);
     }
 
+    #[derive(Serialize)]
+    struct Book {
+        title: String,
+        #[serde(rename = "num_pages")]
+        pages: u64,
+        tags: Vec<String>,
+        subtitle: Option<String>,
+        isbn: String,
+    }
+
+    #[test]
+    fn test_doc_from_serde() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title = schema_builder.add_text_field("title", TEXT);
+        let num_pages = schema_builder.add_u64_field("num_pages", INT_STORED);
+        let tags = schema_builder.add_text_field("tags", STRING);
+        let subtitle = schema_builder.add_text_field("subtitle", TEXT);
+        let schema = schema_builder.build();
+        let book = Book {
+            title: "Moby Dick".to_string(),
+            pages: 635,
+            tags: vec!["whale".to_string(), "sea".to_string()],
+            subtitle: None,
+            isbn: "978-1503280786".to_string(),
+        };
+        match Document::from_serde(&schema, &book) {
+            Err(DocParsingError::NoSuchFieldInSchema(field_name)) => {
+                assert_eq!(field_name, "isbn");
+            }
+            _ => panic!("Expected NoSuchFieldInSchema error"),
+        }
+        let doc = schema
+            .doc_from_serde(&book, UnknownFieldPolicy::Ignore)
+            .unwrap();
+        assert_eq!(doc.get_first(title), Some(&Value::Str("Moby Dick".to_string())));
+        assert_eq!(doc.get_first(num_pages), Some(&Value::U64(635)));
+        assert_eq!(doc.get_all(tags).len(), 2);
+        assert!(doc.get_first(subtitle).is_none());
+        assert!(schema
+            .doc_from_serde(&vec![1, 2], UnknownFieldPolicy::Ignore)
+            .is_err());
+    }
+
 }
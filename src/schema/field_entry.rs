@@ -1,5 +1,6 @@
 use schema::TextOptions;
 use schema::IntOptions;
+use schema::VectorOptions;
 
 use std::fmt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -14,7 +15,7 @@ use schema::FieldType;
 /// - a field name
 /// - a field type, itself wrapping up options describing
 /// how the field should be indexed.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct FieldEntry {
     name: String,
     field_type: FieldType,
@@ -56,6 +57,22 @@ impl FieldEntry {
         }
     }
 
+    /// Creates a field entry for a bytes field.
+    pub fn new_bytes(field_name: String) -> FieldEntry {
+        FieldEntry {
+            name: field_name,
+            field_type: FieldType::Bytes,
+        }
+    }
+
+    /// Creates a field entry for a vector field.
+    pub fn new_vector(field_name: String, vector_options: VectorOptions) -> FieldEntry {
+        FieldEntry {
+            name: field_name,
+            field_type: FieldType::Vector(vector_options),
+        }
+    }
+
     /// Returns the name of the field
     pub fn name(&self) -> &str {
         &self.name
@@ -72,6 +89,7 @@ impl FieldEntry {
             FieldType::Str(ref options) => options.get_indexing_options().is_some(),
             FieldType::U64(ref options) | FieldType::I64(ref options) => options.is_indexed(),
             FieldType::HierarchicalFacet => true,
+            FieldType::Bytes | FieldType::Vector(_) => false,
         }
     }
 
@@ -90,6 +108,7 @@ impl FieldEntry {
             FieldType::Str(ref options) => options.is_stored(),
             FieldType::HierarchicalFacet => true,
             // TODO make stored hierachical facet optional
+            FieldType::Bytes | FieldType::Vector(_) => false,
         }
     }
 }
@@ -118,6 +137,13 @@ impl Serialize for FieldEntry {
             FieldType::HierarchicalFacet => {
                 s.serialize_field("type", "hierarchical_facet")?;
             }
+            FieldType::Bytes => {
+                s.serialize_field("type", "bytes")?;
+            }
+            FieldType::Vector(ref options) => {
+                s.serialize_field("type", "vector")?;
+                s.serialize_field("options", options)?;
+            }
         }
 
         s.end()
@@ -168,8 +194,14 @@ impl<'de> Deserialize<'de> for FieldEntry {
                                 return Err(de::Error::duplicate_field("type"));
                             }
                             ty = Some(map.next_value()?);
-                            if ty == Some("hierarchical_facet") {
-                                field_type = Some(FieldType::HierarchicalFacet);
+                            match ty {
+                                Some("hierarchical_facet") => {
+                                    field_type = Some(FieldType::HierarchicalFacet);
+                                }
+                                Some("bytes") => {
+                                    field_type = Some(FieldType::Bytes);
+                                }
+                                _ => {}
                             }
                         }
                         Field::Options => match ty {
@@ -182,6 +214,9 @@ impl<'de> Deserialize<'de> for FieldEntry {
                                 "text" => field_type = Some(FieldType::Str(map.next_value()?)),
                                 "u64" => field_type = Some(FieldType::U64(map.next_value()?)),
                                 "i64" => field_type = Some(FieldType::I64(map.next_value()?)),
+                                "vector" => {
+                                    field_type = Some(FieldType::Vector(map.next_value()?))
+                                }
                                 _ => {
                                     let msg = format!("Unrecognised type {}", ty);
                                     return Err(de::Error::custom(msg));
@@ -207,7 +242,7 @@ impl<'de> Deserialize<'de> for FieldEntry {
 mod tests {
 
     use super::*;
-    use schema::TEXT;
+    use schema::{VectorSimilarity, TEXT};
     use serde_json;
 
     #[test]
@@ -220,9 +255,13 @@ mod tests {
   "options": {
     "indexing": {
       "record": "position",
-      "tokenizer": "default"
+      "tokenizer": "default",
+      "position_gap": 1,
+      "term_vectors": false
     },
-    "stored": false
+    "stored": false,
+    "fast": false,
+    "completion": false
   }
 }"#;
         let field_value_json = serde_json::to_string_pretty(&field_value).unwrap();
@@ -238,4 +277,35 @@ mod tests {
             _ => panic!("expected FieldType::Str"),
         }
     }
+
+    #[test]
+    fn test_vector_json_serialization() {
+        let vector_options =
+            VectorOptions::with_dimension(3).set_ann_index(VectorSimilarity::Cosine);
+        let field_value = FieldEntry::new_vector(String::from("embedding"), vector_options.clone());
+        let expected = r#"{
+  "name": "embedding",
+  "type": "vector",
+  "options": {
+    "dimension": 3,
+    "ann_index": "cosine"
+  }
+}"#;
+        assert_eq!(expected, &serde_json::to_string_pretty(&field_value).unwrap());
+        let field_value: FieldEntry = serde_json::from_str(expected).unwrap();
+        assert_eq!(field_value.field_type, FieldType::Vector(vector_options));
+
+        let without_ann_index = r#"{
+  "name": "embedding",
+  "type": "vector",
+  "options": {
+    "dimension": 3
+  }
+}"#;
+        let field_value: FieldEntry = serde_json::from_str(without_ann_index).unwrap();
+        assert_eq!(
+            field_value.field_type,
+            FieldType::Vector(VectorOptions::with_dimension(3))
+        );
+    }
 }
@@ -1,9 +1,12 @@
-use schema::{IntOptions, TextOptions};
+use schema::{IntOptions, TextOptions, VectorOptions};
 
 use serde_json::Value as JsonValue;
 use schema::Value;
 use schema::IndexRecordOption;
 use schema::Facet;
+use schema::Vector;
+use std::error::Error as StdError;
+use std::fmt;
 
 /// Possible error that may occur while parsing a field value
 /// At this point the JSON is known to be valid.
@@ -17,6 +20,21 @@ pub enum ValueParsingError {
     TypeError(String),
 }
 
+impl fmt::Display for ValueParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValueParsingError::OverflowError(ref msg) => write!(f, "overflow: {}", msg),
+            ValueParsingError::TypeError(ref msg) => write!(f, "type error: {}", msg),
+        }
+    }
+}
+
+impl StdError for ValueParsingError {
+    fn description(&self) -> &str {
+        "error occurred while parsing a field value"
+    }
+}
+
 /// A `FieldType` describes the type (text, u64) of a field as well as
 /// how it should be handled by tantivy.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -29,6 +47,10 @@ pub enum FieldType {
     I64(IntOptions),
     /// Hierachical Facet
     HierarchicalFacet,
+    /// Bytes (one per document)
+    Bytes,
+    /// Vector of `f32` of a fixed dimension (one per document)
+    Vector(VectorOptions),
 }
 
 impl FieldType {
@@ -40,6 +62,7 @@ impl FieldType {
                 int_options.is_indexed()
             }
             FieldType::HierarchicalFacet => true,
+            FieldType::Bytes | FieldType::Vector(_) => false,
         }
     }
 
@@ -60,6 +83,7 @@ impl FieldType {
                 }
             }
             FieldType::HierarchicalFacet => Some(IndexRecordOption::Basic),
+            FieldType::Bytes | FieldType::Vector(_) => None,
         }
     }
 
@@ -76,6 +100,14 @@ impl FieldType {
                     format!("Expected an integer, got {:?}", json),
                 )),
                 FieldType::HierarchicalFacet => Ok(Value::Facet(Facet::from(field_text))),
+                FieldType::Bytes => Err(ValueParsingError::TypeError(format!(
+                    "Bytes values cannot be parsed from json, got {:?}",
+                    json
+                ))),
+                FieldType::Vector(_) => Err(ValueParsingError::TypeError(format!(
+                    "Expected an array of numbers, got {:?}",
+                    json
+                ))),
             },
             JsonValue::Number(ref field_val_num) => match *self {
                 FieldType::I64(_) => {
@@ -98,6 +130,41 @@ impl FieldType {
                     let msg = format!("Expected a string, got {:?}", json);
                     Err(ValueParsingError::TypeError(msg))
                 }
+                FieldType::Bytes => {
+                    let msg = format!("Bytes values cannot be parsed from json, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
+                FieldType::Vector(_) => {
+                    let msg = format!("Expected an array of numbers, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
+            },
+            JsonValue::Array(ref json_items) => match *self {
+                FieldType::Vector(ref options) => {
+                    if json_items.len() != options.dimension() {
+                        let msg = format!(
+                            "Expected a vector of dimension {}, got {} components",
+                            options.dimension(),
+                            json_items.len()
+                        );
+                        return Err(ValueParsingError::TypeError(msg));
+                    }
+                    let mut components = Vec::with_capacity(json_items.len());
+                    for json_item in json_items {
+                        match json_item.as_f64() {
+                            Some(component) => components.push(component as f32),
+                            None => {
+                                let msg = format!("Expected a number, got {:?}", json_item);
+                                return Err(ValueParsingError::TypeError(msg));
+                            }
+                        }
+                    }
+                    Ok(Value::Vector(Vector::from(components)))
+                }
+                _ => {
+                    let msg = format!("Expected a single value, got {:?}", json);
+                    Err(ValueParsingError::TypeError(msg))
+                }
             },
             _ => {
                 let msg = format!(
@@ -26,13 +26,23 @@ pub enum IndexRecordOption {
     /// Positions are required to run [PhraseQueries](../query/struct.PhraseQuery.html).
     #[serde(rename = "position")]
     WithFreqsAndPositions,
+    /// records the document id, the term frequency, the positions of
+    /// the occurences in the document, as well as their byte offsets
+    /// in the original text.
+    /// Offsets make it possible to highlight the matches of a query
+    /// without analyzing the text again
+    /// (See [SnippetGenerator](../snippet/struct.SnippetGenerator.html)).
+    #[serde(rename = "offset")]
+    WithFreqsAndPositionsAndOffsets,
 }
 
 impl IndexRecordOption {
     /// Returns true iff the term frequency will be encoded.
     pub fn is_termfreq_enabled(&self) -> bool {
         match *self {
-            IndexRecordOption::WithFreqsAndPositions | IndexRecordOption::WithFreqs => true,
+            IndexRecordOption::WithFreqsAndPositionsAndOffsets
+            | IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqs => true,
             _ => false,
         }
     }
@@ -40,7 +50,8 @@ impl IndexRecordOption {
     /// Returns true iff the term positions within the document are stored as well.
     pub fn is_position_enabled(&self) -> bool {
         match *self {
-            IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqsAndPositionsAndOffsets
+            | IndexRecordOption::WithFreqsAndPositions => true,
             _ => false,
         }
     }
@@ -50,7 +61,9 @@ impl IndexRecordOption {
     pub fn has_freq(&self) -> bool {
         match *self {
             IndexRecordOption::Basic => false,
-            IndexRecordOption::WithFreqs | IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqs
+            | IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsAndPositionsAndOffsets => true,
         }
     }
 
@@ -59,7 +72,17 @@ impl IndexRecordOption {
     pub fn has_positions(&self) -> bool {
         match *self {
             IndexRecordOption::Basic | IndexRecordOption::WithFreqs => false,
-            IndexRecordOption::WithFreqsAndPositions => true,
+            IndexRecordOption::WithFreqsAndPositions
+            | IndexRecordOption::WithFreqsAndPositionsAndOffsets => true,
+        }
+    }
+
+    /// Returns true iff this option include encoding
+    /// the offsets of the terms.
+    pub fn has_offsets(&self) -> bool {
+        match *self {
+            IndexRecordOption::WithFreqsAndPositionsAndOffsets => true,
+            _ => false,
         }
     }
 }
@@ -111,6 +111,7 @@ mod field_value;
 
 mod text_options;
 mod int_options;
+mod vector;
 mod field;
 mod value;
 mod named_field_document;
@@ -119,7 +120,9 @@ mod index_record_option;
 pub use self::named_field_document::NamedFieldDocument;
 pub use self::schema::{Schema, SchemaBuilder};
 pub use self::value::Value;
-pub use self::schema::DocParsingError;
+pub(crate) use self::value::skip_serialized_value;
+pub use self::schema::{DocParsingError, SchemaError};
+pub use self::schema::UnknownFieldPolicy;
 
 pub use self::facet::Facet;
 pub use self::facet::FACET_SEP_BYTE;
@@ -135,6 +138,7 @@ pub use self::field_value::FieldValue;
 pub use self::text_options::TextOptions;
 pub use self::index_record_option::IndexRecordOption;
 pub use self::text_options::TextFieldIndexing;
+pub(crate) use self::text_options::DEFAULT_POSITION_GAP;
 pub use self::text_options::TEXT;
 pub use self::text_options::STRING;
 pub use self::text_options::STORED;
@@ -145,6 +149,8 @@ pub use self::int_options::INT_INDEXED;
 pub use self::int_options::INT_STORED;
 pub use self::int_options::Cardinality;
 
+pub use self::vector::{Vector, VectorOptions, VectorSimilarity};
+
 use regex::Regex;
 
 /// Validator for a potential `field_name`.
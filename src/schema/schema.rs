@@ -8,6 +8,7 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::ser::SerializeSeq;
 use serde::de::{SeqAccess, Visitor};
 use super::*;
+use std::error::Error as StdError;
 use std::fmt;
 
 /// Tantivy has a very strict schema.
@@ -95,6 +96,39 @@ impl SchemaBuilder {
         self.add_field(field_entry)
     }
 
+    /// Adds a bytes field to the schema.
+    ///
+    /// Bytes fields are neither indexed nor stored.
+    /// They hold one payload per document, that can be accessed
+    /// through `SegmentReader::bytes_fast_field_reader(...)`.
+    pub fn add_bytes_field(&mut self, field_name: &str) -> Field {
+        let field_entry = FieldEntry::new_bytes(field_name.to_string());
+        self.add_field(field_entry)
+    }
+
+    /// Adds a vector field of the given dimension to the schema.
+    ///
+    /// Vector fields are neither indexed nor stored.
+    /// They hold one vector per document, that can be accessed
+    /// through `SegmentReader::vector_fast_field_reader(...)`, and
+    /// are typically used with a `VectorSimilarityQuery`.
+    pub fn add_vector_field(&mut self, field_name: &str, dimension: usize) -> Field {
+        self.add_vector_field_with_options(field_name, VectorOptions::with_dimension(dimension))
+    }
+
+    /// Adds a vector field to the schema, with the given options.
+    ///
+    /// This makes it possible to build an approximate nearest neighbor
+    /// index for the field, which can then be searched with a `KnnQuery`.
+    pub fn add_vector_field_with_options(
+        &mut self,
+        field_name: &str,
+        vector_options: VectorOptions,
+    ) -> Field {
+        let field_entry = FieldEntry::new_vector(field_name.to_string(), vector_options);
+        self.add_field(field_entry)
+    }
+
     /// Adds a field entry to the schema in build.
     fn add_field(&mut self, field_entry: FieldEntry) -> Field {
         let field = Field(self.fields.len() as u32);
@@ -214,15 +248,62 @@ impl Schema {
                 };
                 DocParsingError::NotJSON(doc_json_sample)
             })?;
+        self.json_object_to_doc(&json_obj, UnknownFieldPolicy::Error)
+    }
+
+    /// Build a document object from any value implementing serde's `Serialize`.
+    ///
+    /// The value is expected to serialize into a map, typically
+    /// a struct, whose keys are the names of the schema fields.
+    /// Serde's attributes (e.g. `#[serde(rename = "...")]`, `#[serde(skip)]`)
+    /// can be used to map the struct fields to the schema fields.
+    ///
+    /// `None` values are simply ignored, and sequences result
+    /// in several values for the same field.
+    ///
+    /// The handling of the keys that do not match any field of
+    /// the schema is defined by `unknown_field_policy`.
+    pub fn doc_from_serde<T: Serialize>(
+        &self,
+        value: &T,
+        unknown_field_policy: UnknownFieldPolicy,
+    ) -> Result<Document, DocParsingError> {
+        let json_value = serde_json::to_value(value)
+            .map_err(|e| DocParsingError::InvalidSerdeValue(format!("{}", e)))?;
+        match json_value {
+            JsonValue::Object(json_obj) => {
+                let json_obj: JsonObject<String, JsonValue> = json_obj
+                    .into_iter()
+                    .filter(|&(_, ref json_value)| !json_value.is_null())
+                    .collect();
+                self.json_object_to_doc(&json_obj, unknown_field_policy)
+            }
+            _ => Err(DocParsingError::InvalidSerdeValue(format!(
+                "Expected a value serializing into a map, got {:?}",
+                json_value
+            ))),
+        }
+    }
 
+    fn json_object_to_doc(
+        &self,
+        json_obj: &JsonObject<String, JsonValue>,
+        unknown_field_policy: UnknownFieldPolicy,
+    ) -> Result<Document, DocParsingError> {
         let mut doc = Document::default();
         for (field_name, json_value) in json_obj.iter() {
             match self.get_field(field_name) {
                 Some(field) => {
                     let field_entry = self.get_field_entry(field);
                     let field_type = field_entry.field_type();
+                    // the components of a vector are not a list of values.
+                    let is_vector = if let FieldType::Vector(_) = *field_type {
+                        true
+                    } else {
+                        false
+                    };
                     match *json_value {
-                        JsonValue::Array(ref json_items) => for json_item in json_items {
+                        JsonValue::Array(ref json_items) if !is_vector => for json_item in json_items {
                             let value = field_type
                                 .value_from_json(json_item)
                                 .map_err(|e| DocParsingError::ValueError(field_name.clone(), e))?;
@@ -236,13 +317,24 @@ impl Schema {
                         }
                     }
                 }
-                None => return Err(DocParsingError::NoSuchFieldInSchema(field_name.clone())),
+                None => {
+                    if unknown_field_policy == UnknownFieldPolicy::Error {
+                        return Err(DocParsingError::NoSuchFieldInSchema(field_name.clone()));
+                    }
+                }
             }
         }
         Ok(doc)
     }
 }
 
+impl PartialEq for Schema {
+    fn eq(&self, other: &Schema) -> bool {
+        self.fields() == other.fields()
+    }
+}
+
+impl Eq for Schema {}
 
 impl Serialize for Schema {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
@@ -303,6 +395,83 @@ pub enum DocParsingError {
     ValueError(String, ValueParsingError),
     /// The json-document contains a field that is not declared in the schema.
     NoSuchFieldInSchema(String),
+    /// The value given to `doc_from_serde` does not serialize into a map.
+    InvalidSerdeValue(String),
+}
+
+impl fmt::Display for DocParsingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            DocParsingError::NotJSON(ref doc_json_sample) => {
+                write!(f, "the document is not valid JSON: '{}'", doc_json_sample)
+            }
+            DocParsingError::ValueError(ref field_name, ref err) => {
+                write!(f, "the value of the field '{}' is invalid: {}", field_name, err)
+            }
+            DocParsingError::NoSuchFieldInSchema(ref field_name) => {
+                write!(f, "the field '{}' is not declared in the schema", field_name)
+            }
+            DocParsingError::InvalidSerdeValue(ref msg) => {
+                write!(f, "the document is not a map of fields: {}", msg)
+            }
+        }
+    }
+}
+
+impl StdError for DocParsingError {
+    fn description(&self) -> &str {
+        "error occurred while parsing a document"
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            DocParsingError::ValueError(_, ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Error due to a field that is missing from the schema,
+/// or that is not configured as required by an operation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaError {
+    /// `FieldNotFound(field_name: String)`
+    /// The field is not declared in the schema.
+    FieldNotFound(String),
+    /// `IncompatibleFieldType(field_name: String, expected: String)`
+    /// The field is declared in the schema, but its type or its options
+    /// do not suit the operation. `expected` describes the configuration
+    /// the operation requires, e.g. "a single-valued u64 fast field".
+    IncompatibleFieldType(String, String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SchemaError::FieldNotFound(ref field_name) => {
+                write!(f, "the field '{}' is not declared in the schema", field_name)
+            }
+            SchemaError::IncompatibleFieldType(ref field_name, ref expected) => {
+                write!(f, "the field '{}' is not {}", field_name, expected)
+            }
+        }
+    }
+}
+
+impl StdError for SchemaError {
+    fn description(&self) -> &str {
+        "the schema does not suit the operation"
+    }
+}
+
+/// Defines how the fields that are not declared in the schema
+/// are handled when building a document.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum UnknownFieldPolicy {
+    /// Unknown fields yield a `DocParsingError::NoSuchFieldInSchema` error.
+    Error,
+    /// Unknown fields are silently ignored.
+    Ignore,
 }
 
 #[cfg(test)]
@@ -343,9 +512,13 @@ mod tests {
     "options": {
       "indexing": {
         "record": "position",
-        "tokenizer": "default"
+        "tokenizer": "default",
+        "position_gap": 1,
+        "term_vectors": false
       },
-      "stored": false
+      "stored": false,
+      "fast": false,
+      "completion": false
     }
   },
   {
@@ -354,9 +527,13 @@ mod tests {
     "options": {
       "indexing": {
         "record": "basic",
-        "tokenizer": "raw"
+        "tokenizer": "raw",
+        "position_gap": 1,
+        "term_vectors": false
       },
-      "stored": false
+      "stored": false,
+      "fast": false,
+      "completion": false
     }
   },
   {
@@ -513,4 +690,25 @@ mod tests {
             assert_matches!(json_err, Err(NotJSON(_)));
         }
     }
+
+    #[test]
+    pub fn test_parse_vector_document() {
+        let mut schema_builder = SchemaBuilder::default();
+        let embedding_field = schema_builder.add_vector_field("embedding", 3);
+        let schema = schema_builder.build();
+        let doc = schema
+            .parse_document(r#"{"embedding": [0.5, -1, 2]}"#)
+            .unwrap();
+        assert_eq!(doc.get_all(embedding_field).len(), 1);
+        assert_eq!(
+            doc.get_first(embedding_field).unwrap().vector_value(),
+            &[0.5f32, -1f32, 2f32]
+        );
+        let json_err = schema.parse_document(r#"{"embedding": [0.5, "a", 1]}"#);
+        assert_matches!(json_err, Err(DocParsingError::ValueError(_, ValueParsingError::TypeError(_))));
+        let json_err = schema.parse_document(r#"{"embedding": [0.5, 1]}"#);
+        assert_matches!(json_err, Err(DocParsingError::ValueError(_, ValueParsingError::TypeError(_))));
+        let json_err = schema.parse_document(r#"{"embedding": 0.5}"#);
+        assert_matches!(json_err, Err(DocParsingError::ValueError(_, ValueParsingError::TypeError(_))));
+    }
 }
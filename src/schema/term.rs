@@ -3,6 +3,7 @@ use std::fmt;
 use common;
 use byteorder::{BigEndian, ByteOrder};
 use super::Field;
+use super::Facet;
 use std::str;
 
 /// Size (in bytes) of the buffer of a int field.
@@ -43,6 +44,18 @@ impl Term {
         term
     }
 
+    /// Builds a term given a field, and a facet.
+    ///
+    /// The facet is stored in its binary representation.
+    /// See `Facet::encoded_bytes()`.
+    pub fn from_facet(field: Field, facet: &Facet) -> Term {
+        let facet_bytes = facet.encoded_bytes();
+        let mut term = Term(Vec::with_capacity(4 + facet_bytes.len()));
+        term.set_field(field);
+        term.0.extend_from_slice(facet_bytes);
+        term
+    }
+
     /// Builds a term given a field, and a u64-value
     ///
     /// Assuming the term has a field id of 1, and a u64 value of 3234,
@@ -57,6 +70,15 @@ impl Term {
         term
     }
 
+    /// Builds a term given a field, and the bytes of its value,
+    /// as found in the term dictionary.
+    pub(crate) fn from_field_bytes(field: Field, value_bytes: &[u8]) -> Term {
+        let mut term = Term(Vec::with_capacity(4 + value_bytes.len()));
+        term.set_field(field);
+        term.0.extend_from_slice(value_bytes);
+        term
+    }
+
     /// Creates a new Term with an empty buffer,
     /// but with a given capacity.
     ///
@@ -7,6 +7,10 @@ use schema::IndexRecordOption;
 pub struct TextOptions {
     indexing: Option<TextFieldIndexing>,
     stored: bool,
+    #[serde(default)]
+    fast: bool,
+    #[serde(default)]
+    completion: bool,
 }
 
 impl TextOptions {
@@ -26,6 +30,41 @@ impl TextOptions {
         self
     }
 
+    /// Returns true iff the field is a fast field.
+    pub fn is_fast(&self) -> bool {
+        self.fast
+    }
+
+    /// Sets the field as a fast field.
+    ///
+    /// The terms of the field are stored, for each document, as ordinals
+    /// in the segment's term dictionary, so that they can be accessed without
+    /// loading the stored documents. See `StrFastFieldReader`.
+    ///
+    /// The field must also be indexed. It is typically combined
+    /// with `STRING`, so that each value is a single term.
+    pub fn set_fast(mut self) -> TextOptions {
+        self.fast = true;
+        self
+    }
+
+    /// Returns true iff the values of the field are completed by `Searcher::complete`.
+    pub fn is_completion(&self) -> bool {
+        self.completion
+    }
+
+    /// Sets the field as a completion field.
+    ///
+    /// The values of the field are recorded as is, without being tokenized,
+    /// in the completion index of the segment, with the number of documents
+    /// containing them. See `Searcher::complete`.
+    ///
+    /// The field does not need to be indexed.
+    pub fn set_completion(mut self) -> TextOptions {
+        self.completion = true;
+        self
+    }
+
     /// Sets the field as indexed, with the specific indexing options.
     pub fn set_indexing_options(mut self, indexing: TextFieldIndexing) -> TextOptions {
         self.indexing = Some(indexing);
@@ -38,19 +77,34 @@ impl Default for TextOptions {
         TextOptions {
             indexing: None,
             stored: false,
+            fast: false,
+            completion: false,
         }
     }
 }
 
+/// Number of positions left empty, by default, between
+/// two values of a multivalued text field.
+pub(crate) const DEFAULT_POSITION_GAP: usize = 1;
+
+fn default_position_gap() -> usize {
+    DEFAULT_POSITION_GAP
+}
+
 /// Configuration defining indexing for a text field.
 /// It wraps:
 ///
 /// * record (See [`IndexRecordOption`](./enum.IndexRecordOption.html))
 /// * tokenizer
+/// * position gap
 #[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub struct TextFieldIndexing {
     record: IndexRecordOption,
     tokenizer: Cow<'static, str>,
+    #[serde(default = "default_position_gap")]
+    position_gap: usize,
+    #[serde(default)]
+    term_vectors: bool,
 }
 
 impl Default for TextFieldIndexing {
@@ -58,6 +112,8 @@ impl Default for TextFieldIndexing {
         TextFieldIndexing {
             tokenizer: Cow::Borrowed("default"),
             record: IndexRecordOption::Basic,
+            position_gap: DEFAULT_POSITION_GAP,
+            term_vectors: false,
         }
     }
 }
@@ -88,6 +144,42 @@ impl TextFieldIndexing {
     pub fn index_option(&self) -> IndexRecordOption {
         self.record
     }
+
+    /// Sets the number of positions left empty between two values
+    /// of the field, when a document has several of them.
+    ///
+    /// The gap prevents a `PhraseQuery` from matching a phrase that starts
+    /// in a value and ends in the next one. It defaults to `1`, which is
+    /// sufficient for exact phrases. With a gap of `0`, the values
+    /// are indexed as if they were concatenated.
+    ///
+    /// Changing the gap of an existing field only affects the documents
+    /// indexed afterwards.
+    pub fn set_position_gap(mut self, position_gap: usize) -> TextFieldIndexing {
+        self.position_gap = position_gap;
+        self
+    }
+
+    /// Returns the number of positions left empty between two values of the field.
+    pub fn position_gap(&self) -> usize {
+        self.position_gap
+    }
+
+    /// Stores the term vector of the field for each document.
+    ///
+    /// The term vector of a document lists the terms of the field with their
+    /// positions and offsets, as emitted by the tokenizer, whatever the
+    /// `IndexRecordOption` of the field. It is stored in a dedicated file
+    /// of the segment, and retrieved with `Searcher::term_vector(...)`.
+    pub fn set_term_vectors(mut self) -> TextFieldIndexing {
+        self.term_vectors = true;
+        self
+    }
+
+    /// Returns true iff the term vectors of the field are stored.
+    pub fn term_vectors(&self) -> bool {
+        self.term_vectors
+    }
 }
 
 /// The field will be untokenized and indexed
@@ -95,8 +187,12 @@ pub const STRING: TextOptions = TextOptions {
     indexing: Some(TextFieldIndexing {
         tokenizer: Cow::Borrowed("raw"),
         record: IndexRecordOption::Basic,
+        position_gap: DEFAULT_POSITION_GAP,
+        term_vectors: false,
     }),
     stored: false,
+    fast: false,
+    completion: false,
 };
 
 /// The field will be tokenized and indexed
@@ -104,17 +200,27 @@ pub const TEXT: TextOptions = TextOptions {
     indexing: Some(TextFieldIndexing {
         tokenizer: Cow::Borrowed("default"),
         record: IndexRecordOption::WithFreqsAndPositions,
+        position_gap: DEFAULT_POSITION_GAP,
+        term_vectors: false,
     }),
     stored: false,
+    fast: false,
+    completion: false,
 };
 
 /// A stored fields of a document can be retrieved given its `DocId`.
 /// Stored field are stored together and LZ4 compressed.
 /// Reading the stored fields of a document is relatively slow.
 /// (100 microsecs)
+///
+/// Unless it is combined with `TEXT` or `STRING`, the field is only
+/// stored: it has no postings, no fieldnorms, and no term dictionary entries,
+/// so that large payloads do not add any indexing overhead.
 pub const STORED: TextOptions = TextOptions {
     indexing: None,
     stored: true,
+    fast: false,
+    completion: false,
 };
 
 impl BitOr for TextOptions {
@@ -124,6 +230,8 @@ impl BitOr for TextOptions {
         let mut res = TextOptions::default();
         res.indexing = self.indexing.or(other.indexing);
         res.stored = self.stored | other.stored;
+        res.fast = self.fast | other.fast;
+        res.completion = self.completion | other.completion;
         res
     }
 }
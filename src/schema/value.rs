@@ -2,6 +2,7 @@ use std::fmt;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde::de::Visitor;
 use schema::Facet;
+use schema::Vector;
 
 /// Value represents the value of a any field.
 /// It is an enum over all over all of the possible field type.
@@ -15,6 +16,10 @@ pub enum Value {
     I64(i64),
     /// Hierarchical Facet
     Facet(Facet),
+    /// Arbitrary sequence of bytes
+    Bytes(Vec<u8>),
+    /// Vector of `f32`
+    Vector(Vector),
 }
 
 impl Serialize for Value {
@@ -27,6 +32,8 @@ impl Serialize for Value {
             Value::U64(u) => serializer.serialize_u64(u),
             Value::I64(u) => serializer.serialize_i64(u),
             Value::Facet(ref facet) => facet.serialize(serializer),
+            Value::Bytes(ref bytes) => serializer.serialize_bytes(bytes),
+            Value::Vector(ref vector) => vector.serialize(serializer),
         }
     }
 }
@@ -60,6 +67,14 @@ impl<'de> Deserialize<'de> for Value {
             fn visit_string<E>(self, v: String) -> Result<Self::Value, E> {
                 Ok(Value::Str(v))
             }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E> {
+                Ok(Value::Bytes(v.to_owned()))
+            }
+
+            fn visit_byte_buf<E>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(Value::Bytes(v))
+            }
         }
 
         deserializer.deserialize_any(ValueVisitor)
@@ -99,6 +114,29 @@ impl Value {
             _ => panic!("This is not a text field."),
         }
     }
+
+    /// Returns the bytes, provided the value is of the `Bytes` type.
+    ///
+    /// # Panics
+    /// If the value is not of type `Bytes`
+    pub fn bytes_value(&self) -> &[u8] {
+        match *self {
+            Value::Bytes(ref bytes) => bytes,
+            _ => panic!("This is not a bytes field."),
+        }
+    }
+
+    /// Returns the components of the vector,
+    /// provided the value is of the `Vector` type.
+    ///
+    /// # Panics
+    /// If the value is not of type `Vector`
+    pub fn vector_value(&self) -> &[f32] {
+        match *self {
+            Value::Vector(ref vector) => vector.as_slice(),
+            _ => panic!("This is not a vector field."),
+        }
+    }
 }
 
 impl From<String> for Value {
@@ -131,16 +169,32 @@ impl<'a> From<Facet> for Value {
     }
 }
 
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Value {
+        Value::Bytes(bytes)
+    }
+}
+
+impl From<Vec<f32>> for Value {
+    fn from(components: Vec<f32>) -> Value {
+        Value::Vector(Vector::from(components))
+    }
+}
+
+pub(crate) use self::binary_serialize::skip_serialized_value;
+
 mod binary_serialize {
-    use common::BinarySerializable;
+    use common::{BinarySerializable, VInt};
     use std::io::{self, Read, Write};
     use super::Value;
-    use schema::Facet;
+    use schema::{Facet, Vector};
 
     const TEXT_CODE: u8 = 0;
     const U64_CODE: u8 = 1;
     const I64_CODE: u8 = 2;
     const HIERARCHICAL_FACET_CODE: u8 = 3;
+    const BYTES_CODE: u8 = 4;
+    const VECTOR_CODE: u8 = 5;
 
     impl BinarySerializable for Value {
         fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
@@ -161,6 +215,18 @@ mod binary_serialize {
                     HIERARCHICAL_FACET_CODE.serialize(writer)?;
                     facet.serialize(writer)
                 }
+                Value::Bytes(ref bytes) => {
+                    BYTES_CODE.serialize(writer)?;
+                    bytes.serialize(writer)
+                }
+                Value::Vector(ref vector) => {
+                    VECTOR_CODE.serialize(writer)?;
+                    VInt(vector.dimension() as u64).serialize(writer)?;
+                    for component in vector.as_slice() {
+                        component.to_bits().serialize(writer)?;
+                    }
+                    Ok(())
+                }
             }
         }
         fn deserialize<R: Read>(reader: &mut R) -> io::Result<Self> {
@@ -179,11 +245,52 @@ mod binary_serialize {
                     Ok(Value::I64(value))
                 }
                 HIERARCHICAL_FACET_CODE => Ok(Value::Facet(Facet::deserialize(reader)?)),
-                _ => Err(io::Error::new(
-                    io::ErrorKind::InvalidData,
-                    format!("No field type is associated with code {:?}", type_code),
-                )),
+                BYTES_CODE => Ok(Value::Bytes(Vec::<u8>::deserialize(reader)?)),
+                VECTOR_CODE => {
+                    let dimension = VInt::deserialize(reader)?.val() as usize;
+                    let mut components = Vec::with_capacity(dimension);
+                    for _ in 0..dimension {
+                        components.push(f32::from_bits(u32::deserialize(reader)?));
+                    }
+                    Ok(Value::Vector(Vector::from(components)))
+                }
+                _ => Err(unknown_type_code(type_code)),
+            }
+        }
+    }
+
+    fn unknown_type_code(type_code: u8) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("No field type is associated with code {:?}", type_code),
+        )
+    }
+
+    fn advance(reader: &mut &[u8], num_bytes: usize) -> io::Result<()> {
+        if reader.len() < num_bytes {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "Serialized value is truncated",
+            ));
+        }
+        *reader = &reader[num_bytes..];
+        Ok(())
+    }
+
+    /// Advances `reader` past a serialized `Value`, without deserializing it.
+    pub fn skip_serialized_value(reader: &mut &[u8]) -> io::Result<()> {
+        let type_code = u8::deserialize(reader)?;
+        match type_code {
+            TEXT_CODE | HIERARCHICAL_FACET_CODE | BYTES_CODE => {
+                let num_bytes = VInt::deserialize(reader)?.val() as usize;
+                advance(reader, num_bytes)
+            }
+            U64_CODE | I64_CODE => advance(reader, 8),
+            VECTOR_CODE => {
+                let dimension = VInt::deserialize(reader)?.val() as usize;
+                advance(reader, dimension * 4)
             }
+            _ => Err(unknown_type_code(type_code)),
         }
     }
 }
@@ -0,0 +1,154 @@
+use std::cmp::Ordering;
+
+/// Similarity function between two vectors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VectorSimilarity {
+    /// Dot product of the two vectors.
+    #[serde(rename = "dot")]
+    Dot,
+    /// Cosine of the angle between the two vectors.
+    ///
+    /// If either of the vectors is null, the similarity is `0`.
+    #[serde(rename = "cosine")]
+    Cosine,
+}
+
+impl VectorSimilarity {
+    /// Computes the similarity between two vectors of the same dimension.
+    pub fn compute(&self, left: &[f32], right: &[f32]) -> f32 {
+        let dot_product = dot(left, right);
+        match *self {
+            VectorSimilarity::Dot => dot_product,
+            VectorSimilarity::Cosine => {
+                let norms = dot(left, left).sqrt() * dot(right, right).sqrt();
+                if norms == 0f32 {
+                    0f32
+                } else {
+                    dot_product / norms
+                }
+            }
+        }
+    }
+}
+
+fn dot(left: &[f32], right: &[f32]) -> f32 {
+    left.iter().zip(right).map(|(l, r)| l * r).sum()
+}
+
+/// Define how a vector field should be handled by tantivy.
+///
+/// Vector fields are neither indexed nor stored. They hold one vector
+/// of `f32` per document, all of the same dimension.
+///
+/// Optionally, an approximate nearest neighbor (ANN) index can be
+/// built for each segment, so that the field can be searched with a `KnnQuery`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorOptions {
+    dimension: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ann_index: Option<VectorSimilarity>,
+}
+
+impl VectorOptions {
+    /// Creates the options of a vector field of the given dimension.
+    pub fn with_dimension(dimension: usize) -> VectorOptions {
+        VectorOptions {
+            dimension,
+            ann_index: None,
+        }
+    }
+
+    /// Returns the number of components of the vectors of the field.
+    pub fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    /// Returns the similarity of the approximate nearest neighbor index
+    /// of the field, or `None` if the field does not have such an index.
+    pub fn ann_index(&self) -> Option<VectorSimilarity> {
+        self.ann_index
+    }
+
+    /// Builds an approximate nearest neighbor index for the field,
+    /// for the given similarity.
+    ///
+    /// The index is built when segments are serialized or merged.
+    pub fn set_ann_index(mut self, similarity: VectorSimilarity) -> VectorOptions {
+        self.ann_index = Some(similarity);
+        self
+    }
+}
+
+/// Dense vector of `f32`.
+///
+/// Two vectors are equal if their components have the same bit representation,
+/// so that `Vector` can be used as a `Value`. `NaN` components are therefore
+/// equal to themselves, and `0.0` is different from `-0.0`. The order is arbitrary,
+/// but consistent with equality.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Vector(Vec<f32>);
+
+impl Vector {
+    /// Returns the components of the vector.
+    pub fn as_slice(&self) -> &[f32] {
+        &self.0
+    }
+
+    /// Returns the number of components of the vector.
+    pub fn dimension(&self) -> usize {
+        self.0.len()
+    }
+
+    fn bits<'a>(&'a self) -> impl Iterator<Item = u32> + 'a {
+        self.0.iter().map(|component| component.to_bits())
+    }
+}
+
+impl From<Vec<f32>> for Vector {
+    fn from(components: Vec<f32>) -> Vector {
+        Vector(components)
+    }
+}
+
+impl PartialEq for Vector {
+    fn eq(&self, other: &Vector) -> bool {
+        self.bits().eq(other.bits())
+    }
+}
+
+impl Eq for Vector {}
+
+impl PartialOrd for Vector {
+    fn partial_cmp(&self, other: &Vector) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Vector {
+    fn cmp(&self, other: &Vector) -> Ordering {
+        self.bits().cmp(other.bits())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::f32;
+
+    #[test]
+    fn test_vector_eq() {
+        assert_eq!(Vector::from(vec![1f32, 2f32]), Vector::from(vec![1f32, 2f32]));
+        assert_ne!(Vector::from(vec![1f32, 2f32]), Vector::from(vec![1f32]));
+        assert_eq!(Vector::from(vec![f32::NAN]), Vector::from(vec![f32::NAN]));
+        assert_ne!(Vector::from(vec![0f32]), Vector::from(vec![-0f32]));
+    }
+
+    #[test]
+    fn test_vector_similarity() {
+        assert_eq!(VectorSimilarity::Dot.compute(&[1f32, 2f32], &[3f32, -1f32]), 1f32);
+        assert_eq!(VectorSimilarity::Cosine.compute(&[2f32, 0f32], &[3f32, 0f32]), 1f32);
+        assert_eq!(VectorSimilarity::Cosine.compute(&[1f32, 0f32], &[0f32, 1f32]), 0f32);
+        assert_eq!(VectorSimilarity::Cosine.compute(&[0f32, 0f32], &[1f32, 1f32]), 0f32);
+    }
+}
@@ -0,0 +1,690 @@
+//! Highlighting of the matches of a query in stored text.
+//!
+//! A `SnippetGenerator` is created for a given query and text field.
+//! It re-analyzes the stored text of the field with the tokenizer of
+//! the field, and selects the fragment of the text containing
+//! the most relevant matches of the query.
+//!
+//! Fragments start and end on token boundaries.
+//!
+//! For fields indexed with `IndexRecordOption::WithFreqsAndPositionsAndOffsets`,
+//! the matches can instead be located with the offsets stored in the postings
+//! (See `SnippetGenerator::snippet_from_postings`).
+//!
+//! ```rust
+//! # #[macro_use]
+//! # extern crate tantivy;
+//! # use tantivy::Index;
+//! # use tantivy::schema::*;
+//! # use tantivy::query::QueryParser;
+//! use tantivy::snippet::SnippetGenerator;
+//!
+//! # fn main() {
+//! #     run().unwrap();
+//! # }
+//! # fn run() -> tantivy::Result<()> {
+//! # let mut schema_builder = SchemaBuilder::default();
+//! # let body = schema_builder.add_text_field("body", TEXT | STORED);
+//! # let index = Index::create_in_ram(schema_builder.build());
+//! # let mut index_writer = index.writer_with_num_threads(1, 40_000_000)?;
+//! # index_writer.add_document(doc!(body=>"He was an old man who fished alone in a skiff."));
+//! # index_writer.commit()?;
+//! # index.load_searchers()?;
+//! let searcher = index.searcher();
+//! let query_parser = QueryParser::for_index(&index, vec![body]);
+//! let query = query_parser.parse_query("old man")?;
+//! let mut snippet_generator = SnippetGenerator::create(&index, &*searcher, &*query, body)?;
+//! snippet_generator.set_max_num_chars(100);
+//! let doc = searcher.doc(&tantivy::DocAddress(0, 0))?;
+//! let snippet = snippet_generator.snippet_from_doc(&doc);
+//! assert_eq!(
+//!     snippet.to_html(),
+//!     "He was an <b>old</b> <b>man</b> who fished alone in a skiff"
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use DocAddress;
+use DocSet;
+use Index;
+use Postings;
+use Result;
+use Searcher;
+use SkipResult;
+use Term;
+use error::ErrorKind;
+use query::Query;
+use schema::{Document, Field, FieldType, IndexRecordOption, Value};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use tokenizer::{BoxedTokenizer, Token};
+
+const DEFAULT_MAX_NUM_CHARS: usize = 150;
+
+const HTML_PRE_TAG: &str = "<b>";
+const HTML_POST_TAG: &str = "</b>";
+
+/// A fragment of text, along with the byte ranges
+/// of the matches of the query it contains.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Snippet {
+    fragment: String,
+    highlighted: Vec<Range<usize>>,
+}
+
+impl Snippet {
+    /// Returns the text of the fragment.
+    pub fn fragment(&self) -> &str {
+        &self.fragment
+    }
+
+    /// Returns the byte ranges, within the fragment, of the matches
+    /// of the query, in increasing order.
+    pub fn highlighted(&self) -> &[Range<usize>] {
+        &self.highlighted
+    }
+
+    /// Returns true iff the fragment does not contain any match of the query.
+    pub fn is_empty(&self) -> bool {
+        self.highlighted.is_empty()
+    }
+
+    /// Returns the fragment, with the matches of the query
+    /// surrounded by `pre_tag` and `post_tag`.
+    ///
+    /// The text of the fragment is not escaped.
+    pub fn highlight(&self, pre_tag: &str, post_tag: &str) -> String {
+        self.render(pre_tag, post_tag, |text, output| output.push_str(text))
+    }
+
+    /// Returns the fragment as HTML, with the matches of the query
+    /// surrounded by `<b>` and `</b>`.
+    ///
+    /// The text of the fragment is escaped.
+    pub fn to_html(&self) -> String {
+        self.render(HTML_PRE_TAG, HTML_POST_TAG, escape_html)
+    }
+
+    fn render<F>(&self, pre_tag: &str, post_tag: &str, push_text: F) -> String
+    where
+        F: Fn(&str, &mut String),
+    {
+        let mut output = String::with_capacity(self.fragment.len());
+        let mut start = 0;
+        for highlighted in &self.highlighted {
+            push_text(&self.fragment[start..highlighted.start], &mut output);
+            output.push_str(pre_tag);
+            push_text(&self.fragment[highlighted.clone()], &mut output);
+            output.push_str(post_tag);
+            start = highlighted.end;
+        }
+        push_text(&self.fragment[start..], &mut output);
+        output
+    }
+}
+
+fn escape_html(text: &str, output: &mut String) {
+    for c in text.chars() {
+        match c {
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '&' => output.push_str("&amp;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(c),
+        }
+    }
+}
+
+struct FragmentCandidate {
+    score: f32,
+    start_offset: usize,
+    stop_offset: usize,
+    highlighted: Vec<Range<usize>>,
+}
+
+impl FragmentCandidate {
+    fn new(start_offset: usize) -> FragmentCandidate {
+        FragmentCandidate {
+            score: 0f32,
+            start_offset,
+            stop_offset: start_offset,
+            highlighted: vec![],
+        }
+    }
+
+    fn try_add_token(&mut self, token: &Token, terms: &BTreeMap<String, f32>) {
+        self.stop_offset = token.offset_to;
+        if let Some(&score) = terms.get(&token.text) {
+            self.score += score;
+            self.highlighted.push(token.offset_from..token.offset_to);
+        }
+    }
+
+    fn into_snippet(self, text: &str) -> Snippet {
+        let start_offset = self.start_offset;
+        Snippet {
+            fragment: text[start_offset..self.stop_offset].to_string(),
+            highlighted: self.highlighted
+                .into_iter()
+                .map(|range| range.start - start_offset..range.end - start_offset)
+                .collect(),
+        }
+    }
+}
+
+/// Splits `text` in fragments of at most `max_num_chars` bytes,
+/// starting and ending on token boundaries, and returns
+/// the fragments containing at least one of the `terms`.
+///
+/// A token longer than `max_num_chars` makes a fragment on its own.
+fn search_fragments(
+    tokenizer: &BoxedTokenizer,
+    text: &str,
+    terms: &BTreeMap<String, f32>,
+    max_num_chars: usize,
+) -> Vec<FragmentCandidate> {
+    let mut fragments = vec![];
+    let mut token_stream = tokenizer.token_stream(text);
+    let mut fragment = FragmentCandidate::new(0);
+    while let Some(token) = token_stream.next() {
+        if token.offset_to - fragment.start_offset > max_num_chars {
+            if fragment.score > 0f32 {
+                fragments.push(fragment);
+            }
+            fragment = FragmentCandidate::new(token.offset_from);
+        }
+        fragment.try_add_token(token, terms);
+    }
+    if fragment.score > 0f32 {
+        fragments.push(fragment);
+    }
+    fragments
+}
+
+/// Returns the fragment with the highest score,
+/// the first one in case of a tie.
+///
+/// If none of the fragments contain any term, the beginning of the text
+/// is returned, without any highlighting.
+fn select_best_fragment(
+    fragments: Vec<FragmentCandidate>,
+    text: &str,
+    max_num_chars: usize,
+) -> Snippet {
+    let mut best_fragment_opt: Option<FragmentCandidate> = None;
+    for fragment in fragments {
+        let is_better = match best_fragment_opt {
+            Some(ref best_fragment) => {
+                fragment.score.partial_cmp(&best_fragment.score) == Some(Ordering::Greater)
+            }
+            None => true,
+        };
+        if is_better {
+            best_fragment_opt = Some(fragment);
+        }
+    }
+    match best_fragment_opt {
+        Some(best_fragment) => best_fragment.into_snippet(text),
+        None => beginning_snippet(text, max_num_chars),
+    }
+}
+
+// Returns the beginning of the text, without any highlighting.
+fn beginning_snippet(text: &str, max_num_chars: usize) -> Snippet {
+    let stop_offset = floor_char_boundary(text, max_num_chars);
+    Snippet {
+        fragment: text[..stop_offset].to_string(),
+        highlighted: vec![],
+    }
+}
+
+fn floor_char_boundary(text: &str, mut offset: usize) -> usize {
+    if offset >= text.len() {
+        return text.len();
+    }
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    offset
+}
+
+fn ceil_char_boundary(text: &str, mut offset: usize) -> usize {
+    while !text.is_char_boundary(offset) {
+        offset += 1;
+    }
+    offset
+}
+
+/// Returns the fragment of at most `max_num_chars` bytes
+/// containing the matches with the highest overall score,
+/// the first one in case of a tie.
+///
+/// `matches` are the byte ranges of the matches in `text`, with their score,
+/// sorted and non-overlapping. As the text is not tokenized, the fragment
+/// is extended around the matches up to whitespaces.
+fn select_best_window(text: &str, matches: &[(Range<usize>, f32)], max_num_chars: usize) -> Snippet {
+    let mut best_window_opt: Option<(f32, usize, usize)> = None;
+    for (first, &(ref first_range, first_score)) in matches.iter().enumerate() {
+        let window_start = first_range.start;
+        let mut score = first_score;
+        let mut last = first;
+        while last + 1 < matches.len() && matches[last + 1].0.end - window_start <= max_num_chars
+        {
+            last += 1;
+            score += matches[last].1;
+        }
+        let is_better = match best_window_opt {
+            Some((best_score, _, _)) => score.partial_cmp(&best_score) == Some(Ordering::Greater),
+            None => true,
+        };
+        if is_better {
+            best_window_opt = Some((score, first, last));
+        }
+    }
+    let (first, last) = match best_window_opt {
+        Some((_, first, last)) => (first, last),
+        None => {
+            return beginning_snippet(text, max_num_chars);
+        }
+    };
+    let matches_start = matches[first].0.start;
+    let matches_end = matches[last].0.end;
+
+    // the remaining room is split between the text before and after the matches.
+    let slack = max_num_chars.saturating_sub(matches_end - matches_start);
+    let mut start_offset = ceil_char_boundary(text, matches_start.saturating_sub(slack / 2));
+    if start_offset > 0 && !text[..start_offset].ends_with(char::is_whitespace) {
+        // starts after a whitespace, so that words are not cut.
+        start_offset = text[start_offset..matches_start]
+            .char_indices()
+            .find(|&(_, c)| c.is_whitespace())
+            .map(|(pos, c)| start_offset + pos + c.len_utf8())
+            .unwrap_or(matches_start);
+    }
+    let mut stop_offset = start_offset + max_num_chars;
+    if stop_offset >= text.len() {
+        stop_offset = text.len();
+    } else if stop_offset <= matches_end {
+        stop_offset = matches_end;
+    } else {
+        // ends before a whitespace, so that words are not cut.
+        let limit = floor_char_boundary(text, stop_offset);
+        stop_offset = if text[limit..].starts_with(char::is_whitespace) {
+            limit
+        } else {
+            text[matches_end..limit]
+                .rfind(char::is_whitespace)
+                .map(|pos| matches_end + pos)
+                .unwrap_or(matches_end)
+        };
+    }
+    Snippet {
+        fragment: text[start_offset..stop_offset].to_string(),
+        highlighted: matches[first..last + 1]
+            .iter()
+            .map(|&(ref range, _)| range.start - start_offset..range.end - start_offset)
+            .collect(),
+    }
+}
+
+/// `SnippetGenerator` computes the `Snippet`s highlighting the matches of
+/// a query in the stored values of a text field.
+///
+/// The text is re-analyzed with the tokenizer of the field,
+/// so that the highlighted words are exactly those matching the
+/// terms of the query (see `Query::query_terms`).
+/// Terms are weighted by their inverse document frequency,
+/// so that the fragments containing rare terms are preferred.
+///
+/// If the field is indexed with
+/// `IndexRecordOption::WithFreqsAndPositionsAndOffsets`,
+/// `.snippet_from_postings(...)` locates the matches with the offsets
+/// recorded in the postings instead, which spares the analysis of
+/// large texts.
+pub struct SnippetGenerator {
+    field: Field,
+    terms: BTreeMap<String, f32>,
+    tokenizer: Box<BoxedTokenizer>,
+    offsets_enabled: bool,
+    max_num_chars: usize,
+}
+
+impl SnippetGenerator {
+    /// Creates a `SnippetGenerator` highlighting the terms of `query`
+    /// in the text field `field`.
+    ///
+    /// The document frequencies of the terms are read from `searcher`.
+    ///
+    /// # Errors
+    /// If `field` is not an indexed text field, or if its tokenizer
+    /// is not registered in the index, returns `ErrorKind::InvalidArgument`.
+    pub fn create(
+        index: &Index,
+        searcher: &Searcher,
+        query: &Query,
+        field: Field,
+    ) -> Result<SnippetGenerator> {
+        let schema = index.schema();
+        let field_entry = schema.get_field_entry(field);
+        let indexing_options = match *field_entry.field_type() {
+            FieldType::Str(ref text_options) => text_options.get_indexing_options().cloned(),
+            _ => None,
+        };
+        let indexing_options = match indexing_options {
+            Some(indexing_options) => indexing_options,
+            None => bail!(ErrorKind::InvalidArgument(format!(
+                "Cannot highlight the field {:?}: it is not an indexed text field.",
+                field_entry.name()
+            ))),
+        };
+        let tokenizer = match index.tokenizers().get(indexing_options.tokenizer()) {
+            Some(tokenizer) => tokenizer,
+            None => bail!(ErrorKind::InvalidArgument(format!(
+                "The tokenizer {:?} of the field {:?} is not registered.",
+                indexing_options.tokenizer(),
+                field_entry.name()
+            ))),
+        };
+        let mut term_set = BTreeSet::new();
+        query.query_terms(&mut term_set);
+        let statistics_provider = searcher.statistics_provider();
+        let num_docs = statistics_provider.total_num_docs() as f32;
+        let terms = term_set
+            .into_iter()
+            .filter(|term| term.field() == field)
+            .map(|term| {
+                let doc_freq = statistics_provider.doc_freq(&term) as f32;
+                let idf = (1f32 + num_docs / (1f32 + doc_freq)).ln();
+                (term.text().to_string(), idf)
+            })
+            .collect();
+        Ok(SnippetGenerator {
+            field,
+            terms,
+            tokenizer,
+            offsets_enabled: indexing_options.index_option().has_offsets(),
+            max_num_chars: DEFAULT_MAX_NUM_CHARS,
+        })
+    }
+
+    /// Sets the maximum size of the fragments, in bytes.
+    ///
+    /// Defaults to 150.
+    pub fn set_max_num_chars(&mut self, max_num_chars: usize) {
+        self.max_num_chars = max_num_chars;
+    }
+
+    /// Returns the best fragment of `text`.
+    pub fn snippet(&self, text: &str) -> Snippet {
+        let fragments = search_fragments(&*self.tokenizer, text, &self.terms, self.max_num_chars);
+        select_best_fragment(fragments, text, self.max_num_chars)
+    }
+
+    /// Returns the best fragment of the values of the field in `doc`.
+    ///
+    /// The values of the field are joined by a space.
+    pub fn snippet_from_doc(&self, doc: &Document) -> Snippet {
+        self.snippet(&field_texts(doc, self.field).join(" "))
+    }
+
+    /// Returns the best fragment of the values of the field in `doc`,
+    /// stored at `doc_address` in `searcher`.
+    ///
+    /// The matches are located by reading the offsets of the terms in
+    /// the postings of the document, rather than by analyzing its text.
+    /// If the field is not indexed with offsets, this is
+    /// equivalent to `.snippet_from_doc(doc)`.
+    ///
+    /// The values of the field are joined by a space.
+    pub fn snippet_from_postings(
+        &self,
+        searcher: &Searcher,
+        doc_address: &DocAddress,
+        doc: &Document,
+    ) -> Snippet {
+        if !self.offsets_enabled {
+            return self.snippet_from_doc(doc);
+        }
+        let DocAddress(segment_ord, doc_id) = *doc_address;
+        let inverted_index = searcher
+            .segment_reader(segment_ord)
+            .inverted_index(self.field);
+        let texts = field_texts(doc, self.field);
+        if texts.is_empty() {
+            return beginning_snippet("", self.max_num_chars);
+        }
+        // offsets are those of the concatenation of the values,
+        // while the values are joined by a space.
+        let mut value_starts = Vec::with_capacity(texts.len());
+        let mut value_start = 0;
+        for text in &texts {
+            value_starts.push(value_start);
+            value_start += text.len();
+        }
+        let text = texts.join(" ");
+        let mut matches = vec![];
+        for (term_text, &score) in &self.terms {
+            let term = Term::from_field_text(self.field, term_text);
+            let mut postings = match inverted_index
+                .read_postings(&term, IndexRecordOption::WithFreqsAndPositionsAndOffsets)
+            {
+                Ok(Some(postings)) => postings,
+                Ok(None) => continue,
+                // the text of the document can still be analyzed.
+                Err(_) => return self.snippet_from_doc(doc),
+            };
+            if postings.skip_next(doc_id) != SkipResult::Reached {
+                continue;
+            }
+            for offsets in postings.offsets() {
+                let (start, end) = (offsets.start as usize, offsets.end as usize);
+                let value_ord = value_starts
+                    .iter()
+                    .take_while(|&&value_start| value_start <= start)
+                    .count() - 1;
+                let range = start + value_ord..end + value_ord;
+                // the stored text may not match the indexed text,
+                // for instance if the document is not the one at `doc_address`.
+                if text.get(range.clone()).is_some() {
+                    matches.push((range, score));
+                }
+            }
+        }
+        matches.sort_by_key(|&(ref range, _)| range.start);
+        let mut non_overlapping_matches: Vec<(Range<usize>, f32)> = vec![];
+        for (range, score) in matches {
+            let overlaps = non_overlapping_matches
+                .last()
+                .map(|&(ref last_range, _)| range.start < last_range.end)
+                .unwrap_or(false);
+            if !overlaps {
+                non_overlapping_matches.push((range, score));
+            }
+        }
+        select_best_window(&text, &non_overlapping_matches, self.max_num_chars)
+    }
+}
+
+fn field_texts(doc: &Document, field: Field) -> Vec<&str> {
+    doc.get_all(field)
+        .into_iter()
+        .filter_map(|value| match *value {
+            Value::Str(ref text) => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{search_fragments, select_best_fragment, select_best_window, SnippetGenerator};
+    use DocAddress;
+    use Index;
+    use Term;
+    use query::{BooleanQuery, Occur, Query, TermQuery};
+    use schema::{IndexRecordOption, SchemaBuilder, TextFieldIndexing, TextOptions, STORED, TEXT};
+    use std::collections::BTreeMap;
+    use tokenizer::TokenizerManager;
+
+    const TEST_TEXT: &str = "Rust is a systems programming language sponsored by Mozilla which \
+                             describes it as a \"safe, concurrent, practical language\", \
+                             supporting functional and imperative-procedural paradigms. Rust is \
+                             syntactically similar to C++ but its designers intend it to provide \
+                             better memory safety while still maintaining performance.";
+
+    #[test]
+    fn test_snippet_fragments() {
+        let tokenizer = TokenizerManager::default().get("default").unwrap();
+        let mut terms = BTreeMap::new();
+        terms.insert("rust".to_string(), 1f32);
+        terms.insert("language".to_string(), 0.9f32);
+        let fragments = search_fragments(&*tokenizer, TEST_TEXT, &terms, 100);
+        assert_eq!(fragments.len(), 2);
+        // both fragments have the same score: the first one is selected.
+        let snippet = select_best_fragment(fragments, TEST_TEXT, 100);
+        assert_eq!(
+            snippet.fragment(),
+            "Rust is a systems programming language sponsored by Mozilla which describes \
+             it as a \"safe"
+        );
+        assert_eq!(snippet.highlighted(), &[0..4, 30..38]);
+        assert_eq!(
+            snippet.to_html(),
+            "<b>Rust</b> is a systems programming <b>language</b> sponsored by Mozilla which \
+             describes it as a &quot;safe"
+        );
+        assert_eq!(
+            snippet.highlight("[", "]"),
+            "[Rust] is a systems programming [language] sponsored by Mozilla which describes \
+             it as a \"safe"
+        );
+    }
+
+    #[test]
+    fn test_snippet_best_fragment() {
+        let tokenizer = TokenizerManager::default().get("default").unwrap();
+        let mut terms = BTreeMap::new();
+        terms.insert("c".to_string(), 1f32);
+        terms.insert("safe".to_string(), 0.5f32);
+        let fragments = search_fragments(&*tokenizer, TEST_TEXT, &terms, 60);
+        assert_eq!(fragments.len(), 2);
+        let snippet = select_best_fragment(fragments, TEST_TEXT, 60);
+        assert_eq!(
+            snippet.to_html(),
+            "paradigms. Rust is syntactically similar to <b>C</b>++ but its"
+        );
+    }
+
+    #[test]
+    fn test_snippet_no_match() {
+        let tokenizer = TokenizerManager::default().get("default").unwrap();
+        let mut terms = BTreeMap::new();
+        terms.insert("java".to_string(), 1f32);
+        let fragments = search_fragments(&*tokenizer, TEST_TEXT, &terms, 20);
+        assert!(fragments.is_empty());
+        let snippet = select_best_fragment(fragments, TEST_TEXT, 20);
+        assert!(snippet.is_empty());
+        assert_eq!(snippet.fragment(), "Rust is a systems pr");
+    }
+
+    #[test]
+    fn test_snippet_best_window() {
+        let matches = vec![(85..89, 0.5f32), (216..217, 1f32)];
+        let snippet = select_best_window(TEST_TEXT, &matches, 60);
+        assert_eq!(
+            snippet.highlight("[", "]"),
+            "is syntactically similar to [C]++ but its designers intend it"
+        );
+        let matches = vec![
+            (0..4, 1f32),
+            (30..38, 0.9f32),
+            (113..121, 0.9f32),
+            (183..187, 1f32),
+        ];
+        let snippet = select_best_window(TEST_TEXT, &matches, 100);
+        assert_eq!(
+            snippet.highlight("[", "]"),
+            "[Rust] is a systems programming [language] sponsored by Mozilla which describes \
+             it as a \"safe,"
+        );
+        let snippet = select_best_window(TEST_TEXT, &[], 20);
+        assert!(snippet.is_empty());
+        assert_eq!(snippet.fragment(), "Rust is a systems pr");
+    }
+
+    #[test]
+    fn test_snippet_generator_postings_offsets() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_options = TextOptions::default()
+            .set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositionsAndOffsets),
+            )
+            .set_stored();
+        let body_field = schema_builder.add_text_field("body", text_options);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(body_field=>"A bucket of chips"));
+        index_writer.add_document(doc!(
+            body_field=>"Fish & <chips>",
+            body_field=>"in a rust bucket"
+        ));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let query = TermQuery::new(
+            Term::from_field_text(body_field, "rust"),
+            IndexRecordOption::Basic,
+        );
+        let snippet_generator =
+            SnippetGenerator::create(&index, &*searcher, &query, body_field).unwrap();
+        let doc_address = DocAddress(0, 1);
+        let doc = searcher.doc(&doc_address).unwrap();
+        let snippet = snippet_generator.snippet_from_postings(&*searcher, &doc_address, &doc);
+        assert_eq!(
+            snippet.to_html(),
+            "Fish &amp; &lt;chips&gt; in a <b>rust</b> bucket"
+        );
+        assert_eq!(snippet, snippet_generator.snippet_from_doc(&doc));
+    }
+
+    #[test]
+    fn test_snippet_generator() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let body_field = schema_builder.add_text_field("body", TEXT | STORED);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.add_document(doc!(
+            title_field=>"Rust",
+            body_field=>"Fish & <chips> in a rust bucket"
+        ));
+        index_writer.add_document(doc!(body_field=>"A bucket of chips"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let term_query = |field, text| -> Box<Query> {
+            box TermQuery::new(
+                Term::from_field_text(field, text),
+                IndexRecordOption::Basic,
+            )
+        };
+        let query = BooleanQuery::from(vec![
+            (Occur::Should, term_query(title_field, "fish")),
+            (Occur::Should, term_query(body_field, "rust")),
+            (Occur::MustNot, term_query(body_field, "bucket")),
+        ]);
+        let snippet_generator =
+            SnippetGenerator::create(&index, &*searcher, &query, body_field).unwrap();
+        let doc = searcher.doc(&::DocAddress(0, 0)).unwrap();
+        // "fish" belongs to another field, and "bucket" is excluded.
+        assert_eq!(
+            snippet_generator.snippet_from_doc(&doc).to_html(),
+            "Fish &amp; &lt;chips&gt; in a <b>rust</b> bucket"
+        );
+    }
+}
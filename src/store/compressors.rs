@@ -0,0 +1,228 @@
+use error::DataCorruption;
+use std::io;
+#[cfg(feature = "lz4-compression")]
+use lz4;
+#[cfg(feature = "zstd-compression")]
+use zstd;
+
+/// Number of bits of the block header used to encode the block length.
+///
+/// The remaining high bits hold the id of the codec used to compress the block.
+/// Stores written before codecs were pluggable always have these bits
+/// set to 0, which is the id of `LZ4`.
+const BLOCK_LEN_NUM_BITS: u32 = 28;
+const BLOCK_LEN_MASK: u32 = (1u32 << BLOCK_LEN_NUM_BITS) - 1;
+
+const LZ4_ID: u8 = 0;
+const NONE_ID: u8 = 1;
+const ZSTD_ID: u8 = 2;
+
+/// Compression codec used for the blocks of the doc store.
+///
+/// The codec is chosen for a whole index via its `IndexSettings`.
+/// Each block records the codec it was compressed with, so that
+/// changing the codec of an existing index does not prevent reading the
+/// segments that were written before the change.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compressor {
+    /// Blocks are stored uncompressed.
+    None,
+    /// Blocks are compressed using `LZ4`. This is the default.
+    ///
+    /// Requires the `lz4-compression` feature, which is enabled by default.
+    Lz4,
+    /// Blocks are compressed using `Zstd`.
+    ///
+    /// Requires the `zstd-compression` feature.
+    Zstd(ZstdCompressor),
+}
+
+impl Default for Compressor {
+    fn default() -> Compressor {
+        Compressor::Lz4
+    }
+}
+
+/// Options of the `Zstd` compressor.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ZstdCompressor {
+    /// Compression level. If `None`, zstd's default level is used.
+    #[serde(default)]
+    pub compression_level: Option<i32>,
+    /// Dictionary used to compress and decompress blocks.
+    ///
+    /// Small blocks of similar documents compress a lot better with
+    /// a dictionary trained on a sample of the documents.
+    /// The dictionary is required to read the blocks back, and
+    /// should therefore never be changed once documents were indexed.
+    #[serde(default)]
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl Compressor {
+    fn id(&self) -> u8 {
+        match *self {
+            Compressor::Lz4 => LZ4_ID,
+            Compressor::None => NONE_ID,
+            Compressor::Zstd(_) => ZSTD_ID,
+        }
+    }
+
+    fn zstd_dictionary(&self) -> Option<&[u8]> {
+        match *self {
+            Compressor::Zstd(ref zstd_compressor) => {
+                zstd_compressor.dictionary.as_ref().map(|dictionary| &dictionary[..])
+            }
+            _ => None,
+        }
+    }
+
+    /// Compresses `uncompressed` into `compressed`, and returns the
+    /// header of the block, encoding both its length and the codec used.
+    pub(crate) fn compress(&self, uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<u32> {
+        compressed.clear();
+        match *self {
+            Compressor::None => {
+                compressed.extend_from_slice(uncompressed);
+            }
+            Compressor::Lz4 => {
+                lz4_compress(uncompressed, compressed)?;
+            }
+            Compressor::Zstd(ref zstd_compressor) => {
+                zstd_compress(zstd_compressor, uncompressed, compressed)?;
+            }
+        }
+        if compressed.len() > BLOCK_LEN_MASK as usize {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Doc store block is too large.",
+            ));
+        }
+        Ok((u32::from(self.id()) << BLOCK_LEN_NUM_BITS) | compressed.len() as u32)
+    }
+
+    /// Decompresses a block given its header.
+    ///
+    /// The codec is read from the block header, so that blocks written with
+    /// another codec than the current one can still be decompressed.
+    /// Only the zstd dictionary is taken from `self`.
+    pub(crate) fn decompress(
+        &self,
+        block_header: u32,
+        compressed: &[u8],
+        decompressed: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        decompressed.clear();
+        match (block_header >> BLOCK_LEN_NUM_BITS) as u8 {
+            LZ4_ID => {
+                lz4_decompress(compressed, decompressed)?;
+            }
+            NONE_ID => {
+                decompressed.extend_from_slice(compressed);
+            }
+            ZSTD_ID => {
+                zstd_decompress(self.zstd_dictionary(), compressed, decompressed)?;
+            }
+            codec_id => {
+                return Err(DataCorruption::comment_only(
+                    format!("Unknown doc store codec {}", codec_id),
+                ).into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the length of a block given its header.
+pub(crate) fn block_len(block_header: u32) -> usize {
+    (block_header & BLOCK_LEN_MASK) as usize
+}
+
+#[cfg(feature = "lz4-compression")]
+fn lz4_compress(uncompressed: &[u8], compressed: &mut Vec<u8>) -> io::Result<()> {
+    use std::io::Write;
+    let mut encoder = lz4::EncoderBuilder::new().build(&mut *compressed)?;
+    encoder.write_all(uncompressed)?;
+    let (_, encoder_result) = encoder.finish();
+    encoder_result
+}
+
+#[cfg(feature = "lz4-compression")]
+fn lz4_decompress(compressed: &[u8], decompressed: &mut Vec<u8>) -> io::Result<()> {
+    use std::io::Read;
+    let mut lz4_decoder = lz4::Decoder::new(compressed)?;
+    lz4_decoder.read_to_end(decompressed)?;
+    Ok(())
+}
+
+#[cfg(not(feature = "lz4-compression"))]
+fn lz4_compress(_: &[u8], _: &mut Vec<u8>) -> io::Result<()> {
+    Err(lz4_unsupported())
+}
+
+#[cfg(not(feature = "lz4-compression"))]
+fn lz4_decompress(_: &[u8], _: &mut Vec<u8>) -> io::Result<()> {
+    Err(lz4_unsupported())
+}
+
+#[cfg(not(feature = "lz4-compression"))]
+fn lz4_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "LZ4 compression requires tantivy to be compiled with the `lz4-compression` feature.",
+    )
+}
+
+#[cfg(feature = "zstd-compression")]
+fn zstd_compress(
+    zstd_compressor: &ZstdCompressor,
+    uncompressed: &[u8],
+    compressed: &mut Vec<u8>,
+) -> io::Result<()> {
+    use std::io::Write;
+    let level = zstd_compressor.compression_level.unwrap_or(0);
+    let mut encoder = match zstd_compressor.dictionary {
+        Some(ref dictionary) => zstd::stream::Encoder::with_dictionary(&mut *compressed, level, dictionary)?,
+        None => zstd::stream::Encoder::new(&mut *compressed, level)?,
+    };
+    encoder.write_all(uncompressed)?;
+    encoder.finish()?;
+    Ok(())
+}
+
+#[cfg(feature = "zstd-compression")]
+fn zstd_decompress(
+    dictionary: Option<&[u8]>,
+    compressed: &[u8],
+    decompressed: &mut Vec<u8>,
+) -> io::Result<()> {
+    use std::io::Read;
+    match dictionary {
+        Some(dictionary) => {
+            zstd::stream::Decoder::with_dictionary(compressed, dictionary)?
+                .read_to_end(decompressed)?;
+        }
+        None => {
+            zstd::stream::Decoder::new(compressed)?.read_to_end(decompressed)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "zstd-compression"))]
+fn zstd_compress(_: &ZstdCompressor, _: &[u8], _: &mut Vec<u8>) -> io::Result<()> {
+    Err(zstd_unsupported())
+}
+
+#[cfg(not(feature = "zstd-compression"))]
+fn zstd_decompress(_: Option<&[u8]>, _: &[u8], _: &mut Vec<u8>) -> io::Result<()> {
+    Err(zstd_unsupported())
+}
+
+#[cfg(not(feature = "zstd-compression"))]
+fn zstd_unsupported() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Other,
+        "Zstd compression requires tantivy to be compiled with the `zstd-compression` feature.",
+    )
+}
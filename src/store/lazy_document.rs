@@ -0,0 +1,170 @@
+use Result;
+use common::{BinarySerializable, VInt};
+use schema::{skip_serialized_value, Document, Field, FieldValue, Value};
+use std::io;
+
+/// Document of the doc store whose field values are
+/// deserialized on demand.
+///
+/// Deserializing a `Document` allocates all of its values.
+/// When only a few small fields of large stored documents are
+/// needed, for instance to display the title of the top 10 hits,
+/// a `LazyDocument` only deserializes the requested values,
+/// skipping over the others.
+///
+/// It is returned by `Searcher::doc_lazy(...)`.
+#[derive(Clone, Debug)]
+pub struct LazyDocument {
+    data: Vec<u8>,
+}
+
+impl From<Vec<u8>> for LazyDocument {
+    fn from(data: Vec<u8>) -> LazyDocument {
+        LazyDocument { data }
+    }
+}
+
+/// Iterates over the field values of a serialized document,
+/// yielding each field along with the serialized bytes of its value.
+struct SerializedFieldValues<'a> {
+    cursor: &'a [u8],
+    num_remaining: usize,
+}
+
+impl<'a> SerializedFieldValues<'a> {
+    fn next_field_value(&mut self) -> io::Result<(Field, &'a [u8])> {
+        let field = Field::deserialize(&mut self.cursor)?;
+        let value_start = self.cursor;
+        skip_serialized_value(&mut self.cursor)?;
+        let value_len = value_start.len() - self.cursor.len();
+        Ok((field, &value_start[..value_len]))
+    }
+}
+
+impl<'a> Iterator for SerializedFieldValues<'a> {
+    type Item = io::Result<(Field, &'a [u8])>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.num_remaining == 0 {
+            return None;
+        }
+        self.num_remaining -= 1;
+        let field_value = self.next_field_value();
+        if field_value.is_err() {
+            // the document is corrupted, and cannot be read further.
+            self.num_remaining = 0;
+        }
+        Some(field_value)
+    }
+}
+
+fn deserialize_value(mut value_bytes: &[u8]) -> io::Result<Value> {
+    Value::deserialize(&mut value_bytes)
+}
+
+impl LazyDocument {
+    fn serialized_field_values(&self) -> io::Result<SerializedFieldValues> {
+        let mut cursor = &self.data[..];
+        let num_remaining = VInt::deserialize(&mut cursor)?.val() as usize;
+        Ok(SerializedFieldValues {
+            cursor,
+            num_remaining,
+        })
+    }
+
+    /// Returns the first value of a given field, if any.
+    ///
+    /// The field values following it are not read.
+    pub fn get_first(&self, field: Field) -> Result<Option<Value>> {
+        for field_value in self.serialized_field_values()? {
+            let (value_field, value_bytes) = field_value?;
+            if value_field == field {
+                return Ok(Some(deserialize_value(value_bytes)?));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns all of the values of a given field.
+    ///
+    /// Only the values of `field` are deserialized.
+    pub fn get_all(&self, field: Field) -> Result<Vec<Value>> {
+        let mut values = vec![];
+        for field_value in self.serialized_field_values()? {
+            let (value_field, value_bytes) = field_value?;
+            if value_field == field {
+                values.push(deserialize_value(value_bytes)?);
+            }
+        }
+        Ok(values)
+    }
+
+    /// Returns the fields of the document, in the order of their values,
+    /// without deserializing any value.
+    pub fn fields(&self) -> Result<Vec<Field>> {
+        let mut fields = vec![];
+        for field_value in self.serialized_field_values()? {
+            let (field, _) = field_value?;
+            fields.push(field);
+        }
+        Ok(fields)
+    }
+
+    /// Deserializes the entire document.
+    pub fn to_document(&self) -> Result<Document> {
+        let mut field_values = vec![];
+        for field_value in self.serialized_field_values()? {
+            let (field, value_bytes) = field_value?;
+            field_values.push(FieldValue::new(field, deserialize_value(value_bytes)?));
+        }
+        Ok(Document::from(field_values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use DocAddress;
+    use schema::{SchemaBuilder, Value, FAST, INT_STORED, STORED, TEXT};
+
+    #[test]
+    fn test_lazy_document() {
+        let mut schema_builder = SchemaBuilder::default();
+        let title_field = schema_builder.add_text_field("title", TEXT | STORED);
+        let body_field = schema_builder.add_text_field("body", STORED);
+        let id_field = schema_builder.add_u64_field("id", INT_STORED | FAST);
+        let tag_field = schema_builder.add_text_field("tag", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        let body = "lorem ipsum ".repeat(1_000);
+        index_writer.add_document(doc!(
+            body_field => body.clone(),
+            title_field => "first title",
+            id_field => 7u64,
+            title_field => "second title",
+            tag_field => "not stored"
+        ));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let lazy_doc = searcher.doc_lazy(&DocAddress(0, 0)).unwrap();
+        assert_eq!(
+            lazy_doc.get_first(title_field).unwrap(),
+            Some(Value::from("first title"))
+        );
+        assert_eq!(
+            lazy_doc.get_all(title_field).unwrap(),
+            vec![Value::from("first title"), Value::from("second title")]
+        );
+        assert_eq!(lazy_doc.get_first(id_field).unwrap(), Some(Value::U64(7u64)));
+        assert_eq!(lazy_doc.get_first(tag_field).unwrap(), None);
+        assert_eq!(
+            lazy_doc.fields().unwrap(),
+            vec![body_field, title_field, id_field, title_field]
+        );
+        let doc = lazy_doc.to_document().unwrap();
+        assert_eq!(doc, searcher.doc(&DocAddress(0, 0)).unwrap());
+        assert_eq!(doc.get_first(body_field).unwrap().text(), &body[..]);
+    }
+}
@@ -5,9 +5,28 @@ A field needs to be marked as stored in the schema in
 order to be handled in the `Store`.
 
 Internally, documents (or rather their stored fields) are serialized to a buffer.
-When the buffer exceeds 16K, the buffer is compressed using `LZ4`
+When the buffer exceeds the block size, the buffer is compressed
 and the resulting block is written to disk.
 
+# Block size and layout
+
+The block size (16KB by default) and the
+[`DocStoreLayout`](./enum.DocStoreLayout.html) are defined by the
+`IndexSettings`, and are a tradeoff between random access and
+compression.
+
+Retrieving a document requires decompressing its entire block:
+small blocks, laid out with `DocStoreLayout::RowGreedy` so that blocks never
+exceed the block size, are best suited to retrieving the few documents of
+a search result page. Large blocks, with the default
+`DocStoreLayout::LargeBlock`, compress better and are cheaper to
+scan, which suits workloads such as exporting logs.
+
+The compression codec is defined by the [`Compressor`](./enum.Compressor.html)
+of the `IndexSettings`, and defaults to `LZ4`. Every block keeps track of the
+codec it was compressed with, so that segments written with different codecs
+can be read and merged together.
+
 One can then request for a specific `DocId`.
 A skip list helps navigating to the right block,
 decompresses it entirely and returns the document within it.
@@ -21,6 +40,11 @@ A typical use case for the store is, once
 the search result page has been computed, returning
 the actual content of the 10 best document.
 
+Jobs that need all of the documents, such as exporting
+or reindexing them, should rather scan the store with
+[`StoreReader::iter_documents`](./struct.StoreReader.html#method.iter_documents),
+which decompresses every block only once.
+
 # Usage
 
 Most users should not access the `StoreReader` directly
@@ -31,26 +55,51 @@ and should rely on either
 - at the index level, the
 [`Searcher`'s `doc` method](../struct.Searcher.html#method.doc)
 
+When only a few fields of large documents are needed, the
+[`Searcher`'s `doc_lazy` method](../struct.Searcher.html#method.doc_lazy)
+returns a [`LazyDocument`](./struct.LazyDocument.html), which
+only deserializes the values that are requested.
+
 !*/
 
+mod compressors;
+mod lazy_document;
 mod reader;
 mod writer;
-pub use self::reader::StoreReader;
-pub use self::writer::StoreWriter;
+pub use self::compressors::{Compressor, ZstdCompressor};
+pub use self::lazy_document::LazyDocument;
+pub use self::reader::{StoreDocumentIter, StoreReader};
+pub use self::writer::{DocStoreLayout, StoreWriter};
+pub(crate) use self::writer::DEFAULT_BLOCK_SIZE;
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    #[cfg(feature = "mmap")]
     use test::Bencher;
+    use common::HasLen;
     use std::path::Path;
     use schema::{Schema, SchemaBuilder};
     use schema::TextOptions;
     use schema::FieldValue;
     use schema::Document;
-    use directory::{Directory, MmapDirectory, RAMDirectory, WritePtr};
+    use directory::{Directory, RAMDirectory, WritePtr};
+    #[cfg(feature = "mmap")]
+    use directory::MmapDirectory;
+    use common::BitSet;
+    use DocId;
+    use fastfield::{write_delete_bitset, DeleteBitSet};
 
     fn write_lorem_ipsum_store(writer: WritePtr, num_docs: usize) -> Schema {
+        write_lorem_ipsum_store_with_compressor(writer, num_docs, Compressor::default())
+    }
+
+    fn write_lorem_ipsum_store_with_compressor(
+        writer: WritePtr,
+        num_docs: usize,
+        compressor: Compressor,
+    ) -> Schema {
         let mut schema_builder = SchemaBuilder::default();
         let field_body = schema_builder.add_text_field("body", TextOptions::default().set_stored());
         let field_title =
@@ -67,7 +116,7 @@ mod tests {
              laborum.",
         );
         {
-            let mut store_writer = StoreWriter::new(writer);
+            let mut store_writer = StoreWriter::new(writer, compressor);
             for i in 0..num_docs {
                 let mut fields: Vec<FieldValue> = Vec::new();
                 {
@@ -96,7 +145,7 @@ mod tests {
         let schema = write_lorem_ipsum_store(store_file, 1_000);
         let field_title = schema.get_field("title").unwrap();
         let store_source = directory.open_read(path).unwrap();
-        let store = StoreReader::from_source(store_source);
+        let store = StoreReader::from_source(store_source, Compressor::default()).unwrap();
         for i in 0..1_000 {
             assert_eq!(
                 *store.get(i).unwrap().get_first(field_title).unwrap().text(),
@@ -105,6 +154,176 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_store_iter_documents() {
+        let path = Path::new("store");
+        let delete_path = Path::new("delete");
+        let mut directory = RAMDirectory::create();
+        let store_file = directory.open_write(path).unwrap();
+        let schema = write_lorem_ipsum_store(store_file, 1_000);
+        let field_title = schema.get_field("title").unwrap();
+        let store_source = directory.open_read(path).unwrap();
+        let store = StoreReader::from_source(store_source, Compressor::default()).unwrap();
+        let titles = |delete_bitset: &DeleteBitSet| -> Vec<String> {
+            store
+                .iter_documents(delete_bitset)
+                .map(|doc_res| {
+                    let (doc, document) = doc_res.unwrap();
+                    let title = document.get_first(field_title).unwrap().text().to_string();
+                    assert_eq!(title, format!("Doc {}", doc));
+                    title
+                })
+                .collect()
+        };
+        assert_eq!(titles(&DeleteBitSet::empty()).len(), 1_000);
+
+        // whole blocks are deleted between 100 and 400.
+        let is_deleted = |doc: u32| doc % 3 == 0 || (doc >= 100 && doc < 400);
+        let mut bitset = BitSet::with_max_value(1_000);
+        for doc in (0..1_000).filter(|&doc| is_deleted(doc)) {
+            bitset.insert(doc);
+        }
+        {
+            let mut delete_file = directory.open_write(delete_path).unwrap();
+            write_delete_bitset(&bitset, &mut delete_file).unwrap();
+        }
+        let delete_bitset = DeleteBitSet::open(directory.open_read(delete_path).unwrap());
+        let expected_titles: Vec<String> = (0..1_000)
+            .filter(|&doc| !is_deleted(doc))
+            .map(|doc| format!("Doc {}", doc))
+            .collect();
+        assert_eq!(titles(&delete_bitset), expected_titles);
+    }
+
+    #[test]
+    fn test_store_compressors() {
+        let mut directory = RAMDirectory::create();
+        let compressors = vec![
+            ("none", Compressor::None),
+            ("lz4", Compressor::Lz4),
+        ];
+        for &(name, ref compressor) in &compressors {
+            let path = Path::new(name);
+            let store_file = directory.open_write(path).unwrap();
+            let schema = write_lorem_ipsum_store_with_compressor(store_file, 300, compressor.clone());
+            let field_title = schema.get_field("title").unwrap();
+            let store_source = directory.open_read(path).unwrap();
+            // the codec is read from the blocks, whatever the current compressor.
+            let store = StoreReader::from_source(store_source, Compressor::default()).unwrap();
+            for i in 0..300 {
+                assert_eq!(
+                    *store.get(i).unwrap().get_first(field_title).unwrap().text(),
+                    format!("Doc {}", i)
+                );
+            }
+        }
+        assert!(directory.open_read(Path::new("none")).unwrap().len() >
+            directory.open_read(Path::new("lz4")).unwrap().len());
+    }
+
+    #[test]
+    fn test_store_stack_different_compressors() {
+        let mut directory = RAMDirectory::create();
+        let none_path = Path::new("none");
+        let lz4_path = Path::new("lz4");
+        let stacked_path = Path::new("stacked");
+        let schema = write_lorem_ipsum_store_with_compressor(
+            directory.open_write(none_path).unwrap(), 100, Compressor::None);
+        write_lorem_ipsum_store_with_compressor(
+            directory.open_write(lz4_path).unwrap(), 100, Compressor::Lz4);
+        {
+            let mut store_writer = StoreWriter::new(
+                directory.open_write(stacked_path).unwrap(), Compressor::Lz4);
+            for path in &[none_path, lz4_path] {
+                let store_source = directory.open_read(path).unwrap();
+                let store_reader = StoreReader::from_source(store_source, Compressor::Lz4).unwrap();
+                store_writer.stack(&store_reader).unwrap();
+            }
+            store_writer.close().unwrap();
+        }
+        let field_title = schema.get_field("title").unwrap();
+        let store_source = directory.open_read(stacked_path).unwrap();
+        let store = StoreReader::from_source(store_source, Compressor::Lz4).unwrap();
+        for i in 0..200 {
+            assert_eq!(
+                *store.get(i).unwrap().get_first(field_title).unwrap().text(),
+                format!("Doc {}", i % 100)
+            );
+        }
+        let delete_bitset = DeleteBitSet::empty();
+        let docs: Vec<DocId> = store
+            .iter_documents(&delete_bitset)
+            .map(|doc_res| doc_res.unwrap().0)
+            .collect();
+        assert_eq!(docs, (0..200).collect::<Vec<DocId>>());
+    }
+
+    #[test]
+    fn test_store_layouts() {
+        let body = |i: u32| {
+            if i % 10 == 0 {
+                "b".repeat(3_000)
+            } else {
+                format!("doc {}", i)
+            }
+        };
+        let mut directory = RAMDirectory::create();
+        // every tenth document is larger than the block size.
+        let layouts = vec![
+            ("large", DocStoreLayout::LargeBlock, vec![1, 11, 21]),
+            ("row_greedy", DocStoreLayout::RowGreedy, vec![1, 10, 11, 20, 21]),
+        ];
+        for &(name, layout, ref expected_block_ends) in &layouts {
+            let path = Path::new(name);
+            let mut schema_builder = SchemaBuilder::default();
+            let field_body =
+                schema_builder.add_text_field("body", TextOptions::default().set_stored());
+            {
+                let store_file = directory.open_write(path).unwrap();
+                let mut store_writer =
+                    StoreWriter::with_layout(store_file, Compressor::None, 1_000, layout);
+                for i in 0..25 {
+                    store_writer.store(&doc!(field_body => body(i))).unwrap();
+                }
+                store_writer.close().unwrap();
+            }
+            let store_source = directory.open_read(path).unwrap();
+            let store = StoreReader::from_source(store_source, Compressor::None).unwrap();
+            let block_ends: Vec<u64> = store
+                .block_index()
+                .map(|(block_end, _)| block_end)
+                .take(expected_block_ends.len())
+                .collect();
+            assert_eq!(&block_ends, expected_block_ends);
+            for i in 0..25 {
+                assert_eq!(store.get(i).unwrap().get_first(field_body).unwrap().text(), body(i));
+            }
+        }
+    }
+
+    #[cfg(feature = "zstd-compression")]
+    #[test]
+    fn test_store_zstd() {
+        let path = Path::new("store");
+        let mut directory = RAMDirectory::create();
+        let compressor = Compressor::Zstd(ZstdCompressor {
+            compression_level: Some(3),
+            dictionary: None,
+        });
+        let store_file = directory.open_write(path).unwrap();
+        let schema = write_lorem_ipsum_store_with_compressor(store_file, 300, compressor.clone());
+        let field_title = schema.get_field("title").unwrap();
+        let store_source = directory.open_read(path).unwrap();
+        let store = StoreReader::from_source(store_source, compressor).unwrap();
+        for i in 0..300 {
+            assert_eq!(
+                *store.get(i).unwrap().get_first(field_title).unwrap().text(),
+                format!("Doc {}", i)
+            );
+        }
+    }
+
+    #[cfg(feature = "mmap")]
     #[bench]
     fn bench_store_encode(b: &mut Bencher) {
         let mut directory = MmapDirectory::create_from_tempdir().unwrap();
@@ -115,13 +334,14 @@ mod tests {
         });
     }
 
+    #[cfg(feature = "mmap")]
     #[bench]
     fn bench_store_decode(b: &mut Bencher) {
         let mut directory = MmapDirectory::create_from_tempdir().unwrap();
         let path = Path::new("store");
         write_lorem_ipsum_store(directory.open_write(path).unwrap(), 1_000);
         let store_source = directory.open_read(path).unwrap();
-        let store = StoreReader::from_source(store_source);
+        let store = StoreReader::from_source(store_source, Compressor::default()).unwrap();
         b.iter(|| {
             store.get(12).unwrap();
         });
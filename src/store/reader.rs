@@ -1,37 +1,96 @@
 use Result;
 
 use directory::ReadOnlySource;
-use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use core::{MemoryBudget, MemoryReservation};
 use DocId;
 use schema::Document;
 use common::BinarySerializable;
 use std::mem::size_of;
-use std::io::{self, Read};
+use std::io;
 use common::VInt;
 use datastruct::SkipList;
-use lz4;
+use super::Compressor;
+use super::LazyDocument;
+use super::compressors::block_len;
+use fastfield::DeleteBitSet;
+use error::DataCorruption;
+
+struct CachedBlock {
+    offset: usize,
+    data: Arc<Vec<u8>>,
+    // registered against the memory budget of the index, if any.
+    reservation: Option<MemoryReservation>,
+}
+
+/// Removes a block evicted by the memory budget,
+/// unless it has been replaced in the meantime.
+fn evict_block(current_block: &Mutex<Option<CachedBlock>>, entry_id: u64) {
+    let mut current_block = current_block.lock().expect("Doc store block lock poisoned");
+    let is_evicted_block = current_block
+        .as_ref()
+        .and_then(|cached_block| cached_block.reservation.as_ref())
+        .map(|reservation| reservation.entry_id() == entry_id)
+        .unwrap_or(false);
+    if is_evicted_block {
+        *current_block = None;
+    }
+}
 
 /// Reads document off tantivy's [`Store`](./index.html)
-#[derive(Clone)]
+///
+/// The last decompressed block is kept, so that reading documents
+/// of a same block one after the other only decompresses it once.
+/// Each clone of a `StoreReader` keeps its own block.
 pub struct StoreReader {
+    compressor: Compressor,
     data: ReadOnlySource,
     offset_index_source: ReadOnlySource,
-    current_block_offset: RefCell<usize>,
-    current_block: RefCell<Vec<u8>>,
+    current_block: Arc<Mutex<Option<CachedBlock>>>,
+    memory_budget: Option<MemoryBudget>,
     max_doc: DocId,
 }
 
+impl Clone for StoreReader {
+    fn clone(&self) -> StoreReader {
+        StoreReader {
+            compressor: self.compressor.clone(),
+            data: self.data.clone(),
+            offset_index_source: self.offset_index_source.clone(),
+            current_block: Arc::new(Mutex::new(None)),
+            memory_budget: self.memory_budget.clone(),
+            max_doc: self.max_doc,
+        }
+    }
+}
+
 impl StoreReader {
     /// Opens a store reader
-    pub fn from_source(data: ReadOnlySource) -> StoreReader {
-        let (data_source, offset_index_source, max_doc) = split_source(data);
-        StoreReader {
+    ///
+    /// Blocks are decompressed with the codec they were written with.
+    /// `compressor` is only used to provide the zstd dictionary, if any.
+    ///
+    /// Returns a `DataCorruption` error if the footer of the store is invalid.
+    pub fn from_source(data: ReadOnlySource, compressor: Compressor) -> io::Result<StoreReader> {
+        let (data_source, offset_index_source, max_doc) = split_source(data)?;
+        Ok(StoreReader {
+            compressor,
             data: data_source,
             offset_index_source,
-            current_block_offset: RefCell::new(usize::max_value()),
-            current_block: RefCell::new(Vec::new()),
+            current_block: Arc::new(Mutex::new(None)),
+            memory_budget: None,
             max_doc,
-        }
+        })
+    }
+
+    /// Registers the decompressed blocks against `memory_budget`.
+    pub(crate) fn set_memory_budget(&mut self, memory_budget: MemoryBudget) {
+        self.memory_budget = Some(memory_budget);
+    }
+
+    /// Returns the number of documents in the store.
+    pub(crate) fn max_doc(&self) -> DocId {
+        self.max_doc
     }
 
     pub(crate) fn block_index(&self) -> SkipList<u64> {
@@ -49,60 +108,255 @@ impl StoreReader {
         self.data.as_slice()
     }
 
-    fn compressed_block(&self, addr: usize) -> &[u8] {
+    fn compressed_block(&self, addr: usize) -> io::Result<(u32, &[u8])> {
         let total_buffer = self.data.as_slice();
+        if addr > total_buffer.len() {
+            return Err(DataCorruption::comment_only("Doc store block is out of bounds.").into());
+        }
         let mut buffer = &total_buffer[addr..];
-        let block_len = u32::deserialize(&mut buffer).expect("") as usize;
-        &buffer[..block_len]
-    }
-
-    fn read_block(&self, block_offset: usize) -> io::Result<()> {
-        if block_offset != *self.current_block_offset.borrow() {
-            let mut current_block_mut = self.current_block.borrow_mut();
-            current_block_mut.clear();
-            let compressed_block = self.compressed_block(block_offset);
-            let mut lz4_decoder = lz4::Decoder::new(compressed_block)?;
-            *self.current_block_offset.borrow_mut() = usize::max_value();
-            lz4_decoder.read_to_end(&mut current_block_mut).map(|_| ())?;
-            *self.current_block_offset.borrow_mut() = block_offset;
+        let block_header = u32::deserialize(&mut buffer)?;
+        let block_len = block_len(block_header);
+        if block_len > buffer.len() {
+            return Err(DataCorruption::comment_only("Doc store block is truncated.").into());
+        }
+        Ok((block_header, &buffer[..block_len]))
+    }
+
+    fn decompress_block(&self, block_offset: usize, block: &mut Vec<u8>) -> io::Result<()> {
+        let (block_header, compressed_block) = self.compressed_block(block_offset)?;
+        self.compressor
+            .decompress(block_header, compressed_block, block)
+    }
+
+    /// Returns the decompressed block starting at `block_offset`.
+    ///
+    /// The lock of the block is not held while decompressing it.
+    fn read_block(&self, block_offset: usize) -> io::Result<Arc<Vec<u8>>> {
+        {
+            let current_block = self.current_block.lock().expect("Doc store block lock poisoned");
+            if let Some(ref cached_block) = *current_block {
+                if cached_block.offset == block_offset {
+                    if let Some(ref reservation) = cached_block.reservation {
+                        reservation.touch();
+                    }
+                    return Ok(Arc::clone(&cached_block.data));
+                }
+            }
+        }
+        let mut block = Vec::new();
+        self.decompress_block(block_offset, &mut block)?;
+        let block = Arc::new(block);
+        let mut evictions = None;
+        {
+            let mut current_block = self.current_block
+                .lock()
+                .expect("Doc store block lock poisoned");
+            let reservation = match self.memory_budget {
+                Some(ref memory_budget) => {
+                    let current_block_ref = Arc::downgrade(&self.current_block);
+                    let evict = move |entry_id: u64| {
+                        if let Some(current_block) = current_block_ref.upgrade() {
+                            evict_block(&current_block, entry_id);
+                        }
+                    };
+                    match memory_budget.reserve(block.len(), evict) {
+                        Some((reservation, block_evictions)) => {
+                            evictions = Some(block_evictions);
+                            Some(reservation)
+                        }
+                        // larger than the whole budget.
+                        None => return Ok(block),
+                    }
+                }
+                None => None,
+            };
+            *current_block = Some(CachedBlock {
+                offset: block_offset,
+                data: Arc::clone(&block),
+                reservation,
+            });
+        }
+        if let Some(evictions) = evictions {
+            evictions.run();
+        }
+        Ok(block)
+    }
+
+    /// Calls `f` on the serialized bytes of a given document.
+    fn with_doc_bytes<T, F: FnOnce(&[u8]) -> T>(&self, doc_id: DocId, f: F) -> Result<T> {
+        let (first_doc_id, block_offset) = self.block_offset(doc_id);
+        let block = self.read_block(block_offset as usize)?;
+        let mut cursor = &block[..];
+        for _ in first_doc_id..doc_id {
+            read_doc_bytes(&mut cursor)?;
         }
-        Ok(())
+        Ok(f(read_doc_bytes(&mut cursor)?))
     }
 
     /// Reads a given document.
     ///
     /// Calling `.get(doc)` is relatively costly as it requires
-    /// decompressing a LZ4-compressed block.
+    /// decompressing a compressed block.
     ///
     /// It should not be called to score documents
     /// for instance.
     pub fn get(&self, doc_id: DocId) -> Result<Document> {
-        let (first_doc_id, block_offset) = self.block_offset(doc_id);
-        self.read_block(block_offset as usize)?;
-        let current_block_mut = self.current_block.borrow_mut();
-        let mut cursor = &current_block_mut[..];
-        for _ in first_doc_id..doc_id {
-            let doc_length = VInt::deserialize(&mut cursor)?.val() as usize;
-            cursor = &cursor[doc_length..];
+        let doc = self.with_doc_bytes(doc_id, |mut doc_bytes| {
+            Document::deserialize(&mut doc_bytes)
+        })??;
+        Ok(doc)
+    }
+
+    /// Reads a given document, without deserializing its fields.
+    ///
+    /// The block of the document is decompressed as with `.get(doc)`,
+    /// but the values of the fields are only deserialized when
+    /// they are requested. See `LazyDocument`.
+    pub fn get_lazy(&self, doc_id: DocId) -> Result<LazyDocument> {
+        self.with_doc_bytes(doc_id, |doc_bytes| LazyDocument::from(doc_bytes.to_vec()))
+    }
+
+    /// Iterates over all of the documents of the store that
+    /// are not deleted, in the order of their `DocId`.
+    ///
+    /// Blocks are decompressed one after the other, and each of them
+    /// only once, which makes scanning the whole store, for instance to
+    /// export or reindex its documents, much faster than calling `.get(doc)`
+    /// for every document. Blocks in which all of the documents are
+    /// deleted are not decompressed at all.
+    ///
+    /// The iterator stops after returning an error.
+    pub fn iter_documents<'a>(
+        &'a self,
+        delete_bitset: &'a DeleteBitSet,
+    ) -> StoreDocumentIter<'a> {
+        let blocks = self.block_index()
+            .map(|(block_end_doc, block_end_offset)| {
+                (block_end_doc as DocId, block_end_offset as usize)
+            })
+            .collect();
+        StoreDocumentIter {
+            store_reader: self,
+            delete_bitset,
+            blocks,
+            block_ord: 0,
+            block_start_offset: 0,
+            block_end_doc: 0,
+            block: Vec::new(),
+            block_cursor: 0,
+            doc: 0,
         }
-        let doc_length = VInt::deserialize(&mut cursor)?.val() as usize;
-        cursor = &cursor[..doc_length];
-        Ok(Document::deserialize(&mut cursor)?)
     }
 }
 
+/// Iterator over the documents of a store, along with their `DocId`.
+///
+/// See `StoreReader::iter_documents`.
+pub struct StoreDocumentIter<'a> {
+    store_reader: &'a StoreReader,
+    delete_bitset: &'a DeleteBitSet,
+    // end doc and end offset of each of the blocks.
+    blocks: Vec<(DocId, usize)>,
+    block_ord: usize,
+    block_start_offset: usize,
+    block_end_doc: DocId,
+    block: Vec<u8>,
+    block_cursor: usize,
+    doc: DocId,
+}
+
+impl<'a> StoreDocumentIter<'a> {
+    /// Moves on to the next block, skipping it if all
+    /// of its documents are deleted.
+    ///
+    /// Returns false once all of the blocks have been read.
+    fn advance_block(&mut self) -> io::Result<bool> {
+        if self.block_ord == self.blocks.len() {
+            return Ok(false);
+        }
+        let (block_end_doc, block_end_offset) = self.blocks[self.block_ord];
+        self.block_ord += 1;
+        let block_start_offset = self.block_start_offset;
+        self.block_start_offset = block_end_offset;
+        self.block_end_doc = block_end_doc;
+        self.block_cursor = 0;
+        self.block.clear();
+        let delete_bitset = self.delete_bitset;
+        if (self.doc..block_end_doc).all(|doc| delete_bitset.is_deleted(doc)) {
+            self.doc = block_end_doc;
+        } else {
+            self.store_reader
+                .decompress_block(block_start_offset, &mut self.block)?;
+        }
+        Ok(true)
+    }
+
+    fn advance(&mut self) -> Result<Option<(DocId, Document)>> {
+        loop {
+            while self.doc == self.block_end_doc {
+                if !self.advance_block()? {
+                    return Ok(None);
+                }
+            }
+            let doc = self.doc;
+            self.doc += 1;
+            let mut cursor = &self.block[self.block_cursor..];
+            let mut doc_bytes = read_doc_bytes(&mut cursor)?;
+            self.block_cursor = self.block.len() - cursor.len();
+            if !self.delete_bitset.is_deleted(doc) {
+                return Ok(Some((doc, Document::deserialize(&mut doc_bytes)?)));
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for StoreDocumentIter<'a> {
+    type Item = Result<(DocId, Document)>;
+
+    fn next(&mut self) -> Option<Result<(DocId, Document)>> {
+        match self.advance() {
+            Ok(doc_opt) => doc_opt.map(Ok),
+            Err(e) => {
+                self.block_ord = self.blocks.len();
+                self.block_end_doc = self.doc;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Reads the serialized bytes of the document at the start
+/// of `cursor`, and advances `cursor` past them.
+fn read_doc_bytes<'a>(cursor: &mut &'a [u8]) -> io::Result<&'a [u8]> {
+    let doc_length = VInt::deserialize(cursor)?.val() as usize;
+    if doc_length > cursor.len() {
+        return Err(DataCorruption::comment_only("Doc store document is truncated.").into());
+    }
+    let data: &'a [u8] = *cursor;
+    let (doc_bytes, remaining) = data.split_at(doc_length);
+    *cursor = remaining;
+    Ok(doc_bytes)
+}
+
 #[allow(needless_pass_by_value)]
-fn split_source(data: ReadOnlySource) -> (ReadOnlySource, ReadOnlySource, DocId) {
+fn split_source(data: ReadOnlySource) -> io::Result<(ReadOnlySource, ReadOnlySource, DocId)> {
     let data_len = data.len();
-    let footer_offset = data_len - size_of::<u64>() - size_of::<u32>();
+    let footer_len = size_of::<u64>() + size_of::<u32>();
+    if data_len < footer_len {
+        return Err(DataCorruption::comment_only("Doc store is too short.").into());
+    }
+    let footer_offset = data_len - footer_len;
     let serialized_offset: ReadOnlySource = data.slice(footer_offset, data_len);
     let mut serialized_offset_buf = serialized_offset.as_slice();
-    let offset = u64::deserialize(&mut serialized_offset_buf).unwrap();
+    let offset = u64::deserialize(&mut serialized_offset_buf)?;
+    let max_doc = u32::deserialize(&mut serialized_offset_buf)?;
+    if offset > footer_offset as u64 {
+        return Err(DataCorruption::comment_only("Doc store index is out of bounds.").into());
+    }
     let offset = offset as usize;
-    let max_doc = u32::deserialize(&mut serialized_offset_buf).unwrap();
-    (
+    Ok((
         data.slice(0, offset),
         data.slice(offset, footer_offset),
         max_doc,
-    )
+    ))
 }
@@ -3,12 +3,45 @@ use DocId;
 use common::{BinarySerializable, VInt};
 use std::io::{self, Write};
 use super::StoreReader;
-use lz4;
+use super::Compressor;
 use datastruct::SkipListBuilder;
 use common::CountingWriter;
 use schema::Document;
 
-const BLOCK_SIZE: usize = 16_384;
+/// Default size of the uncompressed blocks of the doc store, in bytes.
+pub(crate) const DEFAULT_BLOCK_SIZE: usize = 16_384;
+
+/// Defines how documents are grouped into the blocks of the doc store.
+///
+/// Reading a document requires decompressing its entire block, while
+/// larger blocks compress better and are cheaper to scan.
+/// The layout, along with the block size, lets one pick a side of
+/// this tradeoff. See the [`store` module documentation](./index.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocStoreLayout {
+    /// Documents are appended to a block until it exceeds the block size.
+    ///
+    /// Blocks are slightly larger than the block size, and are all
+    /// close to it. This is the default, and works best with
+    /// a large block size for workloads that scan many documents,
+    /// such as exporting logs.
+    LargeBlock,
+    /// A block is closed before appending a document that would make
+    /// it exceed the block size, and documents larger than the block size
+    /// get a block of their own.
+    ///
+    /// Reading a document never decompresses more than the block size,
+    /// or than the document itself. This works best with a small
+    /// block size for random access to single documents, such as
+    /// displaying the top hits of a search.
+    RowGreedy,
+}
+
+impl Default for DocStoreLayout {
+    fn default() -> DocStoreLayout {
+        DocStoreLayout::LargeBlock
+    }
+}
 
 /// Write tantivy's [`Store`](./index.html)
 ///
@@ -19,25 +52,47 @@ const BLOCK_SIZE: usize = 16_384;
 /// The skip list index on the other hand, is build in memory.
 ///
 pub struct StoreWriter {
+    compressor: Compressor,
+    block_size: usize,
+    layout: DocStoreLayout,
     doc: DocId,
     offset_index_writer: SkipListBuilder<u64>,
     writer: CountingWriter<WritePtr>,
     intermediary_buffer: Vec<u8>,
     current_block: Vec<u8>,
+    compressed_block: Vec<u8>,
 }
 
 impl StoreWriter {
     /// Create a store writer.
     ///
     /// The store writer will writes blocks on disc as
-    /// document are added.
-    pub fn new(writer: WritePtr) -> StoreWriter {
+    /// document are added, compressing them with
+    /// the given `compressor`.
+    ///
+    /// Blocks are laid out with the default block size and layout.
+    pub fn new(writer: WritePtr, compressor: Compressor) -> StoreWriter {
+        StoreWriter::with_layout(writer, compressor, DEFAULT_BLOCK_SIZE, DocStoreLayout::default())
+    }
+
+    /// Create a store writer, grouping documents into
+    /// blocks of `block_size` bytes according to `layout`.
+    pub fn with_layout(
+        writer: WritePtr,
+        compressor: Compressor,
+        block_size: usize,
+        layout: DocStoreLayout,
+    ) -> StoreWriter {
         StoreWriter {
+            compressor,
+            block_size,
+            layout,
             doc: 0,
             offset_index_writer: SkipListBuilder::new(4),
             writer: CountingWriter::wrap(writer),
             intermediary_buffer: Vec::new(),
             current_block: Vec::new(),
+            compressed_block: Vec::new(),
         }
     }
 
@@ -50,10 +105,19 @@ impl StoreWriter {
         self.intermediary_buffer.clear();
         stored_document.serialize(&mut self.intermediary_buffer)?;
         let doc_num_bytes = self.intermediary_buffer.len();
+        if self.layout == DocStoreLayout::RowGreedy && !self.current_block.is_empty()
+            && self.current_block.len() + doc_num_bytes > self.block_size
+        {
+            self.write_and_compress_block()?;
+        }
         VInt(doc_num_bytes as u64).serialize(&mut self.current_block)?;
         self.current_block.write_all(&self.intermediary_buffer[..])?;
         self.doc += 1;
-        if self.current_block.len() > BLOCK_SIZE {
+        let block_is_full = match self.layout {
+            DocStoreLayout::LargeBlock => self.current_block.len() > self.block_size,
+            DocStoreLayout::RowGreedy => self.current_block.len() >= self.block_size,
+        };
+        if block_is_full {
             self.write_and_compress_block()?;
         }
         Ok(())
@@ -63,6 +127,9 @@ impl StoreWriter {
     /// This method is an optimization compared to iterating over the documents
     /// in the store and adding them one by one, as the store's data will
     /// not be decompressed and then recompressed.
+    ///
+    /// The blocks of the store reader are kept as is, even if they were
+    /// written with another compressor than the one of this writer.
     pub fn stack(&mut self, store_reader: &StoreReader) -> io::Result<()> {
         if !self.current_block.is_empty() {
             self.write_and_compress_block()?;
@@ -86,15 +153,10 @@ impl StoreWriter {
     }
 
     fn write_and_compress_block(&mut self) -> io::Result<()> {
-        self.intermediary_buffer.clear();
-        {
-            let mut encoder = lz4::EncoderBuilder::new().build(&mut self.intermediary_buffer)?;
-            encoder.write_all(&self.current_block)?;
-            let (_, encoder_result) = encoder.finish();
-            encoder_result?;
-        }
-        (self.intermediary_buffer.len() as u32).serialize(&mut self.writer)?;
-        self.writer.write_all(&self.intermediary_buffer)?;
+        let block_header = self.compressor
+            .compress(&self.current_block, &mut self.compressed_block)?;
+        block_header.serialize(&mut self.writer)?;
+        self.writer.write_all(&self.compressed_block)?;
         self.offset_index_writer
             .insert(u64::from(self.doc), &(self.writer.written_bytes() as u64))?;
         self.current_block.clear();
@@ -1,6 +1,7 @@
 use fst::{IntoStreamer, Streamer};
 use fst::map::{Stream, StreamBuilder};
 use postings::TermInfo;
+use std::collections::Bound;
 use super::TermDictionaryImpl;
 use termdict::{TermDictionary, TermOrdinal, TermStreamer, TermStreamerBuilder};
 
@@ -8,6 +9,11 @@ use termdict::{TermDictionary, TermOrdinal, TermStreamer, TermStreamerBuilder};
 pub struct TermStreamerBuilderImpl<'a> {
     fst_map: &'a TermDictionaryImpl,
     stream_builder: StreamBuilder<'a>,
+    // the bounds are kept to resolve the range
+    // of term ordinals of a backward stream.
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+    backward: bool,
 }
 
 impl<'a> TermStreamerBuilderImpl<'a> {
@@ -15,37 +21,86 @@ impl<'a> TermStreamerBuilderImpl<'a> {
         TermStreamerBuilderImpl {
             fst_map: fst_map,
             stream_builder: stream_builder,
+            lower: Bound::Unbounded,
+            upper: Bound::Unbounded,
+            backward: false,
         }
     }
 }
 
+/// Returns the ordinal of the first term of the stream,
+/// or the number of terms if the stream is empty.
+fn first_ord(fst_map: &TermDictionaryImpl, mut streamer: TermStreamerImpl) -> TermOrdinal {
+    if streamer.advance() {
+        streamer.term_ord()
+    } else {
+        fst_map.num_terms() as TermOrdinal
+    }
+}
+
 impl<'a> TermStreamerBuilder for TermStreamerBuilderImpl<'a> {
     type Streamer = TermStreamerImpl<'a>;
 
     fn ge<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.lower = Bound::Included(bound.as_ref().to_vec());
         self.stream_builder = self.stream_builder.ge(bound);
         self
     }
 
     fn gt<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.lower = Bound::Excluded(bound.as_ref().to_vec());
         self.stream_builder = self.stream_builder.gt(bound);
         self
     }
 
     fn le<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.upper = Bound::Included(bound.as_ref().to_vec());
         self.stream_builder = self.stream_builder.le(bound);
         self
     }
 
     fn lt<T: AsRef<[u8]>>(mut self, bound: T) -> Self {
+        self.upper = Bound::Excluded(bound.as_ref().to_vec());
         self.stream_builder = self.stream_builder.lt(bound);
         self
     }
 
+    fn backward(mut self) -> Self {
+        self.backward = true;
+        self
+    }
+
     fn into_stream(self) -> Self::Streamer {
+        let fst_map = self.fst_map;
+        let stream = if self.backward {
+            let start_ord = match self.lower {
+                Bound::Included(ref bound) => {
+                    first_ord(fst_map, fst_map.range().ge(bound).into_stream())
+                }
+                Bound::Excluded(ref bound) => {
+                    first_ord(fst_map, fst_map.range().gt(bound).into_stream())
+                }
+                Bound::Unbounded => 0,
+            };
+            let stop_ord = match self.upper {
+                Bound::Included(ref bound) => {
+                    first_ord(fst_map, fst_map.range().gt(bound).into_stream())
+                }
+                Bound::Excluded(ref bound) => {
+                    first_ord(fst_map, fst_map.range().ge(bound).into_stream())
+                }
+                Bound::Unbounded => fst_map.num_terms() as TermOrdinal,
+            };
+            TermStream::Backward {
+                start_ord,
+                stop_ord,
+            }
+        } else {
+            TermStream::Forward(self.stream_builder.into_stream())
+        };
         TermStreamerImpl {
-            fst_map: self.fst_map,
-            stream: self.stream_builder.into_stream(),
+            fst_map,
+            stream,
             term_ord: 0u64,
             current_key: Vec::with_capacity(100),
             current_value: TermInfo::default(),
@@ -53,26 +108,88 @@ impl<'a> TermStreamerBuilder for TermStreamerBuilderImpl<'a> {
     }
 }
 
+enum TermStream<'a> {
+    Forward(Stream<'a>),
+    // Terms are read by ordinal, from `stop_ord - 1` down to `start_ord`.
+    Backward {
+        start_ord: TermOrdinal,
+        stop_ord: TermOrdinal,
+    },
+}
+
 /// See [`TermStreamer`](./trait.TermStreamer.html)
 pub struct TermStreamerImpl<'a> {
     fst_map: &'a TermDictionaryImpl,
-    stream: Stream<'a>,
+    stream: TermStream<'a>,
     term_ord: TermOrdinal,
     current_key: Vec<u8>,
     current_value: TermInfo,
 }
 
+impl<'a> TermStreamerImpl<'a> {
+    /// Returns true if the stream yields its terms in decreasing order.
+    pub(crate) fn is_backward(&self) -> bool {
+        match self.stream {
+            TermStream::Forward(_) => false,
+            TermStream::Backward { .. } => true,
+        }
+    }
+
+    /// Advances a backward stream to the greatest term of its range
+    /// lower or equal to `target`, without reading the terms in between.
+    ///
+    /// If `target` is greater than the current term, the stream simply
+    /// advances to the next term. Returns false if there is no more term.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the stream was not built with `.backward()`.
+    pub fn skip_backwards<T: AsRef<[u8]>>(&mut self, target: T) -> bool {
+        let fst_map = self.fst_map;
+        match self.stream {
+            TermStream::Backward { ref mut stop_ord, .. } => {
+                let target_stop_ord =
+                    first_ord(fst_map, fst_map.range().gt(target).into_stream());
+                if target_stop_ord < *stop_ord {
+                    *stop_ord = target_stop_ord;
+                }
+            }
+            TermStream::Forward(_) => {
+                panic!("skip_backwards can only be called on a backward stream.");
+            }
+        }
+        self.advance()
+    }
+}
+
 impl<'a> TermStreamer for TermStreamerImpl<'a> {
     fn advance(&mut self) -> bool {
-        if let Some((term, term_ord)) = self.stream.next() {
-            self.current_key.clear();
-            self.current_key.extend_from_slice(term);
-            self.term_ord = term_ord;
-            self.current_value = self.fst_map.term_info_from_ord(term_ord);
-            true
-        } else {
-            false
+        match self.stream {
+            TermStream::Forward(ref mut stream) => {
+                if let Some((term, term_ord)) = stream.next() {
+                    self.current_key.clear();
+                    self.current_key.extend_from_slice(term);
+                    self.term_ord = term_ord;
+                } else {
+                    return false;
+                }
+            }
+            TermStream::Backward {
+                start_ord,
+                ref mut stop_ord,
+            } => {
+                if *stop_ord <= start_ord {
+                    return false;
+                }
+                *stop_ord -= 1;
+                self.term_ord = *stop_ord;
+                let found = self.fst_map
+                    .ord_to_term(self.term_ord, &mut self.current_key);
+                assert!(found, "Term ordinal out of bounds. The term dictionary is corrupted.");
+            }
         }
+        self.current_value = self.fst_map.term_info_from_ord(self.term_ord);
+        true
     }
 
     fn term_ord(&self) -> TermOrdinal {
@@ -10,6 +10,7 @@ use common::bitpacker::BitPacker;
 use directory::ReadOnlySource;
 use termdict::TermOrdinal;
 use byteorder::ByteOrder;
+use error::DataCorruption;
 
 const BLOCK_LEN: usize = 256;
 
@@ -76,8 +77,8 @@ impl TermInfoBlockMeta {
 
         TermInfo {
             doc_freq,
-            postings_offset: postings_offset + self.ref_term_info.postings_offset,
-            positions_offset: positions_offset + self.ref_term_info.positions_offset,
+            postings_offset: postings_offset.wrapping_add(self.ref_term_info.postings_offset),
+            positions_offset: positions_offset.wrapping_add(self.ref_term_info.positions_offset),
             positions_inner_offset,
         }
     }
@@ -100,20 +101,65 @@ fn extract_bits(data: &[u8], addr_bits: usize, num_bits: u8) -> u64 {
 }
 
 impl TermInfoStore {
-    pub fn open(data: &ReadOnlySource) -> TermInfoStore {
+    /// Opens a `TermInfoStore`.
+    ///
+    /// The metadata of all of the blocks is checked, so that
+    /// reading a term info afterwards cannot read out of bounds.
+    pub fn open(data: &ReadOnlySource) -> io::Result<TermInfoStore> {
         let buffer = data.as_slice();
-        let len = Endianness::read_u64(&buffer[0..8]) as usize;
-        let num_terms = Endianness::read_u64(&buffer[8..16]) as usize;
-        let block_meta_source = data.slice(16, 16 + len);
-        let term_info_source = data.slice_from(16 + len);
-        TermInfoStore {
-            num_terms,
-            block_meta_source,
-            term_info_source,
+        if buffer.len() < 16 {
+            return Err(DataCorruption::comment_only("Term info store is too short.").into());
         }
+        let len = Endianness::read_u64(&buffer[0..8]);
+        let num_terms = Endianness::read_u64(&buffer[8..16]);
+        if len > (buffer.len() - 16) as u64 {
+            return Err(
+                DataCorruption::comment_only("Term info block metas are out of bounds.").into(),
+            );
+        }
+        let len = len as usize;
+        let num_blocks = len / TermInfoBlockMeta::SIZE_IN_BYTES;
+        if num_terms > num_blocks as u64 * BLOCK_LEN as u64 {
+            return Err(DataCorruption::comment_only("Term info block metas are missing.").into());
+        }
+        let term_info_store = TermInfoStore {
+            num_terms: num_terms as usize,
+            block_meta_source: data.slice(16, 16 + len),
+            term_info_source: data.slice_from(16 + len),
+        };
+        term_info_store.check_blocks()?;
+        Ok(term_info_store)
+    }
+
+    fn check_blocks(&self) -> io::Result<()> {
+        let mut block_meta_data = self.block_meta_source.as_slice();
+        let term_info_len = self.term_info_source.len() as u64;
+        let num_blocks = (self.num_terms + BLOCK_LEN - 1) / BLOCK_LEN;
+        for block_id in 0..num_blocks {
+            let block_meta = TermInfoBlockMeta::deserialize(&mut block_meta_data)?;
+            if block_meta.doc_freq_nbits > 32
+                || block_meta.postings_offset_nbits > 56
+                || block_meta.positions_offset_nbits > 56
+            {
+                return Err(DataCorruption::comment_only("Term info block meta is invalid.").into());
+            }
+            let num_packed_terms =
+                cmp::min(BLOCK_LEN, self.num_terms - block_id * BLOCK_LEN) as u64 - 1;
+            // `extract_bits` reads 8 bytes at a time, relying on the 7 bytes of padding.
+            let num_bytes = (u64::from(block_meta.num_bits()) * num_packed_terms + 7) / 8 + 7;
+            if block_meta.offset > term_info_len || num_bytes > term_info_len - block_meta.offset
+            {
+                return Err(DataCorruption::comment_only("Term info block is truncated.").into());
+            }
+        }
+        Ok(())
     }
 
     pub fn get(&self, term_ord: TermOrdinal) -> TermInfo {
+        if term_ord >= self.num_terms as u64 {
+            // only a corrupted term dictionary may return such an ordinal.
+            return TermInfo::default();
+        }
         let block_id = (term_ord as usize) / BLOCK_LEN;
         let buffer = self.block_meta_source.as_slice();
         let mut block_data: &[u8] = &buffer[block_id * TermInfoBlockMeta::SIZE_IN_BYTES..];
@@ -322,10 +368,13 @@ mod tests {
         }
         let mut buffer = Vec::new();
         store_writer.serialize(&mut buffer).unwrap();
-        let term_info_store = TermInfoStore::open(&ReadOnlySource::from(buffer));
+        let term_info_store = TermInfoStore::open(&ReadOnlySource::from(buffer.clone())).unwrap();
         for i in 0..1000 {
             assert_eq!(term_info_store.get(i as u64), term_infos[i]);
         }
+        for len in 0..buffer.len() {
+            assert!(TermInfoStore::open(&ReadOnlySource::from(buffer[..len].to_vec())).is_err());
+        }
     }
 
 }
@@ -4,6 +4,7 @@ use fst::raw::Fst;
 use directory::ReadOnlySource;
 use common::BinarySerializable;
 use common::CountingWriter;
+use error::DataCorruption;
 use schema::FieldType;
 use postings::TermInfo;
 use termdict::{TermDictionary, TermDictionaryBuilder, TermOrdinal};
@@ -81,16 +82,18 @@ where
     }
 }
 
-fn open_fst_index(source: ReadOnlySource) -> fst::Map {
-    let fst = match source {
-        ReadOnlySource::Anonymous(data) => {
-            Fst::from_shared_bytes(data.data, data.start, data.len).expect("FST data is corrupted")
-        }
-        ReadOnlySource::Mmap(mmap_readonly) => {
-            Fst::from_mmap(mmap_readonly).expect("FST data is corrupted")
-        }
+fn open_fst_index(source: ReadOnlySource) -> io::Result<fst::Map> {
+    let fst_result = match source {
+        ReadOnlySource::Anonymous(data) => Fst::from_shared_bytes(data.data, data.start, data.len),
+        #[cfg(feature = "mmap")]
+        ReadOnlySource::Mmap(mmap_readonly) => Fst::from_mmap(mmap_readonly),
+        ReadOnlySource::Static(data) => Fst::from_static_slice(data),
+        #[cfg(feature = "object_store")]
+        lazy @ ReadOnlySource::Lazy(_) => Fst::from_bytes(lazy.as_slice().to_vec()),
     };
-    fst::Map::from(fst)
+    let fst = fst_result
+        .map_err(|err| DataCorruption::comment_only(format!("FST data is corrupted: {}", err)))?;
+    Ok(fst::Map::from(fst))
 }
 
 /// See [`TermDictionary`](./trait.TermDictionary.html)
@@ -99,25 +102,48 @@ pub struct TermDictionaryImpl {
     term_info_store: TermInfoStore,
 }
 
+impl TermDictionaryImpl {
+    /// Creates an empty term dictionary.
+    ///
+    /// Used for segments that do not contain any term for a given field.
+    pub(crate) fn empty(field_type: FieldType) -> TermDictionaryImpl {
+        let term_dictionary_data: Vec<u8> =
+            TermDictionaryBuilderImpl::new(Vec::<u8>::new(), field_type)
+                .expect("Creating a TermDictionaryBuilder in a Vec<u8> should never fail")
+                .finish()
+                .expect("Writing in a Vec<u8> should never fail");
+        TermDictionaryImpl::from_source(ReadOnlySource::from(term_dictionary_data))
+            .expect("An empty term dictionary should always be valid")
+    }
+}
+
 impl<'a> TermDictionary<'a> for TermDictionaryImpl {
     type Streamer = TermStreamerImpl<'a>;
 
     type StreamBuilder = TermStreamerBuilderImpl<'a>;
 
-    fn from_source(source: ReadOnlySource) -> Self {
+    fn from_source(source: ReadOnlySource) -> io::Result<Self> {
         let total_len = source.len();
+        if total_len < 8 {
+            return Err(DataCorruption::comment_only("Term dictionary is too short.").into());
+        }
         let length_offset = total_len - 8;
         let mut split_len_buffer: &[u8] = &source.as_slice()[length_offset..];
-        let footer_size = u64::deserialize(&mut split_len_buffer)
-            .expect("Deserializing 8 bytes should always work") as usize;
-        let split_len = length_offset - footer_size;
+        let footer_size = u64::deserialize(&mut split_len_buffer)?;
+        if footer_size > length_offset as u64 {
+            return Err(
+                DataCorruption::comment_only("Term dictionary footer is out of bounds.").into(),
+            );
+        }
+        let split_len = length_offset - footer_size as usize;
         let fst_source = source.slice(0, split_len);
         let values_source = source.slice(split_len, length_offset);
-        let fst_index = open_fst_index(fst_source);
-        TermDictionaryImpl {
+        let fst_index = open_fst_index(fst_source)?;
+        let term_info_store = TermInfoStore::open(&values_source)?;
+        Ok(TermDictionaryImpl {
             fst_index,
-            term_info_store: TermInfoStore::open(&values_source),
-        }
+            term_info_store,
+        })
     }
 
     fn num_terms(&self) -> usize {
@@ -0,0 +1,296 @@
+use postings::TermInfo;
+use std::char;
+use std::cmp;
+use termdict::{TermDictionary, TermDictionaryImpl, TermStreamer, TermStreamerBuilder};
+
+/// Automaton matching the strings within a given
+/// Levenshtein distance of a query string.
+///
+/// Distances are computed over unicode characters,
+/// while the automaton consumes the UTF-8 bytes of the strings.
+pub(crate) struct LevenshteinAutomaton {
+    query: Vec<char>,
+    max_distance: u32,
+}
+
+/// State of a `LevenshteinAutomaton`.
+///
+/// `row[i]` is the distance between the consumed string and
+/// the `i` first characters of the query.
+#[derive(Clone)]
+pub(crate) struct LevenshteinState {
+    row: Vec<u32>,
+    // code point of the character being decoded, if its
+    // UTF-8 encoding has not been entirely consumed yet.
+    code_point: u32,
+    num_missing_bytes: u8,
+}
+
+impl LevenshteinAutomaton {
+    pub fn new(query: &str, max_distance: u8) -> LevenshteinAutomaton {
+        LevenshteinAutomaton {
+            query: query.chars().collect(),
+            max_distance: u32::from(max_distance),
+        }
+    }
+
+    pub fn start(&self) -> LevenshteinState {
+        LevenshteinState {
+            row: (0..self.query.len() as u32 + 1).collect(),
+            code_point: 0u32,
+            num_missing_bytes: 0u8,
+        }
+    }
+
+    fn accept_char(&self, row: &mut Vec<u32>, c: char) {
+        let mut diagonal = row[0];
+        row[0] += 1;
+        for i in 1..row.len() {
+            let substitution_cost = if self.query[i - 1] == c { 0 } else { 1 };
+            let distance = cmp::min(
+                diagonal + substitution_cost,
+                cmp::min(row[i], row[i - 1]) + 1,
+            );
+            diagonal = row[i];
+            row[i] = distance;
+        }
+    }
+
+    pub fn accept(&self, state: &LevenshteinState, byte: u8) -> LevenshteinState {
+        let mut state = state.clone();
+        if state.num_missing_bytes > 0 {
+            if byte & 0xC0 == 0x80 {
+                state.code_point = (state.code_point << 6) | u32::from(byte & 0x3F);
+                state.num_missing_bytes -= 1;
+                if state.num_missing_bytes == 0 {
+                    let c = char::from_u32(state.code_point).unwrap_or(char::REPLACEMENT_CHARACTER);
+                    self.accept_char(&mut state.row, c);
+                }
+                return state;
+            }
+            // the previous character is truncated.
+            state.num_missing_bytes = 0;
+            self.accept_char(&mut state.row, char::REPLACEMENT_CHARACTER);
+        }
+        let (code_point, num_missing_bytes) = match byte {
+            0x00...0x7F => (u32::from(byte), 0u8),
+            0xC0...0xDF => (u32::from(byte & 0x1F), 1u8),
+            0xE0...0xEF => (u32::from(byte & 0x0F), 2u8),
+            0xF0...0xF7 => (u32::from(byte & 0x07), 3u8),
+            _ => (char::REPLACEMENT_CHARACTER as u32, 0u8),
+        };
+        if num_missing_bytes == 0 {
+            let c = char::from_u32(code_point).unwrap_or(char::REPLACEMENT_CHARACTER);
+            self.accept_char(&mut state.row, c);
+        } else {
+            state.code_point = code_point;
+            state.num_missing_bytes = num_missing_bytes;
+        }
+        state
+    }
+
+    /// Returns false if none of the strings starting with
+    /// the consumed string are matched.
+    pub fn can_match(&self, state: &LevenshteinState) -> bool {
+        state.row.iter().cloned().min().unwrap_or(0) <= self.max_distance
+    }
+
+    /// Returns the distance between the consumed string and the query,
+    /// if the consumed string is matched.
+    pub fn distance(&self, state: &LevenshteinState) -> Option<u8> {
+        if state.num_missing_bytes > 0 {
+            return None;
+        }
+        let distance = state.row[self.query.len()];
+        if distance <= self.max_distance {
+            Some(distance as u8)
+        } else {
+            None
+        }
+    }
+}
+
+// Returns the smallest key greater than all of the keys starting with `prefix`.
+pub(crate) fn next_prefix(prefix: &[u8]) -> Option<Vec<u8>> {
+    let mut next = prefix.to_vec();
+    while let Some(last_byte) = next.pop() {
+        if last_byte != 0xFF {
+            next.push(last_byte + 1);
+            return Some(next);
+        }
+    }
+    None
+}
+
+fn common_prefix_len(left: &[u8], right: &[u8]) -> usize {
+    left.iter()
+        .zip(right.iter())
+        .take_while(|&(left_byte, right_byte)| left_byte == right_byte)
+        .count()
+}
+
+/// Calls `callback` with the terms of `termdict` matched by the `automaton`,
+/// their distance to the query, and their `TermInfo`.
+///
+/// The automaton states are shared between the terms having a common prefix,
+/// and the terms starting with a prefix that cannot be matched
+/// are skipped by seeking the term stream after them.
+pub(crate) fn search_levenshtein<F>(
+    termdict: &TermDictionaryImpl,
+    automaton: &LevenshteinAutomaton,
+    mut callback: F,
+) where
+    F: FnMut(&[u8], u8, &TermInfo),
+{
+    // `states[i]` is the state of the automaton after
+    // the `i` first bytes of `prev_key`.
+    let mut states = vec![automaton.start()];
+    let mut prev_key: Vec<u8> = vec![];
+    let mut lower_bound: Option<Vec<u8>> = None;
+    loop {
+        let mut stream = match lower_bound.take() {
+            Some(lower_bound) => termdict.range().ge(lower_bound).into_stream(),
+            None => termdict.stream(),
+        };
+        while stream.advance() {
+            let key = stream.key();
+            let num_valid_states = cmp::min(common_prefix_len(&prev_key, key), states.len() - 1);
+            states.truncate(num_valid_states + 1);
+            let mut dead_prefix_len = None;
+            for &byte in &key[num_valid_states..] {
+                let state = automaton.accept(&states[states.len() - 1], byte);
+                let can_match = automaton.can_match(&state);
+                states.push(state);
+                if !can_match {
+                    dead_prefix_len = Some(states.len() - 1);
+                    break;
+                }
+            }
+            prev_key.clear();
+            prev_key.extend_from_slice(key);
+            if let Some(dead_prefix_len) = dead_prefix_len {
+                lower_bound = next_prefix(&key[..dead_prefix_len]);
+                break;
+            }
+            if let Some(distance) = automaton.distance(&states[states.len() - 1]) {
+                callback(key, distance, stream.value());
+            }
+        }
+        if lower_bound.is_none() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{next_prefix, search_levenshtein, LevenshteinAutomaton};
+    use directory::{Directory, RAMDirectory};
+    use postings::TermInfo;
+    use schema::{FieldType, TEXT};
+    use std::cmp;
+    use std::path::PathBuf;
+    use termdict::{TermDictionary, TermDictionaryBuilder, TermDictionaryBuilderImpl,
+                   TermDictionaryImpl};
+
+    fn levenshtein_distance(left: &str, right: &str) -> u32 {
+        let right: Vec<char> = right.chars().collect();
+        let mut row: Vec<u32> = (0..right.len() as u32 + 1).collect();
+        for (i, left_char) in left.chars().enumerate() {
+            let mut new_row = vec![i as u32 + 1];
+            for j in 1..right.len() + 1 {
+                let substitution_cost = if right[j - 1] == left_char { 0 } else { 1 };
+                let distance = cmp::min(
+                    row[j - 1] + substitution_cost,
+                    cmp::min(row[j], new_row[j - 1]) + 1,
+                );
+                new_row.push(distance);
+            }
+            row = new_row;
+        }
+        row[right.len()]
+    }
+
+    fn automaton_distance(automaton: &LevenshteinAutomaton, text: &str) -> Option<u8> {
+        let mut state = automaton.start();
+        for &byte in text.as_bytes() {
+            state = automaton.accept(&state, byte);
+        }
+        automaton.distance(&state)
+    }
+
+    const WORDS: [&str; 12] = [
+        "cafe", "café", "cafés", "cave", "chef", "coffee", "hallo", "hello", "help", "hell",
+        "world", "\u{ff}\u{ff}",
+    ];
+
+    #[test]
+    fn test_levenshtein_automaton() {
+        for query in &WORDS {
+            for max_distance in 0u8..3u8 {
+                let automaton = LevenshteinAutomaton::new(query, max_distance);
+                for word in &WORDS {
+                    let distance = levenshtein_distance(query, word);
+                    let expected = if distance <= u32::from(max_distance) {
+                        Some(distance as u8)
+                    } else {
+                        None
+                    };
+                    assert_eq!(automaton_distance(&automaton, word), expected);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_next_prefix() {
+        assert_eq!(next_prefix(b"abc"), Some(b"abd".to_vec()));
+        assert_eq!(next_prefix(b"a\xFF"), Some(b"b".to_vec()));
+        assert_eq!(next_prefix(b"\xFF\xFF"), None);
+        assert_eq!(next_prefix(b""), None);
+    }
+
+    #[test]
+    fn test_search_levenshtein() {
+        let mut words = WORDS.to_vec();
+        words.sort();
+        let mut directory = RAMDirectory::create();
+        let path = PathBuf::from("TermDictionary");
+        {
+            let write = directory.open_write(&path).unwrap();
+            let mut term_dictionary_builder =
+                TermDictionaryBuilderImpl::new(write, FieldType::Str(TEXT)).unwrap();
+            for (ord, word) in words.iter().enumerate() {
+                let term_info = TermInfo {
+                    doc_freq: ord as u32,
+                    ..TermInfo::default()
+                };
+                term_dictionary_builder
+                    .insert(word.as_bytes(), &term_info)
+                    .unwrap();
+            }
+            term_dictionary_builder.finish().unwrap();
+        }
+        let source = directory.open_read(&path).unwrap();
+        let term_dictionary = TermDictionaryImpl::from_source(source).unwrap();
+        for query in &["cafe", "helo", "wordl", "xyz", ""] {
+            for max_distance in 0u8..3u8 {
+                let automaton = LevenshteinAutomaton::new(query, max_distance);
+                let mut matches = vec![];
+                search_levenshtein(&term_dictionary, &automaton, |key, distance, term_info| {
+                    let word = words[term_info.doc_freq as usize];
+                    assert_eq!(key, word.as_bytes());
+                    matches.push((word, distance));
+                });
+                let expected: Vec<(&str, u8)> = words
+                    .iter()
+                    .map(|word| (*word, levenshtein_distance(query, word)))
+                    .filter(|&(_, distance)| distance <= u32::from(max_distance))
+                    .map(|(word, distance)| (word, distance as u8))
+                    .collect();
+                assert_eq!(matches, expected);
+            }
+        }
+    }
+}
@@ -25,13 +25,23 @@ impl<'a> PartialOrd for HeapItem<'a> {
 
 impl<'a> Ord for HeapItem<'a> {
     fn cmp(&self, other: &HeapItem<'a>) -> Ordering {
-        (&other.streamer.key(), &other.segment_ord).cmp(&(&self.streamer.key(), &self.segment_ord))
+        // `BinaryHeap` is a max-heap: for forward streams,
+        // the order is reversed to pop the smallest term first.
+        let key_ordering = if self.streamer.is_backward() {
+            self.streamer.key().cmp(other.streamer.key())
+        } else {
+            other.streamer.key().cmp(self.streamer.key())
+        };
+        key_ordering.then_with(|| other.segment_ord.cmp(&self.segment_ord))
     }
 }
 
 /// Given a list of sorted term streams,
 /// returns an iterator over sorted unique terms.
 ///
+/// The streams are either all forward, or all backward,
+/// in which case the terms are yielded in decreasing order.
+///
 /// The item yield is actually a pair with
 /// - the term
 /// - a slice with the ordinal of the segments containing
@@ -4,7 +4,14 @@ tantivy. It associates sorted `terms` to a `TermInfo` struct
 that serves as an address in their respective posting list.
 
 The term dictionary API makes it possible to iterate through
-a range of keys in a sorted manner.
+a range of keys in a sorted manner, in increasing or decreasing order,
+together with their `TermInfo`.
+
+Backward streams are obtained by calling `.backward()` on
+the `TermStreamerBuilder`. They can skip to the greatest term lower
+or equal to a given target with `.skip_backwards(...)`, which makes it
+possible to serve descending range queries, or to fetch the last
+terms of a range, without reading the terms in between.
 
 
 # Implementations
@@ -69,6 +76,8 @@ pub use self::streamdict::{TermDictionaryBuilderImpl, TermDictionaryImpl, TermSt
                            TermStreamerImpl};
 
 mod merger;
+mod levenshtein;
+pub(crate) use self::levenshtein::{next_prefix, search_levenshtein, LevenshteinAutomaton};
 use std::io;
 
 /// Dictionary associating sorted `&[u8]` to values
@@ -83,7 +92,9 @@ where
     type StreamBuilder: TermStreamerBuilder<Streamer = Self::Streamer> + 'a;
 
     /// Opens a `TermDictionary` given a data source.
-    fn from_source(source: ReadOnlySource) -> Self;
+    ///
+    /// Returns a `DataCorruption` error if the term dictionary is malformed.
+    fn from_source(source: ReadOnlySource) -> io::Result<Self>;
 
     /// Returns the number of terms in the dictionary.
     /// Term ordinals range from 0 to `num_terms() - 1`.
@@ -214,6 +225,12 @@ pub trait TermStreamerBuilder {
     /// Limit the range to terms lesser or equal to the bound
     fn le<T: AsRef<[u8]>>(self, bound: T) -> Self;
 
+    /// Stream the terms of the range in decreasing order.
+    ///
+    /// Backward streams are slower than forward streams, as each of
+    /// their terms is looked up from its term ordinal.
+    fn backward(self) -> Self;
+
     /// Creates the stream corresponding to the range
     /// of terms defined using the `TermStreamerBuilder`.
     fn into_stream(self) -> Self::Streamer;
@@ -270,7 +287,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap();
         }
         let source = directory.open_read(&path).unwrap();
-        let term_dict: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dict: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
         for (term_ord, term) in COUNTRIES.iter().enumerate() {
             assert_eq!(term_dict.term_ord(term).unwrap(), term_ord as u64);
             let mut bytes = vec![];
@@ -297,7 +314,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap();
         }
         let source = directory.open_read(&path).unwrap();
-        let term_dict: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dict: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
         assert_eq!(term_dict.get("abc").unwrap().doc_freq, 34u32);
         assert_eq!(term_dict.get("abcd").unwrap().doc_freq, 346u32);
         let mut stream = term_dict.stream();
@@ -384,7 +401,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
         {
             let mut streamer = term_dictionary.stream();
             let mut i = 0;
@@ -419,7 +436,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
         let mut kv_stream = term_dictionary.stream();
         assert!(kv_stream.advance());
         assert_eq!(kv_stream.key(), "abcdefghijklmnopqrstuvwxy".as_bytes());
@@ -451,7 +468,7 @@ mod tests {
 
         let source = ReadOnlySource::from(buffer);
 
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
         {
             for i in (0..20).chain(6000..8_000) {
                 let &(ref target_key, _) = &ids[i];
@@ -519,7 +536,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
         let mut stream = term_dictionary.stream();
         assert!(stream.advance());
         assert!(stream.key().is_empty());
@@ -543,7 +560,7 @@ mod tests {
             term_dictionary_builder.finish().unwrap()
         };
         let source = ReadOnlySource::from(buffer);
-        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
 
         let value_list = |mut streamer: TermStreamerImpl| {
             let mut res: Vec<u32> = vec![];
@@ -581,6 +598,124 @@ mod tests {
             let range = term_dictionary.range().ge([0u8]).lt([5u8]).into_stream();
             assert_eq!(value_list(range), vec![0u32, 1u32, 2u32, 3u32, 4u32]);
         }
+        {
+            let range = term_dictionary.range().backward().into_stream();
+            assert_eq!(
+                value_list(range),
+                vec![9u32, 8u32, 7u32, 6u32, 5u32, 4u32, 3u32, 2u32, 1u32, 0u32]
+            );
+        }
+        {
+            let range = term_dictionary
+                .range()
+                .ge([2u8])
+                .lt([5u8])
+                .backward()
+                .into_stream();
+            assert_eq!(value_list(range), vec![4u32, 3u32, 2u32]);
+        }
+        {
+            let range = term_dictionary
+                .range()
+                .gt([2u8])
+                .le([5u8])
+                .backward()
+                .into_stream();
+            assert_eq!(value_list(range), vec![5u32, 4u32, 3u32]);
+        }
+        {
+            let range = term_dictionary
+                .range()
+                .ge([20u8])
+                .backward()
+                .into_stream();
+            assert!(value_list(range).is_empty());
+        }
+        {
+            let range = term_dictionary
+                .range()
+                .ge([6u8])
+                .lt([3u8])
+                .backward()
+                .into_stream();
+            assert!(value_list(range).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_stream_backward() {
+        let field_type = FieldType::Str(TEXT);
+        let keys = ["", "a", "ab", "abc", "b", "ba", "c"];
+        let buffer: Vec<u8> = {
+            let mut term_dictionary_builder =
+                TermDictionaryBuilderImpl::new(vec![], field_type).unwrap();
+            for (i, key) in keys.iter().enumerate() {
+                term_dictionary_builder
+                    .insert(key.as_bytes(), &make_term_info(i as u64))
+                    .unwrap();
+            }
+            term_dictionary_builder.finish().unwrap()
+        };
+        let source = ReadOnlySource::from(buffer);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
+        let mut streamer = term_dictionary
+            .range()
+            .ge("a")
+            .lt("b")
+            .backward()
+            .into_stream();
+        for &i in &[3usize, 2, 1] {
+            assert!(streamer.advance());
+            assert_eq!(streamer.key(), keys[i].as_bytes());
+            assert_eq!(streamer.term_ord(), i as u64);
+            assert_eq!(streamer.value(), &make_term_info(i as u64));
+        }
+        assert!(!streamer.advance());
+        let all_keys: Vec<Vec<u8>> = {
+            let mut streamer = term_dictionary.range().backward().into_stream();
+            let mut all_keys = Vec::new();
+            while streamer.advance() {
+                all_keys.push(streamer.key().to_vec());
+            }
+            all_keys
+        };
+        let expected: Vec<Vec<u8>> = keys.iter().rev().map(|key| key.as_bytes().to_vec()).collect();
+        assert_eq!(all_keys, expected);
+    }
+
+    #[test]
+    fn test_stream_skip_backwards() {
+        let field_type = FieldType::Str(TEXT);
+        let keys = ["a", "ab", "abc", "b", "ba", "c", "d"];
+        let buffer: Vec<u8> = {
+            let mut term_dictionary_builder =
+                TermDictionaryBuilderImpl::new(vec![], field_type).unwrap();
+            for (i, key) in keys.iter().enumerate() {
+                term_dictionary_builder
+                    .insert(key.as_bytes(), &make_term_info(i as u64))
+                    .unwrap();
+            }
+            term_dictionary_builder.finish().unwrap()
+        };
+        let source = ReadOnlySource::from(buffer);
+        let term_dictionary: TermDictionaryImpl = TermDictionaryImpl::from_source(source).unwrap();
+        let mut streamer = term_dictionary
+            .range()
+            .gt("a")
+            .lt("d")
+            .backward()
+            .into_stream();
+        assert!(streamer.skip_backwards("bz"));
+        assert_eq!(streamer.key(), b"ba");
+        assert_eq!(streamer.value(), &make_term_info(4u64));
+        // skipping to a greater term does not go back up.
+        assert!(streamer.skip_backwards("c"));
+        assert_eq!(streamer.key(), b"b");
+        assert!(streamer.skip_backwards("abc"));
+        assert_eq!(streamer.key(), b"abc");
+        assert!(streamer.advance());
+        assert_eq!(streamer.key(), b"ab");
+        assert!(!streamer.skip_backwards("a"));
     }
 
 }
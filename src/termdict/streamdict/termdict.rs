@@ -8,6 +8,7 @@ use fst::raw::Fst;
 use directory::ReadOnlySource;
 use common::BinarySerializable;
 use common::CountingWriter;
+use error::DataCorruption;
 use postings::TermInfo;
 use schema::FieldType;
 use super::{DeltaTermInfo, TermDeltaEncoder, TermInfoDeltaEncoder};
@@ -21,7 +22,7 @@ const PADDING_SIZE: usize = 4;
 const INDEX_INTERVAL: usize = 1024;
 
 fn convert_fst_error(e: fst::Error) -> io::Error {
-    io::Error::new(io::ErrorKind::Other, e)
+    DataCorruption::comment_only(e).into()
 }
 
 fn has_positions(field_type: &FieldType) -> bool {
@@ -230,6 +231,19 @@ pub struct TermDictionaryImpl {
 }
 
 impl TermDictionaryImpl {
+    /// Creates an empty term dictionary.
+    ///
+    /// Used for segments that do not contain any term for a given field.
+    pub(crate) fn empty(field_type: FieldType) -> TermDictionaryImpl {
+        let term_dictionary_data: Vec<u8> =
+            TermDictionaryBuilderImpl::new(Vec::<u8>::new(), field_type)
+                .expect("Creating a TermDictionaryBuilder in a Vec<u8> should never fail")
+                .finish()
+                .expect("Writing in a Vec<u8> should never fail");
+        TermDictionaryImpl::from_source(ReadOnlySource::from(term_dictionary_data))
+            .expect("An empty term dictionary should always be valid")
+    }
+
     pub(crate) fn stream_data(&self) -> &[u8] {
         self.stream_data.as_slice()
     }
@@ -294,7 +308,10 @@ impl<'a> TermDictionary<'a> for TermDictionaryImpl {
     type StreamBuilder = TermStreamerBuilderImpl<'a>;
 
     /// Opens a `TermDictionary` given a data source.
-    fn from_source(mut source: ReadOnlySource) -> Self {
+    fn from_source(mut source: ReadOnlySource) -> io::Result<Self> {
+        if source.len() < 1 + 16 {
+            return Err(DataCorruption::comment_only("Term dictionary is too short.").into());
+        }
         let has_positions = source.slice(0, 1)[0] == 255u8;
         source = source.slice_from(1);
 
@@ -302,24 +319,27 @@ impl<'a> TermDictionary<'a> for TermDictionaryImpl {
         let (body, footer) = source.split(total_len - 16);
 
         let mut footer_buffer: &[u8] = footer.as_slice();
-        let fst_addr = u64::deserialize(&mut footer_buffer)
-            .expect("deserializing 8 byte should never fail") as usize;
-        let checkpoints_addr = u64::deserialize(&mut footer_buffer)
-            .expect("deserializing 8 byte should never fail")
-            as usize;
+        let fst_addr = u64::deserialize(&mut footer_buffer)? as usize;
+        let checkpoints_addr = u64::deserialize(&mut footer_buffer)? as usize;
+        if fst_addr < PADDING_SIZE || fst_addr > checkpoints_addr || checkpoints_addr > body.len()
+        {
+            return Err(
+                DataCorruption::comment_only("Term dictionary footer is out of bounds.").into(),
+            );
+        }
 
         let stream_data = body.slice(0, fst_addr - PADDING_SIZE);
         let fst_data = body.slice(fst_addr, checkpoints_addr);
         let checkpoints_data = body.slice_from(checkpoints_addr);
 
-        let fst_index = open_fst_index(fst_data).expect("Index FST data corrupted");
+        let fst_index = open_fst_index(fst_data)?;
 
-        TermDictionaryImpl {
+        Ok(TermDictionaryImpl {
             has_positions,
             stream_data,
             checkpoints_data,
             fst_index,
-        }
+        })
     }
 
     /// Lookups the value corresponding to the key.
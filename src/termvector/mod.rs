@@ -0,0 +1,465 @@
+//! Per-document term vectors.
+//!
+//! For each field with term vectors (See `TextFieldIndexing::set_term_vectors`),
+//! a segment stores, for every document, the terms of the field with their
+//! positions and their byte offsets in the original text. They make it possible
+//! to highlight a document, or to find the terms that made it match, without
+//! having to tokenize its stored text again.
+//!
+//! Term vectors are serialized in the `TERMVECTORS` component of the segment,
+//! with one entry per field in the composite file, laid out as follows:
+//!
+//! - the term vectors of all of the documents, one after the other,
+//! - the end offset of the term vector of each document, as a `u64`,
+//! - the number of documents, as a `u64`.
+//!
+//! The term vector of a document is encoded as the number of its terms,
+//! followed by the terms in sorted order. Each term is written as its text,
+//! its number of occurrences, and for each occurrence, the delta of its
+//! position with the previous one, its start offset, and its length.
+//!
+//! Term vectors are merged by copying the bytes of the documents
+//! that are not deleted.
+
+use byteorder::ByteOrder;
+use common::{BinarySerializable, CompositeWrite, Endianness, VInt};
+use directory::{ReadOnlySource, WritePtr};
+use schema::{Field, FieldType, Schema};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use tokenizer::{Token, TokenStream};
+use DocId;
+
+/// Occurrences of a term in a field of a document.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TermVectorEntry {
+    /// Text of the term.
+    pub text: String,
+    /// Positions of the occurrences of the term, in increasing order.
+    pub positions: Vec<u32>,
+    /// Byte offsets `(from, to)` of the occurrences of the term,
+    /// in the same order as the positions.
+    ///
+    /// The offsets of a multivalued field are expressed as if
+    /// its values were concatenated.
+    pub offsets: Vec<(u32, u32)>,
+}
+
+impl TermVectorEntry {
+    /// Returns the number of occurrences of the term in the field.
+    pub fn term_freq(&self) -> u32 {
+        self.positions.len() as u32
+    }
+}
+
+/// Terms of a field of a document, returned by `Searcher::term_vector(...)`.
+///
+/// Terms are sorted by text.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TermVector {
+    entries: Vec<TermVectorEntry>,
+}
+
+impl TermVector {
+    /// Builds the term vector of the tokens of a field.
+    pub(crate) fn from_tokens(tokens: &[Token]) -> TermVector {
+        let mut occurrences: BTreeMap<&str, Vec<(u32, (u32, u32))>> = BTreeMap::new();
+        for token in tokens {
+            occurrences
+                .entry(&token.text[..])
+                .or_insert_with(Vec::new)
+                .push((
+                    token.position as u32,
+                    (token.offset_from as u32, token.offset_to as u32),
+                ));
+        }
+        let entries = occurrences
+            .into_iter()
+            .map(|(text, mut term_occurrences)| {
+                term_occurrences.sort_by_key(|&(position, _)| position);
+                TermVectorEntry {
+                    text: text.to_string(),
+                    positions: term_occurrences
+                        .iter()
+                        .map(|&(position, _)| position)
+                        .collect(),
+                    offsets: term_occurrences
+                        .iter()
+                        .map(|&(_, offsets)| offsets)
+                        .collect(),
+                }
+            })
+            .collect();
+        TermVector { entries }
+    }
+
+    /// Returns the terms of the field, sorted by text.
+    pub fn terms(&self) -> &[TermVectorEntry] {
+        &self.entries[..]
+    }
+
+    /// Returns the occurrences of a term, if the field contains it.
+    pub fn get(&self, text: &str) -> Option<&TermVectorEntry> {
+        self.entries
+            .binary_search_by(|entry| entry.text.as_str().cmp(text))
+            .ok()
+            .map(|ord| &self.entries[ord])
+    }
+
+    /// Returns the number of distinct terms of the field.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns true iff the field does not contain any term.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes the terms for which `predicate` returns false.
+    pub(crate) fn retain<P: FnMut(&TermVectorEntry) -> bool>(&mut self, predicate: P) {
+        self.entries.retain(predicate);
+    }
+
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        VInt(self.entries.len() as u64).serialize(writer)?;
+        for entry in &self.entries {
+            entry.text.serialize(writer)?;
+            VInt(entry.positions.len() as u64).serialize(writer)?;
+            let mut previous_position = 0u32;
+            for (&position, &(offset_from, offset_to)) in
+                entry.positions.iter().zip(entry.offsets.iter())
+            {
+                VInt(u64::from(position - previous_position)).serialize(writer)?;
+                VInt(u64::from(offset_from)).serialize(writer)?;
+                VInt(u64::from(offset_to - offset_from)).serialize(writer)?;
+                previous_position = position;
+            }
+        }
+        Ok(())
+    }
+
+    fn deserialize(mut data: &[u8]) -> io::Result<TermVector> {
+        if data.is_empty() {
+            return Ok(TermVector::default());
+        }
+        let num_terms = VInt::deserialize_u64(&mut data)? as usize;
+        let mut entries = Vec::with_capacity(num_terms);
+        for _ in 0..num_terms {
+            let text = String::deserialize(&mut data)?;
+            let num_occurrences = VInt::deserialize_u64(&mut data)? as usize;
+            let mut positions = Vec::with_capacity(num_occurrences);
+            let mut offsets = Vec::with_capacity(num_occurrences);
+            let mut position = 0u32;
+            for _ in 0..num_occurrences {
+                position += VInt::deserialize_u64(&mut data)? as u32;
+                let offset_from = VInt::deserialize_u64(&mut data)? as u32;
+                let len = VInt::deserialize_u64(&mut data)? as u32;
+                positions.push(position);
+                offsets.push((offset_from, offset_from + len));
+            }
+            entries.push(TermVectorEntry {
+                text,
+                positions,
+                offsets,
+            });
+        }
+        Ok(TermVector { entries })
+    }
+}
+
+/// Returns the fields of the schema with term vectors.
+pub(crate) fn term_vector_fields(schema: &Schema) -> Vec<Field> {
+    schema
+        .fields()
+        .iter()
+        .enumerate()
+        .filter(|&(_, field_entry)| match *field_entry.field_type() {
+            FieldType::Str(ref text_options) => text_options
+                .get_indexing_options()
+                .map(|indexing_options| indexing_options.term_vectors())
+                .unwrap_or(false),
+            _ => false,
+        })
+        .map(|(field_id, _)| Field(field_id as u32))
+        .collect()
+}
+
+/// `TokenStream` recording a copy of the tokens it goes through.
+pub(crate) struct RecordingTokenStream<'a> {
+    token_stream: &'a mut TokenStream,
+    tokens: Vec<Token>,
+}
+
+impl<'a> RecordingTokenStream<'a> {
+    pub fn new(token_stream: &'a mut TokenStream) -> RecordingTokenStream<'a> {
+        RecordingTokenStream {
+            token_stream,
+            tokens: Vec::new(),
+        }
+    }
+
+    /// Returns the tokens emitted so far.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens[..]
+    }
+}
+
+impl<'a> TokenStream for RecordingTokenStream<'a> {
+    fn advance(&mut self) -> bool {
+        if !self.token_stream.advance() {
+            return false;
+        }
+        let token = self.token_stream.token();
+        self.tokens.push(Token {
+            offset_from: token.offset_from,
+            offset_to: token.offset_to,
+            position: token.position,
+            text: token.text.clone(),
+        });
+        true
+    }
+
+    fn token(&self) -> &Token {
+        self.token_stream.token()
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        self.token_stream.token_mut()
+    }
+}
+
+/// Accumulates the term vectors of a field, one document after the other.
+pub(crate) struct FieldTermVectorsWriter {
+    data: Vec<u8>,
+    doc_ends: Vec<u64>,
+}
+
+impl FieldTermVectorsWriter {
+    pub fn new() -> FieldTermVectorsWriter {
+        FieldTermVectorsWriter {
+            data: Vec::new(),
+            doc_ends: Vec::new(),
+        }
+    }
+
+    /// Records the term vector of the current document.
+    pub fn add_term_vector(&mut self, term_vector: &TermVector) -> io::Result<()> {
+        term_vector.serialize(&mut self.data)
+    }
+
+    /// Records the term vector of the current document, as serialized
+    /// by a `TermVectorReader`.
+    pub fn add_raw_term_vector(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Closes the current document.
+    ///
+    /// A document for which no term vector was recorded
+    /// gets an empty term vector.
+    pub fn end_document(&mut self) {
+        self.doc_ends.push(self.data.len() as u64);
+    }
+
+    pub fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.data[..])?;
+        for &doc_end in &self.doc_ends {
+            doc_end.serialize(writer)?;
+        }
+        (self.doc_ends.len() as u64).serialize(writer)
+    }
+}
+
+/// Accumulates the term vectors of the documents of a segment.
+pub(crate) struct TermVectorsWriter {
+    field_writers: Vec<(Field, FieldTermVectorsWriter)>,
+}
+
+impl TermVectorsWriter {
+    pub fn from_schema(schema: &Schema) -> TermVectorsWriter {
+        TermVectorsWriter {
+            field_writers: term_vector_fields(schema)
+                .into_iter()
+                .map(|field| (field, FieldTermVectorsWriter::new()))
+                .collect(),
+        }
+    }
+
+    /// Returns true iff term vectors are recorded for `field`.
+    pub fn has_field(&self, field: Field) -> bool {
+        self.field_writers
+            .iter()
+            .any(|&(term_vector_field, _)| term_vector_field == field)
+    }
+
+    /// Records the term vector of a field of the current document,
+    /// given its tokens.
+    pub fn add_tokens(&mut self, field: Field, tokens: &[Token]) -> io::Result<()> {
+        for &mut (term_vector_field, ref mut field_writer) in &mut self.field_writers {
+            if term_vector_field == field {
+                field_writer.add_term_vector(&TermVector::from_tokens(tokens))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Closes the current document, for all of the fields.
+    pub fn end_document(&mut self) {
+        for &mut (_, ref mut field_writer) in &mut self.field_writers {
+            field_writer.end_document();
+        }
+    }
+
+    /// Serializes the term vectors of each of the fields.
+    pub fn serialize(&self, serializer: &mut CompositeWrite<WritePtr>) -> io::Result<()> {
+        for &(field, ref field_writer) in &self.field_writers {
+            field_writer.serialize(serializer.for_field(field))?;
+        }
+        Ok(())
+    }
+}
+
+fn corrupted_term_vectors() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "Corrupted term vectors")
+}
+
+/// Term vectors of a field, in a segment.
+pub(crate) struct TermVectorReader {
+    data: ReadOnlySource,
+    doc_ends: ReadOnlySource,
+}
+
+impl TermVectorReader {
+    pub fn open(source: ReadOnlySource) -> io::Result<TermVectorReader> {
+        let len = source.len();
+        if len < 8 {
+            return Err(corrupted_term_vectors());
+        }
+        let num_docs = Endianness::read_u64(&source.as_slice()[len - 8..]) as usize;
+        let doc_ends_len = num_docs
+            .checked_mul(8)
+            .and_then(|doc_ends_len| doc_ends_len.checked_add(8))
+            .ok_or_else(corrupted_term_vectors)?;
+        if doc_ends_len > len {
+            return Err(corrupted_term_vectors());
+        }
+        let data_len = len - doc_ends_len;
+        Ok(TermVectorReader {
+            data: source.slice(0, data_len),
+            doc_ends: source.slice(data_len, len - 8),
+        })
+    }
+
+    fn doc_end(&self, doc: DocId) -> usize {
+        let start = doc as usize * 8;
+        Endianness::read_u64(&self.doc_ends.as_slice()[start..start + 8]) as usize
+    }
+
+    /// Returns the serialized term vector of a document.
+    pub fn raw_term_vector(&self, doc: DocId) -> io::Result<&[u8]> {
+        if (doc as usize + 1) * 8 > self.doc_ends.len() {
+            return Err(corrupted_term_vectors());
+        }
+        let start = if doc == 0 { 0 } else { self.doc_end(doc - 1) };
+        let end = self.doc_end(doc);
+        if start > end || end > self.data.len() {
+            return Err(corrupted_term_vectors());
+        }
+        Ok(&self.data.as_slice()[start..end])
+    }
+
+    /// Returns the term vector of a document.
+    pub fn term_vector(&self, doc: DocId) -> io::Result<TermVector> {
+        TermVector::deserialize(self.raw_term_vector(doc)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use Index;
+    use DocAddress;
+    use futures::Future;
+    use indexer::NoMergePolicy;
+    use schema::{IndexRecordOption, SchemaBuilder, TextFieldIndexing, TextOptions, TEXT};
+    use super::{TermVector, TermVectorEntry};
+
+    #[test]
+    fn test_term_vectors() {
+        let mut schema_builder = SchemaBuilder::default();
+        let text_options = TextOptions::default().set_indexing_options(
+            TextFieldIndexing::default()
+                .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                .set_term_vectors(),
+        );
+        let text = schema_builder.add_text_field("text", text_options);
+        let title = schema_builder.add_text_field("title", TEXT);
+        let index = Index::create_in_ram(schema_builder.build());
+        let mut index_writer = index.writer_with_num_threads(1, 40_000_000).unwrap();
+        index_writer.set_merge_policy(box NoMergePolicy);
+        index_writer.add_document(doc!(text => "to be or not to be"));
+        index_writer.add_document(doc!(title => "no text"));
+        index_writer.commit().unwrap();
+        index_writer.add_document(doc!(text => "hello", text => "happy hello"));
+        index_writer.commit().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        let doc_addresses: Vec<DocAddress> = searcher
+            .segment_readers()
+            .iter()
+            .enumerate()
+            .flat_map(|(segment_ord, segment_reader)| {
+                (0..segment_reader.max_doc()).map(move |doc| DocAddress(segment_ord as u32, doc))
+            })
+            .collect();
+        let mut term_vectors: Vec<TermVector> = doc_addresses
+            .iter()
+            .map(|doc_address| searcher.term_vector(doc_address, text).unwrap().unwrap())
+            .collect();
+        assert!(searcher.term_vector(&doc_addresses[0], title).unwrap().is_none());
+
+        let term_vector = term_vectors
+            .iter()
+            .find(|term_vector| term_vector.get("to").is_some())
+            .unwrap();
+        let texts: Vec<&str> = term_vector
+            .terms()
+            .iter()
+            .map(|entry| entry.text.as_str())
+            .collect();
+        assert_eq!(texts, vec!["be", "not", "or", "to"]);
+        assert_eq!(
+            term_vector.get("be"),
+            Some(&TermVectorEntry {
+                text: "be".to_string(),
+                positions: vec![1, 5],
+                offsets: vec![(3, 5), (16, 18)],
+            })
+        );
+        assert_eq!(term_vector.get("to").unwrap().term_freq(), 2);
+        assert!(term_vector.get("hello").is_none());
+        let multivalued_term_vector = term_vectors
+            .iter()
+            .find(|term_vector| term_vector.get("hello").is_some())
+            .unwrap();
+        assert_eq!(multivalued_term_vector.len(), 2);
+        assert_eq!(
+            multivalued_term_vector.get("hello").unwrap().positions,
+            vec![0, 3]
+        );
+        assert_eq!(term_vectors.iter().filter(|tv| tv.is_empty()).count(), 1);
+
+        let segment_ids = index.searchable_segment_ids().unwrap();
+        index_writer.merge(&segment_ids).wait().unwrap();
+        index.load_searchers().unwrap();
+        let searcher = index.searcher();
+        assert_eq!(searcher.segment_readers().len(), 1);
+        let mut merged_term_vectors: Vec<TermVector> = (0..3)
+            .map(|doc| searcher.term_vector(&DocAddress(0, doc), text).unwrap().unwrap())
+            .collect();
+        term_vectors.sort_by_key(|term_vector| term_vector.len());
+        merged_term_vectors.sort_by_key(|term_vector| term_vector.len());
+        assert_eq!(merged_term_vectors, term_vectors);
+    }
+}
@@ -139,6 +139,7 @@ mod japanese_tokenizer;
 mod token_stream_chain;
 mod raw_tokenizer;
 mod alphanum_only;
+mod stop_word_filter;
 
 pub use self::alphanum_only::AlphaNumOnlyFilter;
 pub use self::tokenizer::{Token, TokenFilter, TokenStream, Tokenizer};
@@ -149,6 +150,7 @@ pub use self::raw_tokenizer::RawTokenizer;
 pub(crate) use self::token_stream_chain::TokenStreamChain;
 pub use self::japanese_tokenizer::JapaneseTokenizer;
 pub use self::remove_long::RemoveLongFilter;
+pub use self::stop_word_filter::StopWordFilter;
 pub use self::lower_caser::LowerCaser;
 pub use self::stemmer::Stemmer;
 pub use self::facet_tokenizer::FacetTokenizer;
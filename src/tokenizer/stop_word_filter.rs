@@ -0,0 +1,144 @@
+use super::{Token, TokenFilter, TokenStream};
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// `StopWordFilter` removes the tokens of a given list of stop words.
+///
+/// By default, the positions of the removed tokens are left empty,
+/// so that the remaining tokens keep the position they had in the text.
+/// A phrase query parsed with the same tokenizer leaves the same gaps
+/// between its terms, so that `"end of the world"` with the stop words
+/// `of` and `the` still only matches `end` three positions before `world`.
+///
+/// With `.keep_position_gaps(false)`, the positions of the remaining
+/// tokens are shifted to close the gaps, as if the stop words
+/// were not in the text.
+///
+/// Stop words are compared to the text of the tokens as is, so that the
+/// filter is usually placed after a `LowerCaser`.
+#[derive(Clone)]
+pub struct StopWordFilter {
+    words: Arc<HashSet<String>>,
+    keep_position_gaps: bool,
+}
+
+impl StopWordFilter {
+    /// Creates a `StopWordFilter` removing the given words.
+    pub fn remove(words: Vec<String>) -> StopWordFilter {
+        StopWordFilter {
+            words: Arc::new(words.into_iter().collect()),
+            keep_position_gaps: true,
+        }
+    }
+
+    /// Sets whether the positions of the removed tokens are left empty.
+    ///
+    /// Defaults to `true`. Changing it for an existing field only
+    /// affects the documents indexed afterwards.
+    pub fn keep_position_gaps(mut self, keep_position_gaps: bool) -> StopWordFilter {
+        self.keep_position_gaps = keep_position_gaps;
+        self
+    }
+}
+
+impl<TailTokenStream> TokenFilter<TailTokenStream> for StopWordFilter
+where
+    TailTokenStream: TokenStream,
+{
+    type ResultTokenStream = StopWordFilterStream<TailTokenStream>;
+
+    fn transform(&self, token_stream: TailTokenStream) -> Self::ResultTokenStream {
+        StopWordFilterStream {
+            words: Arc::clone(&self.words),
+            keep_position_gaps: self.keep_position_gaps,
+            num_removed_tokens: 0,
+            token: Token::default(),
+            tail: token_stream,
+        }
+    }
+}
+
+pub struct StopWordFilterStream<TailTokenStream>
+where
+    TailTokenStream: TokenStream,
+{
+    words: Arc<HashSet<String>>,
+    keep_position_gaps: bool,
+    num_removed_tokens: usize,
+    // copy of the token of the tail with its position shifted,
+    // when the gaps are closed. Tokenizers compute the position
+    // of a token from the previous one, so that the token
+    // of the tail cannot be modified in place.
+    token: Token,
+    tail: TailTokenStream,
+}
+
+impl<TailTokenStream> TokenStream for StopWordFilterStream<TailTokenStream>
+where
+    TailTokenStream: TokenStream,
+{
+    fn token(&self) -> &Token {
+        if self.keep_position_gaps {
+            self.tail.token()
+        } else {
+            &self.token
+        }
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        if self.keep_position_gaps {
+            self.tail.token_mut()
+        } else {
+            &mut self.token
+        }
+    }
+
+    fn advance(&mut self) -> bool {
+        while self.tail.advance() {
+            if self.words.contains(&self.tail.token().text) {
+                self.num_removed_tokens += 1;
+                continue;
+            }
+            if !self.keep_position_gaps {
+                let tail_token = self.tail.token();
+                self.token.offset_from = tail_token.offset_from;
+                self.token.offset_to = tail_token.offset_to;
+                self.token.position = tail_token.position - self.num_removed_tokens;
+                self.token.text.clone_from(&tail_token.text);
+            }
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::StopWordFilter;
+    use tokenizer::{LowerCaser, SimpleTokenizer, Token, TokenStream, Tokenizer};
+
+    fn tokens(stop_word_filter: StopWordFilter, text: &str) -> Vec<(String, usize)> {
+        let tokenizer = SimpleTokenizer
+            .filter(LowerCaser)
+            .filter(stop_word_filter);
+        let mut tokens = vec![];
+        tokenizer
+            .token_stream(text)
+            .process(&mut |token: &Token| tokens.push((token.text.clone(), token.position)));
+        tokens
+    }
+
+    #[test]
+    fn test_stop_word_filter() {
+        let stop_words = vec!["of".to_string(), "the".to_string()];
+        let filter = StopWordFilter::remove(stop_words);
+        let expected = vec![("end".to_string(), 0), ("world".to_string(), 3)];
+        assert_eq!(tokens(filter.clone(), "End of the World"), expected);
+        let expected = vec![("end".to_string(), 0), ("world".to_string(), 1)];
+        assert_eq!(
+            tokens(filter.keep_position_gaps(false), "End of the World"),
+            expected
+        );
+    }
+}
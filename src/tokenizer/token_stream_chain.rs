@@ -4,6 +4,7 @@ pub(crate) struct TokenStreamChain<TTokenStream: TokenStream> {
     offsets: Vec<usize>,
     token_streams: Vec<TTokenStream>,
     position_shift: usize,
+    position_gap: usize,
     stream_idx: usize,
     token: Token,
 }
@@ -15,12 +16,14 @@ where
     pub fn new(
         offsets: Vec<usize>,
         token_streams: Vec<TTokenStream>,
+        position_gap: usize,
     ) -> TokenStreamChain<TTokenStream> {
         TokenStreamChain {
             offsets,
             stream_idx: 0,
             token_streams,
             position_shift: 0,
+            position_gap,
             token: Token::default(),
         }
     }
@@ -37,14 +40,14 @@ where
                 let token = token_stream.token();
                 let offset_offset = self.offsets[self.stream_idx];
                 self.token.offset_from = token.offset_from + offset_offset;
-                self.token.offset_from = token.offset_from + offset_offset;
+                self.token.offset_to = token.offset_to + offset_offset;
                 self.token.position = token.position + self.position_shift;
                 self.token.text.clear();
                 self.token.text.push_str(token.text.as_str());
                 return true;
             } else {
                 self.stream_idx += 1;
-                self.position_shift = self.token.position + 2;
+                self.position_shift = self.token.position + 1 + self.position_gap;
             }
         }
         false
@@ -66,3 +69,32 @@ where
         &mut self.token
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::TokenStreamChain;
+    use tokenizer::{SimpleTokenizer, TokenStream, Tokenizer};
+
+    #[test]
+    fn test_token_stream_chain_position_gap() {
+        let texts = ["a b", "c"];
+        let token_streams = texts
+            .iter()
+            .map(|text| SimpleTokenizer.token_stream(text))
+            .collect();
+        let mut token_stream = TokenStreamChain::new(vec![0, 3], token_streams, 5);
+        let mut tokens = vec![];
+        token_stream.process(&mut |token| {
+            tokens.push((token.text.clone(), token.position, token.offset_from))
+        });
+        assert_eq!(
+            tokens,
+            vec![
+                ("a".to_string(), 0, 0),
+                ("b".to_string(), 1, 2),
+                ("c".to_string(), 7, 3),
+            ]
+        );
+    }
+}
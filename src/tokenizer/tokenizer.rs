@@ -3,6 +3,7 @@
 
 use std::borrow::{Borrow, BorrowMut};
 use tokenizer::TokenStreamChain;
+use schema::DEFAULT_POSITION_GAP;
 
 /// Token
 pub struct Token {
@@ -84,9 +85,19 @@ pub trait BoxedTokenizer: Send + Sync {
     /// Tokenize an array`&str`
     ///
     /// The resulting `TokenStream` is equivalent to what would be obtained if the &str were
-    /// one concatenated `&str`, with an artificial position gap of `2` between the different fields
-    /// to prevent accidental `PhraseQuery` to match accross two terms.
-    fn token_stream_texts<'b>(&self, texts: &'b [&'b str]) -> Box<TokenStream + 'b>;
+    /// one concatenated `&str`, with an artificial position gap of `1` between the different
+    /// fields to prevent accidental `PhraseQuery` to match accross two terms.
+    fn token_stream_texts<'b>(&self, texts: &'b [&'b str]) -> Box<TokenStream + 'b> {
+        self.token_stream_texts_with_gap(texts, DEFAULT_POSITION_GAP)
+    }
+
+    /// Tokenize an array`&str`, leaving `position_gap` empty positions
+    /// between the different fields.
+    fn token_stream_texts_with_gap<'b>(
+        &self,
+        texts: &'b [&'b str],
+        position_gap: usize,
+    ) -> Box<TokenStream + 'b>;
 
     /// Return a boxed clone of the tokenizer
     fn boxed_clone(&self) -> Box<BoxedTokenizer>;
@@ -105,7 +116,11 @@ where
         box self.0.token_stream(text)
     }
 
-    fn token_stream_texts<'b>(&self, texts: &'b [&'b str]) -> Box<TokenStream + 'b> {
+    fn token_stream_texts_with_gap<'b>(
+        &self,
+        texts: &'b [&'b str],
+        position_gap: usize,
+    ) -> Box<TokenStream + 'b> {
         assert!(!texts.is_empty());
         if texts.len() == 1 {
             box self.0.token_stream(texts[0])
@@ -118,7 +133,7 @@ where
             }
             let token_streams: Vec<_> =
                 texts.iter().map(|text| self.0.token_stream(text)).collect();
-            box TokenStreamChain::new(offsets, token_streams)
+            box TokenStreamChain::new(offsets, token_streams, position_gap)
         }
     }
 